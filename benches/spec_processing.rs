@@ -0,0 +1,129 @@
+//! Spec processing (and ledger printing) throughput on large inputs.
+//!
+//! Run with `cargo bench --features test-util`.
+
+use chrono::{Days, NaiveDate};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use fractic_ifrs_hledger::{
+    entities::{
+        AccountingLogic, BackingAccount, FinancialRecordSpecs, ProcessOptions, TransactionSpec,
+        TransactionSpecId,
+    },
+    printing::PrintOptions,
+    test_util::minimal::{
+        Asset, Cash, Commodity, Expense, Income, MinimalHandlers, Payee, Reimbursable, Shareholder,
+    },
+    util,
+};
+
+const SPEC_COUNT: u64 = 50_000;
+
+/// Mix of expenses paid by the bank and by the founder (reimbursed monthly),
+/// and income, from a small set of repeating payees.
+fn specs(count: u64) -> FinancialRecordSpecs<MinimalHandlers> {
+    let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+    let payees = ["Acme", "Landlord", "Cloud Co", "Stationer", "Client"];
+    let transaction_specs = (0..count)
+        .map(|i| {
+            let date = start + Days::new(i / 20);
+            let (accounting_logic, backing_account, amount) = match i % 20 {
+                19 => (
+                    AccountingLogic::ImmaterialIncome(Income::Sales),
+                    BackingAccount::Cash(Cash::Bank),
+                    150_000.0,
+                ),
+                i if i % 3 == 0 => (
+                    AccountingLogic::SimpleExpense(Expense::Supplies),
+                    BackingAccount::Reimburse(Reimbursable::Founder),
+                    -1_000.0,
+                ),
+                _ => (
+                    AccountingLogic::SimpleExpense(Expense::Cloud),
+                    BackingAccount::Cash(Cash::Bank),
+                    -5_000.0,
+                ),
+            };
+            spec(i, date, accounting_logic, backing_account, amount, payees)
+        })
+        .chain((1..=count / 600).map(|month| {
+            // Settle the founder's expenses of the last 30 days.
+            spec(
+                count + month,
+                start + Days::new(month * 30 - 1),
+                AccountingLogic::Reimburse(Reimbursable::Founder),
+                BackingAccount::Cash(Cash::Bank),
+                -30.0 * 7.0 * 1_000.0,
+                payees,
+            )
+        }))
+        .collect();
+    FinancialRecordSpecs {
+        transaction_specs,
+        assertion_specs: Vec::new(),
+        commands: Vec::new(),
+        budget_specs: Vec::new(),
+    }
+}
+
+fn spec(
+    id: u64,
+    date: NaiveDate,
+    accounting_logic: AccountingLogic<Expense, Asset, Income, Reimbursable, Shareholder>,
+    backing_account: BackingAccount<Reimbursable, Cash, Shareholder>,
+    amount: f64,
+    payees: [&str; 5],
+) -> TransactionSpec<MinimalHandlers> {
+    TransactionSpec {
+        id: TransactionSpecId::new(id),
+        accrual_start: date,
+        accrual_end: None,
+        payment_date: date,
+        accounting_logic,
+        decorators: Vec::new(),
+        payee: Payee(payees[id as usize % payees.len()].to_string()),
+        description: format!("Spec {id}"),
+        amount,
+        commodity: Commodity::Krw,
+        backing_account,
+        annotations: Vec::new(),
+        intercompany: None,
+        dimensions: Default::default(),
+        entity: None,
+        recurrence: None,
+        status: Default::default(),
+        amends: None,
+    }
+}
+
+fn bench_process(c: &mut Criterion) {
+    let mut group = c.benchmark_group("process");
+    group.sample_size(10);
+    group.bench_function("50k_specs", |b| {
+        b.iter_batched(
+            || specs(SPEC_COUNT),
+            |specs| {
+                futures::executor::block_on(util::process(specs, ProcessOptions::default()))
+                    .unwrap()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("50k_specs_and_print", |b| {
+        b.iter_batched(
+            || specs(SPEC_COUNT),
+            |specs| {
+                futures::executor::block_on(util::process_and_print(
+                    specs,
+                    ProcessOptions::default(),
+                    PrintOptions::default(),
+                ))
+                .unwrap()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_process);
+criterion_main!(benches);
@@ -0,0 +1,19 @@
+use std::{path::Path, sync::Arc};
+
+use fractic_ifrs_hledger::{errors::ReadError, ext::standard_handlers::HandlersConfig};
+use fractic_server_error::ServerError;
+
+/// Load the handlers configuration (JSON) the spec files are resolved against
+/// (see `ConfiguredHandlers::with_config`). `main_currency` is used unless the
+/// handlers configuration sets its own.
+pub(crate) async fn load(
+    path: &Path,
+    main_currency: Option<String>,
+) -> Result<Arc<HandlersConfig>, ServerError> {
+    let raw = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| ReadError::with_debug(&e))?;
+    let mut config = HandlersConfig::from_json(&raw)?;
+    config.main_currency = config.main_currency.or(main_currency);
+    Ok(Arc::new(config))
+}
@@ -0,0 +1,23 @@
+use fractic_server_error::define_client_error;
+
+// IO-related.
+define_client_error!(WriteError, "Error writing file.");
+#[cfg(feature = "server")]
+define_client_error!(ServeError, "Error running the HTTP service.");
+
+// Input-related.
+define_client_error!(
+    InvalidJsonInput,
+    "Invalid JSON input (expected an array of objects): {details}.",
+    { details: &str }
+);
+define_client_error!(
+    UnknownJsonField,
+    "Unknown field '{field}' in JSON input. Expected one of: {expected}.",
+    { field: &str, expected: String }
+);
+define_client_error!(
+    MissingArgument,
+    "Missing '{flag}' (set it on the command line, or as '{config_key}' in the config file).",
+    { flag: &str, config_key: &str }
+);
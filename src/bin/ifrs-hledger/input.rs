@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use fractic_ifrs_hledger::errors::{InvalidCsv, ReadError};
+use fractic_server_error::ServerError;
+use serde_json::{Map, Value};
+
+use crate::errors::{InvalidJsonInput, UnknownJsonField};
+
+// JSON input is an array of objects, converted to the positional CSV layout
+// expected by the library.
+
+enum Column {
+    Field(&'static str),
+    Blank,
+    /// Fixed value, used to mark command rows.
+    Marker(&'static str),
+}
+
+use Column::*;
+
+const SPEC_COLUMNS: [Column; 16] = [
+    Field("accrual"),
+    Field("until"),
+    Field("payment"),
+    Field("logic"),
+    Field("decorators"),
+    Field("payee"),
+    Field("description"),
+    Field("amount"),
+    Field("commodity"),
+    Field("backing_account"),
+    Field("notes"),
+    Field("intercompany"),
+    Field("entity"),
+    Field("recurrence"),
+    Field("dimensions"),
+    Field("status"),
+];
+
+/// Command rows are identified by the presence of an `exec` key.
+const COMMAND_COLUMNS: [Column; 16] = [
+    Marker(":"),
+    Blank,
+    Field("date"),
+    Field("exec"),
+    Field("arguments"),
+    Blank,
+    Field("description"),
+    Field("amount"),
+    Field("commodity"),
+    Blank,
+    Field("notes"),
+    Blank,
+    Field("entity"),
+    Blank,
+    Blank,
+    Blank,
+];
+
+const BALANCE_COLUMNS: [Column; 5] = [
+    Field("account"),
+    Field("date"),
+    Field("balance"),
+    Field("commodity"),
+    Field("entity"),
+];
+
+/// Read the transactions file, as CSV or (if the extension is `.json`) JSON.
+pub(crate) async fn read_specs(path: &Path) -> Result<String, ServerError> {
+    let raw = read(path).await?;
+    if is_json(path) {
+        json_to_csv(&raw, &SPEC_COLUMNS, |row| {
+            if row.contains_key("exec") {
+                &COMMAND_COLUMNS
+            } else {
+                &SPEC_COLUMNS
+            }
+        })
+    } else {
+        Ok(raw)
+    }
+}
+
+/// Read the balances file, as CSV or (if the extension is `.json`) JSON.
+pub(crate) async fn read_balances(path: &Path) -> Result<String, ServerError> {
+    let raw = read(path).await?;
+    if is_json(path) {
+        json_to_csv(&raw, &BALANCE_COLUMNS, |_| &BALANCE_COLUMNS)
+    } else {
+        Ok(raw)
+    }
+}
+
+async fn read(path: &Path) -> Result<String, ServerError> {
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| ReadError::with_debug(&e))
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+fn json_to_csv(
+    json: &str,
+    header: &[Column],
+    columns_for: impl Fn(&Map<String, Value>) -> &'static [Column],
+) -> Result<String, ServerError> {
+    let rows: Vec<Map<String, Value>> =
+        serde_json::from_str(json).map_err(|e| InvalidJsonInput::new(&e.to_string()))?;
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    // The first CSV line is treated as a header, and skipped.
+    writer
+        .write_record(header.iter().map(|column| match column {
+            Field(key) => *key,
+            Blank | Marker(_) => "",
+        }))
+        .map_err(|e| InvalidCsv::with_debug(&e))?;
+    for row in rows {
+        let columns = columns_for(&row);
+        let expected = || {
+            columns.iter().filter_map(|column| match column {
+                Field(key) => Some(*key),
+                Blank | Marker(_) => None,
+            })
+        };
+        if let Some(field) = row.keys().find(|k| !expected().any(|key| key == *k)) {
+            return Err(UnknownJsonField::new(
+                field,
+                expected().collect::<Vec<_>>().join(", "),
+            ));
+        }
+        let record = columns.iter().map(|column| match column {
+            Field(key) => match row.get(*key) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s.clone(),
+                Some(v) => v.to_string(),
+            },
+            Blank => String::new(),
+            Marker(value) => value.to_string(),
+        });
+        writer
+            .write_record(record)
+            .map_err(|e| InvalidCsv::with_debug(&e))?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| InvalidCsv::with_debug(&e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| InvalidCsv::with_debug(&e))
+}
@@ -0,0 +1,104 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use fractic_ifrs_hledger::{
+    entities::PipelineConfig,
+    errors::{Locale, ServerErrorExt as _},
+};
+use fractic_server_error::ServerError;
+
+use crate::errors::WriteError;
+
+mod config;
+mod errors;
+mod input;
+mod process;
+#[cfg(feature = "server")]
+mod serve;
+mod statement;
+
+#[derive(Parser)]
+#[command(name = "ifrs-hledger", version, about)]
+struct Cli {
+    /// Pipeline config file (TOML). Command-line flags take precedence.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Language of error messages.
+    #[arg(long, global = true, value_enum, default_value_t = Lang::En)]
+    lang: Lang,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate an hledger journal from transaction and balance specs.
+    Process(process::ProcessArgs),
+    /// Generate financial statements from a generated journal (requires the
+    /// hledger binary).
+    #[command(subcommand)]
+    Statement(statement::StatementCommand),
+    /// Serve spec processing and statements over HTTP (see
+    /// `fractic_ifrs_hledger::server`).
+    #[cfg(feature = "server")]
+    Serve(serve::ServeArgs),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Lang {
+    En,
+    Ko,
+}
+
+impl From<Lang> for Locale {
+    fn from(value: Lang) -> Self {
+        match value {
+            Lang::En => Locale::En,
+            Lang::Ko => Locale::Ko,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let locale = cli.lang.into();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e.localized(locale));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), ServerError> {
+    let pipeline = match &cli.config {
+        Some(path) => PipelineConfig::from_toml_file(path).await?,
+        None => PipelineConfig::default(),
+    };
+    match cli.command {
+        Command::Process(args) => process::run(args, pipeline).await,
+        Command::Statement(command) => statement::run(command, pipeline).await,
+        #[cfg(feature = "server")]
+        Command::Serve(args) => serve::run(args, pipeline).await,
+    }
+}
+
+/// Write to the given file, or stdout if not set.
+async fn write_output(path: Option<&Path>, output: &str) -> Result<(), ServerError> {
+    match path {
+        Some(path) => tokio::fs::write(path, output)
+            .await
+            .map_err(|e| WriteError::with_debug(&e)),
+        None => {
+            print!("{output}");
+            Ok(())
+        }
+    }
+}
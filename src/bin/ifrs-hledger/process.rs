@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use fractic_ifrs_hledger::{
+    entities::PipelineConfig, ext::standard_handlers::ConfiguredHandlers,
+    printing::AnnotationVerbosity, util,
+};
+use fractic_server_error::ServerError;
+
+use crate::{
+    config,
+    errors::{MissingArgument, WriteError},
+    input, write_output,
+};
+
+#[derive(Args)]
+pub(crate) struct ProcessArgs {
+    /// Transaction specs (CSV, or JSON if the extension is `.json`). Defaults
+    /// to `inputs.transactions` from the config.
+    #[arg(long)]
+    specs: Option<PathBuf>,
+
+    /// Balance assertion specs (CSV, or JSON if the extension is `.json`).
+    /// Defaults to `inputs.balances` from the config.
+    #[arg(long)]
+    balances: Option<PathBuf>,
+
+    /// JSON file defining the accounts referenced by name in the specs.
+    #[arg(long)]
+    handlers: PathBuf,
+
+    /// Write the output to this file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Hledger)]
+    format: OutputFormat,
+
+    /// Group transactions under monthly section headers.
+    #[arg(long)]
+    group_by_month: bool,
+
+    /// Print per-account subtotals after each month (implies
+    /// --group-by-month).
+    #[arg(long)]
+    monthly_subtotals: bool,
+
+    /// How much annotation text to print inline [default: full].
+    #[arg(long, value_enum)]
+    annotations: Option<Annotations>,
+
+    /// Collect the annotations in an appendix at the end of the journal.
+    #[arg(long)]
+    annotation_appendix: bool,
+
+    /// Declare every standard account, not only the ones used.
+    #[arg(long)]
+    full_chart_of_accounts: bool,
+
+    /// Print a description under each account declaration.
+    #[arg(long)]
+    account_notes: bool,
+
+    /// Maximum number of decorators evaluated concurrently.
+    #[arg(long)]
+    decorator_concurrency: Option<usize>,
+
+    /// Process each entity of the specs separately, writing one journal per
+    /// entity to this directory, plus `all.journal` including them.
+    #[arg(long, conflicts_with_all = ["output", "format"])]
+    entity_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Hledger,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Annotations {
+    None,
+    Markers,
+    Full,
+}
+
+impl From<Annotations> for AnnotationVerbosity {
+    fn from(value: Annotations) -> Self {
+        match value {
+            Annotations::None => AnnotationVerbosity::None,
+            Annotations::Markers => AnnotationVerbosity::Markers,
+            Annotations::Full => AnnotationVerbosity::Full,
+        }
+    }
+}
+
+pub(crate) async fn run(args: ProcessArgs, pipeline: PipelineConfig) -> Result<(), ServerError> {
+    let specs = args
+        .specs
+        .or(pipeline.transactions_path)
+        .ok_or_else(|| MissingArgument::new("--specs", "inputs.transactions"))?;
+    let balances = args.balances.or(pipeline.balances_path);
+
+    let handlers = config::load(&args.handlers, pipeline.main_currency).await?;
+    let transactions_csv = input::read_specs(&specs).await?;
+    let balances_csv = match &balances {
+        Some(path) => input::read_balances(path).await?,
+        None => String::new(),
+    };
+
+    // Flags take precedence over the config.
+    let mut options = pipeline.process_options;
+    if let Some(decorator_concurrency) = args.decorator_concurrency {
+        options.decorator_concurrency = decorator_concurrency;
+    }
+    let mut print_options = pipeline.print_options;
+    print_options.group_by_month |= args.group_by_month || args.monthly_subtotals;
+    print_options.monthly_subtotals |= args.monthly_subtotals;
+    print_options.annotation_appendix |= args.annotation_appendix;
+    print_options.full_chart_of_accounts |= args.full_chart_of_accounts;
+    print_options.account_notes |= args.account_notes;
+    if let Some(annotations) = args.annotations {
+        print_options.annotation_verbosity = annotations.into();
+    }
+
+    // Names are resolved as the specs are parsed.
+    let specs = ConfiguredHandlers::with_config(&handlers, || {
+        util::parse_csv::<ConfiguredHandlers>(&transactions_csv, &balances_csv)
+    })?;
+
+    if let Some(dir) = args.entity_dir {
+        let ledgers = util::process_by_entity(specs, options, print_options).await?;
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| WriteError::with_debug(&e))?;
+        for entity in &ledgers.entities {
+            write_output(Some(&dir.join(&entity.file_name)), &entity.ledger).await?;
+        }
+        return write_output(Some(&dir.join("all.journal")), &ledgers.combined).await;
+    }
+
+    let (financial_records, _, ledger) =
+        util::process_and_print(specs, options, print_options).await?;
+    let output = match args.format {
+        OutputFormat::Hledger => ledger,
+        OutputFormat::Json => financial_records.to_json()?,
+    };
+
+    write_output(args.output.as_deref(), &output).await
+}
@@ -0,0 +1,35 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use clap::Args;
+use fractic_ifrs_hledger::{
+    entities::PipelineConfig, ext::standard_handlers::ConfiguredHandlers, server::JournalService,
+};
+use fractic_server_error::ServerError;
+
+use crate::{config, errors::ServeError};
+
+#[derive(Args)]
+pub(crate) struct ServeArgs {
+    /// JSON file defining the accounts referenced by name in the specs.
+    #[arg(long)]
+    handlers: PathBuf,
+
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: SocketAddr,
+}
+
+pub(crate) async fn run(args: ServeArgs, pipeline: PipelineConfig) -> Result<(), ServerError> {
+    let handlers = config::load(&args.handlers, pipeline.main_currency.clone()).await?;
+    let router = JournalService::<ConfiguredHandlers>::new()
+        .with_config(&pipeline)
+        .with_parse_scope(move |parse| ConfiguredHandlers::with_config(&handlers, parse))
+        .router();
+    let listener = tokio::net::TcpListener::bind(args.addr)
+        .await
+        .map_err(|e| ServeError::with_debug(&e))?;
+    eprintln!("Listening on http://{}", args.addr);
+    axum::serve(listener, router)
+        .await
+        .map_err(|e| ServeError::with_debug(&e))
+}
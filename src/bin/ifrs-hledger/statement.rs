@@ -0,0 +1,167 @@
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand, ValueEnum};
+use fractic_ifrs_hledger::{
+    entities::PipelineConfig,
+    ext::custom_statements::{
+        CashFlowStatementGenerator, FinancialRatiosGenerator, ReportPeriod, StandardStatement,
+        StandardStatementGenerator, StatementFormat,
+    },
+};
+use fractic_server_error::ServerError;
+
+use crate::{errors::MissingArgument, write_output};
+
+#[derive(Subcommand)]
+pub(crate) enum StatementCommand {
+    /// Cash flow statement (indirect method).
+    Cashflow {
+        #[command(flatten)]
+        common: StatementArgs,
+
+        /// Reporting period, ex. 2024, 2024Q1, 2024-03 or
+        /// 2024-01-01..2024-06-30 (inclusive). Repeat to add comparative
+        /// columns. Defaults to `fiscal_year` from the config.
+        #[arg(long)]
+        period: Vec<String>,
+
+        /// Currency of the amounts in the journal. Defaults to
+        /// `main_currency` from the config, or USD.
+        #[arg(long)]
+        currency: Option<String>,
+
+        /// Append footnotes listing the specs contributing to each line item
+        /// of at least this amount in the latest period (text and JSON only).
+        #[arg(long, value_name = "MATERIALITY")]
+        footnotes: Option<f64>,
+    },
+    /// Balance sheet (statement of financial position).
+    Balance {
+        #[command(flatten)]
+        common: StatementArgs,
+
+        /// Reporting period, ex. 2024, 2024Q1, 2024-03 or
+        /// 2024-01-01..2024-06-30 (inclusive). Defaults to `fiscal_year` from
+        /// the config.
+        #[arg(long)]
+        period: Option<String>,
+    },
+    /// Income statement (profit or loss).
+    Income {
+        #[command(flatten)]
+        common: StatementArgs,
+
+        /// Reporting period, ex. 2024, 2024Q1, 2024-03 or
+        /// 2024-01-01..2024-06-30 (inclusive). Defaults to `fiscal_year` from
+        /// the config.
+        #[arg(long)]
+        period: Option<String>,
+    },
+    /// Financial ratios (liquidity, margins and leverage).
+    Ratios {
+        #[command(flatten)]
+        common: StatementArgs,
+
+        /// Reporting period, ex. 2024, 2024Q1, 2024-03 or
+        /// 2024-01-01..2024-06-30 (inclusive). Repeat to add comparative
+        /// columns. Defaults to `fiscal_year` from the config.
+        #[arg(long)]
+        period: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+pub(crate) struct StatementArgs {
+    /// Journal generated by `ifrs-hledger process`.
+    #[arg(long)]
+    ledger: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Write the statement to this file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl From<Format> for StatementFormat {
+    fn from(value: Format) -> Self {
+        match value {
+            Format::Text => StatementFormat::Text,
+            Format::Json => StatementFormat::Json,
+            Format::Csv => StatementFormat::Csv,
+        }
+    }
+}
+
+pub(crate) async fn run(
+    command: StatementCommand,
+    pipeline: PipelineConfig,
+) -> Result<(), ServerError> {
+    let default_period = || {
+        pipeline
+            .fiscal_year
+            .ok_or_else(|| MissingArgument::new("--period", "fiscal_year"))
+            .and_then(ReportPeriod::year)
+    };
+    let parse_periods = |periods: Vec<String>| {
+        if periods.is_empty() {
+            return Ok(vec![default_period()?]);
+        }
+        periods
+            .iter()
+            .map(|p| p.parse())
+            .collect::<Result<Vec<ReportPeriod>, ServerError>>()
+    };
+    let (common, output) = match command {
+        StatementCommand::Cashflow {
+            common,
+            period,
+            currency,
+            footnotes,
+        } => {
+            let periods = parse_periods(period)?;
+            let currency = currency
+                .or(pipeline.main_currency)
+                .unwrap_or_else(|| "USD".into());
+            let mut generator = CashFlowStatementGenerator::new(&common.ledger, periods, currency)?;
+            if let Some(materiality) = footnotes {
+                generator = generator.with_footnotes(materiality);
+            }
+            let output = generator.generate_with_format(common.format.into())?;
+            (common, output)
+        }
+        StatementCommand::Balance { common, period } => {
+            let output = StandardStatementGenerator::new(
+                &common.ledger,
+                period.map_or_else(default_period, |p| p.parse())?,
+                StandardStatement::BalanceSheet,
+            )?
+            .generate(common.format.into())?;
+            (common, output)
+        }
+        StatementCommand::Income { common, period } => {
+            let output = StandardStatementGenerator::new(
+                &common.ledger,
+                period.map_or_else(default_period, |p| p.parse())?,
+                StandardStatement::IncomeStatement,
+            )?
+            .generate(common.format.into())?;
+            (common, output)
+        }
+        StatementCommand::Ratios { common, period } => {
+            let periods = parse_periods(period)?;
+            let output = FinancialRatiosGenerator::new(&common.ledger, periods)?
+                .generate_with_format(common.format.into())?;
+            (common, output)
+        }
+    };
+    write_output(common.output.as_deref(), &output).await
+}
@@ -1,127 +1,815 @@
-use std::process::Command;
+use std::{collections::BTreeMap, process::Command};
 
 use chrono::NaiveDate;
-use fractic_server_error::{define_client_error, define_internal_error};
+use fractic_server_error::{define_client_error, define_internal_error, ServerError};
 
-use crate::entities::{LiabilityAccount, TransactionSpecId};
+use crate::entities::{AssetClassification, InventoryCosting, LiabilityAccount, TransactionSpecId};
+use crate::presentation::error_catalog::{
+    extract_args, localize, parse_message, split_spec_suffix,
+};
+
+// Taxonomy.
+// ----------------------------------------------------------------------------
+
+/// Broad kind of an error, for callers that need to branch on failures (ex.
+/// show input errors to the user, but report invariant violations as bugs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// Malformed input (CSV, RON, configuration, paths).
+    InputError,
+    /// Well-formed input that breaks an accounting rule.
+    AccountingRuleViolation,
+    /// An external tool (ex. hledger) failed or returned an unexpected
+    /// response.
+    ExternalToolError,
+    /// Internal consistency check failed. Indicates a bug in this crate.
+    InternalInvariant,
+}
+
+/// Stable code of an error defined in this module. The code is included at
+/// the start of the error message (ex. `"[E103] Invalid CSV content: ..."`),
+/// and is never reassigned to a different error.
+///
+/// Each code has a message template per language in `res/errors/`. The English
+/// templates must match the messages defined below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    /// Name of the error constructor (ex. `"InvalidCsvContent"`).
+    pub name: &'static str,
+    pub category: ErrorCategory,
+    /// Format string of the English message, including the code prefix.
+    pub(crate) message: &'static str,
+}
+
+use ErrorCategory::*;
+
+/// Defines an error constructor (see `define_client_error!`) with a stable
+/// code, available as `Name::CODE`. Input and accounting rule errors are client
+/// errors, and the others are internal errors. Fails to compile if the message
+/// doesn't start with the code.
+macro_rules! define_coded_error {
+    ($code:ident, InputError, $name:ident, $message:literal $(, $args:tt)?) => {
+        define_client_error!($name, $message $(, $args)?);
+        define_coded_error!(@code $code, InputError, $name, $message);
+    };
+    ($code:ident, AccountingRuleViolation, $name:ident, $message:literal $(, $args:tt)?) => {
+        define_client_error!($name, $message $(, $args)?);
+        define_coded_error!(@code $code, AccountingRuleViolation, $name, $message);
+    };
+    ($code:ident, $category:ident, $name:ident, $message:literal $(, $args:tt)?) => {
+        define_internal_error!($name, $message $(, $args)?);
+        define_coded_error!(@code $code, $category, $name, $message);
+    };
+    (@code $code:ident, $category:ident, $name:ident, $message:literal) => {
+        impl $name {
+            pub const CODE: ErrorCode =
+                ErrorCode::new(stringify!($code), stringify!($name), $category, $message);
+        }
+        const _: () = assert!(
+            has_code_prefix($message, stringify!($code)),
+            concat!("message of ", stringify!($name), " must start with [", stringify!($code), "]"),
+        );
+    };
+}
+
+/// Whether the message starts with `"[<code>] "`.
+const fn has_code_prefix(message: &str, code: &str) -> bool {
+    let (message, code) = (message.as_bytes(), code.as_bytes());
+    if message.len() < code.len() + 3
+        || message[0] != b'['
+        || message[code.len() + 1] != b']'
+        || message[code.len() + 2] != b' '
+    {
+        return false;
+    }
+    let mut i = 0;
+    while i < code.len() {
+        if message[i + 1] != code[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const ERROR_CODES: &[ErrorCode] = &[
+    ReadError::CODE,
+    InvalidCsv::CODE,
+    InvalidCsvContent::CODE,
+    InvalidRon::CODE,
+    InvalidIsoDate::CODE,
+    InvalidIsoCurrencyCode::CODE,
+    InvalidAccountingAmount::CODE,
+    InvalidConfig::CODE,
+    UnknownStandardAccount::CODE,
+    HledgerInvalidPath::CODE,
+    InvalidCashFlowStatementPeriods::CODE,
+    MissingPriceIndex::CODE,
+    InvalidFinancialRatiosPeriods::CODE,
+    InvalidRecurrence::CODE,
+    InvalidDimension::CODE,
+    MissingFxRate::CODE,
+    WriteError::CODE,
+    InvalidReportPeriod::CODE,
+    InvalidAnnotation::CODE,
+    NothingToReverse::CODE,
+    UnknownAmendedSpec::CODE,
+    PayeeCountryUnknown::CODE,
+    InvalidCustomCommodity::CODE,
+    UnknownJournal::CODE,
+    InvalidHandlersConfig::CODE,
+    InvalidStandardStatementPeriods::CODE,
+    CommonStockCannotBePrepaid::CODE,
+    NonAmortizableAsset::CODE,
+    VariableExpenseInvalidPaymentDate::CODE,
+    VariableExpenseNotEnoughHistoricalData::CODE,
+    VariableExpenseNoInit::CODE,
+    VariableExpenseDoubleInit::CODE,
+    ClearVatInvalidBackingAccount::CODE,
+    InvalidArgumentsForAccountingLogic::CODE,
+    UnexpectedNegativeValue::CODE,
+    UnexpectedPositiveValue::CODE,
+    NoTransactionsToReimburse::CODE,
+    UnexpectedPartialReimbursement::CODE,
+    NoAccountsToClose::CODE,
+    NonRemeasurableAsset::CODE,
+    BalanceAssertionsFailed::CODE,
+    ClearVatSpansFilingPeriods::CODE,
+    BackdatedEntries::CODE,
+    InvalidResidualValue::CODE,
+    NegativeBorrowingRate::CODE,
+    EntriesAfterDisposal::CODE,
+    NonImpairableAsset::CODE,
+    AllowanceOverReversed::CODE,
+    InvoiceOverCollected::CODE,
+    NonPositiveInventoryQuantity::CODE,
+    IncomeTaxSettledBeforeYearEnd::CODE,
+    NonPositiveShareCount::CODE,
+    MissingIntercompanyCounterparty::CODE,
+    InterestCreditedBeforeAccrualEnd::CODE,
+    DepositOverRefunded::CODE,
+    NonPositiveDecliningBalanceRate::CODE,
+    NonPositiveTotalUnits::CODE,
+    NegativeMonthlyUnits::CODE,
+    MonthlyUnitsExceedUsefulLife::CODE,
+    MonthlyUnitsExceedTotalUnits::CODE,
+    InterestAccruedBeforeDrawdown::CODE,
+    MaturityNotAfterAccrualStart::CODE,
+    NothingToDispose::CODE,
+    InventoryOversold::CODE,
+    NonInventoryAsset::CODE,
+    InvalidFiscalYear::CODE,
+    TreasurySharesOverReissued::CODE,
+    IntercompanyCounterpartyMismatch::CODE,
+    IncomeTaxNotDatedAtYearEnd::CODE,
+    NoIncomeTaxAccrued::CODE,
+    InventoryCostingMismatch::CODE,
+    EntriesAfterImpairment::CODE,
+    HledgerCommandFailed::CODE,
+    HledgerQueryInvalidResponse::CODE,
+    HledgerCloseInvalidResponse::CODE,
+    ReimbursementTracingError::CODE,
+    UnreplacedPlaceholdersRemain::CODE,
+    RoundTripMismatch::CODE,
+    UnresolvedDefaultCommodity::CODE,
+];
+
+impl ErrorCode {
+    const fn new(
+        code: &'static str,
+        name: &'static str,
+        category: ErrorCategory,
+        message: &'static str,
+    ) -> Self {
+        Self {
+            code,
+            name,
+            category,
+            message,
+        }
+    }
+
+    pub fn all() -> &'static [ErrorCode] {
+        ERROR_CODES
+    }
+
+    pub fn from_code(code: &str) -> Option<ErrorCode> {
+        ERROR_CODES.iter().find(|c| c.code == code).copied()
+    }
+}
+
+/// Accessors for the structured information carried by errors from this crate.
+/// Errors from other crates (ex. `CriticalError`) have no code or category.
+pub trait ServerErrorExt {
+    fn code(&self) -> Option<ErrorCode>;
+    fn category(&self) -> Option<ErrorCategory>;
+    /// The transaction spec that caused the error, if any.
+    fn spec_id(&self) -> Option<TransactionSpecId>;
+    /// Code and message arguments, separated from the message text.
+    fn context(&self) -> Option<ErrorContext>;
+    /// User-facing message in the given language. Errors without a code are
+    /// returned as-is (in English).
+    fn localized(&self, locale: Locale) -> String;
+}
+
+impl ServerErrorExt for ServerError {
+    fn code(&self) -> Option<ErrorCode> {
+        parse_message(&self.to_string()).map(|parsed| parsed.code)
+    }
+
+    fn category(&self) -> Option<ErrorCategory> {
+        self.code().map(|c| c.category)
+    }
+
+    fn spec_id(&self) -> Option<TransactionSpecId> {
+        split_spec_suffix(&self.to_string()).1
+    }
+
+    fn context(&self) -> Option<ErrorContext> {
+        let message = self.to_string();
+        let parsed = parse_message(&message)?;
+        Some(ErrorContext {
+            code: parsed.code,
+            args: extract_args(&parsed)?,
+            spec_id: parsed.wrapped_spec_id,
+        })
+    }
+
+    fn localized(&self, locale: Locale) -> String {
+        let message = self.to_string();
+        parse_message(&message)
+            .and_then(|parsed| localize(&parsed, locale))
+            .unwrap_or(message)
+    }
+}
+
+/// Machine-readable context of an error, for callers that render their own
+/// messages.
+#[derive(Debug, Clone)]
+pub struct ErrorContext {
+    pub code: ErrorCode,
+    /// Values interpolated into the message, keyed by placeholder name (see
+    /// `res/errors/en.toml`). Values are formatted as in the English message.
+    pub args: BTreeMap<String, String>,
+    pub spec_id: Option<TransactionSpecId>,
+}
+
+/// Languages available for user-facing error messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    En,
+    Ko,
+}
+
+/// Attaches the spec ID to errors raised while processing a spec, unless it
+/// is already attached. The code of the original error is kept, and only
+/// input and accounting rule errors stay client errors: internal errors and
+/// errors from other crates (ex. `CriticalError`) are wrapped as internal
+/// errors.
+pub(crate) fn with_spec_id(error: ServerError, spec_id: TransactionSpecId) -> ServerError {
+    if error.spec_id().is_some() {
+        return error;
+    }
+    let details = error.to_string();
+    match error.category() {
+        Some(InputError | AccountingRuleViolation) => {
+            SpecProcessingFailed::with_debug(&details, &spec_id, &error)
+        }
+        _ => SpecProcessingInternalError::with_debug(&details, &spec_id, &error),
+    }
+}
+
+// Context. The suffix must match `error_catalog::SPEC_SUFFIX_START`.
+define_client_error!(
+    SpecProcessingFailed,
+    "{details} (spec: {spec_id})",
+    { details: &str, spec_id: &TransactionSpecId }
+);
+define_internal_error!(
+    SpecProcessingInternalError,
+    "{details} (spec: {spec_id})",
+    { details: &str, spec_id: &TransactionSpecId }
+);
 
 // IO-related.
-define_client_error!(ReadError, "Error reading file.");
+define_coded_error!(E101, InputError, ReadError, "[E101] Error reading file.");
+define_coded_error!(E117, InputError, WriteError, "[E117] Error writing file.");
 
 // Parsing-related.
-define_client_error!(InvalidCsv, "Invalid CSV format.");
-define_client_error!(InvalidCsvContent, "Invalid CSV content: {details}.", { details: &str });
-define_client_error!(InvalidRon, "Invalid {ron_type} (invalid RON format).", { ron_type: &str });
-define_client_error!(InvalidIsoDate, "Invalid ISO date: {date}.", { date: &str });
-define_client_error!(InvalidIsoCurrencyCode, "Invalid ISO currency code: {code}.", { code: &str });
-define_client_error!(
+define_coded_error!(E102, InputError, InvalidCsv, "[E102] Invalid CSV format.");
+define_coded_error!(E103, InputError, InvalidCsvContent, "[E103] Invalid CSV content: {details}.", { details: &str });
+define_coded_error!(E104, InputError, InvalidRon, "[E104] Invalid {ron_type} (invalid RON format).", { ron_type: &str });
+define_coded_error!(E105, InputError, InvalidIsoDate, "[E105] Invalid ISO date: {date}.", { date: &str });
+define_coded_error!(E106, InputError, InvalidIsoCurrencyCode, "[E106] Invalid ISO currency code: {code}.", { code: &str });
+define_coded_error!(
+    E107,
+    InputError,
     InvalidAccountingAmount,
-    "Invalid accounting amount: '{value}'.",
+    "[E107] Invalid accounting amount: '{value}'.",
     { value: &str }
 );
+define_coded_error!(
+    E114,
+    InputError,
+    InvalidRecurrence,
+    "[E114] Invalid recurrence rule for '{description}': {details}.",
+    { description: &str, details: &str }
+);
+define_coded_error!(
+    E115,
+    InputError,
+    InvalidDimension,
+    "[E115] Invalid dimension '{value}': {details}.",
+    { value: &str, details: &str }
+);
+define_coded_error!(
+    E116,
+    InputError,
+    MissingFxRate,
+    "[E116] No exchange rate available from {from} to {to}.",
+    { from: &str, to: &str }
+);
+
+// Configuration-related.
+define_coded_error!(
+    E108,
+    InputError,
+    InvalidConfig,
+    "[E108] Invalid configuration: {details}.",
+    { details: &str }
+);
+define_coded_error!(
+    E109,
+    InputError,
+    UnknownStandardAccount,
+    "[E109] Unknown standard account '{name}'. Overrides must use the default name of a standard named sub-account (ex. 'PaymentFees').",
+    { name: &str }
+);
+define_coded_error!(
+    E122,
+    InputError,
+    PayeeCountryUnknown,
+    "[E122] Payee '{payee}' has no country, which the {decorator} decorator needs to pick its treatment.",
+    { payee: &str, decorator: &str }
+);
+define_coded_error!(
+    E123,
+    InputError,
+    InvalidCustomCommodity,
+    "[E123] Invalid custom commodity '{symbol}': {details}.",
+    { symbol: &str, details: &str }
+);
+define_coded_error!(
+    E124,
+    InputError,
+    UnknownJournal,
+    "[E124] Unknown journal: {id}.",
+    { id: u64 }
+);
+define_coded_error!(
+    E125,
+    InputError,
+    InvalidHandlersConfig,
+    "[E125] Invalid handlers configuration: {details}.",
+    { details: &str }
+);
+define_coded_error!(
+    E126,
+    InputError,
+    InvalidStandardStatementPeriods,
+    "[E126] Balance sheets and income statements take exactly 1 period, but {count} were given.",
+    { count: usize }
+);
 
 // Accounting-related.
-define_client_error!(
+define_coded_error!(
+    E201,
+    AccountingRuleViolation,
     CommonStockCannotBePrepaid,
-    "CommonStock: '{description}' cannot have a payment date before accrual, since this would indicate prepayment for stock.",
+    "[E201] CommonStock: '{description}' cannot have a payment date before accrual, since this would indicate prepayment for stock.",
     { description: &str }
 );
-define_client_error!(
+define_coded_error!(
+    E202,
+    AccountingRuleViolation,
     NonAmortizableAsset,
-    "Asset '{name}' does not have any value defined for 'upon_accrual()'. Provide a non-None value to support amortization.",
+    "[E202] Asset '{name}' does not have any value defined for 'upon_accrual()'. Provide a non-None value to support amortization.",
     { name: &str }
 );
-define_client_error!(
+define_coded_error!(
+    E203,
+    AccountingRuleViolation,
     VariableExpenseInvalidPaymentDate,
-    "Invalid VariableExpense: '{description}'. Payment date ({payment_date}) must be after accrual period (accrual end: {until_date}), otherwise it would indicate we're prepaying for an unknown expense.",
+    "[E203] Invalid VariableExpense: '{description}'. Payment date ({payment_date}) must be after accrual period (accrual end: {until_date}), otherwise it would indicate we're prepaying for an unknown expense.",
     { description: &str, payment_date: &NaiveDate, until_date: &NaiveDate }
 );
-define_client_error!(
+define_coded_error!(
+    E204,
+    AccountingRuleViolation,
     VariableExpenseNotEnoughHistoricalData,
-    "No historical data for VariableExpense: '{description}' in the previous 90 days.",
+    "[E204] No historical data for VariableExpense: '{description}' in the previous 90 days.",
     { description: &str }
 );
-define_client_error!(
+define_coded_error!(
+    E205,
+    AccountingRuleViolation,
     VariableExpenseNoInit,
-    "VariableExpense: '{description}' not initialized. Must initiate with a VariableExpenseInit entry.",
+    "[E205] VariableExpense: '{description}' not initialized. Must initiate with a VariableExpenseInit entry.",
     { description: &str }
 );
-define_client_error!(
+define_coded_error!(
+    E206,
+    AccountingRuleViolation,
     VariableExpenseDoubleInit,
-    "VariableExpense: '{description}' already initialized. Cannot initialize twice.",
+    "[E206] VariableExpense: '{description}' already initialized. Cannot initialize twice.",
     { description: &str }
 );
-define_client_error!(
+define_coded_error!(
+    E207,
+    AccountingRuleViolation,
     ClearVatInvalidBackingAccount,
-    "ClearVat entry '{description}' requires a Cash backing account.",
+    "[E207] ClearVat entry '{description}' requires a Cash backing account.",
     { description: &str }
 );
-define_client_error!(
+define_coded_error!(
+    E208,
+    AccountingRuleViolation,
     InvalidArgumentsForAccountingLogic,
-    "Invalid arguments provided for accounting logic type."
+    "[E208] Invalid arguments provided for accounting logic type."
 );
-define_client_error!(
+define_coded_error!(
+    E209,
+    AccountingRuleViolation,
     UnexpectedNegativeValue,
-    "Unexpected negative amount ({amount}) for '{accounting_logic}' accounting logic (id: {spec_id:?}).",
+    "[E209] Unexpected negative amount ({amount}) for '{accounting_logic}' accounting logic (id: {spec_id:?}).",
     { amount: f64, accounting_logic: &str, spec_id: &TransactionSpecId }
 );
-define_client_error!(
+define_coded_error!(
+    E210,
+    AccountingRuleViolation,
     UnexpectedPositiveValue,
-    "Unexpected positive amount ({amount}) for '{accounting_logic}' accounting logic (id: {spec_id:?}).",
+    "[E210] Unexpected positive amount ({amount}) for '{accounting_logic}' accounting logic (id: {spec_id:?}).",
     { amount: f64, accounting_logic: &str, spec_id: &TransactionSpecId }
 );
-define_internal_error!(
+define_coded_error!(
+    E401,
+    InternalInvariant,
     ReimbursementTracingError,
-    "Error tracing reimbursements: {details}.",
+    "[E401] Error tracing reimbursements: {details}.",
     { details: &str }
 );
-define_client_error!(
+define_coded_error!(
+    E403,
+    InternalInvariant,
+    RoundTripMismatch,
+    "[E403] Round-trip verification failed: {details}.",
+    { details: &str }
+);
+define_coded_error!(
+    E404,
+    InternalInvariant,
+    UnresolvedDefaultCommodity,
+    "[E404] The default commodity of `ConfiguredHandlers` is only known while parsing specs (within `ConfiguredHandlers::with_config`)."
+);
+define_coded_error!(
+    E211,
+    AccountingRuleViolation,
     NoTransactionsToReimburse,
-    "Reimburse spec '{spec_id:?}' can't be mapped to any unreimbursed transactions for '{account:?}'.",
+    "[E211] Reimburse spec '{spec_id:?}' can't be mapped to any unreimbursed transactions for '{account:?}'.",
     { spec_id: &TransactionSpecId, account: &LiabilityAccount }
 );
-define_client_error!(
+define_coded_error!(
+    E212,
+    AccountingRuleViolation,
     UnexpectedPartialReimbursement,
-    "Reimburse spec '{spec_id:?}' unexpectedly leaves an unreimbursed amount of {amount} for '{account:?}'.",
+    "[E212] Reimburse spec '{spec_id:?}' unexpectedly leaves an unreimbursed amount of {amount} for '{account:?}'.",
     { spec_id: &TransactionSpecId, account: &LiabilityAccount, amount: f64 }
 );
+define_coded_error!(
+    E214,
+    AccountingRuleViolation,
+    NonRemeasurableAsset,
+    "[E214] Asset '{name}' is not an investment (ShortTermInvestments or LongTermInvestments), so it can't be remeasured to fair value.",
+    { name: &str }
+);
+define_coded_error!(
+    E215,
+    AccountingRuleViolation,
+    BalanceAssertionsFailed,
+    "[E215] {count} balance assertion(s) fail for the generated transactions:\n{details}",
+    { count: usize, details: &str }
+);
+define_coded_error!(
+    E216,
+    AccountingRuleViolation,
+    ClearVatSpansFilingPeriods,
+    "[E216] ClearVat entry '{description}' spans more than one VAT filing period ({from_period} to {to_period}).",
+    { description: &str, from_period: &str, to_period: &str }
+);
+define_coded_error!(
+    E217,
+    AccountingRuleViolation,
+    BackdatedEntries,
+    "[E217] {count} spec(s) generate entries dated on or before an already reconciled balance:\n{details}",
+    { count: usize, details: &str }
+);
+define_coded_error!(
+    E218,
+    AccountingRuleViolation,
+    InvalidResidualValue,
+    "[E218] Depreciate: '{description}' has a residual value ({residual_value}) that isn't between 0 and the cost ({cost}).",
+    { description: &str, residual_value: f64, cost: f64 }
+);
+define_coded_error!(
+    E219,
+    AccountingRuleViolation,
+    NegativeBorrowingRate,
+    "[E219] Borrowing: '{description}' has a negative annual rate ({annual_rate}).",
+    { description: &str, annual_rate: f64 }
+);
+define_coded_error!(
+    E220,
+    AccountingRuleViolation,
+    EntriesAfterDisposal,
+    "[E220] DisposeAsset: '{description}' has entries dated {later}, after its disposal on {payment_date} (ex. depreciation past the disposal date).",
+    { description: &str, later: &NaiveDate, payment_date: &NaiveDate }
+);
+define_coded_error!(
+    E221,
+    AccountingRuleViolation,
+    NonImpairableAsset,
+    "[E221] Asset '{name}' is not a non-current asset (PropertyPlantEquipment, IntangibleAssets or OtherNonCurrentAssets), so it can't be impaired.",
+    { name: &str }
+);
+define_coded_error!(
+    E222,
+    AccountingRuleViolation,
+    AllowanceOverReversed,
+    "[E222] ProvisionForDoubtfulDebt: '{description}' reverses {amount}, more than the allowance of {allowance} as of {payment_date}.",
+    { description: &str, amount: &str, allowance: &str, payment_date: &NaiveDate }
+);
+define_coded_error!(
+    E223,
+    AccountingRuleViolation,
+    InvoiceOverCollected,
+    "[E223] CollectInvoice: '{description}' collects {amount}, more than the {outstanding} outstanding from '{payee}'.",
+    { description: &str, amount: &str, outstanding: &str, payee: &str }
+);
+define_coded_error!(
+    E224,
+    AccountingRuleViolation,
+    NonPositiveInventoryQuantity,
+    "[E224] {logic}: '{description}' has a non-positive quantity ({quantity}).",
+    { logic: &str, description: &str, quantity: f64 }
+);
+define_coded_error!(
+    E225,
+    AccountingRuleViolation,
+    IncomeTaxSettledBeforeYearEnd,
+    "[E225] SettleIncomeTax: '{description}' is paid on {payment_date}, before the end of fiscal year {fiscal_year}.",
+    { description: &str, payment_date: &NaiveDate, fiscal_year: i32 }
+);
+define_coded_error!(
+    E226,
+    AccountingRuleViolation,
+    NonPositiveShareCount,
+    "[E226] {logic}: '{description}' has a non-positive number of shares ({shares}).",
+    { logic: &str, description: &str, shares: f64 }
+);
+define_coded_error!(
+    E227,
+    AccountingRuleViolation,
+    MissingIntercompanyCounterparty,
+    "[E227] IntercompanyTransfer: '{description}' has no counterparty.",
+    { description: &str }
+);
+define_coded_error!(
+    E228,
+    AccountingRuleViolation,
+    InterestCreditedBeforeAccrualEnd,
+    "[E228] InterestIncome: '{description}' is credited on {payment_date}, before the end of its accrual period ({accrual_end}).",
+    { description: &str, payment_date: &str, accrual_end: &str }
+);
+define_coded_error!(
+    E229,
+    AccountingRuleViolation,
+    DepositOverRefunded,
+    "[E229] RefundDeposit: '{description}' refunds {amount}, more than the {outstanding} of open deposits with '{payee}'.",
+    { description: &str, amount: &str, outstanding: &str, payee: &str }
+);
+define_coded_error!(
+    E230,
+    AccountingRuleViolation,
+    NonPositiveDecliningBalanceRate,
+    "[E230] Depreciate: '{description}' has a non-positive declining balance rate ({rate}).",
+    { description: &str, rate: f64 }
+);
+define_coded_error!(
+    E231,
+    AccountingRuleViolation,
+    NonPositiveTotalUnits,
+    "[E231] Depreciate: '{description}' has non-positive total units ({total_units}).",
+    { description: &str, total_units: f64 }
+);
+define_coded_error!(
+    E232,
+    AccountingRuleViolation,
+    NegativeMonthlyUnits,
+    "[E232] Depreciate: '{description}' has negative monthly units.",
+    { description: &str }
+);
+define_coded_error!(
+    E233,
+    AccountingRuleViolation,
+    MonthlyUnitsExceedUsefulLife,
+    "[E233] Depreciate: '{description}' has units for {unit_months} months, but a useful life of {useful_life_months} months.",
+    { description: &str, unit_months: usize, useful_life_months: usize }
+);
+define_coded_error!(
+    E234,
+    AccountingRuleViolation,
+    MonthlyUnitsExceedTotalUnits,
+    "[E234] Depreciate: '{description}' has more monthly units ({produced}) than total units ({total_units}).",
+    { description: &str, produced: f64, total_units: f64 }
+);
+define_coded_error!(
+    E235,
+    AccountingRuleViolation,
+    InterestAccruedBeforeDrawdown,
+    "[E235] Borrowing: '{description}' accrues interest from {accrual_start}, before the drawdown on {payment_date}.",
+    { description: &str, accrual_start: &NaiveDate, payment_date: &NaiveDate }
+);
+define_coded_error!(
+    E236,
+    AccountingRuleViolation,
+    MaturityNotAfterAccrualStart,
+    "[E236] Borrowing: '{description}' matures on {accrual_end}, not after the accrual start ({accrual_start}).",
+    { description: &str, accrual_end: &NaiveDate, accrual_start: &NaiveDate }
+);
+define_coded_error!(
+    E237,
+    AccountingRuleViolation,
+    NothingToDispose,
+    "[E237] DisposeAsset: '{description}' has no carrying amount to dispose of on {payment_date}.",
+    { description: &str, payment_date: &NaiveDate }
+);
+define_coded_error!(
+    E238,
+    AccountingRuleViolation,
+    InventoryOversold,
+    "[E238] RecognizeCogs: '{description}' sells {quantity} units, more than the {in_stock} in stock as of {sale_date}.",
+    { description: &str, quantity: f64, in_stock: f64, sale_date: &NaiveDate }
+);
+define_coded_error!(
+    E239,
+    AccountingRuleViolation,
+    NonInventoryAsset,
+    "[E239] {logic}: '{description}' is for a {classification:?} asset, not Inventory.",
+    { logic: &str, description: &str, classification: &AssetClassification }
+);
+define_coded_error!(
+    E240,
+    AccountingRuleViolation,
+    InvalidFiscalYear,
+    "[E240] {logic}: '{description}' has an invalid fiscal year ({fiscal_year}).",
+    { logic: &str, description: &str, fiscal_year: i32 }
+);
+define_coded_error!(
+    E241,
+    AccountingRuleViolation,
+    TreasurySharesOverReissued,
+    "[E241] ReissueTreasuryShares: '{description}' reissues {shares} shares, more than the {held} held as of {payment_date}.",
+    { description: &str, shares: f64, held: f64, payment_date: &NaiveDate }
+);
+define_coded_error!(
+    E242,
+    AccountingRuleViolation,
+    IntercompanyCounterpartyMismatch,
+    "[E242] IntercompanyTransfer: '{description}' is with '{counterparty}', but the spec's intercompany counterparty is '{intercompany}'.",
+    { description: &str, counterparty: &str, intercompany: &str }
+);
+define_coded_error!(
+    E243,
+    AccountingRuleViolation,
+    IncomeTaxNotDatedAtYearEnd,
+    "[E243] IncomeTax: '{description}' is dated {payment_date}, but must be dated at the end of fiscal year {fiscal_year} ({year_end}), when it is accrued.",
+    { description: &str, payment_date: &NaiveDate, fiscal_year: i32, year_end: &NaiveDate }
+);
+define_coded_error!(
+    E244,
+    AccountingRuleViolation,
+    NoIncomeTaxAccrued,
+    "[E244] SettleIncomeTax: '{description}' pays income tax for fiscal year {fiscal_year}, but none is accrued as of {payment_date}. Each fiscal year is settled by a single payment, after its IncomeTax accrual.",
+    { description: &str, fiscal_year: i32, payment_date: &NaiveDate }
+);
+define_coded_error!(
+    E245,
+    AccountingRuleViolation,
+    InventoryCostingMismatch,
+    "[E245] PurchaseInventory: '{description}' uses {costing:?} costing, but earlier purchases of the item use {previous:?}.",
+    { description: &str, costing: &InventoryCosting, previous: &InventoryCosting }
+);
+define_coded_error!(
+    E246,
+    AccountingRuleViolation,
+    EntriesAfterImpairment,
+    "[E246] Impair: '{description}' has entries dated {later}, after its impairment on {payment_date} (ex. depreciation past the impairment date). Depreciation isn't recalculated for an impairment, so it must end by the impairment date.",
+    { description: &str, later: &NaiveDate, payment_date: &NaiveDate }
+);
 
 // Hledger-related.
-define_client_error!(
+define_coded_error!(
+    E110,
+    InputError,
     HledgerInvalidPath,
-    "Invalid path to hledger ledger file: '{ledger}'.",
+    "[E110] Invalid path to hledger ledger file: '{ledger}'.",
     { ledger: &str }
 );
-define_internal_error!(
+define_coded_error!(
+    E301,
+    ExternalToolError,
     HledgerCommandFailed,
-    "hledger command failed for ledger '{ledger}':\n\n{command:?}",
+    "[E301] hledger command failed for ledger '{ledger}':\n\n{command:?}",
     { ledger: &str, command: &Command }
 );
-define_internal_error!(
+define_coded_error!(
+    E302,
+    ExternalToolError,
     HledgerQueryInvalidResponse,
-    "hledger command returned an unexpected response. Could not parse total change during the given period:\n\n{command:?}\n\nQuery: {query}\n\nReturn: {fetch}",
+    "[E302] hledger command returned an unexpected response. Could not parse total change during the given period:\n\n{command:?}\n\nQuery: {query}\n\nReturn: {fetch}",
     { command: &Command, query: String, fetch: String }
 );
-define_internal_error!(
+define_coded_error!(
+    E303,
+    ExternalToolError,
     HledgerCloseInvalidResponse,
-    "'hledger close' returned an unexpected response: {details}.",
+    "[E303] 'hledger close' returned an unexpected response: {details}.",
     { details: String }
 );
 
 // Custom statement generation.
-define_client_error!(
+define_coded_error!(
+    E111,
+    InputError,
     InvalidCashFlowStatementPeriods,
-    "The cash flow statement requires at least 1 period."
+    "[E111] The cash flow statement requires at least 1 period."
 );
-define_internal_error!(
+define_coded_error!(
+    E113,
+    InputError,
+    InvalidFinancialRatiosPeriods,
+    "[E113] The financial ratios report requires at least 1 period."
+);
+define_coded_error!(
+    E118,
+    InputError,
+    InvalidReportPeriod,
+    "[E118] Invalid report period '{period}': {details}.",
+    { period: &str, details: &str }
+);
+define_coded_error!(
+    E119,
+    InputError,
+    InvalidAnnotation,
+    "[E119] Invalid annotation '{value}': {details}.",
+    { value: &str, details: &str }
+);
+define_coded_error!(
+    E402,
+    InternalInvariant,
     UnreplacedPlaceholdersRemain,
-    "Unexpected placeholders remain: {unreplaced:?}.",
+    "[E402] Unexpected placeholders remain: {unreplaced:?}.",
     { unreplaced: &Vec<String> }
 );
 
 // Derived record generation.
-define_client_error!(
+define_coded_error!(
+    E112,
+    InputError,
+    MissingPriceIndex,
+    "[E112] No price index value on or before {date}.",
+    { date: &NaiveDate }
+);
+define_coded_error!(
+    E120,
+    InputError,
+    NothingToReverse,
+    "[E120] Spec {spec_id:?} generated no transactions to reverse.",
+    { spec_id: &TransactionSpecId }
+);
+define_coded_error!(
+    E121,
+    InputError,
+    UnknownAmendedSpec,
+    "[E121] Spec {spec_id:?} amends spec {amended}, which generated no transactions.",
+    { spec_id: &TransactionSpecId, amended: &TransactionSpecId }
+);
+define_coded_error!(
+    E213,
+    AccountingRuleViolation,
     NoAccountsToClose,
-    "No income/expense accounts to close for year {year}. Does the ledger already have a close entry for {year}?",
+    "[E213] No income/expense accounts to close for year {year}. Does the ledger already have a close entry for {year}?",
     { year: i32 }
 );
@@ -2,19 +2,24 @@ use std::str::FromStr as _;
 
 use async_trait::async_trait;
 use fractic_server_error::ServerError;
+use iso_currency::Currency;
 use ron::from_str;
 
 use crate::{
     data::models::{accounting_amount_model::AccountingAmountModel, iso_date_model::ISODateModel},
-    domain::entities::assertion_spec::AssertionSpec,
-    entities::Handlers,
-    errors::{InvalidCsv, InvalidRon, ReadError},
+    domain::entities::assertion_spec::{AssertionCommodity, AssertionSpec},
+    entities::{CommodityHandler as _, Handlers},
+    errors::{InvalidCsv, InvalidRon},
 };
 
+#[cfg(feature = "fs")]
+use crate::errors::ReadError;
+
 #[async_trait]
 pub(crate) trait BalancesCsvDatasource<H: Handlers>: Send + Sync {
     fn from_string(&self, s: &str) -> Result<Vec<AssertionSpec<H>>, ServerError>;
 
+    #[cfg(feature = "fs")]
     async fn from_file<P>(&self, path: P) -> Result<Vec<AssertionSpec<H>>, ServerError>
     where
         P: AsRef<std::path::Path> + Send;
@@ -35,7 +40,13 @@ impl<H: Handlers> BalancesCsvDatasourceImpl<H> {
 #[async_trait]
 impl<H: Handlers> BalancesCsvDatasource<H> for BalancesCsvDatasourceImpl<H> {
     fn from_string(&self, s: &str) -> Result<Vec<AssertionSpec<H>>, ServerError> {
-        csv::Reader::from_reader(s.as_bytes())
+        let mut reader = csv::Reader::from_reader(s.as_bytes());
+        let column_currency = reader
+            .headers()
+            .map_err(|e| InvalidCsv::with_debug(&e))?
+            .get(2)
+            .and_then(currency_from_header);
+        reader
             .records()
             .map(|r| {
                 r.map_err(|e| InvalidCsv::with_debug(&e)).and_then(|r| {
@@ -43,7 +54,8 @@ impl<H: Handlers> BalancesCsvDatasource<H> for BalancesCsvDatasourceImpl<H> {
                     let raw_account = r.get(0).unwrap_or("");
                     let raw_date = r.get(1).unwrap_or("");
                     let raw_balance = r.get(2).unwrap_or("0");
-                    let raw_commodity = r.get(3).unwrap_or("");
+                    let raw_commodity = r.get(3).unwrap_or("").trim();
+                    let raw_entity = r.get(4).unwrap_or("").trim();
 
                     // Parse.
                     let date: ISODateModel = ISODateModel::from_str(raw_date)?;
@@ -51,8 +63,16 @@ impl<H: Handlers> BalancesCsvDatasource<H> for BalancesCsvDatasourceImpl<H> {
                         from_str(raw_account).map_err(|e| InvalidRon::with_debug("Cash", &e))?;
                     let balance: AccountingAmountModel =
                         AccountingAmountModel::from_str(raw_balance)?;
-                    let commodity: H::M = from_str(raw_commodity)
-                        .map_err(|e| InvalidRon::with_debug("Commodity", &e))?;
+                    let commodity = if !raw_commodity.is_empty() {
+                        AssertionCommodity::Explicit(
+                            from_str(raw_commodity)
+                                .map_err(|e| InvalidRon::with_debug("Commodity", &e))?,
+                        )
+                    } else if let Some(currency) = column_currency {
+                        AssertionCommodity::Column(currency)
+                    } else {
+                        AssertionCommodity::Infer(H::M::default())
+                    };
 
                     // Build.
                     Ok(AssertionSpec {
@@ -60,12 +80,14 @@ impl<H: Handlers> BalancesCsvDatasource<H> for BalancesCsvDatasourceImpl<H> {
                         cash_handler,
                         balance: balance.into(),
                         commodity,
+                        entity: (!raw_entity.is_empty()).then(|| raw_entity.into()),
                     })
                 })
             })
             .collect()
     }
 
+    #[cfg(feature = "fs")]
     async fn from_file<P>(&self, path: P) -> Result<Vec<AssertionSpec<H>>, ServerError>
     where
         P: AsRef<std::path::Path> + Send,
@@ -77,3 +99,14 @@ impl<H: Handlers> BalancesCsvDatasource<H> for BalancesCsvDatasourceImpl<H> {
         )
     }
 }
+
+/// Currency given in the balance column header, as an ISO code in parentheses
+/// or as the last word (ex. "Balance (KRW)" or "Balance KRW").
+fn currency_from_header(header: &str) -> Option<Currency> {
+    let header = header.trim();
+    let code = match header.strip_suffix(')') {
+        Some(rest) => &rest[rest.rfind('(')? + 1..],
+        None => header.rsplit(' ').next()?,
+    };
+    Currency::from_code(code.trim())
+}
@@ -0,0 +1,109 @@
+use std::str::FromStr as _;
+
+use async_trait::async_trait;
+use fractic_server_error::ServerError;
+use ron::from_str;
+
+use crate::{
+    data::models::{
+        accounting_amount_model::AccountingAmountModel, budget_target_model::BudgetTargetModel,
+        iso_date_model::ISODateModel,
+    },
+    entities::{BudgetSpec, CommodityHandler, Handlers},
+    errors::{InvalidCsv, InvalidCsvContent, InvalidRon},
+};
+
+#[cfg(feature = "fs")]
+use crate::errors::ReadError;
+
+#[async_trait]
+pub(crate) trait BudgetsCsvDatasource<H: Handlers>: Send + Sync {
+    fn from_string(&self, s: &str) -> Result<Vec<BudgetSpec<H>>, ServerError>;
+
+    #[cfg(feature = "fs")]
+    async fn from_file<P>(&self, path: P) -> Result<Vec<BudgetSpec<H>>, ServerError>
+    where
+        P: AsRef<std::path::Path> + Send;
+}
+
+pub(crate) struct BudgetsCsvDatasourceImpl<H: Handlers> {
+    _phantom: std::marker::PhantomData<H>,
+}
+
+impl<H: Handlers> BudgetsCsvDatasourceImpl<H> {
+    pub(crate) fn new() -> Self {
+        Self {
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<H: Handlers> BudgetsCsvDatasource<H> for BudgetsCsvDatasourceImpl<H> {
+    fn from_string(&self, s: &str) -> Result<Vec<BudgetSpec<H>>, ServerError> {
+        csv::Reader::from_reader(s.as_bytes())
+            .records()
+            .filter(|r| {
+                // Skip empty/whitespace-only lines.
+                r.as_ref()
+                    .map_or(true, |r| !r.iter().all(|cell| cell.trim().is_empty()))
+            })
+            .map(|r| {
+                r.map_err(|e| InvalidCsv::with_debug(&e)).and_then(|r| {
+                    // Extract from CSV record.
+                    let raw_target = r.get(0).unwrap_or("");
+                    let raw_from = r.get(1).unwrap_or("");
+                    let raw_to = r.get(2).unwrap_or("");
+                    let raw_amount = r.get(3).unwrap_or("0");
+                    let raw_commodity = r.get(4).unwrap_or("").trim();
+                    let raw_entity = r.get(5).unwrap_or("").trim();
+
+                    // Parse.
+                    let target: BudgetTargetModel<H::E, H::I> = from_str(raw_target)
+                        .map_err(|e| InvalidRon::with_debug("BudgetTarget", &e))?;
+                    let from: ISODateModel = ISODateModel::from_str(raw_from)?;
+                    let to: ISODateModel = ISODateModel::from_str(raw_to)?;
+                    let amount: AccountingAmountModel =
+                        AccountingAmountModel::from_str(raw_amount)?;
+                    let commodity: H::M = if raw_commodity.is_empty() {
+                        H::M::default()
+                    } else {
+                        from_str(raw_commodity)
+                            .map_err(|e| InvalidRon::with_debug("Commodity", &e))?
+                    };
+
+                    // Build.
+                    let spec = BudgetSpec {
+                        target: target.into(),
+                        from: from.into(),
+                        to: to.into(),
+                        amount: amount.into(),
+                        commodity,
+                        entity: (!raw_entity.is_empty()).then(|| raw_entity.into()),
+                    };
+
+                    // Run assertions.
+                    if spec.to < spec.from {
+                        return Err(InvalidCsvContent::with_debug(
+                            "Budget end date must be after start date.",
+                            &spec,
+                        ));
+                    }
+                    Ok(spec)
+                })
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "fs")]
+    async fn from_file<P>(&self, path: P) -> Result<Vec<BudgetSpec<H>>, ServerError>
+    where
+        P: AsRef<std::path::Path> + Send,
+    {
+        self.from_string(
+            &tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| ReadError::with_debug(&e))?,
+        )
+    }
+}
@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use fractic_server_error::ServerError;
+
+use crate::{
+    data::models::pipeline_config_model::PipelineConfigModel, entities::PipelineConfig,
+    errors::InvalidConfig,
+};
+
+#[cfg(feature = "fs")]
+use crate::errors::ReadError;
+
+#[async_trait]
+pub(crate) trait ConfigTomlDatasource: Send + Sync {
+    fn from_string(&self, s: &str) -> Result<PipelineConfig, ServerError>;
+
+    #[cfg(feature = "fs")]
+    async fn from_file<P>(&self, path: P) -> Result<PipelineConfig, ServerError>
+    where
+        P: AsRef<std::path::Path> + Send;
+}
+
+pub(crate) struct ConfigTomlDatasourceImpl;
+
+impl ConfigTomlDatasourceImpl {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ConfigTomlDatasource for ConfigTomlDatasourceImpl {
+    fn from_string(&self, s: &str) -> Result<PipelineConfig, ServerError> {
+        parse(s)?.into_config(None)
+    }
+
+    #[cfg(feature = "fs")]
+    async fn from_file<P>(&self, path: P) -> Result<PipelineConfig, ServerError>
+    where
+        P: AsRef<std::path::Path> + Send,
+    {
+        let path = path.as_ref().to_path_buf();
+        let raw = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| ReadError::with_debug(&e))?;
+        parse(&raw)?.into_config(path.parent())
+    }
+}
+
+fn parse(s: &str) -> Result<PipelineConfigModel, ServerError> {
+    toml::from_str(s).map_err(|e| InvalidConfig::new(e.message()))
+}
+
+impl PipelineConfig {
+    /// Parse a TOML config. Relative paths are kept as-is.
+    pub fn from_toml(s: &str) -> Result<Self, ServerError> {
+        ConfigTomlDatasourceImpl::new().from_string(s)
+    }
+
+    /// Load a TOML config file. Relative paths are resolved against the file's
+    /// directory.
+    #[cfg(feature = "fs")]
+    pub async fn from_toml_file<P>(path: P) -> Result<Self, ServerError>
+    where
+        P: AsRef<std::path::Path> + Send,
+    {
+        ConfigTomlDatasourceImpl::new().from_file(path).await
+    }
+}
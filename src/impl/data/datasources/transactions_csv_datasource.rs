@@ -7,24 +7,37 @@ use ron::from_str;
 use crate::{
     data::models::{
         accounting_amount_model::AccountingAmountModel,
-        accounting_logic_model::AccountingLogicModel, backing_account_model::BackingAccountModel,
-        command_logic_model::CommandLogicModel, iso_date_model::ISODateModel,
+        accounting_logic_model::AccountingLogicModel, amendment_model::AmendmentModel,
+        annotation_model::AnnotationModel, backing_account_model::BackingAccountModel,
+        command_logic_model::CommandLogicModel, dimensions_model::DimensionsModel,
+        iso_date_model::ISODateModel, recurrence_model::RecurrenceModel,
     },
-    entities::{Annotation, Command, CommandSpecId, Handlers, TransactionSpec, TransactionSpecId},
-    errors::{InvalidCsv, InvalidCsvContent, InvalidRon, ReadError},
+    entities::{
+        Annotation, Command, CommandLogic, CommandSpecId, CommodityHandler as _, Handlers,
+        TransactionSpec, TransactionSpecId, TransactionStatus,
+    },
+    errors::{InvalidCsv, InvalidCsvContent, InvalidRon},
 };
 
+#[cfg(feature = "fs")]
+use crate::errors::ReadError;
+
+/// Transaction specs and commands, in file order.
+pub(crate) type ParsedRows<H> = (Vec<TransactionSpec<H>>, Vec<Command<H>>);
+
 #[async_trait]
 pub(crate) trait TransactionsCsvDatasource<H: Handlers>: Send + Sync {
-    fn from_string(
-        &self,
-        s: &str,
-    ) -> Result<(Vec<TransactionSpec<H>>, Vec<Command<H>>), ServerError>;
+    fn from_string(&self, s: &str) -> Result<ParsedRows<H>, ServerError>;
 
-    async fn from_file<P>(
-        &self,
-        path: P,
-    ) -> Result<(Vec<TransactionSpec<H>>, Vec<Command<H>>), ServerError>
+    /// Parses rows as they are read, without loading the full input.
+    fn from_reader<R>(&self, reader: R) -> Result<ParsedRows<H>, ServerError>
+    where
+        R: std::io::Read + Send;
+
+    /// Streams the file (or, with the `mmap` feature, reads it through a
+    /// memory map), so that large files are never loaded into a string.
+    #[cfg(feature = "fs")]
+    async fn from_file<P>(&self, path: P) -> Result<ParsedRows<H>, ServerError>
     where
         P: AsRef<std::path::Path> + Send;
 }
@@ -43,180 +56,253 @@ impl<H: Handlers> TransactionsCsvDatasourceImpl<H> {
 
 #[async_trait]
 impl<H: Handlers> TransactionsCsvDatasource<H> for TransactionsCsvDatasourceImpl<H> {
-    fn from_string(
-        &self,
-        s: &str,
-    ) -> Result<(Vec<TransactionSpec<H>>, Vec<Command<H>>), ServerError> {
-        csv::Reader::from_reader(s.as_bytes())
-            .records()
-            .enumerate()
-            .try_fold(
-                (Vec::new(), Vec::new()),
-                |(mut transaction_specs, mut commands), (i, r)| {
-                    let r = r.map_err(|e| InvalidCsv::with_debug(&e))?;
-                    let first_char = r
-                        .get(0)
-                        .and_then(|c| c.trim().chars().next())
-                        .unwrap_or(' ');
-
-                    // Skip empty/whitespace-only lines.
-                    if r.iter().all(|cell| cell.trim().is_empty()) {
-                        return Ok((transaction_specs, commands));
-                    }
-                    // Skip comment lines.
-                    if first_char == ';' {
-                        return Ok((transaction_specs, commands));
-                    }
-
-                    if first_char == ':' {
-                        // Parse command entry.
-                        // --
-
-                        // Extract from CSV record.
-                        let raw_date = r.get(2).unwrap_or("").trim();
-                        let raw_exec = r.get(3).unwrap_or("").trim();
-                        let raw_arguments = r.get(4).unwrap_or("").trim();
-                        let raw_description = r.get(6).unwrap_or("").trim();
-                        let raw_amount = r.get(7).unwrap_or("").trim();
-                        let raw_commodity = r.get(8).unwrap_or("").trim();
-                        let raw_notes = r.get(10).unwrap_or("");
-
-                        // Parse.
-                        let date: ISODateModel = ISODateModel::from_str(raw_date)?;
-                        let exec: CommandLogicModel<H::F> = from_str(raw_exec)
-                            .map_err(|e| InvalidRon::with_debug("CommandLogic", &e))?;
-                        let arguments: Vec<String> = if raw_arguments.trim().is_empty() {
-                            vec![]
-                        } else {
-                            raw_arguments.split(',').map(|s| s.to_string()).collect()
-                        };
-                        let description: Option<String> = if raw_description.trim().is_empty() {
-                            None
-                        } else {
-                            Some(raw_description.into())
-                        };
-                        let amount: Option<AccountingAmountModel> = if raw_amount.trim().is_empty()
-                        {
-                            None
-                        } else {
-                            Some(AccountingAmountModel::from_str(raw_amount)?)
-                        };
-                        let commodity: Option<H::M> = if raw_commodity.trim().is_empty() {
-                            None
-                        } else {
-                            Some(
-                                from_str(raw_commodity)
-                                    .map_err(|e| InvalidRon::with_debug("Commodity", &e))?,
-                            )
-                        };
-                        let notes: Vec<String> = if raw_notes.trim().is_empty() {
-                            vec![]
-                        } else {
-                            raw_notes.lines().map(|n| n.into()).collect()
-                        };
-
-                        // Build.
-                        commands.push(Command {
-                            id: CommandSpecId((i + 2) as u64),
-                            date: date.into(),
-                            exec: exec.into(),
-                            arguments,
-                            description,
-                            amount: amount.map(Into::into),
-                            commodity,
-                            notes,
-                        });
-                    } else {
-                        // Parse transaction entry.
-                        // --
-
-                        // Extract from CSV record.
-                        let raw_accrual_date = r.get(0).unwrap_or("");
-                        let raw_until = match r.get(1) {
-                            Some(s) if !s.is_empty() => Some(s),
-                            _ => None,
-                        };
-                        let raw_payment_date = r.get(2).unwrap_or("");
-                        let raw_accounting_logic = r.get(3).unwrap_or("");
-                        let raw_decorators = r.get(4).unwrap_or("");
-                        let raw_entity = r.get(5).unwrap_or("");
-                        let raw_description = r.get(6).unwrap_or("");
-                        let raw_amount = r.get(7).unwrap_or("0");
-                        let raw_commodity = r.get(8).unwrap_or("");
-                        let raw_backing_account = r.get(9).unwrap_or("");
-                        let raw_notes = r.get(10).unwrap_or("");
-
-                        // Parse.
-                        let accrual_start: ISODateModel = ISODateModel::from_str(raw_accrual_date)?;
-                        let accrual_end: Option<ISODateModel> =
-                            raw_until.map(ISODateModel::from_str).transpose()?;
-                        let payment_date: ISODateModel = ISODateModel::from_str(raw_payment_date)?;
-                        let accounting_logic: AccountingLogicModel<H::E, H::A, H::I, H::R, H::S> =
-                            from_str(raw_accounting_logic)
-                                .map_err(|e| InvalidRon::with_debug("AccountingLogic", &e))?;
-                        let decorators: Vec<H::D> = from_str(&format!("[{}]", raw_decorators))
-                            .map_err(|e| InvalidRon::with_debug("Decorator", &e))?;
-                        let payee: H::P = from_str(raw_entity)
-                            .map_err(|e| InvalidRon::with_debug("Payee", &e))?;
-                        let description: String = raw_description.into();
-                        let amount: AccountingAmountModel =
-                            AccountingAmountModel::from_str(raw_amount)?;
-                        let commodity: H::M = from_str(raw_commodity)
-                            .map_err(|e| InvalidRon::with_debug("Commodity", &e))?;
-                        let backing_account: BackingAccountModel<H::R, H::C, H::S> =
-                            from_str(raw_backing_account)
-                                .map_err(|e| InvalidRon::with_debug("BackingAccount", &e))?;
-                        let custom_notes: Vec<Annotation> = if raw_notes.trim().is_empty() {
-                            vec![]
-                        } else {
-                            raw_notes
-                                .lines()
-                                .map(|n| Annotation::Custom(n.into()))
-                                .collect()
-                        };
-
-                        // Build.
-                        let spec = TransactionSpec {
-                            id: TransactionSpecId((i + 2) as u64),
-                            accrual_start: accrual_start.into(),
-                            accrual_end: accrual_end.map(Into::into),
-                            payment_date: payment_date.into(),
-                            accounting_logic: accounting_logic.into(),
-                            decorators,
-                            payee,
-                            description,
-                            amount: amount.into(),
-                            commodity,
-                            backing_account: backing_account.into(),
-                            annotations: custom_notes,
-                        };
-
-                        // Run assertions.
-                        self.validate(&spec)?;
-                        transaction_specs.push(spec);
-                    }
-
-                    Ok((transaction_specs, commands))
-                },
-            )
+    fn from_string(&self, s: &str) -> Result<ParsedRows<H>, ServerError> {
+        self.from_reader(s.as_bytes())
     }
 
-    async fn from_file<P>(
-        &self,
-        path: P,
-    ) -> Result<(Vec<TransactionSpec<H>>, Vec<Command<H>>), ServerError>
+    fn from_reader<R>(&self, reader: R) -> Result<ParsedRows<H>, ServerError>
+    where
+        R: std::io::Read + Send,
+    {
+        // Reuse a single record buffer, so rows are parsed without allocating
+        // per row.
+        let mut reader = csv::Reader::from_reader(reader);
+        let mut record = csv::StringRecord::new();
+        let mut transaction_specs = Vec::new();
+        let mut commands = Vec::new();
+        let mut i = 0;
+        while reader
+            .read_record(&mut record)
+            .map_err(|e| InvalidCsv::with_debug(&e))?
+        {
+            self.parse_record(&record, i, &mut transaction_specs, &mut commands)?;
+            i += 1;
+        }
+        Ok((transaction_specs, commands))
+    }
+
+    #[cfg(feature = "fs")]
+    async fn from_file<P>(&self, path: P) -> Result<ParsedRows<H>, ServerError>
     where
         P: AsRef<std::path::Path> + Send,
     {
-        self.from_string(
-            &tokio::fs::read_to_string(path)
-                .await
-                .map_err(|e| ReadError::with_debug(&e))?,
-        )
+        let file = std::fs::File::open(path).map_err(|e| ReadError::with_debug(&e))?;
+        #[cfg(feature = "mmap")]
+        {
+            // SAFETY: The map is only read while parsing. Input files must
+            // not be modified (in particular, truncated) during processing.
+            let map =
+                unsafe { memmap2::Mmap::map(&file) }.map_err(|e| ReadError::with_debug(&e))?;
+            self.from_reader(&map[..])
+        }
+        #[cfg(not(feature = "mmap"))]
+        {
+            self.from_reader(file)
+        }
     }
 }
 
 impl<H: Handlers> TransactionsCsvDatasourceImpl<H> {
+    /// i: Index of the record (excluding the header), from which the spec ID
+    /// is derived.
+    fn parse_record(
+        &self,
+        r: &csv::StringRecord,
+        i: usize,
+        transaction_specs: &mut Vec<TransactionSpec<H>>,
+        commands: &mut Vec<Command<H>>,
+    ) -> Result<(), ServerError> {
+        let first_char = r
+            .get(0)
+            .and_then(|c| c.trim().chars().next())
+            .unwrap_or(' ');
+
+        // Skip empty/whitespace-only lines.
+        if r.iter().all(|cell| cell.trim().is_empty()) {
+            return Ok(());
+        }
+        // Skip comment lines.
+        if first_char == ';' {
+            return Ok(());
+        }
+
+        if first_char == ':' {
+            // Parse command entry.
+            // --
+
+            // Extract from CSV record.
+            let raw_date = r.get(2).unwrap_or("").trim();
+            let raw_exec = r.get(3).unwrap_or("").trim();
+            let raw_arguments = r.get(4).unwrap_or("").trim();
+            let raw_description = r.get(6).unwrap_or("").trim();
+            let raw_amount = r.get(7).unwrap_or("").trim();
+            let raw_commodity = r.get(8).unwrap_or("").trim();
+            let raw_notes = r.get(10).unwrap_or("");
+            let raw_group_entity = r.get(12).unwrap_or("").trim();
+
+            // Parse.
+            let date: ISODateModel = ISODateModel::from_str(raw_date)?;
+            let exec: CommandLogicModel<H::F> =
+                from_str(raw_exec).map_err(|e| InvalidRon::with_debug("CommandLogic", &e))?;
+            let arguments: Vec<String> = if raw_arguments.trim().is_empty() {
+                vec![]
+            } else {
+                raw_arguments.split(',').map(|s| s.to_string()).collect()
+            };
+            let description: Option<String> = if raw_description.trim().is_empty() {
+                None
+            } else {
+                Some(raw_description.into())
+            };
+            let amount: Option<AccountingAmountModel> = if raw_amount.trim().is_empty() {
+                None
+            } else {
+                Some(AccountingAmountModel::from_str(raw_amount)?)
+            };
+            let exec: CommandLogic<H::F> = exec.into();
+            let commodity: Option<H::M> = if !raw_commodity.trim().is_empty() {
+                Some(from_str(raw_commodity).map_err(|e| InvalidRon::with_debug("Commodity", &e))?)
+            } else if matches!(exec, CommandLogic::Close(_)) {
+                Some(H::M::default())
+            } else {
+                None
+            };
+            let notes: Vec<String> = if raw_notes.trim().is_empty() {
+                vec![]
+            } else {
+                raw_notes.lines().map(|n| n.into()).collect()
+            };
+
+            // Build.
+            commands.push(Command {
+                id: CommandSpecId((i + 2) as u64),
+                date: date.into(),
+                exec,
+                arguments,
+                description,
+                amount: amount.map(Into::into),
+                commodity,
+                notes,
+                entity: (!raw_group_entity.is_empty()).then(|| raw_group_entity.into()),
+            });
+        } else {
+            // Parse transaction entry.
+            // --
+
+            // Extract from CSV record.
+            let raw_accrual_date = r.get(0).unwrap_or("");
+            let raw_until = match r.get(1) {
+                Some(s) if !s.is_empty() => Some(s),
+                _ => None,
+            };
+            let raw_payment_date = r.get(2).unwrap_or("");
+            let raw_accounting_logic = r.get(3).unwrap_or("");
+            let raw_decorators = r.get(4).unwrap_or("");
+            let raw_entity = r.get(5).unwrap_or("");
+            let raw_description = r.get(6).unwrap_or("");
+            let raw_amount = r.get(7).unwrap_or("0");
+            let raw_commodity = r.get(8).unwrap_or("");
+            let raw_backing_account = r.get(9).unwrap_or("");
+            let raw_notes = r.get(10).unwrap_or("");
+            let raw_intercompany = r.get(11).unwrap_or("").trim();
+            let raw_group_entity = r.get(12).unwrap_or("").trim();
+            let raw_recurrence = r.get(13).unwrap_or("").trim();
+            let raw_dimensions = r.get(14).unwrap_or("");
+            let raw_status = r.get(15).unwrap_or("").trim();
+            let raw_amends = r.get(16).unwrap_or("").trim();
+
+            // Parse.
+            let accrual_start: ISODateModel = ISODateModel::from_str(raw_accrual_date)?;
+            let accrual_end: Option<ISODateModel> =
+                raw_until.map(ISODateModel::from_str).transpose()?;
+            let payment_date: ISODateModel = ISODateModel::from_str(raw_payment_date)?;
+            let accounting_logic: AccountingLogicModel<H::E, H::A, H::I, H::R, H::S> =
+                from_str(raw_accounting_logic)
+                    .map_err(|e| InvalidRon::with_debug("AccountingLogic", &e))?;
+            let decorators: Vec<H::D> = if raw_decorators.trim().is_empty() {
+                vec![]
+            } else {
+                from_str(&format!("[{}]", raw_decorators))
+                    .map_err(|e| InvalidRon::with_debug("Decorator", &e))?
+            };
+            let payee: H::P =
+                from_str(raw_entity).map_err(|e| InvalidRon::with_debug("Payee", &e))?;
+            let description: String = raw_description.into();
+            let amount: AccountingAmountModel = AccountingAmountModel::from_str(raw_amount)?;
+            let commodity: H::M =
+                from_str(raw_commodity).map_err(|e| InvalidRon::with_debug("Commodity", &e))?;
+            let backing_account: BackingAccountModel<H::R, H::C, H::S> =
+                from_str(raw_backing_account)
+                    .map_err(|e| InvalidRon::with_debug("BackingAccount", &e))?;
+            let custom_notes: Vec<Annotation> = if raw_notes.trim().is_empty() {
+                vec![]
+            } else {
+                raw_notes
+                    .lines()
+                    .filter(|n| !n.trim().is_empty())
+                    .map(|n| AnnotationModel::from_str(n).map(Annotation::from))
+                    .collect::<Result<_, _>>()?
+            };
+            let dimensions: DimensionsModel = DimensionsModel::from_str(raw_dimensions)?;
+            let status = match raw_status {
+                "" => TransactionStatus::Unmarked,
+                "!" | "pending" => TransactionStatus::Pending,
+                "*" | "cleared" => TransactionStatus::Cleared,
+                _ => {
+                    return Err(InvalidCsvContent::with_debug(
+                        "Status must be empty, '!' (pending) or '*' (cleared).",
+                        &raw_status,
+                    ))
+                }
+            };
+            let recurrence: Option<RecurrenceModel> = if raw_recurrence.is_empty() {
+                None
+            } else {
+                Some(
+                    ron::Options::default()
+                        .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+                        .from_str(raw_recurrence)
+                        .map_err(|e| InvalidRon::with_debug("Recurrence", &e))?,
+                )
+            };
+
+            let amends: Option<AmendmentModel> = if raw_amends.is_empty() {
+                None
+            } else {
+                Some(from_str(raw_amends).map_err(|e| InvalidRon::with_debug("Amends", &e))?)
+            };
+
+            // Build.
+            let recurrence = recurrence.map(|r| r.into_rule(&description)).transpose()?;
+            let spec = TransactionSpec {
+                id: TransactionSpecId((i + 2) as u64),
+                accrual_start: accrual_start.into(),
+                accrual_end: accrual_end.map(Into::into),
+                payment_date: payment_date.into(),
+                accounting_logic: accounting_logic.into(),
+                decorators,
+                payee,
+                description,
+                amount: amount.into(),
+                commodity,
+                backing_account: backing_account.into(),
+                annotations: custom_notes,
+                intercompany: (!raw_intercompany.is_empty()).then(|| raw_intercompany.into()),
+                dimensions: dimensions.into(),
+                status,
+                entity: (!raw_group_entity.is_empty()).then(|| raw_group_entity.into()),
+                recurrence,
+                amends: amends.map(Into::into),
+            };
+
+            // Run assertions.
+            self.validate(&spec)?;
+            transaction_specs.push(spec);
+        }
+
+        Ok(())
+    }
+
     fn validate(&self, spec: &TransactionSpec<H>) -> Result<(), ServerError> {
         if let Some(until) = spec.accrual_end {
             if until < spec.accrual_start {
@@ -227,6 +313,14 @@ impl<H: Handlers> TransactionsCsvDatasourceImpl<H> {
                 .into());
             }
         }
+        if spec.recurrence.is_some() && spec.amends.is_some() {
+            // Each occurrence would reverse the amended spec again.
+            return Err(InvalidCsvContent::with_debug(
+                "A recurring spec can't amend another spec.",
+                &spec,
+            )
+            .into());
+        }
         Ok(())
     }
 }
@@ -1,4 +1,7 @@
-use crate::entities::{AccountingLogic, CommonStockWhileUnpaid, ShareIssuanceCostBookTo};
+use crate::entities::{
+    AccountingLogic, CommonStockWhileUnpaid, DepreciationMethod, FairValueGainsTo,
+    InventoryCosting, RepaymentSchedule, ShareIssuanceCostBookTo,
+};
 
 use super::iso_date_model::ISODateModel;
 
@@ -14,6 +17,36 @@ pub enum ShareIssuanceCostBookToModel {
     DiscountOnStockIssuance,
 }
 
+#[derive(Debug, serde_derive::Deserialize)]
+pub enum FairValueGainsToModel {
+    ProfitOrLoss,
+    OtherComprehensiveIncome,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+pub enum DepreciationMethodModel {
+    StraightLine,
+    DecliningBalance {
+        rate: f64,
+    },
+    UnitsOfProduction {
+        total_units: f64,
+        monthly_units: Vec<f64>,
+    },
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+pub enum InventoryCostingModel {
+    Fifo,
+    WeightedAverage,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+pub enum RepaymentScheduleModel {
+    Annuity,
+    StraightPrincipal,
+}
+
 #[derive(Debug, serde_derive::Deserialize)]
 pub enum AccountingLogicModel<E, A, I, R, S> {
     CommonStock {
@@ -23,9 +56,23 @@ pub enum AccountingLogicModel<E, A, I, R, S> {
     ShareIssuanceCost {
         book_to: ShareIssuanceCostBookToModel,
     },
+    ShareBuyback {
+        shares: f64,
+    },
+    ReissueTreasuryShares {
+        shares: f64,
+    },
     SimpleExpense(E),
     Capitalize(A),
     Amortize(A),
+    Depreciate {
+        asset: A,
+        method: DepreciationMethodModel,
+        #[serde(default)]
+        residual_value: f64,
+    },
+    DisposeAsset(A),
+    Impair(A),
     FixedExpense(E),
     VariableExpense(E),
     VariableExpenseInit {
@@ -34,8 +81,47 @@ pub enum AccountingLogicModel<E, A, I, R, S> {
     },
     ImmaterialIncome(I),
     ImmaterialExpense(E),
+    FixedIncome(I),
+    AccrualIncome(I),
+    InterestIncome,
+    PayDeposit,
+    RefundDeposit,
+    Invoice(I),
+    CollectInvoice(I),
+    ProvisionForDoubtfulDebt(I),
+    WriteOffReceivable(I),
+    PurchaseInventory {
+        item: A,
+        quantity: f64,
+        costing: InventoryCostingModel,
+    },
+    RecognizeCogs {
+        item: A,
+        quantity: f64,
+    },
+    Grant {
+        asset: Option<A>,
+    },
+    IncomeTax {
+        fiscal_year: i32,
+    },
+    SettleIncomeTax {
+        fiscal_year: i32,
+    },
+    IntercompanyTransfer {
+        counterparty: String,
+    },
     Reimburse(R),
     ReimbursePartial(R),
+    PayCardStatement(R),
+    RemeasureInvestment {
+        investment: A,
+        gains_to: FairValueGainsToModel,
+    },
+    Borrowing {
+        annual_rate: f64,
+        repayment: RepaymentScheduleModel,
+    },
     ClearVat {
         from: ISODateModel,
         to: ISODateModel,
@@ -71,9 +157,38 @@ impl<E, A, I, R, S> Into<AccountingLogic<E, A, I, R, S>> for AccountingLogicMode
                     },
                 }
             }
+            AccountingLogicModel::ShareBuyback { shares } => {
+                AccountingLogic::ShareBuyback { shares }
+            }
+            AccountingLogicModel::ReissueTreasuryShares { shares } => {
+                AccountingLogic::ReissueTreasuryShares { shares }
+            }
             AccountingLogicModel::SimpleExpense(e) => AccountingLogic::SimpleExpense(e),
             AccountingLogicModel::Capitalize(a) => AccountingLogic::Capitalize(a),
             AccountingLogicModel::Amortize(a) => AccountingLogic::Amortize(a),
+            AccountingLogicModel::Depreciate {
+                asset,
+                method,
+                residual_value,
+            } => AccountingLogic::Depreciate {
+                asset,
+                method: match method {
+                    DepreciationMethodModel::StraightLine => DepreciationMethod::StraightLine,
+                    DepreciationMethodModel::DecliningBalance { rate } => {
+                        DepreciationMethod::DecliningBalance { rate }
+                    }
+                    DepreciationMethodModel::UnitsOfProduction {
+                        total_units,
+                        monthly_units,
+                    } => DepreciationMethod::UnitsOfProduction {
+                        total_units,
+                        monthly_units,
+                    },
+                },
+                residual_value,
+            },
+            AccountingLogicModel::DisposeAsset(a) => AccountingLogic::DisposeAsset(a),
+            AccountingLogicModel::Impair(a) => AccountingLogic::Impair(a),
             AccountingLogicModel::FixedExpense(e) => AccountingLogic::FixedExpense(e),
             AccountingLogicModel::VariableExpense(e) => AccountingLogic::VariableExpense(e),
             AccountingLogicModel::VariableExpenseInit { account, estimate } => {
@@ -81,8 +196,69 @@ impl<E, A, I, R, S> Into<AccountingLogic<E, A, I, R, S>> for AccountingLogicMode
             }
             AccountingLogicModel::ImmaterialIncome(i) => AccountingLogic::ImmaterialIncome(i),
             AccountingLogicModel::ImmaterialExpense(e) => AccountingLogic::ImmaterialExpense(e),
+            AccountingLogicModel::FixedIncome(i) => AccountingLogic::FixedIncome(i),
+            AccountingLogicModel::AccrualIncome(i) => AccountingLogic::AccrualIncome(i),
+            AccountingLogicModel::InterestIncome => AccountingLogic::InterestIncome,
+            AccountingLogicModel::PayDeposit => AccountingLogic::PayDeposit,
+            AccountingLogicModel::RefundDeposit => AccountingLogic::RefundDeposit,
+            AccountingLogicModel::Invoice(i) => AccountingLogic::Invoice(i),
+            AccountingLogicModel::CollectInvoice(i) => AccountingLogic::CollectInvoice(i),
+            AccountingLogicModel::ProvisionForDoubtfulDebt(i) => {
+                AccountingLogic::ProvisionForDoubtfulDebt(i)
+            }
+            AccountingLogicModel::WriteOffReceivable(i) => AccountingLogic::WriteOffReceivable(i),
+            AccountingLogicModel::PurchaseInventory {
+                item,
+                quantity,
+                costing,
+            } => AccountingLogic::PurchaseInventory {
+                item,
+                quantity,
+                costing: match costing {
+                    InventoryCostingModel::Fifo => InventoryCosting::Fifo,
+                    InventoryCostingModel::WeightedAverage => InventoryCosting::WeightedAverage,
+                },
+            },
+            AccountingLogicModel::RecognizeCogs { item, quantity } => {
+                AccountingLogic::RecognizeCogs { item, quantity }
+            }
+            AccountingLogicModel::Grant { asset } => AccountingLogic::Grant { asset },
+            AccountingLogicModel::IncomeTax { fiscal_year } => {
+                AccountingLogic::IncomeTax { fiscal_year }
+            }
+            AccountingLogicModel::SettleIncomeTax { fiscal_year } => {
+                AccountingLogic::SettleIncomeTax { fiscal_year }
+            }
+            AccountingLogicModel::IntercompanyTransfer { counterparty } => {
+                AccountingLogic::IntercompanyTransfer { counterparty }
+            }
             AccountingLogicModel::Reimburse(r) => AccountingLogic::Reimburse(r),
             AccountingLogicModel::ReimbursePartial(r) => AccountingLogic::ReimbursePartial(r),
+            AccountingLogicModel::PayCardStatement(r) => AccountingLogic::PayCardStatement(r),
+            AccountingLogicModel::RemeasureInvestment {
+                investment,
+                gains_to,
+            } => AccountingLogic::RemeasureInvestment {
+                investment,
+                gains_to: match gains_to {
+                    FairValueGainsToModel::ProfitOrLoss => FairValueGainsTo::ProfitOrLoss,
+                    FairValueGainsToModel::OtherComprehensiveIncome => {
+                        FairValueGainsTo::OtherComprehensiveIncome
+                    }
+                },
+            },
+            AccountingLogicModel::Borrowing {
+                annual_rate,
+                repayment,
+            } => AccountingLogic::Borrowing {
+                annual_rate,
+                repayment: match repayment {
+                    RepaymentScheduleModel::Annuity => RepaymentSchedule::Annuity,
+                    RepaymentScheduleModel::StraightPrincipal => {
+                        RepaymentSchedule::StraightPrincipal
+                    }
+                },
+            },
             AccountingLogicModel::ClearVat { from, to } => AccountingLogic::ClearVat {
                 from: from.into(),
                 to: to.into(),
@@ -0,0 +1,23 @@
+use crate::entities::{Amendment, TransactionSpecId};
+
+use super::iso_date_model::ISODateModel;
+
+/// The amended spec's ID (its line number, or the ID of a recurring
+/// occurrence) and the date its entries are reversed on. Ex.:
+///
+/// `(spec: 12, date: "2024-07-01")`
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AmendmentModel {
+    spec: u64,
+    date: ISODateModel,
+}
+
+impl From<AmendmentModel> for Amendment {
+    fn from(value: AmendmentModel) -> Self {
+        Amendment {
+            spec_id: TransactionSpecId(value.spec),
+            date: value.date.into(),
+        }
+    }
+}
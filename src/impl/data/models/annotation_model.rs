@@ -0,0 +1,60 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use fractic_server_error::ServerError;
+
+use crate::{entities::Annotation, errors::InvalidAnnotation};
+
+/// A title, optionally followed by a body and comma-separated `name:value`
+/// metadata, separated by `|`. Ex.:
+///
+/// `Lease policy | Straight-line over the lease term | contract:L-2024-03, approval:A-118`
+#[derive(Debug)]
+pub(crate) struct AnnotationModel(Annotation);
+impl FromStr for AnnotationModel {
+    type Err = ServerError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('|').map(str::trim);
+        let title = parts.next().unwrap_or_default();
+        let body = parts.next().unwrap_or_default();
+        let raw_meta = parts.next().unwrap_or_default();
+        if parts.next().is_some() {
+            return Err(InvalidAnnotation::new(
+                s,
+                "expected 'title', 'title | body' or 'title | body | name:value, ...'",
+            ));
+        }
+        if title.is_empty() {
+            return Err(InvalidAnnotation::new(s, "title must be non-empty"));
+        }
+        let mut meta = BTreeMap::new();
+        for pair in raw_meta.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((name, value)) = pair.split_once(':') else {
+                return Err(InvalidAnnotation::new(pair, "expected 'name:value'"));
+            };
+            let (name, value) = (name.trim(), value.trim());
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(InvalidAnnotation::new(
+                    pair,
+                    "name must be non-empty and contain no spaces",
+                ));
+            }
+            if value.is_empty() {
+                return Err(InvalidAnnotation::new(pair, "value must be non-empty"));
+            }
+            if meta.insert(name.into(), value.into()).is_some() {
+                return Err(InvalidAnnotation::new(pair, "name is given more than once"));
+            }
+        }
+        Ok(AnnotationModel(Annotation::Custom {
+            title: title.into(),
+            body: body.into(),
+            meta,
+        }))
+    }
+}
+
+impl From<AnnotationModel> for Annotation {
+    fn from(value: AnnotationModel) -> Self {
+        value.0
+    }
+}
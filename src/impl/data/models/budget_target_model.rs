@@ -0,0 +1,20 @@
+use crate::entities::{BudgetTarget, ExpenseClassification, IncomeClassification};
+
+#[derive(Debug, serde_derive::Deserialize)]
+pub enum BudgetTargetModel<E, I> {
+    Expense(E),
+    Income(I),
+    ExpenseClassification(ExpenseClassification),
+    IncomeClassification(IncomeClassification),
+}
+
+impl<E, I> From<BudgetTargetModel<E, I>> for BudgetTarget<E, I> {
+    fn from(value: BudgetTargetModel<E, I>) -> Self {
+        match value {
+            BudgetTargetModel::Expense(e) => BudgetTarget::Expense(e),
+            BudgetTargetModel::Income(i) => BudgetTarget::Income(i),
+            BudgetTargetModel::ExpenseClassification(c) => BudgetTarget::ExpenseClassification(c),
+            BudgetTargetModel::IncomeClassification(c) => BudgetTarget::IncomeClassification(c),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{CashflowTracingTag, INTERCOMPANY_TAG},
+    errors::InvalidDimension,
+};
+
+/// Comma-separated `name:value` pairs, same as hledger tags. Ex.:
+///
+/// `project:Acme website, department:Sales`
+#[derive(Debug)]
+pub(crate) struct DimensionsModel(BTreeMap<String, String>);
+impl FromStr for DimensionsModel {
+    type Err = ServerError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut dimensions = BTreeMap::new();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((name, value)) = pair.split_once(':') else {
+                return Err(InvalidDimension::new(pair, "expected 'name:value'"));
+            };
+            let (name, value) = (name.trim(), value.trim());
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(InvalidDimension::new(
+                    pair,
+                    "name must be non-empty and contain no spaces",
+                ));
+            }
+            if value.is_empty() {
+                return Err(InvalidDimension::new(pair, "value must be non-empty"));
+            }
+            if name == INTERCOMPANY_TAG || name == CashflowTracingTag::key() {
+                return Err(InvalidDimension::new(pair, "name is reserved"));
+            }
+            if dimensions.insert(name.into(), value.into()).is_some() {
+                return Err(InvalidDimension::new(pair, "name is given more than once"));
+            }
+        }
+        Ok(DimensionsModel(dimensions))
+    }
+}
+
+impl From<DimensionsModel> for BTreeMap<String, String> {
+    fn from(value: DimensionsModel) -> Self {
+        value.0
+    }
+}
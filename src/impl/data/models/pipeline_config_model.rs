@@ -0,0 +1,283 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use fractic_server_error::ServerError;
+use iso_currency::Currency;
+
+use crate::{
+    data::models::iso_date_model::ISODateModel,
+    entities::{
+        BackdatingGuard, BackdatingPolicy, DiscrepancyPolicy, InterestInference, InterestSchedule,
+        PipelineConfig, ProcessOptions, RoundingMode,
+    },
+    errors::InvalidIsoCurrencyCode,
+    ext::{standard_accounts::StandardAccountOverrides, verification::RoundTripVerifier},
+    presentation::print_options::{AnnotationVerbosity, PrintOptions},
+};
+
+#[derive(Debug, Default, serde_derive::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct PipelineConfigModel {
+    main_currency: Option<String>,
+    fiscal_year: Option<i32>,
+    inputs: InputsModel,
+    fx: FxModel,
+    processing: ProcessingModel,
+    printing: PrintingModel,
+    accounts: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, serde_derive::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct InputsModel {
+    transactions: Option<PathBuf>,
+    balances: Option<PathBuf>,
+    budgets: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, serde_derive::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FxModel {
+    cache_dir: Option<PathBuf>,
+    api_key: Option<String>,
+}
+
+#[derive(Debug, Default, serde_derive::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProcessingModel {
+    decorator_concurrency: Option<usize>,
+    interest_inference: Option<InterestInferenceModel>,
+    discrepancy_policy: Option<DiscrepancyPolicyModel>,
+    verify_round_trip: bool,
+    preflight_assertions: bool,
+    suspense_routing: bool,
+    /// Keyed by ISO currency code.
+    materiality_thresholds: BTreeMap<String, f64>,
+    backdating_guard: Option<BackdatingGuardModel>,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InterestInferenceModel {
+    max_amount: f64,
+    schedule: InterestScheduleModel,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum InterestScheduleModel {
+    MonthEnd,
+    QuarterEnd,
+    DayOfMonth(u32),
+    Any,
+}
+
+impl From<InterestInferenceModel> for InterestInference {
+    fn from(model: InterestInferenceModel) -> Self {
+        InterestInference {
+            max_amount: model.max_amount,
+            schedule: match model.schedule {
+                InterestScheduleModel::MonthEnd => InterestSchedule::MonthEnd,
+                InterestScheduleModel::QuarterEnd => InterestSchedule::QuarterEnd,
+                InterestScheduleModel::DayOfMonth(day) => InterestSchedule::DayOfMonth(day),
+                InterestScheduleModel::Any => InterestSchedule::Any,
+            },
+        }
+    }
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BackdatingGuardModel {
+    policy: BackdatingPolicyModel,
+    #[serde(default)]
+    filing_dates: Vec<ISODateModel>,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BackdatingPolicyModel {
+    Warn,
+    Error,
+}
+
+impl From<BackdatingGuardModel> for BackdatingGuard {
+    fn from(model: BackdatingGuardModel) -> Self {
+        BackdatingGuard {
+            policy: match model.policy {
+                BackdatingPolicyModel::Warn => BackdatingPolicy::Warn,
+                BackdatingPolicyModel::Error => BackdatingPolicy::Error,
+            },
+            filing_dates: model.filing_dates.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DiscrepancyPolicyModel {
+    PrecisionCutoff,
+    Absolute(f64),
+    Relative(f64),
+    Always,
+    Never,
+}
+
+impl Into<DiscrepancyPolicy> for DiscrepancyPolicyModel {
+    fn into(self) -> DiscrepancyPolicy {
+        match self {
+            DiscrepancyPolicyModel::PrecisionCutoff => DiscrepancyPolicy::PrecisionCutoff,
+            DiscrepancyPolicyModel::Absolute(amount) => DiscrepancyPolicy::Absolute(amount),
+            DiscrepancyPolicyModel::Relative(percent) => DiscrepancyPolicy::Relative(percent),
+            DiscrepancyPolicyModel::Always => DiscrepancyPolicy::Always,
+            DiscrepancyPolicyModel::Never => DiscrepancyPolicy::Never,
+        }
+    }
+}
+
+#[derive(Debug, Default, serde_derive::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PrintingModel {
+    generation_header: bool,
+    group_by_month: bool,
+    monthly_subtotals: bool,
+    annotation_verbosity: Option<AnnotationVerbosityModel>,
+    annotation_appendix: bool,
+    full_chart_of_accounts: bool,
+    account_notes: bool,
+    assertions_include: Option<String>,
+    budget_entries: bool,
+    rounding_mode: Option<RoundingModeModel>,
+    /// Keyed by ISO currency code.
+    decimal_places: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AnnotationVerbosityModel {
+    None,
+    Markers,
+    Full,
+}
+
+impl Into<AnnotationVerbosity> for AnnotationVerbosityModel {
+    fn into(self) -> AnnotationVerbosity {
+        match self {
+            AnnotationVerbosityModel::None => AnnotationVerbosity::None,
+            AnnotationVerbosityModel::Markers => AnnotationVerbosity::Markers,
+            AnnotationVerbosityModel::Full => AnnotationVerbosity::Full,
+        }
+    }
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RoundingModeModel {
+    HalfUp,
+    HalfEven,
+    Truncate,
+}
+
+impl Into<RoundingMode> for RoundingModeModel {
+    fn into(self) -> RoundingMode {
+        match self {
+            RoundingModeModel::HalfUp => RoundingMode::HalfUp,
+            RoundingModeModel::HalfEven => RoundingMode::HalfEven,
+            RoundingModeModel::Truncate => RoundingMode::Truncate,
+        }
+    }
+}
+
+impl PipelineConfigModel {
+    /// Relative paths are resolved against `base_dir`, if given.
+    pub(crate) fn into_config(
+        self,
+        base_dir: Option<&Path>,
+    ) -> Result<PipelineConfig, ServerError> {
+        let resolve = |path: Option<PathBuf>| match (path, base_dir) {
+            (Some(path), Some(base_dir)) if path.is_relative() => Some(base_dir.join(path)),
+            (path, _) => path,
+        };
+
+        let mut process_options = ProcessOptions::default();
+        if let Some(decorator_concurrency) = self.processing.decorator_concurrency {
+            process_options.decorator_concurrency = decorator_concurrency;
+        }
+        process_options.interest_inference = self.processing.interest_inference.map(Into::into);
+        if let Some(discrepancy_policy) = self.processing.discrepancy_policy {
+            process_options.discrepancy_policy = discrepancy_policy.into();
+        }
+        process_options.preflight_assertions = self.processing.preflight_assertions;
+        process_options.suspense_routing = self.processing.suspense_routing;
+        process_options.materiality_thresholds = self
+            .processing
+            .materiality_thresholds
+            .into_iter()
+            .map(|(code, threshold)| {
+                Currency::from_code(&code)
+                    .map(|currency| (currency.into(), threshold))
+                    .ok_or_else(|| InvalidIsoCurrencyCode::new(&code))
+            })
+            .collect::<Result<_, _>>()?;
+        process_options.backdating_guard = self.processing.backdating_guard.map(Into::into);
+        if self.processing.verify_round_trip {
+            // Before the overrides, which rename accounts.
+            process_options
+                .spec_hooks
+                .push(Arc::new(RoundTripVerifier::new()));
+        }
+        if !self.accounts.is_empty() {
+            process_options
+                .spec_hooks
+                .push(Arc::new(StandardAccountOverrides::new(&self.accounts)?));
+        }
+
+        let print_options = PrintOptions {
+            generation_header: self.printing.generation_header,
+            group_by_month: self.printing.group_by_month,
+            monthly_subtotals: self.printing.monthly_subtotals,
+            annotation_verbosity: self
+                .printing
+                .annotation_verbosity
+                .map(Into::into)
+                .unwrap_or_default(),
+            annotation_appendix: self.printing.annotation_appendix,
+            full_chart_of_accounts: self.printing.full_chart_of_accounts,
+            declared_accounts: Vec::new(),
+            account_notes: self.printing.account_notes,
+            assertions_include: self.printing.assertions_include,
+            budget_entries: self.printing.budget_entries,
+            rounding_mode: self
+                .printing
+                .rounding_mode
+                .map(Into::into)
+                .unwrap_or_default(),
+            decimal_places: self
+                .printing
+                .decimal_places
+                .into_iter()
+                .map(|(code, decimal_places)| {
+                    Currency::from_code(&code)
+                        .map(|currency| (currency.into(), decimal_places))
+                        .ok_or_else(|| InvalidIsoCurrencyCode::new(&code))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(PipelineConfig {
+            transactions_path: resolve(self.inputs.transactions),
+            balances_path: resolve(self.inputs.balances),
+            budgets_path: resolve(self.inputs.budgets),
+            main_currency: self.main_currency,
+            fiscal_year: self.fiscal_year,
+            fx_cache_dir: resolve(self.fx.cache_dir),
+            fx_api_key: self.fx.api_key,
+            process_options,
+            print_options,
+            account_overrides: self.accounts,
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use std::str::FromStr as _;
+
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{
+        RecurrenceDay, RecurrenceEnd, RecurrenceFrequency, RecurrenceOverride, RecurrenceRule,
+    },
+    errors::InvalidRecurrence,
+};
+
+use super::{accounting_amount_model::AccountingAmountModel, iso_date_model::ISODateModel};
+
+/// Parsed with RON's `implicit_some` extension, so optional fields can be
+/// given directly. Ex.:
+///
+/// `(every: Monthly, on: LastBusinessDay, until: "2024-12-31", except: ["2024-08-30"])`
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RecurrenceModel {
+    every: RecurrenceFrequency,
+    #[serde(default)]
+    on: RecurrenceDay,
+    #[serde(default)]
+    until: Option<ISODateModel>,
+    #[serde(default)]
+    count: Option<u32>,
+    #[serde(default)]
+    except: Vec<ISODateModel>,
+    #[serde(default)]
+    overrides: Vec<RecurrenceOverrideModel>,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RecurrenceOverrideModel {
+    date: ISODateModel,
+    #[serde(default)]
+    payment: Option<ISODateModel>,
+    /// Accounting format, same as the amount column (ex. "(1,200.00)").
+    #[serde(default)]
+    amount: Option<String>,
+}
+
+impl RecurrenceModel {
+    /// `description` is the spec's description, used in errors.
+    pub(crate) fn into_rule(self, description: &str) -> Result<RecurrenceRule, ServerError> {
+        let end = match (self.until, self.count) {
+            (Some(until), None) => RecurrenceEnd::Until(until.into()),
+            (None, Some(count)) => RecurrenceEnd::Count(count),
+            _ => {
+                return Err(InvalidRecurrence::new(
+                    description,
+                    "exactly one of 'until' or 'count' must be set",
+                ))
+            }
+        };
+        let overrides = self
+            .overrides
+            .into_iter()
+            .map(|o| {
+                Ok(RecurrenceOverride {
+                    date: o.date.into(),
+                    payment_date: o.payment.map(Into::into),
+                    amount: o
+                        .amount
+                        .map(|a| AccountingAmountModel::from_str(&a))
+                        .transpose()?
+                        .map(Into::into),
+                })
+            })
+            .collect::<Result<Vec<_>, ServerError>>()?;
+        Ok(RecurrenceRule {
+            frequency: self.every,
+            day: self.on,
+            end,
+            exceptions: self.except.into_iter().map(Into::into).collect(),
+            overrides,
+        })
+    }
+}
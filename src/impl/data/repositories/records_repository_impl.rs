@@ -1,35 +1,45 @@
 use async_trait::async_trait;
+use chrono::{Months, NaiveDate};
 use fractic_server_error::ServerError;
 
 use crate::{
     data::datasources::{
         balances_csv_datasource::{BalancesCsvDatasource, BalancesCsvDatasourceImpl},
+        budgets_csv_datasource::{BudgetsCsvDatasource, BudgetsCsvDatasourceImpl},
         transactions_csv_datasource::{TransactionsCsvDatasource, TransactionsCsvDatasourceImpl},
     },
-    domain::repositories::records_repository::RecordsRepository,
-    entities::{FinancialRecordSpecs, Handlers},
+    domain::{
+        entities::transaction_spec::MAX_OCCURRENCES,
+        repositories::records_repository::RecordsRepository,
+    },
+    entities::{BudgetSpec, FinancialRecordSpecs, Handlers, TransactionSpec},
+    errors::InvalidRecurrence,
 };
 
 pub(crate) struct RecordsRepositoryImpl<
     H,
     DS1 = BalancesCsvDatasourceImpl<H>,     // Default.
     DS2 = TransactionsCsvDatasourceImpl<H>, // Default.
+    DS3 = BudgetsCsvDatasourceImpl<H>,      // Default.
 > where
     H: Handlers,
     DS1: BalancesCsvDatasource<H>,
     DS2: TransactionsCsvDatasource<H>,
+    DS3: BudgetsCsvDatasource<H>,
 {
     transactions_datasource: DS2,
     balances_datasource: DS1,
+    budgets_datasource: DS3,
     _phantom: std::marker::PhantomData<H>,
 }
 
 #[async_trait]
-impl<H, DS1, DS2> RecordsRepository<H> for RecordsRepositoryImpl<H, DS1, DS2>
+impl<H, DS1, DS2, DS3> RecordsRepository<H> for RecordsRepositoryImpl<H, DS1, DS2, DS3>
 where
     H: Handlers,
     DS1: BalancesCsvDatasource<H>,
     DS2: TransactionsCsvDatasource<H>,
+    DS3: BudgetsCsvDatasource<H>,
 {
     fn from_string(
         &self,
@@ -41,12 +51,14 @@ where
             self.balances_datasource.from_string(balances_csv)?,
         );
         Ok(FinancialRecordSpecs {
-            transaction_specs,
+            transaction_specs: expand_recurrences(transaction_specs)?,
             commands,
             assertion_specs,
+            budget_specs: Vec::new(),
         })
     }
 
+    #[cfg(feature = "fs")]
     async fn from_file<P>(
         &self,
         transactions_csv: P,
@@ -62,11 +74,24 @@ where
             self.balances_datasource.from_file(balances_csv).await?,
         );
         Ok(FinancialRecordSpecs {
-            transaction_specs,
+            transaction_specs: expand_recurrences(transaction_specs)?,
             commands,
             assertion_specs,
+            budget_specs: Vec::new(),
         })
     }
+
+    fn budgets_from_string(&self, budgets_csv: &str) -> Result<Vec<BudgetSpec<H>>, ServerError> {
+        self.budgets_datasource.from_string(budgets_csv)
+    }
+
+    #[cfg(feature = "fs")]
+    async fn budgets_from_file<P>(&self, budgets_csv: P) -> Result<Vec<BudgetSpec<H>>, ServerError>
+    where
+        P: AsRef<std::path::Path> + Send,
+    {
+        self.budgets_datasource.from_file(budgets_csv).await
+    }
 }
 
 impl<H: Handlers> RecordsRepositoryImpl<H> {
@@ -74,7 +99,72 @@ impl<H: Handlers> RecordsRepositoryImpl<H> {
         RecordsRepositoryImpl {
             transactions_datasource: TransactionsCsvDatasourceImpl::new(),
             balances_datasource: BalancesCsvDatasourceImpl::new(),
+            budgets_datasource: BudgetsCsvDatasourceImpl::new(),
             _phantom: std::marker::PhantomData,
         }
     }
 }
+
+/// Replaces each recurring spec by one spec per occurrence, skipping the
+/// exceptions.
+fn expand_recurrences<H: Handlers>(
+    transaction_specs: Vec<TransactionSpec<H>>,
+) -> Result<Vec<TransactionSpec<H>>, ServerError> {
+    let mut expanded = Vec::with_capacity(transaction_specs.len());
+    for spec in transaction_specs {
+        let Some(rule) = &spec.recurrence else {
+            expanded.push(spec);
+            continue;
+        };
+        let occurrences = rule
+            .occurrences(spec.payment_date, MAX_OCCURRENCES)
+            .ok_or_else(|| {
+                InvalidRecurrence::new(
+                    &spec.description,
+                    &format!("more than {} occurrences", MAX_OCCURRENCES),
+                )
+            })?;
+        if occurrences.is_empty() {
+            return Err(InvalidRecurrence::new(
+                &spec.description,
+                "no occurrences before the end date",
+            ));
+        }
+        let listed_dates = rule
+            .exceptions
+            .iter()
+            .chain(rule.overrides.iter().map(|o| &o.date));
+        for date in listed_dates {
+            if !occurrences.iter().any(|o| o.scheduled_date == *date) {
+                return Err(InvalidRecurrence::new(
+                    &spec.description,
+                    &format!("{} is not a scheduled date", date),
+                ));
+            }
+        }
+        for occurrence in occurrences.into_iter().filter(|o| !o.skipped) {
+            let shift = |date: NaiveDate| date + Months::new(occurrence.offset_months);
+            expanded.push(TransactionSpec {
+                id: spec.id.occurrence(occurrence.index),
+                accrual_start: shift(spec.accrual_start),
+                accrual_end: spec.accrual_end.map(shift),
+                payment_date: occurrence.payment_date,
+                accounting_logic: spec.accounting_logic.clone(),
+                decorators: spec.decorators.clone(),
+                payee: spec.payee.clone(),
+                description: spec.description.clone(),
+                amount: occurrence.amount.unwrap_or(spec.amount),
+                commodity: spec.commodity.clone(),
+                backing_account: spec.backing_account.clone(),
+                annotations: spec.annotations.clone(),
+                intercompany: spec.intercompany.clone(),
+                dimensions: spec.dimensions.clone(),
+                status: spec.status,
+                entity: spec.entity.clone(),
+                recurrence: None,
+                amends: None,
+            });
+        }
+    }
+    Ok(expanded)
+}
@@ -1,5 +1,43 @@
 use crate::entities::CashflowTracingTag;
 
+/// Defines a classification enum, along with a `description()` returning the
+/// doc comment of each variant (see `Account::description`), so the
+/// explanations printed in journals can't drift from the documentation.
+macro_rules! classification {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[doc = $doc:literal])*
+                $variant:ident $({ $($fields:tt)* })?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $(
+                $(#[doc = $doc])*
+                $variant $({ $($fields)* })?
+            ),*
+        }
+
+        impl $name {
+            /// Doc comment of the variant, joined into a single line.
+            pub(crate) fn description(&self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variant { .. } => classification!(@doc $($doc)*),)*
+                }
+            }
+        }
+    };
+    (@doc) => {
+        None
+    };
+    (@doc $($doc:literal)+) => {
+        Some(concat!($($doc),+).trim_start())
+    };
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Account {
     Asset(AssetAccount),
@@ -9,195 +47,223 @@ pub enum Account {
     Equity(EquityAccount),
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
-pub enum AssetClassification {
-    // Current.
-    // =========================================================================
-    //
-    CashAndCashEquivalents,
-    AccountsReceivable,
-    Inventory,
-    PrepaidExpenses,
-    ShortTermInvestments,
-    ShortTermDeposits,
-    OtherCurrentAssets,
-
-    // Non-current.
-    // =========================================================================
-    //
-    PropertyPlantEquipment,
-    IntangibleAssets,
-    LongTermInvestments,
-    LongTermDeposits,
-    DeferredIncomeTax,
-    OtherNonCurrentAssets,
+classification! {
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde_derive::Deserialize)]
+    pub enum AssetClassification {
+        // Current.
+        // =====================================================================
+        //
+        CashAndCashEquivalents,
+        AccountsReceivable,
+        Inventory,
+        PrepaidExpenses,
+        ShortTermInvestments,
+        ShortTermDeposits,
+        OtherCurrentAssets,
+
+        // Non-current.
+        // =====================================================================
+        //
+        PropertyPlantEquipment,
+        IntangibleAssets,
+        LongTermInvestments,
+        LongTermDeposits,
+        DeferredIncomeTax,
+        OtherNonCurrentAssets,
+        //
+        /// Contra-asset accumulating the depreciation of property, plant and
+        /// equipment, netted against its cost for presentation.
+        AccumulatedDepreciation,
+        /// Contra-asset accumulating the amortization of intangible assets,
+        /// netted against their cost for presentation.
+        AccumulatedAmortization,
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
-pub enum LiabilityClassification {
-    // Current.
-    // =========================================================================
-    //
-    AccountsPayable,
-    AccruedExpenses,
-    DeferredRevenue,
-    ShortTermDebt,
-    OtherCurrentLiabilities,
-
-    // Non-current.
-    // =========================================================================
-    //
-    LongTermDebt,
-    DeferredIncomeTax,
-    OtherNonCurrentLiabilities,
+classification! {
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde_derive::Deserialize)]
+    pub enum LiabilityClassification {
+        // Current.
+        // =====================================================================
+        //
+        AccountsPayable,
+        AccruedExpenses,
+        DeferredRevenue,
+        ShortTermDebt,
+        OtherCurrentLiabilities,
+
+        // Non-current.
+        // =====================================================================
+        //
+        LongTermDebt,
+        DeferredIncomeTax,
+        OtherNonCurrentLiabilities,
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
-pub enum IncomeClassification {
-    // Operating (core business) revenues.
-    // =========================================================================
-    //
-    /// Revenue from selling goods.
-    SalesRevenue,
-    /// Revenue from providing services.
-    ServiceRevenue,
-
-    // Financing revenues.
-    // =========================================================================
-    //
-    /// Interest earned on loans or other interest-bearing assets part of the
-    /// core business operations.
-    InterestIncome,
-    /// Earnings from investments in other companies part of the core business
-    /// operations.
-    DividendIncome,
-    /// Income from rental properties part of the core business operations.
-    RentalIncome,
-
-    // Non-operating income.
-    // =========================================================================
-    //
-    /// Bank interest earned on cash, or from non-core financing activities.
-    NonCoreInterestIncome,
-    /// Earnings from investments in other companies not part of the core
-    /// business operations.
-    NonCoreDividendIncome,
-    /// Income from rental properties not part of the core business operations.
-    NonCoreRentalIncome,
-    /// Gains from foreign exchange transactions.
-    RealizedFxGain,
-    /// Other non-operating financial income.
-    OtherNonCoreFinancialIncome,
-    //
-    /// Gains from the sale of long-term assets.
-    GainOnSaleOfAssets,
-    /// Gains from VAT filing (ex. discounts, rounding gains).
-    VatRefundGain,
-    //
-    OtherNonOperatingIncome,
+classification! {
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde_derive::Deserialize)]
+    pub enum IncomeClassification {
+        // Operating (core business) revenues.
+        // =====================================================================
+        //
+        /// Revenue from selling goods.
+        SalesRevenue,
+        /// Revenue from providing services.
+        ServiceRevenue,
+
+        // Financing revenues.
+        // =====================================================================
+        //
+        /// Interest earned on loans or other interest-bearing assets part of
+        /// the core business operations.
+        InterestIncome,
+        /// Earnings from investments in other companies part of the core
+        /// business operations.
+        DividendIncome,
+        /// Income from rental properties part of the core business operations.
+        RentalIncome,
+
+        // Non-operating income.
+        // =====================================================================
+        //
+        /// Bank interest earned on cash, or from non-core financing activities.
+        NonCoreInterestIncome,
+        /// Earnings from investments in other companies not part of the core
+        /// business operations.
+        NonCoreDividendIncome,
+        /// Income from rental properties not part of the core business
+        /// operations.
+        NonCoreRentalIncome,
+        /// Gains from foreign exchange transactions.
+        RealizedFxGain,
+        /// Other non-operating financial income.
+        OtherNonCoreFinancialIncome,
+        //
+        /// Gains from the sale of long-term assets.
+        GainOnSaleOfAssets,
+        /// Gains from VAT filing (ex. discounts, rounding gains).
+        VatRefundGain,
+        //
+        OtherNonOperatingIncome,
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
-pub enum ExpenseClassification {
-    // Operating expenses.
-    // =========================================================================
-    //
-    /// Direct costs associated with production or purchase of goods.
-    CostOfGoodsSold,
-    //
-    /// Expenses related to selling, such as marketing, advertising, and sales
-    /// commissions.
-    SellingExpenses,
-    /// Overhead costs, including office expenses, rent, utilities, and salaries
-    /// of non-sales staff.
-    GeneralAdministrativeExpenses,
-    /// Costs incurred for R&D activities.
-    ResearchAndDevelopmentExpenses,
-    /// Costs incurred for cloud services (ex. AWS).
-    CloudServicesExpenses,
-    //
-    /// Allocation of cost for tangible assets.
-    DepreciationExpense,
-    /// Allocation of cost for intangible assets.
-    AmortizationExpense,
-
-    // Financing expenses.
-    // =========================================================================
-    //
-    /// Interest paid on borrowings part of the core business operations.
-    InterestExpense,
-
-    // Tax expenses.
-    // =========================================================================
-    //
-    /// Income tax expense.
-    IncomeTaxExpense,
-    /// Other tax expenses (ex. residence tax, incorporation tax).
-    OtherTaxExpense,
-
-    // Non-operating expenses.
-    // =========================================================================
-    //
-    /// Losses from the sale of long-term assets.
-    LossOnSaleOfAssets,
-    /// Losses from VAT filing (ex. rounding losses).
-    VatRefundLoss,
-    //
-    /// Costs incurred on borrowings not part of the core business operations.
-    NonCoreInterestExpense,
-    /// Losses from foreign exchange transactions.
-    RealizedFxLoss,
-    /// Other non-administrative financial expenses, such as foreign transaction
-    /// fees.
-    OtherNonCoreFinancialExpense,
-    //
-    OtherNonOperatingCashExpense,
-    OtherNonOperatingNonCashExpense,
+classification! {
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde_derive::Deserialize)]
+    pub enum ExpenseClassification {
+        // Operating expenses.
+        // =====================================================================
+        //
+        /// Direct costs associated with production or purchase of goods.
+        CostOfGoodsSold,
+        //
+        /// Expenses related to selling, such as marketing, advertising, and
+        /// sales commissions.
+        SellingExpenses,
+        /// Overhead costs, including office expenses, rent, utilities, and
+        /// salaries of non-sales staff.
+        GeneralAdministrativeExpenses,
+        /// Costs incurred for R&D activities.
+        ResearchAndDevelopmentExpenses,
+        /// Costs incurred for cloud services (ex. AWS).
+        CloudServicesExpenses,
+        //
+        /// Allocation of cost for tangible assets.
+        DepreciationExpense,
+        /// Allocation of cost for intangible assets.
+        AmortizationExpense,
+
+        // Financing expenses.
+        // =====================================================================
+        //
+        /// Interest paid on borrowings part of the core business operations.
+        InterestExpense,
+
+        // Tax expenses.
+        // =====================================================================
+        //
+        /// Income tax expense.
+        IncomeTaxExpense,
+        /// Other tax expenses (ex. residence tax, incorporation tax).
+        OtherTaxExpense,
+
+        // Non-operating expenses.
+        // =====================================================================
+        //
+        /// Losses from the sale of long-term assets.
+        LossOnSaleOfAssets,
+        /// Losses from VAT filing (ex. rounding losses).
+        VatRefundLoss,
+        //
+        /// Costs incurred on borrowings not part of the core business
+        /// operations.
+        NonCoreInterestExpense,
+        /// Losses from foreign exchange transactions.
+        RealizedFxLoss,
+        /// Other non-administrative financial expenses, such as foreign
+        /// transaction fees.
+        OtherNonCoreFinancialExpense,
+        //
+        OtherNonOperatingCashExpense,
+        OtherNonOperatingNonCashExpense,
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
-pub enum EquityClassification {
-    // Share capital.
-    // =========================================================================
-    //
-    CommonStock,
-    PreferredStock,
-    //
-    /// Some accounting standards (e.g. certain EU) require unpaid share capital
-    /// to be recorded as negative equity (instead of a receivable asset).
-    UnpaidShareCapital,
-
-    // Capital surplus.
-    // =========================================================================
-    //
-    /// Excess paid over the par-value of shares issued.
-    SharePremium,
-    //
-    /// Surplus payments made by shareholders without expectation of
-    /// reimbursement or share issuance.
-    ContributedSurplus,
-
-    // Capital adjustments.
-    // =========================================================================
-    //
-    TreasuryStock,
-    //
-    /// Costs directly associated with issuing shares, such as legal and
-    /// registration fees. Such costs are generally first debited against
-    /// SharePremium, and any remainder (under some standards, e.g. Korea) must
-    /// be debited against this contra-equity account. Note that other standards
-    /// may permit debiting such costs directly against RetainedEarnings.
-    DiscountOnStockIssuance,
-
-    // Earned capital.
-    // =========================================================================
-    //
-    RetainedEarnings,
-    RetainedEarningsOpt {
-        // Allow overriding the default cashflow tagging.
-        on_inflow: Option<CashflowTracingTag>,
-        on_outflow: Option<CashflowTracingTag>,
-    },
+classification! {
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, serde_derive::Deserialize)]
+    pub enum EquityClassification {
+        // Share capital.
+        // =====================================================================
+        //
+        CommonStock,
+        PreferredStock,
+        //
+        /// Some accounting standards (e.g. certain EU) require unpaid share
+        /// capital to be recorded as negative equity (instead of a receivable
+        /// asset).
+        UnpaidShareCapital,
+
+        // Capital surplus.
+        // =====================================================================
+        //
+        /// Excess paid over the par-value of shares issued.
+        SharePremium,
+        //
+        /// Surplus payments made by shareholders without expectation of
+        /// reimbursement or share issuance.
+        ContributedSurplus,
+
+        // Capital adjustments.
+        // =====================================================================
+        //
+        TreasuryStock,
+        //
+        /// Costs directly associated with issuing shares, such as legal and
+        /// registration fees. Such costs are generally first debited against
+        /// SharePremium, and any remainder (under some standards, e.g. Korea)
+        /// must be debited against this contra-equity account. Note that other
+        /// standards may permit debiting such costs directly against
+        /// RetainedEarnings.
+        DiscountOnStockIssuance,
+
+        // Accumulated other comprehensive income.
+        // =====================================================================
+        //
+        /// Gains and losses recognized outside of profit or loss, such as
+        /// exchange differences on translating foreign operations (IAS 21).
+        AccumulatedOtherComprehensiveIncome,
+
+        // Earned capital.
+        // =====================================================================
+        //
+        RetainedEarnings,
+        RetainedEarningsOpt {
+            // Allow overriding the default cashflow tagging.
+            on_inflow: Option<CashflowTracingTag>,
+            on_outflow: Option<CashflowTracingTag>,
+        },
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
@@ -1,3 +1,9 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use super::transaction_spec::TransactionSpecId;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Annotation {
     ImmaterialExpense,
@@ -6,11 +12,68 @@ pub enum Annotation {
     VatKorea,
     VatKoreaUnrecoverable,
     VatKoreaReverseChargeExempt,
+    /// Value-added rate of the industry, in percent.
+    VatKoreaSimplifiedSale(i32),
+    VatKoreaSimplifiedPurchase,
+    /// Grounds for the zero rate (ex. "export of goods").
+    VatKoreaZeroRated(String),
+    ImportVatCustomsDutyCapitalized,
+    ImportVatCustomsDutyExpensed,
     CardFxBySettle,
     CardFxByFee,
     ForeignWithholdingTax(i32),
+    KoreaFreelancerWithholding,
+    KoreaSocialInsurance,
     ShareIssuanceCostsDirectedToRetainedEarnings,
-    Custom(String),
+    FairValueThroughProfitOrLoss,
+    FairValueThroughOci,
+    /// Recoverable amount the asset was written down to (formatted, ex.
+    /// "1,200.00 USD").
+    Impairment(String),
+    InferredInterest,
+    /// Spec downgraded to `ImmaterialExpense` (or `ImmaterialIncome`) since
+    /// its amount is under the materiality threshold (formatted, ex. "20.00
+    /// USD").
+    BelowMaterialityThreshold(String),
+    /// Spec with entries dated on or before an already reconciled balance
+    /// (see `ProcessOptions::backdating_guard`), described by the reason it
+    /// was reconciled (ex. "the 2024-03-31 filing date").
+    Backdated(String),
+    /// Spec booked to the suspense account, since it failed validation for
+    /// its accounting logic (see `ProcessOptions::suspense_routing`). Holds
+    /// the validation error.
+    Suspense(String),
+    /// Correction of an earlier spec (see `Amendment`).
+    Restatement {
+        amended: TransactionSpecId,
+        date: NaiveDate,
+    },
+    /// User-defined note (ex. a company policy), with optional details and
+    /// metadata (ex. contract references, approval IDs).
+    ///
+    /// The texts can contain placeholders filled in from the spec when it is
+    /// processed: `{{payee}}`, `{{description}}`, `{{amount}}`,
+    /// `{{currency}}`, `{{accrual_start}}`, `{{accrual_end}}`,
+    /// `{{payment_date}}` and `{{spec_id}}`.
+    Custom {
+        title: String,
+        /// Empty if the title says it all.
+        body: String,
+        /// Printed as `name: value` pairs, which hledger reads as tags of the
+        /// transaction when the annotation is printed in full.
+        meta: BTreeMap<String, String>,
+    },
+}
+
+impl Annotation {
+    /// Custom annotation with only a title.
+    pub fn custom(title: impl Into<String>) -> Self {
+        Annotation::Custom {
+            title: title.into(),
+            body: String::new(),
+            meta: BTreeMap::new(),
+        }
+    }
 }
 
 impl std::fmt::Display for Annotation {
@@ -22,11 +85,39 @@ impl std::fmt::Display for Annotation {
             Annotation::VatKorea => write!(f, "VAT is removed from transaction and separately recorded as VAT that is awaiting a proper receipt. Upon receipt, it is reclassified as a VAT receivable asset or payable liability. Upon VAT tax payment / refund, those accounts are cleared."),
             Annotation::VatKoreaUnrecoverable => write!(f, "Due to insufficient VAT receipts, the VAT charged for this purchase can not be claimed. As such, the entire cost of the purchase (including unrecoverable VAT) is recorded in the books. Any accrual logic or amortization is applied to the total cost."),
             Annotation::VatKoreaReverseChargeExempt => write!(f, "VAT was charged on a reverse-charge basis, meaning it is the company's responsibility to pay VAT through proxy payment. However, since the purchase is used for taxable business, the proxy payment is exempt, and the cost is simply recorded in the books without VAT."),
+            Annotation::VatKoreaSimplifiedSale(r) => write!(f, "The company is a simplified VAT taxpayer. VAT on sales is computed as 10% of the sale amount (including VAT) multiplied by the {}% value-added rate of its industry, removed from the transaction, and recorded as VAT payable.", r),
+            Annotation::VatKoreaSimplifiedPurchase => write!(f, "The company is a simplified VAT taxpayer. Instead of recovering the VAT charged on purchases, a credit of 0.5% of the purchase amount (including VAT) is deducted from VAT payable, and the rest of the amount is recorded as the cost of the purchase."),
+            Annotation::VatKoreaZeroRated(basis) => write!(f, "Zero-rated sale ({}). VAT is charged at 0%, so the full amount is recorded as revenue. VAT paid on related purchases remains recoverable, and is recorded as a VAT receivable asset as usual.", basis),
+            Annotation::ImportVatCustomsDutyCapitalized => write!(f, "Imported purchase. Import VAT paid at customs is recoverable, and recorded separately as a VAT receivable asset. Customs duty is included in the cost of the purchase, and any accounting logic (amortization, etc.) is applied to the total cost."),
+            Annotation::ImportVatCustomsDutyExpensed => write!(f, "Imported purchase. Import VAT paid at customs is recoverable, and recorded separately as a VAT receivable asset. Customs duty is recorded separately as an expense on payment date."),
             Annotation::CardFxBySettle => write!(f, "Transaction amount was converted to the target currency using the latest available exchange rate at the time of payment. On settlement, the amount was adjusted to reflect the actual exchange rate."),
             Annotation::CardFxByFee => write!(f, "Transaction amount was converted to the target currency using the latest available exchange rate at the time of payment. The foreign transaction fee charged by the card issuer was recorded as a separate transaction."),
             Annotation::ForeignWithholdingTax(w) => write!(f, "A {}% foreign withholding tax was imposed on this transaction. Any accounting logic (amortization, etc.) is applied to the pre-withholding amount, and the withholding amount itself is recorded as a foreign withholding tax expense on payment date.", w),
+            Annotation::KoreaFreelancerWithholding => write!(f, "Payment to a freelancer, subject to 3.3% withholding on the gross fee (3% income tax and 0.3% local income tax). The gross fee is recorded as the cost, and the withheld amount is recorded as a withholding tax payable liability until it is remitted by the 10th of the following month."),
+            Annotation::KoreaSocialInsurance => write!(f, "Wage subject to the four major social insurances (national pension, health and long-term care, employment, and industrial accident insurance). The gross wage is recorded as the cost, and the employee portions withheld from it, together with the employer portions (recorded as a social insurance expense), are recorded as a social insurance payable liability until they are remitted by the 10th of the following month."),
             Annotation::ShareIssuanceCostsDirectedToRetainedEarnings => write!(f, "WARNING: Since share premium is not yet supported, it is assumed to be zero. As such, share issuance costs are directly debited to retained earnings."),
-            Annotation::Custom(s) => write!(f, "{}", s),
+            Annotation::FairValueThroughProfitOrLoss => write!(f, "Investment remeasured to fair value at the reporting date, with the change in fair value recognized in profit or loss."),
+            Annotation::FairValueThroughOci => write!(f, "Investment remeasured to fair value at the reporting date, with the change in fair value recognized in other comprehensive income and accumulated in equity (fair value reserve)."),
+            Annotation::Impairment(recoverable) => write!(f, "Asset written down to its recoverable amount of {}, with the impairment loss recognized in profit or loss. Depreciation / amortization recorded after the impairment is not revised for the lower carrying amount.", recoverable),
+            Annotation::InferredInterest => write!(f, "REVIEW: Interest income inferred from the difference between the asserted bank balance and the balance implied by the transactions, rather than recorded from a statement."),
+            Annotation::BelowMaterialityThreshold(threshold) => write!(f, "Amount is below the company's materiality threshold of {}, so it is recorded as immaterial rather than accrued, per company policy.", threshold),
+            Annotation::Backdated(reconciled) => write!(f, "WARNING: Entries of this spec are dated on or before {}, which preceded the spec's payment date. Balances reconciled as of that date may no longer hold.", reconciled),
+            Annotation::Suspense(error) => write!(f, "REVIEW: Booked to the suspense account since the spec failed validation for its accounting logic, to be reclassified once the spec is corrected. Error: {}", error),
+            Annotation::Restatement { amended, date } => write!(f, "Restatement correcting an error in the entries of spec {}. The original entries are kept, but reversed as of {}, and replaced by the entries of this transaction.", amended, date),
+            Annotation::Custom { title, body, meta } => {
+                write!(f, "{}", title)?;
+                if !body.is_empty() || !meta.is_empty() {
+                    write!(f, " | {}", body)?;
+                }
+                if !meta.is_empty() {
+                    let pairs = meta
+                        .iter()
+                        .map(|(name, value)| format!("{}: {}", name, value))
+                        .collect::<Vec<_>>();
+                    write!(f, " | {}", pairs.join(", "))?;
+                }
+                Ok(())
+            }
         }
     }
 }
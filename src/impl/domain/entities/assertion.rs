@@ -1,12 +1,24 @@
+use super::{account::Account, commodity::Commodity, transaction_spec::TransactionSpecId};
 use chrono::NaiveDate;
-use iso_currency::Currency;
-
-use super::account::Account;
 
 #[derive(Debug, Clone)]
 pub struct Assertion {
     pub date: NaiveDate,
     pub account: Account,
     pub balance: f64,
-    pub currency: Currency,
+    pub commodity: Commodity,
+}
+
+/// Balance assertion that doesn't hold for the generated transactions (see
+/// `ProcessOptions::preflight_assertions`).
+#[derive(Debug, Clone)]
+pub struct FailedAssertion {
+    pub assertion: Assertion,
+    /// Balance implied by the transactions up to (and including) the date.
+    pub actual: f64,
+    /// Specs likely responsible, most likely first: those whose amount would
+    /// explain the difference (if misdated, duplicated or mis-signed),
+    /// followed by the other specs posting to the account since its previous
+    /// passing assertion, latest first.
+    pub suspects: Vec<TransactionSpecId>,
 }
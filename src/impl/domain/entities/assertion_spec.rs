@@ -1,4 +1,5 @@
 use chrono::NaiveDate;
+use iso_currency::Currency;
 
 use super::handlers::Handlers;
 
@@ -7,5 +8,20 @@ pub struct AssertionSpec<H: Handlers> {
     pub date: NaiveDate,
     pub cash_handler: H::C,
     pub balance: f64,
-    pub commodity: H::M,
+    pub commodity: AssertionCommodity<H>,
+    /// Group entity owning the cash account (see `TransactionSpec::entity`).
+    pub entity: Option<String>,
+}
+
+/// Source of the currency of a balance assertion.
+#[derive(Debug)]
+pub enum AssertionCommodity<H: Handlers> {
+    /// Set explicitly (ex. in the Commodity column of the balances CSV).
+    Explicit(H::M),
+    /// Given by the balance column header (ex. "Balance (KRW)").
+    Column(Currency),
+    /// Inferred from the cash account: its `CashHandler::currency`, otherwise
+    /// the currency of its postings if they all share one, otherwise the given
+    /// commodity (the default commodity, resolved when parsed).
+    Infer(H::M),
 }
@@ -0,0 +1,104 @@
+use chrono::NaiveDate;
+use fractic_server_error::ServerError;
+use iso_currency::Currency;
+
+use super::{
+    account::{
+        Account, ExpenseAccount, ExpenseClassification, IncomeAccount, IncomeClassification,
+    },
+    handlers::{CommodityHandler, ExpenseHandler, Handlers, IncomeHandler},
+};
+
+/// Budgeted income or expense for a period, compared against the actual
+/// amounts by `ext::reports::BudgetVariance`.
+#[derive(Debug)]
+pub struct BudgetSpec<H: Handlers> {
+    pub target: BudgetTarget<H::E, H::I>,
+    pub from: NaiveDate,
+    /// Inclusive.
+    pub to: NaiveDate,
+    /// Expected spending (for expenses) or earnings (for income), as a
+    /// positive number.
+    pub amount: f64,
+    pub commodity: H::M,
+    /// Group entity the budget belongs to (see `TransactionSpec::entity`).
+    pub entity: Option<String>,
+}
+
+/// What a budget applies to: a single account, or all accounts of a
+/// classification (including named sub-accounts).
+#[derive(Debug, Clone)]
+pub enum BudgetTarget<E, I> {
+    Expense(E),
+    Income(I),
+    ExpenseClassification(ExpenseClassification),
+    IncomeClassification(IncomeClassification),
+}
+
+/// Budget with the handlers resolved, as kept in `FinancialRecords`.
+#[derive(Debug, Clone)]
+pub struct Budget {
+    pub scope: BudgetScope,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub amount: f64,
+    pub currency: Currency,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BudgetScope {
+    Expense(ExpenseAccount),
+    Income(IncomeAccount),
+    ExpenseClassification(ExpenseClassification),
+    IncomeClassification(IncomeClassification),
+}
+
+// --
+
+impl<H: Handlers> BudgetSpec<H> {
+    pub fn resolve(&self) -> Result<Budget, ServerError> {
+        Ok(Budget {
+            scope: match &self.target {
+                BudgetTarget::Expense(e) => BudgetScope::Expense(e.account()),
+                BudgetTarget::Income(i) => BudgetScope::Income(i.account()),
+                BudgetTarget::ExpenseClassification(c) => BudgetScope::ExpenseClassification(*c),
+                BudgetTarget::IncomeClassification(c) => BudgetScope::IncomeClassification(*c),
+            },
+            from: self.from,
+            to: self.to,
+            amount: self.amount,
+            currency: self.commodity.currency()?,
+        })
+    }
+}
+
+impl BudgetScope {
+    /// Whether postings to the account count towards the budget.
+    pub fn matches(&self, account: &Account) -> bool {
+        match (self, account) {
+            (BudgetScope::Expense(e), Account::Expense(a)) => e == a,
+            (BudgetScope::Income(i), Account::Income(a)) => i == a,
+            (BudgetScope::ExpenseClassification(c), Account::Expense(a)) => a.1 == *c,
+            (BudgetScope::IncomeClassification(c), Account::Income(a)) => a.1 == *c,
+            _ => false,
+        }
+    }
+
+    /// Income is budgeted as a positive amount, but posted as a negative one.
+    pub fn is_income(&self) -> bool {
+        matches!(
+            self,
+            BudgetScope::Income(_) | BudgetScope::IncomeClassification(_)
+        )
+    }
+
+    /// The budgeted account, or the top-level account of the classification.
+    pub fn account(&self) -> Account {
+        match self {
+            BudgetScope::Expense(e) => e.clone().into(),
+            BudgetScope::Income(i) => i.clone().into(),
+            BudgetScope::ExpenseClassification(c) => ExpenseAccount(None, *c).into(),
+            BudgetScope::IncomeClassification(c) => IncomeAccount(None, *c).into(),
+        }
+    }
+}
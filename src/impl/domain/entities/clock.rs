@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+
+/// Source of the current time, used to timestamp generated records (see
+/// `GenerationMetadata::generated_at`).
+///
+/// Implement this to pin the time (ex. a fixed instant in snapshot tests), or
+/// to read it from the browser in WASM builds, where the system clock isn't
+/// available.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
@@ -17,19 +17,29 @@ pub enum CommandLogic<F> {
 }
 
 #[derive(Debug)]
-pub(crate) struct Command<H: Handlers> {
+pub struct Command<H: Handlers> {
     pub id: CommandSpecId,
     pub date: NaiveDate,
     pub exec: CommandLogic<H::F>,
     pub arguments: Vec<String>,
     pub description: Option<String>,
     pub amount: Option<f64>,
+    /// For `Close`, the default commodity if not given (resolved when
+    /// parsed, so handlers resolving it from configuration can do so).
     pub commodity: Option<H::M>,
     pub notes: Vec<String>,
+    /// Group entity the command applies to (see `TransactionSpec::entity`).
+    pub entity: Option<String>,
 }
 
 // --
 
+impl CommandSpecId {
+    pub fn new(id: u64) -> Self {
+        Self(id)
+    }
+}
+
 impl std::fmt::Display for CommandSpecId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
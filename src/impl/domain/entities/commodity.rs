@@ -0,0 +1,120 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Mutex, OnceLock},
+};
+
+use fractic_server_error::ServerError;
+use iso_currency::Currency;
+
+use crate::errors::InvalidCustomCommodity;
+
+/// Amounts with more decimal places can't be formatted exactly.
+const MAX_DECIMAL_PLACES: u32 = 12;
+
+/// Unit an amount is denominated in: an ISO currency, or a custom commodity
+/// (ex. shares, loyalty points or crypto) with its own symbol and precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Commodity {
+    Currency(Currency),
+    Custom {
+        /// As printed in the journal (ex. "AAPL", "BTC").
+        symbol: &'static str,
+        decimal_places: u32,
+    },
+}
+
+impl Commodity {
+    /// Custom commodity (ex. `Commodity::custom("BTC", 8)`). Symbols are
+    /// interned, so commodities stay cheap to copy.
+    pub fn custom(symbol: &str, decimal_places: u32) -> Result<Self, ServerError> {
+        if symbol.trim().is_empty() || symbol.contains(['"', '\n', ';']) {
+            return Err(InvalidCustomCommodity::new(
+                symbol,
+                "symbol must be non-empty, and can't contain '\"', ';' or line breaks",
+            ));
+        }
+        if decimal_places > MAX_DECIMAL_PLACES {
+            return Err(InvalidCustomCommodity::new(
+                symbol,
+                &format!(
+                    "at most {} decimal places are supported",
+                    MAX_DECIMAL_PLACES
+                ),
+            ));
+        }
+        Ok(Self::Custom {
+            symbol: intern(symbol),
+            decimal_places,
+        })
+    }
+
+    /// The ISO currency, if the commodity is one (ex. for FX conversion or
+    /// tax logic that only applies to currencies).
+    pub fn currency(&self) -> Option<Currency> {
+        match self {
+            Self::Currency(currency) => Some(*currency),
+            Self::Custom { .. } => None,
+        }
+    }
+
+    /// ISO code (ex. "USD"), or the custom commodity's symbol.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Currency(currency) => currency.code(),
+            Self::Custom { symbol, .. } => symbol,
+        }
+    }
+
+    /// Symbol amounts are printed with (ex. "$", "AAPL").
+    pub fn symbol(&self) -> String {
+        match self {
+            Self::Currency(currency) => currency.symbol().to_string(),
+            Self::Custom { symbol, .. } => symbol.to_string(),
+        }
+    }
+
+    /// Standard number of decimal places (ex. JPY = 0, USD = 2, or as given
+    /// for custom commodities).
+    pub fn decimal_places(&self) -> u32 {
+        match self {
+            Self::Currency(currency) => currency.exponent().unwrap_or(0) as u32,
+            Self::Custom { decimal_places, .. } => *decimal_places,
+        }
+    }
+}
+
+impl From<Currency> for Commodity {
+    fn from(currency: Currency) -> Self {
+        Self::Currency(currency)
+    }
+}
+
+impl PartialEq<Currency> for Commodity {
+    fn eq(&self, currency: &Currency) -> bool {
+        self.currency() == Some(*currency)
+    }
+}
+
+impl fmt::Display for Commodity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Leaks each distinct symbol once.
+fn intern(symbol: &str) -> &'static str {
+    static SYMBOLS: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut symbols = SYMBOLS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match symbols.get(symbol) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(symbol.to_string().into_boxed_str());
+            symbols.insert(interned);
+            interned
+        }
+    }
+}
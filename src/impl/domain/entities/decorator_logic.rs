@@ -3,10 +3,11 @@ use fractic_server_error::ServerError;
 
 use crate::entities::DecoratedTransactionSpec;
 
-use super::handlers::Handlers;
+use super::{handlers::Handlers, maybe_send::MaybeSendSync};
 
-#[async_trait]
-pub trait DecoratorLogic<H: Handlers>: std::fmt::Debug + Send + Sync {
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait DecoratorLogic<H: Handlers>: std::fmt::Debug + MaybeSendSync {
     async fn apply(
         &self,
         tx: DecoratedTransactionSpec<H>,
@@ -0,0 +1,67 @@
+use fractic_server_error::ServerError;
+
+use super::handlers::CommodityHandler;
+
+/// When a discrepancy (ex. between an estimate and the actual amount, or
+/// between the converted and charged FX amount) is material enough to be
+/// posted as a separate correction. Discrepancies that aren't posted are
+/// absorbed into the main transaction. Differences too small to display (see
+/// `CommodityHandler::precision_cutoff`) are never posted.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DiscrepancyPolicy {
+    /// Post if the discrepancy would display as a non-zero amount.
+    #[default]
+    PrecisionCutoff,
+    /// Post if the discrepancy is at least the given amount.
+    Absolute(f64),
+    /// Post if the discrepancy is at least the given percentage (ex. 0.5 for
+    /// 0.5%) of the amount it corrects.
+    Relative(f64),
+    /// Post any discrepancy. Same as `PrecisionCutoff`, for configurations
+    /// that state the choice explicitly.
+    Always,
+    /// Always absorb discrepancies into the main transaction.
+    Never,
+}
+
+impl DiscrepancyPolicy {
+    /// reference: The amount the discrepancy corrects (sign is ignored), used
+    /// by the `Relative` policy.
+    pub fn should_post(
+        &self,
+        discrepancy: f64,
+        reference: f64,
+        commodity: &impl CommodityHandler,
+    ) -> Result<bool, ServerError> {
+        let discrepancy = discrepancy.abs();
+        if discrepancy < commodity.precision_cutoff()? {
+            return Ok(false);
+        }
+        Ok(match self {
+            DiscrepancyPolicy::PrecisionCutoff | DiscrepancyPolicy::Always => true,
+            DiscrepancyPolicy::Absolute(threshold) => discrepancy >= threshold.abs(),
+            DiscrepancyPolicy::Relative(percent) => {
+                discrepancy >= reference.abs() * percent.abs() / 100.0
+            }
+            DiscrepancyPolicy::Never => false,
+        })
+    }
+
+    /// Part of the discrepancy to absorb into the main transaction: the full
+    /// discrepancy if it would display as a non-zero amount but isn't posted
+    /// separately, otherwise zero.
+    pub fn absorbed(
+        &self,
+        discrepancy: f64,
+        reference: f64,
+        commodity: &impl CommodityHandler,
+    ) -> Result<f64, ServerError> {
+        if discrepancy.abs() < commodity.precision_cutoff()?
+            || self.should_post(discrepancy, reference, commodity)?
+        {
+            Ok(0.0)
+        } else {
+            Ok(discrepancy)
+        }
+    }
+}
@@ -1,7 +1,5 @@
+use crate::entities::{CloseLogic, Commodity};
 use chrono::{Datelike, NaiveDate};
-use iso_currency::Currency;
-
-use crate::entities::CloseLogic;
 
 #[derive(Debug, Clone)]
 pub enum EndOfYearEntry {
@@ -16,8 +14,8 @@ pub enum EndOfYearEntry {
         total: Option<f64>,
         /// The filing logic to be applied.
         logic: CloseLogic,
-        /// Currency used to format entry & total amounts.
-        currency: Currency,
+        /// Commodity used to format entry & total amounts.
+        commodity: Commodity,
     },
     Correction {
         /// Date on which to apply the correction entry.
@@ -1,13 +1,14 @@
 // Before processing.
 // ---
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::{
     domain::logic::spec_processor::UnreimbursedEntry,
     entities::{
-        Annotation, Assertion, AssertionSpec, Command, DecoratedTransactionSpec, EndOfYearEntry,
-        Transaction, TransactionLabel, TransactionSpec, TransactionSpecId,
+        Annotation, Assertion, AssertionSpec, Budget, BudgetSpec, Command,
+        DecoratedTransactionSpec, EndOfYearEntry, GenerationMetadata, PayeeMetadata, SpecLookup,
+        Transaction, TransactionLabel, TransactionSpec,
     },
 };
 
@@ -17,11 +18,57 @@ use super::{account::LiabilityAccount, handlers::Handlers};
 // ---
 
 #[derive(Debug)]
-pub(crate) struct FinancialRecordSpecs<H: Handlers> {
+pub struct FinancialRecordSpecs<H: Handlers> {
     // Unprocessed:
     pub transaction_specs: Vec<TransactionSpec<H>>,
     pub assertion_specs: Vec<AssertionSpec<H>>,
     pub commands: Vec<Command<H>>,
+    /// Not processed, only resolved and passed through to
+    /// `FinancialRecords::budgets`.
+    pub budget_specs: Vec<BudgetSpec<H>>,
+}
+
+impl<H: Handlers> FinancialRecordSpecs<H> {
+    /// Split the specs by their group entity, ordered by entity name. Specs
+    /// without an entity are grouped under `None` (listed first).
+    pub fn partition_by_entity(self) -> Vec<(Option<String>, FinancialRecordSpecs<H>)> {
+        fn partition<'a, H: Handlers>(
+            partitions: &'a mut BTreeMap<Option<String>, FinancialRecordSpecs<H>>,
+            entity: &Option<String>,
+        ) -> &'a mut FinancialRecordSpecs<H> {
+            partitions
+                .entry(entity.clone())
+                .or_insert_with(|| FinancialRecordSpecs {
+                    transaction_specs: Vec::new(),
+                    assertion_specs: Vec::new(),
+                    commands: Vec::new(),
+                    budget_specs: Vec::new(),
+                })
+        }
+
+        let mut partitions = BTreeMap::new();
+        for spec in self.transaction_specs {
+            partition(&mut partitions, &spec.entity)
+                .transaction_specs
+                .push(spec);
+        }
+        for spec in self.assertion_specs {
+            partition(&mut partitions, &spec.entity)
+                .assertion_specs
+                .push(spec);
+        }
+        for command in self.commands {
+            partition(&mut partitions, &command.entity)
+                .commands
+                .push(command);
+        }
+        for budget in self.budget_specs {
+            partition(&mut partitions, &budget.entity)
+                .budget_specs
+                .push(budget);
+        }
+        partitions.into_iter().collect()
+    }
 }
 
 // 1. After decorator processing.
@@ -29,7 +76,7 @@ pub(crate) struct FinancialRecordSpecs<H: Handlers> {
 
 #[derive(Debug)]
 #[allow(non_camel_case_types)]
-pub(crate) struct FinancialRecords_Intermediate1<H: Handlers> {
+pub struct FinancialRecords_Intermediate1<H: Handlers> {
     // Partially processed:
     pub transaction_specs: Vec<DecoratedTransactionSpec<H>>,
     // Unprocessed:
@@ -42,13 +89,13 @@ pub(crate) struct FinancialRecords_Intermediate1<H: Handlers> {
 
 #[derive(Debug)]
 #[allow(non_camel_case_types)]
-pub(crate) struct FinancialRecords_Intermediate2<H: Handlers> {
+pub struct FinancialRecords_Intermediate2<H: Handlers> {
     // Processed:
     pub transactions: Vec<Transaction>,
     pub assertions: Vec<Assertion>,
     pub ledger_extensions: Vec<String>,
-    pub label_lookup: HashMap<TransactionSpecId, TransactionLabel>,
-    pub annotations_lookup: HashMap<TransactionSpecId, Vec<Annotation>>,
+    pub label_lookup: SpecLookup<TransactionLabel>,
+    pub annotations_lookup: SpecLookup<Vec<Annotation>>,
     pub unreimbursed_entries: Vec<(LiabilityAccount, UnreimbursedEntry)>,
     // Unprocessed:
     pub commands: Vec<Command<H>>,
@@ -63,9 +110,16 @@ pub struct FinancialRecords {
     pub assertions: Vec<Assertion>,
     pub ledger_extensions: Vec<String>,
     pub eoy_entries: Vec<EndOfYearEntry>,
-    pub label_lookup: HashMap<TransactionSpecId, TransactionLabel>,
-    pub annotations_lookup: HashMap<TransactionSpecId, Vec<Annotation>>,
+    pub label_lookup: SpecLookup<TransactionLabel>,
+    pub annotations_lookup: SpecLookup<Vec<Annotation>>,
     pub unreimbursed_entries: Vec<(LiabilityAccount, UnreimbursedEntry)>,
+    pub budgets: Vec<Budget>,
+    /// Metadata of the payees that have any, by name (see
+    /// `PayeeHandler::metadata`).
+    pub payees: BTreeMap<String, PayeeMetadata>,
+    /// Set by the processing pipeline. Records derived from processed records
+    /// (ex. filtered by dimension) keep the metadata of their source.
+    pub metadata: Option<GenerationMetadata>,
 }
 
 #[derive(Debug, Clone)]
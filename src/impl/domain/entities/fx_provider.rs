@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use fractic_server_error::ServerError;
+
+use super::maybe_send::MaybeSendSync;
+
+/// Source of end-of-day exchange rates, used by FX-aware decorators.
+///
+/// Implement this to supply rates from somewhere other than the default
+/// Open Exchange Rates API integration (ex. a fixed table in tests, or a
+/// browser `fetch` in WASM builds, where providers and their futures needn't
+/// be `Send`).
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait FxProvider: MaybeSendSync {
+    /// Convert the amount from one ISO currency to another, at the EOD rate of
+    /// the given date.
+    async fn convert(
+        &self,
+        date: NaiveDate,
+        from: &str,
+        to: &str,
+        amount: f64,
+    ) -> Result<f64, ServerError>;
+}
+
+// Compile check: on wasm32, a provider may hold `!Send` state across awaits
+// (as a `JsFuture` would).
+#[cfg(target_arch = "wasm32")]
+const _: () = {
+    struct LocalFxProvider(std::rc::Rc<f64>);
+
+    #[async_trait(?Send)]
+    impl FxProvider for LocalFxProvider {
+        async fn convert(
+            &self,
+            _date: NaiveDate,
+            _from: &str,
+            _to: &str,
+            amount: f64,
+        ) -> Result<f64, ServerError> {
+            let rate = self.0.clone();
+            std::future::ready(()).await;
+            Ok(amount * *rate)
+        }
+    }
+
+    let _: fn() -> std::sync::Arc<dyn FxProvider> =
+        || std::sync::Arc::new(LocalFxProvider(std::rc::Rc::new(1.0)));
+};
@@ -0,0 +1,71 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Write as _},
+};
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use super::process_options::ProcessOptions;
+
+/// Provenance of processed records, so a generated journal can be traced back
+/// to the exact inputs that produced it (see `PrintOptions::generation_header`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationMetadata {
+    pub crate_version: String,
+    /// See `ProcessOptions::clock`.
+    pub generated_at: DateTime<Utc>,
+    /// SHA-256 of the specs, as parsed. Formatting differences of the input
+    /// CSVs (ex. quoting) therefore don't change it.
+    pub input_hash: String,
+    /// SHA-256 of the processing options. Maps are hashed in sorted order, so
+    /// the same options always give the same hash.
+    pub config_hash: String,
+}
+
+impl GenerationMetadata {
+    pub(crate) fn new(input: &impl Debug, options: &ProcessOptions) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: options.clock.now(),
+            input_hash: debug_hash(input),
+            config_hash: debug_hash(options),
+        }
+    }
+}
+
+/// Hex-encoded SHA-256 of the value's `Debug` output, hashed as it is
+/// formatted rather than collected into a string first (the specs can be
+/// large). The output must be canonical: `Debug` impls of hashed values print
+/// their `HashMap`s through `SortedMap`, since iteration order varies between
+/// runs.
+pub(crate) fn debug_hash(value: &impl Debug) -> String {
+    struct HashWriter(Sha256);
+    impl fmt::Write for HashWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.update(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    let mut writer = HashWriter(Sha256::new());
+    write!(writer, "{:?}", value).expect("hashing should not fail");
+    writer
+        .0
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Formats the map like its `Debug` impl, but with the entries sorted by the
+/// `Debug` output of their keys.
+pub(crate) struct SortedMap<'a, K, V>(pub(crate) &'a HashMap<K, V>);
+
+impl<K: Debug, V: Debug> Debug for SortedMap<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_by_cached_key(|(key, _)| format!("{:?}", key));
+        f.debug_map().entries(entries).finish()
+    }
+}
@@ -4,7 +4,7 @@ use iso_currency::Currency;
 use serde::Deserialize;
 
 use crate::{
-    entities::{expense_tl, income_tl, ExpenseClassification, IncomeClassification},
+    entities::{expense_tl, income_tl, ExpenseClassification, IncomeClassification, RoundingMode},
     errors::InvalidIsoCurrencyCode,
 };
 
@@ -14,7 +14,9 @@ use super::{
         AssetClassification, EquityAccount, EquityClassification, ExpenseAccount, IncomeAccount,
         LiabilityAccount, LiabilityClassification,
     },
+    commodity::Commodity,
     decorator_logic::DecoratorLogic,
+    payee_metadata::PayeeMetadata,
     transaction::Transaction,
 };
 
@@ -51,6 +53,27 @@ pub trait AssetHandler:
     fn upon_accrual(&self) -> Option<ExpenseAccount> {
         None
     }
+
+    /// Contra-asset account credited with the accrual adjustments, so the
+    /// asset account keeps its gross cost. Defaults to the accumulated
+    /// depreciation (for property, plant and equipment) or amortization (for
+    /// intangible assets) account of the same name. If `None`, the asset
+    /// account is credited directly.
+    fn accumulated_amortization(&self) -> Option<AssetAccount> {
+        default_accumulated_amortization(self.account())
+    }
+}
+
+/// Default of `AssetHandler::accumulated_amortization`, for handlers only
+/// overriding it in some cases.
+pub fn default_accumulated_amortization(account: AssetAccount) -> Option<AssetAccount> {
+    let AssetAccount(name, classification) = account;
+    let contra = match classification {
+        AssetClassification::PropertyPlantEquipment => AssetClassification::AccumulatedDepreciation,
+        AssetClassification::IntangibleAssets => AssetClassification::AccumulatedAmortization,
+        _ => return None,
+    };
+    Some(AssetAccount(name, contra))
 }
 
 pub trait IncomeHandler:
@@ -93,6 +116,13 @@ pub trait CashHandler:
     for<'de> Deserialize<'de> + std::fmt::Debug + Clone + Send + Sync + 'static
 {
     fn account(&self) -> AssetAccount;
+
+    /// Currency the account is held in, used for balance assertions that
+    /// don't specify a commodity. If not set, it is inferred from the
+    /// account's postings.
+    fn currency(&self) -> Option<Currency> {
+        None
+    }
 }
 
 pub trait ShareholderHandler:
@@ -115,6 +145,12 @@ pub trait PayeeHandler:
     for<'de> Deserialize<'de> + std::fmt::Debug + Clone + Send + Sync + 'static
 {
     fn name(&self) -> String;
+
+    /// Declared with the payee in the journal, and used by decorators
+    /// depending on the payee's jurisdiction. None by default.
+    fn metadata(&self) -> PayeeMetadata {
+        PayeeMetadata::default()
+    }
 }
 
 pub trait ReimbursableEntityHandler:
@@ -139,22 +175,52 @@ pub trait CommodityHandler:
     }
     fn default() -> Self;
 
+    /// Commodity amounts are posted in. Defaults to the ISO currency. Override
+    /// to post in a custom commodity instead (ex. share counts, loyalty points
+    /// or crypto, see `Commodity::custom`), in which case `iso_symbol()` and
+    /// `currency()` are only used by logic requiring a currency (ex. FX).
+    fn commodity(&self) -> Result<Commodity, ServerError> {
+        Ok(self.currency()?.into())
+    }
+
+    /// Number of decimal places amounts are kept to. Defaults to the
+    /// commodity's (ex. JPY = 0, USD = 2). Override to use a different
+    /// precision, and set `PrintOptions::decimal_places` to match so the
+    /// journal displays amounts the same way.
+    fn decimal_places(&self) -> Result<u32, ServerError> {
+        Ok(self.commodity()?.decimal_places())
+    }
+
     /// Smallest value that would display as a non-zero number.
     ///
     /// For example, for USD, this would be 0.005, since this is the smallest
     /// number that rounds up to a displayable value. For KRW, this would be
     /// 0.5.
     fn precision_cutoff(&self) -> Result<f64, ServerError> {
-        let exp = self.currency()?.exponent().unwrap_or(0) as i32;
+        let exp = self.decimal_places()? as i32;
         Ok(1f64 / 10f64.powi(exp) / 2f64)
     }
+
+    /// Rounding used when splitting amounts (ex. monthly accruals, VAT). Set
+    /// `PrintOptions::rounding_mode` to match, so the journal displays amounts
+    /// the same way.
+    fn rounding_mode(&self) -> RoundingMode {
+        RoundingMode::HalfUp
+    }
+
+    /// Round to `decimal_places()` using `rounding_mode()`.
+    fn round(&self, amount: f64) -> Result<f64, ServerError> {
+        Ok(self
+            .rounding_mode()
+            .round(amount, self.decimal_places()? as i32))
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct MacroContext {
     pub description: Option<String>,
     pub amount: Option<f64>,
-    pub currency: Option<Currency>,
+    pub commodity: Option<Commodity>,
 }
 
 pub trait MacroHandler:
@@ -0,0 +1,24 @@
+use fractic_server_error::ServerError;
+
+use super::{
+    financial_records::{FinancialRecords_Intermediate1, FinancialRecords_Intermediate2},
+    handlers::Handlers,
+    process_options::ProcessOptions,
+};
+
+/// Strategy for turning decorated transaction specs into transactions and
+/// assertions. Parsing, decorators, commands and printing are shared across
+/// strategies, so alternative policies (ex. cash-basis processing for a side
+/// entity) only need to implement this step.
+pub trait IfrsLogic<H: Handlers>: Send + Sync {
+    fn process(
+        &self,
+        input: FinancialRecords_Intermediate1<H>,
+        options: &ProcessOptions,
+    ) -> Result<FinancialRecords_Intermediate2<H>, ServerError>;
+}
+
+/// Default strategy, implementing the accrual-based accounting logic of this
+/// crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardIfrsLogic;
@@ -0,0 +1,12 @@
+/// `Send + Sync`, except on wasm32, where decorators and FX providers may hold
+/// `!Send` state (ex. the `JsFuture` of a browser `fetch`), and their futures
+/// run on the browser's single-threaded event loop.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSendSync for T {}
@@ -0,0 +1,59 @@
+use iso_currency::Currency;
+
+/// Details of a payee (see `PayeeHandler::metadata`), declared with the payee
+/// in the journal, and used by decorators to pick the treatment of the
+/// payee's jurisdiction (ex. `StandardDecoratorVatKorea::by_payee_country`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayeeMetadata {
+    /// ISO 3166-1 alpha-2 country code (ex. "KR").
+    pub country: Option<String>,
+    /// Tax or business registration number (ex. 사업자등록번호).
+    pub tax_id: Option<String>,
+    /// Currency the payee usually invoices in.
+    pub default_currency: Option<Currency>,
+}
+
+impl PayeeMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_country(mut self, country: &str) -> Self {
+        self.country = Some(country.to_uppercase());
+        self
+    }
+
+    pub fn with_tax_id(mut self, tax_id: &str) -> Self {
+        self.tax_id = Some(tax_id.to_string());
+        self
+    }
+
+    pub fn with_default_currency(mut self, currency: Currency) -> Self {
+        self.default_currency = Some(currency);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Whether the payee is known to be in the given country (ex. "KR").
+    pub fn is_in(&self, country: &str) -> bool {
+        self.country
+            .as_deref()
+            .is_some_and(|c| c.eq_ignore_ascii_case(country))
+    }
+
+    /// hledger tags declared with the payee (ex. "country: KR").
+    pub(crate) fn tags(&self) -> Vec<String> {
+        [
+            self.country.as_ref().map(|c| format!("country: {}", c)),
+            self.tax_id.as_ref().map(|t| format!("tax_id: {}", t)),
+            self.default_currency
+                .map(|c| format!("default_currency: {}", c.code())),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
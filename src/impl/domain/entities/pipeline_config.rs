@@ -0,0 +1,80 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::presentation::print_options::PrintOptions;
+
+use super::process_options::ProcessOptions;
+
+/// Settings for a full processing run, normally loaded from a TOML file (see
+/// `PipelineConfig::from_toml`). Example:
+///
+/// ```toml
+/// main_currency = "USD"
+/// fiscal_year = 2024
+///
+/// [inputs]
+/// transactions = "transactions.csv"
+/// balances = "balances.csv"
+/// budgets = "budgets.csv"
+///
+/// [fx]
+/// cache_dir = ".fx-cache"
+/// api_key = "..."
+///
+/// [processing]
+/// decorator_concurrency = 8
+/// interest_inference = { max_amount = 5.0, schedule = "month_end" }
+/// discrepancy_policy = { absolute = 1.0 } # or { relative = 0.5 }, "always", "never"
+/// verify_round_trip = true
+/// preflight_assertions = true
+/// suspense_routing = true
+/// materiality_thresholds = { USD = 20.0, KRW = 25000 }
+/// backdating_guard = { policy = "warn", filing_dates = ["2024-12-31"] } # or "error"
+///
+/// [printing]
+/// group_by_month = true
+/// annotation_verbosity = "markers"
+/// rounding_mode = "half_even"
+/// decimal_places = { USD = 0 }
+///
+/// [accounts]
+/// PaymentFees = "BankCharges"
+/// ```
+///
+/// Relative paths are resolved against the config file's directory.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineConfig {
+    pub transactions_path: Option<PathBuf>,
+    pub balances_path: Option<PathBuf>,
+    /// Optional, see `util::parse_budgets_csv`.
+    pub budgets_path: Option<PathBuf>,
+    /// ISO code of the reporting currency.
+    pub main_currency: Option<String>,
+    /// Default reporting period for statements.
+    pub fiscal_year: Option<i32>,
+    pub fx_cache_dir: Option<PathBuf>,
+    pub fx_api_key: Option<String>,
+    /// Includes a `RoundTripVerifier` hook if `verify_round_trip` is set, and
+    /// a `StandardAccountOverrides` hook if any overrides are set.
+    pub process_options: ProcessOptions,
+    pub print_options: PrintOptions,
+    /// Replacement names for the standard named sub-accounts, keyed by their
+    /// default name.
+    pub account_overrides: BTreeMap<String, String>,
+}
+
+#[cfg(feature = "fx-api")]
+impl PipelineConfig {
+    /// Default FX provider from the `[fx]` section, if both the cache directory
+    /// and API key are configured.
+    pub fn fx_provider(&self) -> Option<std::sync::Arc<dyn super::fx_provider::FxProvider>> {
+        match (&self.fx_cache_dir, &self.fx_api_key) {
+            (Some(cache_dir), Some(api_key)) => Some(std::sync::Arc::new(
+                crate::ext::standard_decorators::OpenExchangeRatesFxProvider::new(
+                    cache_dir.clone(),
+                    api_key.clone(),
+                ),
+            )),
+            _ => None,
+        }
+    }
+}
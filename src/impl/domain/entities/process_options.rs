@@ -0,0 +1,150 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{Datelike as _, NaiveDate};
+
+use super::{
+    clock::{Clock, SystemClock},
+    commodity::Commodity,
+    discrepancy_policy::DiscrepancyPolicy,
+    generation_metadata::SortedMap,
+    progress::ProgressReporter,
+    spec_hook::SpecHook,
+};
+
+/// Options controlling how specs are processed into financial records.
+#[derive(Clone)]
+pub struct ProcessOptions {
+    /// Maximum number of specs decorated concurrently. Decorators may await
+    /// external lookups (ex. FX rates), so independent specs are decorated in
+    /// parallel. Output order is unaffected.
+    pub decorator_concurrency: usize,
+    /// Hooks run (in order) after each transaction spec is processed.
+    pub spec_hooks: Vec<Arc<dyn SpecHook>>,
+    /// Receives progress updates for each processing phase.
+    pub progress: Option<Arc<dyn ProgressReporter>>,
+    /// If set, small shortfalls of the ledger balance below an asserted cash
+    /// balance are booked as interest income (flagged for review) rather than
+    /// failing the assertion.
+    pub interest_inference: Option<InterestInference>,
+    /// When the discrepancy between a `VariableExpense`'s estimates and the
+    /// actual amount is recorded as a separate correction. Smaller
+    /// discrepancies are absorbed into the clearing transaction. Decorators
+    /// (ex. `StandardDecoratorCardFx`) are configured separately.
+    pub discrepancy_policy: DiscrepancyPolicy,
+    /// If set, the balance assertions are evaluated against the generated
+    /// transactions before the records are returned, failing with every
+    /// failing assertion and the specs likely responsible. Much quicker to act
+    /// on than hledger, which only reports the first failing assertion.
+    pub preflight_assertions: bool,
+    /// If set, specs failing validation for their accounting logic (ex. an
+    /// amount of the wrong sign, or a missing accrual end) are booked against
+    /// the suspense account with an annotation holding the error, rather than
+    /// aborting processing. The rest of the books can then still be generated
+    /// while the specs are corrected.
+    pub suspense_routing: bool,
+    /// Amounts (absolute, after decorators) under which `SimpleExpense` and
+    /// `FixedExpense` specs are booked as `ImmaterialExpense` instead (and
+    /// `FixedIncome` / `AccrualIncome` specs as `ImmaterialIncome`), with an
+    /// annotation noting the policy, so tiny recurring charges aren't accrued.
+    /// Commodities without a threshold are never downgraded.
+    pub materiality_thresholds: HashMap<Commodity, f64>,
+    /// If set, specs generating entries dated on or before a balance that was
+    /// already reconciled when they were recorded (a passing assertion, or a
+    /// filing date) are flagged, since backdated entries silently invalidate
+    /// those balances.
+    pub backdating_guard: Option<BackdatingGuard>,
+    /// Timestamps the generated records (see `FinancialRecords::metadata`).
+    /// Not covered by the config hash.
+    pub clock: Arc<dyn Clock>,
+}
+
+#[derive(Debug, Clone)]
+pub struct InterestInference {
+    /// Largest difference attributed to interest. Larger differences are left
+    /// to fail the assertion.
+    pub max_amount: f64,
+    /// Dates on which the bank pays interest. Differences on other dates are
+    /// left to fail the assertion.
+    pub schedule: InterestSchedule,
+}
+
+/// Specs are considered recorded on their payment date. Entries of a spec are
+/// backdated if they are dated on or before a reconciled date that precedes
+/// it: the date of a passing balance assertion on an account the entries post
+/// to, or a filing date.
+#[derive(Debug, Clone)]
+pub struct BackdatingGuard {
+    pub policy: BackdatingPolicy,
+    /// Dates up to which statements were filed (ex. fiscal year or VAT period
+    /// ends).
+    pub filing_dates: Vec<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackdatingPolicy {
+    /// Annotate the backdated specs.
+    Warn,
+    /// Fail processing, listing the backdated specs.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterestSchedule {
+    MonthEnd,
+    QuarterEnd,
+    /// Day of the month (ex. 25), or the month end for shorter months.
+    DayOfMonth(u32),
+    Any,
+}
+
+impl InterestSchedule {
+    pub fn matches(&self, date: NaiveDate) -> bool {
+        let is_month_end = date
+            .succ_opt()
+            .is_none_or(|next| next.month() != date.month());
+        match self {
+            InterestSchedule::MonthEnd => is_month_end,
+            InterestSchedule::QuarterEnd => is_month_end && date.month().is_multiple_of(3),
+            InterestSchedule::DayOfMonth(day) => {
+                date.day() == *day || (is_month_end && date.day() < *day)
+            }
+            InterestSchedule::Any => true,
+        }
+    }
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            decorator_concurrency: 8,
+            spec_hooks: Vec::new(),
+            progress: None,
+            interest_inference: None,
+            discrepancy_policy: DiscrepancyPolicy::default(),
+            preflight_assertions: false,
+            suspense_routing: false,
+            materiality_thresholds: HashMap::new(),
+            backdating_guard: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl std::fmt::Debug for ProcessOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessOptions")
+            .field("decorator_concurrency", &self.decorator_concurrency)
+            .field("spec_hooks", &self.spec_hooks.len())
+            .field("progress", &self.progress.is_some())
+            .field("interest_inference", &self.interest_inference)
+            .field("discrepancy_policy", &self.discrepancy_policy)
+            .field("preflight_assertions", &self.preflight_assertions)
+            .field("suspense_routing", &self.suspense_routing)
+            .field(
+                "materiality_thresholds",
+                &SortedMap(&self.materiality_thresholds),
+            )
+            .field("backdating_guard", &self.backdating_guard)
+            .finish()
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+/// Receives progress updates from long-running operations (processing, and
+/// statement generation), ex. to drive a progress bar in an embedding UI.
+///
+/// Implemented for any `Fn(Progress) + Send + Sync` closure. Updates are sent
+/// synchronously from the processing thread, so implementations should return
+/// quickly.
+pub trait ProgressReporter: Send + Sync {
+    fn report(&self, progress: Progress);
+}
+
+impl<F> ProgressReporter for F
+where
+    F: Fn(Progress) + Send + Sync,
+{
+    fn report(&self, progress: Progress) {
+        self(progress)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: ProgressPhase,
+    /// Number of items (specs, commands, periods, ...) completed in the current
+    /// phase.
+    pub completed: usize,
+    /// Total number of items in the current phase.
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressPhase {
+    /// Running decorators on the transaction specs.
+    Decorating,
+    /// Processing transaction specs into transactions.
+    ProcessingSpecs,
+    /// Applying commands (closing entries, corrections).
+    ApplyingCommands,
+    /// Generating notes from the annotations.
+    Annotating,
+    /// Querying hledger for a financial statement.
+    GeneratingStatement,
+}
+
+/// Counts completed items for a single phase, reporting each step to the
+/// (optional) reporter.
+pub(crate) struct PhaseProgress {
+    reporter: Option<Arc<dyn ProgressReporter>>,
+    phase: ProgressPhase,
+    completed: usize,
+    total: usize,
+}
+
+impl PhaseProgress {
+    /// Reports the start of the phase (0 of `total` completed).
+    pub(crate) fn start(
+        reporter: Option<Arc<dyn ProgressReporter>>,
+        phase: ProgressPhase,
+        total: usize,
+    ) -> Self {
+        let progress = Self {
+            reporter,
+            phase,
+            completed: 0,
+            total,
+        };
+        progress.report();
+        progress
+    }
+
+    pub(crate) fn advance(&mut self) {
+        self.completed += 1;
+        self.report();
+    }
+
+    fn report(&self) {
+        if let Some(reporter) = &self.reporter {
+            reporter.report(Progress {
+                phase: self.phase,
+                completed: self.completed,
+                total: self.total,
+            });
+        }
+    }
+}
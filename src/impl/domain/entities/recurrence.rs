@@ -0,0 +1,152 @@
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+
+/// Repeats a transaction spec on a fixed schedule, along the lines of an
+/// RFC 5545 recurrence rule. Each occurrence is expanded into its own spec
+/// when the specs are loaded, with the spec's dates shifted by the number of
+/// months since the first occurrence (which falls in the month of the spec's
+/// payment date).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: RecurrenceFrequency,
+    pub day: RecurrenceDay,
+    pub end: RecurrenceEnd,
+    /// Scheduled payment dates to skip.
+    pub exceptions: Vec<NaiveDate>,
+    pub overrides: Vec<RecurrenceOverride>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum RecurrenceFrequency {
+    Monthly,
+    Quarterly,
+    Annually,
+}
+
+/// Day of the month on which the payment of each occurrence is scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde_derive::Deserialize)]
+pub enum RecurrenceDay {
+    /// Same day of the month as the spec's payment date.
+    #[default]
+    SameDay,
+    /// Clamped to the last day of shorter months.
+    DayOfMonth(u32),
+    LastDay,
+    /// Last weekday of the month (holidays aren't taken into account).
+    LastBusinessDay,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrenceEnd {
+    /// Last date on which an occurrence can be scheduled (inclusive).
+    Until(NaiveDate),
+    /// Number of occurrences, including skipped ones.
+    Count(u32),
+}
+
+/// Changes a single occurrence, identified by its scheduled payment date.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceOverride {
+    pub date: NaiveDate,
+    pub payment_date: Option<NaiveDate>,
+    pub amount: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceOccurrence {
+    /// Position in the schedule, counting skipped occurrences, so that it stays
+    /// the same when exceptions are added.
+    pub index: u32,
+    /// Months since the first occurrence.
+    pub offset_months: u32,
+    pub scheduled_date: NaiveDate,
+    /// Listed in the exceptions.
+    pub skipped: bool,
+    /// Scheduled date, unless overridden.
+    pub payment_date: NaiveDate,
+    /// Overridden amount, if any.
+    pub amount: Option<f64>,
+}
+
+// --
+
+impl RecurrenceFrequency {
+    pub fn months(&self) -> u32 {
+        match self {
+            RecurrenceFrequency::Monthly => 1,
+            RecurrenceFrequency::Quarterly => 3,
+            RecurrenceFrequency::Annually => 12,
+        }
+    }
+}
+
+impl RecurrenceDay {
+    /// Date in the month of `month_start` (the first of a month). `anchor` is
+    /// the spec's payment date.
+    pub fn in_month(&self, month_start: NaiveDate, anchor: NaiveDate) -> NaiveDate {
+        let last_day = (month_start + Months::new(1))
+            .pred_opt()
+            .expect("date should have a predecessor");
+        let day = |d: u32| {
+            month_start
+                .with_day(d.clamp(1, last_day.day()))
+                .expect("clamped day should be valid")
+        };
+        match self {
+            RecurrenceDay::SameDay => day(anchor.day()),
+            RecurrenceDay::DayOfMonth(d) => day(*d),
+            RecurrenceDay::LastDay => last_day,
+            RecurrenceDay::LastBusinessDay => {
+                let mut date = last_day;
+                while matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+                    date = date.pred_opt().expect("date should have a predecessor");
+                }
+                date
+            }
+        }
+    }
+}
+
+impl RecurrenceRule {
+    /// Scheduled occurrences for a spec paid on `first_payment_date`, with
+    /// overrides applied. At most `max` occurrences are scheduled; `None` is
+    /// returned if the rule would produce more.
+    pub fn occurrences(
+        &self,
+        first_payment_date: NaiveDate,
+        max: u32,
+    ) -> Option<Vec<RecurrenceOccurrence>> {
+        let first_month = first_payment_date
+            .with_day(1)
+            .expect("day 1 should always be valid");
+        let mut occurrences = Vec::new();
+        for index in 0.. {
+            if let RecurrenceEnd::Count(count) = self.end {
+                if index >= count {
+                    break;
+                }
+            }
+            let offset_months = index * self.frequency.months();
+            let scheduled = self
+                .day
+                .in_month(first_month + Months::new(offset_months), first_payment_date);
+            if let RecurrenceEnd::Until(until) = self.end {
+                if scheduled > until {
+                    break;
+                }
+            }
+            if index >= max {
+                return None;
+            }
+            let r#override = self.overrides.iter().find(|o| o.date == scheduled);
+            occurrences.push(RecurrenceOccurrence {
+                index,
+                offset_months,
+                scheduled_date: scheduled,
+                skipped: self.exceptions.contains(&scheduled),
+                payment_date: r#override.and_then(|o| o.payment_date).unwrap_or(scheduled),
+                amount: r#override.and_then(|o| o.amount),
+            });
+        }
+        Some(occurrences)
+    }
+}
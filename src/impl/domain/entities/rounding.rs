@@ -0,0 +1,55 @@
+use iso_currency::Currency;
+
+/// Differences from a tie (or from a whole number, when truncating) smaller
+/// than this fraction of the smallest unit are treated as floating point
+/// error (ex. 2.675 * 100 = 267.49999999999997).
+const EPSILON: f64 = 1e-6;
+
+/// How amounts are rounded to the currency's decimal places.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Ties are rounded away from zero (ex. 2.5 to 3, -2.5 to -3).
+    #[default]
+    HalfUp,
+    /// Ties are rounded to the nearest even number (ex. 2.5 to 2, 3.5 to 4),
+    /// also known as banker's rounding.
+    HalfEven,
+    /// Rounded towards zero (ex. 2.9 to 2, -2.9 to -2).
+    Truncate,
+}
+
+impl RoundingMode {
+    pub fn round(&self, amount: f64, decimal_places: i32) -> f64 {
+        let factor = 10_f64.powi(decimal_places);
+        let scaled = amount * factor;
+        let whole = scaled.trunc();
+        let is_tie = ((scaled - whole).abs() - 0.5).abs() < EPSILON;
+        let rounded = match self {
+            RoundingMode::HalfUp if is_tie => whole + scaled.signum(),
+            RoundingMode::HalfEven if is_tie => {
+                if whole % 2.0 == 0.0 {
+                    whole
+                } else {
+                    whole + scaled.signum()
+                }
+            }
+            RoundingMode::HalfUp | RoundingMode::HalfEven => scaled.round(),
+            RoundingMode::Truncate => {
+                let nearest = scaled.round();
+                if (scaled - nearest).abs() < EPSILON {
+                    nearest
+                } else {
+                    whole
+                }
+            }
+        };
+        // Avoid negative zero.
+        rounded / factor + 0.0
+    }
+
+    /// Rounds to the standard number of decimal places for the currency (ex.
+    /// JPY = 0, USD = 2).
+    pub fn round_currency(&self, amount: f64, currency: Currency) -> f64 {
+        self.round(amount, currency.exponent().unwrap_or(0) as i32)
+    }
+}
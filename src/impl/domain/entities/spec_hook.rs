@@ -0,0 +1,91 @@
+use chrono::NaiveDate;
+use fractic_server_error::ServerError;
+
+use super::{
+    account::{Account, AssetAccount, LiabilityAccount},
+    annotation::Annotation,
+    assertion::Assertion,
+    commodity::Commodity,
+    transaction::{Transaction, TransactionLabel},
+    transaction_spec::TransactionSpecId,
+};
+
+/// Invoked after each transaction spec is processed, before its outcome is
+/// applied to the records. Can be used to log, veto (by returning an error), or
+/// enrich (ex. attach external document IDs as custom tags) the results.
+pub trait SpecHook: Send + Sync {
+    fn after_spec(&self, outcome: &mut SpecOutcome) -> Result<(), ServerError>;
+}
+
+/// The result of processing a single transaction spec.
+#[derive(Debug)]
+pub struct SpecOutcome {
+    pub spec_id: TransactionSpecId,
+    pub payment_date: NaiveDate,
+    pub label: TransactionLabel,
+    pub transactions: Vec<Transaction>,
+    /// Transactions added by decorators.
+    pub ext_transactions: Vec<Transaction>,
+    /// Assertions added by decorators.
+    pub ext_assertions: Vec<Assertion>,
+    pub annotations: Vec<Annotation>,
+    /// Read-only summary of how this spec affects the tracking state used by
+    /// later specs. Changes made to this field are ignored.
+    pub state_changes: Vec<SpecStateChange>,
+    /// Read-only summary of the spec (after decorators). Changes made to this
+    /// field are ignored.
+    pub spec: SpecSummary,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpecSummary {
+    /// Name of the accounting logic (ex. "SimpleExpense").
+    pub accounting_logic: &'static str,
+    pub amount: f64,
+    pub commodity: Commodity,
+    pub backing_account: Account,
+    /// Account the full amount is eventually booked to, for logics with a
+    /// single such account (ex. the expense account of a `SimpleExpense`, or
+    /// the asset account of a `Capitalize`).
+    pub target_account: Option<Account>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SpecStateChange {
+    /// New unreimbursed entries were recorded against the account.
+    UnreimbursedEntriesAdded {
+        account: LiabilityAccount,
+        total_amount: f64,
+    },
+    /// Unreimbursed entries were cleared from the account.
+    UnreimbursedEntriesCleared {
+        account: LiabilityAccount,
+        amount: f64,
+    },
+    /// An invoice was issued, and is outstanding on the receivable account.
+    InvoiceIssued { account: AssetAccount, amount: f64 },
+    /// Outstanding invoices on the receivable account were (partly) collected.
+    InvoiceCollected { account: AssetAccount, amount: f64 },
+    /// Units of the inventory item were purchased, at the given cost.
+    InventoryPurchased {
+        account: AssetAccount,
+        quantity: f64,
+        cost: f64,
+    },
+    /// Units of the inventory item were sold, at the given cost.
+    InventorySold {
+        account: AssetAccount,
+        quantity: f64,
+        cost: f64,
+    },
+    /// Treasury shares were bought back, at the given cost.
+    TreasurySharesBoughtBack { shares: f64, cost: f64 },
+    /// Treasury shares were reissued, carried at the given cost.
+    TreasurySharesReissued { shares: f64, cost: f64 },
+    /// A security deposit was paid into the account.
+    DepositPaid { account: AssetAccount, amount: f64 },
+    /// Open security deposits with the payee were (partly) refunded.
+    DepositRefunded { amount: f64 },
+    /// A variable expense price record was added, to be used for estimates.
+    ExpenseHistoryRecorded { is_init: bool },
+}
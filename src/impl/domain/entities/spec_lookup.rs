@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use super::transaction_spec::TransactionSpecId;
+
+/// Per-spec values (ex. labels, annotations), consulted for every transaction
+/// when printing. Values are stored densely in the order specs were processed
+/// (the spec ordinal), and spec IDs are resolved to their ordinal by direct
+/// indexing, since spec IDs are mostly CSV line numbers. Other IDs (ex.
+/// recurring occurrences, generated entries) fall back to a hash map.
+///
+/// Mirrors the subset of the `HashMap` API used for lookups.
+#[derive(Debug, Clone)]
+pub struct SpecLookup<T> {
+    entries: Vec<(TransactionSpecId, T)>,
+    /// Ordinal + 1 of the entry for each spec ID (0 if none).
+    dense: Vec<u32>,
+    /// Ordinals of spec IDs too large to be indexed directly.
+    sparse: HashMap<TransactionSpecId, u32>,
+}
+
+/// Spec IDs at most this many times the number of entries (plus some slack)
+/// are indexed directly, which keeps the index compact even if a few IDs are
+/// far apart.
+const DENSE_FACTOR: usize = 4;
+const DENSE_SLACK: usize = 1024;
+
+impl<T> SpecLookup<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            dense: Vec::with_capacity(capacity + 1),
+            sparse: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, spec_id: &TransactionSpecId) -> bool {
+        self.ordinal(spec_id).is_some()
+    }
+
+    pub fn get(&self, spec_id: &TransactionSpecId) -> Option<&T> {
+        self.ordinal(spec_id).map(|i| &self.entries[i].1)
+    }
+
+    pub fn get_mut(&mut self, spec_id: &TransactionSpecId) -> Option<&mut T> {
+        self.ordinal(spec_id).map(|i| &mut self.entries[i].1)
+    }
+
+    /// Returns the previous value, if any. New specs are appended in ordinal
+    /// order.
+    pub fn insert(&mut self, spec_id: TransactionSpecId, value: T) -> Option<T> {
+        if let Some(i) = self.ordinal(&spec_id) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+        let ordinal = self.entries.len() as u32;
+        self.entries.push((spec_id, value));
+        let id = spec_id.0 as usize;
+        if id
+            < self
+                .dense
+                .len()
+                .max(DENSE_FACTOR * self.entries.len() + DENSE_SLACK)
+        {
+            if id >= self.dense.len() {
+                self.dense.resize(id + 1, 0);
+            }
+            self.dense[id] = ordinal + 1;
+        } else {
+            self.sparse.insert(spec_id, ordinal);
+        }
+        None
+    }
+
+    /// Value for the spec, inserting the default value if there is none.
+    pub fn get_or_insert_default(&mut self, spec_id: TransactionSpecId) -> &mut T
+    where
+        T: Default,
+    {
+        let i = match self.ordinal(&spec_id) {
+            Some(i) => i,
+            None => {
+                self.insert(spec_id, T::default());
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[i].1
+    }
+
+    /// Entries in spec ordinal order.
+    pub fn iter(&self) -> impl Iterator<Item = (&TransactionSpecId, &T)> {
+        self.entries.iter().map(|(spec_id, value)| (spec_id, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &TransactionSpecId> {
+        self.entries.iter().map(|(spec_id, _)| spec_id)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    fn ordinal(&self, spec_id: &TransactionSpecId) -> Option<usize> {
+        match self.dense.get(spec_id.0 as usize) {
+            Some(slot) if *slot > 0 => Some(*slot as usize - 1),
+            // May have been inserted before the index grew to cover it.
+            _ => self.sparse.get(spec_id).map(|i| *i as usize),
+        }
+    }
+}
+
+impl<T> Default for SpecLookup<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<(TransactionSpecId, T)> for SpecLookup<T> {
+    fn from_iter<I: IntoIterator<Item = (TransactionSpecId, T)>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut lookup = Self::with_capacity(iter.size_hint().0);
+        for (spec_id, value) in iter {
+            lookup.insert(spec_id, value);
+        }
+        lookup
+    }
+}
+
+impl<T> Extend<(TransactionSpecId, T)> for SpecLookup<T> {
+    fn extend<I: IntoIterator<Item = (TransactionSpecId, T)>>(&mut self, iter: I) {
+        for (spec_id, value) in iter {
+            self.insert(spec_id, value);
+        }
+    }
+}
+
+impl<T> IntoIterator for SpecLookup<T> {
+    type Item = (TransactionSpecId, T);
+    type IntoIter = std::vec::IntoIter<(TransactionSpecId, T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SpecLookup<T> {
+    type Item = (&'a TransactionSpecId, &'a T);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (TransactionSpecId, T)>,
+        fn(&'a (TransactionSpecId, T)) -> (&'a TransactionSpecId, &'a T),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(spec_id, value)| (spec_id, value))
+    }
+}
@@ -1,13 +1,15 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
+use super::{account::Account, commodity::Commodity, transaction_spec::TransactionSpecId};
 use chrono::NaiveDate;
-use iso_currency::Currency;
 
-use super::{account::Account, transaction_spec::TransactionSpecId};
+/// Posting tag holding the counterparty of an intercompany transaction.
+pub const INTERCOMPANY_TAG: &str = "intercompany";
 
 #[derive(Debug, Clone)]
 pub struct TransactionLabel {
-    pub payee: String,
+    /// Shared between transactions with the same payee.
+    pub payee: Arc<str>,
     pub description: String,
 }
 
@@ -18,8 +20,25 @@ pub struct TransactionPosting {
     /// "tracing through" the posting for proper cashflow tagging).
     pub source_account: Option<Account>,
     pub amount: f64,
-    pub currency: Currency,
+    pub commodity: Commodity,
     pub custom_tags: HashMap<String, String>,
+    /// Free-text note explaining the posting (ex. "issuer FX fee"), printed
+    /// in the posting's comment before its tags. Shouldn't contain ':', since
+    /// hledger would read the preceding word as a tag.
+    pub comment: Option<String>,
+    /// Conversion of the amount into another commodity (ex. for FX or
+    /// investment postings), printed as the posting's cost. The transaction
+    /// must then balance in the price's commodity.
+    pub price: Option<PostingPrice>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostingPrice {
+    /// Price per unit of the posting's commodity (printed as `@ rate`).
+    Unit { rate: f64, commodity: Commodity },
+    /// Total cost of the posting's amount (printed as `@@ total`), as a
+    /// positive number.
+    Total { amount: f64, commodity: Commodity },
 }
 
 #[derive(Debug, Clone)]
@@ -27,19 +46,37 @@ pub struct Transaction {
     pub spec_id: TransactionSpecId,
     pub date: NaiveDate,
     pub postings: Vec<TransactionPosting>,
-    pub comment: Option<String>,
+    /// Shared between transactions with the same comment.
+    pub comment: Option<Arc<str>>,
+    /// Printed as hledger's status mark. Set to the status of the originating
+    /// spec, unless set explicitly (ex. by a decorator).
+    pub status: TransactionStatus,
+}
+
+/// Review status of a transaction (ex. to tell imported entries still to be
+/// reviewed from confirmed ones).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TransactionStatus {
+    #[default]
+    Unmarked,
+    /// Printed as `!`.
+    Pending,
+    /// Printed as `*`.
+    Cleared,
 }
 
 // --
 
 impl TransactionPosting {
-    pub fn new(account: Account, amount: f64, currency: Currency) -> Self {
+    pub fn new(account: Account, amount: f64, commodity: impl Into<Commodity>) -> Self {
         Self {
             account,
             source_account: None,
             amount,
-            currency,
+            commodity: commodity.into(),
             custom_tags: HashMap::new(),
+            comment: None,
+            price: None,
         }
     }
 
@@ -47,26 +84,61 @@ impl TransactionPosting {
         account: Account,
         source_account: Account,
         amount: f64,
-        currency: Currency,
+        commodity: impl Into<Commodity>,
     ) -> Self {
         Self {
             account,
             source_account: Some(source_account),
             amount,
-            currency,
+            commodity: commodity.into(),
             custom_tags: HashMap::new(),
+            comment: None,
+            price: None,
         }
     }
 
-    pub fn non_cash_reclassification(account: Account, amount: f64, currency: Currency) -> Self {
+    pub fn non_cash_reclassification(
+        account: Account,
+        amount: f64,
+        commodity: impl Into<Commodity>,
+    ) -> Self {
         Self {
             account,
             source_account: None,
             amount,
-            currency,
+            commodity: commodity.into(),
             custom_tags: vec![("s".to_string(), "non_cash_reclassification".to_string())]
                 .into_iter()
                 .collect(),
+            comment: None,
+            price: None,
+        }
+    }
+
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    pub fn with_tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.custom_tags.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_price(mut self, price: PostingPrice) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Amount (and commodity) the posting contributes when balancing the
+    /// transaction: its cost if it has a price, otherwise its amount.
+    pub fn cost(&self) -> (f64, Commodity) {
+        match self.price {
+            Some(PostingPrice::Unit { rate, commodity }) => (self.amount * rate, commodity),
+            Some(PostingPrice::Total { amount, commodity }) => {
+                (amount.abs().copysign(self.amount), commodity)
+            }
+            None => (self.amount, self.commodity),
         }
     }
 }
@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
+
 use chrono::NaiveDate;
 
-use crate::entities::{Annotation, Assertion, Transaction};
+use crate::entities::{Annotation, Assertion, RecurrenceRule, Transaction, TransactionStatus};
 
 use super::{
     account::Account,
@@ -10,19 +12,70 @@ use super::{
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TransactionSpecId(pub(crate) u64);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum CommonStockWhileUnpaid {
     ReceivableAsset,
     NegativeEquity,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ShareIssuanceCostBookTo {
     RetainedEarnings,
     DiscountOnStockIssuance,
 }
 
-#[derive(Debug)]
+/// Where fair value changes of an investment are recognized (IFRS 9).
+#[derive(Debug, Clone)]
+pub enum FairValueGainsTo {
+    /// Fair value through profit or loss (FVTPL).
+    ProfitOrLoss,
+    /// Fair value through other comprehensive income (FVOCI), accumulated in
+    /// equity.
+    OtherComprehensiveIncome,
+}
+
+/// How the depreciable amount (cost less residual value) of an asset is
+/// allocated over its useful life.
+#[derive(Debug, Clone)]
+pub enum DepreciationMethod {
+    /// Evenly, by day.
+    StraightLine,
+    /// The given annual rate (ex. 0.4 for 40%) of the carrying amount, by day.
+    /// Whatever remains above the residual value is depreciated in the last
+    /// month of the useful life.
+    DecliningBalance { rate: f64 },
+    /// In proportion to the units produced each month of the useful life (in
+    /// order, from the accrual start), out of the total units the asset is
+    /// expected to produce. Months without units aren't depreciated.
+    UnitsOfProduction {
+        total_units: f64,
+        monthly_units: Vec<f64>,
+    },
+}
+
+/// How the cost of inventory sold is measured, out of the units purchased at
+/// different costs. The formula is chosen by the purchases of an item, which
+/// must all use the same one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryCosting {
+    /// Units purchased first are sold first.
+    Fifo,
+    /// At the average cost of the units in stock at the time of the sale.
+    WeightedAverage,
+}
+
+/// How the principal of a borrowing is repaid over its term.
+#[derive(Debug, Clone)]
+pub enum RepaymentSchedule {
+    /// Equal monthly installments of principal and interest. Whatever
+    /// principal remains is repaid with the last installment.
+    Annuity,
+    /// Equal monthly principal repayments, plus the interest accrued on the
+    /// outstanding principal.
+    StraightPrincipal,
+}
+
+#[derive(Debug, Clone)]
 pub enum AccountingLogic<E, A, I, R, S> {
     CommonStock {
         subscriber: S,
@@ -31,9 +84,47 @@ pub enum AccountingLogic<E, A, I, R, S> {
     ShareIssuanceCost {
         book_to: ShareIssuanceCostBookTo,
     },
+    /// Buys back the number of the company's own shares, paying the amount on
+    /// the payment date. Treasury shares are held at cost, deducted from
+    /// equity (IAS 32).
+    ShareBuyback {
+        shares: f64,
+    },
+    /// Reissues the number of treasury shares for the amount, received on the
+    /// payment date. The shares are carried at the weighted average cost of
+    /// those held, and no gain or loss is recognized in profit or loss: the
+    /// difference with the proceeds goes to contributed surplus, and losses
+    /// beyond the surplus from previous reissues to retained earnings.
+    ReissueTreasuryShares {
+        shares: f64,
+    },
     SimpleExpense(E),
     Capitalize(A),
     Amortize(A),
+    /// Capitalizes the asset like `Capitalize`, then depreciates it monthly
+    /// from the accrual start to the accrual end (its useful life), down to the
+    /// residual value. Adjustments are credited to the asset's accumulated
+    /// depreciation account (see `AssetHandler::accumulated_amortization`).
+    Depreciate {
+        asset: A,
+        method: DepreciationMethod,
+        residual_value: f64,
+    },
+    /// Disposes of the asset on the payment date, for the proceeds given as
+    /// the amount (0 if scrapped) received into the backing account. The
+    /// asset is derecognized with its accumulated depreciation / amortization,
+    /// and the difference between the proceeds and its carrying amount is
+    /// recognized as a gain or loss on sale. The asset account is expected to
+    /// hold only this asset.
+    DisposeAsset(A),
+    /// Writes a non-current asset down to its recoverable amount (given as the
+    /// amount) on the payment date, if its carrying amount is higher. The
+    /// impairment loss is credited to the asset's accumulated depreciation /
+    /// amortization account (see `AssetHandler::accumulated_amortization`).
+    /// No cash moves, so the backing account is ignored. Depreciation isn't
+    /// recalculated for the impairment, so the asset can't have entries after
+    /// the impairment date (ex. a `Depreciate` schedule running past it).
+    Impair(A),
     FixedExpense(E),
     VariableExpense(E),
     VariableExpenseInit {
@@ -42,8 +133,133 @@ pub enum AccountingLogic<E, A, I, R, S> {
     },
     ImmaterialIncome(I),
     ImmaterialExpense(E),
+    /// Income earned evenly over the accrual period, recognized monthly like
+    /// `FixedExpense`. The part received in advance is deferred (deferred
+    /// revenue), and the part earned before payment is accrued as receivable.
+    FixedIncome(I),
+    /// Income earned at once on the accrual date (ex. on delivery). Deferred
+    /// until then if received in advance, or accrued as receivable until
+    /// received.
+    AccrualIncome(I),
+    /// Interest on a deposit (the backing account) earned evenly over the
+    /// accrual period, accrued monthly as bank interest receivable, and
+    /// cleared when credited on the payment date (on or after the accrual
+    /// end).
+    InterestIncome,
+    /// Pays a security deposit to the payee (ex. a landlord), held as a
+    /// deposit until refunded (see `RefundDeposit`). The accrual end is the
+    /// date the deposit is expected to be refunded: within 12 months of the
+    /// payment, it's a short-term deposit, otherwise (or if not set) a
+    /// long-term one, reclassified as short-term at the last year end within
+    /// 12 months of the refund date.
+    PayDeposit,
+    /// Refunds the amount against the payee's open deposits (oldest first).
+    /// Refunding more than is open is an error. Deposits not fully refunded
+    /// remain open (ex. to be refunded later, or written off).
+    RefundDeposit,
+    /// Issues an invoice on the payment date (the issue date), booking the
+    /// amount as income receivable from the payee until collected (see
+    /// `CollectInvoice`). No cash moves, so the backing account is ignored.
+    Invoice(I),
+    /// Collects the amount, in one or more installments, against the payee's
+    /// outstanding invoices (oldest first) for the same income. Collecting
+    /// more than is outstanding is an error.
+    CollectInvoice(I),
+    /// Adjusts the allowance for doubtful debts on the income's receivable
+    /// (see `IncomeHandler::while_receivable`) on the payment date. A negative
+    /// amount provides for expected credit losses (expensed as bad debts), and
+    /// a positive amount reverses part of the allowance (ex. once a doubtful
+    /// receivable is collected). No cash moves, so the backing account is
+    /// ignored.
+    ProvisionForDoubtfulDebt(I),
+    /// Writes off the amount (negative) of the income's receivable as
+    /// uncollectible on the payment date, against the allowance for doubtful
+    /// debts first, and as bad debts for any excess. No cash moves, so the
+    /// backing account is ignored.
+    WriteOffReceivable(I),
+    /// Purchases the quantity of the inventory item (an `Inventory` asset),
+    /// at a cost of the amount (negative). The units are tracked at cost until
+    /// sold (see `RecognizeCogs`), which is measured with the costing formula.
+    /// All purchases of the item must use the same formula.
+    PurchaseInventory {
+        item: A,
+        quantity: f64,
+        costing: InventoryCosting,
+    },
+    /// Sells the quantity of the inventory item on the payment date (the sale
+    /// date), expensing its cost (measured with the costing formula of its
+    /// purchases) as cost of goods sold, to the item's `upon_accrual` account
+    /// if any. The sale that empties the stock expenses all of its remaining
+    /// cost. The amount is ignored, and no cash moves (the sale itself is
+    /// booked as income).
+    RecognizeCogs {
+        item: A,
+        quantity: f64,
+    },
+    /// Government grant received on the payment date (IAS 20), recognized in
+    /// profit or loss by month over the accrual period (the period of the
+    /// costs it compensates). Without an asset, the grant is presented as
+    /// deferred income, released to grant income. With an asset (a grant
+    /// towards its purchase), the grant is deducted from its carrying amount,
+    /// released against its depreciation expense (so the accrual period should
+    /// be its useful life). Releases before the grant is received are accrued
+    /// as receivable.
+    Grant {
+        asset: Option<A>,
+    },
+    /// Estimated income tax for the fiscal year (calendar year), accrued at
+    /// its year end as current tax expense, into the payable of the year. The
+    /// payment date must be the year end, so the accrual is processed before
+    /// the settlement.
+    IncomeTax {
+        fiscal_year: i32,
+    },
+    /// Payment of the income tax for the fiscal year, after its year end.
+    /// Clears the payable accrued by `IncomeTax` for the year, and books the
+    /// difference with the estimate as a true-up (a change in estimate, so
+    /// recognized in the period of the payment). Each fiscal year is settled
+    /// by a single payment: split or instalment payments aren't supported, so
+    /// a payment for a year with no accrued payable left is an error.
+    SettleIncomeTax {
+        fiscal_year: i32,
+    },
+    /// Transfers the amount to (negative) or from (positive) another entity
+    /// of the group, named by the counterparty. Any balance due in the other
+    /// direction is settled first, and the rest is recorded as due from (an
+    /// intercompany receivable) or due to (an intercompany payable) the
+    /// counterparty. The due from/due to postings (but not the backing
+    /// account's) are tagged with the counterparty (see `INTERCOMPANY_TAG`),
+    /// so they can be eliminated on consolidation (see
+    /// `IntercompanyElimination`).
+    IntercompanyTransfer {
+        counterparty: String,
+    },
     Reimburse(R),
     ReimbursePartial(R),
+    /// Pays a corporate credit card statement. The card is a reimbursable
+    /// entity, used as the backing account of its purchases. The accrual end
+    /// is the statement closing date: purchases up to then are settled oldest
+    /// first, and any excess over them is booked as card interest and fees.
+    /// Paying less than the statement balance leaves the rest revolving.
+    PayCardStatement(R),
+    /// Remeasures an investment (`ShortTermInvestments` or
+    /// `LongTermInvestments` asset) to its fair value at the payment date,
+    /// given as the amount. No cash moves, so the backing account is ignored.
+    RemeasureInvestment {
+        investment: A,
+        gains_to: FairValueGainsTo,
+    },
+    /// Draws down a loan of the amount into the backing account on the payment
+    /// date, repaid from it in monthly installments (on each month end, and on
+    /// the accrual end for the last one) until the accrual end. Interest at
+    /// the annual rate (ex. 0.05 for 5%) accrues by day on the outstanding
+    /// principal from the accrual start, and is paid with each installment.
+    /// Principal due within 12 months is presented as current debt, and is
+    /// reclassified from non-current debt at each year end.
+    Borrowing {
+        annual_rate: f64,
+        repayment: RepaymentSchedule,
+    },
     ClearVat {
         from: NaiveDate,
         to: NaiveDate,
@@ -58,7 +274,7 @@ pub enum BackingAccount<R, C, S> {
 }
 
 #[derive(Debug)]
-pub(crate) struct TransactionSpec<H: Handlers> {
+pub struct TransactionSpec<H: Handlers> {
     pub id: TransactionSpecId,
     pub accrual_start: NaiveDate,
     pub accrual_end: Option<NaiveDate>,
@@ -71,6 +287,36 @@ pub(crate) struct TransactionSpec<H: Handlers> {
     pub commodity: H::M,
     pub backing_account: BackingAccount<H::R, H::C, H::S>,
     pub annotations: Vec<Annotation>,
+    /// Group entity on the other side of an intercompany transaction. The
+    /// generated postings are tagged with it (see `INTERCOMPANY_TAG`), except
+    /// for `IntercompanyTransfer`, which only tags its intercompany balances.
+    pub intercompany: Option<String>,
+    /// Reporting dimensions (ex. `project`, `department`) by name. The
+    /// generated postings are tagged with them, so reports can be filtered by
+    /// dimension (see `ext::reports::filter_by_dimension`).
+    pub dimensions: BTreeMap<String, String>,
+    /// Group entity whose books the spec belongs to, when several entities
+    /// share one spec input (see `util::process_by_entity`).
+    pub entity: Option<String>,
+    /// Repeats the spec. Recurring specs are expanded into one spec per
+    /// occurrence when loaded (see `TransactionSpecId::occurrence`), so this
+    /// is always `None` by the time specs are processed.
+    pub recurrence: Option<RecurrenceRule>,
+    /// Status of the generated transactions (see `Transaction::status`).
+    pub status: TransactionStatus,
+    /// Previously processed spec this spec corrects (see `Amendment`).
+    pub amends: Option<Amendment>,
+}
+
+/// Correction of an earlier spec (ex. found to be wrong after its period was
+/// closed): the entries of the amended spec are kept, but reversed as of the
+/// amendment date, and the amending spec's own entries are booked as usual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amendment {
+    pub spec_id: TransactionSpecId,
+    /// Date of the reversing entries (or the date of the reversed entry, if
+    /// later).
+    pub date: NaiveDate,
 }
 
 #[derive(Debug)]
@@ -86,6 +332,9 @@ pub struct DecoratedTransactionSpec<H: Handlers> {
     pub commodity: H::M,
     pub backing_account: BackingAccount<H::R, H::C, H::S>,
     pub annotations: Vec<Annotation>,
+    pub intercompany: Option<String>,
+    pub dimensions: BTreeMap<String, String>,
+    pub status: TransactionStatus,
     pub ext_transactions: Vec<Transaction>,
     pub ext_assertions: Vec<Assertion>,
     pub ext_raw: Vec<String>,
@@ -93,12 +342,76 @@ pub struct DecoratedTransactionSpec<H: Handlers> {
 
 // --
 
+/// IDs of specs expanded from a recurring spec start here, above any line
+/// number of the spec input.
+const OCCURRENCE_ID_OFFSET: u64 = 1_000_000_000;
+
+/// Maximum number of occurrences of a recurring spec.
+pub(crate) const MAX_OCCURRENCES: u32 = 1_000;
+
+impl TransactionSpecId {
+    pub fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// ID of the `index`-th occurrence of a recurring spec, stable as long as
+    /// the spec stays on the same line. Ex. the 4th occurrence (index 3) of
+    /// the spec on line 12 is `1000012003`.
+    pub fn occurrence(&self, index: u32) -> Self {
+        Self(OCCURRENCE_ID_OFFSET + self.0 * MAX_OCCURRENCES as u64 + index as u64)
+    }
+}
+
 impl std::fmt::Display for TransactionSpecId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
     }
 }
 
+impl<E, A, I, R, S> AccountingLogic<E, A, I, R, S> {
+    /// Name of the logic, as written in the spec input (ex. "SimpleExpense").
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccountingLogic::CommonStock { .. } => "CommonStock",
+            AccountingLogic::ShareIssuanceCost { .. } => "ShareIssuanceCost",
+            AccountingLogic::ShareBuyback { .. } => "ShareBuyback",
+            AccountingLogic::ReissueTreasuryShares { .. } => "ReissueTreasuryShares",
+            AccountingLogic::SimpleExpense(_) => "SimpleExpense",
+            AccountingLogic::Capitalize(_) => "Capitalize",
+            AccountingLogic::Amortize(_) => "Amortize",
+            AccountingLogic::Depreciate { .. } => "Depreciate",
+            AccountingLogic::DisposeAsset(_) => "DisposeAsset",
+            AccountingLogic::Impair(_) => "Impair",
+            AccountingLogic::FixedExpense(_) => "FixedExpense",
+            AccountingLogic::VariableExpense(_) => "VariableExpense",
+            AccountingLogic::VariableExpenseInit { .. } => "VariableExpenseInit",
+            AccountingLogic::ImmaterialIncome(_) => "ImmaterialIncome",
+            AccountingLogic::ImmaterialExpense(_) => "ImmaterialExpense",
+            AccountingLogic::FixedIncome(_) => "FixedIncome",
+            AccountingLogic::AccrualIncome(_) => "AccrualIncome",
+            AccountingLogic::InterestIncome => "InterestIncome",
+            AccountingLogic::PayDeposit => "PayDeposit",
+            AccountingLogic::RefundDeposit => "RefundDeposit",
+            AccountingLogic::Invoice(_) => "Invoice",
+            AccountingLogic::CollectInvoice(_) => "CollectInvoice",
+            AccountingLogic::ProvisionForDoubtfulDebt(_) => "ProvisionForDoubtfulDebt",
+            AccountingLogic::WriteOffReceivable(_) => "WriteOffReceivable",
+            AccountingLogic::PurchaseInventory { .. } => "PurchaseInventory",
+            AccountingLogic::RecognizeCogs { .. } => "RecognizeCogs",
+            AccountingLogic::Grant { .. } => "Grant",
+            AccountingLogic::IncomeTax { .. } => "IncomeTax",
+            AccountingLogic::SettleIncomeTax { .. } => "SettleIncomeTax",
+            AccountingLogic::IntercompanyTransfer { .. } => "IntercompanyTransfer",
+            AccountingLogic::Reimburse(_) => "Reimburse",
+            AccountingLogic::ReimbursePartial(_) => "ReimbursePartial",
+            AccountingLogic::PayCardStatement(_) => "PayCardStatement",
+            AccountingLogic::RemeasureInvestment { .. } => "RemeasureInvestment",
+            AccountingLogic::Borrowing { .. } => "Borrowing",
+            AccountingLogic::ClearVat { .. } => "ClearVat",
+        }
+    }
+}
+
 impl<R, C, S> BackingAccount<R, C, S>
 where
     R: ReimbursableEntityHandler,
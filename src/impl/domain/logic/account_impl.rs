@@ -34,7 +34,9 @@ impl Account {
                 | AssetClassification::ShortTermInvestments
                 | AssetClassification::ShortTermDeposits
                 | AssetClassification::OtherCurrentAssets
-                | AssetClassification::DeferredIncomeTax => None,
+                | AssetClassification::DeferredIncomeTax
+                | AssetClassification::AccumulatedDepreciation
+                | AssetClassification::AccumulatedAmortization => None,
 
                 AssetClassification::PropertyPlantEquipment => match direction.into() {
                     Direction::Inflow => Some(CashflowTracingTag::CashInflowPpe),
@@ -61,14 +63,17 @@ impl Account {
                 LiabilityClassification::AccountsPayable
                 | LiabilityClassification::AccruedExpenses
                 | LiabilityClassification::DeferredRevenue
-                | LiabilityClassification::ShortTermDebt
                 | LiabilityClassification::OtherCurrentLiabilities
                 | LiabilityClassification::DeferredIncomeTax => None,
 
-                LiabilityClassification::LongTermDebt => match direction.into() {
-                    Direction::Inflow => Some(CashflowTracingTag::CashInflowBorrowings),
-                    Direction::Outflow => Some(CashflowTracingTag::CashOutflowBorrowings),
-                },
+                // Short-term borrowings are financing activities too (IAS 7),
+                // and reclassifications between the two have no cash effect.
+                LiabilityClassification::ShortTermDebt | LiabilityClassification::LongTermDebt => {
+                    match direction.into() {
+                        Direction::Inflow => Some(CashflowTracingTag::CashInflowBorrowings),
+                        Direction::Outflow => Some(CashflowTracingTag::CashOutflowBorrowings),
+                    }
+                }
                 LiabilityClassification::OtherNonCurrentLiabilities => {
                     Some(CashflowTracingTag::CashInOutflowOtherFinancing)
                 }
@@ -141,13 +146,15 @@ impl Account {
                     Direction::Outflow => None,
                 },
                 EquityClassification::TreasuryStock => match direction.into() {
-                    Direction::Inflow => None,
+                    // Proceeds of reissued treasury shares.
+                    Direction::Inflow => Some(CashflowTracingTag::CashInflowIssuanceShares),
                     Direction::Outflow => Some(CashflowTracingTag::CashOutflowShareBuybacks),
                 },
                 EquityClassification::DiscountOnStockIssuance => match direction.into() {
                     Direction::Inflow => None,
                     Direction::Outflow => Some(CashflowTracingTag::CashOutflowShareIssuanceCosts),
                 },
+                EquityClassification::AccumulatedOtherComprehensiveIncome => None,
                 EquityClassification::RetainedEarnings => None,
                 EquityClassification::RetainedEarningsOpt {
                     on_inflow,
@@ -160,3 +167,136 @@ impl Account {
         }
     }
 }
+
+impl Account {
+    /// One top-level (unnamed) account per classification, covering the full
+    /// chart of accounts.
+    pub(crate) fn all_top_level() -> Vec<Account> {
+        use AssetClassification as A;
+        use EquityClassification as Q;
+        use ExpenseClassification as X;
+        use IncomeClassification as I;
+        use LiabilityClassification as L;
+
+        let assets = [
+            A::CashAndCashEquivalents,
+            A::AccountsReceivable,
+            A::Inventory,
+            A::PrepaidExpenses,
+            A::ShortTermInvestments,
+            A::ShortTermDeposits,
+            A::OtherCurrentAssets,
+            A::PropertyPlantEquipment,
+            A::IntangibleAssets,
+            A::LongTermInvestments,
+            A::LongTermDeposits,
+            A::DeferredIncomeTax,
+            A::OtherNonCurrentAssets,
+            A::AccumulatedDepreciation,
+            A::AccumulatedAmortization,
+        ];
+        let liabilities = [
+            L::AccountsPayable,
+            L::AccruedExpenses,
+            L::DeferredRevenue,
+            L::ShortTermDebt,
+            L::OtherCurrentLiabilities,
+            L::LongTermDebt,
+            L::DeferredIncomeTax,
+            L::OtherNonCurrentLiabilities,
+        ];
+        let income = [
+            I::SalesRevenue,
+            I::ServiceRevenue,
+            I::InterestIncome,
+            I::DividendIncome,
+            I::RentalIncome,
+            I::NonCoreInterestIncome,
+            I::NonCoreDividendIncome,
+            I::NonCoreRentalIncome,
+            I::RealizedFxGain,
+            I::OtherNonCoreFinancialIncome,
+            I::GainOnSaleOfAssets,
+            I::VatRefundGain,
+            I::OtherNonOperatingIncome,
+        ];
+        let expenses = [
+            X::CostOfGoodsSold,
+            X::SellingExpenses,
+            X::GeneralAdministrativeExpenses,
+            X::ResearchAndDevelopmentExpenses,
+            X::CloudServicesExpenses,
+            X::DepreciationExpense,
+            X::AmortizationExpense,
+            X::InterestExpense,
+            X::IncomeTaxExpense,
+            X::OtherTaxExpense,
+            X::LossOnSaleOfAssets,
+            X::VatRefundLoss,
+            X::NonCoreInterestExpense,
+            X::RealizedFxLoss,
+            X::OtherNonCoreFinancialExpense,
+            X::OtherNonOperatingCashExpense,
+            X::OtherNonOperatingNonCashExpense,
+        ];
+        let equity = [
+            Q::CommonStock,
+            Q::PreferredStock,
+            Q::UnpaidShareCapital,
+            Q::SharePremium,
+            Q::ContributedSurplus,
+            Q::TreasuryStock,
+            Q::DiscountOnStockIssuance,
+            Q::AccumulatedOtherComprehensiveIncome,
+            Q::RetainedEarnings,
+        ];
+
+        assets
+            .into_iter()
+            .map(|c| Account::Asset(AssetAccount(None, c)))
+            .chain(
+                liabilities
+                    .into_iter()
+                    .map(|c| Account::Liability(LiabilityAccount(None, c))),
+            )
+            .chain(
+                income
+                    .into_iter()
+                    .map(|c| Account::Income(IncomeAccount(None, c))),
+            )
+            .chain(
+                expenses
+                    .into_iter()
+                    .map(|c| Account::Expense(ExpenseAccount(None, c))),
+            )
+            .chain(
+                equity
+                    .into_iter()
+                    .map(|c| Account::Equity(EquityAccount(None, c))),
+            )
+            .collect()
+    }
+}
+
+impl Account {
+    /// Sub-account name (`None` for top-level accounts).
+    pub(crate) fn name(&self) -> Option<&str> {
+        match self {
+            Account::Asset(a) => a.0.as_deref(),
+            Account::Liability(l) => l.0.as_deref(),
+            Account::Income(i) => i.0.as_deref(),
+            Account::Expense(e) => e.0.as_deref(),
+            Account::Equity(q) => q.0.as_deref(),
+        }
+    }
+
+    pub(crate) fn name_mut(&mut self) -> &mut Option<String> {
+        match self {
+            Account::Asset(a) => &mut a.0,
+            Account::Liability(l) => &mut l.0,
+            Account::Income(i) => &mut i.0,
+            Account::Expense(e) => &mut e.0,
+            Account::Equity(q) => &mut q.0,
+        }
+    }
+}
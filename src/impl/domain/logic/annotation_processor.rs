@@ -14,6 +14,10 @@ impl<'a> AnnotationProcessor<'a> {
         Self { records }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "annotations", skip_all)
+    )]
     pub(crate) fn process(self) -> Result<NotesToFinancialRecords, ServerError> {
         let unknown_label = "Unknown".to_string();
         let annotations_map: BTreeMap<String, BTreeSet<String>> = self
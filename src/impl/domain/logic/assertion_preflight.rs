@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{Duration, NaiveDate};
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{Assertion, Commodity, FailedAssertion, FinancialRecords, TransactionSpecId},
+    errors::BalanceAssertionsFailed,
+    presentation::utils::format_amount,
+};
+
+/// Evaluates the balance assertions against balances simulated from the
+/// generated transactions, the same way hledger would when reading the printed
+/// ledger, but reporting every failing assertion along with the specs likely
+/// responsible.
+///
+/// Closing and correction entries are not simulated: closing entries only touch
+/// the income statement accounts, and corrections are raw ledger text.
+pub(crate) struct AssertionPreflight<'a> {
+    records: &'a FinancialRecords,
+}
+
+/// How many days after a failing assertion to look for misdated specs.
+const MISDATED_WINDOW: i64 = 7;
+
+/// Suspects listed per failing assertion in the error message.
+const MAX_LISTED_SUSPECTS: usize = 5;
+
+/// Ledger account name and commodity code.
+type AccountKey = (String, &'static str);
+type Posting = (NaiveDate, TransactionSpecId, f64);
+
+impl<'a> AssertionPreflight<'a> {
+    pub(crate) fn new(records: &'a FinancialRecords) -> Self {
+        Self { records }
+    }
+
+    /// Fails with a description of every failing assertion, if any.
+    pub(crate) fn check(&self) -> Result<(), ServerError> {
+        let failures = self.failures();
+        if failures.is_empty() {
+            return Ok(());
+        }
+        let details = failures
+            .iter()
+            .map(|failure| self.describe(failure))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Err(BalanceAssertionsFailed::new(failures.len(), &details))
+    }
+
+    /// Failing assertions, ordered by account, then date.
+    pub(crate) fn failures(&self) -> Vec<FailedAssertion> {
+        let mut assertions: BTreeMap<AccountKey, Vec<&Assertion>> = BTreeMap::new();
+        for assertion in &self.records.assertions {
+            assertions
+                .entry((assertion.account.ledger(), assertion.commodity.code()))
+                .or_default()
+                .push(assertion);
+        }
+        let mut postings: BTreeMap<AccountKey, Vec<Posting>> = BTreeMap::new();
+        for transaction in &self.records.transactions {
+            for posting in &transaction.postings {
+                let key = (posting.account.ledger(), posting.commodity.code());
+                if assertions.contains_key(&key) {
+                    postings.entry(key).or_default().push((
+                        transaction.date,
+                        transaction.spec_id,
+                        posting.amount,
+                    ));
+                }
+            }
+        }
+
+        let mut failures = Vec::new();
+        for (key, mut account_assertions) in assertions {
+            let mut account_postings = postings.remove(&key).unwrap_or_default();
+            account_postings.sort_by_key(|(date, _, _)| *date);
+            account_assertions.sort_by_key(|a| a.date);
+
+            // Balances are accumulated as the assertions are visited in date
+            // order.
+            let mut balance = 0.0;
+            let mut next_posting = 0;
+            let mut last_passing = None;
+            for assertion in account_assertions {
+                while let Some((date, _, amount)) = account_postings.get(next_posting) {
+                    if *date > assertion.date {
+                        break;
+                    }
+                    balance += amount;
+                    next_posting += 1;
+                }
+                let difference = round(assertion.balance - balance, assertion.commodity);
+                if difference == 0.0 {
+                    last_passing = Some(assertion.date);
+                    continue;
+                }
+                failures.push(FailedAssertion {
+                    assertion: assertion.clone(),
+                    actual: round(balance, assertion.commodity),
+                    suspects: suspects(&account_postings, assertion, last_passing, difference),
+                });
+            }
+        }
+        failures
+    }
+
+    fn describe(&self, failure: &FailedAssertion) -> String {
+        let assertion = &failure.assertion;
+        let mut suspects = failure
+            .suspects
+            .iter()
+            .take(MAX_LISTED_SUSPECTS)
+            .map(|spec_id| match self.records.label_lookup.get(spec_id) {
+                Some(label) if !label.payee.is_empty() => format!("({}) {}", spec_id, label.payee),
+                _ => format!("({})", spec_id),
+            })
+            .collect::<Vec<_>>();
+        if failure.suspects.len() > MAX_LISTED_SUSPECTS {
+            suspects.push(format!(
+                "and {} more",
+                failure.suspects.len() - MAX_LISTED_SUSPECTS
+            ));
+        }
+        format!(
+            "{} {}: expected {}, but the transactions give {}. Likely responsible: {}",
+            assertion.date,
+            assertion.account.ledger(),
+            format_amount(assertion.balance, assertion.commodity, false),
+            format_amount(failure.actual, assertion.commodity, false),
+            if suspects.is_empty() {
+                "none found (a spec may be missing)".to_string()
+            } else {
+                suspects.join(", ")
+            },
+        )
+    }
+}
+
+/// Specs explaining `difference` (asserted minus simulated balance) come
+/// first, followed by the other specs posting to the account since the last
+/// passing assertion, latest first.
+fn suspects(
+    postings: &[Posting],
+    assertion: &Assertion,
+    last_passing: Option<NaiveDate>,
+    difference: f64,
+) -> Vec<TransactionSpecId> {
+    let commodity = assertion.commodity;
+    let misdated_until = assertion.date + Duration::days(MISDATED_WINDOW);
+    let mut explaining = Vec::new();
+    let mut others = Vec::new();
+    for &(date, spec_id, amount) in postings.iter().rev() {
+        if date > assertion.date {
+            // Booked after the assertion, but reflected in its balance.
+            if date <= misdated_until && round(amount - difference, commodity) == 0.0 {
+                explaining.push(spec_id);
+            }
+        } else if last_passing.is_none_or(|d| date > d) {
+            // Duplicated (or not reflected in the balance), or mis-signed.
+            if round(amount + difference, commodity) == 0.0
+                || round(2.0 * amount + difference, commodity) == 0.0
+            {
+                explaining.push(spec_id);
+            } else {
+                others.push(spec_id);
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    explaining
+        .into_iter()
+        .chain(others)
+        .filter(|spec_id| seen.insert(*spec_id))
+        .collect()
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
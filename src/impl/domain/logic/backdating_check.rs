@@ -0,0 +1,170 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{
+        Annotation, BackdatingGuard, BackdatingPolicy, FinancialRecords, Transaction,
+        TransactionSpecId,
+    },
+    errors::BackdatedEntries,
+};
+
+use super::assertion_preflight::AssertionPreflight;
+
+/// Flags specs whose entries are dated on or before a balance that was
+/// already reconciled when the spec was recorded (see `BackdatingGuard`).
+///
+/// Entries not originating from a spec (ex. closing entries, inferred
+/// interest) are not checked.
+pub(crate) struct BackdatingCheck<'a> {
+    guard: &'a BackdatingGuard,
+    payment_dates: &'a HashMap<TransactionSpecId, NaiveDate>,
+}
+
+/// Ledger account name and commodity code.
+type AccountKey = (String, &'static str);
+
+struct Backdated {
+    spec_id: TransactionSpecId,
+    entry_date: NaiveDate,
+    reconciled_date: NaiveDate,
+    reconciled: String,
+}
+
+impl<'a> BackdatingCheck<'a> {
+    pub(crate) fn new(
+        guard: &'a BackdatingGuard,
+        payment_dates: &'a HashMap<TransactionSpecId, NaiveDate>,
+    ) -> Self {
+        Self {
+            guard,
+            payment_dates,
+        }
+    }
+
+    /// Annotates the backdated specs, or fails with a description of each,
+    /// depending on the policy.
+    pub(crate) fn apply(&self, records: &mut FinancialRecords) -> Result<(), ServerError> {
+        let backdated = self.backdated(records);
+        match self.guard.policy {
+            BackdatingPolicy::Warn => {
+                for b in backdated {
+                    records
+                        .annotations_lookup
+                        .get_or_insert_default(b.spec_id)
+                        .push(Annotation::Backdated(b.reconciled));
+                }
+                Ok(())
+            }
+            BackdatingPolicy::Error if backdated.is_empty() => Ok(()),
+            BackdatingPolicy::Error => {
+                let details = backdated
+                    .iter()
+                    .map(|b| {
+                        let payee = records
+                            .label_lookup
+                            .get(&b.spec_id)
+                            .filter(|label| !label.payee.is_empty())
+                            .map(|label| format!(" {}", label.payee))
+                            .unwrap_or_default();
+                        format!(
+                            "({}){}: entry dated {}, on or before {}",
+                            b.spec_id, payee, b.entry_date, b.reconciled
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Err(BackdatedEntries::new(backdated.len(), &details))
+            }
+        }
+    }
+
+    /// At most one per spec: its earliest entry, against the latest reconciled
+    /// date it precedes. Ordered by spec ID.
+    fn backdated(&self, records: &FinancialRecords) -> Vec<Backdated> {
+        // Failing assertions were never reconciled.
+        let failing: HashSet<(NaiveDate, AccountKey)> = AssertionPreflight::new(records)
+            .failures()
+            .into_iter()
+            .map(|f| {
+                let a = f.assertion;
+                (a.date, (a.account.ledger(), a.commodity.code()))
+            })
+            .collect();
+        let mut reconciled: HashMap<AccountKey, Vec<NaiveDate>> = HashMap::new();
+        for assertion in &records.assertions {
+            let key = (assertion.account.ledger(), assertion.commodity.code());
+            if !failing.contains(&(assertion.date, key.clone())) {
+                reconciled.entry(key).or_default().push(assertion.date);
+            }
+        }
+
+        let mut backdated: HashMap<TransactionSpecId, Backdated> = HashMap::new();
+        for tx in &records.transactions {
+            let Some(&payment_date) = self.payment_dates.get(&tx.spec_id) else {
+                continue;
+            };
+            if tx.date >= payment_date {
+                continue;
+            }
+            let Some((reconciled_date, reason)) =
+                self.latest_reconciled(tx, payment_date, &reconciled)
+            else {
+                continue;
+            };
+            let flagged = backdated.entry(tx.spec_id).or_insert(Backdated {
+                spec_id: tx.spec_id,
+                entry_date: tx.date,
+                reconciled_date,
+                reconciled: reason.clone(),
+            });
+            flagged.entry_date = flagged.entry_date.min(tx.date);
+            if reconciled_date > flagged.reconciled_date {
+                flagged.reconciled_date = reconciled_date;
+                flagged.reconciled = reason;
+            }
+        }
+        let mut backdated: Vec<Backdated> = backdated.into_values().collect();
+        backdated.sort_by_key(|b| b.spec_id.0);
+        backdated
+    }
+
+    /// Latest reconciled date in `[tx.date, payment_date)`, with a description.
+    fn latest_reconciled(
+        &self,
+        tx: &Transaction,
+        payment_date: NaiveDate,
+        reconciled: &HashMap<AccountKey, Vec<NaiveDate>>,
+    ) -> Option<(NaiveDate, String)> {
+        let in_window = |date: &NaiveDate| tx.date <= *date && *date < payment_date;
+        let filing = self
+            .guard
+            .filing_dates
+            .iter()
+            .filter(|d| in_window(d))
+            .max()
+            .map(|d| (*d, format!("the {} filing date", d)));
+        let assertion = tx
+            .postings
+            .iter()
+            .filter_map(|p| {
+                let key = (p.account.ledger(), p.commodity.code());
+                let date = reconciled
+                    .get(&key)?
+                    .iter()
+                    .filter(|d| in_window(d))
+                    .max()?;
+                Some((
+                    *date,
+                    format!("the {} balance assertion of {}", date, key.0),
+                ))
+            })
+            .max_by_key(|(date, _)| *date);
+        filing
+            .into_iter()
+            .chain(assertion)
+            .max_by_key(|(date, _)| *date)
+    }
+}
@@ -1,3 +1,5 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use chrono::Datelike;
 use fractic_server_error::{CriticalError, ServerError};
@@ -5,13 +7,15 @@ use fractic_server_error::{CriticalError, ServerError};
 use crate::{
     entities::{
         Assertion, Command, CommandLogic, CommodityHandler, EndOfYearEntry, FinancialRecords,
-        FinancialRecords_Intermediate2, Handlers, MacroContext, MacroHandler, Transaction,
+        FinancialRecords_Intermediate2, Handlers, MacroContext, MacroHandler, PhaseProgress,
+        ProcessOptions, ProgressPhase, ProgressReporter, Transaction,
     },
     errors::InvalidCsvContent,
 };
 
 pub(crate) struct CommandProcessor<H: Handlers> {
     specs: FinancialRecords_Intermediate2<H>,
+    progress: Option<Arc<dyn ProgressReporter>>,
 }
 
 #[derive(Debug, Default)]
@@ -23,10 +27,17 @@ struct Delta {
 }
 
 impl<H: Handlers> CommandProcessor<H> {
-    pub(crate) fn new(specs: FinancialRecords_Intermediate2<H>) -> Self {
-        Self { specs }
+    pub(crate) fn new(specs: FinancialRecords_Intermediate2<H>, options: &ProcessOptions) -> Self {
+        Self {
+            specs,
+            progress: options.progress.clone(),
+        }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "commands", skip_all, fields(commands = self.specs.commands.len()))
+    )]
     pub(crate) fn process(self) -> Result<FinancialRecords, ServerError> {
         let FinancialRecords_Intermediate2 {
             transactions,
@@ -39,6 +50,11 @@ impl<H: Handlers> CommandProcessor<H> {
         } = self.specs;
 
         // Collect deltas from each command.
+        let mut progress = PhaseProgress::start(
+            self.progress,
+            ProgressPhase::ApplyingCommands,
+            commands.len(),
+        );
         let deltas = commands
             .into_iter()
             .map(|command| {
@@ -48,6 +64,7 @@ impl<H: Handlers> CommandProcessor<H> {
                         Self::process_correction(command, &transactions)?
                     }
                 };
+                progress.advance();
                 Ok(delta)
             })
             .collect::<Result<Vec<Delta>, ServerError>>()?;
@@ -73,6 +90,9 @@ impl<H: Handlers> CommandProcessor<H> {
             label_lookup,
             annotations_lookup,
             unreimbursed_entries,
+            budgets: Vec::new(),
+            payees: BTreeMap::new(),
+            metadata: None,
         })
     }
 
@@ -139,7 +159,7 @@ impl<H: Handlers> CommandProcessor<H> {
             postings: entries,
             logic,
             total: amount,
-            currency: commodity.unwrap_or_else(|| H::M::default()).currency()?,
+            commodity: commodity.unwrap_or_else(|| H::M::default()).commodity()?,
         };
 
         Ok(Delta {
@@ -178,7 +198,7 @@ impl<H: Handlers> CommandProcessor<H> {
                 Some(MacroContext {
                     description,
                     amount,
-                    currency: commodity.map(|c| c.currency()).transpose()?,
+                    commodity: commodity.map(|c| c.commodity()).transpose()?,
                 }),
                 Some(transactions),
             )?,
@@ -1,30 +1,53 @@
+use std::sync::Arc;
+
 use fractic_server_error::ServerError;
 use futures::{
     stream::{self, StreamExt},
     TryStreamExt,
 };
 
-use crate::entities::{
-    DecoratedTransactionSpec, DecoratorHandler, FinancialRecordSpecs,
-    FinancialRecords_Intermediate1, Handlers,
+use crate::{
+    entities::{
+        DecoratedTransactionSpec, DecoratorHandler, FinancialRecordSpecs,
+        FinancialRecords_Intermediate1, Handlers, PhaseProgress, ProcessOptions, ProgressPhase,
+        ProgressReporter,
+    },
+    errors::with_spec_id,
 };
 
 pub(crate) struct DecoratorProcessor<H: Handlers> {
     specs: FinancialRecordSpecs<H>,
+    concurrency: usize,
+    progress: Option<Arc<dyn ProgressReporter>>,
 }
 
 impl<H: Handlers> DecoratorProcessor<H> {
-    pub(crate) fn new(specs: FinancialRecordSpecs<H>) -> Self {
-        Self { specs }
+    pub(crate) fn new(specs: FinancialRecordSpecs<H>, options: &ProcessOptions) -> Self {
+        Self {
+            specs,
+            concurrency: options.decorator_concurrency.max(1),
+            progress: options.progress.clone(),
+        }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "decorators", skip_all, fields(specs = self.specs.transaction_specs.len()))
+    )]
     pub(crate) async fn process(self) -> Result<FinancialRecords_Intermediate1<H>, ServerError> {
         let FinancialRecordSpecs {
             transaction_specs,
             commands,
             assertion_specs,
+            budget_specs: _,
         } = self.specs;
 
+        let mut progress = PhaseProgress::start(
+            self.progress,
+            ProgressPhase::Decorating,
+            transaction_specs.len(),
+        );
+
         // let decorated_transaction_specs = transaction_specs
         //     .into_iter()
         //     .map(|tx| {
@@ -50,7 +73,7 @@ impl<H: Handlers> DecoratorProcessor<H> {
         //     .collect::<Result<Vec<_>, ServerError>>()?;
 
         let decorated_transaction_specs = stream::iter(transaction_specs)
-            .then(|tx| async move {
+            .map(|tx| async move {
                 let initial = DecoratedTransactionSpec {
                     id: tx.id,
                     accrual_start: tx.accrual_start,
@@ -63,18 +86,21 @@ impl<H: Handlers> DecoratorProcessor<H> {
                     commodity: tx.commodity,
                     backing_account: tx.backing_account,
                     annotations: tx.annotations,
+                    intercompany: tx.intercompany,
+                    dimensions: tx.dimensions,
+                    status: tx.status,
                     ext_transactions: Default::default(),
                     ext_assertions: Default::default(),
                     ext_raw: Default::default(),
                 };
 
                 stream::iter(tx.decorators.into_iter().map(Ok))
-                    .try_fold(initial, |acc, dec| async move {
-                        let l = dec.logic()?;
-                        l.apply(acc).await
-                    })
+                    .try_fold(initial, |acc, dec| apply_decorator(dec, acc))
                     .await
             })
+            // Decorate independent specs concurrently, preserving order.
+            .buffered(self.concurrency)
+            .inspect(|_| progress.advance())
             .try_collect::<Vec<_>>()
             .await?;
 
@@ -85,3 +111,21 @@ impl<H: Handlers> DecoratorProcessor<H> {
         })
     }
 }
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "decorator",
+        level = "debug",
+        skip_all,
+        fields(spec_id = spec.id.0, decorator = ?decorator)
+    )
+)]
+async fn apply_decorator<H: Handlers>(
+    decorator: H::D,
+    spec: DecoratedTransactionSpec<H>,
+) -> Result<DecoratedTransactionSpec<H>, ServerError> {
+    let spec_id = spec.id;
+    let apply = async { decorator.logic()?.apply(spec).await };
+    apply.await.map_err(|e| with_spec_id(e, spec_id))
+}
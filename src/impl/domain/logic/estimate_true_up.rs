@@ -0,0 +1,212 @@
+use std::iter::once;
+
+use chrono::{Duration, NaiveDate};
+use fractic_server_error::ServerError;
+
+use crate::entities::{
+    Account, CommodityHandler, DiscrepancyPolicy, Transaction, TransactionPosting,
+    TransactionSpecId, TransactionStatus,
+};
+
+use super::utils::{
+    compute_daily_average, monthly_accrual_adjustments, round_to_commodity_precision,
+    MonthlyAccrualAdjustment,
+};
+
+/// Store historical information of estimated accruals (ex. variable
+/// expenses), to use for making estimates.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExpenseHistory {
+    pub(crate) init_date: Option<NaiveDate>,
+    pub(crate) price_records: Vec<ExpenseHistoryPriceRecord>,
+}
+#[derive(Debug, Clone)]
+pub(crate) struct ExpenseHistoryPriceRecord {
+    pub(crate) start: NaiveDate,
+    pub(crate) end: NaiveDate,
+    pub(crate) daily_rate: f64,
+}
+#[derive(Debug, Clone)]
+pub(crate) struct ExpenseHistoryDelta {
+    /// Account the history is kept for (ex. the expense).
+    pub(crate) account: Account,
+    pub(crate) price_record: ExpenseHistoryPriceRecord,
+    pub(crate) is_init: bool,
+}
+
+impl ExpenseHistory {
+    /// Average daily rate over the `lookback_days` before `date` (or since the
+    /// init date, if later). `None` if not initialized, or if there is no
+    /// history in that window.
+    pub(crate) fn average_daily_rate(&self, date: NaiveDate, lookback_days: i64) -> Option<f64> {
+        let window_start = std::cmp::max(self.init_date?, date - Duration::days(lookback_days));
+        let window_end = date - Duration::days(1);
+        compute_daily_average(&self.price_records, window_start, window_end)
+    }
+}
+
+/// Where the estimated amount comes from.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Estimate {
+    /// Total for the accrual period (ex. a manual estimate).
+    Total(f64),
+    /// Daily rate applied to each day of the accrual period (ex. an average
+    /// of the history).
+    DailyRate(f64),
+}
+
+/// When the estimate is accrued.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum AccrualSchedule {
+    /// At the end of each month, in proportion to its days in the period (ex.
+    /// utilities, incurred continuously).
+    Monthly,
+    /// In full at the end of the period (ex. an audit fee for the year).
+    #[allow(dead_code)] // Not used by the standard logics yet.
+    PeriodEnd,
+}
+
+/// Accrues an estimate of a cost (ex. a variable expense, bonus, audit fee or
+/// tax) over its accrual period, then trues it up to the actual amount when
+/// paid. The discrepancy is posted as a correction or absorbed into the
+/// clearing transaction, depending on the true-up policy.
+///
+/// Logics using it only need to pick the estimate, schedule and accounts, and
+/// record the returned price record in the history (see `ExpenseHistory`) if
+/// later estimates are based on it.
+pub(crate) struct EstimateTrueUp<'a, M: CommodityHandler> {
+    pub(crate) spec_id: TransactionSpecId,
+    pub(crate) accrual_start: NaiveDate,
+    pub(crate) accrual_end: NaiveDate,
+    pub(crate) payment_date: NaiveDate,
+    /// Account the cost is recognized in (ex. the expense).
+    pub(crate) account: Account,
+    /// Liability the estimate is accrued to until paid.
+    pub(crate) accrued: Account,
+    /// Account paying the actual amount (ex. the spec's backing account).
+    pub(crate) payer: Account,
+    pub(crate) commodity: &'a M,
+    pub(crate) estimate: Estimate,
+    pub(crate) schedule: AccrualSchedule,
+    pub(crate) true_up: &'a DiscrepancyPolicy,
+    /// Used in the transaction comments (ex. "expense" for "Estimated
+    /// expense accrual for ...").
+    pub(crate) kind: &'static str,
+}
+
+impl<M: CommodityHandler> EstimateTrueUp<'_, M> {
+    /// The accrual, true-up and clearing transactions, and the price record of
+    /// the actual amount.
+    ///
+    /// actual: The amount paid (sign is ignored).
+    pub(crate) fn book(
+        &self,
+        actual: f64,
+    ) -> Result<(Vec<Transaction>, ExpenseHistoryPriceRecord), ServerError> {
+        let commodity = self.commodity.commodity()?;
+        let actual = actual.abs();
+        let accrual_days = (self.accrual_end - self.accrual_start).num_days() + 1;
+        let estimated_total = match self.estimate {
+            Estimate::Total(total) => total.abs(),
+            Estimate::DailyRate(daily_rate) => daily_rate * (accrual_days as f64),
+        };
+
+        let accrual =
+            |date: NaiveDate, start: NaiveDate, end: NaiveDate, amount: f64| Transaction {
+                spec_id: self.spec_id,
+                date,
+                comment: Some(
+                    format!("Estimated {} accrual for {} - {}", self.kind, start, end).into(),
+                ),
+                postings: vec![
+                    TransactionPosting::new(self.accrued.clone(), -amount, commodity),
+                    TransactionPosting::new(self.account.clone(), amount, commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            };
+        let mut transactions = match self.schedule {
+            AccrualSchedule::Monthly => monthly_accrual_adjustments(
+                self.accrual_start,
+                self.accrual_end,
+                estimated_total,
+                self.commodity.decimal_places()?,
+                self.commodity.rounding_mode(),
+            )?
+            .into_iter()
+            .map(
+                |MonthlyAccrualAdjustment {
+                     period_start,
+                     period_end,
+                     adjustment_amount,
+                     adjustment_date,
+                 }| {
+                    accrual(adjustment_date, period_start, period_end, adjustment_amount)
+                },
+            )
+            .collect::<Vec<_>>(),
+            AccrualSchedule::PeriodEnd => vec![accrual(
+                self.accrual_end,
+                self.accrual_start,
+                self.accrual_end,
+                self.commodity.round(estimated_total)?,
+            )],
+        };
+
+        // Record any estimation discrepancies.
+        //
+        // Note, to ensure we don't have lingering pennies, the discrepancy must
+        // be calculated at the precision level of the commodity.
+        let discrepancy = round_to_commodity_precision(actual, &commodity)?
+            - round_to_commodity_precision(estimated_total, &commodity)?;
+        if self
+            .true_up
+            .should_post(discrepancy, actual, self.commodity)?
+        {
+            transactions.push(Transaction {
+                spec_id: self.spec_id,
+                date: self.payment_date,
+                comment: Some("Correct estimate discrepancy".into()),
+                postings: vec![
+                    TransactionPosting::new(self.accrued.clone(), -discrepancy, commodity),
+                    TransactionPosting::new(self.account.clone(), discrepancy, commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+        }
+
+        // Discrepancies below the policy's threshold are absorbed into the
+        // clearing transaction instead.
+        let absorbed = self.true_up.absorbed(discrepancy, actual, self.commodity)?;
+
+        // Record the clearing transaction.
+        transactions.push(Transaction {
+            spec_id: self.spec_id,
+            date: self.payment_date,
+            comment: Some(format!("Clear payable {}", self.kind).into()),
+            postings: once(TransactionPosting::new(
+                self.payer.clone(),
+                -actual,
+                commodity,
+            ))
+            .chain(once(TransactionPosting::linked(
+                self.accrued.clone(),
+                self.account.clone(),
+                actual - absorbed,
+                commodity,
+            )))
+            .chain(
+                (absorbed != 0.0)
+                    .then(|| TransactionPosting::new(self.account.clone(), absorbed, commodity)),
+            )
+            .collect(),
+            status: TransactionStatus::Unmarked,
+        });
+
+        let actual_record = ExpenseHistoryPriceRecord {
+            start: self.accrual_start,
+            end: self.accrual_end,
+            daily_rate: actual / (accrual_days as f64),
+        };
+        Ok((transactions, actual_record))
+    }
+}
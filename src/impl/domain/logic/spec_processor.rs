@@ -1,67 +1,90 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     iter::once,
+    ops::Bound,
+    sync::Arc,
 };
 
-use chrono::{Duration, NaiveDate};
-use fractic_server_error::ServerError;
+use chrono::{Datelike as _, Months, NaiveDate};
+use fractic_server_error::{CriticalError, ServerError};
 
 use crate::{
-    domain::logic::utils::{
-        compute_daily_average, monthly_accrual_adjustments, round_to_currency_precision,
-        track_unreimbursed_entries, MonthlyAccrualAdjustment,
+    domain::logic::{
+        estimate_true_up::{
+            AccrualSchedule, Estimate, EstimateTrueUp, ExpenseHistory, ExpenseHistoryDelta,
+        },
+        utils::{
+            monthly_accrual_adjustments, monthly_accrual_periods, monthly_depreciation_adjustments,
+            monthly_loan_installments, posting_commodities, principal_due_within_a_year,
+            reissue_treasury_shares, round_to_commodity_precision, sell_inventory,
+            track_unreimbursed_entries, LoanInstallment, MonthlyAccrualAdjustment, TreasuryReissue,
+        },
     },
     entities::{
-        equity_tl, Account, AccountingLogic, Annotation, Assertion, AssetHandler, BackingAccount,
-        CashHandler, CashflowTracingTag, CommodityHandler, CommonStockWhileUnpaid,
-        DecoratedTransactionSpec, EquityClassification, ExpenseAccount, ExpenseHandler,
-        FinancialRecords_Intermediate1, FinancialRecords_Intermediate2, Handlers, IncomeHandler,
-        LiabilityAccount, PayeeHandler, ReimbursableEntityHandler, ShareIssuanceCostBookTo,
-        ShareholderHandler, Transaction, TransactionLabel, TransactionPosting, TransactionSpecId,
+        equity_tl, expense_tl, Account, AccountingLogic, Annotation, Assertion, AssertionCommodity,
+        AssetAccount, AssetClassification, AssetHandler, BackingAccount, CashHandler,
+        CashflowTracingTag, Commodity, CommodityHandler, CommonStockWhileUnpaid,
+        DecoratedTransactionSpec, DepreciationMethod, DiscrepancyPolicy, EquityClassification,
+        ExpenseAccount, ExpenseClassification, ExpenseHandler, FairValueGainsTo,
+        FinancialRecords_Intermediate1, FinancialRecords_Intermediate2, Handlers, IfrsLogic,
+        IncomeHandler, InterestInference, InventoryCosting, LiabilityAccount, PayeeHandler,
+        PhaseProgress, ProcessOptions, ProgressPhase, ProgressReporter, ReimbursableEntityHandler,
+        ShareIssuanceCostBookTo, ShareholderHandler, SpecHook, SpecLookup, SpecOutcome,
+        SpecStateChange, SpecSummary, StandardIfrsLogic, Transaction, TransactionLabel,
+        TransactionPosting, TransactionSpecId, TransactionStatus, INTERCOMPANY_TAG,
     },
     errors::{
-        CommonStockCannotBePrepaid, InvalidArgumentsForAccountingLogic, NoTransactionsToReimburse,
-        NonAmortizableAsset, UnexpectedNegativeValue, UnexpectedPartialReimbursement,
-        UnexpectedPositiveValue, VariableExpenseDoubleInit, VariableExpenseInvalidPaymentDate,
-        VariableExpenseNoInit, VariableExpenseNotEnoughHistoricalData,
+        with_spec_id, AllowanceOverReversed, ClearVatSpansFilingPeriods,
+        CommonStockCannotBePrepaid, DepositOverRefunded, EntriesAfterDisposal,
+        EntriesAfterImpairment, ErrorCategory, IncomeTaxNotDatedAtYearEnd,
+        IncomeTaxSettledBeforeYearEnd, IntercompanyCounterpartyMismatch,
+        InterestAccruedBeforeDrawdown, InterestCreditedBeforeAccrualEnd,
+        InvalidArgumentsForAccountingLogic, InvalidFiscalYear, InvalidResidualValue,
+        InventoryCostingMismatch, InventoryOversold, InvoiceOverCollected,
+        MaturityNotAfterAccrualStart, MissingIntercompanyCounterparty,
+        MonthlyUnitsExceedTotalUnits, MonthlyUnitsExceedUsefulLife, NegativeBorrowingRate,
+        NegativeMonthlyUnits, NoIncomeTaxAccrued, NoTransactionsToReimburse, NonAmortizableAsset,
+        NonImpairableAsset, NonInventoryAsset, NonPositiveDecliningBalanceRate,
+        NonPositiveInventoryQuantity, NonPositiveShareCount, NonPositiveTotalUnits,
+        NonRemeasurableAsset, NothingToDispose, ServerErrorExt as _, TreasurySharesOverReissued,
+        UnexpectedNegativeValue, UnexpectedPartialReimbursement, UnexpectedPositiveValue,
+        VariableExpenseDoubleInit, VariableExpenseInvalidPaymentDate, VariableExpenseNoInit,
+        VariableExpenseNotEnoughHistoricalData,
     },
     ext::standard_accounts::{
-        DISCOUNT_ON_STOCK_ISSUANCE, PREPAID_SHARE_ISSUANCE_COSTS, SHARE_ISSUANCE_COSTS_PAYABLE,
-        UNPAID_SHARE_CAPITAL_AS_ASSET, UNPAID_SHARE_CAPITAL_AS_EQUITY,
+        allowance_for_doubtful_debts_for, deducted_grants_for, due_from_group_entity,
+        due_to_group_entity, income_tax_payable_for, security_deposits_for, BAD_DEBTS,
+        BANK_INTEREST, CARD_INTEREST_AND_FEES, CURRENT_BORROWINGS, CURRENT_TAX_EXPENSE,
+        DEFERRED_GRANT_INCOME, DISCOUNT_ON_STOCK_ISSUANCE, FAIR_VALUE_RESERVE,
+        GAIN_ON_SALE_OF_ASSETS, GRANTS_RECEIVABLE, GRANT_INCOME, IMPAIRMENT_LOSS,
+        INCOME_TAX_TRUE_UP, INTEREST_ON_BORROWINGS, INTEREST_RECEIVABLE,
+        LONG_TERM_SECURITY_DEPOSITS, LOSS_ON_SALE_OF_ASSETS, NON_CURRENT_BORROWINGS,
+        PREPAID_SHARE_ISSUANCE_COSTS, RETAINED_EARNINGS, SHARE_ISSUANCE_COSTS_PAYABLE,
+        SHORT_TERM_SECURITY_DEPOSITS, SUSPENSE, TREASURY_SHARE_SURPLUS, TREASURY_STOCK,
+        UNPAID_SHARE_CAPITAL_AS_ASSET, UNPAID_SHARE_CAPITAL_AS_EQUITY, UNREALIZED_INVESTMENT_GAIN,
+        UNREALIZED_INVESTMENT_LOSS,
     },
-    impl_ext::standard_accounts::vat::{VAT_PAYABLE, VAT_RECEIVABLE},
+    impl_ext::standard_accounts::vat::{vat_filing_period, vat_payable_for, vat_receivable_for},
+    presentation::utils::{format_amount, replace_all_placeholders_in_string},
 };
 
-use super::utils::PopByAmount;
+use super::utils::{PopByAmount, SettleOpenItems};
 
 pub(crate) struct SpecProcessor<H: Handlers> {
     specs: FinancialRecords_Intermediate1<H>,
-}
-
-/// Store historical information of variables expenses, to use for making
-/// estimates.
-#[derive(Debug, Clone, Default)]
-pub(crate) struct ExpenseHistory {
-    pub(crate) init_date: Option<NaiveDate>,
-    pub(crate) price_records: Vec<ExpenseHistoryPriceRecord>,
-}
-#[derive(Debug, Clone)]
-pub(crate) struct ExpenseHistoryPriceRecord {
-    pub(crate) start: NaiveDate,
-    pub(crate) end: NaiveDate,
-    pub(crate) daily_rate: f64,
-}
-#[derive(Debug, Clone)]
-pub(crate) struct ExpenseHistoryDelta {
-    pub(crate) account: ExpenseAccount,
-    pub(crate) price_record: ExpenseHistoryPriceRecord,
-    pub(crate) is_init: bool,
+    hooks: Vec<Arc<dyn SpecHook>>,
+    progress: Option<Arc<dyn ProgressReporter>>,
+    interest_inference: Option<InterestInference>,
+    discrepancy_policy: DiscrepancyPolicy,
+    suspense_routing: bool,
+    materiality_thresholds: HashMap<Commodity, f64>,
 }
 
 /// Keep track of unreimbursed entries.
 pub(crate) type ReimbursementState = HashMap<LiabilityAccount, VecDeque<UnreimbursedEntry>>;
 #[derive(Debug, Clone)]
 pub struct UnreimbursedEntry {
+    pub(crate) spec_id: TransactionSpecId,
     pub(crate) transaction_date: NaiveDate,
     pub(crate) total_amount: f64,
     pub(crate) credit_postings: Vec<TransactionPosting>,
@@ -77,6 +100,121 @@ pub(crate) enum ReimbursementStateDelta {
         account: LiabilityAccount,
         entries: Vec<UnreimbursedEntry>,
     },
+    /// Like `Pop`, but the amount need not cover a whole number of entries.
+    Settle {
+        date: NaiveDate,
+        account: LiabilityAccount,
+        amount: f64,
+    },
+}
+
+/// An amount recorded by a spec (ex. an invoice), to be settled by later
+/// specs, possibly in parts. Settled oldest first (see `SettleOpenItems`).
+#[derive(Debug, Clone)]
+pub(crate) struct OpenItem<T> {
+    pub(crate) spec_id: TransactionSpecId,
+    pub(crate) outstanding_amount: f64,
+    pub(crate) detail: T,
+}
+
+/// Keep track of invoices not yet fully collected, by receivable account,
+/// payee and commodity.
+pub(crate) type InvoiceState = HashMap<InvoiceKey, VecDeque<OpenItem<()>>>;
+pub(crate) type InvoiceKey = (AssetAccount, String, Commodity);
+#[derive(Debug)]
+pub(crate) enum InvoiceStateDelta {
+    Issue {
+        key: InvoiceKey,
+        invoice: OpenItem<()>,
+    },
+    /// Collected against the oldest outstanding invoices first.
+    Collect { key: InvoiceKey, amount: f64 },
+}
+
+/// Keep track of the inventory in stock, by inventory account and commodity
+/// (of the cost).
+pub(crate) type InventoryState = HashMap<(AssetAccount, Commodity), InventoryStock>;
+/// Units in stock of an item, and the costing formula its purchases use.
+#[derive(Debug, Clone)]
+pub(crate) struct InventoryStock {
+    pub(crate) costing: InventoryCosting,
+    pub(crate) lots: VecDeque<InventoryLot>,
+}
+/// Units purchased together, oldest first.
+#[derive(Debug, Clone)]
+pub(crate) struct InventoryLot {
+    pub(crate) quantity: f64,
+    pub(crate) cost: f64,
+}
+#[derive(Debug)]
+pub(crate) enum InventoryStateDelta {
+    Purchase {
+        key: (AssetAccount, Commodity),
+        costing: InventoryCosting,
+        lot: InventoryLot,
+    },
+    /// Replaces the lots with the ones remaining after the sale.
+    Sell {
+        key: (AssetAccount, Commodity),
+        quantity: f64,
+        cost: f64,
+        remaining: VecDeque<InventoryLot>,
+    },
+}
+
+/// Keep track of the treasury shares held, by commodity (of the cost).
+/// Reissued shares are carried at the weighted average cost of those held.
+pub(crate) type TreasuryState = HashMap<Commodity, TreasuryShares>;
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TreasuryShares {
+    pub(crate) shares: f64,
+    pub(crate) cost: f64,
+}
+#[derive(Debug)]
+pub(crate) enum TreasuryStateDelta {
+    Buyback {
+        commodity: Commodity,
+        shares: f64,
+        cost: f64,
+    },
+    Reissue {
+        commodity: Commodity,
+        shares: f64,
+        cost: f64,
+    },
+}
+
+/// Keep track of the security deposits not yet fully refunded, by payee (the
+/// holder) and commodity.
+pub(crate) type DepositState = HashMap<(String, Commodity), VecDeque<OpenItem<HeldDeposit>>>;
+#[derive(Debug, Clone)]
+pub(crate) struct HeldDeposit {
+    /// The account the deposit was paid into.
+    pub(crate) account: AssetAccount,
+    /// Year end at which a long-term deposit is reclassified as short-term.
+    pub(crate) reclassified_on: Option<NaiveDate>,
+}
+#[derive(Debug)]
+pub(crate) enum DepositStateDelta {
+    Pay {
+        key: (String, Commodity),
+        deposit: OpenItem<HeldDeposit>,
+    },
+    /// Refunded against the oldest open deposits first.
+    Refund {
+        key: (String, Commodity),
+        amount: f64,
+    },
+}
+
+/// Changes to the state kept for logics that track open items across specs
+/// (ex. invoices collected by a later spec).
+#[derive(Debug)]
+pub(crate) enum StateDelta {
+    Invoice(InvoiceStateDelta),
+    Inventory(InventoryStateDelta),
+    Treasury(TreasuryStateDelta),
+    Deposit(DepositStateDelta),
 }
 
 struct Delta {
@@ -87,72 +225,347 @@ struct Delta {
     ext_assertions: Vec<Assertion>,
     ext_raw: Vec<String>,
     expense_history_delta: Option<ExpenseHistoryDelta>,
+    state_delta: Vec<StateDelta>,
     reimbursement_state_delta: Option<ReimbursementStateDelta>,
     annotations: Vec<Annotation>,
 }
 
+/// What's needed to book a spec to the suspense account if its accounting
+/// logic rejects it (see `ProcessOptions::suspense_routing`).
+struct SuspenseEntry<H: Handlers> {
+    spec_id: TransactionSpecId,
+    payment_date: NaiveDate,
+    label: TransactionLabel,
+    amount: f64,
+    commodity: H::M,
+    backing_account: BackingAccount<H::R, H::C, H::S>,
+    annotations: Vec<Annotation>,
+    ext_transactions: Vec<Transaction>,
+    ext_assertions: Vec<Assertion>,
+    ext_raw: Vec<String>,
+}
+
+/// The parts of a `Delta` not exposed to hooks.
+struct DeltaState {
+    ext_raw: Vec<String>,
+    expense_history_delta: Option<ExpenseHistoryDelta>,
+    state_delta: Vec<StateDelta>,
+    reimbursement_state_delta: Option<ReimbursementStateDelta>,
+}
+
+impl Delta {
+    fn into_outcome(self, payment_date: NaiveDate, spec: SpecSummary) -> (SpecOutcome, DeltaState) {
+        let state_changes = self
+            .reimbursement_state_delta
+            .iter()
+            .map(|d| match d {
+                ReimbursementStateDelta::Push { account, entries } => {
+                    SpecStateChange::UnreimbursedEntriesAdded {
+                        account: account.clone(),
+                        total_amount: entries.iter().map(|e| e.total_amount).sum(),
+                    }
+                }
+                ReimbursementStateDelta::Pop {
+                    account, amount, ..
+                }
+                | ReimbursementStateDelta::Settle {
+                    account, amount, ..
+                } => SpecStateChange::UnreimbursedEntriesCleared {
+                    account: account.clone(),
+                    amount: *amount,
+                },
+            })
+            .chain(self.state_delta.iter().map(|d| match d {
+                StateDelta::Invoice(InvoiceStateDelta::Issue { key, invoice }) => {
+                    SpecStateChange::InvoiceIssued {
+                        account: key.0.clone(),
+                        amount: invoice.outstanding_amount,
+                    }
+                }
+                StateDelta::Invoice(InvoiceStateDelta::Collect { key, amount }) => {
+                    SpecStateChange::InvoiceCollected {
+                        account: key.0.clone(),
+                        amount: *amount,
+                    }
+                }
+                StateDelta::Inventory(InventoryStateDelta::Purchase { key, lot, .. }) => {
+                    SpecStateChange::InventoryPurchased {
+                        account: key.0.clone(),
+                        quantity: lot.quantity,
+                        cost: lot.cost,
+                    }
+                }
+                StateDelta::Inventory(InventoryStateDelta::Sell {
+                    key,
+                    quantity,
+                    cost,
+                    ..
+                }) => SpecStateChange::InventorySold {
+                    account: key.0.clone(),
+                    quantity: *quantity,
+                    cost: *cost,
+                },
+                StateDelta::Treasury(TreasuryStateDelta::Buyback { shares, cost, .. }) => {
+                    SpecStateChange::TreasurySharesBoughtBack {
+                        shares: *shares,
+                        cost: *cost,
+                    }
+                }
+                StateDelta::Treasury(TreasuryStateDelta::Reissue { shares, cost, .. }) => {
+                    SpecStateChange::TreasurySharesReissued {
+                        shares: *shares,
+                        cost: *cost,
+                    }
+                }
+                StateDelta::Deposit(DepositStateDelta::Pay { deposit, .. }) => {
+                    SpecStateChange::DepositPaid {
+                        account: deposit.detail.account.clone(),
+                        amount: deposit.outstanding_amount,
+                    }
+                }
+                StateDelta::Deposit(DepositStateDelta::Refund { amount, .. }) => {
+                    SpecStateChange::DepositRefunded { amount: *amount }
+                }
+            }))
+            .chain(
+                self.expense_history_delta
+                    .iter()
+                    .map(|d| SpecStateChange::ExpenseHistoryRecorded { is_init: d.is_init }),
+            )
+            .collect();
+        (
+            SpecOutcome {
+                spec_id: self.spec_id,
+                payment_date,
+                label: self.label,
+                transactions: self.transactions,
+                ext_transactions: self.ext_transactions,
+                ext_assertions: self.ext_assertions,
+                annotations: self.annotations,
+                state_changes,
+                spec,
+            },
+            DeltaState {
+                ext_raw: self.ext_raw,
+                expense_history_delta: self.expense_history_delta,
+                state_delta: self.state_delta,
+                reimbursement_state_delta: self.reimbursement_state_delta,
+            },
+        )
+    }
+
+    fn from_outcome(outcome: SpecOutcome, rest: DeltaState) -> Self {
+        Self {
+            spec_id: outcome.spec_id,
+            label: outcome.label,
+            transactions: outcome.transactions,
+            ext_transactions: outcome.ext_transactions,
+            ext_assertions: outcome.ext_assertions,
+            ext_raw: rest.ext_raw,
+            expense_history_delta: rest.expense_history_delta,
+            state_delta: rest.state_delta,
+            reimbursement_state_delta: rest.reimbursement_state_delta,
+            annotations: outcome.annotations,
+        }
+    }
+}
+
+/// Net postings to each account by commodity and date, so balances can be
+/// looked up without keeping the transactions (see `SpecTransactions`).
+#[derive(Default)]
+pub(crate) struct Balances(HashMap<(Account, Commodity), BTreeMap<NaiveDate, f64>>);
+
+impl Balances {
+    fn record(&mut self, tx: &Transaction) {
+        for posting in &tx.postings {
+            let key = (posting.account.clone(), posting.commodity);
+            *self.0.entry(key).or_default().entry(tx.date).or_default() += posting.amount;
+        }
+    }
+
+    /// Balance of the account in the commodity, as of the end of the given
+    /// date.
+    fn as_of(
+        &self,
+        account: &Account,
+        commodity: Commodity,
+        date: NaiveDate,
+    ) -> Result<f64, ServerError> {
+        let Some(by_date) = self.0.get(&(account.clone(), commodity)) else {
+            return Ok(0.0);
+        };
+        round_to_commodity_precision(by_date.range(..=date).map(|(_, a)| a).sum(), &commodity)
+    }
+
+    /// Earliest date after the given one with postings to any of the matching
+    /// accounts (in any commodity).
+    fn first_entry_after(
+        &self,
+        date: NaiveDate,
+        matches: impl Fn(&Account) -> bool,
+    ) -> Option<NaiveDate> {
+        self.0
+            .iter()
+            .filter(|((account, _), _)| matches(account))
+            .filter_map(|(_, by_date)| {
+                by_date
+                    .range((Bound::Excluded(date), Bound::Unbounded))
+                    .next()
+                    .map(|(d, _)| *d)
+            })
+            .min()
+    }
+}
+
 struct FoldState {
     transactions: Vec<Transaction>,
+    balances: Balances,
     assertions: Vec<Assertion>,
     ledger_extensions: Vec<String>,
-    expense_history_lookup: HashMap<ExpenseAccount, ExpenseHistory>,
-    label_lookup: HashMap<TransactionSpecId, TransactionLabel>,
-    annotations_lookup: HashMap<TransactionSpecId, Vec<Annotation>>,
+    expense_history_lookup: HashMap<Account, ExpenseHistory>,
+    label_lookup: SpecLookup<TransactionLabel>,
+    annotations_lookup: SpecLookup<Vec<Annotation>>,
     reimbursement_state: ReimbursementState,
+    invoice_state: InvoiceState,
+    inventory_state: InventoryState,
+    treasury_state: TreasuryState,
+    deposit_state: DepositState,
+    strings: Interner,
 }
 
 impl FoldState {
     fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Sized for the given number of specs, which generate at least one
+    /// transaction and one label each.
+    fn with_capacity(spec_count: usize) -> Self {
         Self {
-            transactions: Vec::new(),
+            transactions: Vec::with_capacity(spec_count),
+            balances: Balances::default(),
             assertions: Vec::new(),
             ledger_extensions: Vec::new(),
             expense_history_lookup: HashMap::new(),
-            label_lookup: HashMap::new(),
-            annotations_lookup: HashMap::new(),
+            label_lookup: SpecLookup::with_capacity(spec_count),
+            annotations_lookup: SpecLookup::with_capacity(spec_count),
             reimbursement_state: HashMap::new(),
+            invoice_state: HashMap::new(),
+            inventory_state: HashMap::new(),
+            treasury_state: HashMap::new(),
+            deposit_state: HashMap::new(),
+            strings: Interner::default(),
         }
     }
 
     /// Update current state with the given transformation.
-    fn step(self, t: Delta) -> Result<Self, ServerError> {
-        let FoldState {
-            mut transactions,
-            mut assertions,
-            mut ledger_extensions,
-            mut expense_history_lookup,
-            mut label_lookup,
-            mut annotations_lookup,
-            mut reimbursement_state,
-        } = self;
-
+    fn step(&mut self, t: Delta) -> Result<(), ServerError> {
         match t.reimbursement_state_delta {
             Some(ReimbursementStateDelta::Pop {
                 date,
                 account,
                 amount,
             }) => {
-                reimbursement_state
+                self.reimbursement_state
                     .entry(account)
                     .or_default()
                     .pop_until_exactly(amount, date)?;
             }
             Some(ReimbursementStateDelta::Push { account, entries }) => {
-                reimbursement_state
+                self.reimbursement_state
                     .entry(account)
                     .or_default()
                     .extend(entries);
             }
+            Some(ReimbursementStateDelta::Settle {
+                date,
+                account,
+                amount,
+            }) => {
+                self.reimbursement_state
+                    .entry(account)
+                    .or_default()
+                    .pop_settled(amount, date);
+            }
             None => {}
         }
+        for delta in t.state_delta {
+            match delta {
+                StateDelta::Invoice(InvoiceStateDelta::Issue { key, invoice }) => {
+                    self.invoice_state
+                        .entry(key)
+                        .or_default()
+                        .push_back(invoice);
+                }
+                StateDelta::Invoice(InvoiceStateDelta::Collect { key, amount }) => {
+                    let commodity = key.2;
+                    self.invoice_state
+                        .entry(key)
+                        .or_default()
+                        .settle_items(amount, &commodity)?;
+                }
+                StateDelta::Inventory(InventoryStateDelta::Purchase { key, costing, lot }) => {
+                    self.inventory_state
+                        .entry(key)
+                        .or_insert_with(|| InventoryStock {
+                            costing,
+                            lots: VecDeque::new(),
+                        })
+                        .lots
+                        .push_back(lot);
+                }
+                StateDelta::Inventory(InventoryStateDelta::Sell { key, remaining, .. }) => {
+                    if let Some(stock) = self.inventory_state.get_mut(&key) {
+                        stock.lots = remaining;
+                    }
+                }
+                StateDelta::Treasury(TreasuryStateDelta::Buyback {
+                    commodity,
+                    shares,
+                    cost,
+                }) => {
+                    let held = self.treasury_state.entry(commodity).or_default();
+                    held.shares += shares;
+                    held.cost = round_to_commodity_precision(held.cost + cost, &commodity)?;
+                }
+                StateDelta::Treasury(TreasuryStateDelta::Reissue {
+                    commodity,
+                    shares,
+                    cost,
+                }) => {
+                    let held = self.treasury_state.entry(commodity).or_default();
+                    held.shares -= shares;
+                    held.cost = round_to_commodity_precision(held.cost - cost, &commodity)?;
+                }
+                StateDelta::Deposit(DepositStateDelta::Pay { key, deposit }) => {
+                    self.deposit_state
+                        .entry(key)
+                        .or_default()
+                        .push_back(deposit);
+                }
+                StateDelta::Deposit(DepositStateDelta::Refund { key, amount }) => {
+                    let commodity = key.1;
+                    self.deposit_state
+                        .entry(key)
+                        .or_default()
+                        .settle_items(amount, &commodity)?;
+                }
+            }
+        }
 
-        transactions.extend(t.transactions);
-        transactions.extend(t.ext_transactions);
-        assertions.extend(t.ext_assertions);
-        ledger_extensions.extend(t.ext_raw);
+        for mut tx in t.transactions.into_iter().chain(t.ext_transactions) {
+            self.balances.record(&tx);
+            tx.comment = tx.comment.map(|comment| self.strings.intern(comment));
+            self.transactions.push(tx);
+        }
+        self.assertions.extend(t.ext_assertions);
+        self.ledger_extensions.extend(t.ext_raw);
 
         if let Some(delta) = t.expense_history_delta {
-            let expense_history = expense_history_lookup.entry(delta.account).or_default();
+            let expense_history = self
+                .expense_history_lookup
+                .entry(delta.account)
+                .or_default();
             if delta.is_init {
                 if expense_history.init_date.is_some() {
                     return Err(VariableExpenseDoubleInit::new(&t.label.description));
@@ -162,24 +575,167 @@ impl FoldState {
             expense_history.price_records.push(delta.price_record);
         }
 
-        label_lookup.insert(t.spec_id, t.label);
-        annotations_lookup
-            .entry(t.spec_id)
-            .or_default()
-            .extend(t.annotations);
+        let label = TransactionLabel {
+            payee: self.strings.intern(t.label.payee),
+            description: t.label.description,
+        };
+        self.label_lookup.insert(t.spec_id, label);
+        match self.annotations_lookup.get_mut(&t.spec_id) {
+            Some(annotations) => annotations.extend(t.annotations),
+            None => {
+                self.annotations_lookup.insert(t.spec_id, t.annotations);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Strings repeated across specs (payees, generated transaction comments),
+/// shared so the records hold a single copy of each.
+#[derive(Default)]
+struct Interner(HashSet<Arc<str>>);
+
+impl Interner {
+    fn intern(&mut self, s: Arc<str>) -> Arc<str> {
+        match self.0.get(&s) {
+            Some(interned) => interned.clone(),
+            None => {
+                self.0.insert(s.clone());
+                s
+            }
+        }
+    }
+}
+
+impl<H: Handlers> SuspenseEntry<H> {
+    fn from_spec(spec: &DecoratedTransactionSpec<H>) -> Self {
+        Self {
+            spec_id: spec.id,
+            payment_date: spec.payment_date,
+            label: TransactionLabel {
+                payee: spec.payee.name().into(),
+                description: spec.description.clone(),
+            },
+            amount: spec.amount,
+            commodity: spec.commodity.clone(),
+            backing_account: spec.backing_account.clone(),
+            annotations: spec.annotations.clone(),
+            ext_transactions: spec.ext_transactions.clone(),
+            ext_assertions: spec.ext_assertions.clone(),
+            ext_raw: spec.ext_raw.clone(),
+        }
+    }
+
+    /// Book the payment against the suspense account, so the backing account
+    /// still reflects it, with the error kept as an annotation. Entries added
+    /// by decorators are kept as-is.
+    fn into_delta(self, error: ServerError) -> Result<Delta, ServerError> {
+        let tx = Transaction {
+            spec_id: self.spec_id,
+            date: self.payment_date,
+            comment: None,
+            postings: vec![
+                TransactionPosting::new(
+                    self.backing_account.account(),
+                    self.amount,
+                    self.commodity.commodity()?,
+                ),
+                TransactionPosting::new(
+                    SUSPENSE.clone().into(),
+                    -self.amount,
+                    self.commodity.commodity()?,
+                ),
+            ],
+            status: TransactionStatus::Unmarked,
+        };
+        let note = Annotation::Suspense(error.to_string());
 
-        Ok(Self {
+        let transactions = vec![tx];
+        Ok(Delta {
+            spec_id: self.spec_id,
+            label: self.label,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &self.backing_account,
+                &transactions,
+                &self.ext_transactions,
+            )?,
             transactions,
-            assertions,
-            ledger_extensions,
-            expense_history_lookup,
-            label_lookup,
-            annotations_lookup,
-            reimbursement_state,
+            ext_transactions: self.ext_transactions,
+            ext_assertions: self.ext_assertions,
+            ext_raw: self.ext_raw,
+            annotations: self.annotations.into_iter().chain(once(note)).collect(),
         })
     }
 }
 
+/// Iterator over generated transactions (in spec payment date order), paired
+/// with the label of their originating spec. Generated transactions are handed
+/// off rather than accumulated, so only the state needed to process later specs
+/// (open items, and account balances by date) is kept in memory. Iteration
+/// stops after the first error.
+pub(crate) struct SpecTransactions<H: Handlers> {
+    specs: std::vec::IntoIter<DecoratedTransactionSpec<H>>,
+    hooks: Vec<Arc<dyn SpecHook>>,
+    discrepancy_policy: DiscrepancyPolicy,
+    suspense_routing: bool,
+    materiality_thresholds: HashMap<Commodity, f64>,
+    progress: PhaseProgress,
+    state: Option<FoldState>,
+    pending: VecDeque<(Transaction, TransactionLabel)>,
+}
+
+impl<H: Handlers> Iterator for SpecTransactions<H> {
+    type Item = Result<(Transaction, TransactionLabel), ServerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(next) = self.pending.pop_front() {
+                return Some(Ok(next));
+            }
+            let mut state = self.state.take()?;
+            let spec = self.specs.next()?;
+            let spec_id = spec.id;
+            if let Err(e) = SpecProcessor::process_spec(
+                spec,
+                &state,
+                &self.hooks,
+                &self.discrepancy_policy,
+                self.suspense_routing,
+                &self.materiality_thresholds,
+            )
+            .and_then(|delta| state.step(delta))
+            {
+                return Some(Err(e));
+            }
+            self.progress.advance();
+            let label = state
+                .label_lookup
+                .get(&spec_id)
+                .cloned()
+                .unwrap_or(TransactionLabel {
+                    payee: "".into(),
+                    description: String::new(),
+                });
+            self.pending
+                .extend(state.transactions.drain(..).map(|tx| (tx, label.clone())));
+            self.state = Some(state);
+        }
+    }
+}
+
+impl<H: Handlers> IfrsLogic<H> for StandardIfrsLogic {
+    fn process(
+        &self,
+        input: FinancialRecords_Intermediate1<H>,
+        options: &ProcessOptions,
+    ) -> Result<FinancialRecords_Intermediate2<H>, ServerError> {
+        SpecProcessor::new(input, options).process()
+    }
+}
+
 macro_rules! amount_should_be_negative {
     ($amount:expr, $logic:expr, $id:expr) => {
         if $amount >= 0.0 {
@@ -197,10 +753,22 @@ macro_rules! amount_should_be_positive {
 }
 
 impl<H: Handlers> SpecProcessor<H> {
-    pub(crate) fn new(specs: FinancialRecords_Intermediate1<H>) -> Self {
-        Self { specs }
+    pub(crate) fn new(specs: FinancialRecords_Intermediate1<H>, options: &ProcessOptions) -> Self {
+        Self {
+            specs,
+            hooks: options.spec_hooks.clone(),
+            progress: options.progress.clone(),
+            interest_inference: options.interest_inference.clone(),
+            discrepancy_policy: options.discrepancy_policy,
+            suspense_routing: options.suspense_routing,
+            materiality_thresholds: options.materiality_thresholds.clone(),
+        }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "specs", skip_all, fields(specs = self.specs.transaction_specs.len()))
+    )]
     pub(crate) fn process(self) -> Result<FinancialRecords_Intermediate2<H>, ServerError> {
         let FinancialRecords_Intermediate1 {
             mut transaction_specs,
@@ -208,69 +776,89 @@ impl<H: Handlers> SpecProcessor<H> {
             commands,
         } = self.specs;
 
-        // Important for reimbursement tracking.
+        // Important for reimbursement and invoice tracking.
         transaction_specs.sort_by_key(|s| s.payment_date);
 
-        let transactions_fold_result =
-            transaction_specs
-                .into_iter()
-                .try_fold(FoldState::new(), |state, spec| {
-                    let delta = match &spec.accounting_logic {
-                        AccountingLogic::CommonStock { .. } => Self::process_common_stock(spec)?,
-                        AccountingLogic::ShareIssuanceCost { .. } => {
-                            Self::process_share_issuance_cost(spec)?
-                        }
-                        AccountingLogic::SimpleExpense(..) => Self::process_simple_expense(spec)?,
-                        AccountingLogic::Capitalize(..) => Self::process_capitalize(spec)?,
-                        AccountingLogic::Amortize(..) => Self::process_amortize(spec)?,
-                        AccountingLogic::FixedExpense(..) => Self::process_fixed_expense(spec)?,
-                        AccountingLogic::VariableExpenseInit { .. } => {
-                            Self::process_variable_expense_init(spec)?
-                        }
-                        AccountingLogic::VariableExpense(..) => {
-                            Self::process_variable_expense(spec, &state.expense_history_lookup)?
-                        }
-                        AccountingLogic::ImmaterialIncome(..) => {
-                            Self::process_immaterial_income(spec)?
-                        }
-                        AccountingLogic::ImmaterialExpense(..) => {
-                            Self::process_immaterial_expense(spec)?
-                        }
-                        AccountingLogic::Reimburse(..) => {
-                            Self::process_reimburse(spec, &state.reimbursement_state)?
-                        }
-                        AccountingLogic::ReimbursePartial { .. } => {
-                            Self::process_reimburse_partial(spec, &state.reimbursement_state)?
-                        }
-                        AccountingLogic::ClearVat { .. } => Self::process_clear_vat(spec)?,
-                    };
-                    state.step(delta)
-                })?;
+        let mut progress = PhaseProgress::start(
+            self.progress,
+            ProgressPhase::ProcessingSpecs,
+            transaction_specs.len(),
+        );
+        let mut state = FoldState::with_capacity(transaction_specs.len());
+        for spec in transaction_specs {
+            let delta = Self::process_spec(
+                spec,
+                &state,
+                &self.hooks,
+                &self.discrepancy_policy,
+                self.suspense_routing,
+                &self.materiality_thresholds,
+            )?;
+            state.step(delta)?;
+            progress.advance();
+        }
+
+        let FoldState {
+            mut transactions,
+            assertions: ext_assertions,
+            ledger_extensions,
+            mut label_lookup,
+            mut annotations_lookup,
+            reimbursement_state,
+            ..
+        } = state;
 
-        let assertions = assertion_specs
+        let posting_commodities = posting_commodities(&transactions);
+        let assertions: Vec<Assertion> = assertion_specs
             .into_iter()
             .map(|spec| {
+                let account: Account = spec.cash_handler.account().into();
+                let commodity = match spec.commodity {
+                    AssertionCommodity::Explicit(commodity) => commodity.commodity()?,
+                    AssertionCommodity::Column(currency) => currency.into(),
+                    AssertionCommodity::Infer(fallback) => match spec.cash_handler.currency() {
+                        Some(currency) => currency.into(),
+                        None => match posting_commodities.get(&account) {
+                            Some(commodities) if commodities.len() == 1 => commodities[0],
+                            _ => fallback.commodity()?,
+                        },
+                    },
+                };
                 Ok(Assertion {
                     date: spec.date,
-                    account: spec.cash_handler.account().into(),
+                    account,
                     balance: spec.balance,
-                    currency: spec.commodity.currency()?,
+                    commodity,
                 })
             })
-            .collect::<Result<Vec<Assertion>, ServerError>>()?
-            .into_iter()
-            .chain(transactions_fold_result.assertions.into_iter())
-            .collect();
+            .collect::<Result<_, ServerError>>()?;
+
+        if let Some(inference) = &self.interest_inference {
+            let inferred = infer_interest(&assertions, &transactions, inference)?;
+            if !inferred.is_empty() {
+                label_lookup.insert(
+                    INFERRED_INTEREST_SPEC_ID,
+                    TransactionLabel {
+                        payee: "Bank".into(),
+                        description: "Interest (inferred)".into(),
+                    },
+                );
+                annotations_lookup.insert(
+                    INFERRED_INTEREST_SPEC_ID,
+                    vec![Annotation::InferredInterest],
+                );
+                transactions.extend(inferred);
+            }
+        }
 
         Ok(FinancialRecords_Intermediate2 {
-            transactions: transactions_fold_result.transactions,
-            assertions,
+            transactions,
+            assertions: assertions.into_iter().chain(ext_assertions).collect(),
             commands,
-            ledger_extensions: transactions_fold_result.ledger_extensions,
-            label_lookup: transactions_fold_result.label_lookup,
-            annotations_lookup: transactions_fold_result.annotations_lookup,
-            unreimbursed_entries: transactions_fold_result
-                .reimbursement_state
+            ledger_extensions,
+            label_lookup,
+            annotations_lookup,
+            unreimbursed_entries: reimbursement_state
                 .into_iter()
                 .flat_map(|(account, entries)| {
                     entries
@@ -281,6 +869,297 @@ impl<H: Handlers> SpecProcessor<H> {
         })
     }
 
+    /// Process the transaction specs incrementally, yielding each spec's
+    /// transactions as soon as they are finalized. Assertions and commands are
+    /// ignored.
+    pub(crate) fn into_transactions(self) -> SpecTransactions<H> {
+        let mut transaction_specs = self.specs.transaction_specs;
+
+        // Important for reimbursement and invoice tracking.
+        transaction_specs.sort_by_key(|s| s.payment_date);
+
+        SpecTransactions {
+            progress: PhaseProgress::start(
+                self.progress,
+                ProgressPhase::ProcessingSpecs,
+                transaction_specs.len(),
+            ),
+            specs: transaction_specs.into_iter(),
+            hooks: self.hooks,
+            discrepancy_policy: self.discrepancy_policy,
+            suspense_routing: self.suspense_routing,
+            materiality_thresholds: self.materiality_thresholds,
+            state: Some(FoldState::new()),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Dispatch a single spec to its accounting logic, then run the hooks on
+    /// the result.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "spec",
+            level = "debug",
+            skip_all,
+            fields(spec_id = spec.id.0, payment_date = %spec.payment_date)
+        )
+    )]
+    fn process_spec(
+        mut spec: DecoratedTransactionSpec<H>,
+        state: &FoldState,
+        hooks: &[Arc<dyn SpecHook>],
+        discrepancy_policy: &DiscrepancyPolicy,
+        suspense_routing: bool,
+        materiality_thresholds: &HashMap<Commodity, f64>,
+    ) -> Result<Delta, ServerError> {
+        let spec_id = spec.id;
+        let payment_date = spec.payment_date;
+        Self::apply_materiality_threshold(&mut spec, materiality_thresholds)
+            .map_err(|e| with_spec_id(e, spec_id))?;
+        // IntercompanyTransfer tags its own postings (see
+        // `process_intercompany_transfer`).
+        let intercompany = spec.intercompany.clone().filter(|_| {
+            !matches!(
+                spec.accounting_logic,
+                AccountingLogic::IntercompanyTransfer { .. }
+            )
+        });
+        let dimensions = spec.dimensions.clone();
+        let status = spec.status;
+        let placeholders = spec
+            .annotations
+            .iter()
+            .any(has_placeholders)
+            .then(|| annotation_placeholders(&spec))
+            .transpose()
+            .map_err(|e| with_spec_id(e, spec_id))?;
+        let summary = (!hooks.is_empty())
+            .then(|| Self::summarize(&spec))
+            .transpose()
+            .map_err(|e| with_spec_id(e, spec_id))?;
+        let suspense = suspense_routing.then(|| SuspenseEntry::from_spec(&spec));
+        let mut delta = match (
+            Self::dispatch_spec(spec, state, discrepancy_policy),
+            suspense,
+        ) {
+            (Ok(delta), _) => delta,
+            (Err(e), Some(suspense))
+                if e.category() == Some(ErrorCategory::AccountingRuleViolation) =>
+            {
+                suspense
+                    .into_delta(e)
+                    .map_err(|e| with_spec_id(e, spec_id))?
+            }
+            (Err(e), _) => return Err(with_spec_id(e, spec_id)),
+        };
+        if intercompany.is_some() || !dimensions.is_empty() {
+            delta
+                .transactions
+                .iter_mut()
+                .chain(delta.ext_transactions.iter_mut())
+                .flat_map(|tx| tx.postings.iter_mut())
+                .for_each(|p| {
+                    if let Some(counterparty) = &intercompany {
+                        p.custom_tags
+                            .insert(INTERCOMPANY_TAG.to_string(), counterparty.clone());
+                    }
+                    p.custom_tags.extend(dimensions.clone());
+                });
+        }
+        if let Some(placeholders) = placeholders {
+            for annotation in &mut delta.annotations {
+                fill_placeholders(annotation, &placeholders)
+                    .map_err(|e| with_spec_id(e, spec_id))?;
+            }
+        }
+        if status != TransactionStatus::Unmarked {
+            delta
+                .transactions
+                .iter_mut()
+                .chain(delta.ext_transactions.iter_mut())
+                .filter(|tx| tx.status == TransactionStatus::Unmarked)
+                .for_each(|tx| tx.status = status);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            transactions = delta.transactions.len(),
+            ext_transactions = delta.ext_transactions.len(),
+            "spec processed"
+        );
+        let Some(summary) = summary else {
+            return Ok(delta);
+        };
+        let (mut outcome, rest) = delta.into_outcome(payment_date, summary);
+        for hook in hooks {
+            hook.after_spec(&mut outcome)
+                .map_err(|e| with_spec_id(e, spec_id))?;
+        }
+        Ok(Delta::from_outcome(outcome, rest))
+    }
+
+    /// Downgrade expenses under the materiality threshold of their commodity
+    /// to `ImmaterialExpense` (see `ProcessOptions::materiality_thresholds`).
+    fn apply_materiality_threshold(
+        spec: &mut DecoratedTransactionSpec<H>,
+        thresholds: &HashMap<Commodity, f64>,
+    ) -> Result<(), ServerError> {
+        if thresholds.is_empty() {
+            return Ok(());
+        }
+        let immaterial = match &spec.accounting_logic {
+            AccountingLogic::SimpleExpense(e) | AccountingLogic::FixedExpense(e) => {
+                AccountingLogic::ImmaterialExpense(e.clone())
+            }
+            AccountingLogic::FixedIncome(i) | AccountingLogic::AccrualIncome(i) => {
+                AccountingLogic::ImmaterialIncome(i.clone())
+            }
+            _ => return Ok(()),
+        };
+        let commodity = spec.commodity.commodity()?;
+        let Some(&threshold) = thresholds.get(&commodity) else {
+            return Ok(());
+        };
+        if spec.amount.abs() >= threshold {
+            return Ok(());
+        }
+        spec.accounting_logic = immaterial;
+        spec.annotations
+            .push(Annotation::BelowMaterialityThreshold(format_amount(
+                threshold, commodity, false,
+            )));
+        Ok(())
+    }
+
+    /// Summary of the spec passed to hooks (see `SpecOutcome::spec`).
+    fn summarize(spec: &DecoratedTransactionSpec<H>) -> Result<SpecSummary, ServerError> {
+        let target_account = match &spec.accounting_logic {
+            AccountingLogic::SimpleExpense(e)
+            | AccountingLogic::FixedExpense(e)
+            | AccountingLogic::VariableExpense(e)
+            | AccountingLogic::VariableExpenseInit { account: e, .. }
+            | AccountingLogic::ImmaterialExpense(e) => Some(e.account().into()),
+            AccountingLogic::ImmaterialIncome(i)
+            | AccountingLogic::FixedIncome(i)
+            | AccountingLogic::AccrualIncome(i)
+            | AccountingLogic::Invoice(i) => Some(i.account().into()),
+            AccountingLogic::InterestIncome => Some(BANK_INTEREST.clone().into()),
+            AccountingLogic::PayDeposit => {
+                Some(security_deposits_for(spec.payment_date, spec.accrual_end).into())
+            }
+            AccountingLogic::ProvisionForDoubtfulDebt(i)
+            | AccountingLogic::WriteOffReceivable(i)
+            | AccountingLogic::CollectInvoice(i) => Some(i.while_receivable().into()),
+            AccountingLogic::Capitalize(a)
+            | AccountingLogic::DisposeAsset(a)
+            | AccountingLogic::Impair(a)
+            | AccountingLogic::PurchaseInventory { item: a, .. } => Some(a.account().into()),
+            AccountingLogic::RecognizeCogs { item, .. } => {
+                Some(cost_of_goods_sold_for(item).into())
+            }
+            AccountingLogic::Grant { asset: None } => Some(GRANT_INCOME.clone().into()),
+            AccountingLogic::Grant { asset: Some(a) } => a.upon_accrual().map(Into::into),
+            AccountingLogic::IncomeTax { .. } => Some(CURRENT_TAX_EXPENSE.clone().into()),
+            AccountingLogic::SettleIncomeTax { fiscal_year } => {
+                Some(income_tax_payable_for(*fiscal_year).into())
+            }
+            AccountingLogic::IntercompanyTransfer { counterparty } => {
+                Some(due_from_group_entity(counterparty).into())
+            }
+            AccountingLogic::Amortize(a) | AccountingLogic::Depreciate { asset: a, .. } => {
+                a.upon_accrual().map(Into::into)
+            }
+            AccountingLogic::CommonStock { subscriber, .. } => Some(subscriber.account().into()),
+            AccountingLogic::ShareBuyback { .. }
+            | AccountingLogic::ReissueTreasuryShares { .. } => Some(TREASURY_STOCK.clone().into()),
+            _ => None,
+        };
+        Ok(SpecSummary {
+            accounting_logic: spec.accounting_logic.name(),
+            amount: spec.amount,
+            commodity: spec.commodity.commodity()?,
+            backing_account: spec.backing_account.account(),
+            target_account,
+        })
+    }
+
+    fn dispatch_spec(
+        spec: DecoratedTransactionSpec<H>,
+        state: &FoldState,
+        discrepancy_policy: &DiscrepancyPolicy,
+    ) -> Result<Delta, ServerError> {
+        match &spec.accounting_logic {
+            AccountingLogic::CommonStock { .. } => Self::process_common_stock(spec),
+            AccountingLogic::ShareIssuanceCost { .. } => Self::process_share_issuance_cost(spec),
+            AccountingLogic::ShareBuyback { .. } => Self::process_share_buyback(spec),
+            AccountingLogic::ReissueTreasuryShares { .. } => {
+                Self::process_reissue_treasury_shares(spec, &state.treasury_state, &state.balances)
+            }
+            AccountingLogic::SimpleExpense(..) => Self::process_simple_expense(spec),
+            AccountingLogic::Capitalize(..) => Self::process_capitalize(spec),
+            AccountingLogic::Amortize(..) => Self::process_amortize(spec),
+            AccountingLogic::Depreciate { .. } => Self::process_depreciate(spec),
+            AccountingLogic::DisposeAsset(..) => Self::process_dispose_asset(spec, &state.balances),
+            AccountingLogic::Impair(..) => Self::process_impair(spec, &state.balances),
+            AccountingLogic::FixedExpense(..) => Self::process_fixed_expense(spec),
+            AccountingLogic::VariableExpenseInit { .. } => {
+                Self::process_variable_expense_init(spec, discrepancy_policy)
+            }
+            AccountingLogic::VariableExpense(..) => Self::process_variable_expense(
+                spec,
+                &state.expense_history_lookup,
+                discrepancy_policy,
+            ),
+            AccountingLogic::ImmaterialIncome(..) => Self::process_immaterial_income(spec),
+            AccountingLogic::FixedIncome(..) => Self::process_fixed_income(spec),
+            AccountingLogic::AccrualIncome(..) => Self::process_accrual_income(spec),
+            AccountingLogic::InterestIncome => Self::process_interest_income(spec),
+            AccountingLogic::PayDeposit => Self::process_pay_deposit(spec),
+            AccountingLogic::RefundDeposit => {
+                Self::process_refund_deposit(spec, &state.deposit_state)
+            }
+            AccountingLogic::Invoice(..) => Self::process_invoice(spec),
+            AccountingLogic::CollectInvoice(..) => {
+                Self::process_collect_invoice(spec, &state.invoice_state)
+            }
+            AccountingLogic::ProvisionForDoubtfulDebt(..) => {
+                Self::process_provision_for_doubtful_debt(spec, &state.balances)
+            }
+            AccountingLogic::WriteOffReceivable(..) => {
+                Self::process_write_off_receivable(spec, &state.balances)
+            }
+            AccountingLogic::ImmaterialExpense(..) => Self::process_immaterial_expense(spec),
+            AccountingLogic::PurchaseInventory { .. } => {
+                Self::process_purchase_inventory(spec, &state.inventory_state)
+            }
+            AccountingLogic::RecognizeCogs { .. } => {
+                Self::process_recognize_cogs(spec, &state.inventory_state)
+            }
+            AccountingLogic::Grant { .. } => Self::process_grant(spec),
+            AccountingLogic::IncomeTax { .. } => Self::process_income_tax(spec),
+            AccountingLogic::SettleIncomeTax { .. } => {
+                Self::process_settle_income_tax(spec, &state.balances)
+            }
+            AccountingLogic::IntercompanyTransfer { .. } => {
+                Self::process_intercompany_transfer(spec, &state.balances)
+            }
+            AccountingLogic::Reimburse(..) => {
+                Self::process_reimburse(spec, &state.reimbursement_state)
+            }
+            AccountingLogic::ReimbursePartial { .. } => {
+                Self::process_reimburse_partial(spec, &state.reimbursement_state)
+            }
+            AccountingLogic::PayCardStatement(..) => {
+                Self::process_pay_card_statement(spec, &state.reimbursement_state)
+            }
+            AccountingLogic::RemeasureInvestment { .. } => {
+                Self::process_remeasure_investment(spec, &state.balances)
+            }
+            AccountingLogic::Borrowing { .. } => Self::process_borrowing(spec),
+            AccountingLogic::ClearVat { .. } => Self::process_clear_vat(spec),
+        }
+    }
+
     fn process_common_stock(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
         let DecoratedTransactionSpec {
             id,
@@ -298,6 +1177,9 @@ impl<H: Handlers> SpecProcessor<H> {
             commodity,
             backing_account,
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -318,14 +1200,15 @@ impl<H: Handlers> SpecProcessor<H> {
                     TransactionPosting::new(
                         s_handler.account().into(),
                         -amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                     TransactionPosting::new(
                         backing_account.account(),
                         amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                 ],
+                status: TransactionStatus::Unmarked,
             }]
         } else if payment_date > accrual_date {
             // Capital contribution was made later, so record unpaid share
@@ -347,14 +1230,15 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             s_handler.account().into(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::new(
                             account.clone(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
                 Transaction {
                     spec_id: id,
@@ -365,14 +1249,15 @@ impl<H: Handlers> SpecProcessor<H> {
                             account,
                             s_handler.account().into(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::new(
                             backing_account.account(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
             ]
         } else {
@@ -382,10 +1267,11 @@ impl<H: Handlers> SpecProcessor<H> {
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
             expense_history_delta: None,
+            state_delta: Vec::new(),
             reimbursement_state_delta: track_unreimbursed_entries(
                 &backing_account,
                 &transactions,
@@ -414,6 +1300,9 @@ impl<H: Handlers> SpecProcessor<H> {
             commodity,
             backing_account,
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -450,10 +1339,11 @@ impl<H: Handlers> SpecProcessor<H> {
                     TransactionPosting::new(
                         backing_account.account(),
                         -amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
-                    TransactionPosting::new(destination, amount.abs(), commodity.currency()?),
+                    TransactionPosting::new(destination, amount.abs(), commodity.commodity()?),
                 ],
+                status: TransactionStatus::Unmarked,
             }]
         } else if payment_date < accrual_date {
             // Record as prepaid, then clear on accrual.
@@ -466,15 +1356,16 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             backing_account.account(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::linked(
                             PREPAID_SHARE_ISSUANCE_COSTS.clone().into(),
                             destination.clone(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
                 Transaction {
                     spec_id: id,
@@ -484,10 +1375,11 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             PREPAID_SHARE_ISSUANCE_COSTS.clone().into(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
-                        TransactionPosting::new(destination, amount.abs(), commodity.currency()?),
+                        TransactionPosting::new(destination, amount.abs(), commodity.commodity()?),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
             ]
         } else {
@@ -501,14 +1393,15 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             SHARE_ISSUANCE_COSTS_PAYABLE.clone().into(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::new(
                             destination.clone(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
                 Transaction {
                     spec_id: id,
@@ -518,15 +1411,16 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             backing_account.account(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::linked(
                             SHARE_ISSUANCE_COSTS_PAYABLE.clone().into(),
                             destination,
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
             ]
         };
@@ -543,10 +1437,11 @@ impl<H: Handlers> SpecProcessor<H> {
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
             expense_history_delta: None,
+            state_delta: Vec::new(),
             reimbursement_state_delta: track_unreimbursed_entries(
                 &backing_account,
                 &transactions,
@@ -560,10 +1455,204 @@ impl<H: Handlers> SpecProcessor<H> {
         })
     }
 
-    fn process_simple_expense(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+    fn process_share_buyback(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
         let DecoratedTransactionSpec {
             id,
-            accrual_start: accrual_date,
+            accrual_start: _, // Ignored.
+            accrual_end: None,
+            payment_date,
+            accounting_logic: AccountingLogic::ShareBuyback { shares },
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "ShareBuyback", &id);
+        if shares <= 0.0 {
+            return Err(NonPositiveShareCount::new(
+                "ShareBuyback",
+                &description,
+                shares,
+            ));
+        }
+
+        // Debiting treasury stock tags the payment as a share buyback.
+        let posting_commodity = commodity.commodity()?;
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some(format!("Buy back shares ({})", shares).into()),
+            postings: vec![
+                TransactionPosting::new(TREASURY_STOCK.clone().into(), -amount, posting_commodity),
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+            ],
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: vec![StateDelta::Treasury(TreasuryStateDelta::Buyback {
+                commodity: posting_commodity,
+                shares,
+                cost: -amount,
+            })],
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
+    }
+
+    fn process_reissue_treasury_shares(
+        spec: DecoratedTransactionSpec<H>,
+        treasury_state: &TreasuryState,
+        balances: &Balances,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: None,
+            payment_date,
+            accounting_logic: AccountingLogic::ReissueTreasuryShares { shares },
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "ReissueTreasuryShares", &id);
+        if shares <= 0.0 {
+            return Err(NonPositiveShareCount::new(
+                "ReissueTreasuryShares",
+                &description,
+                shares,
+            ));
+        }
+
+        let posting_commodity = commodity.commodity()?;
+        let held = treasury_state
+            .get(&posting_commodity)
+            .cloned()
+            .unwrap_or_default();
+        if shares > held.shares {
+            return Err(TreasurySharesOverReissued::new(
+                &description,
+                shares,
+                held.shares,
+                &payment_date,
+            ));
+        }
+        let TreasuryReissue {
+            cost,
+            to_surplus,
+            to_retained_earnings,
+        } = reissue_treasury_shares(
+            &held,
+            shares,
+            amount,
+            balances.as_of(
+                &TREASURY_SHARE_SURPLUS.clone().into(),
+                posting_commodity,
+                payment_date,
+            )?,
+            &posting_commodity,
+        )?;
+
+        // IMPORTANT: The surplus and retained earnings postings are linked to
+        // an account tagged as share issuance, so the whole proceeds (and not
+        // only the cost credited to treasury stock) are traced as such.
+        let proceeds_source: Account = equity_tl(EquityClassification::RetainedEarningsOpt {
+            on_inflow: Some(CashflowTracingTag::CashInflowIssuanceShares),
+            on_outflow: Some(CashflowTracingTag::CashInflowIssuanceShares),
+        })
+        .into();
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some(format!("Reissue treasury shares ({})", shares).into()),
+            postings: vec![
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+                TransactionPosting::new(TREASURY_STOCK.clone().into(), -cost, posting_commodity),
+                TransactionPosting::linked(
+                    TREASURY_SHARE_SURPLUS.clone().into(),
+                    proceeds_source.clone(),
+                    -to_surplus,
+                    posting_commodity,
+                ),
+                TransactionPosting::linked(
+                    RETAINED_EARNINGS.clone().into(),
+                    proceeds_source,
+                    -to_retained_earnings,
+                    posting_commodity,
+                ),
+            ]
+            .into_iter()
+            .filter(|p| p.amount != 0.0)
+            .collect(),
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: vec![StateDelta::Treasury(TreasuryStateDelta::Reissue {
+                commodity: posting_commodity,
+                shares,
+                cost,
+            })],
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
+    }
+
+    fn process_simple_expense(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: accrual_date,
             accrual_end: None,
             payment_date,
             accounting_logic: AccountingLogic::SimpleExpense(e_handler),
@@ -573,6 +1662,9 @@ impl<H: Handlers> SpecProcessor<H> {
             commodity,
             backing_account,
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -593,14 +1685,15 @@ impl<H: Handlers> SpecProcessor<H> {
                     TransactionPosting::new(
                         backing_account.account(),
                         -amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                     TransactionPosting::new(
                         e_handler.account().into(),
                         amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                 ],
+                status: TransactionStatus::Unmarked,
             }]
         } else if payment_date < accrual_date {
             // Record prepaid expense, then clear on accrual.
@@ -613,15 +1706,16 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             backing_account.account(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::linked(
                             e_handler.while_prepaid().into(),
                             e_handler.account().into(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
                 Transaction {
                     spec_id: id,
@@ -631,14 +1725,15 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             e_handler.while_prepaid().into(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::new(
                             e_handler.account().into(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
             ]
         } else {
@@ -652,14 +1747,15 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             e_handler.while_payable().into(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::new(
                             e_handler.account().into(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
                 Transaction {
                     spec_id: id,
@@ -669,15 +1765,16 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             backing_account.account(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::linked(
                             e_handler.while_payable().into(),
                             e_handler.account().into(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
             ]
         };
@@ -685,10 +1782,11 @@ impl<H: Handlers> SpecProcessor<H> {
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
             expense_history_delta: None,
+            state_delta: Vec::new(),
             reimbursement_state_delta: track_unreimbursed_entries(
                 &backing_account,
                 &transactions,
@@ -715,6 +1813,9 @@ impl<H: Handlers> SpecProcessor<H> {
             commodity,
             backing_account,
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -735,14 +1836,15 @@ impl<H: Handlers> SpecProcessor<H> {
                     TransactionPosting::new(
                         backing_account.account(),
                         -amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                     TransactionPosting::new(
                         a_handler.account().into(),
                         amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                 ],
+                status: TransactionStatus::Unmarked,
             }]
         } else if payment_date < accrual_date {
             // Record prepaid asset, then clear on accrual.
@@ -754,7 +1856,7 @@ impl<H: Handlers> SpecProcessor<H> {
                     TransactionPosting::new(
                         backing_account.account(),
                         -amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                     // At this point, it's not linked, since the cash
                     // transaction is to pre-pay, and can't yet be
@@ -767,9 +1869,10 @@ impl<H: Handlers> SpecProcessor<H> {
                     TransactionPosting::new(
                         a_handler.while_prepaid().into(),
                         amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                 ],
+                status: TransactionStatus::Unmarked,
             }];
             if a_handler.account() != a_handler.while_prepaid() {
                 ts.push(Transaction {
@@ -780,14 +1883,15 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             a_handler.while_prepaid().into(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::non_cash_reclassification(
                             a_handler.account().into(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 });
             }
             ts
@@ -802,14 +1906,15 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             a_handler.while_payable().into(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::new(
                             a_handler.account().into(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
                 Transaction {
                     spec_id: id,
@@ -819,15 +1924,16 @@ impl<H: Handlers> SpecProcessor<H> {
                         TransactionPosting::new(
                             backing_account.account(),
                             -amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                         TransactionPosting::linked(
                             a_handler.while_payable().into(),
                             a_handler.account().into(),
                             amount.abs(),
-                            commodity.currency()?,
+                            commodity.commodity()?,
                         ),
                     ],
+                    status: TransactionStatus::Unmarked,
                 },
             ]
         };
@@ -835,10 +1941,11 @@ impl<H: Handlers> SpecProcessor<H> {
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
             expense_history_delta: None,
+            state_delta: Vec::new(),
             reimbursement_state_delta: track_unreimbursed_entries(
                 &backing_account,
                 &transactions,
@@ -865,6 +1972,9 @@ impl<H: Handlers> SpecProcessor<H> {
             commodity,
             backing_account,
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -889,6 +1999,9 @@ impl<H: Handlers> SpecProcessor<H> {
             commodity: commodity.clone(),
             backing_account: backing_account.clone(),
             annotations: annotations.clone(),
+            intercompany: None,
+            dimensions: BTreeMap::new(),
+            status: TransactionStatus::Unmarked,
             ext_transactions: Default::default(),
             ext_assertions: Default::default(),
             ext_raw: ext_raw.clone(),
@@ -898,6 +2011,10 @@ impl<H: Handlers> SpecProcessor<H> {
         let accrual_account = a_handler
             .upon_accrual()
             .ok_or_else(|| NonAmortizableAsset::new(&description))?;
+        let amortized_account: Account = a_handler
+            .accumulated_amortization()
+            .unwrap_or_else(|| a_handler.account())
+            .into();
 
         // Record the monthly amortization adjustments.
         for MonthlyAccrualAdjustment {
@@ -909,38 +2026,44 @@ impl<H: Handlers> SpecProcessor<H> {
             accrual_start,
             accrual_end,
             amount.abs(),
-            commodity.currency()?,
+            commodity.decimal_places()?,
+            commodity.rounding_mode(),
         )? {
             transactions.push(Transaction {
                 spec_id: id,
                 date: adjustment_date,
-                comment: Some(format!(
-                    "Amortization adjustment for {} - {}",
-                    period_start, period_end
-                )),
+                comment: Some(
+                    format!(
+                        "Amortization adjustment for {} - {}",
+                        period_start, period_end
+                    )
+                    .into(),
+                ),
                 postings: vec![
                     TransactionPosting::linked(
-                        a_handler.account().into(),
+                        amortized_account.clone(),
                         backing_account.account(),
                         -monthly_amort,
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                     TransactionPosting::new(
                         accrual_account.clone().into(),
                         monthly_amort,
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                 ],
+                status: TransactionStatus::Unmarked,
             });
         }
 
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
             expense_history_delta: None,
+            state_delta: Vec::new(),
             reimbursement_state_delta: track_unreimbursed_entries(
                 &backing_account,
                 &transactions,
@@ -954,19 +2077,27 @@ impl<H: Handlers> SpecProcessor<H> {
         })
     }
 
-    fn process_fixed_expense(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+    fn process_depreciate(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
         let DecoratedTransactionSpec {
             id,
             accrual_start,
             accrual_end: Some(accrual_end),
             payment_date,
-            accounting_logic: AccountingLogic::FixedExpense(e_handler),
+            accounting_logic:
+                AccountingLogic::Depreciate {
+                    asset: a_handler,
+                    method,
+                    residual_value,
+                },
             payee,
             description,
             amount,
             commodity,
             backing_account,
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -974,113 +2105,98 @@ impl<H: Handlers> SpecProcessor<H> {
         else {
             return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
         };
-        amount_should_be_negative!(amount, "FixedExpense", &id);
+        amount_should_be_negative!(amount, "Depreciate", &id);
+        validate_depreciation(
+            &description,
+            amount.abs(),
+            residual_value,
+            &method,
+            accrual_start,
+            accrual_end,
+        )?;
 
         let mut transactions = Vec::new();
-        let mut payable_sum = 0.0;
-        let mut prepaid_sum = 0.0;
+
+        // Record the capitalization.
+        let cap_delta = Self::process_capitalize(DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end: None,
+            payment_date,
+            accounting_logic: AccountingLogic::Capitalize(a_handler.clone()),
+            payee: payee.clone(),
+            description: description.clone(),
+            amount,
+            commodity: commodity.clone(),
+            backing_account: backing_account.clone(),
+            annotations: annotations.clone(),
+            intercompany: None,
+            dimensions: BTreeMap::new(),
+            status: TransactionStatus::Unmarked,
+            ext_transactions: Default::default(),
+            ext_assertions: Default::default(),
+            ext_raw: ext_raw.clone(),
+        })?;
+        transactions.extend(cap_delta.transactions);
+
+        let accrual_account = a_handler
+            .upon_accrual()
+            .ok_or_else(|| NonAmortizableAsset::new(&description))?;
+        let depreciated_account: Account = a_handler
+            .accumulated_amortization()
+            .unwrap_or_else(|| a_handler.account())
+            .into();
+
+        // Record the monthly depreciation adjustments.
         for MonthlyAccrualAdjustment {
             period_start,
             period_end,
-            adjustment_amount: period_expense,
+            adjustment_amount: monthly_depreciation,
             adjustment_date,
-        } in monthly_accrual_adjustments(
+        } in monthly_depreciation_adjustments(
             accrual_start,
             accrual_end,
             amount.abs(),
-            commodity.currency()?,
+            residual_value,
+            &method,
+            commodity.decimal_places()?,
+            commodity.rounding_mode(),
         )? {
-            if adjustment_date <= payment_date {
-                // Record accrual adjustment as payable, since the clearing
-                // transaction will occur in the future.
-                transactions.push(Transaction {
-                    spec_id: id,
-                    date: adjustment_date,
-                    comment: Some(format!(
-                        "Accrue fixed expense for {} - {}",
-                        period_start, period_end
-                    )),
-                    postings: vec![
-                        TransactionPosting::new(
-                            e_handler.while_payable().into(),
-                            -period_expense,
-                            commodity.currency()?,
-                        ),
-                        TransactionPosting::new(
-                            e_handler.account().into(),
-                            period_expense,
-                            commodity.currency()?,
-                        ),
-                    ],
-                });
-                payable_sum += period_expense;
-            } else {
-                // Record accrual adjustment as prepaid, since the clearing
-                // transaction will occur in the past.
-                transactions.push(Transaction {
-                    spec_id: id,
-                    date: adjustment_date,
-                    comment: Some(format!(
-                        "Accrue fixed expense for {} - {}",
+            transactions.push(Transaction {
+                spec_id: id,
+                date: adjustment_date,
+                comment: Some(
+                    format!(
+                        "Depreciation adjustment for {} - {}",
                         period_start, period_end
-                    )),
-                    postings: vec![
-                        TransactionPosting::new(
-                            e_handler.while_prepaid().into(),
-                            -period_expense,
-                            commodity.currency()?,
-                        ),
-                        TransactionPosting::new(
-                            e_handler.account().into(),
-                            period_expense,
-                            commodity.currency()?,
-                        ),
-                    ],
-                });
-                prepaid_sum += period_expense;
-            }
-        }
-
-        // Record prepaid / payable amounts on payment date.
-        //
-        // This is kind of confusing, but can think of this clearing transaction
-        // as happening anywhere before, during or after the accrual period --
-        // so this transaction may be before, in between, or after the
-        // transactions we added above. On this clearing date, we need to clear
-        // the payables already accrued up to that point, and pour the remainder
-        // into prepaid account (to be accrued in the later transactions).
-        transactions.push(Transaction {
-            spec_id: id,
-            date: payment_date,
-            comment: Some("Clear / pre-pay fixed expense".into()),
-            postings: vec![
-                TransactionPosting::new(
-                    backing_account.account(),
-                    -amount.abs(),
-                    commodity.currency()?,
+                    )
+                    .into(),
                 ),
-                TransactionPosting::linked(
-                    e_handler.while_prepaid().into(),
-                    e_handler.account().into(),
-                    prepaid_sum,
-                    commodity.currency()?,
-                ),
-                TransactionPosting::linked(
-                    e_handler.while_payable().into(),
-                    e_handler.account().into(),
-                    -payable_sum,
-                    commodity.currency()?,
-                ),
-            ],
-        });
+                postings: vec![
+                    TransactionPosting::linked(
+                        depreciated_account.clone(),
+                        backing_account.account(),
+                        -monthly_depreciation,
+                        commodity.commodity()?,
+                    ),
+                    TransactionPosting::new(
+                        accrual_account.clone().into(),
+                        monthly_depreciation,
+                        commodity.commodity()?,
+                    ),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+        }
 
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
             expense_history_delta: None,
+            state_delta: Vec::new(),
             reimbursement_state_delta: track_unreimbursed_entries(
                 &backing_account,
                 &transactions,
@@ -1094,94 +2210,169 @@ impl<H: Handlers> SpecProcessor<H> {
         })
     }
 
-    /// Initiates variable expense calculations with a manual estimate.
-    fn process_variable_expense_init(
+    fn process_dispose_asset(
         spec: DecoratedTransactionSpec<H>,
+        balances: &Balances,
     ) -> Result<Delta, ServerError> {
         let DecoratedTransactionSpec {
-            accrual_start,
-            accrual_end: Some(accrual_end),
-            accounting_logic:
-                AccountingLogic::VariableExpenseInit {
-                    account: ref e_handler,
-                    estimate,
-                },
-            ..
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: _,   // Ignored.
+            payment_date,
+            accounting_logic: AccountingLogic::DisposeAsset(a_handler),
+            payee,
+            description,
+            amount: proceeds,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
         } = spec
         else {
             return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
         };
+        if proceeds < 0.0 {
+            return Err(UnexpectedNegativeValue::new(proceeds, "DisposeAsset", &id));
+        }
 
-        let accrual_days = (accrual_end - accrual_start).num_days() + 1;
-        let init_daily = (estimate.abs() as f64) / (accrual_days as f64);
-
-        let e_handler = e_handler.clone();
-        Self::process_variable_expense_helper(spec, e_handler, init_daily, true)
-    }
-
-    /// Uses the past 90 days of historical data (prior to accrual date) to
-    /// compute the daily average. If VariableExpenseInit is less than 90 days
-    /// prior, the window instead starts at the init date. If there is not
-    /// enough historical data, it returns an error.
-    ///
-    /// NOTE: May go without saying, but the order of transaction specs
-    /// therefore matters.
-    fn process_variable_expense(
-        spec: DecoratedTransactionSpec<H>,
-        history_lookup: &HashMap<ExpenseAccount, ExpenseHistory>,
-    ) -> Result<Delta, ServerError> {
-        let DecoratedTransactionSpec {
-            accrual_start,
-            accounting_logic: AccountingLogic::VariableExpense(ref e_handler),
-            ref description,
-            ..
-        } = spec
-        else {
-            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        let posting_commodity = commodity.commodity()?;
+        let asset_account: Account = a_handler.account().into();
+        let accumulated_account: Option<Account> =
+            a_handler.accumulated_amortization().map(Into::into);
+        let is_disposed = |account: &Account| {
+            *account == asset_account || Some(account) == accumulated_account.as_ref()
         };
-
-        // Use the last 90 days of history before the accrual_date.
-        let expense_account = e_handler.account();
-        let records = history_lookup
-            .get(&expense_account)
-            .map(|v| v.price_records.as_slice())
-            .unwrap_or(&[]);
-        let Some(history_init_date) = history_lookup
-            .get(&expense_account)
-            .and_then(|v| v.init_date)
-        else {
-            return Err(VariableExpenseNoInit::new(description));
+        if let Some(later) = balances.first_entry_after(payment_date, is_disposed) {
+            return Err(EntriesAfterDisposal::new(
+                &description,
+                &later,
+                &payment_date,
+            ));
+        }
+        let balance_of =
+            |account: &Account| balances.as_of(account, posting_commodity, payment_date);
+        let cost = balance_of(&asset_account)?;
+        if cost <= 0.0 {
+            return Err(NothingToDispose::new(&description, &payment_date));
+        }
+        let accumulated = match &accumulated_account {
+            Some(account) => balance_of(account)?,
+            None => 0.0,
         };
-        let history_window_start =
-            std::cmp::max(history_init_date, accrual_start - Duration::days(90));
-        let history_window_end = accrual_start - Duration::days(1);
+        let carrying_amount = round_to_commodity_precision(cost + accumulated, &posting_commodity)?;
+        let gain = round_to_commodity_precision(proceeds - carrying_amount, &posting_commodity)?;
 
-        // Compute the average daily accrual rate over the 90-day window.
-        let daily_rate = compute_daily_average(records, history_window_start, history_window_end)
-            .ok_or_else(|| VariableExpenseNotEnoughHistoricalData::new(description))?;
+        let mut transactions = Vec::new();
 
-        let e_handler = e_handler.clone();
-        Self::process_variable_expense_helper(spec, e_handler, daily_rate, false)
+        // Clear the accumulated depreciation / amortization against the cost.
+        if let Some(accumulated_account) = accumulated_account.filter(|_| accumulated != 0.0) {
+            transactions.push(Transaction {
+                spec_id: id,
+                date: payment_date,
+                comment: Some(
+                    match a_handler.account().1 {
+                        AssetClassification::IntangibleAssets => {
+                            "Clear accumulated amortization on disposal"
+                        }
+                        _ => "Clear accumulated depreciation on disposal",
+                    }
+                    .into(),
+                ),
+                postings: vec![
+                    TransactionPosting::new(accumulated_account, -accumulated, posting_commodity),
+                    TransactionPosting::new(asset_account.clone(), accumulated, posting_commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+        }
+
+        // Record the proceeds against the asset, so they are traced as cash
+        // from investing activities.
+        if proceeds != 0.0 {
+            transactions.push(Transaction {
+                spec_id: id,
+                date: payment_date,
+                comment: Some("Proceeds from disposal".into()),
+                postings: vec![
+                    TransactionPosting::new(backing_account.account(), proceeds, posting_commodity),
+                    TransactionPosting::new(asset_account.clone(), -proceeds, posting_commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+        }
+
+        // Recognize the remaining balance as a gain or loss on sale, which is
+        // tagged for reclassification out of operating activities.
+        if gain != 0.0 {
+            let gain_or_loss_account: Account = if gain > 0.0 {
+                GAIN_ON_SALE_OF_ASSETS.clone().into()
+            } else {
+                LOSS_ON_SALE_OF_ASSETS.clone().into()
+            };
+            transactions.push(Transaction {
+                spec_id: id,
+                date: payment_date,
+                comment: Some(
+                    if gain > 0.0 {
+                        "Gain on disposal"
+                    } else {
+                        "Loss on disposal"
+                    }
+                    .into(),
+                ),
+                postings: vec![
+                    TransactionPosting::new(asset_account, gain, posting_commodity),
+                    TransactionPosting::new(gain_or_loss_account, -gain, posting_commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+        }
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
     }
 
-    fn process_variable_expense_helper(
+    fn process_impair(
         spec: DecoratedTransactionSpec<H>,
-        e_handler: H::E,
-        estimated_daily_rate: f64,
-        is_init: bool,
+        balances: &Balances,
     ) -> Result<Delta, ServerError> {
         let DecoratedTransactionSpec {
             id,
-            accrual_start,
-            accrual_end: Some(accrual_end),
+            accrual_start: _, // Ignored.
+            accrual_end: _,   // Ignored.
             payment_date,
-            accounting_logic: _,
+            accounting_logic: AccountingLogic::Impair(a_handler),
             payee,
             description,
-            amount,
+            amount: recoverable_amount,
             commodity,
-            backing_account,
+            backing_account: _, // Ignored.
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -1189,134 +2380,109 @@ impl<H: Handlers> SpecProcessor<H> {
         else {
             return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
         };
-        amount_should_be_negative!(amount, "VariableExpense", &id);
+        if recoverable_amount < 0.0 {
+            return Err(UnexpectedNegativeValue::new(
+                recoverable_amount,
+                "Impair",
+                &id,
+            ));
+        }
 
-        if payment_date <= accrual_end {
-            return Err(VariableExpenseInvalidPaymentDate::new(
+        let asset_account = a_handler.account();
+        if !matches!(
+            asset_account.1,
+            AssetClassification::PropertyPlantEquipment
+                | AssetClassification::IntangibleAssets
+                | AssetClassification::OtherNonCurrentAssets
+        ) {
+            return Err(NonImpairableAsset::new(&description));
+        }
+        let posting_commodity = commodity.commodity()?;
+        let asset_account: Account = asset_account.into();
+        let accumulated_account: Option<Account> =
+            a_handler.accumulated_amortization().map(Into::into);
+        // Depreciation is generated for the whole useful life up front, so it
+        // can't take the impairment into account.
+        let is_impaired = |account: &Account| {
+            *account == asset_account || Some(account) == accumulated_account.as_ref()
+        };
+        if let Some(later) = balances.first_entry_after(payment_date, is_impaired) {
+            return Err(EntriesAfterImpairment::new(
                 &description,
+                &later,
                 &payment_date,
-                &accrual_end,
             ));
         }
+        let balance_of =
+            |account: &Account| balances.as_of(account, posting_commodity, payment_date);
+        let carrying_amount = balance_of(&asset_account)?
+            + match &accumulated_account {
+                Some(account) => balance_of(account)?,
+                None => 0.0,
+            };
+        let impairment_loss =
+            round_to_commodity_precision(carrying_amount - recoverable_amount, &posting_commodity)?;
 
-        let currency = commodity.currency()?;
-        let accrual_days = (accrual_end - accrual_start).num_days() + 1;
-        let estimated_total = estimated_daily_rate * (accrual_days as f64);
-        let actual_daily_rate = amount.abs() / (accrual_days as f64);
-
-        // Break into monthly accrual periods.
-        let mut transactions = Vec::new();
-        for MonthlyAccrualAdjustment {
-            period_start,
-            period_end,
-            adjustment_amount: period_estimate,
-            adjustment_date,
-        } in monthly_accrual_adjustments(accrual_start, accrual_end, estimated_total, currency)?
-        {
-            transactions.push(Transaction {
+        // Only write down; reversals of impairment losses aren't supported.
+        let (transactions, notes) = if impairment_loss <= 0.0 {
+            (vec![], vec![])
+        } else {
+            let tx = Transaction {
                 spec_id: id,
-                date: adjustment_date,
-                comment: Some(format!(
-                    "Estimated expense accrual for {} - {}",
-                    period_start, period_end
-                )),
+                date: payment_date,
+                comment: Some("Impairment loss".into()),
                 postings: vec![
                     TransactionPosting::new(
-                        e_handler.while_payable().into(),
-                        -period_estimate,
-                        currency,
+                        IMPAIRMENT_LOSS.clone().into(),
+                        impairment_loss,
+                        posting_commodity,
                     ),
-                    TransactionPosting::new(e_handler.account().into(), period_estimate, currency),
-                ],
-            });
-        }
-
-        // Record any estimation discrepancies.
-        //
-        // Note, to ensure we don't have lingering pennies, the discrepancy must
-        // be calculated at the precision level of the currency.
-        let discrepancy = round_to_currency_precision(amount.abs(), &currency)?
-            - round_to_currency_precision(estimated_total, &currency)?;
-        if discrepancy.abs() >= commodity.precision_cutoff()? {
-            transactions.push(Transaction {
-                spec_id: id,
-                date: payment_date,
-                comment: Some("Correct estimate discrepancy".into()),
-                postings: vec![
                     TransactionPosting::new(
-                        e_handler.while_payable().into(),
-                        -discrepancy,
-                        currency,
+                        accumulated_account.unwrap_or(asset_account),
+                        -impairment_loss,
+                        posting_commodity,
                     ),
-                    TransactionPosting::new(e_handler.account().into(), discrepancy, currency),
                 ],
-            });
-        }
-
-        // Record the clearing transaction.
-        transactions.push(Transaction {
-            spec_id: id,
-            date: payment_date,
-            comment: Some("Clear payable expense".into()),
-            postings: vec![
-                TransactionPosting::new(backing_account.account(), -amount.abs(), currency),
-                TransactionPosting::linked(
-                    e_handler.while_payable().into(),
-                    e_handler.account().into(),
-                    amount.abs(),
-                    currency,
-                ),
-            ],
-        });
-
-        // Record this variable expense’s daily rate for future history.
-        let expense_history_delta = ExpenseHistoryDelta {
-            account: e_handler.account(),
-            price_record: ExpenseHistoryPriceRecord {
-                start: accrual_start,
-                end: accrual_end,
-                daily_rate: actual_daily_rate,
-            },
-            is_init,
+                status: TransactionStatus::Unmarked,
+            };
+            let note =
+                Annotation::Impairment(format_amount(recoverable_amount, posting_commodity, false));
+            (vec![tx], vec![note])
         };
 
-        // Tag this transaction, since the accounting logic deserves a note in
-        // the financial records.
-        let note = Annotation::VariableExpense;
-
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
-            expense_history_delta: Some(expense_history_delta),
-            reimbursement_state_delta: track_unreimbursed_entries(
-                &backing_account,
-                &transactions,
-                &ext_transactions,
-            )?,
             transactions,
             ext_transactions,
             ext_assertions,
             ext_raw,
-            annotations: annotations.into_iter().chain(once(note)).collect(),
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations: annotations.into_iter().chain(notes).collect(),
         })
     }
 
-    fn process_immaterial_income(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+    fn process_fixed_expense(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
         let DecoratedTransactionSpec {
             id,
-            accrual_start: _, // Ignored.
-            accrual_end: _,   // Ignored.
+            accrual_start,
+            accrual_end: Some(accrual_end),
             payment_date,
-            accounting_logic: AccountingLogic::ImmaterialIncome(i_handler),
+            accounting_logic: AccountingLogic::FixedExpense(e_handler),
             payee,
             description,
             amount,
             commodity,
             backing_account,
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -1324,59 +2490,2095 @@ impl<H: Handlers> SpecProcessor<H> {
         else {
             return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
         };
-        amount_should_be_positive!(amount, "ImmaterialIncome", &id);
+        amount_should_be_negative!(amount, "FixedExpense", &id);
 
-        let tx = Transaction {
+        let mut transactions = Vec::new();
+        let mut payable_sum = 0.0;
+        let mut prepaid_sum = 0.0;
+        for MonthlyAccrualAdjustment {
+            period_start,
+            period_end,
+            adjustment_amount: period_expense,
+            adjustment_date,
+        } in monthly_accrual_adjustments(
+            accrual_start,
+            accrual_end,
+            amount.abs(),
+            commodity.decimal_places()?,
+            commodity.rounding_mode(),
+        )? {
+            if adjustment_date <= payment_date {
+                // Record accrual adjustment as payable, since the clearing
+                // transaction will occur in the future.
+                transactions.push(Transaction {
+                    spec_id: id,
+                    date: adjustment_date,
+                    comment: Some(
+                        format!("Accrue fixed expense for {} - {}", period_start, period_end)
+                            .into(),
+                    ),
+                    postings: vec![
+                        TransactionPosting::new(
+                            e_handler.while_payable().into(),
+                            -period_expense,
+                            commodity.commodity()?,
+                        ),
+                        TransactionPosting::new(
+                            e_handler.account().into(),
+                            period_expense,
+                            commodity.commodity()?,
+                        ),
+                    ],
+                    status: TransactionStatus::Unmarked,
+                });
+                payable_sum += period_expense;
+            } else {
+                // Record accrual adjustment as prepaid, since the clearing
+                // transaction will occur in the past.
+                transactions.push(Transaction {
+                    spec_id: id,
+                    date: adjustment_date,
+                    comment: Some(
+                        format!("Accrue fixed expense for {} - {}", period_start, period_end)
+                            .into(),
+                    ),
+                    postings: vec![
+                        TransactionPosting::new(
+                            e_handler.while_prepaid().into(),
+                            -period_expense,
+                            commodity.commodity()?,
+                        ),
+                        TransactionPosting::new(
+                            e_handler.account().into(),
+                            period_expense,
+                            commodity.commodity()?,
+                        ),
+                    ],
+                    status: TransactionStatus::Unmarked,
+                });
+                prepaid_sum += period_expense;
+            }
+        }
+
+        // Record prepaid / payable amounts on payment date.
+        //
+        // This is kind of confusing, but can think of this clearing transaction
+        // as happening anywhere before, during or after the accrual period --
+        // so this transaction may be before, in between, or after the
+        // transactions we added above. On this clearing date, we need to clear
+        // the payables already accrued up to that point, and pour the remainder
+        // into prepaid account (to be accrued in the later transactions).
+        transactions.push(Transaction {
             spec_id: id,
             date: payment_date,
-            comment: None,
+            comment: Some("Clear / pre-pay fixed expense".into()),
             postings: vec![
                 TransactionPosting::new(
-                    i_handler.account().into(),
+                    backing_account.account(),
                     -amount.abs(),
-                    commodity.currency()?,
+                    commodity.commodity()?,
                 ),
-                TransactionPosting::new(
-                    backing_account.account(),
-                    amount.abs(),
-                    commodity.currency()?,
+                TransactionPosting::linked(
+                    e_handler.while_prepaid().into(),
+                    e_handler.account().into(),
+                    prepaid_sum,
+                    commodity.commodity()?,
+                ),
+                TransactionPosting::linked(
+                    e_handler.while_payable().into(),
+                    e_handler.account().into(),
+                    payable_sum,
+                    commodity.commodity()?,
                 ),
             ],
-        };
+            status: TransactionStatus::Unmarked,
+        });
 
-        // Tag this transaction, since the accounting logic deserves a note in
-        // the financial records.
-        let note = Annotation::ImmaterialIncome;
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
+    }
+
+    /// Initiates variable expense calculations with a manual estimate.
+    fn process_variable_expense_init(
+        spec: DecoratedTransactionSpec<H>,
+        discrepancy_policy: &DiscrepancyPolicy,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            accrual_end: Some(_),
+            accounting_logic:
+                AccountingLogic::VariableExpenseInit {
+                    account: ref e_handler,
+                    estimate,
+                },
+            ..
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+
+        let e_handler = e_handler.clone();
+        Self::process_variable_expense_helper(
+            spec,
+            e_handler,
+            Estimate::Total(estimate as f64),
+            true,
+            discrepancy_policy,
+        )
+    }
+
+    /// Uses the past 90 days of historical data (prior to accrual date) to
+    /// compute the daily average. If VariableExpenseInit is less than 90 days
+    /// prior, the window instead starts at the init date. If there is not
+    /// enough historical data, it returns an error.
+    ///
+    /// NOTE: May go without saying, but the order of transaction specs
+    /// therefore matters.
+    fn process_variable_expense(
+        spec: DecoratedTransactionSpec<H>,
+        history_lookup: &HashMap<Account, ExpenseHistory>,
+        discrepancy_policy: &DiscrepancyPolicy,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            accrual_start,
+            accounting_logic: AccountingLogic::VariableExpense(ref e_handler),
+            ref description,
+            ..
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+
+        // Use the last 90 days of history before the accrual_date.
+        let Some(history) = history_lookup
+            .get(&e_handler.account().into())
+            .filter(|v| v.init_date.is_some())
+        else {
+            return Err(VariableExpenseNoInit::new(description));
+        };
+        let daily_rate = history
+            .average_daily_rate(accrual_start, 90)
+            .ok_or_else(|| VariableExpenseNotEnoughHistoricalData::new(description))?;
+
+        let e_handler = e_handler.clone();
+        Self::process_variable_expense_helper(
+            spec,
+            e_handler,
+            Estimate::DailyRate(daily_rate),
+            false,
+            discrepancy_policy,
+        )
+    }
+
+    fn process_variable_expense_helper(
+        spec: DecoratedTransactionSpec<H>,
+        e_handler: H::E,
+        estimate: Estimate,
+        is_init: bool,
+        discrepancy_policy: &DiscrepancyPolicy,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end: Some(accrual_end),
+            payment_date,
+            accounting_logic: _,
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "VariableExpense", &id);
+
+        if payment_date <= accrual_end {
+            return Err(VariableExpenseInvalidPaymentDate::new(
+                &description,
+                &payment_date,
+                &accrual_end,
+            ));
+        }
+
+        let (transactions, price_record) = EstimateTrueUp {
+            spec_id: id,
+            accrual_start,
+            accrual_end,
+            payment_date,
+            account: e_handler.account().into(),
+            accrued: e_handler.while_payable().into(),
+            payer: backing_account.account(),
+            commodity: &commodity,
+            estimate,
+            schedule: AccrualSchedule::Monthly,
+            true_up: discrepancy_policy,
+            kind: "expense",
+        }
+        .book(amount)?;
+
+        // Record this variable expense’s daily rate for future history.
+        let expense_history_delta = ExpenseHistoryDelta {
+            account: e_handler.account().into(),
+            price_record,
+            is_init,
+        };
+
+        // Tag this transaction, since the accounting logic deserves a note in
+        // the financial records.
+        let note = Annotation::VariableExpense;
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: Some(expense_history_delta),
+            state_delta: Vec::new(),
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations: annotations.into_iter().chain(once(note)).collect(),
+        })
+    }
+
+    fn process_fixed_income(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end: Some(accrual_end),
+            payment_date,
+            accounting_logic: AccountingLogic::FixedIncome(i_handler),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "FixedIncome", &id);
+
+        let mut transactions = Vec::new();
+        let mut receivable_sum = 0.0;
+        let mut deferred_sum = 0.0;
+        for MonthlyAccrualAdjustment {
+            period_start,
+            period_end,
+            adjustment_amount: period_income,
+            adjustment_date,
+        } in monthly_accrual_adjustments(
+            accrual_start,
+            accrual_end,
+            amount.abs(),
+            commodity.decimal_places()?,
+            commodity.rounding_mode(),
+        )? {
+            // Same as for fixed expenses: earned before payment is receivable,
+            // and earned after payment was deferred.
+            let (counter_account, counter_sum): (Account, _) = if adjustment_date <= payment_date {
+                (i_handler.while_receivable().into(), &mut receivable_sum)
+            } else {
+                (i_handler.while_prepaid().into(), &mut deferred_sum)
+            };
+            transactions.push(Transaction {
+                spec_id: id,
+                date: adjustment_date,
+                comment: Some(
+                    format!("Accrue fixed income for {} - {}", period_start, period_end).into(),
+                ),
+                postings: vec![
+                    TransactionPosting::new(
+                        i_handler.account().into(),
+                        -period_income,
+                        commodity.commodity()?,
+                    ),
+                    TransactionPosting::new(counter_account, period_income, commodity.commodity()?),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+            *counter_sum += period_income;
+        }
+
+        // Collect the receivable accrued up to the payment date, and defer the
+        // remainder (see `process_fixed_expense`).
+        transactions.push(Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some("Collect / defer fixed income".into()),
+            postings: vec![
+                TransactionPosting::new(
+                    backing_account.account(),
+                    amount.abs(),
+                    commodity.commodity()?,
+                ),
+                TransactionPosting::linked(
+                    i_handler.while_prepaid().into(),
+                    i_handler.account().into(),
+                    -deferred_sum,
+                    commodity.commodity()?,
+                ),
+                TransactionPosting::linked(
+                    i_handler.while_receivable().into(),
+                    i_handler.account().into(),
+                    -receivable_sum,
+                    commodity.commodity()?,
+                ),
+            ],
+            status: TransactionStatus::Unmarked,
+        });
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_accrual_income(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: accrual_date,
+            accrual_end: None,
+            payment_date,
+            accounting_logic: AccountingLogic::AccrualIncome(i_handler),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "AccrualIncome", &id);
+
+        let transactions = if payment_date == accrual_date {
+            // Earned and received on the same day.
+            vec![Transaction {
+                spec_id: id,
+                date: payment_date,
+                comment: None,
+                postings: vec![
+                    TransactionPosting::new(
+                        i_handler.account().into(),
+                        -amount.abs(),
+                        commodity.commodity()?,
+                    ),
+                    TransactionPosting::new(
+                        backing_account.account(),
+                        amount.abs(),
+                        commodity.commodity()?,
+                    ),
+                ],
+                status: TransactionStatus::Unmarked,
+            }]
+        } else {
+            // Received in advance: deferred until earned. Received in arrears:
+            // receivable until received.
+            let counter_account: Account = if payment_date < accrual_date {
+                i_handler.while_prepaid().into()
+            } else {
+                i_handler.while_receivable().into()
+            };
+            let (receive_comment, earn_comment) = if payment_date < accrual_date {
+                (
+                    "Defer income received in advance",
+                    "Recognize deferred income",
+                )
+            } else {
+                ("Collect receivable income", "Accrue receivable income")
+            };
+            vec![
+                Transaction {
+                    spec_id: id,
+                    date: payment_date,
+                    comment: Some(receive_comment.into()),
+                    postings: vec![
+                        TransactionPosting::linked(
+                            counter_account.clone(),
+                            i_handler.account().into(),
+                            -amount.abs(),
+                            commodity.commodity()?,
+                        ),
+                        TransactionPosting::new(
+                            backing_account.account(),
+                            amount.abs(),
+                            commodity.commodity()?,
+                        ),
+                    ],
+                    status: TransactionStatus::Unmarked,
+                },
+                Transaction {
+                    spec_id: id,
+                    date: accrual_date,
+                    comment: Some(earn_comment.into()),
+                    postings: vec![
+                        TransactionPosting::new(
+                            i_handler.account().into(),
+                            -amount.abs(),
+                            commodity.commodity()?,
+                        ),
+                        TransactionPosting::new(
+                            counter_account,
+                            amount.abs(),
+                            commodity.commodity()?,
+                        ),
+                    ],
+                    status: TransactionStatus::Unmarked,
+                },
+            ]
+        };
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_provision_for_doubtful_debt(
+        spec: DecoratedTransactionSpec<H>,
+        balances: &Balances,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: _,   // Ignored.
+            payment_date,
+            accounting_logic: AccountingLogic::ProvisionForDoubtfulDebt(i_handler),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account: _, // Ignored.
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+
+        let posting_commodity = commodity.commodity()?;
+        let allowance_account: Account =
+            allowance_for_doubtful_debts_for(&i_handler.while_receivable()).into();
+
+        let transactions = if amount < 0.0 {
+            vec![Transaction {
+                spec_id: id,
+                date: payment_date,
+                comment: Some("Provision for doubtful debts".into()),
+                postings: vec![
+                    TransactionPosting::new(BAD_DEBTS.clone().into(), -amount, posting_commodity),
+                    TransactionPosting::new(allowance_account, amount, posting_commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            }]
+        } else if amount > 0.0 {
+            // The allowance is a contra-asset, so has a credit balance.
+            let allowance = -balances.as_of(&allowance_account, posting_commodity, payment_date)?;
+            if amount > allowance {
+                return Err(AllowanceOverReversed::new(
+                    &description,
+                    &format_amount(amount, posting_commodity, false),
+                    &format_amount(allowance, posting_commodity, false),
+                    &payment_date,
+                ));
+            }
+            vec![Transaction {
+                spec_id: id,
+                date: payment_date,
+                comment: Some("Reversal of provision for doubtful debts".into()),
+                postings: vec![
+                    TransactionPosting::new(allowance_account, amount, posting_commodity),
+                    TransactionPosting::new(BAD_DEBTS.clone().into(), -amount, posting_commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            }]
+        } else {
+            vec![]
+        };
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_write_off_receivable(
+        spec: DecoratedTransactionSpec<H>,
+        balances: &Balances,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: _,   // Ignored.
+            payment_date,
+            accounting_logic: AccountingLogic::WriteOffReceivable(i_handler),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account: _, // Ignored.
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "WriteOffReceivable", &id);
+
+        let posting_commodity = commodity.commodity()?;
+        let receivable_account: Account = i_handler.while_receivable().into();
+        let allowance_account: Account =
+            allowance_for_doubtful_debts_for(&i_handler.while_receivable()).into();
+        let allowance = -balances.as_of(&allowance_account, posting_commodity, payment_date)?;
+        let written_off = -amount;
+
+        // Use up the allowance first; any excess wasn't provided for, so is
+        // expensed directly. The receivable itself isn't checked, since the
+        // income it originates from may be paid (so processed) later.
+        let from_allowance = written_off.min(allowance.max(0.0));
+        let from_expense =
+            round_to_commodity_precision(written_off - from_allowance, &posting_commodity)?;
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some("Write-off of receivable".into()),
+            postings: vec![
+                TransactionPosting::new(allowance_account, from_allowance, posting_commodity),
+                TransactionPosting::new(BAD_DEBTS.clone().into(), from_expense, posting_commodity),
+                TransactionPosting::new(receivable_account, amount, posting_commodity),
+            ]
+            .into_iter()
+            .filter(|p| p.amount != 0.0)
+            .collect(),
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_interest_income(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end: Some(accrual_end),
+            payment_date,
+            accounting_logic: AccountingLogic::InterestIncome,
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "InterestIncome", &id);
+        if payment_date < accrual_end {
+            return Err(InterestCreditedBeforeAccrualEnd::new(
+                &description,
+                &payment_date.to_string(),
+                &accrual_end.to_string(),
+            ));
+        }
+
+        let posting_commodity = commodity.commodity()?;
+        let mut transactions = monthly_accrual_adjustments(
+            accrual_start,
+            accrual_end,
+            amount,
+            commodity.decimal_places()?,
+            commodity.rounding_mode(),
+        )?
+        .into_iter()
+        .map(
+            |MonthlyAccrualAdjustment {
+                 period_start,
+                 period_end,
+                 adjustment_amount: period_interest,
+                 adjustment_date,
+             }| Transaction {
+                spec_id: id,
+                date: adjustment_date,
+                comment: Some(
+                    format!("Accrue interest for {} - {}", period_start, period_end).into(),
+                ),
+                postings: vec![
+                    TransactionPosting::new(
+                        BANK_INTEREST.clone().into(),
+                        -period_interest,
+                        posting_commodity,
+                    ),
+                    TransactionPosting::new(
+                        INTEREST_RECEIVABLE.clone().into(),
+                        period_interest,
+                        posting_commodity,
+                    ),
+                ],
+                status: TransactionStatus::Unmarked,
+            },
+        )
+        .collect::<Vec<_>>();
+
+        // All of it was accrued by the credit date.
+        transactions.push(Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some("Interest credited".into()),
+            postings: vec![
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+                TransactionPosting::linked(
+                    INTEREST_RECEIVABLE.clone().into(),
+                    BANK_INTEREST.clone().into(),
+                    -amount,
+                    posting_commodity,
+                ),
+            ],
+            status: TransactionStatus::Unmarked,
+        });
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_pay_deposit(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: refund_date,
+            payment_date,
+            accounting_logic: AccountingLogic::PayDeposit,
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "PayDeposit", &id);
+
+        // Long-term deposits are investing cash flows (tagged by their
+        // classification), short-term ones working capital.
+        let posting_commodity = commodity.commodity()?;
+        let deposit_account = security_deposits_for(payment_date, refund_date);
+        let mut transactions = vec![Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some("Pay deposit".into()),
+            postings: vec![
+                TransactionPosting::new(deposit_account.clone().into(), -amount, posting_commodity),
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+            ],
+            status: TransactionStatus::Unmarked,
+        }];
+
+        // Reclassify a long-term deposit as short-term at the last year end
+        // within 12 months of its expected refund.
+        let reclassified_on = match refund_date {
+            Some(refund_date) if deposit_account == *LONG_TERM_SECURITY_DEPOSITS => {
+                NaiveDate::from_ymd_opt(refund_date.year() - 1, 12, 31)
+                    .filter(|year_end| *year_end > payment_date)
+            }
+            _ => None,
+        };
+        if let Some(year_end) = reclassified_on {
+            transactions.push(reclassify_deposit(
+                id,
+                year_end,
+                "Reclassify deposit as current",
+                -amount,
+                posting_commodity,
+            ));
+        }
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: vec![StateDelta::Deposit(DepositStateDelta::Pay {
+                key: (payee.name(), posting_commodity),
+                deposit: OpenItem {
+                    spec_id: id,
+                    outstanding_amount: -amount,
+                    detail: HeldDeposit {
+                        account: deposit_account,
+                        reclassified_on,
+                    },
+                },
+            })],
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
+    }
+
+    fn process_refund_deposit(
+        spec: DecoratedTransactionSpec<H>,
+        deposit_state: &DepositState,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: None,
+            payment_date,
+            accounting_logic: AccountingLogic::RefundDeposit,
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "RefundDeposit", &id);
+
+        let posting_commodity = commodity.commodity()?;
+        let key = (payee.name(), posting_commodity);
+        let no_deposits = VecDeque::new();
+        let deposits = deposit_state.get(&key).unwrap_or(&no_deposits);
+        let outstanding = deposits.outstanding_amount(&posting_commodity)?;
+        if amount > outstanding {
+            return Err(DepositOverRefunded::new(
+                &description,
+                &format_amount(amount, posting_commodity, false),
+                &format_amount(outstanding, posting_commodity, false),
+                &key.0,
+            ));
+        }
+
+        // Refund the open deposits oldest first, from the account each is
+        // held in by then. A deposit refunded before its reclassification as
+        // short-term is refunded as long-term, and the reclassification of the
+        // refunded part reversed.
+        let settled = deposits.peek_settled_items(amount, &posting_commodity)?;
+        let mut postings = vec![TransactionPosting::new(
+            backing_account.account(),
+            amount,
+            posting_commodity,
+        )];
+        let mut reversals = Vec::new();
+        for (deposit, part) in &settled {
+            let account = match deposit.detail.reclassified_on {
+                Some(year_end) if year_end < payment_date => SHORT_TERM_SECURITY_DEPOSITS.clone(),
+                Some(year_end) => {
+                    reversals.push(reclassify_deposit(
+                        id,
+                        year_end,
+                        "Reverse reclassification of refunded deposit",
+                        -part,
+                        posting_commodity,
+                    ));
+                    deposit.detail.account.clone()
+                }
+                None => deposit.detail.account.clone(),
+            };
+            postings.push(TransactionPosting::new(
+                account.into(),
+                -part,
+                posting_commodity,
+            ));
+        }
+
+        let transactions = once(Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some(format!("Refund {}", settled_items("deposit", &settled)).into()),
+            postings,
+            status: TransactionStatus::Unmarked,
+        })
+        .chain(reversals)
+        .collect::<Vec<_>>();
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: vec![StateDelta::Deposit(DepositStateDelta::Refund {
+                key,
+                amount,
+            })],
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
+    }
+
+    fn process_invoice(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: None,
+            payment_date: issue_date,
+            accounting_logic: AccountingLogic::Invoice(i_handler),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account: _, // Ignored.
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "Invoice", &id);
+
+        let posting_commodity = commodity.commodity()?;
+        let receivable_account = i_handler.while_receivable();
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: issue_date,
+            comment: Some("Issue invoice".into()),
+            postings: vec![
+                TransactionPosting::new(i_handler.account().into(), -amount, posting_commodity),
+                TransactionPosting::new(
+                    receivable_account.clone().into(),
+                    amount,
+                    posting_commodity,
+                ),
+            ],
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: vec![StateDelta::Invoice(InvoiceStateDelta::Issue {
+                key: (receivable_account, payee.name(), posting_commodity),
+                invoice: OpenItem {
+                    spec_id: id,
+                    outstanding_amount: amount,
+                    detail: (),
+                },
+            })],
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_collect_invoice(
+        spec: DecoratedTransactionSpec<H>,
+        invoice_state: &InvoiceState,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: None,
+            payment_date,
+            accounting_logic: AccountingLogic::CollectInvoice(i_handler),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "CollectInvoice", &id);
+
+        let posting_commodity = commodity.commodity()?;
+        let key = (
+            i_handler.while_receivable(),
+            payee.name(),
+            posting_commodity,
+        );
+        let no_invoices = VecDeque::new();
+        let invoices = invoice_state.get(&key).unwrap_or(&no_invoices);
+        let outstanding = invoices.outstanding_amount(&posting_commodity)?;
+        if amount > outstanding {
+            return Err(InvoiceOverCollected::new(
+                &description,
+                &format_amount(amount, posting_commodity, false),
+                &format_amount(outstanding, posting_commodity, false),
+                &key.1,
+            ));
+        }
+
+        // The invoices (partly) settled, oldest first.
+        let collected = settled_items(
+            "invoice",
+            &invoices.peek_settled_items(amount, &posting_commodity)?,
+        );
+
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some(format!("Collect {}", collected).into()),
+            postings: vec![
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+                TransactionPosting::linked(
+                    key.0.clone().into(),
+                    i_handler.account().into(),
+                    -amount,
+                    posting_commodity,
+                ),
+            ],
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: vec![StateDelta::Invoice(InvoiceStateDelta::Collect {
+                key,
+                amount,
+            })],
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_immaterial_income(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: _,   // Ignored.
+            payment_date,
+            accounting_logic: AccountingLogic::ImmaterialIncome(i_handler),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "ImmaterialIncome", &id);
+
+        let tx = Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: None,
+            postings: vec![
+                TransactionPosting::new(
+                    i_handler.account().into(),
+                    -amount.abs(),
+                    commodity.commodity()?,
+                ),
+                TransactionPosting::new(
+                    backing_account.account(),
+                    amount.abs(),
+                    commodity.commodity()?,
+                ),
+            ],
+            status: TransactionStatus::Unmarked,
+        };
+
+        // Tag this transaction, since the accounting logic deserves a note in
+        // the financial records.
+        let note = Annotation::ImmaterialIncome;
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions: vec![tx],
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations: annotations.into_iter().chain(once(note)).collect(),
+        })
+    }
+
+    fn process_immaterial_expense(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: _,   // Ignored.
+            payment_date,
+            accounting_logic: AccountingLogic::ImmaterialExpense(e_handler),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "ImmaterialExpense", &id);
+
+        let tx = Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: None,
+            postings: vec![
+                TransactionPosting::new(
+                    backing_account.account(),
+                    -amount.abs(),
+                    commodity.commodity()?,
+                ),
+                TransactionPosting::new(
+                    e_handler.account().into(),
+                    amount.abs(),
+                    commodity.commodity()?,
+                ),
+            ],
+            status: TransactionStatus::Unmarked,
+        };
+
+        // Tag this transaction, since the accounting logic deserves a note in
+        // the financial records.
+        let note = Annotation::ImmaterialExpense;
+
+        let transactions = vec![tx];
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations: annotations.into_iter().chain(once(note)).collect(),
+        })
+    }
+
+    fn process_purchase_inventory(
+        spec: DecoratedTransactionSpec<H>,
+        inventory_state: &InventoryState,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: None,
+            payment_date,
+            accounting_logic:
+                AccountingLogic::PurchaseInventory {
+                    item,
+                    quantity,
+                    costing,
+                },
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "PurchaseInventory", &id);
+        let inventory_account = inventory_account_of(&item, "PurchaseInventory", &description)?;
+        if quantity <= 0.0 {
+            return Err(NonPositiveInventoryQuantity::new(
+                "PurchaseInventory",
+                &description,
+                quantity,
+            ));
+        }
+
+        let posting_commodity = commodity.commodity()?;
+        let key = (inventory_account, posting_commodity);
+        if let Some(stock) = inventory_state.get(&key) {
+            if stock.costing != costing {
+                return Err(InventoryCostingMismatch::new(
+                    &description,
+                    &costing,
+                    &stock.costing,
+                ));
+            }
+        }
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some(format!("Purchase inventory (quantity {})", quantity).into()),
+            postings: vec![
+                TransactionPosting::new(key.0.clone().into(), -amount, posting_commodity),
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+            ],
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: vec![StateDelta::Inventory(InventoryStateDelta::Purchase {
+                key,
+                costing,
+                lot: InventoryLot {
+                    quantity,
+                    cost: -amount,
+                },
+            })],
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
+    }
+
+    fn process_recognize_cogs(
+        spec: DecoratedTransactionSpec<H>,
+        inventory_state: &InventoryState,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: None,
+            payment_date: sale_date,
+            accounting_logic: AccountingLogic::RecognizeCogs { item, quantity },
+            payee,
+            description,
+            amount: _, // Ignored.
+            commodity,
+            backing_account: _, // Ignored.
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        let inventory_account = inventory_account_of(&item, "RecognizeCogs", &description)?;
+        if quantity <= 0.0 {
+            return Err(NonPositiveInventoryQuantity::new(
+                "RecognizeCogs",
+                &description,
+                quantity,
+            ));
+        }
+
+        let posting_commodity = commodity.commodity()?;
+        let key = (inventory_account, posting_commodity);
+        let sold = inventory_state
+            .get(&key)
+            .map(|stock| sell_inventory(stock, quantity, &posting_commodity))
+            .transpose()?
+            .flatten();
+        let Some((cost, remaining)) = sold else {
+            return Err(InventoryOversold::new(
+                &description,
+                quantity,
+                inventory_state.get(&key).map_or(0.0, |stock| {
+                    stock.lots.iter().map(|lot| lot.quantity).sum::<f64>()
+                }),
+                &sale_date,
+            ));
+        };
+
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: sale_date,
+            comment: Some(format!("Cost of goods sold (quantity {})", quantity).into()),
+            postings: vec![
+                TransactionPosting::new(
+                    cost_of_goods_sold_for(&item).into(),
+                    cost,
+                    posting_commodity,
+                ),
+                TransactionPosting::new(key.0.clone().into(), -cost, posting_commodity),
+            ],
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: vec![StateDelta::Inventory(InventoryStateDelta::Sell {
+                key,
+                quantity,
+                cost,
+                remaining,
+            })],
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_grant(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end: Some(accrual_end),
+            payment_date,
+            accounting_logic: AccountingLogic::Grant { asset: a_handler },
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_positive!(amount, "Grant", &id);
+
+        // Income approach: deferred income released to grant income. Asset
+        // deduction approach: deducted from the asset, and released against
+        // its depreciation (so depreciation is effectively on the net cost).
+        let (deferred_account, release_account): (Account, Account) = match &a_handler {
+            None => (
+                DEFERRED_GRANT_INCOME.clone().into(),
+                GRANT_INCOME.clone().into(),
+            ),
+            Some(a_handler) => (
+                deducted_grants_for(&a_handler.account()).into(),
+                a_handler
+                    .upon_accrual()
+                    .ok_or_else(|| NonAmortizableAsset::new(&description))?
+                    .into(),
+            ),
+        };
+        let posting_commodity = commodity.commodity()?;
+
+        let mut transactions = Vec::new();
+        let mut receivable_sum = 0.0;
+        let mut deferred_sum = 0.0;
+        for MonthlyAccrualAdjustment {
+            period_start,
+            period_end,
+            adjustment_amount: period_release,
+            adjustment_date,
+        } in monthly_accrual_adjustments(
+            accrual_start,
+            accrual_end,
+            amount,
+            commodity.decimal_places()?,
+            commodity.rounding_mode(),
+        )? {
+            // Same as for fixed income: released before receipt is receivable,
+            // and released after receipt was deferred.
+            let (counter_account, counter_sum): (Account, _) = if adjustment_date <= payment_date {
+                (GRANTS_RECEIVABLE.clone().into(), &mut receivable_sum)
+            } else {
+                (deferred_account.clone(), &mut deferred_sum)
+            };
+            transactions.push(Transaction {
+                spec_id: id,
+                date: adjustment_date,
+                comment: Some(
+                    format!("Release grant for {} - {}", period_start, period_end).into(),
+                ),
+                postings: vec![
+                    TransactionPosting::new(
+                        release_account.clone(),
+                        -period_release,
+                        posting_commodity,
+                    ),
+                    TransactionPosting::new(counter_account, period_release, posting_commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+            *counter_sum += period_release;
+        }
+
+        // Collect the receivable released up to the payment date, and defer
+        // the remainder (see `process_fixed_income`). The receivable is
+        // working capital, so its collection is an operating cash flow
+        // whatever the presentation of the grant.
+        transactions.push(Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some("Receive / defer grant".into()),
+            postings: vec![
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+                TransactionPosting::new(
+                    GRANTS_RECEIVABLE.clone().into(),
+                    -receivable_sum,
+                    posting_commodity,
+                ),
+                TransactionPosting::new(deferred_account, -deferred_sum, posting_commodity),
+            ]
+            .into_iter()
+            .filter(|p| p.amount != 0.0)
+            .collect(),
+            status: TransactionStatus::Unmarked,
+        });
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_income_tax(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: _,   // Ignored.
+            payment_date,
+            accounting_logic: AccountingLogic::IncomeTax { fiscal_year },
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account: _, // Ignored.
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "IncomeTax", &id);
+        let year_end = fiscal_year_end(fiscal_year, "IncomeTax", &description)?;
+        // Specs are processed in payment date order, so the accrual must be
+        // dated when it's booked to be visible to the settlement.
+        if payment_date != year_end {
+            return Err(IncomeTaxNotDatedAtYearEnd::new(
+                &description,
+                &payment_date,
+                fiscal_year,
+                &year_end,
+            ));
+        }
+
+        let posting_commodity = commodity.commodity()?;
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: year_end,
+            comment: Some(format!("Accrue income tax for {}", fiscal_year).into()),
+            postings: vec![
+                TransactionPosting::new(
+                    CURRENT_TAX_EXPENSE.clone().into(),
+                    -amount,
+                    posting_commodity,
+                ),
+                TransactionPosting::new(
+                    income_tax_payable_for(fiscal_year).into(),
+                    amount,
+                    posting_commodity,
+                ),
+            ],
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
+            annotations,
+        })
+    }
+
+    fn process_settle_income_tax(
+        spec: DecoratedTransactionSpec<H>,
+        balances: &Balances,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: _,   // Ignored.
+            payment_date,
+            accounting_logic: AccountingLogic::SettleIncomeTax { fiscal_year },
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "SettleIncomeTax", &id);
+        let year_end = fiscal_year_end(fiscal_year, "SettleIncomeTax", &description)?;
+        if payment_date <= year_end {
+            return Err(IncomeTaxSettledBeforeYearEnd::new(
+                &description,
+                &payment_date,
+                fiscal_year,
+            ));
+        }
+
+        // The payable is a liability, so has a credit balance. Anything paid
+        // beyond it (or short of it) is a true-up of the estimate.
+        let posting_commodity = commodity.commodity()?;
+        let payable_account: Account = income_tax_payable_for(fiscal_year).into();
+        let accrued = -balances.as_of(&payable_account, posting_commodity, payment_date)?;
+        if accrued <= 0.0 {
+            return Err(NoIncomeTaxAccrued::new(
+                &description,
+                fiscal_year,
+                &payment_date,
+            ));
+        }
+        let true_up = round_to_commodity_precision(-amount - accrued, &posting_commodity)?;
+
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some(format!("Pay income tax for {}", fiscal_year).into()),
+            postings: vec![
+                TransactionPosting::new(payable_account, accrued, posting_commodity),
+                TransactionPosting::new(
+                    INCOME_TAX_TRUE_UP.clone().into(),
+                    true_up,
+                    posting_commodity,
+                ),
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+            ]
+            .into_iter()
+            .filter(|p| p.amount != 0.0)
+            .collect(),
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
+    }
+
+    fn process_intercompany_transfer(
+        spec: DecoratedTransactionSpec<H>,
+        balances: &Balances,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _, // Ignored.
+            accrual_end: None,
+            payment_date,
+            accounting_logic: AccountingLogic::IntercompanyTransfer { counterparty },
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        if counterparty.is_empty() {
+            return Err(MissingIntercompanyCounterparty::new(&description));
+        }
+        if let Some(intercompany) = intercompany.filter(|i| *i != counterparty) {
+            return Err(IntercompanyCounterpartyMismatch::new(
+                &description,
+                &counterparty,
+                &intercompany,
+            ));
+        }
+
+        // Settle the balance due in the other direction first (so at most
+        // one of the two is outstanding), and record the rest as due.
+        let posting_commodity = commodity.commodity()?;
+        let due_from: Account = due_from_group_entity(&counterparty).into();
+        let due_to: Account = due_to_group_entity(&counterparty).into();
+        let (settled_account, recorded_account, outstanding) = if amount < 0.0 {
+            // The payable has a credit balance.
+            let payable = -balances.as_of(&due_to, posting_commodity, payment_date)?;
+            (due_to, due_from, payable.max(0.0))
+        } else {
+            let receivable = balances.as_of(&due_from, posting_commodity, payment_date)?;
+            (due_from, due_to, receivable.max(0.0))
+        };
+        let settled = amount.abs().min(outstanding).copysign(-amount);
+        let recorded = round_to_commodity_precision(-amount - settled, &posting_commodity)?;
+
+        let transactions = vec![Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: Some(
+                if amount < 0.0 {
+                    format!("Transfer to {}", counterparty)
+                } else {
+                    format!("Transfer from {}", counterparty)
+                }
+                .into(),
+            ),
+            // Only the intercompany balances are tagged, since the backing
+            // account is a real balance of the group.
+            postings: vec![
+                TransactionPosting::new(backing_account.account(), amount, posting_commodity),
+                TransactionPosting::new(settled_account, settled, posting_commodity)
+                    .with_tag(INTERCOMPANY_TAG, counterparty.clone()),
+                TransactionPosting::new(recorded_account, recorded, posting_commodity)
+                    .with_tag(INTERCOMPANY_TAG, counterparty.clone()),
+            ]
+            .into_iter()
+            .filter(|p| p.amount != 0.0)
+            .collect(),
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+            annotations,
+        })
+    }
+
+    fn process_reimburse(
+        spec: DecoratedTransactionSpec<H>,
+        reimbursement_state: &ReimbursementState,
+    ) -> Result<Delta, ServerError> {
+        let AccountingLogic::Reimburse(ref r_handler) = spec.accounting_logic else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        let r_account = r_handler.account();
+        Self::process_reimburse_helper(spec, r_account, false, reimbursement_state)
+    }
+
+    fn process_reimburse_partial(
+        spec: DecoratedTransactionSpec<H>,
+        reimbursement_state: &ReimbursementState,
+    ) -> Result<Delta, ServerError> {
+        let AccountingLogic::ReimbursePartial(ref r_handler) = spec.accounting_logic else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        let r_account = r_handler.account();
+        Self::process_reimburse_helper(spec, r_account, true, reimbursement_state)
+    }
+
+    fn process_reimburse_helper(
+        spec: DecoratedTransactionSpec<H>,
+        r_account: LiabilityAccount,
+        expect_remaining: bool,
+        reimbursement_state: &ReimbursementState,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _,
+            accrual_end: None,
+            payment_date,
+            accounting_logic: _,
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account: BackingAccount::Cash(c_handler),
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "Reimburse", &id);
+
+        let (reimbursement_entries, unreimbursed_remaining) = reimbursement_state
+            .get(&r_account)
+            .map(|v| v.peak_until_exactly(amount.abs(), payment_date))
+            .transpose()?
+            .ok_or_else(|| NoTransactionsToReimburse::new(&id, &r_account))?;
+
+        if !expect_remaining && unreimbursed_remaining.abs() >= commodity.precision_cutoff()? {
+            return Err(UnexpectedPartialReimbursement::new(
+                &id,
+                &r_account,
+                unreimbursed_remaining,
+            ));
+        }
+
+        let linked_postings = reimbursement_entries
+            .into_iter()
+            .flat_map(|e| e.credit_postings.iter())
+            .map(|p| {
+                TransactionPosting::linked(
+                    r_account.clone().into(),
+                    p.source_account.clone().unwrap_or(p.account.clone()),
+                    p.amount.abs(),
+                    p.commodity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let tx = Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: None,
+            postings: once(TransactionPosting::new(
+                c_handler.account().into(),
+                -amount.abs(),
+                commodity.commodity()?,
+            ))
+            .chain(linked_postings)
+            .collect(),
+            status: TransactionStatus::Unmarked,
+        };
+        let assrt = Assertion {
+            date: payment_date,
+            account: r_account.clone().into(),
+            balance: if expect_remaining {
+                -unreimbursed_remaining.abs()
+            } else {
+                0.0
+            },
+            commodity: commodity.commodity()?,
+        };
+
+        Ok(Delta {
+            spec_id: id,
+            label: TransactionLabel {
+                payee: payee.name().into(),
+                description,
+            },
+            transactions: vec![tx],
+            ext_transactions,
+            ext_assertions: ext_assertions.into_iter().chain(once(assrt)).collect(),
+            ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: Some(ReimbursementStateDelta::Pop {
+                date: payment_date,
+                account: r_account,
+                amount: amount.abs(),
+            }),
+            annotations,
+        })
+    }
+
+    fn process_pay_card_statement(
+        spec: DecoratedTransactionSpec<H>,
+        reimbursement_state: &ReimbursementState,
+    ) -> Result<Delta, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start: _,
+            accrual_end: Some(closing_date),
+            payment_date,
+            accounting_logic: AccountingLogic::PayCardStatement(card),
+            payee,
+            description,
+            amount,
+            commodity,
+            backing_account: BackingAccount::Cash(c_handler),
+            annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = spec
+        else {
+            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
+        };
+        amount_should_be_negative!(amount, "PayCardStatement", &id);
+
+        let card_account = card.account();
+        let posting_commodity = commodity.commodity()?;
+        let outstanding = reimbursement_state
+            .get(&card_account)
+            .filter(|entries| !entries.is_empty())
+            .ok_or_else(|| NoTransactionsToReimburse::new(&id, &card_account))?;
+        let (settled_entries, excess) = outstanding.peek_settled(amount.abs(), closing_date);
+        let excess = if excess < commodity.precision_cutoff()? {
+            0.0
+        } else {
+            round_to_commodity_precision(excess, &posting_commodity)?
+        };
+        let settled_amount = amount.abs() - excess;
+
+        // Revolving balance, plus purchases made after the closing date that
+        // are already booked by the payment date.
+        let remaining_balance = outstanding
+            .iter()
+            .filter(|e| e.transaction_date <= payment_date)
+            .map(|e| e.total_amount)
+            .sum::<f64>()
+            - settled_amount;
+
+        let linked_postings = settled_entries
+            .iter()
+            .flat_map(|e| e.credit_postings.iter())
+            .map(|p| {
+                TransactionPosting::linked(
+                    card_account.clone().into(),
+                    p.source_account.clone().unwrap_or(p.account.clone()),
+                    p.amount.abs(),
+                    p.commodity,
+                )
+            });
+        let interest_posting = (excess > 0.0).then(|| {
+            TransactionPosting::new(
+                CARD_INTEREST_AND_FEES.clone().into(),
+                excess,
+                posting_commodity,
+            )
+        });
+
+        let tx = Transaction {
+            spec_id: id,
+            date: payment_date,
+            comment: None,
+            postings: once(TransactionPosting::new(
+                c_handler.account().into(),
+                -amount.abs(),
+                posting_commodity,
+            ))
+            .chain(linked_postings)
+            .chain(interest_posting)
+            .collect(),
+            status: TransactionStatus::Unmarked,
+        };
+        let assrt = Assertion {
+            date: payment_date,
+            account: card_account.clone().into(),
+            balance: -round_to_commodity_precision(remaining_balance.max(0.0), &posting_commodity)?,
+            commodity: posting_commodity,
+        };
 
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
             transactions: vec![tx],
             ext_transactions,
-            ext_assertions,
+            ext_assertions: ext_assertions.into_iter().chain(once(assrt)).collect(),
             ext_raw,
             expense_history_delta: None,
-            reimbursement_state_delta: None,
-            annotations: annotations.into_iter().chain(once(note)).collect(),
+            state_delta: Vec::new(),
+            reimbursement_state_delta: Some(ReimbursementStateDelta::Settle {
+                date: closing_date,
+                account: card_account,
+                amount: settled_amount,
+            }),
+            annotations,
         })
     }
 
-    fn process_immaterial_expense(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
+    fn process_remeasure_investment(
+        spec: DecoratedTransactionSpec<H>,
+        balances: &Balances,
+    ) -> Result<Delta, ServerError> {
         let DecoratedTransactionSpec {
             id,
             accrual_start: _, // Ignored.
             accrual_end: _,   // Ignored.
             payment_date,
-            accounting_logic: AccountingLogic::ImmaterialExpense(e_handler),
+            accounting_logic:
+                AccountingLogic::RemeasureInvestment {
+                    investment,
+                    gains_to,
+                },
             payee,
             description,
-            amount,
+            amount: fair_value,
             commodity,
-            backing_account,
+            backing_account: _, // Ignored.
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -1384,91 +4586,98 @@ impl<H: Handlers> SpecProcessor<H> {
         else {
             return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
         };
-        amount_should_be_negative!(amount, "ImmaterialExpense", &id);
+        if fair_value < 0.0 {
+            return Err(UnexpectedNegativeValue::new(
+                fair_value,
+                "RemeasureInvestment",
+                &id,
+            ));
+        }
 
-        let tx = Transaction {
-            spec_id: id,
+        let investment_account = investment.account();
+        if !matches!(
+            investment_account.1,
+            AssetClassification::ShortTermInvestments | AssetClassification::LongTermInvestments
+        ) {
+            return Err(NonRemeasurableAsset::new(&description));
+        }
+        let posting_commodity = commodity.commodity()?;
+        let investment_account: Account = investment_account.into();
+        let carrying_amount =
+            balances.as_of(&investment_account, posting_commodity, payment_date)?;
+        let change =
+            round_to_commodity_precision(fair_value - carrying_amount, &posting_commodity)?;
+
+        let offset_account: Account = match (&gains_to, change > 0.0) {
+            (FairValueGainsTo::ProfitOrLoss, true) => UNREALIZED_INVESTMENT_GAIN.clone().into(),
+            (FairValueGainsTo::ProfitOrLoss, false) => UNREALIZED_INVESTMENT_LOSS.clone().into(),
+            (FairValueGainsTo::OtherComprehensiveIncome, _) => FAIR_VALUE_RESERVE.clone().into(),
+        };
+        let transactions = if change == 0.0 {
+            vec![]
+        } else {
+            vec![Transaction {
+                spec_id: id,
+                date: payment_date,
+                comment: None,
+                postings: vec![
+                    TransactionPosting::new(investment_account.clone(), change, posting_commodity),
+                    TransactionPosting::new(offset_account, -change, posting_commodity),
+                ],
+                status: TransactionStatus::Unmarked,
+            }]
+        };
+        let assrt = Assertion {
             date: payment_date,
-            comment: None,
-            postings: vec![
-                TransactionPosting::new(
-                    backing_account.account(),
-                    -amount.abs(),
-                    commodity.currency()?,
-                ),
-                TransactionPosting::new(
-                    e_handler.account().into(),
-                    amount.abs(),
-                    commodity.currency()?,
-                ),
-            ],
+            account: investment_account,
+            balance: round_to_commodity_precision(fair_value, &posting_commodity)?,
+            commodity: posting_commodity,
         };
 
-        // Tag this transaction, since the accounting logic deserves a note in
+        // Tag this transaction, since the measurement basis deserves a note in
         // the financial records.
-        let note = Annotation::ImmaterialExpense;
+        let note = match gains_to {
+            FairValueGainsTo::ProfitOrLoss => Annotation::FairValueThroughProfitOrLoss,
+            FairValueGainsTo::OtherComprehensiveIncome => Annotation::FairValueThroughOci,
+        };
 
-        let transactions = vec![tx];
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
-            expense_history_delta: None,
-            reimbursement_state_delta: track_unreimbursed_entries(
-                &backing_account,
-                &transactions,
-                &ext_transactions,
-            )?,
             transactions,
             ext_transactions,
-            ext_assertions,
+            ext_assertions: ext_assertions.into_iter().chain(once(assrt)).collect(),
             ext_raw,
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: None,
             annotations: annotations.into_iter().chain(once(note)).collect(),
         })
     }
 
-    fn process_reimburse(
-        spec: DecoratedTransactionSpec<H>,
-        reimbursement_state: &ReimbursementState,
-    ) -> Result<Delta, ServerError> {
-        let AccountingLogic::Reimburse(ref r_handler) = spec.accounting_logic else {
-            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
-        };
-        let r_account = r_handler.account();
-        Self::process_reimburse_helper(spec, r_account, false, reimbursement_state)
-    }
-
-    fn process_reimburse_partial(
-        spec: DecoratedTransactionSpec<H>,
-        reimbursement_state: &ReimbursementState,
-    ) -> Result<Delta, ServerError> {
-        let AccountingLogic::ReimbursePartial(ref r_handler) = spec.accounting_logic else {
-            return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
-        };
-        let r_account = r_handler.account();
-        Self::process_reimburse_helper(spec, r_account, true, reimbursement_state)
-    }
-
-    fn process_reimburse_helper(
-        spec: DecoratedTransactionSpec<H>,
-        r_account: LiabilityAccount,
-        expect_remaining: bool,
-        reimbursement_state: &ReimbursementState,
-    ) -> Result<Delta, ServerError> {
+    fn process_borrowing(spec: DecoratedTransactionSpec<H>) -> Result<Delta, ServerError> {
         let DecoratedTransactionSpec {
             id,
-            accrual_start: _,
-            accrual_end: None,
+            accrual_start,
+            accrual_end: Some(accrual_end),
             payment_date,
-            accounting_logic: _,
+            accounting_logic:
+                AccountingLogic::Borrowing {
+                    annual_rate,
+                    repayment,
+                },
             payee,
             description,
             amount,
             commodity,
-            backing_account: BackingAccount::Cash(c_handler),
+            backing_account,
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -1476,74 +4685,153 @@ impl<H: Handlers> SpecProcessor<H> {
         else {
             return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
         };
-        amount_should_be_negative!(amount, "Reimburse", &id);
-
-        let (reimbursement_entries, unreimbursed_remaining) = reimbursement_state
-            .get(&r_account)
-            .map(|v| v.peak_until_exactly(amount.abs(), payment_date))
-            .transpose()?
-            .ok_or_else(|| NoTransactionsToReimburse::new(&id, &r_account))?;
+        amount_should_be_positive!(amount, "Borrowing", &id);
+        validate_borrowing(
+            &description,
+            annual_rate,
+            payment_date,
+            accrual_start,
+            accrual_end,
+        )?;
 
-        if !expect_remaining && unreimbursed_remaining.abs() >= commodity.precision_cutoff()? {
-            return Err(UnexpectedPartialReimbursement::new(
-                &id,
-                &r_account,
-                unreimbursed_remaining,
-            ));
-        }
+        let posting_commodity = commodity.commodity()?;
+        let installments = monthly_loan_installments(
+            accrual_start,
+            accrual_end,
+            amount,
+            annual_rate,
+            &repayment,
+            commodity.decimal_places()?,
+            commodity.rounding_mode(),
+        )?;
+        let due_within_a_year = |since: NaiveDate, date: NaiveDate| {
+            round_to_commodity_precision(
+                principal_due_within_a_year(&installments, since, date)?,
+                &posting_commodity,
+            )
+        };
 
-        let linked_postings = reimbursement_entries
-            .into_iter()
-            .flat_map(|e| e.credit_postings.iter())
-            .map(|p| {
-                TransactionPosting::linked(
-                    r_account.clone().into(),
-                    p.source_account.clone().unwrap_or(p.account.clone()),
-                    p.amount.abs(),
-                    p.currency,
-                )
-            })
-            .collect::<Vec<_>>();
+        let mut transactions = Vec::new();
 
-        let tx = Transaction {
+        // Record the drawdown, presenting the principal due within a year as
+        // current.
+        let current = due_within_a_year(NaiveDate::MIN, payment_date)?;
+        let non_current = round_to_commodity_precision(amount - current, &posting_commodity)?;
+        transactions.push(Transaction {
             spec_id: id,
             date: payment_date,
-            comment: None,
-            postings: once(TransactionPosting::new(
-                c_handler.account().into(),
-                -amount.abs(),
-                commodity.currency()?,
-            ))
-            .chain(linked_postings)
+            comment: Some("Drawdown of borrowing".into()),
+            postings: [
+                (CURRENT_BORROWINGS.clone(), current),
+                (NON_CURRENT_BORROWINGS.clone(), non_current),
+            ]
+            .into_iter()
+            .filter(|(_, principal)| *principal != 0.0)
+            .map(|(account, principal)| {
+                TransactionPosting::new(account.into(), -principal, posting_commodity)
+            })
+            .chain(once(TransactionPosting::new(
+                backing_account.account(),
+                amount,
+                posting_commodity,
+            )))
             .collect(),
-        };
-        let assrt = Assertion {
-            date: payment_date,
-            account: r_account.clone().into(),
-            balance: if expect_remaining {
-                -unreimbursed_remaining.abs()
-            } else {
-                0.0
-            },
-            currency: commodity.currency()?,
-        };
+            status: TransactionStatus::Unmarked,
+        });
+
+        // Reclassify the principal falling due within a year at each year
+        // end.
+        let last_installment = installments
+            .last()
+            .map(|i| i.period_end)
+            .unwrap_or(accrual_end);
+        let mut presented_until = payment_date;
+        for year in payment_date.year()..=last_installment.year() {
+            let year_end = NaiveDate::from_ymd_opt(year, 12, 31).ok_or_else(|| {
+                CriticalError::with_debug("year end resulted in invalid date", &year)
+            })?;
+            if year_end <= payment_date || year_end >= last_installment {
+                continue;
+            }
+            let since = presented_until
+                .checked_add_months(Months::new(12))
+                .ok_or_else(|| {
+                    CriticalError::with_debug(
+                        "one-year horizon resulted in invalid date",
+                        &presented_until,
+                    )
+                })?;
+            let reclassified = due_within_a_year(since, year_end)?;
+            presented_until = year_end;
+            if reclassified == 0.0 {
+                continue;
+            }
+            transactions.push(Transaction {
+                spec_id: id,
+                date: year_end,
+                comment: Some("Reclassify current portion of borrowing".into()),
+                postings: vec![
+                    TransactionPosting::new(
+                        NON_CURRENT_BORROWINGS.clone().into(),
+                        reclassified,
+                        posting_commodity,
+                    ),
+                    TransactionPosting::new(
+                        CURRENT_BORROWINGS.clone().into(),
+                        -reclassified,
+                        posting_commodity,
+                    ),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+        }
+
+        // Record the installments, with the interest accrued over each period.
+        for LoanInstallment {
+            period_start,
+            period_end,
+            principal,
+            interest,
+        } in installments
+        {
+            transactions.push(Transaction {
+                spec_id: id,
+                date: period_end,
+                comment: Some(
+                    format!("Loan installment for {} - {}", period_start, period_end).into(),
+                ),
+                postings: [
+                    (CURRENT_BORROWINGS.clone().into(), principal),
+                    (INTEREST_ON_BORROWINGS.clone().into(), interest),
+                    (backing_account.account(), -(principal + interest)),
+                ]
+                .into_iter()
+                .filter(|(_, amount)| *amount != 0.0)
+                .map(|(account, amount)| {
+                    TransactionPosting::new(account, amount, posting_commodity)
+                })
+                .collect(),
+                status: TransactionStatus::Unmarked,
+            });
+        }
 
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
-            transactions: vec![tx],
+            expense_history_delta: None,
+            state_delta: Vec::new(),
+            reimbursement_state_delta: track_unreimbursed_entries(
+                &backing_account,
+                &transactions,
+                &ext_transactions,
+            )?,
+            transactions,
             ext_transactions,
-            ext_assertions: ext_assertions.into_iter().chain(once(assrt)).collect(),
+            ext_assertions,
             ext_raw,
-            expense_history_delta: None,
-            reimbursement_state_delta: Some(ReimbursementStateDelta::Pop {
-                date: payment_date,
-                account: r_account,
-                amount: amount.abs(),
-            }),
             annotations,
         })
     }
@@ -1561,6 +4849,9 @@ impl<H: Handlers> SpecProcessor<H> {
             commodity,
             backing_account: BackingAccount::Cash(cash),
             annotations,
+            intercompany: _,
+            dimensions: _,
+            status: _,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -1569,71 +4860,86 @@ impl<H: Handlers> SpecProcessor<H> {
             return Err(InvalidArgumentsForAccountingLogic::with_debug(&spec));
         };
 
+        // Only the sub-accounts of the filing period are cleared, so VAT
+        // invoiced in the next period doesn't affect the assertions.
+        let (from_period, to_period) = (vat_filing_period(from), vat_filing_period(to));
+        if from_period != to_period {
+            return Err(ClearVatSpansFilingPeriods::new(
+                &description,
+                &from_period,
+                &to_period,
+            ));
+        }
+        let vat_receivable: Account = vat_receivable_for(from).into();
+        let vat_payable: Account = vat_payable_for(from).into();
+
         let tx = if amount > 0.0 {
             Transaction {
                 spec_id: id,
                 date: accrual_date,
-                comment: Some(format!("Clear VAT receivable for {} - {}", from, to)),
+                comment: Some(format!("Clear VAT receivable for {} - {}", from, to).into()),
                 postings: vec![
                     TransactionPosting::new(
-                        VAT_RECEIVABLE.clone().into(),
+                        vat_receivable.clone(),
                         -amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                     TransactionPosting::new(
                         cash.account().into(),
                         amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                 ],
+                status: TransactionStatus::Unmarked,
             }
         } else {
             Transaction {
                 spec_id: id,
                 date: accrual_date,
-                comment: Some(format!("Clear VAT payable for {} - {}", from, to)),
+                comment: Some(format!("Clear VAT payable for {} - {}", from, to).into()),
                 postings: vec![
                     TransactionPosting::new(
                         cash.account().into(),
                         -amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                     TransactionPosting::new(
-                        VAT_PAYABLE.clone().into(),
+                        vat_payable.clone(),
                         amount.abs(),
-                        commodity.currency()?,
+                        commodity.commodity()?,
                     ),
                 ],
+                status: TransactionStatus::Unmarked,
             }
         };
         let assrt = if amount > 0.0 {
             vec![
                 Assertion {
                     date: to,
-                    account: VAT_RECEIVABLE.clone().into(),
+                    account: vat_receivable.clone(),
                     balance: amount.abs(),
-                    currency: commodity.currency()?,
+                    commodity: commodity.commodity()?,
                 },
                 Assertion {
                     date: to,
-                    account: VAT_PAYABLE.clone().into(),
+                    account: vat_payable.clone(),
                     balance: 0.0,
-                    currency: commodity.currency()?,
+                    commodity: commodity.commodity()?,
                 },
             ]
         } else {
             vec![
                 Assertion {
                     date: to,
-                    account: VAT_RECEIVABLE.clone().into(),
+                    account: vat_receivable.clone(),
                     balance: 0.0,
-                    currency: commodity.currency()?,
+                    commodity: commodity.commodity()?,
                 },
                 Assertion {
                     date: to,
-                    account: VAT_PAYABLE.clone().into(),
+                    account: vat_payable.clone(),
                     balance: -amount.abs(),
-                    currency: commodity.currency()?,
+                    commodity: commodity.commodity()?,
                 },
             ]
         };
@@ -1641,7 +4947,7 @@ impl<H: Handlers> SpecProcessor<H> {
         Ok(Delta {
             spec_id: id,
             label: TransactionLabel {
-                payee: payee.name(),
+                payee: payee.name().into(),
                 description,
             },
             transactions: vec![tx],
@@ -1649,8 +4955,260 @@ impl<H: Handlers> SpecProcessor<H> {
             ext_assertions: ext_assertions.into_iter().chain(assrt).collect(),
             ext_raw,
             expense_history_delta: None,
+            state_delta: Vec::new(),
             reimbursement_state_delta: None,
             annotations,
         })
     }
 }
+
+/// Interest transactions are not generated from a spec.
+const INFERRED_INTEREST_SPEC_ID: TransactionSpecId = TransactionSpecId(0);
+
+/// Interest credits explaining small shortfalls of the ledger balance below
+/// the asserted cash balances (see `ProcessOptions::interest_inference`).
+fn infer_interest(
+    assertions: &[Assertion],
+    transactions: &[Transaction],
+    inference: &InterestInference,
+) -> Result<Vec<Transaction>, ServerError> {
+    let mut sorted: Vec<&Assertion> = assertions
+        .iter()
+        .filter(|a| inference.schedule.matches(a.date))
+        .collect();
+    sorted.sort_by_key(|a| a.date);
+
+    let mut inferred: Vec<Transaction> = Vec::new();
+    for assertion in sorted {
+        let ledger_balance = transactions
+            .iter()
+            .chain(inferred.iter())
+            .filter(|tx| tx.date <= assertion.date)
+            .flat_map(|tx| tx.postings.iter())
+            .filter(|p| p.account == assertion.account && p.commodity == assertion.commodity)
+            .map(|p| p.amount)
+            .sum::<f64>();
+        let shortfall =
+            round_to_commodity_precision(assertion.balance - ledger_balance, &assertion.commodity)?;
+        if shortfall > 0.0 && shortfall <= inference.max_amount {
+            inferred.push(Transaction {
+                spec_id: INFERRED_INTEREST_SPEC_ID,
+                date: assertion.date,
+                comment: None,
+                postings: vec![
+                    TransactionPosting::new(
+                        assertion.account.clone(),
+                        shortfall,
+                        assertion.commodity,
+                    ),
+                    TransactionPosting::new(
+                        BANK_INTEREST.clone().into(),
+                        -shortfall,
+                        assertion.commodity,
+                    ),
+                ],
+                status: TransactionStatus::Unmarked,
+            });
+        }
+    }
+    Ok(inferred)
+}
+
+/// Checks that the depreciation parameters describe a schedule that can be
+/// generated over the useful life (`accrual_start` to `accrual_end`).
+fn validate_depreciation(
+    description: &str,
+    cost: f64,
+    residual_value: f64,
+    method: &DepreciationMethod,
+    accrual_start: NaiveDate,
+    accrual_end: NaiveDate,
+) -> Result<(), ServerError> {
+    if !(0.0..cost).contains(&residual_value) {
+        return Err(InvalidResidualValue::new(description, residual_value, cost));
+    }
+    match method {
+        DepreciationMethod::StraightLine => {}
+        DepreciationMethod::DecliningBalance { rate } => {
+            if *rate <= 0.0 {
+                return Err(NonPositiveDecliningBalanceRate::new(description, *rate));
+            }
+        }
+        DepreciationMethod::UnitsOfProduction {
+            total_units,
+            monthly_units,
+        } => {
+            let months = monthly_accrual_periods(accrual_start, accrual_end)?.len();
+            if *total_units <= 0.0 {
+                return Err(NonPositiveTotalUnits::new(description, *total_units));
+            }
+            if monthly_units.iter().any(|units| *units < 0.0) {
+                return Err(NegativeMonthlyUnits::new(description));
+            }
+            if monthly_units.len() > months {
+                return Err(MonthlyUnitsExceedUsefulLife::new(
+                    description,
+                    monthly_units.len(),
+                    months,
+                ));
+            }
+            let produced: f64 = monthly_units.iter().sum();
+            if produced > *total_units {
+                return Err(MonthlyUnitsExceedTotalUnits::new(
+                    description,
+                    produced,
+                    *total_units,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Account of the inventory item, which must be classified as inventory.
+fn inventory_account_of<A: AssetHandler>(
+    item: &A,
+    logic: &str,
+    description: &str,
+) -> Result<AssetAccount, ServerError> {
+    let account = item.account();
+    if account.1 != AssetClassification::Inventory {
+        return Err(NonInventoryAsset::new(logic, description, &account.1));
+    }
+    Ok(account)
+}
+
+/// Expense account the cost of the inventory item is recognized in.
+fn cost_of_goods_sold_for<A: AssetHandler>(item: &A) -> ExpenseAccount {
+    item.upon_accrual()
+        .unwrap_or_else(|| expense_tl(ExpenseClassification::CostOfGoodsSold))
+}
+
+/// "invoice (2)", or "invoices (2), (3)" if several were settled.
+fn settled_items<T>(kind: &str, settled: &[(&OpenItem<T>, f64)]) -> String {
+    let ids = settled
+        .iter()
+        .map(|(item, _)| format!("({})", item.spec_id))
+        .collect::<Vec<_>>();
+    match ids.len() {
+        1 => format!("{} {}", kind, ids[0]),
+        _ => format!("{}s {}", kind, ids.join(", ")),
+    }
+}
+
+/// Move 'amount' of deposits from long-term to short-term (or back, if
+/// negative). No cash is involved, so the long-term side is marked as a
+/// non-cash reclassification for the cash flow statement.
+fn reclassify_deposit(
+    spec_id: TransactionSpecId,
+    date: NaiveDate,
+    comment: &str,
+    amount: f64,
+    commodity: Commodity,
+) -> Transaction {
+    Transaction {
+        spec_id,
+        date,
+        comment: Some(comment.into()),
+        postings: vec![
+            TransactionPosting::new(
+                SHORT_TERM_SECURITY_DEPOSITS.clone().into(),
+                amount,
+                commodity,
+            ),
+            TransactionPosting::non_cash_reclassification(
+                LONG_TERM_SECURITY_DEPOSITS.clone().into(),
+                -amount,
+                commodity,
+            ),
+        ],
+        status: TransactionStatus::Unmarked,
+    }
+}
+
+/// Year end of the fiscal year (fiscal years follow the calendar year).
+fn fiscal_year_end(
+    fiscal_year: i32,
+    logic: &str,
+    description: &str,
+) -> Result<NaiveDate, ServerError> {
+    NaiveDate::from_ymd_opt(fiscal_year, 12, 31)
+        .ok_or_else(|| InvalidFiscalYear::new(logic, description, fiscal_year))
+}
+
+fn validate_borrowing(
+    description: &str,
+    annual_rate: f64,
+    payment_date: NaiveDate,
+    accrual_start: NaiveDate,
+    accrual_end: NaiveDate,
+) -> Result<(), ServerError> {
+    if annual_rate < 0.0 {
+        return Err(NegativeBorrowingRate::new(description, annual_rate));
+    }
+    if accrual_start < payment_date {
+        return Err(InterestAccruedBeforeDrawdown::new(
+            description,
+            &accrual_start,
+            &payment_date,
+        ));
+    }
+    if accrual_end <= accrual_start {
+        return Err(MaturityNotAfterAccrualStart::new(
+            description,
+            &accrual_end,
+            &accrual_start,
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the annotation is a custom annotation with `{{key}}` placeholders.
+fn has_placeholders(annotation: &Annotation) -> bool {
+    match annotation {
+        Annotation::Custom { title, body, meta } => once(title)
+            .chain(once(body))
+            .chain(meta.values())
+            .any(|text| text.contains("{{")),
+        _ => false,
+    }
+}
+
+/// Values for the placeholders custom annotations can contain: `{{payee}}`,
+/// `{{description}}`, `{{amount}}` (absolute, with the commodity symbol),
+/// `{{currency}}` (the commodity's code), `{{accrual_start}}`, `{{accrual_end}}` (same as the start
+/// for single-day accruals), `{{payment_date}}` and `{{spec_id}}`.
+fn annotation_placeholders<H: Handlers>(
+    spec: &DecoratedTransactionSpec<H>,
+) -> Result<HashMap<String, String>, ServerError> {
+    let commodity = spec.commodity.commodity()?;
+    Ok([
+        ("payee", spec.payee.name()),
+        ("description", spec.description.clone()),
+        ("amount", format_amount(spec.amount.abs(), commodity, false)),
+        ("currency", commodity.code().to_string()),
+        ("accrual_start", spec.accrual_start.to_string()),
+        (
+            "accrual_end",
+            spec.accrual_end.unwrap_or(spec.accrual_start).to_string(),
+        ),
+        ("payment_date", spec.payment_date.to_string()),
+        ("spec_id", spec.id.to_string()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect())
+}
+
+/// Unknown placeholders are left as they are.
+fn fill_placeholders(
+    annotation: &mut Annotation,
+    placeholders: &HashMap<String, String>,
+) -> Result<(), ServerError> {
+    if let Annotation::Custom { title, body, meta } = annotation {
+        for text in once(title).chain(once(body)).chain(meta.values_mut()) {
+            *text = replace_all_placeholders_in_string(std::mem::take(text), placeholders, false)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use chrono::NaiveDate;
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{
+        Amendment, Annotation, FinancialRecords, FinancialRecords_Intermediate2, Handlers,
+        Transaction, TransactionSpecId, TransactionStatus,
+    },
+    errors::{NothingToReverse, UnknownAmendedSpec},
+};
+
+impl FinancialRecords {
+    /// Reversing transactions cancelling everything the spec generated (ex.
+    /// to correct an error found after the period was closed, when the
+    /// original entries must be kept). Each transaction is reversed by one
+    /// with the same postings negated, dated `as_of`, or on the original
+    /// date for transactions after `as_of` (ex. later accrual adjustments),
+    /// so they never take effect before the entries they cancel.
+    ///
+    /// The reversals keep the spec's id, so they share its label and
+    /// annotations. They are not added to the records: append them to
+    /// `transactions` (updating any assertions after `as_of` they affect),
+    /// or print them as a separate correction. To correct a spec in the spec
+    /// input instead, see `TransactionSpec::amends`.
+    pub fn reverse(
+        &self,
+        spec_id: TransactionSpecId,
+        as_of: NaiveDate,
+    ) -> Result<Vec<Transaction>, ServerError> {
+        let reversals = reversals(&self.transactions, spec_id, as_of, spec_id);
+        if reversals.is_empty() {
+            return Err(NothingToReverse::new(&spec_id));
+        }
+        Ok(reversals)
+    }
+}
+
+/// Reverses the entries of each amended spec (see `Amendment`), as part of the
+/// amending spec, and annotates the amending spec.
+///
+/// amendments: Amending spec IDs, and what they amend.
+pub(crate) fn apply_amendments<H: Handlers>(
+    records: &mut FinancialRecords_Intermediate2<H>,
+    amendments: &[(TransactionSpecId, Amendment)],
+) -> Result<(), ServerError> {
+    // Only entries generated by the specs themselves are reversed (not the
+    // reversals of other amendments), so amendments can be chained.
+    let mut all_reversals = Vec::new();
+    for (spec_id, amendment) in amendments {
+        let reversals = reversals(
+            &records.transactions,
+            amendment.spec_id,
+            amendment.date,
+            *spec_id,
+        );
+        if reversals.is_empty() {
+            return Err(UnknownAmendedSpec::new(spec_id, &amendment.spec_id));
+        }
+        all_reversals.extend(reversals);
+        let note = Annotation::Restatement {
+            amended: amendment.spec_id,
+            date: amendment.date,
+        };
+        match records.annotations_lookup.get_mut(spec_id) {
+            Some(annotations) => annotations.push(note),
+            None => {
+                records.annotations_lookup.insert(*spec_id, vec![note]);
+            }
+        }
+    }
+    records.transactions.extend(all_reversals);
+    Ok(())
+}
+
+/// reversal_id: Spec ID of the reversing transactions.
+fn reversals(
+    transactions: &[Transaction],
+    spec_id: TransactionSpecId,
+    as_of: NaiveDate,
+    reversal_id: TransactionSpecId,
+) -> Vec<Transaction> {
+    transactions
+        .iter()
+        .filter(|tx| tx.spec_id == spec_id)
+        .map(|tx| Transaction {
+            spec_id: reversal_id,
+            date: tx.date.max(as_of),
+            postings: tx
+                .postings
+                .iter()
+                .cloned()
+                .map(|mut posting| {
+                    posting.amount = -posting.amount;
+                    posting
+                })
+                .collect(),
+            comment: Some(
+                match &tx.comment {
+                    Some(comment) => format!(
+                        "Reversal of {} entry of spec {}: {}",
+                        tx.date, spec_id, comment
+                    ),
+                    None => format!("Reversal of {} entry of spec {}", tx.date, spec_id),
+                }
+                .into(),
+            ),
+            status: TransactionStatus::Unmarked,
+        })
+        .collect()
+}
@@ -1,18 +1,23 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
-use chrono::{Datelike, Duration, NaiveDate};
+use chrono::{Datelike, Duration, Months, NaiveDate};
 use fractic_server_error::{CriticalError, ServerError};
-use iso_currency::Currency;
 
 use crate::{
     entities::{
-        BackingAccount, CashHandler, ReimbursableEntityHandler, ShareholderHandler, Transaction,
+        Account, BackingAccount, CashHandler, Commodity, DepreciationMethod, InventoryCosting,
+        PostingPrice, ReimbursableEntityHandler, RepaymentSchedule, RoundingMode,
+        ShareholderHandler, Transaction, TransactionPosting,
     },
     errors::ReimbursementTracingError,
 };
 
-use super::spec_processor::{
-    ExpenseHistoryPriceRecord, ReimbursementStateDelta, UnreimbursedEntry,
+use super::{
+    estimate_true_up::ExpenseHistoryPriceRecord,
+    spec_processor::{
+        InventoryLot, InventoryStock, OpenItem, ReimbursementStateDelta, TreasuryShares,
+        UnreimbursedEntry,
+    },
 };
 
 /// Returns the last day of each month between the given dates.
@@ -89,7 +94,8 @@ pub(crate) fn monthly_accrual_periods(
 /// Similar to `monthly_accrual_periods`, but returns the end-of-period adjust
 /// amounts, and date on which the adjustment should be recorded.
 ///
-/// Adjustment amounts are rounded to the currency's decimal places, and any
+/// Adjustment amounts are rounded to the given decimal places (using the given
+/// rounding mode), and any
 /// remaining rounding error is corrected on the last period.
 pub(crate) struct MonthlyAccrualAdjustment {
     pub(crate) period_start: NaiveDate,
@@ -101,14 +107,12 @@ pub(crate) fn monthly_accrual_adjustments(
     start: NaiveDate,
     end: NaiveDate,
     total: f64,
-    currency: Currency,
+    decimal_places: u32,
+    rounding: RoundingMode,
 ) -> Result<Vec<MonthlyAccrualAdjustment>, ServerError> {
     let accrual_days = (end - start).num_days() + 1;
     let daily_rate = total / (accrual_days as f64);
 
-    // Determine the number of decimal places from the currency.
-    let decimal_places = currency.exponent().unwrap_or(0) as i32;
-    let factor = 10_f64.powi(decimal_places);
     let periods = monthly_accrual_periods(start, end)?;
     if periods.is_empty() {
         return Ok(vec![]);
@@ -120,7 +124,7 @@ pub(crate) fn monthly_accrual_adjustments(
     for (i, period) in periods.iter().enumerate() {
         let unrounded = daily_rate * (period.num_days as f64);
         if i < periods.len() - 1 {
-            let rounded = (unrounded * factor).round() / factor;
+            let rounded = rounding.round(unrounded, decimal_places as i32);
             adjustments.push(MonthlyAccrualAdjustment {
                 period_start: period.period_start,
                 period_end: period.period_end,
@@ -142,6 +146,167 @@ pub(crate) fn monthly_accrual_adjustments(
     Ok(adjustments)
 }
 
+/// Declining balance rates are annual, applied by day.
+const DAYS_PER_YEAR: f64 = 365.0;
+
+/// Monthly depreciation adjustments of an asset over its useful life (see
+/// `DepreciationMethod`), allocating `cost - residual_value` in total. Months
+/// without depreciation are skipped. Parameters are expected to be validated.
+pub(crate) fn monthly_depreciation_adjustments(
+    start: NaiveDate,
+    end: NaiveDate,
+    cost: f64,
+    residual_value: f64,
+    method: &DepreciationMethod,
+    decimal_places: u32,
+    rounding: RoundingMode,
+) -> Result<Vec<MonthlyAccrualAdjustment>, ServerError> {
+    let depreciable = cost - residual_value;
+    let amounts: Vec<f64> = match method {
+        DepreciationMethod::StraightLine => {
+            return monthly_accrual_adjustments(start, end, depreciable, decimal_places, rounding);
+        }
+        DepreciationMethod::DecliningBalance { rate } => {
+            let periods = monthly_accrual_periods(start, end)?;
+            let mut depreciated = 0.0;
+            let mut amounts = Vec::with_capacity(periods.len());
+            for (i, period) in periods.iter().enumerate() {
+                let remaining = depreciable - depreciated;
+                let amount = if i < periods.len() - 1 {
+                    let carrying = cost - depreciated;
+                    let unrounded = carrying * rate * (period.num_days as f64) / DAYS_PER_YEAR;
+                    rounding
+                        .round(unrounded, decimal_places as i32)
+                        .min(remaining)
+                } else {
+                    // Down to the residual value at the end of the useful life.
+                    remaining
+                };
+                depreciated += amount;
+                amounts.push(amount);
+            }
+            amounts
+        }
+        DepreciationMethod::UnitsOfProduction {
+            total_units,
+            monthly_units,
+        } => {
+            let mut units_so_far = 0.0;
+            let mut depreciated = 0.0;
+            let mut amounts = Vec::with_capacity(monthly_units.len());
+            for units in monthly_units {
+                units_so_far += units;
+                // Computed from the cumulative units, so the total is exact
+                // once all units are produced.
+                let cumulative = rounding.round(
+                    depreciable * units_so_far / total_units,
+                    decimal_places as i32,
+                );
+                amounts.push(cumulative - depreciated);
+                depreciated = cumulative;
+            }
+            amounts
+        }
+    };
+    Ok(monthly_accrual_periods(start, end)?
+        .into_iter()
+        .zip(amounts)
+        .filter(|(_, amount)| *amount != 0.0)
+        .map(|(period, amount)| MonthlyAccrualAdjustment {
+            period_start: period.period_start,
+            period_end: period.period_end,
+            adjustment_amount: amount,
+            adjustment_date: period.adjustment_date,
+        })
+        .collect())
+}
+
+/// Monthly installment of a borrowing, paid at the end of the period.
+pub(crate) struct LoanInstallment {
+    pub(crate) period_start: NaiveDate,
+    pub(crate) period_end: NaiveDate,
+    pub(crate) principal: f64,
+    pub(crate) interest: f64,
+}
+
+/// Monthly installments repaying `principal` over `[start, end]` (see
+/// `RepaymentSchedule`), the last one on `end`, with interest at the annual
+/// rate accruing by day on the outstanding principal. The last installment
+/// repays whatever principal remains. Parameters are expected to be validated.
+pub(crate) fn monthly_loan_installments(
+    start: NaiveDate,
+    end: NaiveDate,
+    principal: f64,
+    annual_rate: f64,
+    repayment: &RepaymentSchedule,
+    decimal_places: u32,
+    rounding: RoundingMode,
+) -> Result<Vec<LoanInstallment>, ServerError> {
+    let mut periods = monthly_accrual_periods(start, end)?;
+    // The loan matures on the end date, even if it isn't a month end.
+    if periods.last().is_none_or(|p| p.period_end < end) {
+        let period_start = std::cmp::max(start, month_start_date(end));
+        periods.push(MonthlyAccrualPeriod {
+            period_start,
+            period_end: end,
+            num_days: (end - period_start).num_days() + 1,
+            adjustment_date: end,
+        });
+    }
+    let n = periods.len() as f64;
+    let round = |amount: f64| rounding.round(amount, decimal_places as i32);
+    // Fixed part of each installment: the principal repayment, or the whole
+    // annuity payment (from the nominal monthly rate).
+    let monthly_rate = annual_rate / 12.0;
+    let fixed = match repayment {
+        RepaymentSchedule::StraightPrincipal => round(principal / n),
+        RepaymentSchedule::Annuity if monthly_rate == 0.0 => round(principal / n),
+        RepaymentSchedule::Annuity => {
+            round(principal * monthly_rate / (1.0 - (1.0 + monthly_rate).powf(-n)))
+        }
+    };
+
+    let mut outstanding = principal;
+    let mut installments = Vec::with_capacity(periods.len());
+    for (i, period) in periods.iter().enumerate() {
+        let interest = round(outstanding * annual_rate * (period.num_days as f64) / DAYS_PER_YEAR);
+        let repaid = if i == periods.len() - 1 {
+            outstanding
+        } else {
+            match repayment {
+                RepaymentSchedule::StraightPrincipal => fixed,
+                RepaymentSchedule::Annuity => fixed - interest,
+            }
+            .clamp(0.0, outstanding)
+        };
+        outstanding = round(outstanding - repaid);
+        installments.push(LoanInstallment {
+            period_start: period.period_start,
+            period_end: period.period_end,
+            principal: repaid,
+            interest,
+        });
+    }
+    Ok(installments)
+}
+
+/// Principal of the installments due after `since`, up to 12 months after
+/// `date` (inclusive), unrounded.
+pub(crate) fn principal_due_within_a_year(
+    installments: &[LoanInstallment],
+    since: NaiveDate,
+    date: NaiveDate,
+) -> Result<f64, ServerError> {
+    let horizon = date.checked_add_months(Months::new(12)).ok_or_else(|| {
+        CriticalError::with_debug("one-year horizon resulted in invalid date", &date)
+    })?;
+    Ok(installments
+        .iter()
+        .filter(|i| since < i.period_end && i.period_end <= horizon)
+        .map(|i| i.principal)
+        .sum())
+}
+
 /// Given a slice of variable expense records and a window defined by
 /// [window_start, window_end], this computes the “effective” daily accrual rate
 /// by summing the contributions of all overlapping records. Days not covered by
@@ -212,6 +377,7 @@ pub(crate) fn track_unreimbursed_entries<
                     ));
                 }
                 reimbursable_entries.push(UnreimbursedEntry {
+                    spec_id: tx.spec_id,
                     transaction_date: tx.date,
                     total_amount: reimbursable_debits,
                     credit_postings,
@@ -248,6 +414,16 @@ pub(crate) trait PopByAmount {
     /// reaching the amount, or if the amount cannot be satisfied in an whole
     /// number of entries.
     fn pop_until_exactly(&mut self, amount: f64, cutoff: NaiveDate) -> Result<(), ServerError>;
+
+    /// Peek the entries (up to 'cutoff') settled by paying 'amount', oldest
+    /// first. If the amount doesn't cover a whole number of entries, the last
+    /// one is split, and only its paid share is returned. Also returns the part
+    /// of 'amount' exceeding all entries up to the cutoff.
+    fn peek_settled(&self, amount: f64, cutoff: NaiveDate) -> (Vec<UnreimbursedEntry>, f64);
+
+    /// Pop the entries settled by paying 'amount' (see `peek_settled`). The
+    /// unpaid share of a split entry stays at the front of the queue.
+    fn pop_settled(&mut self, amount: f64, cutoff: NaiveDate);
 }
 
 impl PopByAmount for VecDeque<UnreimbursedEntry> {
@@ -307,13 +483,633 @@ impl PopByAmount for VecDeque<UnreimbursedEntry> {
         self.drain(..entries.len());
         Ok(())
     }
+
+    fn peek_settled(&self, amount: f64, cutoff: NaiveDate) -> (Vec<UnreimbursedEntry>, f64) {
+        const EPSILON: f64 = 1e-9;
+        let mut remaining = amount;
+        let mut entries = Vec::new();
+        for entry in self.iter() {
+            if entry.transaction_date > cutoff || remaining <= EPSILON {
+                break;
+            }
+            if entry.total_amount <= remaining + EPSILON {
+                entries.push(entry.clone());
+                remaining -= entry.total_amount;
+            } else {
+                entries.push(entry.scaled(remaining / entry.total_amount));
+                remaining = 0.0;
+            }
+        }
+        (entries, remaining.max(0.0))
+    }
+
+    fn pop_settled(&mut self, amount: f64, cutoff: NaiveDate) {
+        let (entries, _) = self.peek_settled(amount, cutoff);
+        let Some(last) = entries.last() else {
+            return;
+        };
+        let split = self
+            .get(entries.len() - 1)
+            .filter(|e| e.total_amount > last.total_amount)
+            .map(|e| e.scaled(1.0 - last.total_amount / e.total_amount));
+        self.drain(..entries.len());
+        if let Some(unpaid) = split {
+            self.push_front(unpaid);
+        }
+    }
+}
+
+pub(crate) trait SettleOpenItems<T> {
+    /// Sum of the amounts outstanding.
+    fn outstanding_amount(&self, commodity: &Commodity) -> Result<f64, ServerError>;
+
+    /// Peek the items settled by paying 'amount', oldest first, with the part
+    /// of 'amount' settling each. The last one may be only partly settled. Any
+    /// part of 'amount' exceeding all items is ignored.
+    fn peek_settled_items(
+        &self,
+        amount: f64,
+        commodity: &Commodity,
+    ) -> Result<Vec<(&OpenItem<T>, f64)>, ServerError>;
+
+    /// Settle 'amount' against the items (see `peek_settled_items`). Fully
+    /// settled items are popped, and the outstanding amount of a partly
+    /// settled one is reduced.
+    fn settle_items(&mut self, amount: f64, commodity: &Commodity) -> Result<(), ServerError>;
+}
+
+impl<T> SettleOpenItems<T> for VecDeque<OpenItem<T>> {
+    fn outstanding_amount(&self, commodity: &Commodity) -> Result<f64, ServerError> {
+        round_to_commodity_precision(
+            self.iter().map(|item| item.outstanding_amount).sum(),
+            commodity,
+        )
+    }
+
+    fn peek_settled_items(
+        &self,
+        amount: f64,
+        commodity: &Commodity,
+    ) -> Result<Vec<(&OpenItem<T>, f64)>, ServerError> {
+        let mut remaining = amount;
+        let mut items = Vec::new();
+        for item in self.iter() {
+            if remaining <= 0.0 {
+                break;
+            }
+            let part = remaining.min(item.outstanding_amount);
+            items.push((item, part));
+            remaining = round_to_commodity_precision(remaining - part, commodity)?;
+        }
+        Ok(items)
+    }
+
+    fn settle_items(&mut self, amount: f64, commodity: &Commodity) -> Result<(), ServerError> {
+        let parts: Vec<f64> = self
+            .peek_settled_items(amount, commodity)?
+            .into_iter()
+            .map(|(_, part)| part)
+            .collect();
+        for part in parts {
+            let Some(oldest) = self.front_mut() else {
+                break;
+            };
+            oldest.outstanding_amount =
+                round_to_commodity_precision(oldest.outstanding_amount - part, commodity)?;
+            if oldest.outstanding_amount <= 0.0 {
+                self.pop_front();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl UnreimbursedEntry {
+    /// Share of the entry (ex. 0.4 for 40%), scaling each posting.
+    fn scaled(&self, ratio: f64) -> Self {
+        Self {
+            spec_id: self.spec_id,
+            transaction_date: self.transaction_date,
+            total_amount: self.total_amount * ratio,
+            credit_postings: self
+                .credit_postings
+                .iter()
+                .map(|p| TransactionPosting {
+                    amount: p.amount * ratio,
+                    price: p.price.map(|price| match price {
+                        PostingPrice::Total { amount, commodity } => PostingPrice::Total {
+                            amount: amount * ratio,
+                            commodity,
+                        },
+                        unit => unit,
+                    }),
+                    ..p.clone()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Cost of selling the quantity out of the stock, rounded to the commodity's
+/// precision, and the lots remaining after the sale. None if there aren't
+/// enough units in stock.
+///
+/// The rounding difference is kept in stock, so the lots still add up to the
+/// balance of the inventory account. The sale that empties the stock expenses
+/// whatever cost is left, rounding difference included.
+pub(crate) fn sell_inventory(
+    stock: &InventoryStock,
+    quantity: f64,
+    commodity: &Commodity,
+) -> Result<Option<(f64, VecDeque<InventoryLot>)>, ServerError> {
+    let Some((unrounded_cost, mut remaining)) = sell_lots(&stock.lots, quantity, stock.costing)
+    else {
+        return Ok(None);
+    };
+    let cost = match remaining.front_mut() {
+        Some(lot) => {
+            let cost = round_to_commodity_precision(unrounded_cost, commodity)?;
+            lot.cost += unrounded_cost - cost;
+            cost
+        }
+        None => {
+            round_to_commodity_precision(stock.lots.iter().map(|lot| lot.cost).sum(), commodity)?
+        }
+    };
+    Ok(Some((cost, remaining)))
+}
+
+/// Cost (unrounded) of selling the quantity out of the lots, and the lots
+/// remaining after the sale. None if there aren't enough units in stock.
+fn sell_lots(
+    lots: &VecDeque<InventoryLot>,
+    quantity: f64,
+    costing: InventoryCosting,
+) -> Option<(f64, VecDeque<InventoryLot>)> {
+    const EPSILON: f64 = 1e-9;
+    let in_stock: f64 = lots.iter().map(|lot| lot.quantity).sum();
+    if quantity > in_stock + EPSILON {
+        return None;
+    }
+    match costing {
+        InventoryCosting::Fifo => {
+            let mut remaining = lots.clone();
+            let mut to_sell = quantity;
+            let mut cost = 0.0;
+            while to_sell > EPSILON {
+                let lot = remaining.front_mut()?;
+                if lot.quantity <= to_sell + EPSILON {
+                    to_sell -= lot.quantity;
+                    cost += lot.cost;
+                    remaining.pop_front();
+                } else {
+                    let sold_cost = lot.cost * to_sell / lot.quantity;
+                    lot.quantity -= to_sell;
+                    lot.cost -= sold_cost;
+                    cost += sold_cost;
+                    to_sell = 0.0;
+                }
+            }
+            Some((cost, remaining))
+        }
+        InventoryCosting::WeightedAverage => {
+            let total_cost: f64 = lots.iter().map(|lot| lot.cost).sum();
+            let cost = total_cost * (quantity / in_stock).min(1.0);
+            let left = in_stock - quantity;
+            let remaining = (left > EPSILON)
+                .then_some(InventoryLot {
+                    quantity: left,
+                    cost: total_cost - cost,
+                })
+                .into_iter()
+                .collect();
+            Some((cost, remaining))
+        }
+    }
+}
+
+/// Treasury shares reissued (see `reissue_treasury_shares`). Amounts are
+/// credits (negative for debits).
+#[derive(Debug)]
+pub(crate) struct TreasuryReissue {
+    /// Cost the shares are carried at, credited to treasury stock.
+    pub(crate) cost: f64,
+    pub(crate) to_surplus: f64,
+    pub(crate) to_retained_earnings: f64,
+}
+
+/// Reissue some of the treasury shares held (at most all of them) for the
+/// proceeds, at the weighted average cost of those held.
+///
+/// A gain is credited to the surplus from treasury share reissues, and a loss
+/// is debited to it up to its balance, with any remainder debited to retained
+/// earnings. 'surplus_balance' is the balance of the surplus as posted (so
+/// negative for the credit balance it has, being equity).
+pub(crate) fn reissue_treasury_shares(
+    held: &TreasuryShares,
+    shares: f64,
+    proceeds: f64,
+    surplus_balance: f64,
+    commodity: &Commodity,
+) -> Result<TreasuryReissue, ServerError> {
+    let cost = if shares == held.shares {
+        held.cost
+    } else {
+        round_to_commodity_precision(held.cost * shares / held.shares, commodity)?
+    };
+    let difference = round_to_commodity_precision(proceeds - cost, commodity)?;
+    let (to_surplus, to_retained_earnings) = if difference >= 0.0 {
+        (difference, 0.0)
+    } else {
+        let to_surplus = difference.max(surplus_balance.min(0.0));
+        (
+            to_surplus,
+            round_to_commodity_precision(difference - to_surplus, commodity)?,
+        )
+    };
+    Ok(TreasuryReissue {
+        cost,
+        to_surplus,
+        to_retained_earnings,
+    })
 }
 
-pub(crate) fn round_to_currency_precision(
+pub(crate) fn round_to_commodity_precision(
     amount: f64,
-    currency: &Currency,
+    commodity: &Commodity,
 ) -> Result<f64, ServerError> {
-    let decimal_places = currency.exponent().unwrap_or(0) as i32;
+    let decimal_places = commodity.decimal_places() as i32;
     let factor = 10_f64.powi(decimal_places);
     Ok((amount * factor).round() / factor)
 }
+
+/// Distinct commodities posted to each account, in order of first use.
+pub(crate) fn posting_commodities(
+    transactions: &[Transaction],
+) -> HashMap<Account, Vec<Commodity>> {
+    let mut commodities: HashMap<Account, Vec<Commodity>> = HashMap::new();
+    for posting in transactions.iter().flat_map(|tx| tx.postings.iter()) {
+        // Avoid cloning the account for every posting.
+        match commodities.get_mut(&posting.account) {
+            Some(account_commodities) => {
+                if !account_commodities.contains(&posting.commodity) {
+                    account_commodities.push(posting.commodity);
+                }
+            }
+            None => {
+                commodities.insert(posting.account.clone(), vec![posting.commodity]);
+            }
+        }
+    }
+    commodities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn assert_amounts(actual: &[f64], expected: &[f64]) {
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    fn installments(
+        start: &str,
+        end: &str,
+        principal: f64,
+        annual_rate: f64,
+        repayment: RepaymentSchedule,
+    ) -> Vec<LoanInstallment> {
+        monthly_loan_installments(
+            date(start),
+            date(end),
+            principal,
+            annual_rate,
+            &repayment,
+            2,
+            RoundingMode::HalfEven,
+        )
+        .unwrap()
+    }
+
+    fn depreciation(
+        start: &str,
+        end: &str,
+        cost: f64,
+        residual_value: f64,
+        method: DepreciationMethod,
+    ) -> Vec<MonthlyAccrualAdjustment> {
+        monthly_depreciation_adjustments(
+            date(start),
+            date(end),
+            cost,
+            residual_value,
+            &method,
+            2,
+            RoundingMode::HalfEven,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn loan_installments_at_zero_rate_repay_principal_evenly() {
+        for repayment in [
+            RepaymentSchedule::Annuity,
+            RepaymentSchedule::StraightPrincipal,
+        ] {
+            let installments = installments("2024-01-01", "2024-12-31", 1200.0, 0.0, repayment);
+            assert_eq!(installments.len(), 12);
+            assert!(installments.iter().all(|i| i.principal == 100.0));
+            assert!(installments.iter().all(|i| i.interest == 0.0));
+            assert_eq!(installments.last().unwrap().period_end, date("2024-12-31"));
+        }
+    }
+
+    #[test]
+    fn loan_installments_end_with_stub_month_on_maturity() {
+        // 0.073 accrues 0.0002 per day.
+        let installments = installments(
+            "2024-01-15",
+            "2024-04-10",
+            1000.0,
+            0.073,
+            RepaymentSchedule::StraightPrincipal,
+        );
+        let periods: Vec<_> = installments
+            .iter()
+            .map(|i| (i.period_start, i.period_end))
+            .collect();
+        assert_eq!(
+            periods,
+            vec![
+                (date("2024-01-15"), date("2024-01-31")),
+                (date("2024-02-01"), date("2024-02-29")),
+                (date("2024-03-01"), date("2024-03-31")),
+                (date("2024-04-01"), date("2024-04-10")),
+            ]
+        );
+        let principal: Vec<_> = installments.iter().map(|i| i.principal).collect();
+        assert_amounts(&principal, &[250.0, 250.0, 250.0, 250.0]);
+        let interest: Vec<_> = installments.iter().map(|i| i.interest).collect();
+        assert_amounts(&interest, &[3.4, 4.35, 3.1, 0.5]);
+    }
+
+    #[test]
+    fn loan_installments_repay_no_principal_while_interest_exceeds_annuity() {
+        // A 10% nominal monthly rate over 5 years gives an annuity payment of
+        // about 100.33, less than the interest accrued over a 31-day month.
+        let installments = installments(
+            "2024-01-01",
+            "2028-12-31",
+            1000.0,
+            1.2,
+            RepaymentSchedule::Annuity,
+        );
+        assert_eq!(installments.len(), 60);
+        assert_eq!(installments[0].interest, 101.92);
+        assert_eq!(installments[0].principal, 0.0);
+        assert!(installments[1].principal > 0.0);
+        assert!(installments.iter().all(|i| i.principal >= 0.0));
+        let repaid: f64 = installments.iter().map(|i| i.principal).sum();
+        assert!((repaid - 1000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn principal_due_within_a_year_includes_installment_on_horizon() {
+        let installments = installments(
+            "2024-01-01",
+            "2025-12-31",
+            2400.0,
+            0.0,
+            RepaymentSchedule::StraightPrincipal,
+        );
+        let due = |since: &str, on: &str| {
+            principal_due_within_a_year(&installments, date(since), date(on)).unwrap()
+        };
+        // Due on 2024-01-31 through 2025-01-31.
+        assert_eq!(due("2023-12-31", "2024-01-31"), 1300.0);
+        // Installments due on `since` are excluded.
+        assert_eq!(due("2024-01-31", "2024-01-31"), 1200.0);
+        // From a leap day, the horizon is the end of February.
+        assert_eq!(due("2024-02-29", "2024-02-29"), 1200.0);
+        assert_eq!(due("2024-12-31", "2024-12-31"), 1200.0);
+        assert_eq!(due("2025-06-30", "2025-06-30"), 600.0);
+        assert_eq!(due("2025-12-31", "2025-12-31"), 0.0);
+    }
+
+    #[test]
+    fn straight_line_depreciation_is_allocated_by_day() {
+        let adjustments = depreciation(
+            "2024-01-16",
+            "2024-03-31",
+            1000.0,
+            240.0,
+            DepreciationMethod::StraightLine,
+        );
+        let amounts: Vec<_> = adjustments.iter().map(|a| a.adjustment_amount).collect();
+        assert_amounts(&amounts, &[160.0, 290.0, 310.0]);
+        assert_eq!(adjustments[0].period_start, date("2024-01-16"));
+    }
+
+    #[test]
+    fn declining_balance_depreciation_ends_at_residual_value() {
+        // 0.365 depreciates 0.001 of the carrying amount per day.
+        let adjustments = depreciation(
+            "2024-01-01",
+            "2024-03-31",
+            1000.0,
+            100.0,
+            DepreciationMethod::DecliningBalance { rate: 0.365 },
+        );
+        let amounts: Vec<_> = adjustments.iter().map(|a| a.adjustment_amount).collect();
+        assert_amounts(&amounts, &[31.0, 28.1, 840.9]);
+    }
+
+    #[test]
+    fn units_of_production_depreciation_skips_months_without_units() {
+        let adjustments = depreciation(
+            "2024-01-01",
+            "2024-03-31",
+            1000.0,
+            0.0,
+            DepreciationMethod::UnitsOfProduction {
+                total_units: 100.0,
+                monthly_units: vec![10.0, 0.0, 30.0],
+            },
+        );
+        let dates: Vec<_> = adjustments.iter().map(|a| a.adjustment_date).collect();
+        assert_eq!(dates, vec![date("2024-01-31"), date("2024-03-31")]);
+        let amounts: Vec<_> = adjustments.iter().map(|a| a.adjustment_amount).collect();
+        assert_amounts(&amounts, &[100.0, 300.0]);
+    }
+
+    /// Cost of the sale, and the (quantity, cost) of the lots remaining.
+    fn sell(
+        costing: InventoryCosting,
+        lots: &[(f64, f64)],
+        quantity: f64,
+    ) -> Option<(f64, Vec<(f64, f64)>)> {
+        let stock = InventoryStock {
+            costing,
+            lots: lots
+                .iter()
+                .map(|&(quantity, cost)| InventoryLot { quantity, cost })
+                .collect(),
+        };
+        let usd = Commodity::Currency(iso_currency::Currency::USD);
+        let (cost, remaining) = sell_inventory(&stock, quantity, &usd).unwrap()?;
+        Some((
+            cost,
+            remaining
+                .iter()
+                .map(|lot| (lot.quantity, lot.cost))
+                .collect(),
+        ))
+    }
+
+    #[test]
+    fn fifo_sale_spans_lots_and_keeps_rounding_difference_in_stock() {
+        let (cost, remaining) =
+            sell(InventoryCosting::Fifo, &[(3.0, 10.0), (3.0, 10.0)], 4.0).unwrap();
+        // 10 + 3.333..., rounded.
+        assert_eq!(cost, 13.33);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, 2.0);
+        // 6.666... left in the lot, plus the 0.00333... not expensed.
+        assert_amounts(&[remaining[0].1], &[6.67]);
+    }
+
+    #[test]
+    fn weighted_average_sale_merges_lots() {
+        let (cost, remaining) = sell(
+            InventoryCosting::WeightedAverage,
+            &[(10.0, 100.0), (10.0, 200.0)],
+            5.0,
+        )
+        .unwrap();
+        assert_eq!(cost, 75.0);
+        assert_eq!(remaining.len(), 1);
+        assert_amounts(&[remaining[0].0, remaining[0].1], &[15.0, 225.0]);
+    }
+
+    #[test]
+    fn sale_emptying_stock_expenses_cost_left() {
+        for costing in [InventoryCosting::Fifo, InventoryCosting::WeightedAverage] {
+            let (first, remaining) = sell(costing, &[(3.0, 10.0)], 1.0).unwrap();
+            assert_eq!(first, 3.33);
+            // The rounding difference carried over is expensed with the rest.
+            let (last, remaining) = sell(costing, &remaining, 2.0).unwrap();
+            assert_eq!(last, 6.67);
+            assert!(remaining.is_empty());
+        }
+    }
+
+    #[test]
+    fn oversold_stock_cannot_be_sold() {
+        for costing in [InventoryCosting::Fifo, InventoryCosting::WeightedAverage] {
+            assert!(sell(costing, &[(3.0, 10.0), (3.0, 10.0)], 6.5).is_none());
+            assert!(sell(costing, &[], 1.0).is_none());
+        }
+    }
+
+    /// Cost, and amounts credited to the surplus and retained earnings, of
+    /// reissuing the shares out of 10 held at a cost of 100.
+    fn reissue(shares: f64, proceeds: f64, surplus_balance: f64) -> [f64; 3] {
+        let held = TreasuryShares {
+            shares: 10.0,
+            cost: 100.0,
+        };
+        let usd = Commodity::Currency(iso_currency::Currency::USD);
+        let reissue =
+            reissue_treasury_shares(&held, shares, proceeds, surplus_balance, &usd).unwrap();
+        [
+            reissue.cost,
+            reissue.to_surplus,
+            reissue.to_retained_earnings,
+        ]
+    }
+
+    #[test]
+    fn treasury_reissue_gain_is_credited_to_surplus() {
+        assert_amounts(&reissue(4.0, 50.0, 0.0), &[40.0, 10.0, 0.0]);
+    }
+
+    #[test]
+    fn treasury_reissue_loss_within_surplus_is_debited_to_it() {
+        // A credit balance of 20.
+        assert_amounts(&reissue(4.0, 35.0, -20.0), &[40.0, -5.0, 0.0]);
+    }
+
+    #[test]
+    fn treasury_reissue_loss_exceeding_surplus_is_debited_to_retained_earnings() {
+        assert_amounts(&reissue(10.0, 70.0, -20.0), &[100.0, -20.0, -10.0]);
+    }
+
+    #[test]
+    fn treasury_reissue_loss_without_surplus_is_debited_to_retained_earnings() {
+        // 3 of 10 shares cost 30 (pro rata).
+        assert_amounts(&reissue(3.0, 25.0, 0.0), &[30.0, 0.0, -5.0]);
+        // A debit balance doesn't absorb losses either.
+        assert_amounts(&reissue(3.0, 25.0, 5.0), &[30.0, 0.0, -5.0]);
+    }
+
+    /// Invoices 1, 2 and 3, with 100, 50 and 30 outstanding.
+    fn open_items() -> VecDeque<OpenItem<()>> {
+        [(1, 100.0), (2, 50.0), (3, 30.0)]
+            .into_iter()
+            .map(|(id, outstanding_amount)| OpenItem {
+                spec_id: crate::entities::TransactionSpecId(id),
+                outstanding_amount,
+                detail: (),
+            })
+            .collect()
+    }
+
+    /// (spec ID, outstanding amount) of the items left after settling.
+    fn settle(amount: f64) -> Vec<(u64, f64)> {
+        let usd = Commodity::Currency(iso_currency::Currency::USD);
+        let mut items = open_items();
+        items.settle_items(amount, &usd).unwrap();
+        items
+            .iter()
+            .map(|item| (item.spec_id.0, item.outstanding_amount))
+            .collect()
+    }
+
+    #[test]
+    fn settling_less_than_oldest_item_reduces_it() {
+        assert_eq!(settle(40.0), vec![(1, 60.0), (2, 50.0), (3, 30.0)]);
+    }
+
+    #[test]
+    fn settling_across_items_pops_those_fully_settled() {
+        let usd = Commodity::Currency(iso_currency::Currency::USD);
+        let items = open_items();
+        let settled: Vec<_> = items
+            .peek_settled_items(130.0, &usd)
+            .unwrap()
+            .into_iter()
+            .map(|(item, part)| (item.spec_id.0, part))
+            .collect();
+        assert_eq!(settled, vec![(1, 100.0), (2, 30.0)]);
+        assert_eq!(settle(130.0), vec![(2, 20.0), (3, 30.0)]);
+        assert_eq!(settle(150.0), vec![(3, 30.0)]);
+    }
+
+    #[test]
+    fn settling_more_than_outstanding_ignores_excess() {
+        assert_eq!(settle(500.0), vec![]);
+    }
+}
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use fractic_server_error::ServerError;
 
-use crate::entities::{FinancialRecordSpecs, Handlers};
+use crate::entities::{BudgetSpec, FinancialRecordSpecs, Handlers};
 
 #[async_trait]
 pub trait RecordsRepository<H: Handlers>: Send + Sync {
@@ -11,6 +11,7 @@ pub trait RecordsRepository<H: Handlers>: Send + Sync {
         balances_csv: &str,
     ) -> Result<FinancialRecordSpecs<H>, ServerError>;
 
+    #[cfg(feature = "fs")]
     async fn from_file<P>(
         &self,
         transactions_csv: P,
@@ -18,4 +19,11 @@ pub trait RecordsRepository<H: Handlers>: Send + Sync {
     ) -> Result<FinancialRecordSpecs<H>, ServerError>
     where
         P: AsRef<std::path::Path> + Send;
+
+    fn budgets_from_string(&self, budgets_csv: &str) -> Result<Vec<BudgetSpec<H>>, ServerError>;
+
+    #[cfg(feature = "fs")]
+    async fn budgets_from_file<P>(&self, budgets_csv: P) -> Result<Vec<BudgetSpec<H>>, ServerError>
+    where
+        P: AsRef<std::path::Path> + Send;
 }
@@ -5,26 +5,39 @@ use crate::{
     data::repositories::records_repository_impl::RecordsRepositoryImpl,
     domain::{
         logic::{
-            annotation_processor::AnnotationProcessor, command_processor::CommandProcessor,
-            decorator_processor::DecoratorProcessor, spec_processor::SpecProcessor,
+            annotation_processor::AnnotationProcessor,
+            assertion_preflight::AssertionPreflight,
+            backdating_check::BackdatingCheck,
+            command_processor::CommandProcessor,
+            decorator_processor::DecoratorProcessor,
+            spec_processor::{SpecProcessor, SpecTransactions},
+            spec_reversal::apply_amendments,
         },
         repositories::records_repository::RecordsRepository,
     },
-    entities::{FinancialRecords, Handlers, NotesToFinancialRecords},
+    entities::{
+        BudgetSpec, FinancialRecordSpecs, FinancialRecords, GenerationMetadata, Handlers,
+        IfrsLogic, NotesToFinancialRecords, PayeeHandler as _, PhaseProgress, ProcessOptions,
+        ProgressPhase, StandardIfrsLogic,
+    },
 };
 
-#[async_trait]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 pub trait ProcessUsecase: Send + Sync {
     async fn from_string(
         &self,
         balances_csv: &str,
         transactions_csv: &str,
+        budgets_csv: Option<&str>,
     ) -> Result<(FinancialRecords, NotesToFinancialRecords), ServerError>;
 
+    #[cfg(feature = "fs")]
     async fn from_file<P>(
         &self,
         balances_csv: P,
         transactions_csv: P,
+        budgets_csv: Option<P>,
     ) -> Result<(FinancialRecords, NotesToFinancialRecords), ServerError>
     where
         P: AsRef<std::path::Path> + Send;
@@ -38,10 +51,12 @@ pub(crate) struct ProcessUsecaseImpl<
     R1: RecordsRepository<H>,
 {
     records_repository: R1,
+    options: ProcessOptions,
     _phantom: std::marker::PhantomData<H>,
 }
 
-#[async_trait]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 impl<H, R1> ProcessUsecase for ProcessUsecaseImpl<H, R1>
 where
     H: Handlers,
@@ -51,34 +66,38 @@ where
         &self,
         transactions_csv: &str,
         balances_csv: &str,
+        budgets_csv: Option<&str>,
     ) -> Result<(FinancialRecords, NotesToFinancialRecords), ServerError> {
-        let input = self
+        let mut input = self
             .records_repository
             .from_string(transactions_csv, balances_csv)?;
-        let intermediate_1 = DecoratorProcessor::new(input).process().await?;
-        let intermediate_2 = SpecProcessor::new(intermediate_1).process()?;
-        let output = CommandProcessor::new(intermediate_2).process()?;
-        let output_notes = AnnotationProcessor::new(&output).process()?;
-        Ok((output, output_notes))
+        if let Some(budgets_csv) = budgets_csv {
+            input.budget_specs = self.records_repository.budgets_from_string(budgets_csv)?;
+        }
+        process_specs(input, &StandardIfrsLogic, &self.options).await
     }
 
+    #[cfg(feature = "fs")]
     async fn from_file<P>(
         &self,
         transactions_csv: P,
         balances_csv: P,
+        budgets_csv: Option<P>,
     ) -> Result<(FinancialRecords, NotesToFinancialRecords), ServerError>
     where
         P: AsRef<std::path::Path> + Send,
     {
-        let input = self
+        let mut input = self
             .records_repository
             .from_file(transactions_csv, balances_csv)
             .await?;
-        let intermediate_1 = DecoratorProcessor::new(input).process().await?;
-        let intermediate_2 = SpecProcessor::new(intermediate_1).process()?;
-        let output = CommandProcessor::new(intermediate_2).process()?;
-        let output_notes = AnnotationProcessor::new(&output).process()?;
-        Ok((output, output_notes))
+        if let Some(budgets_csv) = budgets_csv {
+            input.budget_specs = self
+                .records_repository
+                .budgets_from_file(budgets_csv)
+                .await?;
+        }
+        process_specs(input, &StandardIfrsLogic, &self.options).await
     }
 }
 
@@ -86,7 +105,73 @@ impl<H: Handlers> ProcessUsecaseImpl<H> {
     pub(crate) fn new() -> Self {
         ProcessUsecaseImpl {
             records_repository: RecordsRepositoryImpl::new(),
+            options: ProcessOptions::default(),
             _phantom: std::marker::PhantomData,
         }
     }
+
+    pub(crate) fn with_options(mut self, options: ProcessOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+/// Run the full processing pipeline (decorators, specs, amendments, commands,
+/// annotations) on already-parsed specs, using the given strategy for the spec
+/// processing step.
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "process", skip_all))]
+pub(crate) async fn process_specs<H: Handlers>(
+    input: FinancialRecordSpecs<H>,
+    logic: &dyn IfrsLogic<H>,
+    options: &ProcessOptions,
+) -> Result<(FinancialRecords, NotesToFinancialRecords), ServerError> {
+    let budgets = input
+        .budget_specs
+        .iter()
+        .map(BudgetSpec::resolve)
+        .collect::<Result<Vec<_>, ServerError>>()?;
+    let amendments = input
+        .transaction_specs
+        .iter()
+        .filter_map(|spec| spec.amends.map(|amendment| (spec.id, amendment)))
+        .collect::<Vec<_>>();
+    let payment_dates = input
+        .transaction_specs
+        .iter()
+        .map(|spec| (spec.id, spec.payment_date))
+        .collect();
+    let payees = input
+        .transaction_specs
+        .iter()
+        .map(|spec| (spec.payee.name(), spec.payee.metadata()))
+        .filter(|(_, metadata)| !metadata.is_empty())
+        .collect();
+    let metadata = GenerationMetadata::new(&input, options);
+    let intermediate_1 = DecoratorProcessor::new(input, options).process().await?;
+    let mut intermediate_2 = logic.process(intermediate_1, options)?;
+    apply_amendments(&mut intermediate_2, &amendments)?;
+    let mut output = CommandProcessor::new(intermediate_2, options).process()?;
+    output.budgets = budgets;
+    output.payees = payees;
+    output.metadata = Some(metadata);
+    if options.preflight_assertions {
+        AssertionPreflight::new(&output).check()?;
+    }
+    if let Some(guard) = &options.backdating_guard {
+        BackdatingCheck::new(guard, &payment_dates).apply(&mut output)?;
+    }
+    let mut progress = PhaseProgress::start(options.progress.clone(), ProgressPhase::Annotating, 1);
+    let output_notes = AnnotationProcessor::new(&output).process()?;
+    progress.advance();
+    Ok((output, output_notes))
+}
+
+/// Decorate the specs, then process them incrementally (see
+/// `SpecProcessor::into_transactions`).
+pub(crate) async fn stream_specs<H: Handlers>(
+    input: FinancialRecordSpecs<H>,
+    options: &ProcessOptions,
+) -> Result<SpecTransactions<H>, ServerError> {
+    let intermediate_1 = DecoratorProcessor::new(input, options).process().await?;
+    Ok(SpecProcessor::new(intermediate_1, options).into_transactions())
 }
@@ -4,14 +4,22 @@
 pub(crate) mod data {
     pub(crate) mod datasources {
         pub(crate) mod balances_csv_datasource;
+        pub(crate) mod budgets_csv_datasource;
+        pub(crate) mod config_toml_datasource;
         pub(crate) mod transactions_csv_datasource;
     }
     pub(crate) mod models {
         pub(crate) mod accounting_amount_model;
         pub(crate) mod accounting_logic_model;
+        pub(crate) mod amendment_model;
+        pub(crate) mod annotation_model;
         pub(crate) mod backing_account_model;
+        pub(crate) mod budget_target_model;
         pub(crate) mod command_logic_model;
+        pub(crate) mod dimensions_model;
         pub(crate) mod iso_date_model;
+        pub(crate) mod pipeline_config_model;
+        pub(crate) mod recurrence_model;
     }
     pub(crate) mod repositories {
         pub(crate) mod records_repository_impl;
@@ -24,21 +32,41 @@ pub(crate) mod domain {
         pub(crate) mod annotation;
         pub(crate) mod assertion;
         pub(crate) mod assertion_spec;
+        pub(crate) mod budget;
         pub(crate) mod cashflow_tracing_tag;
+        pub(crate) mod clock;
         pub(crate) mod command;
+        pub(crate) mod commodity;
         pub(crate) mod decorator_logic;
+        pub(crate) mod discrepancy_policy;
         pub(crate) mod end_of_year_entry;
         pub(crate) mod financial_records;
+        pub(crate) mod fx_provider;
+        pub(crate) mod generation_metadata;
         pub(crate) mod handlers;
+        pub(crate) mod ifrs_logic;
+        pub(crate) mod maybe_send;
+        pub(crate) mod payee_metadata;
+        pub(crate) mod pipeline_config;
+        pub(crate) mod process_options;
+        pub(crate) mod progress;
+        pub(crate) mod recurrence;
+        pub(crate) mod rounding;
+        pub(crate) mod spec_hook;
+        pub(crate) mod spec_lookup;
         pub(crate) mod transaction;
         pub(crate) mod transaction_spec;
     }
     pub(crate) mod logic {
         pub(crate) mod account_impl;
         pub(crate) mod annotation_processor;
+        pub(crate) mod assertion_preflight;
+        pub(crate) mod backdating_check;
         pub(crate) mod command_processor;
         pub(crate) mod decorator_processor;
+        pub(crate) mod estimate_true_up;
         pub(crate) mod spec_processor;
+        pub(crate) mod spec_reversal;
         mod utils;
     }
     pub(crate) mod repositories {
@@ -51,8 +79,13 @@ pub(crate) mod domain {
 
 pub(crate) mod presentation {
     pub(crate) mod account_fmt;
+    #[cfg(feature = "arrow")]
+    pub(crate) mod arrow_printer;
     pub(crate) mod cashflow_tracing_tag_fmt;
+    pub(crate) mod error_catalog;
     pub(crate) mod hledger_printer;
+    pub(crate) mod json_printer;
+    pub(crate) mod print_options;
     pub(crate) mod utils;
 }
 
@@ -72,17 +105,40 @@ pub mod exports {
         pub use crate::domain::entities::annotation::*;
         pub use crate::domain::entities::assertion::*;
         pub use crate::domain::entities::assertion_spec::*;
+        pub use crate::domain::entities::budget::*;
         pub use crate::domain::entities::cashflow_tracing_tag::*;
+        pub use crate::domain::entities::clock::*;
         pub use crate::domain::entities::command::*;
+        pub use crate::domain::entities::commodity::*;
         pub use crate::domain::entities::decorator_logic::*;
+        pub use crate::domain::entities::discrepancy_policy::*;
         pub use crate::domain::entities::end_of_year_entry::*;
         pub use crate::domain::entities::financial_records::*;
+        pub use crate::domain::entities::fx_provider::*;
+        pub use crate::domain::entities::generation_metadata::*;
         pub use crate::domain::entities::handlers::*;
+        pub use crate::domain::entities::ifrs_logic::*;
+        pub use crate::domain::entities::maybe_send::*;
+        pub use crate::domain::entities::payee_metadata::*;
+        pub use crate::domain::entities::pipeline_config::*;
+        pub use crate::domain::entities::process_options::*;
+        pub use crate::domain::entities::progress::*;
+        pub use crate::domain::entities::recurrence::*;
+        pub use crate::domain::entities::rounding::*;
+        pub use crate::domain::entities::spec_hook::*;
+        pub use crate::domain::entities::spec_lookup::*;
         pub use crate::domain::entities::transaction::*;
         pub use crate::domain::entities::transaction_spec::*;
     }
 
+    pub mod printing {
+        pub use crate::presentation::print_options::*;
+    }
+
     pub mod utils {
-        pub use crate::presentation::utils::format_amount;
+        pub use crate::presentation::utils::{
+            format_amount, format_amount_rounded, format_amount_with_precision,
+            replace_all_placeholders_in_string,
+        };
     }
 }
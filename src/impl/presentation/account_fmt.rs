@@ -27,6 +27,10 @@ impl Account {
                     AssetClassification::DeferredIncomeTax => "NonCurrent:DeferredIncomeTax",
                     AssetClassification::OtherNonCurrentAssets =>
                         "NonCurrent:OtherNonCurrentAssets",
+                    AssetClassification::AccumulatedDepreciation =>
+                        "NonCurrent:PropertyPlantEquipment:AccumulatedDepreciation",
+                    AssetClassification::AccumulatedAmortization =>
+                        "NonCurrent:IntangibleAssets:AccumulatedAmortization",
                 },
                 match &s.0 {
                     Some(name) => format!(":{}", name),
@@ -143,6 +147,10 @@ impl Account {
                     EquityClassification::DiscountOnStockIssuance =>
                         "CapitalAdjustments:DiscountOnStockIssuance",
 
+                    // Accumulated other comprehensive income.
+                    EquityClassification::AccumulatedOtherComprehensiveIncome =>
+                        "AccumulatedOtherComprehensiveIncome",
+
                     // Earned capital.
                     EquityClassification::RetainedEarnings
                     | EquityClassification::RetainedEarningsOpt { .. } =>
@@ -156,6 +164,123 @@ impl Account {
         }
     }
 
+    /// Short explanation of the account's classification, for reviewers of the
+    /// raw journal: the classification's doc comment.
+    pub(crate) fn description(&self) -> Option<&'static str> {
+        match self {
+            Account::Asset(s) => s.1.description(),
+            Account::Liability(s) => s.1.description(),
+            Account::Income(s) => s.1.description(),
+            Account::Expense(s) => s.1.description(),
+            Account::Equity(s) => s.1.description(),
+        }
+    }
+
+    /// Statement (ex. "Balance sheet") and line item (ex. "Property, plant and
+    /// equipment") the account is presented under.
+    pub(crate) fn statement_line(&self) -> (&'static str, &'static str) {
+        const BALANCE_SHEET: &str = "Balance sheet";
+        const INCOME_STATEMENT: &str = "Income statement";
+        match self {
+            Account::Asset(s) => (
+                BALANCE_SHEET,
+                match &s.1 {
+                    AssetClassification::CashAndCashEquivalents => "Cash and cash equivalents",
+                    AssetClassification::AccountsReceivable => "Trade and other receivables",
+                    AssetClassification::Inventory => "Inventories",
+                    AssetClassification::PrepaidExpenses => "Prepayments",
+                    AssetClassification::ShortTermInvestments => "Short-term investments",
+                    AssetClassification::ShortTermDeposits => "Short-term deposits",
+                    AssetClassification::OtherCurrentAssets => "Other current assets",
+                    AssetClassification::PropertyPlantEquipment
+                    | AssetClassification::AccumulatedDepreciation => {
+                        "Property, plant and equipment"
+                    }
+                    AssetClassification::IntangibleAssets
+                    | AssetClassification::AccumulatedAmortization => "Intangible assets",
+                    AssetClassification::LongTermInvestments => "Long-term investments",
+                    AssetClassification::LongTermDeposits => "Long-term deposits",
+                    AssetClassification::DeferredIncomeTax => "Deferred tax assets",
+                    AssetClassification::OtherNonCurrentAssets => "Other non-current assets",
+                },
+            ),
+            Account::Liability(s) => (
+                BALANCE_SHEET,
+                match &s.1 {
+                    LiabilityClassification::AccountsPayable => "Trade and other payables",
+                    LiabilityClassification::AccruedExpenses => "Accrued expenses",
+                    LiabilityClassification::DeferredRevenue => "Deferred revenue",
+                    LiabilityClassification::ShortTermDebt => "Short-term borrowings",
+                    LiabilityClassification::OtherCurrentLiabilities => "Other current liabilities",
+                    LiabilityClassification::LongTermDebt => "Long-term borrowings",
+                    LiabilityClassification::DeferredIncomeTax => "Deferred tax liabilities",
+                    LiabilityClassification::OtherNonCurrentLiabilities => {
+                        "Other non-current liabilities"
+                    }
+                },
+            ),
+            Account::Income(s) => (
+                INCOME_STATEMENT,
+                match &s.1 {
+                    IncomeClassification::SalesRevenue
+                    | IncomeClassification::ServiceRevenue
+                    | IncomeClassification::InterestIncome
+                    | IncomeClassification::DividendIncome
+                    | IncomeClassification::RentalIncome => "Revenue",
+                    IncomeClassification::NonCoreInterestIncome
+                    | IncomeClassification::NonCoreDividendIncome
+                    | IncomeClassification::NonCoreRentalIncome
+                    | IncomeClassification::RealizedFxGain
+                    | IncomeClassification::OtherNonCoreFinancialIncome => "Finance income",
+                    IncomeClassification::GainOnSaleOfAssets
+                    | IncomeClassification::VatRefundGain
+                    | IncomeClassification::OtherNonOperatingIncome => "Other income",
+                },
+            ),
+            Account::Expense(s) => (
+                INCOME_STATEMENT,
+                match &s.1 {
+                    ExpenseClassification::CostOfGoodsSold => "Cost of sales",
+                    ExpenseClassification::SellingExpenses => "Selling expenses",
+                    ExpenseClassification::GeneralAdministrativeExpenses
+                    | ExpenseClassification::CloudServicesExpenses => "Administrative expenses",
+                    ExpenseClassification::ResearchAndDevelopmentExpenses => {
+                        "Research and development expenses"
+                    }
+                    ExpenseClassification::DepreciationExpense
+                    | ExpenseClassification::AmortizationExpense => "Depreciation and amortization",
+                    ExpenseClassification::InterestExpense
+                    | ExpenseClassification::NonCoreInterestExpense
+                    | ExpenseClassification::RealizedFxLoss
+                    | ExpenseClassification::OtherNonCoreFinancialExpense => "Finance costs",
+                    ExpenseClassification::IncomeTaxExpense => "Income tax expense",
+                    ExpenseClassification::OtherTaxExpense
+                    | ExpenseClassification::LossOnSaleOfAssets
+                    | ExpenseClassification::VatRefundLoss
+                    | ExpenseClassification::OtherNonOperatingCashExpense
+                    | ExpenseClassification::OtherNonOperatingNonCashExpense => "Other expenses",
+                },
+            ),
+            Account::Equity(s) => (
+                BALANCE_SHEET,
+                match &s.1 {
+                    EquityClassification::CommonStock
+                    | EquityClassification::PreferredStock
+                    | EquityClassification::UnpaidShareCapital => "Share capital",
+                    EquityClassification::SharePremium => "Share premium",
+                    EquityClassification::ContributedSurplus => "Contributed surplus",
+                    EquityClassification::TreasuryStock => "Treasury shares",
+                    EquityClassification::DiscountOnStockIssuance => "Capital adjustments",
+                    EquityClassification::AccumulatedOtherComprehensiveIncome => {
+                        "Accumulated other comprehensive income"
+                    }
+                    EquityClassification::RetainedEarnings
+                    | EquityClassification::RetainedEarningsOpt { .. } => "Retained earnings",
+                },
+            ),
+        }
+    }
+
     pub(crate) fn type_tag(&self) -> char {
         match self {
             Account::Asset(AssetAccount(_, AssetClassification::CashAndCashEquivalents)) => 'C',
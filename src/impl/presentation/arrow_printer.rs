@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use arrow_array::{
+    builder::{Date32Builder, Float64Builder, MapBuilder, StringBuilder, UInt64Builder},
+    ArrayRef, RecordBatch,
+};
+use arrow_schema::{Field, Schema};
+use chrono::{DateTime, NaiveDate};
+use fractic_server_error::{CriticalError, ServerError};
+
+use crate::entities::{Account, CashflowTracingTag, FinancialRecords, Transaction};
+#[cfg(feature = "parquet")]
+use crate::errors::WriteError;
+
+/// Levels of the account path split into their own columns. Deeper levels
+/// (ex. named sub-accounts containing ':') are kept in the last column, so the
+/// schema doesn't depend on the records.
+const ACCOUNT_LEVELS: usize = 5;
+
+pub(crate) struct ArrowPrinter;
+
+impl ArrowPrinter {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+
+    pub(crate) fn print_postings(
+        &self,
+        financial_records: &FinancialRecords,
+    ) -> Result<RecordBatch, ServerError> {
+        // Same ordering as the printed ledger.
+        let sorted_transactions = {
+            let mut v: Vec<&Transaction> = financial_records.transactions.iter().collect();
+            v.sort_by_key(|tx| tx.date);
+            v
+        };
+
+        let mut date = Date32Builder::new();
+        let mut spec_id = UInt64Builder::new();
+        let mut account = StringBuilder::new();
+        let mut levels: Vec<StringBuilder> =
+            (0..ACCOUNT_LEVELS).map(|_| StringBuilder::new()).collect();
+        let mut account_type = StringBuilder::new();
+        let mut classification = StringBuilder::new();
+        let mut amount = Float64Builder::new();
+        let mut currency = StringBuilder::new();
+        let mut tags = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+
+        for tx in sorted_transactions {
+            for posting in &tx.postings {
+                let ledger = posting.account.ledger();
+                let mut parts = ledger.splitn(ACCOUNT_LEVELS, ':');
+                for level in levels.iter_mut() {
+                    level.append_option(parts.next());
+                }
+                date.append_value(days_since_epoch(tx.date));
+                spec_id.append_value(tx.spec_id.0);
+                account.append_value(&ledger);
+                account_type.append_value(posting.account.type_tag().to_string());
+                classification.append_value(classification_name(&posting.account));
+                amount.append_value(posting.amount);
+                currency.append_value(posting.commodity.code());
+
+                let cashflow_tag = posting
+                    .source_account
+                    .as_ref()
+                    .unwrap_or(&posting.account)
+                    .cashflow_tag(posting.amount)
+                    .map(|tag| tag.value());
+                if let Some(tag) = cashflow_tag {
+                    tags.keys().append_value(CashflowTracingTag::key());
+                    tags.values().append_value(tag);
+                }
+                let mut custom_tags: Vec<_> = posting.custom_tags.iter().collect();
+                custom_tags.sort();
+                for (key, value) in custom_tags {
+                    tags.keys().append_value(key);
+                    tags.values().append_value(value);
+                }
+                tags.append(true).map_err(|e| {
+                    CriticalError::with_debug("failed to build posting tags column", &e)
+                })?;
+            }
+        }
+
+        let mut columns: Vec<(String, ArrayRef, bool)> = vec![
+            ("date".into(), Arc::new(date.finish()), false),
+            ("spec_id".into(), Arc::new(spec_id.finish()), false),
+            ("account".into(), Arc::new(account.finish()), false),
+        ];
+        columns.extend(levels.iter_mut().enumerate().map(|(i, level)| {
+            (
+                format!("account_{}", i + 1),
+                Arc::new(level.finish()) as ArrayRef,
+                // Every account has at least its top-level and classification.
+                i >= 2,
+            )
+        }));
+        columns.extend([
+            (
+                "account_type".into(),
+                Arc::new(account_type.finish()) as ArrayRef,
+                false,
+            ),
+            (
+                "classification".into(),
+                Arc::new(classification.finish()),
+                false,
+            ),
+            ("amount".into(), Arc::new(amount.finish()), false),
+            ("currency".into(), Arc::new(currency.finish()), false),
+            ("tags".into(), Arc::new(tags.finish()), false),
+        ]);
+        let schema = Schema::new(
+            columns
+                .iter()
+                .map(|(name, array, nullable)| {
+                    Field::new(name.as_str(), array.data_type().clone(), *nullable)
+                })
+                .collect::<Vec<_>>(),
+        );
+        RecordBatch::try_new(
+            Arc::new(schema),
+            columns.into_iter().map(|(_, array, _)| array).collect(),
+        )
+        .map_err(|e| CriticalError::with_debug("failed to build postings record batch", &e))
+    }
+}
+
+fn days_since_epoch(date: NaiveDate) -> i32 {
+    (date - DateTime::UNIX_EPOCH.date_naive()).num_days() as i32
+}
+
+/// Ex. "CashAndCashEquivalents".
+fn classification_name(account: &Account) -> String {
+    match account {
+        Account::Asset(a) => format!("{:?}", a.1),
+        Account::Liability(a) => format!("{:?}", a.1),
+        Account::Income(a) => format!("{:?}", a.1),
+        Account::Expense(a) => format!("{:?}", a.1),
+        Account::Equity(a) => format!("{:?}", a.1),
+    }
+}
+
+impl FinancialRecords {
+    /// Export every posting as a row of an Arrow record batch, for analytics
+    /// tools (ex. DuckDB, pandas) that shouldn't have to parse the journal.
+    ///
+    /// Columns: `date`, `spec_id`, `account` (full path), `account_1` to
+    /// `account_5` (path levels, ex. "Assets", "Current",
+    /// "CashAndCashEquivalents"), `account_type` (hledger type code),
+    /// `classification`, `amount`, `currency` (ISO code or custom commodity
+    /// symbol) and `tags` (map, including the cashflow tracing tag).
+    pub fn to_arrow(&self) -> Result<RecordBatch, ServerError> {
+        ArrowPrinter::new().print_postings(self)
+    }
+
+    /// Same as `to_arrow`, written as a Parquet file.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet<W>(&self, writer: W) -> Result<(), ServerError>
+    where
+        W: std::io::Write + Send,
+    {
+        let batch = self.to_arrow()?;
+        let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)
+            .map_err(|e| WriteError::with_debug(&e))?;
+        writer
+            .write(&batch)
+            .map_err(|e| WriteError::with_debug(&e))?;
+        writer.close().map_err(|e| WriteError::with_debug(&e))?;
+        Ok(())
+    }
+}
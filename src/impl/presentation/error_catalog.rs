@@ -0,0 +1,197 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::LazyLock,
+};
+
+use crate::{
+    entities::TransactionSpecId,
+    errors::{ErrorCode, Locale},
+};
+
+static EN: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| parse_catalog(include_str!("../../../res/errors/en.toml")));
+static KO: LazyLock<HashMap<String, String>> =
+    LazyLock::new(|| parse_catalog(include_str!("../../../res/errors/ko.toml")));
+
+fn parse_catalog(raw: &str) -> HashMap<String, String> {
+    toml::from_str(raw).expect("bundled error catalog should be valid TOML")
+}
+
+/// Key of the suffix template for errors wrapped with their spec ID.
+const SPEC_KEY: &str = "spec";
+
+/// Start of the suffix appended by `errors::with_spec_id`, which ends with the
+/// spec ID and a closing parenthesis.
+const SPEC_SUFFIX_START: &str = " (spec: ";
+
+/// An error message from this crate, split into its parts.
+pub(crate) struct ParsedMessage<'a> {
+    pub(crate) code: ErrorCode,
+    /// Message without the code prefix or spec suffix.
+    pub(crate) body: &'a str,
+    /// Spec ID from the suffix added by `errors::with_spec_id`, if any.
+    pub(crate) wrapped_spec_id: Option<TransactionSpecId>,
+}
+
+/// Split a message whose code prefix (ex. `"[E103] "`) is at the very start.
+pub(crate) fn parse_message(message: &str) -> Option<ParsedMessage<'_>> {
+    let (message, wrapped_spec_id) = split_spec_suffix(message);
+    let (code, body) = message.strip_prefix('[')?.split_once("] ")?;
+    Some(ParsedMessage {
+        code: ErrorCode::from_code(code)?,
+        body,
+        wrapped_spec_id,
+    })
+}
+
+/// Split off the suffix appended by `errors::with_spec_id`, if the message
+/// ends with one.
+pub(crate) fn split_spec_suffix(message: &str) -> (&str, Option<TransactionSpecId>) {
+    let suffix = message
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(SPEC_SUFFIX_START))
+        .filter(|(_, id)| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()));
+    match suffix.and_then(|(body, id)| Some((body, id.parse().ok()?))) {
+        Some((body, id)) => (body, Some(TransactionSpecId(id))),
+        None => (message, None),
+    }
+}
+
+/// Recover the values interpolated into the message, using the English
+/// template of its code. Returns `None` if the message doesn't match.
+pub(crate) fn extract_args(parsed: &ParsedMessage) -> Option<BTreeMap<String, String>> {
+    let template = template(Locale::En, parsed.code.code)?;
+    let segments = segments(template);
+    let mut args = BTreeMap::new();
+    let mut rest = parsed.body;
+    for (i, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Literal(literal) => rest = rest.strip_prefix(literal)?,
+            Segment::Placeholder(name) => {
+                let value_end = match segments.get(i + 1) {
+                    // The last literal is matched from the end, since values
+                    // can contain it (ex. a trailing '.').
+                    Some(Segment::Literal(next)) if i + 2 == segments.len() => rest.rfind(next)?,
+                    Some(Segment::Literal(next)) => rest.find(next)?,
+                    // Adjacent placeholders would be ambiguous.
+                    Some(Segment::Placeholder(_)) => return None,
+                    None => rest.len(),
+                };
+                args.insert(name.to_string(), rest[..value_end].to_string());
+                rest = &rest[value_end..];
+            }
+        }
+    }
+    rest.is_empty().then_some(args)
+}
+
+/// Render the message of a parsed error in the given locale, keeping the code
+/// prefix. Returns `None` if the arguments can't be recovered.
+pub(crate) fn localize(parsed: &ParsedMessage, locale: Locale) -> Option<String> {
+    let args = extract_args(parsed)?;
+    let mut message = format!(
+        "[{}] {}",
+        parsed.code.code,
+        fill(template(locale, parsed.code.code)?, &args)
+    );
+    if let Some(spec_id) = &parsed.wrapped_spec_id {
+        let spec_args = BTreeMap::from([("spec_id".to_string(), spec_id.to_string())]);
+        message.push(' ');
+        message.push_str(&fill(template(locale, SPEC_KEY)?, &spec_args));
+    }
+    Some(message)
+}
+
+fn template(locale: Locale, key: &str) -> Option<&'static str> {
+    let catalog = match locale {
+        Locale::En => &*EN,
+        Locale::Ko => &*KO,
+    };
+    catalog.get(key).map(String::as_str)
+}
+
+fn fill(template: &str, args: &BTreeMap<String, String>) -> String {
+    segments(template)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Literal(literal) => literal,
+            Segment::Placeholder(name) => args.get(name).map_or("", String::as_str),
+        })
+        .collect()
+}
+
+enum Segment<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn segments(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|c| open + c) else {
+            break;
+        };
+        if open > 0 {
+            segments.push(Segment::Literal(&rest[..open]));
+        }
+        segments.push(Segment::Placeholder(&rest[open + 1..close]));
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest));
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_code_has_a_message_in_each_locale() {
+        for code in ErrorCode::all() {
+            assert!(template(Locale::En, code.code).is_some(), "{}", code.code);
+            assert!(template(Locale::Ko, code.code).is_some(), "{}", code.code);
+        }
+    }
+
+    #[test]
+    fn english_templates_match_messages() {
+        for code in ErrorCode::all() {
+            let message = code.message.strip_prefix(&format!("[{}] ", code.code));
+            let mut expected = String::new();
+            for segment in segments(message.unwrap()) {
+                match segment {
+                    Segment::Literal(literal) => expected.push_str(literal),
+                    // Format specs (ex. `:?`) aren't part of the template.
+                    Segment::Placeholder(name) => {
+                        let name = name.split(':').next().unwrap();
+                        expected.push_str(&format!("{{{name}}}"));
+                    }
+                }
+            }
+            assert_eq!(
+                template(Locale::En, code.code),
+                Some(&*expected),
+                "{}",
+                code.code
+            );
+        }
+    }
+
+    #[test]
+    fn localize_recovers_args_and_spec_suffix() {
+        let message = "[E103] Invalid CSV content: missing column. (spec: 7)";
+        let parsed = parse_message(message).unwrap();
+        assert_eq!(parsed.wrapped_spec_id, Some(TransactionSpecId(7)));
+        assert_eq!(
+            extract_args(&parsed).unwrap().get("details").unwrap(),
+            "missing column"
+        );
+        assert_eq!(
+            localize(&parsed, Locale::Ko).unwrap(),
+            "[E103] CSV 내용이 올바르지 않습니다: missing column. (항목: 7)"
+        );
+    }
+}
@@ -1,98 +1,265 @@
-use std::collections::{BTreeMap, BTreeSet, HashSet};
-
-use iso_currency::Currency;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io::{self, Write},
+};
 
 use crate::{
+    domain::entities::generation_metadata::debug_hash,
     entities::{
-        Account, Assertion, CashflowTracingTag, CloseLogic, EndOfYearEntry, FinancialRecords,
-        Transaction,
+        Account, Annotation, Assertion, Budget, CashflowTracingTag, CloseLogic, Commodity,
+        EndOfYearEntry, FinancialRecords, GenerationMetadata, PostingPrice, Transaction,
+        TransactionStatus,
     },
     ext::standard_accounts::RETAINED_EARNINGS,
+    impl_ext::standard_accounts::overrides::standard_named_accounts,
     presentation::utils::header_comment,
 };
+use chrono::Datelike as _;
 
-use super::utils::format_amount;
+use super::{
+    print_options::{AnnotationVerbosity, PrintOptions},
+    utils::{format_amount, format_amount_with_precision},
+};
 
-pub(crate) struct HledgerPrinter;
+pub(crate) struct HledgerPrinter {
+    options: PrintOptions,
+}
 
 const POSTING_INDENT: &str = "    ";
 const POSTING_TOTAL_WIDTH: usize = 100;
 const POSTING_MIN_GAP: usize = 2;
+const PRICE_DECIMAL_PLACES: i32 = 8;
 
 impl HledgerPrinter {
     pub(crate) fn new() -> Self {
-        Self
+        Self::with_options(PrintOptions::default())
+    }
+
+    pub(crate) fn with_options(options: PrintOptions) -> Self {
+        Self { options }
+    }
+
+    fn format_amount(&self, amount: f64, commodity: Commodity, trailing_decimal: bool) -> String {
+        let decimal_places = match self.options.decimal_places.get(&commodity) {
+            Some(decimal_places) => *decimal_places as usize,
+            None => commodity.decimal_places() as usize,
+        };
+        format_amount_with_precision(
+            amount,
+            commodity,
+            decimal_places,
+            trailing_decimal,
+            self.options.rounding_mode,
+        )
+    }
+
+    /// Cost annotation for a posting (ex. " @ 1,312.5 ₩"). Unit prices keep
+    /// up to `PRICE_DECIMAL_PLACES`, since exchange rates are often more
+    /// precise than the commodity.
+    fn format_price(&self, price: &PostingPrice) -> String {
+        match *price {
+            PostingPrice::Unit { rate, commodity } => {
+                let minimum = commodity.decimal_places() as i32;
+                let decimal_places = (minimum..PRICE_DECIMAL_PLACES)
+                    .find(|&places| {
+                        let factor = 10_f64.powi(places);
+                        ((rate * factor).round() / factor - rate).abs() < 1e-9
+                    })
+                    .unwrap_or(PRICE_DECIMAL_PLACES);
+                format!(
+                    " @ {}",
+                    format_amount_with_precision(
+                        rate.abs(),
+                        commodity,
+                        decimal_places as usize,
+                        false,
+                        self.options.rounding_mode,
+                    )
+                )
+            }
+            PostingPrice::Total { amount, commodity } => {
+                format!(" @@ {}", self.format_amount(amount.abs(), commodity, false))
+            }
+        }
     }
 
     pub(crate) fn print_ledger(&self, financial_records: &FinancialRecords) -> String {
+        let mut ledger_output = Vec::new();
+        self.print_ledger_to(&mut ledger_output, financial_records)
+            .expect("writing to a Vec should not fail");
+        String::from_utf8(ledger_output).expect("ledger output should be valid UTF-8")
+    }
+
+    /// Same as `print_ledger`, but writes each section to the writer as soon
+    /// as it is printed, and transactions one at a time, so the full journal
+    /// is never held in memory.
+    pub(crate) fn print_ledger_to(
+        &self,
+        mut writer: impl Write,
+        financial_records: &FinancialRecords,
+    ) -> io::Result<()> {
         let mut ledger_output = String::new();
 
+        if self.options.generation_header {
+            if let Some(metadata) = &financial_records.metadata {
+                self.print_generation_header(&mut ledger_output, metadata);
+            }
+        }
+
         ledger_output.push_str(&header_comment("Accounts"));
         self.print_accounts(&mut ledger_output, financial_records);
+        flush(&mut writer, &mut ledger_output)?;
 
         ledger_output.push_str("\n\n");
         ledger_output.push_str(&header_comment("Commodities"));
         self.print_commodities(&mut ledger_output, financial_records);
+        flush(&mut writer, &mut ledger_output)?;
 
         ledger_output.push_str("\n\n");
         ledger_output.push_str(&header_comment("Payees"));
         self.print_payees(&mut ledger_output, financial_records);
+        flush(&mut writer, &mut ledger_output)?;
 
         ledger_output.push_str("\n\n");
         ledger_output.push_str(&header_comment("Transactions"));
-        self.print_transactions(&mut ledger_output, financial_records);
+        self.print_transactions(&mut writer, &mut ledger_output, financial_records)?;
 
         ledger_output.push_str("\n\n");
         ledger_output.push_str(&header_comment("Assertions"));
-        self.print_assertions(&mut ledger_output, financial_records);
+        if let Some(path) = &self.options.assertions_include {
+            ledger_output.push_str(&format!("include {}\n", path));
+        } else {
+            self.print_assertions(&mut ledger_output, financial_records);
+        }
+        flush(&mut writer, &mut ledger_output)?;
+
+        if self.options.budget_entries && !financial_records.budgets.is_empty() {
+            ledger_output.push_str("\n\n");
+            ledger_output.push_str(&header_comment("Budgets"));
+            self.print_budgets(&mut ledger_output, financial_records);
+            flush(&mut writer, &mut ledger_output)?;
+        }
 
         if !financial_records.ledger_extensions.is_empty() {
             ledger_output.push_str("\n\n");
             ledger_output.push_str(&header_comment("Custom Ledger Extensions"));
             self.print_ledger_extensions(&mut ledger_output, financial_records);
+            flush(&mut writer, &mut ledger_output)?;
         }
 
         for (year, entries) in eoy_entries_by_year(&financial_records.eoy_entries) {
             ledger_output.push_str("\n\n");
             ledger_output.push_str(&header_comment(&format!("{} Corrections / Closing", year)));
             self.print_eoy_entries(&mut ledger_output, entries);
+            flush(&mut writer, &mut ledger_output)?;
+        }
+
+        if self.options.annotation_appendix {
+            ledger_output.push_str("\n\n");
+            ledger_output.push_str(&header_comment("Annotations"));
+            self.print_annotation_appendix(&mut ledger_output, financial_records);
+            flush(&mut writer, &mut ledger_output)?;
         }
 
+        writer.flush()
+    }
+
+    /// Standalone journal containing only the balance assertions, for use with
+    /// `PrintOptions::assertions_include`.
+    pub(crate) fn print_assertions_journal(&self, financial_records: &FinancialRecords) -> String {
+        let mut ledger_output = String::new();
+        ledger_output.push_str(&header_comment("Assertions"));
+        self.print_assertions(&mut ledger_output, financial_records);
+        ledger_output
+    }
+
+    /// Journal combining the given journals with `include` directives (ex. the
+    /// per-entity journals of a group).
+    pub(crate) fn print_includes_journal(&self, paths: &[String]) -> String {
+        let mut ledger_output = String::new();
+        ledger_output.push_str(&header_comment("Entities"));
+        for path in paths {
+            ledger_output.push_str(&format!("include {}\n", path));
+        }
         ledger_output
     }
 
+    /// See `PrintOptions::generation_header`. The config hash covers both the
+    /// processing and print options.
+    fn print_generation_header(&self, ledger_output: &mut String, metadata: &GenerationMetadata) {
+        ledger_output.push_str(&format!(
+            "; Generated by fractic-ifrs-hledger {} at {}\n",
+            metadata.crate_version,
+            metadata.generated_at.format("%Y-%m-%dT%H:%M:%SZ"),
+        ));
+        ledger_output.push_str(&format!("; Input:  sha256:{}\n", metadata.input_hash));
+        ledger_output.push_str(&format!(
+            "; Config: sha256:{}\n",
+            debug_hash(&(&metadata.config_hash, &self.options)),
+        ));
+        ledger_output.push_str("\n\n");
+    }
+
     fn print_accounts(&self, ledger_output: &mut String, financial_records: &FinancialRecords) {
-        let accounts: HashSet<&Account> = financial_records
+        let used_accounts = financial_records
             .transactions
             .iter()
-            .flat_map(|tx| tx.postings.iter().map(|p| &p.account))
-            .chain(financial_records.assertions.iter().map(|a| &a.account))
-            .collect();
-        let sorted_account_declarations = {
-            let mut v: Vec<String> = accounts
+            .flat_map(|tx| tx.postings.iter().map(|p| p.account.clone()))
+            .chain(
+                financial_records
+                    .assertions
+                    .iter()
+                    .map(|a| a.account.clone()),
+            )
+            .chain(
+                financial_records
+                    .budgets
+                    .iter()
+                    .filter(|_| self.options.budget_entries)
+                    .map(|b| b.scope.account()),
+            );
+        let chart_accounts = if self.options.full_chart_of_accounts {
+            Account::all_top_level()
                 .into_iter()
-                .map(format_account_declaration)
-                .collect();
-            v.sort();
-            v
+                .chain(standard_named_accounts())
+                .collect()
+        } else {
+            vec![]
         };
-        for d in sorted_account_declarations {
+        // Several classifications may share the same ledger name, so dedup on
+        // the formatted declaration, keeping the description of the first
+        // account (as `ChartOfAccounts` does).
+        let mut sorted_account_declarations: BTreeMap<String, Option<&str>> = BTreeMap::new();
+        for account in used_accounts
+            .chain(chart_accounts)
+            .chain(self.options.declared_accounts.iter().cloned())
+        {
+            sorted_account_declarations
+                .entry(format_account_declaration(&account))
+                .or_insert_with(|| account.description());
+        }
+        for (d, description) in sorted_account_declarations {
             ledger_output.push_str(&d);
             ledger_output.push('\n');
+            if let Some(description) = description.filter(|_| self.options.account_notes) {
+                for line in textwrap::wrap(description, 94) {
+                    ledger_output.push_str(&format!("{}; {}\n", POSTING_INDENT, line));
+                }
+            }
         }
     }
 
     fn print_commodities(&self, ledger_output: &mut String, financial_records: &FinancialRecords) {
         const SAMPLE_AMOUNT: f64 = 1000.0;
-        let currencies: HashSet<Currency> = financial_records
+        let commodities: HashSet<Commodity> = financial_records
             .transactions
             .iter()
-            .flat_map(|tx| tx.postings.iter().map(|p| p.currency))
+            .flat_map(|tx| tx.postings.iter().map(|p| p.commodity))
             .collect();
         let sorted_commodity_declarations = {
-            let mut v: Vec<String> = currencies
+            let mut v: Vec<String> = commodities
                 .iter()
-                .map(|c| format!("commodity {}", format_amount(SAMPLE_AMOUNT, *c, true)))
+                .map(|c| format!("commodity {}", self.format_amount(SAMPLE_AMOUNT, *c, true)))
                 .collect();
             v.sort();
             v
@@ -105,77 +272,194 @@ impl HledgerPrinter {
     }
 
     fn print_payees(&self, ledger_output: &mut String, financial_records: &FinancialRecords) {
-        let payees: HashSet<&String> = financial_records
+        let payees: HashSet<&str> = financial_records
             .label_lookup
             .values()
-            .map(|label| &label.payee)
+            .map(|label| &*label.payee)
             .collect();
-        let sorted_payee_declarations = {
-            let mut v: Vec<String> = payees.iter().map(|p| format!("payee {}", p)).collect();
+        let sorted_payees = {
+            let mut v: Vec<&str> = payees.into_iter().collect();
             v.sort();
             v
         };
-        for p in sorted_payee_declarations {
-            ledger_output.push_str(&p);
-            ledger_output.push('\n');
+        for p in sorted_payees {
+            ledger_output.push_str(&format!("payee {}\n", p));
+            // Declared as tags of the payee (ex. for 'hledger payees').
+            if let Some(metadata) = financial_records.payees.get(p) {
+                for tag in metadata.tags() {
+                    ledger_output.push_str(&format!("{}; {}\n", POSTING_INDENT, tag));
+                }
+            }
         }
     }
 
-    fn print_transactions(&self, ledger_output: &mut String, financial_records: &FinancialRecords) {
+    /// Writes the buffered output (ex. the section header) followed by each
+    /// transaction as it is printed, leaving the buffer empty.
+    fn print_transactions(
+        &self,
+        writer: &mut impl Write,
+        ledger_output: &mut String,
+        financial_records: &FinancialRecords,
+    ) -> io::Result<()> {
         let sorted_transactions = {
             let mut v: Vec<&Transaction> = financial_records.transactions.iter().collect();
             v.sort_by_key(|tx| tx.date);
             v
         };
+        if !self.options.group_by_month {
+            for tx in sorted_transactions {
+                self.print_transaction(ledger_output, financial_records, tx);
+                flush(writer, ledger_output)?;
+            }
+            return flush(writer, ledger_output);
+        }
+
+        let mut months: BTreeMap<(i32, u32), Vec<&Transaction>> = BTreeMap::new();
         for tx in sorted_transactions {
-            let label = financial_records.label_lookup.get(&tx.spec_id).map_or(
-                "(unknown)".to_string(),
-                |label| {
-                    if let Some(comment) = &tx.comment {
-                        format!("{} | {}: {}", label.payee, comment, label.description)
-                    } else {
-                        format!("{} | {}", label.payee, label.description)
-                    }
-                },
-            );
-            ledger_output.push_str(&format!("{} ({}) {}\n", tx.date, tx.spec_id, label));
-            for posting in &tx.postings {
-                let cashflow_tag = posting
-                    .source_account
-                    .as_ref()
-                    .unwrap_or(&posting.account)
-                    .cashflow_tag(posting.amount)
-                    .map(|tag| format!("{}: {}", CashflowTracingTag::key(), tag.value()));
-                let custom_tags = posting
-                    .custom_tags
-                    .iter()
-                    .map(|(k, v)| format!("{}: {}", k, v))
-                    .collect::<Vec<String>>();
-                let tag_str = match cashflow_tag
-                    .into_iter()
-                    .chain(custom_tags.into_iter())
-                    .collect::<Vec<String>>()
-                {
-                    tags if tags.is_empty() => "".to_string(),
-                    tags => format!("       ; {}", tags.join(", ")),
-                };
-                let posting_line = format_posting_line(
-                    &posting.account.ledger(),
-                    &format_amount(posting.amount, posting.currency, false),
-                );
-                ledger_output.push_str(&format!("{}{}\n", posting_line, tag_str));
+            months
+                .entry((tx.date.year(), tx.date.month()))
+                .or_default()
+                .push(tx);
+        }
+        for ((year, month), transactions) in months {
+            ledger_output.push_str(&format!("; ---- {:04}-{:02} ----\n\n", year, month));
+            for tx in transactions.iter() {
+                self.print_transaction(ledger_output, financial_records, tx);
+                flush(writer, ledger_output)?;
             }
-            for annotation in financial_records
-                .annotations_lookup
-                .get(&tx.spec_id)
-                .unwrap_or(&vec![])
-            {
-                format_note(&annotation.to_string())
+            if self.options.monthly_subtotals {
+                format_monthly_subtotals(year, month, &transactions)
                     .iter()
                     .for_each(|line| {
                         ledger_output.push_str(line);
                         ledger_output.push('\n');
                     });
+                ledger_output.push('\n');
+            }
+        }
+        flush(writer, ledger_output)
+    }
+
+    fn print_transaction(
+        &self,
+        ledger_output: &mut String,
+        financial_records: &FinancialRecords,
+        tx: &Transaction,
+    ) {
+        let label = financial_records.label_lookup.get(&tx.spec_id).map_or(
+            "(unknown)".to_string(),
+            |label| {
+                if let Some(comment) = &tx.comment {
+                    format!("{} | {}: {}", label.payee, comment, label.description)
+                } else {
+                    format!("{} | {}", label.payee, label.description)
+                }
+            },
+        );
+        let status = match tx.status {
+            TransactionStatus::Unmarked => "",
+            TransactionStatus::Pending => "! ",
+            TransactionStatus::Cleared => "* ",
+        };
+        ledger_output.push_str(&format!(
+            "{} {}({}) {}\n",
+            tx.date, status, tx.spec_id, label
+        ));
+        for posting in &tx.postings {
+            let cashflow_tag = posting
+                .source_account
+                .as_ref()
+                .unwrap_or(&posting.account)
+                .cashflow_tag(posting.amount)
+                .map(|tag| format!("{}: {}", CashflowTracingTag::key(), tag.value()));
+            let mut custom_tags = posting
+                .custom_tags
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, v))
+                .collect::<Vec<String>>();
+            // Stable output regardless of the map's iteration order.
+            custom_tags.sort();
+            let comment_str = match posting
+                .comment
+                .clone()
+                .into_iter()
+                .chain(cashflow_tag)
+                .chain(custom_tags.into_iter())
+                .collect::<Vec<String>>()
+            {
+                tags if tags.is_empty() => "".to_string(),
+                tags => format!("       ; {}", tags.join(", ")),
+            };
+            let mut amount = self.format_amount(posting.amount, posting.commodity, false);
+            if let Some(price) = &posting.price {
+                amount.push_str(&self.format_price(price));
+            }
+            let posting_line = format_posting_line(&posting.account.ledger(), &amount);
+            ledger_output.push_str(&format!("{}{}\n", posting_line, comment_str));
+        }
+        let annotations = financial_records
+            .annotations_lookup
+            .get(&tx.spec_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        match self.options.annotation_verbosity {
+            AnnotationVerbosity::None => {}
+            AnnotationVerbosity::Markers if !annotations.is_empty() => {
+                ledger_output.push_str(&format!("{}; [notes: {}]\n", POSTING_INDENT, tx.spec_id));
+            }
+            AnnotationVerbosity::Markers => {}
+            AnnotationVerbosity::Full => {
+                for annotation in annotations {
+                    let lines = match annotation {
+                        // Metadata goes on its own (unwrapped) line, so hledger
+                        // reads each pair as a tag.
+                        Annotation::Custom { title, body, meta } if !meta.is_empty() => {
+                            let mut lines = format_note(&if body.is_empty() {
+                                title.clone()
+                            } else {
+                                format!("{} | {}", title, body)
+                            });
+                            let pairs = meta
+                                .iter()
+                                .map(|(name, value)| format!("{}: {}", name, value))
+                                .collect::<Vec<_>>();
+                            lines.push(format!("    ; {}", pairs.join(", ")));
+                            lines
+                        }
+                        _ => format_note(&annotation.to_string()),
+                    };
+                    lines.iter().for_each(|line| {
+                        ledger_output.push_str(line);
+                        ledger_output.push('\n');
+                    });
+                }
+            }
+        }
+        ledger_output.push('\n');
+    }
+
+    fn print_annotation_appendix(
+        &self,
+        ledger_output: &mut String,
+        financial_records: &FinancialRecords,
+    ) {
+        let sorted_annotations = {
+            let mut v: Vec<_> = financial_records
+                .annotations_lookup
+                .iter()
+                .filter(|(_, annotations)| !annotations.is_empty())
+                .collect();
+            v.sort_by_key(|(spec_id, _)| spec_id.0);
+            v
+        };
+        for (spec_id, annotations) in sorted_annotations {
+            ledger_output.push_str(&format!("; [notes: {}]\n", spec_id));
+            for annotation in annotations {
+                textwrap::wrap(&annotation.to_string(), 94)
+                    .iter()
+                    .for_each(|line| {
+                        ledger_output.push_str(&format!(";   {}\n", line));
+                    });
             }
             ledger_output.push('\n');
         }
@@ -191,7 +475,7 @@ impl HledgerPrinter {
             ledger_output.push_str(&format!("{} <assertion>\n", assertion.date));
             let right = format!(
                 "0 == {}",
-                format_amount(assertion.balance, assertion.currency, false)
+                self.format_amount(assertion.balance, assertion.commodity, false)
             );
             ledger_output.push_str(&format!(
                 "{}\n",
@@ -201,6 +485,35 @@ impl HledgerPrinter {
         }
     }
 
+    /// One periodic transaction per budget, with an unbalanced virtual posting
+    /// (as expected by `hledger balance --budget`). Income budgets are
+    /// negated to match the sign of income postings.
+    fn print_budgets(&self, ledger_output: &mut String, financial_records: &FinancialRecords) {
+        let sorted_budgets = {
+            let mut v: Vec<&Budget> = financial_records.budgets.iter().collect();
+            v.sort_by_key(|b| (b.from, b.to));
+            v
+        };
+        for budget in sorted_budgets {
+            // hledger period ends are exclusive.
+            let end = budget.to.succ_opt().unwrap_or(budget.to);
+            ledger_output.push_str(&format!("~ from {} to {}  ; budget\n", budget.from, end));
+            let amount = if budget.scope.is_income() {
+                -budget.amount
+            } else {
+                budget.amount
+            };
+            ledger_output.push_str(&format!(
+                "{}\n",
+                format_posting_line(
+                    &format!("({})", budget.scope.account().ledger()),
+                    &self.format_amount(amount, budget.currency.into(), false)
+                )
+            ));
+            ledger_output.push('\n');
+        }
+    }
+
     fn print_ledger_extensions(
         &self,
         ledger_output: &mut String,
@@ -259,7 +572,7 @@ impl HledgerPrinter {
                     postings,
                     total,
                     logic,
-                    currency,
+                    commodity,
                 } => {
                     let (title, destination_account): (&str, Account) = match logic {
                         CloseLogic::Retain => ("Retain Earnings", RETAINED_EARNINGS.clone().into()),
@@ -277,8 +590,8 @@ impl HledgerPrinter {
                     for (account, amount) in postings {
                         let right = format!(
                             "{} = {}",
-                            format_amount(*amount, *currency, true),
-                            format_amount(0.0, *currency, true)
+                            self.format_amount(*amount, *commodity, true),
+                            self.format_amount(0.0, *commodity, true)
                         );
                         ledger_output
                             .push_str(&format!("{}\n", format_posting_line(account, &right)));
@@ -290,7 +603,7 @@ impl HledgerPrinter {
                             "{}\n",
                             format_posting_line(
                                 &destination_account.ledger(),
-                                &format_amount(-*total, *currency, true),
+                                &self.format_amount(-*total, *commodity, true),
                             )
                         ));
                     } else {
@@ -320,11 +633,19 @@ impl HledgerPrinter {
     }
 }
 
+/// Writes the buffered output and clears the buffer, keeping its capacity for
+/// the next section or transaction.
+fn flush(writer: &mut impl Write, ledger_output: &mut String) -> io::Result<()> {
+    writer.write_all(ledger_output.as_bytes())?;
+    ledger_output.clear();
+    Ok(())
+}
+
 // Building / manipulating account declarations. Ex:
 // "account Assets:Cash     ; type: C"
 // ----------------------------------------------------------------------------
 
-fn format_account_declaration(account: &Account) -> String {
+pub(crate) fn format_account_declaration(account: &Account) -> String {
     format_account_declaration_raw(&account.ledger(), account.type_tag())
 }
 
@@ -384,6 +705,28 @@ fn parse_posting_line(line: &str) -> Option<(&str, &str)> {
     Some((left, right))
 }
 
+// Building monthly subtotal comment blocks. Ex:
+// "; Subtotals 2024-03:"
+// ";     Assets:Cash                    -1,000.00 $"
+// ----------------------------------------------------------------------------
+
+fn format_monthly_subtotals(year: i32, month: u32, transactions: &[&Transaction]) -> Vec<String> {
+    let mut totals: BTreeMap<(String, &str), (f64, Commodity)> = BTreeMap::new();
+    for posting in transactions.iter().flat_map(|tx| tx.postings.iter()) {
+        totals
+            .entry((posting.account.ledger(), posting.commodity.code()))
+            .or_insert((0.0, posting.commodity))
+            .0 += posting.amount;
+    }
+
+    let mut lines = vec![format!("; Subtotals {:04}-{:02}:", year, month)];
+    for ((account, _), (amount, commodity)) in totals {
+        let line = format_posting_line(&account, &format_amount(amount, commodity, false));
+        lines.push(format!(";{}", &line[1..]));
+    }
+    lines
+}
+
 // Building / manipulating indented note lines. Ex:
 // "    ; Note"
 // ----------------------------------------------------------------------------
@@ -0,0 +1,228 @@
+use fractic_server_error::{CriticalError, ServerError};
+use serde_json::{json, Map, Value};
+
+use crate::entities::{
+    Account, Assertion, CashflowTracingTag, CloseLogic, EndOfYearEntry, FinancialRecords,
+    PostingPrice, Transaction, TransactionPosting, TransactionStatus,
+};
+
+pub(crate) struct JsonPrinter;
+
+impl JsonPrinter {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+
+    pub(crate) fn print_records(
+        &self,
+        financial_records: &FinancialRecords,
+    ) -> Result<String, ServerError> {
+        let value = json!({
+            "transactions": self.transactions(financial_records),
+            "assertions": self.assertions(financial_records),
+            "labels": self.labels(financial_records),
+            "annotations": self.annotations(financial_records),
+            "unreimbursed_entries": self.unreimbursed_entries(financial_records),
+            "ledger_extensions": financial_records.ledger_extensions,
+            "eoy_entries": self.eoy_entries(financial_records),
+        });
+        serde_json::to_string_pretty(&value).map_err(|e| {
+            CriticalError::with_debug("failed to serialize financial records as JSON", &e)
+        })
+    }
+
+    fn transactions(&self, financial_records: &FinancialRecords) -> Value {
+        // Same ordering as the printed ledger.
+        let sorted_transactions = {
+            let mut v: Vec<&Transaction> = financial_records.transactions.iter().collect();
+            v.sort_by_key(|tx| tx.date);
+            v
+        };
+        sorted_transactions
+            .into_iter()
+            .map(|tx| {
+                json!({
+                    "spec_id": tx.spec_id.0,
+                    "date": tx.date.to_string(),
+                    "comment": tx.comment.as_deref(),
+                    "status": match tx.status {
+                        TransactionStatus::Unmarked => "unmarked",
+                        TransactionStatus::Pending => "pending",
+                        TransactionStatus::Cleared => "cleared",
+                    },
+                    "postings": tx.postings.iter().map(posting_json).collect::<Vec<_>>(),
+                })
+            })
+            .collect()
+    }
+
+    fn assertions(&self, financial_records: &FinancialRecords) -> Value {
+        let sorted_assertions = {
+            let mut v: Vec<&Assertion> = financial_records.assertions.iter().collect();
+            v.sort_by_key(|a| a.date);
+            v
+        };
+        sorted_assertions
+            .into_iter()
+            .map(|a| {
+                json!({
+                    "date": a.date.to_string(),
+                    "account": a.account.ledger(),
+                    "balance": a.balance,
+                    "currency": a.commodity.code(),
+                })
+            })
+            .collect()
+    }
+
+    /// Keyed by spec ID.
+    fn labels(&self, financial_records: &FinancialRecords) -> Value {
+        financial_records
+            .label_lookup
+            .iter()
+            .map(|(spec_id, label)| {
+                (
+                    spec_id.to_string(),
+                    json!({
+                        "payee": &*label.payee,
+                        "description": label.description,
+                    }),
+                )
+            })
+            .collect::<Map<String, Value>>()
+            .into()
+    }
+
+    /// Keyed by spec ID. Annotations are rendered as their full note text.
+    fn annotations(&self, financial_records: &FinancialRecords) -> Value {
+        financial_records
+            .annotations_lookup
+            .iter()
+            .filter(|(_, annotations)| !annotations.is_empty())
+            .map(|(spec_id, annotations)| {
+                (
+                    spec_id.to_string(),
+                    annotations
+                        .iter()
+                        .map(|a| Value::String(a.to_string()))
+                        .collect(),
+                )
+            })
+            .collect::<Map<String, Value>>()
+            .into()
+    }
+
+    fn unreimbursed_entries(&self, financial_records: &FinancialRecords) -> Value {
+        // Tracked by entity in a hash map, so sort them for a stable output.
+        let mut entries: Vec<_> = financial_records.unreimbursed_entries.iter().collect();
+        entries.sort_by(|(a, x), (b, y)| {
+            a.cmp(b)
+                .then(x.transaction_date.cmp(&y.transaction_date))
+                .then(x.spec_id.0.cmp(&y.spec_id.0))
+        });
+        entries
+            .into_iter()
+            .map(|(account, entry)| {
+                json!({
+                    "account": Into::<Account>::into(account.clone()).ledger(),
+                    "transaction_date": entry.transaction_date.to_string(),
+                    "total_amount": entry.total_amount,
+                    "credit_postings": entry
+                        .credit_postings
+                        .iter()
+                        .map(posting_json)
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect()
+    }
+
+    fn eoy_entries(&self, financial_records: &FinancialRecords) -> Value {
+        financial_records
+            .eoy_entries
+            .iter()
+            .map(|entry| match entry {
+                EndOfYearEntry::Close {
+                    date,
+                    postings,
+                    total,
+                    logic,
+                    commodity,
+                } => json!({
+                    "type": "close",
+                    "date": date.to_string(),
+                    "logic": match logic {
+                        CloseLogic::Retain => "retain",
+                    },
+                    "postings": postings
+                        .iter()
+                        .map(|(account, amount)| json!({ "account": account, "amount": amount }))
+                        .collect::<Vec<_>>(),
+                    "total": total,
+                    "currency": commodity.code(),
+                }),
+                EndOfYearEntry::Correction {
+                    date,
+                    description,
+                    notes,
+                    macro_output,
+                } => json!({
+                    "type": "correction",
+                    "date": date.to_string(),
+                    "description": description,
+                    "notes": notes,
+                    "ledger": macro_output,
+                }),
+            })
+            .collect()
+    }
+}
+
+fn posting_json(posting: &TransactionPosting) -> Value {
+    let cashflow_tag = posting
+        .source_account
+        .as_ref()
+        .unwrap_or(&posting.account)
+        .cashflow_tag(posting.amount)
+        .map(|tag| tag.value());
+    let mut value = json!({
+        "account": posting.account.ledger(),
+        "account_type": posting.account.type_tag().to_string(),
+        "source_account": posting.source_account.as_ref().map(|a| a.ledger()),
+        "amount": posting.amount,
+        "currency": posting.commodity.code(),
+        "tags": cashflow_tag
+            .map(|tag| (CashflowTracingTag::key().to_string(), Value::String(tag)))
+            .into_iter()
+            .chain(
+                posting
+                    .custom_tags
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            )
+            .collect::<Map<String, Value>>(),
+    });
+    if let Some(comment) = &posting.comment {
+        value["comment"] = Value::String(comment.clone());
+    }
+    if let Some(price) = &posting.price {
+        value["price"] = match price {
+            PostingPrice::Unit { rate, commodity } => {
+                json!({ "rate": rate, "currency": commodity.code() })
+            }
+            PostingPrice::Total { amount, commodity } => {
+                json!({ "total": amount, "currency": commodity.code() })
+            }
+        };
+    }
+    value
+}
+
+impl FinancialRecords {
+    /// Export the processed records as a JSON document (transactions,
+    /// assertions, labels, annotations, unreimbursed entries, and end-of-year
+    /// entries), for consumers that can't easily parse the hledger output.
+    pub fn to_json(&self) -> Result<String, ServerError> {
+        JsonPrinter::new().print_records(self)
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use crate::{
+    domain::entities::generation_metadata::SortedMap,
+    entities::{Account, Commodity, RoundingMode},
+};
+
+/// Options controlling the layout of the generated hledger journal.
+///
+/// The defaults reproduce the standard output.
+#[derive(Clone, Default)]
+pub struct PrintOptions {
+    /// Start the journal with a comment giving the crate version, processing
+    /// time, and hashes of the input specs and of the processing and print
+    /// options (see `FinancialRecords::metadata`), so it can be traced back to
+    /// the inputs that produced it. Off by default, since the timestamp makes
+    /// every generated journal differ (unless `ProcessOptions::clock` is
+    /// fixed).
+    pub generation_header: bool,
+    /// Group transactions under "; ---- YYYY-MM ----" month headers.
+    pub group_by_month: bool,
+    /// When grouping by month, also print the month's net movement per account
+    /// and currency as a comment block after its transactions.
+    pub monthly_subtotals: bool,
+    /// How much of each transaction's annotations to print inline.
+    pub annotation_verbosity: AnnotationVerbosity,
+    /// Collect the full annotation text into an "Annotations" section at the
+    /// end of the journal, keyed by spec ID.
+    pub annotation_appendix: bool,
+    /// Declare every account in the chart up front (one per classification,
+    /// plus the standard named sub-accounts and `declared_accounts`), rather
+    /// than only the accounts used in postings. This keeps `hledger --strict`
+    /// working when later periods introduce new accounts.
+    pub full_chart_of_accounts: bool,
+    /// Additional named sub-accounts to declare, regardless of usage.
+    pub declared_accounts: Vec<Account>,
+    /// Follow each account declaration with a comment explaining its
+    /// classification (where one is documented).
+    pub account_notes: bool,
+    /// Replace the assertions section with an `include` directive for the
+    /// given path (ex. "assertions.journal"). The assertions journal itself is
+    /// then generated separately (see `IfrsHledgerUtil::print_assertions`), so
+    /// it can be toggled or regenerated independently of the transactions.
+    pub assertions_include: Option<String>,
+    /// Print `FinancialRecords::budgets` as periodic transactions, for use
+    /// with `hledger balance --budget`.
+    pub budget_entries: bool,
+    /// Rounding of amounts with more decimal places than their currency. To
+    /// display amounts consistently with processing, match the commodity
+    /// handler's `rounding_mode()`.
+    pub rounding_mode: RoundingMode,
+    /// Decimal places to display for specific commodities, instead of their
+    /// standard (ex. 0 for KRW-only journals enforcing whole amounts). Should
+    /// match the commodity handler's `decimal_places()`.
+    pub decimal_places: HashMap<Commodity, u32>,
+}
+
+impl std::fmt::Debug for PrintOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrintOptions")
+            .field("generation_header", &self.generation_header)
+            .field("group_by_month", &self.group_by_month)
+            .field("monthly_subtotals", &self.monthly_subtotals)
+            .field("annotation_verbosity", &self.annotation_verbosity)
+            .field("annotation_appendix", &self.annotation_appendix)
+            .field("full_chart_of_accounts", &self.full_chart_of_accounts)
+            .field("declared_accounts", &self.declared_accounts)
+            .field("account_notes", &self.account_notes)
+            .field("assertions_include", &self.assertions_include)
+            .field("budget_entries", &self.budget_entries)
+            .field("rounding_mode", &self.rounding_mode)
+            .field("decimal_places", &SortedMap(&self.decimal_places))
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnnotationVerbosity {
+    /// Omit annotations from transactions entirely.
+    None,
+    /// Print a single "; [notes: <spec_id>]" marker on annotated transactions.
+    Markers,
+    /// Print the full, word-wrapped annotation text.
+    #[default]
+    Full,
+}
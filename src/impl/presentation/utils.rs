@@ -1,54 +1,129 @@
-use iso_currency::Currency;
+use std::collections::HashMap;
+
+use fractic_server_error::ServerError;
 use num_format::{Locale, ToFormattedString as _};
+use regex::Regex;
 
-/// Standard number decimal places for the given currency
-/// (ex. JPY = 0, USD = 2).
-fn decimal_places(currency: Currency) -> usize {
-    currency.exponent().unwrap_or(0) as usize
-}
+use crate::{
+    entities::{Commodity, RoundingMode},
+    errors::UnreplacedPlaceholdersRemain,
+};
 
-/// Format cash amount with currency symbol, correct number of decimal places,
+/// Format amount with commodity symbol, correct number of decimal places,
 /// proper thousands separators, and optional trailing decimal mark.
 ///
 /// For consistency, uses en locale ('.' as decimal mark, i.e. 1,000.00)
 /// regardless of user's locale or currency. Could be generalized in the future.
 ///
-/// For currencies with 0 decimal places, a decimal mark is always included if
-/// 'trailing_decimal' is true. For other currencies, this flag has no effect.
-pub fn format_amount(amount: f64, currency: Currency, trailing_decimal: bool) -> String {
-    let decimal_places = decimal_places(currency);
+/// For commodities with 0 decimal places, a decimal mark is always included if
+/// 'trailing_decimal' is true. For other commodities, this flag has no effect.
+pub fn format_amount(
+    amount: f64,
+    commodity: impl Into<Commodity>,
+    trailing_decimal: bool,
+) -> String {
+    format_amount_rounded(amount, commodity, trailing_decimal, RoundingMode::HalfUp)
+}
+
+/// Same as `format_amount`, using the given rounding mode for amounts with
+/// more decimal places than the commodity.
+pub fn format_amount_rounded(
+    amount: f64,
+    commodity: impl Into<Commodity>,
+    trailing_decimal: bool,
+    rounding: RoundingMode,
+) -> String {
+    let commodity = commodity.into();
+    format_amount_with_precision(
+        amount,
+        commodity,
+        commodity.decimal_places() as usize,
+        trailing_decimal,
+        rounding,
+    )
+}
+
+/// Same as `format_amount_rounded`, with a different number of decimal places
+/// than the commodity's standard (ex. 0 to only show whole amounts).
+pub fn format_amount_with_precision(
+    amount: f64,
+    commodity: impl Into<Commodity>,
+    decimal_places: usize,
+    trailing_decimal: bool,
+    rounding: RoundingMode,
+) -> String {
+    let symbol = journal_symbol(commodity.into());
+    let factor = 10_i64.pow(decimal_places as u32);
+    let units = (rounding.round(amount, decimal_places as i32) * factor as f64).round() as i64;
+    let amount_integer_part = (units / factor).to_formatted_string(&Locale::en);
     if decimal_places == 0 {
-        let amount_rounded = (amount.round() as i64).to_formatted_string(&Locale::en);
         return format!(
             "{}{} {}",
-            amount_rounded,
+            amount_integer_part,
             if trailing_decimal { "." } else { "" },
-            currency.symbol()
+            symbol
         );
+    }
+
+    // There is an edge-case where the number is negative, but the integer
+    // part is 0, causing the sign to be omitted.
+    let edge_case_sign = if units < 0 && units / factor == 0 {
+        "-"
     } else {
-        let amount_integer_part = (amount.trunc() as i64).to_formatted_string(&Locale::en);
-        let amount_fractional_part = format!("{:.decimal_places$}", amount.fract())
-            .split('.')
-            .nth(1)
-            .map(|f| f.to_string())
-            .unwrap_or_default();
-
-        // There is an edge-case where the number is negative, but the integer
-        // part is 0, causing the sign to be omitted.
-        let edge_case_sign = if amount < 0.0 && amount.trunc() as i64 == 0 {
-            "-"
+        ""
+    };
+
+    format!(
+        "{}{}.{:0decimal_places$} {}",
+        edge_case_sign,
+        amount_integer_part,
+        (units % factor).abs(),
+        symbol,
+    )
+}
+
+/// Custom commodity symbols hledger can't read unquoted (ex. containing
+/// digits or spaces) are double-quoted.
+fn journal_symbol(commodity: Commodity) -> String {
+    let symbol = commodity.symbol();
+    match commodity {
+        Commodity::Custom { .. } if !symbol.chars().all(char::is_alphabetic) => {
+            format!("\"{}\"", symbol)
+        }
+        _ => symbol,
+    }
+}
+
+/// Replaces each `{{key}}` in the template with the value for the key (ex. to
+/// fill a statement template with amounts).
+pub fn replace_all_placeholders_in_string(
+    content: String,
+    placeholders: &HashMap<String, String>,
+    error_if_unreplaced_placeholders_remain: bool,
+) -> Result<String, ServerError> {
+    // Use a regex to find placeholders of the form {{Key}}.
+    let placeholder_pattern =
+        Regex::new(r"\{\{(\w+)\}\}").expect("hardcoded regex should be valid");
+
+    // Replace all placeholders with their corresponding values.
+    let mut unknown_keys = Vec::new();
+    let result = placeholder_pattern.replace_all(&content, |caps: &regex::Captures| {
+        let key = &caps[1]; // The content inside {{ }}.
+        if let Some(value) = placeholders.get(key) {
+            value.clone()
         } else {
-            ""
-        };
+            unknown_keys.push(key.to_string());
+            caps[0].to_string() // The full '{{Key}}' string.
+        }
+    });
 
-        format!(
-            "{}{}.{:0decimal_places$} {}",
-            edge_case_sign,
-            amount_integer_part,
-            amount_fractional_part,
-            currency.symbol(),
-        )
+    let replaced_content = result.into_owned();
+
+    if error_if_unreplaced_placeholders_remain && !unknown_keys.is_empty() {
+        return Err(UnreplacedPlaceholdersRemain::new(&unknown_keys));
     }
+
+    Ok(replaced_content)
 }
 
 /// Format a 100-char comment header with the given title.
@@ -0,0 +1,162 @@
+use chrono::NaiveDate;
+use iso_currency::Currency;
+
+use crate::{
+    entities::{Account, FinancialRecords, Transaction, TransactionPosting},
+    ext::standard_accounts::FOREIGN_CURRENCY_TRANSLATION_RESERVE,
+};
+
+/// Translation of a foreign operation's records from its functional currency
+/// into the group's presentation currency (IAS 21), so they can be combined
+/// into consolidated statements:
+///
+/// - assets and liabilities at the closing rate,
+/// - income and expenses at the average rate,
+/// - equity at the historical rate of each posting.
+///
+/// The resulting exchange differences are recognized in OCI, on the
+/// `FOREIGN_CURRENCY_TRANSLATION_RESERVE` account. Each translated transaction
+/// carries its own share of the adjustment, so it remains balanced.
+pub struct ForeignOperationTranslation {
+    functional_currency: Currency,
+    presentation_currency: Currency,
+    rates: TranslationRates,
+}
+
+/// Rates in units of the presentation currency per unit of the functional
+/// currency.
+#[derive(Debug, Clone)]
+pub struct TranslationRates {
+    /// Rate at the reporting date.
+    pub closing: f64,
+    /// Average rate over the reporting period.
+    pub average: f64,
+    /// Rates in effect from each date until the next one, for equity postings
+    /// (ex. share capital at the rate on the date of issue). Equity postings
+    /// dated before the first rate use the average rate.
+    pub historical: Vec<(NaiveDate, f64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranslatedRecords {
+    /// Postings in the functional currency are translated; postings in other
+    /// currencies are kept as-is. Balance assertions, closing / correction
+    /// entries, ledger extensions and unreimbursed entries are not translated,
+    /// and are left out.
+    pub records: FinancialRecords,
+    /// Cumulative translation adjustment booked to OCI (positive for a gain).
+    pub translation_adjustment: f64,
+}
+
+impl ForeignOperationTranslation {
+    pub fn new(
+        functional_currency: Currency,
+        presentation_currency: Currency,
+        rates: TranslationRates,
+    ) -> Self {
+        Self {
+            functional_currency,
+            presentation_currency,
+            rates,
+        }
+    }
+
+    pub fn translate(&self, records: &FinancialRecords) -> TranslatedRecords {
+        let mut translation_adjustment = 0.0;
+        let transactions = records
+            .transactions
+            .iter()
+            .map(|tx| {
+                let mut postings: Vec<TransactionPosting> = tx
+                    .postings
+                    .iter()
+                    .map(|p| self.translate_posting(tx.date, p))
+                    .collect();
+                // Functional currency postings balance before translation, so
+                // any remainder is the exchange difference.
+                let imbalance = round(
+                    tx.postings
+                        .iter()
+                        .zip(&postings)
+                        .filter(|(original, _)| original.commodity == self.functional_currency)
+                        .map(|(_, translated)| translated.amount)
+                        .sum::<f64>(),
+                    self.presentation_currency,
+                );
+                if imbalance != 0.0 {
+                    postings.push(TransactionPosting::new(
+                        FOREIGN_CURRENCY_TRANSLATION_RESERVE.clone().into(),
+                        -imbalance,
+                        self.presentation_currency,
+                    ));
+                    translation_adjustment -= imbalance;
+                }
+                Transaction {
+                    spec_id: tx.spec_id,
+                    date: tx.date,
+                    postings,
+                    comment: tx.comment.clone(),
+                    status: tx.status,
+                }
+            })
+            .collect();
+
+        TranslatedRecords {
+            records: FinancialRecords {
+                transactions,
+                assertions: Vec::new(),
+                ledger_extensions: Vec::new(),
+                eoy_entries: Vec::new(),
+                label_lookup: records.label_lookup.clone(),
+                annotations_lookup: records.annotations_lookup.clone(),
+                unreimbursed_entries: Vec::new(),
+                budgets: Vec::new(),
+                payees: records.payees.clone(),
+                metadata: records.metadata.clone(),
+            },
+            // A credit to the reserve is a gain.
+            translation_adjustment: -round(translation_adjustment, self.presentation_currency),
+        }
+    }
+}
+
+// Private.
+// ----------------------------------------------------------------------------
+
+impl ForeignOperationTranslation {
+    fn translate_posting(
+        &self,
+        date: NaiveDate,
+        posting: &TransactionPosting,
+    ) -> TransactionPosting {
+        if posting.commodity != self.functional_currency {
+            return posting.clone();
+        }
+        let rate = match posting.account {
+            Account::Asset(_) | Account::Liability(_) => self.rates.closing,
+            Account::Income(_) | Account::Expense(_) => self.rates.average,
+            Account::Equity(_) => self.historical_rate(date),
+        };
+        TransactionPosting {
+            amount: round(posting.amount * rate, self.presentation_currency),
+            commodity: self.presentation_currency.into(),
+            // Translated at the report rates instead.
+            price: None,
+            ..posting.clone()
+        }
+    }
+
+    fn historical_rate(&self, date: NaiveDate) -> f64 {
+        self.rates
+            .historical
+            .iter()
+            .filter(|(d, _)| *d <= date)
+            .max_by_key(|(d, _)| *d)
+            .map_or(self.rates.average, |(_, rate)| *rate)
+    }
+}
+
+fn round(amount: f64, currency: Currency) -> f64 {
+    let factor = 10_f64.powi(currency.exponent().unwrap_or(0) as i32);
+    (amount * factor).round() / factor + 0.0
+}
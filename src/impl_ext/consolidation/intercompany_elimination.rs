@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::entities::{
+    Account, AssetClassification, Commodity, FinancialRecords, Transaction, TransactionPosting,
+    TransactionSpecId, TransactionStatus, INTERCOMPANY_TAG,
+};
+
+/// Nets intercompany balances between the entities of a group, for
+/// consolidated reports. Postings tagged as intercompany (see
+/// `TransactionSpec::intercompany`) on receivables/payables and
+/// revenue/expense accounts are reversed, one elimination transaction per
+/// entity pair. Cash and equity postings are kept, since they reflect real
+/// balances of the group.
+///
+/// Pairs whose intercompany postings don't net to zero (ex. one side booked a
+/// sale the other never recorded) are not eliminated, and are reported as
+/// mismatches instead.
+pub struct IntercompanyElimination<'a> {
+    as_of: NaiveDate,
+    entities: Vec<(String, &'a FinancialRecords)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EliminationResult {
+    /// To be added to the combined records of the group. Elimination
+    /// transactions are not generated from a spec, and use spec ID 0.
+    pub eliminations: Vec<Transaction>,
+    pub mismatches: Vec<IntercompanyMismatch>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IntercompanyMismatch {
+    pub entity: String,
+    pub counterparty: String,
+    pub commodity: Commodity,
+    /// Net of both entities' intercompany postings with each other. Zero if
+    /// the pair matches.
+    pub difference: f64,
+}
+
+/// Intercompany receivables and payables of an entity with a counterparty
+/// (see `IntercompanyElimination::report`). For a matching pair, the
+/// counterparty's balance with the entity mirrors it.
+#[derive(Debug, Clone)]
+pub struct IntercompanyBalance {
+    pub entity: String,
+    pub counterparty: String,
+    pub commodity: Commodity,
+    /// Due from the counterparty (debit balance of tagged assets).
+    pub receivable: f64,
+    /// Due to the counterparty (credit balance of tagged liabilities).
+    pub payable: f64,
+}
+
+impl IntercompanyBalance {
+    /// Net amount due from the counterparty (negative if due to it).
+    pub fn net(&self) -> f64 {
+        self.receivable - self.payable
+    }
+}
+
+/// Entity pair (ordered by name).
+type Pair = (String, String);
+
+impl<'a> IntercompanyElimination<'a> {
+    /// Only postings up to (and including) `as_of` are eliminated.
+    pub fn new(as_of: NaiveDate) -> Self {
+        Self {
+            as_of,
+            entities: Vec::new(),
+        }
+    }
+
+    /// Add a group entity. The name must match the counterparty used by the
+    /// other entities' intercompany specs.
+    pub fn with_entity(mut self, name: impl Into<String>, records: &'a FinancialRecords) -> Self {
+        self.entities.push((name.into(), records));
+        self
+    }
+
+    /// Intercompany receivable and payable balances as of the date, by entity,
+    /// counterparty and commodity (from both sides of each pair, so they can
+    /// be reviewed before netting them out with `generate`). Ordered by
+    /// entity, then counterparty.
+    pub fn report(&self) -> Vec<IntercompanyBalance> {
+        let mut balances: BTreeMap<(String, String, &'static str), IntercompanyBalance> =
+            BTreeMap::new();
+        for (entity, records) in &self.entities {
+            let postings = records
+                .transactions
+                .iter()
+                .filter(|tx| tx.date <= self.as_of)
+                .flat_map(|tx| tx.postings.iter());
+            for posting in postings {
+                let Some(counterparty) = posting.custom_tags.get(INTERCOMPANY_TAG) else {
+                    continue;
+                };
+                let balance = balances
+                    .entry((
+                        entity.clone(),
+                        counterparty.clone(),
+                        posting.commodity.code(),
+                    ))
+                    .or_insert_with(|| IntercompanyBalance {
+                        entity: entity.clone(),
+                        counterparty: counterparty.clone(),
+                        commodity: posting.commodity,
+                        receivable: 0.0,
+                        payable: 0.0,
+                    });
+                match &posting.account {
+                    Account::Asset(a) if a.1 != AssetClassification::CashAndCashEquivalents => {
+                        balance.receivable += posting.amount
+                    }
+                    Account::Liability(_) => balance.payable -= posting.amount,
+                    _ => {}
+                }
+            }
+        }
+        balances
+            .into_values()
+            .map(|b| IntercompanyBalance {
+                receivable: round(b.receivable, b.commodity),
+                payable: round(b.payable, b.commodity),
+                ..b
+            })
+            .filter(|b| b.receivable != 0.0 || b.payable != 0.0)
+            .collect()
+    }
+
+    pub fn generate(&self) -> EliminationResult {
+        // Sum the eliminated postings by entity pair, then account.
+        let mut balances: BTreeMap<Pair, Vec<TransactionPosting>> = BTreeMap::new();
+        for (entity, records) in &self.entities {
+            let postings = records
+                .transactions
+                .iter()
+                .filter(|tx| tx.date <= self.as_of)
+                .flat_map(|tx| tx.postings.iter())
+                .filter(|p| is_eliminated(&p.account));
+            for posting in postings {
+                let Some(counterparty) = posting.custom_tags.get(INTERCOMPANY_TAG) else {
+                    continue;
+                };
+                if !self.entities.iter().any(|(name, _)| name == counterparty) {
+                    continue;
+                }
+                let pair = if entity <= counterparty {
+                    (entity.clone(), counterparty.clone())
+                } else {
+                    (counterparty.clone(), entity.clone())
+                };
+                let sums = balances.entry(pair).or_default();
+                match sums
+                    .iter_mut()
+                    .find(|s| s.account == posting.account && s.commodity == posting.commodity)
+                {
+                    Some(sum) => sum.amount += posting.amount,
+                    None => sums.push(TransactionPosting::new(
+                        posting.account.clone(),
+                        posting.amount,
+                        posting.commodity,
+                    )),
+                }
+            }
+        }
+
+        let mut eliminations = Vec::new();
+        let mut mismatches = Vec::new();
+        for ((entity, counterparty), sums) in balances {
+            let mut net: Vec<(Commodity, f64)> = Vec::new();
+            for sum in &sums {
+                match net.iter_mut().find(|(c, _)| *c == sum.commodity) {
+                    Some((_, amount)) => *amount += sum.amount,
+                    None => net.push((sum.commodity, sum.amount)),
+                }
+            }
+            let unmatched = net
+                .into_iter()
+                .map(|(commodity, amount)| (commodity, round(amount, commodity)))
+                .filter(|(_, amount)| *amount != 0.0)
+                .collect::<Vec<_>>();
+            if !unmatched.is_empty() {
+                mismatches.extend(unmatched.into_iter().map(|(commodity, difference)| {
+                    IntercompanyMismatch {
+                        entity: entity.clone(),
+                        counterparty: counterparty.clone(),
+                        commodity,
+                        difference,
+                    }
+                }));
+                continue;
+            }
+            let postings = sums
+                .into_iter()
+                .filter(|s| round(s.amount, s.commodity) != 0.0)
+                .map(|s| TransactionPosting::new(s.account, -s.amount, s.commodity))
+                .collect::<Vec<_>>();
+            if postings.is_empty() {
+                continue;
+            }
+            eliminations.push(Transaction {
+                spec_id: TransactionSpecId::new(0),
+                date: self.as_of,
+                postings,
+                comment: Some(
+                    format!("Intercompany elimination: {entity} / {counterparty}").into(),
+                ),
+                status: TransactionStatus::Unmarked,
+            });
+        }
+
+        EliminationResult {
+            eliminations,
+            mismatches,
+        }
+    }
+}
+
+fn is_eliminated(account: &Account) -> bool {
+    match account {
+        Account::Asset(a) => a.1 != AssetClassification::CashAndCashEquivalents,
+        Account::Liability(_) | Account::Income(_) | Account::Expense(_) => true,
+        Account::Equity(_) => false,
+    }
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    (amount * factor).round() / factor + 0.0
+}
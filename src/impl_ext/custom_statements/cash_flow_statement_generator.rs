@@ -1,26 +1,34 @@
 use std::collections::HashMap;
 use std::iter::zip;
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::{self, ScopedJoinHandle};
 
-use fractic_server_error::ServerError;
+use fractic_server_error::{CriticalError, ServerError};
 use iso_currency::Currency;
+use serde_json::{json, Map, Value};
 
 use crate::entities::{
-    asset_tl, liability_tl, Account, AssetClassification, CashflowTracingTag,
-    LiabilityClassification,
+    asset_tl, liability_tl, Account, AssetClassification, CashflowTracingTag, FinancialRecords,
+    LiabilityClassification, PhaseProgress, ProgressPhase, ProgressReporter,
 };
-use crate::errors::{HledgerInvalidPath, InvalidCashFlowStatementPeriods, InvalidIsoCurrencyCode};
+use crate::errors::{InvalidCashFlowStatementPeriods, InvalidIsoCurrencyCode};
 use crate::presentation::utils::format_amount;
 
-use super::utils::{
-    hledger, hledger_register, replace_all_placeholders_in_string, split_sections, Query,
-    RegisterOutput, RegisterQuery, Return,
+use super::custom_statement_generator::CustomStatementGenerator;
+use super::report_period::ReportPeriod;
+use super::statement_format::StatementFormat;
+use super::statement_toolkit::{
+    hledger, hledger_register, hledger_sources, replace_all_placeholders_in_string, split_sections,
+    LedgerSource, Query, RegisterOutput, RegisterQuery, Return, SourceRecord,
 };
 
 pub struct CashFlowStatementGenerator {
-    ledger_path: PathBuf,
-    periods: Vec<String>,
+    ledger: LedgerSource,
+    periods: Vec<ReportPeriod>,
     currency: Currency,
+    progress: Option<Arc<dyn ProgressReporter>>,
+    footnote_materiality: Option<f64>,
 }
 
 struct PeriodReport {
@@ -29,6 +37,18 @@ struct PeriodReport {
     non_cash_reclassifications: Vec<String>,
 }
 
+/// Specs contributing to the material line items of the latest period.
+struct Footnotes {
+    period: String,
+    line_items: Vec<(&'static str, Vec<SourceRecord>)>,
+}
+
+/// Line items of one activity section, keyed by placeholder.
+struct SectionReport {
+    net: f64,
+    amounts: Vec<(&'static str, f64)>,
+}
+
 const START_INDEX: usize = 64; // (65th char)
 const COL_PADDING_LEFT: usize = 4;
 const COL_PADDING_RIGHT: usize = 2;
@@ -100,58 +120,264 @@ impl IntoCurrency for Currency {
 }
 
 impl CashFlowStatementGenerator {
-    pub fn new<P, I, S>(
+    pub fn new<P, I>(
         ledger_path: P,
         periods: I,
         currency: impl IntoCurrency,
     ) -> Result<Self, ServerError>
     where
         P: AsRef<Path>,
-        I: IntoIterator<Item = S>,
-        S: AsRef<str>,
+        I: IntoIterator<Item = ReportPeriod>,
+    {
+        Self::with_ledger(LedgerSource::from_path(ledger_path)?, periods, currency)
+    }
+
+    /// Same as `new`, but queries the journal of the given records (passed to
+    /// hledger in memory) rather than a journal file.
+    pub fn from_records<I>(
+        financial_records: &FinancialRecords,
+        periods: I,
+        currency: impl IntoCurrency,
+    ) -> Result<Self, ServerError>
+    where
+        I: IntoIterator<Item = ReportPeriod>,
     {
-        let periods: Vec<String> = periods
-            .into_iter()
-            .map(|p| p.as_ref().to_string())
-            .collect();
+        Self::with_ledger(
+            LedgerSource::from_records(financial_records),
+            periods,
+            currency,
+        )
+    }
+
+    fn with_ledger<I>(
+        ledger: LedgerSource,
+        periods: I,
+        currency: impl IntoCurrency,
+    ) -> Result<Self, ServerError>
+    where
+        I: IntoIterator<Item = ReportPeriod>,
+    {
+        let periods: Vec<ReportPeriod> = periods.into_iter().collect();
         if periods.is_empty() {
             return Err(InvalidCashFlowStatementPeriods::new());
         }
         Ok(Self {
-            ledger_path: ledger_path
-                .as_ref()
-                .canonicalize()
-                .map_err(|e| {
-                    HledgerInvalidPath::with_debug(
-                        &ledger_path.as_ref().to_string_lossy().to_string(),
-                        &e,
-                    )
-                })?
-                .to_path_buf(),
+            ledger,
             periods,
             currency: currency.try_into()?,
+            progress: None,
+            footnote_materiality: None,
         })
     }
 
+    /// Report progress (one step per period) while generating.
+    pub fn with_progress(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.progress = Some(reporter);
+        self
+    }
+
+    /// Append footnotes listing the specs (and their payees) contributing to
+    /// each line item with an amount of at least `materiality` in the latest
+    /// period, for drill-down from the statement to the source records. Only
+    /// included in the text and JSON formats.
+    pub fn with_footnotes(mut self, materiality: f64) -> Self {
+        self.footnote_materiality = Some(materiality);
+        self
+    }
+
     pub fn generate(self) -> Result<String, ServerError> {
+        self.generate_with_format(StatementFormat::Text)
+    }
+
+    pub fn generate_with_format(self, format: StatementFormat) -> Result<String, ServerError> {
+        let mut progress = PhaseProgress::start(
+            self.progress.clone(),
+            ProgressPhase::GeneratingStatement,
+            self.periods.len(),
+        );
         let reports = self
             .periods
             .iter()
-            .map(|period| self.generate_period_report(period))
+            .map(|period| {
+                let report = self.generate_period_report(period)?;
+                progress.advance();
+                Ok(report)
+            })
             .collect::<Result<Vec<PeriodReport>, ServerError>>()?;
+        let footnotes = match (format, self.footnote_materiality) {
+            (StatementFormat::Csv, _) | (_, None) => None,
+            (_, Some(materiality)) => self.footnotes(&reports, materiality)?,
+        };
+        match format {
+            StatementFormat::Text => self.render_text(&reports, footnotes.as_ref()),
+            StatementFormat::Json => self.render_json(&reports, footnotes.as_ref()),
+            StatementFormat::Csv => self.render_csv(&reports),
+        }
+    }
+
+    fn render_text(
+        &self,
+        reports: &[PeriodReport],
+        footnotes: Option<&Footnotes>,
+    ) -> Result<String, ServerError> {
         let template_bytes = include_bytes!("../../../res/cash_flow_statement_template.txt");
         let template = String::from_utf8_lossy(template_bytes).to_string();
-        let layout = self.build_report_layout(&reports);
-        let placeholder_map = self.build_placeholder_map(&reports, &layout);
+        let layout = self.build_report_layout(reports);
+        let placeholder_map = self.build_placeholder_map(reports, &layout);
         let filled = replace_all_placeholders_in_string(template, &placeholder_map, true)?;
-        Ok(extend_column_separators(filled, &layout))
+        let mut statement = extend_column_separators(filled, &layout);
+        if let Some(footnotes) = footnotes {
+            statement.push_str(&format!(
+                "\n\n\nLine Item Sources ({})\n{}\n",
+                footnotes.period,
+                HR_CHAR
+                    .to_string()
+                    .repeat(START_INDEX + layout.total_width())
+            ));
+            for (key, sources) in &footnotes.line_items {
+                statement.push_str(&format!("\n{}:\n", key));
+                for source in sources {
+                    statement.push_str(&format!("• ({}) {}\n", source.spec_id, source.payee));
+                }
+            }
+        }
+        Ok(statement)
     }
 
-    fn generate_period_report(&self, period: &str) -> Result<PeriodReport, ServerError> {
+    /// Line items are keyed by the same names as the text template
+    /// placeholders.
+    fn render_json(
+        &self,
+        reports: &[PeriodReport],
+        footnotes: Option<&Footnotes>,
+    ) -> Result<String, ServerError> {
+        let mut value = json!({
+            "currency": self.currency.code(),
+            "periods": reports
+                .iter()
+                .map(|report| {
+                    json!({
+                        "period": report.period,
+                        "amounts": PLACEHOLDER_KEYS
+                            .iter()
+                            .map(|key| (key.to_string(), json!(report.amounts[key])))
+                            .collect::<Map<String, Value>>(),
+                        "non_cash_reclassifications": report.non_cash_reclassifications,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        });
+        if let Some(footnotes) = footnotes {
+            value["footnotes"] = json!({
+                "period": footnotes.period,
+                "line_items": footnotes
+                    .line_items
+                    .iter()
+                    .map(|(key, sources)| {
+                        let sources = sources
+                            .iter()
+                            .map(|source| {
+                                json!({ "spec_id": source.spec_id.0, "payee": source.payee })
+                            })
+                            .collect::<Vec<_>>();
+                        (key.to_string(), json!(sources))
+                    })
+                    .collect::<Map<String, Value>>(),
+            });
+        }
+        serde_json::to_string_pretty(&value).map_err(|e| {
+            CriticalError::with_debug("failed to serialize cash flow statement as JSON", &e)
+        })
+    }
+
+    fn render_csv(&self, reports: &[PeriodReport]) -> Result<String, ServerError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let to_error = |e: csv::Error| {
+            CriticalError::with_debug("failed to write cash flow statement as CSV", &e)
+        };
+        writer
+            .write_record(std::iter::once("item").chain(reports.iter().map(|r| r.period.as_str())))
+            .map_err(to_error)?;
+        for key in PLACEHOLDER_KEYS {
+            writer
+                .write_record(
+                    std::iter::once(key.to_string())
+                        .chain(reports.iter().map(|r| r.amounts[key].to_string())),
+                )
+                .map_err(to_error)?;
+        }
+        let bytes = writer.into_inner().map_err(|e| {
+            CriticalError::with_debug("failed to write cash flow statement as CSV", &e.to_string())
+        })?;
+        String::from_utf8(bytes).map_err(|e| {
+            CriticalError::with_debug("failed to write cash flow statement as CSV", &e)
+        })
+    }
+
+    /// The sections (and the opening balance and disclosures) are queried
+    /// concurrently, since they are independent.
+    fn generate_period_report(
+        &self,
+        report_period: &ReportPeriod,
+    ) -> Result<PeriodReport, ServerError> {
+        let period = &report_period.to_hledger();
+        let (operating, investing, financing, balance_opening, non_cash_reclassifications) =
+            thread::scope(|s| {
+                let operating = s.spawn(|| self.operating_activities(period));
+                let investing = s.spawn(|| self.investing_activities(period));
+                let financing = s.spawn(|| self.financing_activities(period));
+                let balance_opening = s.spawn(|| self.period_start_balance(period));
+                let non_cash_reclassifications = self.non_cash_reclassifications(period);
+                (
+                    join(operating),
+                    join(investing),
+                    join(financing),
+                    join(balance_opening),
+                    non_cash_reclassifications,
+                )
+            });
+        let (operating, investing, financing) = (operating?, investing?, financing?);
+
+        // -------------------------------------
+        // RECONCILIATION
+        // -------------------------------------
+
+        let balance_opening = balance_opening?;
+        let balance_change = operating.net + investing.net + financing.net;
+        let balance_before_exchange = balance_opening + balance_change;
+        let exchange_rate_effects = 0.0;
+        let balance_closing = balance_before_exchange + exchange_rate_effects;
+
+        // -------------------------------------
+        // ADDITIONAL DISCLOSURES
+        // -------------------------------------
+
+        let non_cash_reclassifications = non_cash_reclassifications?;
+
         // -------------------------------------
-        // OPERATING ACTIVITIES
+        // BUILD REPORT
         // -------------------------------------
 
+        Ok(PeriodReport {
+            period: report_period.to_string(),
+            amounts: operating
+                .amounts
+                .into_iter()
+                .chain(investing.amounts)
+                .chain(financing.amounts)
+                .chain([
+                    ("balance_opening", balance_opening),
+                    ("balance_change", balance_change),
+                    ("balance_before_exchange", balance_before_exchange),
+                    ("exchange_rate_effects", exchange_rate_effects),
+                    ("balance_closing", balance_closing),
+                ])
+                .collect(),
+            non_cash_reclassifications,
+        })
+    }
+
+    fn operating_activities(&self, period: &str) -> Result<SectionReport, ServerError> {
         let net_income = self.net_income(period)?;
 
         // Adjustments for non-cash items.
@@ -181,9 +407,9 @@ impl CashFlowStatementGenerator {
             self.change_in_liability(period, LiabilityClassification::AccruedExpenses)?;
         let diff_deferred_revenue =
             self.change_in_liability(period, LiabilityClassification::DeferredRevenue)?;
-        let diff_other_current_liabilities = self
-            .change_in_liability(period, LiabilityClassification::ShortTermDebt)?
-            + self.change_in_liability(period, LiabilityClassification::OtherCurrentLiabilities)?;
+        // Short-term debt is traced as financing activities.
+        let diff_other_current_liabilities =
+            self.change_in_liability(period, LiabilityClassification::OtherCurrentLiabilities)?;
 
         // Cash flows included in investing or financing activities.
         //
@@ -203,10 +429,31 @@ impl CashFlowStatementGenerator {
             + diff_other_current_liabilities
             - gain_loss_sale_assets;
 
-        // -------------------------------------
-        // INVESTING ACTIVITIES
-        // -------------------------------------
+        Ok(SectionReport {
+            net: net_operating,
+            amounts: vec![
+                ("net_income", net_income),
+                ("nce_depreciation", nce_depreciation),
+                ("nce_amortization", nce_amortization),
+                ("nce_other", nce_other),
+                ("diff_accounts_receivable", diff_accounts_receivable),
+                ("diff_inventory", diff_inventory),
+                ("diff_prepaid_expenses", diff_prepaid_expenses),
+                ("diff_other_current_assets", diff_other_current_assets),
+                ("diff_accounts_payable", diff_accounts_payable),
+                ("diff_accrued_expenses", diff_accrued_expenses),
+                ("diff_deferred_revenue", diff_deferred_revenue),
+                (
+                    "diff_other_current_liabilities",
+                    diff_other_current_liabilities,
+                ),
+                ("gain_loss_sale_assets", gain_loss_sale_assets),
+                ("net_operating", net_operating),
+            ],
+        })
+    }
 
+    fn investing_activities(&self, period: &str) -> Result<SectionReport, ServerError> {
         let out_ppe = self.cash_outflow_by_tag(period, CashflowTracingTag::CashOutflowPpe)?;
         let out_intangible_assets =
             self.cash_outflow_by_tag(period, CashflowTracingTag::CashOutflowIntangibleAssets)?;
@@ -238,10 +485,25 @@ impl CashFlowStatementGenerator {
             + in_long_term_deposits
             + in_other_investing;
 
-        // -------------------------------------
-        // FINANCING ACTIVITIES
-        // -------------------------------------
+        Ok(SectionReport {
+            net: net_investing,
+            amounts: vec![
+                ("out_ppe", out_ppe),
+                ("out_intangible_assets", out_intangible_assets),
+                ("out_investment_securities", out_investment_securities),
+                ("out_long_term_deposits", out_long_term_deposits),
+                ("out_other_investing", out_other_investing),
+                ("in_ppe", in_ppe),
+                ("in_intangible_assets", in_intangible_assets),
+                ("in_investment_securities", in_investment_securities),
+                ("in_long_term_deposits", in_long_term_deposits),
+                ("in_other_investing", in_other_investing),
+                ("net_investing", net_investing),
+            ],
+        })
+    }
 
+    fn financing_activities(&self, period: &str) -> Result<SectionReport, ServerError> {
         // Debt-related cash flows.
         //
         let in_borrowings =
@@ -271,57 +533,9 @@ impl CashFlowStatementGenerator {
             - out_dividends
             + in_out_other_financing;
 
-        // -------------------------------------
-        // RECONCILIATION
-        // -------------------------------------
-
-        let balance_opening = self.period_start_balance(period)?;
-        let balance_change = net_operating + net_investing + net_financing;
-        let balance_before_exchange = balance_opening + balance_change;
-        let exchange_rate_effects = 0.0;
-        let balance_closing = balance_before_exchange + exchange_rate_effects;
-
-        // -------------------------------------
-        // ADDITIONAL DISCLOSURES
-        // -------------------------------------
-
-        let non_cash_reclassifications = self.non_cash_reclassifications(period)?;
-
-        // -------------------------------------
-        // BUILD REPORT
-        // -------------------------------------
-
-        Ok(PeriodReport {
-            period: period.to_string(),
+        Ok(SectionReport {
+            net: net_financing,
             amounts: vec![
-                ("net_income", net_income),
-                ("nce_depreciation", nce_depreciation),
-                ("nce_amortization", nce_amortization),
-                ("nce_other", nce_other),
-                ("diff_accounts_receivable", diff_accounts_receivable),
-                ("diff_inventory", diff_inventory),
-                ("diff_prepaid_expenses", diff_prepaid_expenses),
-                ("diff_other_current_assets", diff_other_current_assets),
-                ("diff_accounts_payable", diff_accounts_payable),
-                ("diff_accrued_expenses", diff_accrued_expenses),
-                ("diff_deferred_revenue", diff_deferred_revenue),
-                (
-                    "diff_other_current_liabilities",
-                    diff_other_current_liabilities,
-                ),
-                ("gain_loss_sale_assets", gain_loss_sale_assets),
-                ("net_operating", net_operating),
-                ("out_ppe", out_ppe),
-                ("out_intangible_assets", out_intangible_assets),
-                ("out_investment_securities", out_investment_securities),
-                ("out_long_term_deposits", out_long_term_deposits),
-                ("out_other_investing", out_other_investing),
-                ("in_ppe", in_ppe),
-                ("in_intangible_assets", in_intangible_assets),
-                ("in_investment_securities", in_investment_securities),
-                ("in_long_term_deposits", in_long_term_deposits),
-                ("in_other_investing", in_other_investing),
-                ("net_investing", net_investing),
                 ("in_borrowings", in_borrowings),
                 ("out_borrowings", out_borrowings),
                 ("net_issuance_shares", net_issuance_shares),
@@ -329,15 +543,7 @@ impl CashFlowStatementGenerator {
                 ("out_dividends", out_dividends),
                 ("in_out_other_financing", in_out_other_financing),
                 ("net_financing", net_financing),
-                ("balance_opening", balance_opening),
-                ("balance_change", balance_change),
-                ("balance_before_exchange", balance_before_exchange),
-                ("exchange_rate_effects", exchange_rate_effects),
-                ("balance_closing", balance_closing),
-            ]
-            .into_iter()
-            .collect(),
-            non_cash_reclassifications,
+            ],
         })
     }
 
@@ -416,9 +622,40 @@ impl CashFlowStatementGenerator {
         placeholders
     }
 
+    fn footnotes(
+        &self,
+        reports: &[PeriodReport],
+        materiality: f64,
+    ) -> Result<Option<Footnotes>, ServerError> {
+        let Some((period, report)) = zip(&self.periods, reports).max_by_key(|(p, _)| p.end())
+        else {
+            return Ok(None);
+        };
+        let hledger_period = period.to_hledger();
+        let mut line_items = Vec::new();
+        for key in PLACEHOLDER_KEYS {
+            if report.amounts[key].abs() < materiality {
+                continue;
+            }
+            let mut sources = Vec::new();
+            for query in line_item_sources(key) {
+                sources.extend(hledger_sources(&self.ledger, &hledger_period, query, true)?);
+            }
+            sources.sort_by_key(|source| source.spec_id.0);
+            sources.dedup_by_key(|source| source.spec_id.0);
+            if !sources.is_empty() {
+                line_items.push((key, sources));
+            }
+        }
+        Ok(Some(Footnotes {
+            period: report.period.clone(),
+            line_items,
+        }))
+    }
+
     fn net_income(&self, period: &str) -> Result<f64, ServerError> {
         hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::IncomeStatement,
             true,
@@ -429,7 +666,7 @@ impl CashFlowStatementGenerator {
 
     fn expenses_paid_with_non_cash_payment(&self, period: &str) -> Result<f64, ServerError> {
         hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::ChangeInAccountReverse {
                 account: "Expenses".to_string(),
@@ -442,7 +679,7 @@ impl CashFlowStatementGenerator {
 
     fn expense_by_tag(&self, period: &str, tag: CashflowTracingTag) -> Result<f64, ServerError> {
         hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::ChangeInAccount {
                 account: tag.value(),
@@ -463,7 +700,7 @@ impl CashFlowStatementGenerator {
         classification: AssetClassification,
     ) -> Result<f64, ServerError> {
         hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::ChangeInAccount {
                 account: Into::<Account>::into(asset_tl(classification)).ledger(),
@@ -480,7 +717,7 @@ impl CashFlowStatementGenerator {
         classification: LiabilityClassification,
     ) -> Result<f64, ServerError> {
         Ok(-hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::ChangeInAccount {
                 account: Into::<Account>::into(liability_tl(classification)).ledger(),
@@ -497,7 +734,7 @@ impl CashFlowStatementGenerator {
         tag: CashflowTracingTag,
     ) -> Result<f64, ServerError> {
         let cash_backed = hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::ChangeInAccountReverse {
                 account: Into::<Account>::into(asset_tl(
@@ -510,7 +747,7 @@ impl CashFlowStatementGenerator {
             Return::SearchRowOrZero(tag.value()),
         )?;
         let non_cash_reclassifications = hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::ChangeByTag {
                 key: "s",
@@ -533,7 +770,7 @@ impl CashFlowStatementGenerator {
 
     fn period_start_balance(&self, period: &str) -> Result<f64, ServerError> {
         let period_end_balance = hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::CumulativeBalance {
                 account: Into::<Account>::into(asset_tl(
@@ -546,7 +783,7 @@ impl CashFlowStatementGenerator {
             Return::Total,
         )?;
         let period_change = hledger(
-            &self.ledger_path,
+            &self.ledger,
             period,
             Query::ChangeInAccount {
                 account: Into::<Account>::into(asset_tl(
@@ -563,22 +800,22 @@ impl CashFlowStatementGenerator {
 
     fn non_cash_reclassifications(&self, period: &str) -> Result<Vec<String>, ServerError> {
         let source = hledger_register(
-            &self.ledger_path,
+            &self.ledger,
             period,
             RegisterQuery::TagReverse {
                 key: "s",
-                value: "non_cash_reclassification",
+                value: "non_cash_reclassification".to_string(),
             },
             true,
             None,
             RegisterOutput::Raw { width: 200 },
         )?;
         let dest = hledger_register(
-            &self.ledger_path,
+            &self.ledger,
             period,
             RegisterQuery::Tag {
                 key: "s",
-                value: "non_cash_reclassification",
+                value: "non_cash_reclassification".to_string(),
             },
             true,
             None,
@@ -601,6 +838,75 @@ impl CashFlowStatementGenerator {
     }
 }
 
+impl CustomStatementGenerator for CashFlowStatementGenerator {
+    fn generate_with_format(self, format: StatementFormat) -> Result<String, ServerError> {
+        CashFlowStatementGenerator::generate_with_format(self, format)
+    }
+}
+
+/// Postings making up each line item (other than totals), matching the
+/// queries of the line item's amount.
+fn line_item_sources(key: &str) -> Vec<RegisterQuery> {
+    use AssetClassification as A;
+    use CashflowTracingTag as T;
+    use LiabilityClassification as L;
+    let tag = |tag: CashflowTracingTag| RegisterQuery::Tag {
+        key: CashflowTracingTag::key(),
+        value: tag.value(),
+    };
+    let asset = |c| RegisterQuery::Account(Into::<Account>::into(asset_tl(c)).ledger());
+    let liability = |c| RegisterQuery::Account(Into::<Account>::into(liability_tl(c)).ledger());
+    match key {
+        "net_income" => vec![
+            RegisterQuery::Account("Income".to_string()),
+            RegisterQuery::Account("Expenses".to_string()),
+        ],
+        "nce_depreciation" => vec![tag(T::NonCashExpenseDepreciation)],
+        "nce_amortization" => vec![tag(T::NonCashExpenseAmortization)],
+        "nce_other" => vec![tag(T::NonCashExpenseOther), tag(T::NonCashPayment)],
+        "diff_accounts_receivable" => vec![asset(A::AccountsReceivable)],
+        "diff_inventory" => vec![asset(A::Inventory)],
+        "diff_prepaid_expenses" => vec![asset(A::PrepaidExpenses)],
+        "diff_other_current_assets" => vec![
+            asset(A::ShortTermInvestments),
+            asset(A::ShortTermDeposits),
+            asset(A::OtherCurrentAssets),
+        ],
+        "diff_accounts_payable" => vec![liability(L::AccountsPayable)],
+        "diff_accrued_expenses" => vec![liability(L::AccruedExpenses)],
+        "diff_deferred_revenue" => vec![liability(L::DeferredRevenue)],
+        "diff_other_current_liabilities" => vec![liability(L::OtherCurrentLiabilities)],
+        "gain_loss_sale_assets" => vec![tag(T::ReclassifyGainLossOnSaleOfAssets)],
+        "out_ppe" => vec![tag(T::CashOutflowPpe)],
+        "out_intangible_assets" => vec![tag(T::CashOutflowIntangibleAssets)],
+        "out_investment_securities" => vec![tag(T::CashOutflowInvestmentSecurities)],
+        "out_long_term_deposits" => vec![tag(T::CashOutflowLongTermDeposits)],
+        "out_other_investing" => vec![tag(T::CashOutflowOtherInvesting)],
+        "in_ppe" => vec![tag(T::CashInflowPpe)],
+        "in_intangible_assets" => vec![tag(T::CashInflowIntangibleAssets)],
+        "in_investment_securities" => vec![tag(T::CashInflowInvestmentSecurities)],
+        "in_long_term_deposits" => vec![tag(T::CashInflowLongTermDeposits)],
+        "in_other_investing" => vec![tag(T::CashInflowOtherInvesting)],
+        "in_borrowings" => vec![tag(T::CashInflowBorrowings)],
+        "out_borrowings" => vec![tag(T::CashOutflowBorrowings)],
+        "net_issuance_shares" => vec![
+            tag(T::CashInflowIssuanceShares),
+            tag(T::CashOutflowShareIssuanceCosts),
+        ],
+        "out_share_buybacks" => vec![tag(T::CashOutflowShareBuybacks)],
+        "out_dividends" => vec![tag(T::CashOutflowDividends)],
+        "in_out_other_financing" => vec![tag(T::CashInOutflowOtherFinancing)],
+        _ => vec![],
+    }
+}
+
+/// Result of a query thread, resuming its panic (if any) on this thread.
+fn join<T>(handle: ScopedJoinHandle<'_, T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
 // Formatting helpers.
 // ----------------------------------------------------------------------------
 
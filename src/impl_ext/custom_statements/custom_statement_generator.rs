@@ -0,0 +1,12 @@
+use fractic_server_error::ServerError;
+
+use super::statement_format::StatementFormat;
+
+/// Common interface of the statement generators. Custom statements (ex. a
+/// management P&L) can be built on the same hledger queries as the built-in
+/// ones (see `hledger`, `hledger_register` and `hledger_report`, with a
+/// `LedgerSource`), and implement this trait to be used interchangeably with
+/// them.
+pub trait CustomStatementGenerator {
+    fn generate_with_format(self, format: StatementFormat) -> Result<String, ServerError>;
+}
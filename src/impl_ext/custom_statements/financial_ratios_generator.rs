@@ -0,0 +1,362 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use fractic_server_error::{CriticalError, ServerError};
+use serde_json::{json, Value};
+
+use crate::entities::{
+    asset_tl, expense_tl, Account, AssetClassification, ExpenseClassification, FinancialRecords,
+    PhaseProgress, ProgressPhase, ProgressReporter,
+};
+use crate::errors::InvalidFinancialRatiosPeriods;
+
+use super::custom_statement_generator::CustomStatementGenerator;
+use super::report_period::ReportPeriod;
+use super::statement_format::StatementFormat;
+use super::statement_toolkit::{hledger, LedgerSource, Query, Return};
+
+const ASSETS: &str = "Assets";
+const CURRENT_ASSETS: &str = "Assets:Current";
+const LIABILITIES: &str = "Liabilities";
+const CURRENT_LIABILITIES: &str = "Liabilities:Current";
+const OPERATING_INCOME: &str = "Income:Operating";
+const OPERATING_EXPENSES: &str = "Expenses:Operating";
+
+const COL_WIDTH: usize = 14;
+const LABEL_WIDTH: usize = 24;
+
+/// Computes standard financial ratios from a generated journal, one set per
+/// period. Balance sheet ratios use the balances at the end of the period,
+/// and margins use the activity during the period.
+pub struct FinancialRatiosGenerator {
+    ledger: LedgerSource,
+    periods: Vec<ReportPeriod>,
+    progress: Option<Arc<dyn ProgressReporter>>,
+}
+
+/// Amounts the ratios are derived from. Liabilities and income are reported
+/// as positive numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatioInputs {
+    pub current_assets: f64,
+    pub current_liabilities: f64,
+    /// Current assets, excluding inventory and prepaid expenses.
+    pub quick_assets: f64,
+    pub total_liabilities: f64,
+    /// Net assets (total assets less total liabilities), so that the current
+    /// period's profit is included even before closing entries are booked.
+    pub total_equity: f64,
+    pub revenue: f64,
+    pub cost_of_goods_sold: f64,
+    pub operating_expenses: f64,
+}
+
+/// Ratios for a single period. A ratio is `None` if its denominator is zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodRatios {
+    pub period: String,
+    pub inputs: RatioInputs,
+    pub current_ratio: Option<f64>,
+    pub quick_ratio: Option<f64>,
+    pub gross_margin: Option<f64>,
+    pub operating_margin: Option<f64>,
+    pub debt_to_equity: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinancialRatiosReport {
+    pub periods: Vec<PeriodRatios>,
+}
+
+impl FinancialRatiosGenerator {
+    pub fn new<P, I>(ledger_path: P, periods: I) -> Result<Self, ServerError>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = ReportPeriod>,
+    {
+        Self::with_ledger(LedgerSource::from_path(ledger_path)?, periods)
+    }
+
+    /// Same as `new`, but queries the journal of the given records (passed to
+    /// hledger in memory) rather than a journal file.
+    pub fn from_records<I>(
+        financial_records: &FinancialRecords,
+        periods: I,
+    ) -> Result<Self, ServerError>
+    where
+        I: IntoIterator<Item = ReportPeriod>,
+    {
+        Self::with_ledger(LedgerSource::from_records(financial_records), periods)
+    }
+
+    fn with_ledger<I>(ledger: LedgerSource, periods: I) -> Result<Self, ServerError>
+    where
+        I: IntoIterator<Item = ReportPeriod>,
+    {
+        let periods: Vec<ReportPeriod> = periods.into_iter().collect();
+        if periods.is_empty() {
+            return Err(InvalidFinancialRatiosPeriods::new());
+        }
+        Ok(Self {
+            ledger,
+            periods,
+            progress: None,
+        })
+    }
+
+    /// Report progress (one step per period) while generating.
+    pub fn with_progress(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.progress = Some(reporter);
+        self
+    }
+
+    pub fn generate(self) -> Result<FinancialRatiosReport, ServerError> {
+        let mut progress = PhaseProgress::start(
+            self.progress.clone(),
+            ProgressPhase::GeneratingStatement,
+            self.periods.len(),
+        );
+        let periods = self
+            .periods
+            .iter()
+            .map(|period| {
+                let inputs = self.ratio_inputs(&period.to_hledger())?;
+                progress.advance();
+                Ok(PeriodRatios::from_inputs(period.to_string(), inputs))
+            })
+            .collect::<Result<Vec<_>, ServerError>>()?;
+        Ok(FinancialRatiosReport { periods })
+    }
+
+    pub fn generate_with_format(self, format: StatementFormat) -> Result<String, ServerError> {
+        let report = self.generate()?;
+        match format {
+            StatementFormat::Text => Ok(report.to_text()),
+            StatementFormat::Json => report.to_json(),
+            StatementFormat::Csv => report.to_csv(),
+        }
+    }
+
+    fn ratio_inputs(&self, period: &str) -> Result<RatioInputs, ServerError> {
+        let current_assets = self.closing_balance(period, CURRENT_ASSETS)?;
+        let quick_assets = current_assets
+            - self.closing_balance(period, &asset_ledger(AssetClassification::Inventory))?
+            - self.closing_balance(period, &asset_ledger(AssetClassification::PrepaidExpenses))?;
+        let total_liabilities = -self.closing_balance(period, LIABILITIES)?;
+        Ok(RatioInputs {
+            current_assets,
+            current_liabilities: -self.closing_balance(period, CURRENT_LIABILITIES)?,
+            quick_assets,
+            total_liabilities,
+            total_equity: self.closing_balance(period, ASSETS)? - total_liabilities,
+            revenue: -self.change_in_account(period, OPERATING_INCOME)?,
+            cost_of_goods_sold: self.change_in_account(
+                period,
+                &Into::<Account>::into(expense_tl(ExpenseClassification::CostOfGoodsSold)).ledger(),
+            )?,
+            operating_expenses: self.change_in_account(period, OPERATING_EXPENSES)?,
+        })
+    }
+
+    fn closing_balance(&self, period: &str, account: &str) -> Result<f64, ServerError> {
+        hledger(
+            &self.ledger,
+            period,
+            Query::CumulativeBalance {
+                account: account.to_string(),
+            },
+            true,
+            None,
+            Return::Total,
+        )
+    }
+
+    /// Closing entries are ignored, since they would zero out the income and
+    /// expense accounts.
+    fn change_in_account(&self, period: &str, account: &str) -> Result<f64, ServerError> {
+        hledger(
+            &self.ledger,
+            period,
+            Query::ChangeInAccount {
+                account: account.to_string(),
+            },
+            true,
+            None,
+            Return::Total,
+        )
+    }
+}
+
+impl CustomStatementGenerator for FinancialRatiosGenerator {
+    fn generate_with_format(self, format: StatementFormat) -> Result<String, ServerError> {
+        FinancialRatiosGenerator::generate_with_format(self, format)
+    }
+}
+
+impl PeriodRatios {
+    pub fn from_inputs(period: String, inputs: RatioInputs) -> Self {
+        Self {
+            current_ratio: ratio(inputs.current_assets, inputs.current_liabilities),
+            quick_ratio: ratio(inputs.quick_assets, inputs.current_liabilities),
+            gross_margin: ratio(inputs.revenue - inputs.cost_of_goods_sold, inputs.revenue),
+            operating_margin: ratio(inputs.revenue - inputs.operating_expenses, inputs.revenue),
+            debt_to_equity: ratio(inputs.total_liabilities, inputs.total_equity),
+            period,
+            inputs,
+        }
+    }
+
+    fn rows(&self) -> [(&'static str, &'static str, Option<f64>, RatioKind); 5] {
+        [
+            (
+                "current_ratio",
+                "Current ratio",
+                self.current_ratio,
+                RatioKind::Times,
+            ),
+            (
+                "quick_ratio",
+                "Quick ratio",
+                self.quick_ratio,
+                RatioKind::Times,
+            ),
+            (
+                "gross_margin",
+                "Gross margin",
+                self.gross_margin,
+                RatioKind::Percent,
+            ),
+            (
+                "operating_margin",
+                "Operating margin",
+                self.operating_margin,
+                RatioKind::Percent,
+            ),
+            (
+                "debt_to_equity",
+                "Debt-to-equity",
+                self.debt_to_equity,
+                RatioKind::Times,
+            ),
+        ]
+    }
+}
+
+impl FinancialRatiosReport {
+    /// Table with one row per ratio and one column per period.
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{:<LABEL_WIDTH$}", "Financial ratios");
+        for period in &self.periods {
+            out.push_str(&format!("{:>COL_WIDTH$}", period.period));
+        }
+        out.push('\n');
+        out.push_str(&"─".repeat(LABEL_WIDTH + COL_WIDTH * self.periods.len()));
+        out.push('\n');
+        let Some(first) = self.periods.first() else {
+            return out;
+        };
+        for (i, (_, label, _, _)) in first.rows().iter().enumerate() {
+            out.push_str(&format!("{:<LABEL_WIDTH$}", label));
+            for period in &self.periods {
+                let (_, _, value, kind) = period.rows()[i];
+                out.push_str(&format!("{:>COL_WIDTH$}", kind.format(value)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Ratios are keyed by the same names as the CSV rows. Margins are
+    /// fractions (0.25 for 25%), and unavailable ratios are `null`.
+    pub fn to_json(&self) -> Result<String, ServerError> {
+        let value = json!({
+            "periods": self
+                .periods
+                .iter()
+                .map(|p| {
+                    json!({
+                        "period": p.period,
+                        "ratios": p
+                            .rows()
+                            .iter()
+                            .map(|(key, _, value, _)| (key.to_string(), json!(value)))
+                            .collect::<serde_json::Map<String, Value>>(),
+                        "inputs": {
+                            "current_assets": p.inputs.current_assets,
+                            "current_liabilities": p.inputs.current_liabilities,
+                            "quick_assets": p.inputs.quick_assets,
+                            "total_liabilities": p.inputs.total_liabilities,
+                            "total_equity": p.inputs.total_equity,
+                            "revenue": p.inputs.revenue,
+                            "cost_of_goods_sold": p.inputs.cost_of_goods_sold,
+                            "operating_expenses": p.inputs.operating_expenses,
+                        },
+                    })
+                })
+                .collect::<Vec<_>>(),
+        });
+        serde_json::to_string_pretty(&value).map_err(|e| {
+            CriticalError::with_debug("failed to serialize financial ratios as JSON", &e)
+        })
+    }
+
+    /// One row per ratio, one column per period. Unavailable ratios are left
+    /// empty.
+    pub fn to_csv(&self) -> Result<String, ServerError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let to_error = |e: csv::Error| {
+            CriticalError::with_debug("failed to write financial ratios as CSV", &e)
+        };
+        writer
+            .write_record(
+                std::iter::once("ratio").chain(self.periods.iter().map(|p| p.period.as_str())),
+            )
+            .map_err(to_error)?;
+        if let Some(first) = self.periods.first() {
+            for (i, (key, _, _, _)) in first.rows().iter().enumerate() {
+                writer
+                    .write_record(
+                        std::iter::once(key.to_string()).chain(
+                            self.periods
+                                .iter()
+                                .map(|p| p.rows()[i].2.map(|v| v.to_string()).unwrap_or_default()),
+                        ),
+                    )
+                    .map_err(to_error)?;
+            }
+        }
+        let bytes = writer.into_inner().map_err(|e| {
+            CriticalError::with_debug("failed to write financial ratios as CSV", &e.to_string())
+        })?;
+        String::from_utf8(bytes)
+            .map_err(|e| CriticalError::with_debug("failed to write financial ratios as CSV", &e))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RatioKind {
+    Times,
+    Percent,
+}
+
+impl RatioKind {
+    fn format(self, value: Option<f64>) -> String {
+        match (self, value) {
+            (_, None) => "n/a".to_string(),
+            (RatioKind::Times, Some(v)) => format!("{:.2}", v),
+            (RatioKind::Percent, Some(v)) => format!("{:.1}%", v * 100.0),
+        }
+    }
+}
+
+fn ratio(numerator: f64, denominator: f64) -> Option<f64> {
+    if denominator.abs() < 1e-9 {
+        None
+    } else {
+        Some(numerator / denominator)
+    }
+}
+
+fn asset_ledger(classification: AssetClassification) -> String {
+    Into::<Account>::into(asset_tl(classification)).ledger()
+}
@@ -0,0 +1,214 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{Datelike as _, Months, NaiveDate};
+use fractic_server_error::ServerError;
+
+use crate::errors::InvalidReportPeriod;
+
+/// Period covered by a statement (or by one of its comparative columns).
+///
+/// Parsed from (and displayed as) "2024", "2024Q1", "2024-03", or an
+/// inclusive date range "2024-01-01..2024-06-30". Only constructed validated,
+/// so its dates are always representable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReportPeriod(Period);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Period {
+    Year(i32),
+    /// quarter: 1-4.
+    Quarter {
+        year: i32,
+        quarter: u32,
+    },
+    /// month: 1-12.
+    Month {
+        year: i32,
+        month: u32,
+    },
+    /// Both dates inclusive.
+    Range {
+        from: NaiveDate,
+        to: NaiveDate,
+    },
+}
+
+impl ReportPeriod {
+    pub fn year(year: i32) -> Result<Self, ServerError> {
+        Self::validated(Period::Year(year))
+    }
+
+    pub fn quarter(year: i32, quarter: u32) -> Result<Self, ServerError> {
+        Self::validated(Period::Quarter { year, quarter })
+    }
+
+    pub fn month(year: i32, month: u32) -> Result<Self, ServerError> {
+        Self::validated(Period::Month { year, month })
+    }
+
+    pub fn range(from: NaiveDate, to: NaiveDate) -> Result<Self, ServerError> {
+        Self::validated(Period::Range { from, to })
+    }
+
+    /// First day of the period.
+    pub fn start(&self) -> NaiveDate {
+        let (year, month) = match self.0 {
+            Period::Year(year) => (year, 1),
+            Period::Quarter { year, quarter } => (year, (quarter - 1) * 3 + 1),
+            Period::Month { year, month } => (year, month),
+            Period::Range { from, .. } => return from,
+        };
+        NaiveDate::from_ymd_opt(year, month, 1).expect("validated periods should have valid months")
+    }
+
+    /// Last day (inclusive) of the period.
+    pub fn end(&self) -> NaiveDate {
+        let months = match self.0 {
+            Period::Year(_) => 12,
+            Period::Quarter { .. } => 3,
+            Period::Month { .. } => 1,
+            Period::Range { to, .. } => return to,
+        };
+        (self.start() + Months::new(months))
+            .pred_opt()
+            .expect("date should have a predecessor")
+    }
+
+    /// Period expression for `hledger -p`.
+    pub fn to_hledger(&self) -> String {
+        match self.0 {
+            Period::Year(year) => format!("{:04}", year),
+            Period::Quarter { year, quarter } => format!("{:04}q{}", year, quarter),
+            Period::Month { year, month } => format!("{:04}-{:02}", year, month),
+            // hledger period ends are exclusive.
+            Period::Range { from, to } => format!(
+                "from {} to {}",
+                from,
+                to.succ_opt()
+                    .expect("validated periods should end before year 9999")
+            ),
+        }
+    }
+
+    fn validated(period: Period) -> Result<Self, ServerError> {
+        let (first_year, last_year) = match period {
+            Period::Year(year) => (year, year),
+            Period::Quarter { year, quarter } => {
+                if !(1..=4).contains(&quarter) {
+                    return Err(InvalidReportPeriod::new(
+                        &period.to_string(),
+                        "quarter must be between 1 and 4",
+                    ));
+                }
+                (year, year)
+            }
+            Period::Month { year, month } => {
+                if !(1..=12).contains(&month) {
+                    return Err(InvalidReportPeriod::new(
+                        &period.to_string(),
+                        "month must be between 1 and 12",
+                    ));
+                }
+                (year, year)
+            }
+            Period::Range { from, to } => {
+                if to < from {
+                    return Err(InvalidReportPeriod::new(
+                        &period.to_string(),
+                        "end date is before start date",
+                    ));
+                }
+                (from.year(), to.year())
+            }
+        };
+        // Keep to years hledger can parse (and that have a following day).
+        if first_year < 1 || last_year > 9998 {
+            return Err(InvalidReportPeriod::new(
+                &period.to_string(),
+                "year must be between 1 and 9998",
+            ));
+        }
+        Ok(Self(period))
+    }
+}
+
+impl fmt::Display for ReportPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Period::Year(year) => write!(f, "{:04}", year),
+            Period::Quarter { year, quarter } => write!(f, "{:04}Q{}", year, quarter),
+            Period::Month { year, month } => write!(f, "{:04}-{:02}", year, month),
+            Period::Range { from, to } => write!(f, "{}..{}", from, to),
+        }
+    }
+}
+
+impl FromStr for ReportPeriod {
+    type Err = ServerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || {
+            InvalidReportPeriod::new(
+                s,
+                "expected YYYY, YYYYQn, YYYY-MM or YYYY-MM-DD..YYYY-MM-DD",
+            )
+        };
+        let number = |part: &str| -> Result<u32, ServerError> {
+            if !part.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            part.parse().map_err(|_| invalid())
+        };
+        if let Some((from, to)) = s.split_once("..") {
+            let date = |part: &str| {
+                NaiveDate::parse_from_str(part.trim(), "%Y-%m-%d").map_err(|_| invalid())
+            };
+            return Self::range(date(from)?, date(to)?);
+        }
+        if let Some((year, quarter)) = s.split_once(['Q', 'q']) {
+            return Self::quarter(number(year)? as i32, number(quarter)?);
+        }
+        if let Some((year, month)) = s.split_once('-') {
+            return Self::month(number(year)? as i32, number(month)?);
+        }
+        Self::year(number(s)? as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_out_of_range_periods_are_rejected() {
+        assert!(ReportPeriod::quarter(2024, 0).is_err());
+        assert!(ReportPeriod::month(2024, 13).is_err());
+        assert!(ReportPeriod::range(date(2024, 1, 1), NaiveDate::MAX).is_err());
+        assert!(ReportPeriod::range(NaiveDate::MIN, date(2024, 1, 1)).is_err());
+        assert!("2024Q0".parse::<ReportPeriod>().is_err());
+    }
+
+    #[test]
+    fn test_period_bounds() {
+        let q4: ReportPeriod = "2024Q4".parse().unwrap();
+        assert_eq!(
+            (q4.start(), q4.end()),
+            (date(2024, 10, 1), date(2024, 12, 31))
+        );
+        assert_eq!(q4.to_hledger(), "2024q4");
+        let range = ReportPeriod::range(date(2024, 1, 1), date(2024, 6, 30)).unwrap();
+        assert_eq!(range.to_hledger(), "from 2024-01-01 to 2024-07-01");
+        assert_eq!(range.to_string(), "2024-01-01..2024-06-30");
+    }
+}
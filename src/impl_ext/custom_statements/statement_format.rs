@@ -0,0 +1,11 @@
+/// Output format of a generated statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatementFormat {
+    /// Human-readable table.
+    #[default]
+    Text,
+    /// Machine-readable JSON document.
+    Json,
+    /// One row per line item, one column per period.
+    Csv,
+}
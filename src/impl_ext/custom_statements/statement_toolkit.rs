@@ -0,0 +1,515 @@
+use chrono::NaiveDate;
+use fractic_server_error::{CriticalError, ServerError};
+use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    io::Write as _,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    sync::Arc,
+    thread,
+};
+
+use crate::{
+    entities::{FinancialRecords, TransactionSpecId},
+    errors::{HledgerCommandFailed, HledgerInvalidPath, HledgerQueryInvalidResponse},
+    presentation::hledger_printer::HledgerPrinter,
+};
+
+pub use crate::presentation::utils::replace_all_placeholders_in_string;
+
+/// Journal queried by the statement generators (and the `hledger*`
+/// queries).
+#[derive(Debug, Clone)]
+pub enum LedgerSource {
+    Path(PathBuf),
+    /// Journal printed in memory, passed to hledger through stdin.
+    Journal(Arc<str>),
+}
+
+impl LedgerSource {
+    /// Fails if the file doesn't exist.
+    pub fn from_path(ledger_path: impl AsRef<Path>) -> Result<Self, ServerError> {
+        let ledger_path = ledger_path.as_ref();
+        Ok(LedgerSource::Path(ledger_path.canonicalize().map_err(
+            |e| HledgerInvalidPath::with_debug(&ledger_path.to_string_lossy(), &e),
+        )?))
+    }
+
+    /// Prints the records' journal with the default print options.
+    pub fn from_records(financial_records: &FinancialRecords) -> Self {
+        LedgerSource::Journal(HledgerPrinter::new().print_ledger(financial_records).into())
+    }
+
+    /// Name of the journal in error messages.
+    fn name(&self) -> String {
+        match self {
+            LedgerSource::Path(path) => path.display().to_string(),
+            LedgerSource::Journal(_) => "<in-memory journal>".to_string(),
+        }
+    }
+
+    /// hledger command reading this journal.
+    fn command(&self) -> Command {
+        let mut cmd = Command::new("hledger");
+        match self {
+            LedgerSource::Path(path) => cmd.arg("-f").arg(path),
+            LedgerSource::Journal(_) => cmd.arg("-f").arg("-"),
+        };
+        cmd
+    }
+
+    /// Runs a command created by `command`, failing if it exits unsuccessfully.
+    fn output(&self, cmd: &mut Command) -> Result<Output, ServerError> {
+        let output = match self {
+            LedgerSource::Path(_) => cmd.output(),
+            LedgerSource::Journal(journal) => cmd
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .and_then(|mut child| {
+                    let mut stdin = child.stdin.take().expect("stdin should be piped");
+                    // Written from another thread, so that hledger can't block
+                    // on a full stdout pipe while the journal is being written.
+                    thread::scope(|s| {
+                        let writer = s.spawn(move || stdin.write_all(journal.as_bytes()));
+                        let output = child.wait_with_output();
+                        match writer.join() {
+                            Ok(Ok(())) => output,
+                            Ok(Err(e)) => Err(e),
+                            Err(panic) => std::panic::resume_unwind(panic),
+                        }
+                    })
+                }),
+        }
+        .map_err(|e| HledgerCommandFailed::with_debug(&self.name(), cmd, &e))?;
+        if !output.status.success() {
+            return Err(HledgerCommandFailed::with_debug(&self.name(), cmd, &output));
+        }
+        Ok(output)
+    }
+}
+
+/// Balance report to run with `hledger`. Accounts are ledger names (ex.
+/// "Assets:Current"), and include their sub-accounts.
+#[derive(Debug)]
+pub enum Query {
+    IncomeStatement,
+    /// Change in the account during the period.
+    ChangeInAccount {
+        account: String,
+    },
+    /// Same as `ChangeInAccount`, with the sign of amounts reversed.
+    ChangeInAccountReverse {
+        account: String,
+    },
+    /// Change in all accounts, for postings with the given tag value.
+    ChangeByTag {
+        key: &'static str,
+        value: &'static str,
+    },
+    /// Balance of the account at the end of the period.
+    CumulativeBalance {
+        account: String,
+    },
+}
+impl Query {
+    pub(crate) fn dbg(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+/// Value to extract from the output of a `Query`.
+#[derive(Debug)]
+pub enum Return {
+    /// Return the value in the last row, last column.
+    Total,
+    /// Search for the row where the first column matches the given string, and
+    /// return the value in the last column.
+    SearchRowOrZero(String),
+}
+impl Return {
+    pub(crate) fn dbg(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+/// Run a balance query, returning a single amount.
+///
+/// period: hledger period expression (see `ReportPeriod::to_hledger`).
+/// ignore_closing_entries: Exclude the year-end closing entries (see
+/// `CloseEntryGenerator`), ex. for income and expense totals.
+/// pivot: Tag to group postings by, instead of their account (ex. the
+/// cashflow tracing tag), to be used with `Return::SearchRowOrZero`.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "hledger", level = "debug", skip(ledger), err(Debug))
+)]
+pub fn hledger(
+    ledger: &LedgerSource,
+    period: &str,
+    query: Query,
+    ignore_closing_entries: bool,
+    pivot: Option<&'static str>,
+    fetch: Return,
+) -> Result<f64, ServerError> {
+    let mut cmd = ledger.command();
+    cmd.arg("-p").arg(period);
+
+    match &query {
+        Query::IncomeStatement => {
+            cmd.arg("incomestatement");
+        }
+        Query::ChangeInAccount { account } => {
+            let account_query = format!("^{}($|:)", account);
+            cmd.arg("balance").arg(account_query);
+        }
+        Query::ChangeInAccountReverse { account } => {
+            let account_query = format!("^{}($|:)", account);
+            cmd.arg("balance").arg(account_query).arg("-r");
+        }
+        Query::ChangeByTag { key, value } => {
+            let tag_query = format!("tag:{}={}", key, value);
+            cmd.arg("balance").arg(tag_query);
+        }
+        Query::CumulativeBalance { account } => {
+            let account_query = format!("^{}($|:)", account);
+            cmd.arg("balance").arg(account_query).arg("-H");
+        }
+    }
+
+    if ignore_closing_entries {
+        cmd.arg("not:tag:close");
+    }
+
+    if let Some(pivot) = pivot {
+        cmd.arg("--pivot").arg(pivot);
+    }
+
+    cmd.arg("--output-format=csv").arg("--layout=bare");
+
+    let output = ledger.output(&mut cmd)?;
+    let out_csv = String::from_utf8(output.stdout)
+        .map_err(|e| CriticalError::with_debug("failed to parse hledger output as UTF-8", &e))?;
+
+    let amount = match &fetch {
+        Return::Total => {
+            let amount_str = csv::Reader::from_reader(out_csv.as_bytes())
+                .records()
+                .last()
+                .and_then(|r| r.ok())
+                .and_then(|r| r.iter().last().map(|s| s.to_owned()))
+                .ok_or_else(|| {
+                    HledgerQueryInvalidResponse::with_debug(
+                        &cmd,
+                        query.dbg(),
+                        fetch.dbg(),
+                        &out_csv,
+                    )
+                })?;
+            amount_str.parse::<f64>().map_err(|e| {
+                HledgerQueryInvalidResponse::with_debug(&cmd, query.dbg(), fetch.dbg(), &e)
+            })?
+        }
+        Return::SearchRowOrZero(search) => {
+            let amount_str = csv::Reader::from_reader(out_csv.as_bytes())
+                .records()
+                .find(|r| r.as_ref().map_or(false, |r| r[0] == *search))
+                .and_then(|r| r.ok())
+                .and_then(|r| r.iter().last().map(|s| s.to_owned()))
+                .unwrap_or("0".to_string());
+            amount_str.parse::<f64>().map_err(|e| {
+                HledgerQueryInvalidResponse::with_debug(&cmd, query.dbg(), fetch.dbg(), &e)
+            })?
+        }
+    };
+    Ok(amount)
+}
+
+/// Run one of hledger's built-in reports (ex. 'balancesheet'), returning its
+/// output as-is.
+///
+/// aliases: Values for hledger's `--alias` option (ex. 'FROM=TO'), applied in
+/// the given order.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "hledger", level = "debug", skip(ledger), err(Debug))
+)]
+pub fn hledger_report(
+    ledger: &LedgerSource,
+    period: &str,
+    report: &'static str,
+    ignore_closing_entries: bool,
+    aliases: &[String],
+    output_format: &'static str,
+) -> Result<String, ServerError> {
+    let mut cmd = ledger.command();
+    cmd.arg("-p").arg(period).arg(report);
+
+    if ignore_closing_entries {
+        cmd.arg("not:tag:close");
+    }
+
+    for alias in aliases {
+        cmd.arg("--alias").arg(alias);
+    }
+
+    cmd.arg(format!("--output-format={}", output_format));
+
+    let output = ledger.output(&mut cmd)?;
+    String::from_utf8(output.stdout)
+        .map_err(|e| CriticalError::with_debug("failed to parse hledger output as UTF-8", &e))
+}
+
+/// Aliases for `hledger_report` merging the accumulated depreciation and
+/// amortization accounts into the accounts they belong to, so assets are
+/// reported at their carrying amount.
+pub fn net_contra_asset_aliases() -> Vec<String> {
+    // Ex. 'PropertyPlantEquipment:AccumulatedDepreciation:Equipment' is merged
+    // into 'PropertyPlantEquipment:Equipment'.
+    ["AccumulatedDepreciation", "AccumulatedAmortization"]
+        .iter()
+        .map(|contra| format!("/:{}/=", contra))
+        .collect()
+}
+
+/// Postings to list with `hledger_register`.
+#[derive(Debug)]
+pub enum RegisterQuery {
+    Account(String),
+    Tag { key: &'static str, value: String },
+    TagReverse { key: &'static str, value: String },
+}
+/// Format of the lines returned by `hledger_register`.
+#[derive(Debug)]
+pub enum RegisterOutput {
+    Raw { width: i64 },
+}
+/// List the matching postings, one output line per element (see `hledger` for
+/// the parameters).
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "hledger", level = "debug", skip(ledger), err(Debug))
+)]
+pub fn hledger_register(
+    ledger: &LedgerSource,
+    period: &str,
+    query: RegisterQuery,
+    ignore_closing_entries: bool,
+    pivot: Option<&'static str>,
+    format: RegisterOutput,
+) -> Result<Vec<String>, ServerError> {
+    let mut cmd = register_command(ledger, period, &query, ignore_closing_entries);
+
+    if let Some(pivot) = pivot {
+        cmd.arg("--pivot").arg(pivot);
+    }
+
+    match &format {
+        RegisterOutput::Raw { width } => {
+            cmd.arg("-w").arg(width.to_string());
+
+            let output = ledger.output(&mut cmd)?;
+
+            let out_raw = String::from_utf8(output.stdout).map_err(|e| {
+                CriticalError::with_debug("failed to parse hledger output as UTF-8", &e)
+            })?;
+
+            Ok(out_raw.lines().map(|s| s.to_string()).collect())
+        }
+    }
+}
+
+/// Spec a transaction was generated from, as listed by `hledger_sources`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRecord {
+    pub spec_id: TransactionSpecId,
+    pub payee: String,
+}
+
+/// Specs (and their payees) of the transactions with postings matching the
+/// query, in spec ID order (see `hledger` for the parameters). Transactions
+/// not generated from a spec (ex. closing entries or custom ledger
+/// extensions) are skipped.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "hledger", level = "debug", skip(ledger), err(Debug))
+)]
+pub fn hledger_sources(
+    ledger: &LedgerSource,
+    period: &str,
+    query: RegisterQuery,
+    ignore_closing_entries: bool,
+) -> Result<Vec<SourceRecord>, ServerError> {
+    let mut cmd = register_command(ledger, period, &query, ignore_closing_entries);
+    cmd.arg("--output-format=csv");
+
+    let output = ledger.output(&mut cmd)?;
+    let out_csv = String::from_utf8(output.stdout)
+        .map_err(|e| CriticalError::with_debug("failed to parse hledger output as UTF-8", &e))?;
+
+    let invalid_response = |details: &str| {
+        HledgerQueryInvalidResponse::with_debug(
+            &cmd,
+            format!("{:?}", query),
+            "sources".to_string(),
+            &details,
+        )
+    };
+    let mut reader = csv::Reader::from_reader(out_csv.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| invalid_response(&e.to_string()))?
+        .clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| invalid_response(&out_csv))
+    };
+    let (code_column, description_column) = (column("code")?, column("description")?);
+
+    // Transaction descriptions are printed as "<payee> | <description>".
+    let mut sources = BTreeMap::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| invalid_response(&e.to_string()))?;
+        let Some(spec_id) = record
+            .get(code_column)
+            .and_then(|code| code.trim().parse::<u64>().ok())
+        else {
+            continue;
+        };
+        let description = record.get(description_column).unwrap_or("");
+        let payee = description
+            .split_once(" | ")
+            .map_or(description, |(payee, _)| payee);
+        sources
+            .entry(spec_id)
+            .or_insert_with(|| payee.trim().to_string());
+    }
+    Ok(sources
+        .into_iter()
+        .map(|(spec_id, payee)| SourceRecord {
+            spec_id: TransactionSpecId(spec_id),
+            payee,
+        })
+        .collect())
+}
+
+/// Posting of a transaction generated from a spec, as listed by
+/// `hledger_postings`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostingRecord {
+    pub spec_id: TransactionSpecId,
+    /// Index of the posting's transaction in the journal.
+    pub transaction_index: usize,
+    pub date: NaiveDate,
+    pub account: String,
+    pub amount: f64,
+    /// Commodity symbol, as printed in the journal (ex. "$").
+    pub commodity: String,
+}
+
+/// All postings of the transactions generated from specs, in journal order.
+/// Transactions not generated from a spec (ex. closing entries or custom
+/// ledger extensions) are skipped.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "hledger", level = "debug", skip(ledger), err(Debug))
+)]
+pub fn hledger_postings(ledger: &LedgerSource) -> Result<Vec<PostingRecord>, ServerError> {
+    let mut cmd = ledger.command();
+    cmd.arg("print").arg("--output-format=csv");
+
+    let output = ledger.output(&mut cmd)?;
+    let out_csv = String::from_utf8(output.stdout)
+        .map_err(|e| CriticalError::with_debug("failed to parse hledger output as UTF-8", &e))?;
+
+    let invalid_response = |details: &str| {
+        HledgerQueryInvalidResponse::with_debug(
+            &cmd,
+            "print".to_string(),
+            "postings".to_string(),
+            &details,
+        )
+    };
+    let mut reader = csv::Reader::from_reader(out_csv.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| invalid_response(&e.to_string()))?
+        .clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| invalid_response(&out_csv))
+    };
+    let (index_column, date_column, code_column) =
+        (column("txnidx")?, column("date")?, column("code")?);
+    let (account_column, amount_column, commodity_column) =
+        (column("account")?, column("amount")?, column("commodity")?);
+
+    let mut postings = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| invalid_response(&e.to_string()))?;
+        let Some(spec_id) = record
+            .get(code_column)
+            .and_then(|code| code.trim().parse::<u64>().ok())
+        else {
+            continue;
+        };
+        let field = |column: usize| record.get(column).unwrap_or("").trim();
+        postings.push(PostingRecord {
+            spec_id: TransactionSpecId(spec_id),
+            transaction_index: field(index_column)
+                .parse()
+                .map_err(|_| invalid_response(field(index_column)))?,
+            date: NaiveDate::parse_from_str(field(date_column), "%Y-%m-%d")
+                .map_err(|_| invalid_response(field(date_column)))?,
+            account: field(account_column).to_string(),
+            // Amounts follow the commodity declarations (ex. "1,000.00 $").
+            amount: field(amount_column)
+                .replace(',', "")
+                .parse()
+                .map_err(|_| invalid_response(field(amount_column)))?,
+            commodity: field(commodity_column).to_string(),
+        });
+    }
+    Ok(postings)
+}
+
+fn register_command(
+    ledger: &LedgerSource,
+    period: &str,
+    query: &RegisterQuery,
+    ignore_closing_entries: bool,
+) -> Command {
+    let mut cmd = ledger.command();
+    cmd.arg("-p").arg(period).arg("register");
+
+    match query {
+        RegisterQuery::Account(account) => {
+            let account_query = format!("^{}($|:)", account);
+            cmd.arg(account_query);
+        }
+        RegisterQuery::Tag { key, value } => {
+            let tag_query = format!("tag:{}={}", key, value);
+            cmd.arg(tag_query);
+        }
+        RegisterQuery::TagReverse { key, value } => {
+            let tag_query = format!("tag:{}={}", key, value);
+            cmd.arg(tag_query).arg("-r");
+        }
+    }
+
+    if ignore_closing_entries {
+        cmd.arg("not:tag:close");
+    }
+
+    cmd
+}
+
+/// Columns of a raw register line (separated by 4 or more spaces).
+pub fn split_sections(s: &str) -> Vec<&str> {
+    let re = Regex::new(r"\s{4,}").unwrap();
+    re.split(s).filter(|part| !part.trim().is_empty()).collect()
+}
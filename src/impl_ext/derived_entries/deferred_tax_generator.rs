@@ -0,0 +1,196 @@
+use chrono::NaiveDate;
+use iso_currency::Currency;
+
+use crate::{
+    entities::{
+        Account, FinancialRecords, Transaction, TransactionPosting, TransactionSpecId,
+        TransactionStatus,
+    },
+    ext::standard_accounts::{DEFERRED_TAX_ASSET, DEFERRED_TAX_EXPENSE, DEFERRED_TAX_LIABILITY},
+};
+
+// Public interface.
+// ----------------------------------------------------------------------------
+
+/// Deferred tax (IAS 12) on the temporary differences between the carrying
+/// amount of accounts and their tax base, for example:
+///
+/// - an asset amortized faster in the books than it's depreciated for tax
+///   (tax base above the carrying amount: deductible difference),
+/// - a provision only deductible once paid (tax base of zero: deductible
+///   difference).
+///
+/// Generates the entry moving the deferred tax asset / liability balances in
+/// the records (`AssetClassification::DeferredIncomeTax` /
+/// `LiabilityClassification::DeferredIncomeTax`) to the amounts required at
+/// the reporting date, against `DEFERRED_TAX_EXPENSE`.
+#[derive(Debug, Clone)]
+pub struct DeferredTaxGenerator {
+    as_of: NaiveDate,
+    tax_rate: f64,
+    currency: Currency,
+    tax_bases: Vec<(Account, f64)>,
+}
+
+impl DeferredTaxGenerator {
+    /// `tax_rate` as a fraction (ex. 0.2 for 20%).
+    pub fn new(as_of: NaiveDate, tax_rate: f64, currency: Currency) -> Self {
+        Self {
+            as_of,
+            tax_rate,
+            currency,
+            tax_bases: Vec::new(),
+        }
+    }
+
+    /// Tax base of an asset or liability account at the reporting date (ex.
+    /// cost less tax depreciation to date). Only accounts given a tax base
+    /// are considered.
+    pub fn with_tax_base(mut self, account: impl Into<Account>, tax_base: f64) -> Self {
+        self.tax_bases.push((account.into(), tax_base));
+        self
+    }
+
+    pub fn generate(&self, records: &FinancialRecords) -> DeferredTax {
+        let differences: Vec<TemporaryDifference> = self
+            .tax_bases
+            .iter()
+            .filter_map(|(account, tax_base)| {
+                let carrying_amount = match account {
+                    Account::Asset(_) => self.balance(records, account),
+                    Account::Liability(_) => -self.balance(records, account),
+                    _ => return None,
+                };
+                // Positive for taxable differences (ex. an asset carried above
+                // its tax base), negative for deductible ones.
+                let difference = round(
+                    match account {
+                        Account::Liability(_) => tax_base - carrying_amount,
+                        _ => carrying_amount - tax_base,
+                    },
+                    self.currency,
+                );
+                Some(TemporaryDifference {
+                    account: account.clone(),
+                    carrying_amount,
+                    tax_base: *tax_base,
+                    difference,
+                    deferred_tax: round(difference * self.tax_rate, self.currency),
+                })
+            })
+            .collect();
+
+        let deferred_tax_liability = round(
+            differences
+                .iter()
+                .map(|d| d.deferred_tax)
+                .filter(|t| *t > 0.0)
+                .sum(),
+            self.currency,
+        );
+        let deferred_tax_asset = round(
+            -differences
+                .iter()
+                .map(|d| d.deferred_tax)
+                .filter(|t| *t < 0.0)
+                .sum::<f64>(),
+            self.currency,
+        );
+
+        // Movements from the balances already booked.
+        let dta_account: Account = DEFERRED_TAX_ASSET.clone().into();
+        let dtl_account: Account = DEFERRED_TAX_LIABILITY.clone().into();
+        let dta_movement = round(
+            deferred_tax_asset - self.balance(records, &dta_account),
+            self.currency,
+        );
+        let dtl_movement = round(
+            -deferred_tax_liability - self.balance(records, &dtl_account),
+            self.currency,
+        );
+        let mut postings: Vec<TransactionPosting> =
+            [(dta_account, dta_movement), (dtl_account, dtl_movement)]
+                .into_iter()
+                .filter(|(_, amount)| *amount != 0.0)
+                .map(|(account, amount)| TransactionPosting::new(account, amount, self.currency))
+                .collect();
+        let expense = round(-(dta_movement + dtl_movement), self.currency);
+        if expense != 0.0 {
+            postings.push(TransactionPosting::new(
+                DEFERRED_TAX_EXPENSE.clone().into(),
+                expense,
+                self.currency,
+            ));
+        }
+
+        let entries = if postings.is_empty() {
+            vec![]
+        } else {
+            vec![Transaction {
+                spec_id: TransactionSpecId::new(0),
+                date: self.as_of,
+                postings,
+                comment: Some(
+                    format!(
+                        "Deferred tax at {}% on temporary differences",
+                        self.tax_rate * 100.0
+                    )
+                    .into(),
+                ),
+                status: TransactionStatus::Unmarked,
+            }]
+        };
+        DeferredTax {
+            entries,
+            differences,
+            deferred_tax_asset,
+            deferred_tax_liability,
+        }
+    }
+}
+
+// Output format.
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct DeferredTax {
+    /// To be added to the records. Deferred tax entries are not generated
+    /// from a spec, and use spec ID 0.
+    pub entries: Vec<Transaction>,
+    pub differences: Vec<TemporaryDifference>,
+    /// Required balances at the reporting date.
+    pub deferred_tax_asset: f64,
+    pub deferred_tax_liability: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TemporaryDifference {
+    pub account: Account,
+    pub carrying_amount: f64,
+    pub tax_base: f64,
+    /// Positive if taxable (giving a deferred tax liability), negative if
+    /// deductible (giving a deferred tax asset).
+    pub difference: f64,
+    pub deferred_tax: f64,
+}
+
+// Private.
+// ----------------------------------------------------------------------------
+
+impl DeferredTaxGenerator {
+    fn balance(&self, records: &FinancialRecords, account: &Account) -> f64 {
+        records
+            .transactions
+            .iter()
+            .filter(|tx| tx.date <= self.as_of)
+            .flat_map(|tx| tx.postings.iter())
+            .filter(|p| p.account == *account && p.commodity == self.currency)
+            .map(|p| p.amount)
+            .sum()
+    }
+}
+
+fn round(amount: f64, currency: Currency) -> f64 {
+    let factor = 10_f64.powi(currency.exponent().unwrap_or(0) as i32);
+    (amount * factor).round() / factor + 0.0
+}
@@ -0,0 +1,197 @@
+use chrono::NaiveDate;
+use fractic_server_error::ServerError;
+use iso_currency::Currency;
+
+use crate::{
+    entities::{
+        Account, AssetClassification, FinancialRecords, LiabilityClassification, Transaction,
+        TransactionPosting, TransactionSpecId, TransactionStatus,
+    },
+    errors::MissingPriceIndex,
+    ext::standard_accounts::{MONETARY_GAIN, MONETARY_LOSS},
+};
+
+// Public interface.
+// ----------------------------------------------------------------------------
+
+/// General price index (ex. monthly CPI), used to restate amounts in a
+/// hyperinflationary currency.
+#[derive(Debug, Clone)]
+pub struct PriceIndexSeries {
+    points: Vec<(NaiveDate, f64)>,
+}
+
+impl PriceIndexSeries {
+    /// Each point gives the index value from its date until the next point.
+    pub fn new(points: impl IntoIterator<Item = (NaiveDate, f64)>) -> Self {
+        let mut points: Vec<_> = points.into_iter().collect();
+        points.sort_by_key(|(date, _)| *date);
+        Self { points }
+    }
+
+    /// Value of the latest point on or before the given date.
+    pub fn index_at(&self, date: NaiveDate) -> Option<f64> {
+        self.points
+            .iter()
+            .take_while(|(d, _)| *d <= date)
+            .last()
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Restatement of the records in terms of the measuring unit current at the
+/// reporting date, for entities whose functional currency is
+/// hyperinflationary (IAS 29).
+///
+/// Non-monetary items (ex. inventory, PPE, deferred revenue), equity, and
+/// income and expenses are indexed from the date of each posting to the
+/// reporting date. Monetary items (ex. cash, receivables, debt) are already
+/// expressed in current units, so are not restated; the offsetting amount is
+/// the gain or loss on the net monetary position, booked to `MONETARY_GAIN` /
+/// `MONETARY_LOSS`.
+///
+/// Only postings in the given currency are restated. Closing and correction
+/// entries (`FinancialRecords::eoy_entries`) are not included.
+#[derive(Debug, Clone)]
+pub struct HyperinflationRestatement {
+    currency: Currency,
+    index: PriceIndexSeries,
+    reporting_date: NaiveDate,
+    overrides: Vec<(Account, bool)>,
+}
+
+impl HyperinflationRestatement {
+    pub fn new(currency: Currency, index: PriceIndexSeries, reporting_date: NaiveDate) -> Self {
+        Self {
+            currency,
+            index,
+            reporting_date,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Override whether an account is treated as a monetary item, for
+    /// accounts whose classification doesn't determine it (ex. an investment
+    /// in bonds under `LongTermInvestments`).
+    pub fn with_monetary(mut self, account: Account, monetary: bool) -> Self {
+        self.overrides.push((account, monetary));
+        self
+    }
+
+    pub fn generate(&self, records: &FinancialRecords) -> Result<Restatement, ServerError> {
+        let current_index = self.index_at(self.reporting_date)?;
+
+        // Restatement adjustment of each non-monetary account.
+        let mut adjustments: Vec<(Account, f64)> = Vec::new();
+        for tx in records
+            .transactions
+            .iter()
+            .filter(|tx| tx.date <= self.reporting_date)
+        {
+            let postings = tx
+                .postings
+                .iter()
+                .filter(|p| p.commodity == self.currency && !self.is_monetary(&p.account));
+            for posting in postings {
+                let factor = current_index / self.index_at(tx.date)?;
+                let adjustment = posting.amount * (factor - 1.0);
+                match adjustments.iter_mut().find(|(a, _)| *a == posting.account) {
+                    Some((_, total)) => *total += adjustment,
+                    None => adjustments.push((posting.account.clone(), adjustment)),
+                }
+            }
+        }
+
+        let mut postings: Vec<TransactionPosting> = adjustments
+            .into_iter()
+            .map(|(account, amount)| (account, round(amount, self.currency)))
+            .filter(|(_, amount)| *amount != 0.0)
+            .map(|(account, amount)| TransactionPosting::new(account, amount, self.currency))
+            .collect();
+        let offset = round(
+            -postings.iter().map(|p| p.amount).sum::<f64>(),
+            self.currency,
+        );
+        if offset != 0.0 {
+            let account = if offset < 0.0 {
+                MONETARY_GAIN.clone().into()
+            } else {
+                MONETARY_LOSS.clone().into()
+            };
+            postings.push(TransactionPosting::new(account, offset, self.currency));
+        }
+
+        let entries = if postings.is_empty() {
+            vec![]
+        } else {
+            vec![Transaction {
+                spec_id: TransactionSpecId::new(0),
+                date: self.reporting_date,
+                postings,
+                comment: Some(
+                    format!(
+                        "IAS 29 restatement to the {} price index ({current_index})",
+                        self.reporting_date
+                    )
+                    .into(),
+                ),
+                status: TransactionStatus::Unmarked,
+            }]
+        };
+        Ok(Restatement {
+            entries,
+            // A credit to the gain account is a gain.
+            monetary_gain_or_loss: -offset,
+        })
+    }
+}
+
+// Output format.
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct Restatement {
+    /// To be added to the records. Restatement entries are not generated from
+    /// a spec, and use spec ID 0.
+    pub entries: Vec<Transaction>,
+    /// Gain (positive) or loss (negative) on the net monetary position.
+    pub monetary_gain_or_loss: f64,
+}
+
+// Private.
+// ----------------------------------------------------------------------------
+
+impl HyperinflationRestatement {
+    fn index_at(&self, date: NaiveDate) -> Result<f64, ServerError> {
+        self.index
+            .index_at(date)
+            .filter(|value| *value > 0.0)
+            .ok_or_else(|| MissingPriceIndex::new(&date))
+    }
+
+    fn is_monetary(&self, account: &Account) -> bool {
+        if let Some((_, monetary)) = self.overrides.iter().find(|(a, _)| a == account) {
+            return *monetary;
+        }
+        match account {
+            Account::Asset(a) => !matches!(
+                a.1,
+                AssetClassification::Inventory
+                    | AssetClassification::PrepaidExpenses
+                    | AssetClassification::ShortTermInvestments
+                    | AssetClassification::PropertyPlantEquipment
+                    | AssetClassification::IntangibleAssets
+                    | AssetClassification::LongTermInvestments
+                    | AssetClassification::AccumulatedDepreciation
+                    | AssetClassification::AccumulatedAmortization
+            ),
+            Account::Liability(l) => l.1 != LiabilityClassification::DeferredRevenue,
+            Account::Equity(_) | Account::Income(_) | Account::Expense(_) => false,
+        }
+    }
+}
+
+fn round(amount: f64, currency: Currency) -> f64 {
+    let factor = 10_f64.powi(currency.exponent().unwrap_or(0) as i32);
+    (amount * factor).round() / factor + 0.0
+}
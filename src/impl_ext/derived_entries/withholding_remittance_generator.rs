@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike as _, Months, NaiveDate};
+use iso_currency::Currency;
+
+use crate::{
+    entities::{
+        Account, FinancialRecords, Transaction, TransactionPosting, TransactionSpecId,
+        TransactionStatus,
+    },
+    ext::standard_accounts::WITHHOLDING_TAX_PAYABLE,
+};
+
+// Public interface.
+// ----------------------------------------------------------------------------
+
+/// Remittance of the tax withheld on payments (ex. with
+/// `StandardDecoratorWithholdingTax::korea_freelancer`), which is due by the
+/// 10th of the month following the payment. Social insurance premiums
+/// (`StandardDecoratorSocialInsuranceKorea`) follow the same schedule; use
+/// `with_payable(SOCIAL_INSURANCE_PAYABLE.clone())` for those.
+///
+/// Generates one entry per month with withholdings in the records, clearing
+/// that month's `WITHHOLDING_TAX_PAYABLE` balance from the given cash account
+/// on the due date. Remittances already in the records (ex. previously
+/// generated entries) are taken into account, oldest month first.
+#[derive(Debug, Clone)]
+pub struct WithholdingRemittanceGenerator {
+    payable: Account,
+    cash_account: Account,
+    currency: Currency,
+    until: Option<NaiveDate>,
+}
+
+impl WithholdingRemittanceGenerator {
+    pub fn new(cash_account: impl Into<Account>, currency: Currency) -> Self {
+        Self {
+            payable: WITHHOLDING_TAX_PAYABLE.clone().into(),
+            cash_account: cash_account.into(),
+            currency,
+            until: None,
+        }
+    }
+
+    /// Payable to clear, instead of `WITHHOLDING_TAX_PAYABLE`.
+    pub fn with_payable(mut self, payable: impl Into<Account>) -> Self {
+        self.payable = payable.into();
+        self
+    }
+
+    /// Only generate remittances due on or before the given date (ex. the
+    /// reporting date), leaving the rest of the withholdings as payable.
+    pub fn until(mut self, date: NaiveDate) -> Self {
+        self.until = Some(date);
+        self
+    }
+
+    pub fn generate(&self, records: &FinancialRecords) -> WithholdingRemittance {
+        let payable = &self.payable;
+
+        // Withheld amounts (positive), by month of the payment, and amounts
+        // already remitted.
+        let mut withheld_by_month: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+        let mut remitted = 0.0;
+        for tx in &records.transactions {
+            for posting in &tx.postings {
+                if posting.account != *payable || posting.commodity != self.currency {
+                    continue;
+                }
+                if posting.amount < 0.0 {
+                    *withheld_by_month
+                        .entry(tx.date.with_day(1).unwrap())
+                        .or_default() -= posting.amount;
+                } else {
+                    remitted += posting.amount;
+                }
+            }
+        }
+
+        // Remittances already in the records settle the oldest months first.
+        let mut remittances = Vec::new();
+        for (month, withheld) in withheld_by_month {
+            let settled = withheld.min(remitted);
+            remitted -= settled;
+            let amount = round(withheld - settled, self.currency);
+            let due_date = due_date(month);
+            if amount > 0.0 && self.until.is_none_or(|until| due_date <= until) {
+                remittances.push(RemittanceDue {
+                    month,
+                    amount,
+                    due_date,
+                });
+            }
+        }
+
+        let entries = remittances
+            .iter()
+            .map(|r| Transaction {
+                spec_id: TransactionSpecId::new(0),
+                date: r.due_date,
+                postings: vec![
+                    TransactionPosting::new(payable.clone(), r.amount, self.currency),
+                    TransactionPosting::new(self.cash_account.clone(), -r.amount, self.currency),
+                ],
+                comment: Some(
+                    format!(
+                        "Remittance of {} for {}",
+                        payable.ledger(),
+                        r.month.format("%Y-%m")
+                    )
+                    .into(),
+                ),
+                status: TransactionStatus::Unmarked,
+            })
+            .collect();
+
+        WithholdingRemittance {
+            entries,
+            remittances,
+        }
+    }
+}
+
+// Output format.
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
+pub struct WithholdingRemittance {
+    /// To be added to the records. Remittance entries are not generated from
+    /// a spec, and use spec ID 0.
+    pub entries: Vec<Transaction>,
+    pub remittances: Vec<RemittanceDue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RemittanceDue {
+    /// First day of the month the tax was withheld in.
+    pub month: NaiveDate,
+    pub amount: f64,
+    pub due_date: NaiveDate,
+}
+
+// Private.
+// ----------------------------------------------------------------------------
+
+/// 10th of the following month.
+fn due_date(month: NaiveDate) -> NaiveDate {
+    (month + Months::new(1)).with_day(10).unwrap()
+}
+
+fn round(amount: f64, currency: Currency) -> f64 {
+    let factor = 10_f64.powi(currency.exponent().unwrap_or(0) as i32);
+    (amount * factor).round() / factor + 0.0
+}
@@ -1,27 +1,73 @@
 // Crate-internal.
 // ---
 
+pub(crate) mod consolidation {
+    pub(crate) mod foreign_operation_translation;
+    pub(crate) mod intercompany_elimination;
+}
+
+#[cfg(feature = "hledger")]
 pub(crate) mod custom_statements {
     pub(crate) mod cash_flow_statement_generator;
-    mod utils;
+    pub(crate) mod custom_statement_generator;
+    pub(crate) mod financial_ratios_generator;
+    pub(crate) mod report_period;
+    pub(crate) mod standard_statement_generator;
+    pub(crate) mod statement_format;
+    pub(crate) mod statement_toolkit;
 }
 
 pub(crate) mod derived_entries {
+    #[cfg(feature = "hledger")]
     pub(crate) mod close_entry_generator;
+    pub(crate) mod deferred_tax_generator;
+    pub(crate) mod hyperinflation_restatement;
+    pub(crate) mod withholding_remittance_generator;
+}
+
+pub(crate) mod reports {
+    pub(crate) mod bank_reconciliation;
+    pub(crate) mod budget_variance;
+    pub(crate) mod cash_flow_forecast;
+    pub(crate) mod chart_of_accounts;
+    pub(crate) mod dimensions;
+    pub(crate) mod expense_summary;
+    pub(crate) mod fund_balances;
+    pub(crate) mod ledger_diff;
+    pub(crate) mod report_interval;
+    pub(crate) mod spending_by_payee;
+    pub(crate) mod unreimbursed_balances;
 }
 
 pub(crate) mod standard_accounts {
     pub(crate) mod core;
+    pub(crate) mod overrides;
     pub(crate) mod vat;
 }
 
 pub(crate) mod standard_decorators {
     pub(crate) mod card_fx;
+    pub(crate) mod import_vat;
     pub(crate) mod payment_fee;
+    pub(crate) mod social_insurance_korea;
     pub(crate) mod vat_korea;
     pub(crate) mod withholding_tax;
 }
 
+pub(crate) mod standard_handlers {
+    pub(crate) mod configured;
+    pub(crate) mod small_company;
+}
+
+pub(crate) mod statutory_accounts {
+    pub(crate) mod account_mapping;
+    pub(crate) mod korea;
+}
+
+pub(crate) mod verification {
+    pub(crate) mod round_trip_verifier;
+}
+
 // Public exports.
 // ---
 
@@ -31,23 +77,69 @@ pub mod exports {
     //
     // The contents of this mod are re-exported in the root of the crate.
 
+    pub mod consolidation {
+        pub use crate::impl_ext::consolidation::foreign_operation_translation::*;
+        pub use crate::impl_ext::consolidation::intercompany_elimination::*;
+    }
+
+    #[cfg(feature = "hledger")]
     pub mod custom_statements {
         pub use crate::impl_ext::custom_statements::cash_flow_statement_generator::*;
+        pub use crate::impl_ext::custom_statements::custom_statement_generator::*;
+        pub use crate::impl_ext::custom_statements::financial_ratios_generator::*;
+        pub use crate::impl_ext::custom_statements::report_period::*;
+        pub use crate::impl_ext::custom_statements::standard_statement_generator::*;
+        pub use crate::impl_ext::custom_statements::statement_format::*;
+        pub use crate::impl_ext::custom_statements::statement_toolkit::*;
     }
 
     pub mod derived_entries {
+        #[cfg(feature = "hledger")]
         pub use crate::impl_ext::derived_entries::close_entry_generator::*;
+        pub use crate::impl_ext::derived_entries::deferred_tax_generator::*;
+        pub use crate::impl_ext::derived_entries::hyperinflation_restatement::*;
+        pub use crate::impl_ext::derived_entries::withholding_remittance_generator::*;
+    }
+
+    pub mod reports {
+        pub use crate::impl_ext::reports::bank_reconciliation::*;
+        pub use crate::impl_ext::reports::budget_variance::*;
+        pub use crate::impl_ext::reports::cash_flow_forecast::*;
+        pub use crate::impl_ext::reports::chart_of_accounts::*;
+        pub use crate::impl_ext::reports::dimensions::*;
+        pub use crate::impl_ext::reports::expense_summary::*;
+        pub use crate::impl_ext::reports::fund_balances::*;
+        pub use crate::impl_ext::reports::ledger_diff::*;
+        pub use crate::impl_ext::reports::report_interval::*;
+        pub use crate::impl_ext::reports::spending_by_payee::*;
+        pub use crate::impl_ext::reports::unreimbursed_balances::*;
     }
 
     pub mod standard_accounts {
         pub use crate::impl_ext::standard_accounts::core::*;
+        pub use crate::impl_ext::standard_accounts::overrides::StandardAccountOverrides;
         pub use crate::impl_ext::standard_accounts::vat::*;
     }
 
     pub mod standard_decorators {
         pub use crate::impl_ext::standard_decorators::card_fx::*;
+        pub use crate::impl_ext::standard_decorators::import_vat::*;
         pub use crate::impl_ext::standard_decorators::payment_fee::*;
+        pub use crate::impl_ext::standard_decorators::social_insurance_korea::*;
         pub use crate::impl_ext::standard_decorators::vat_korea::*;
         pub use crate::impl_ext::standard_decorators::withholding_tax::*;
     }
+
+    pub mod standard_handlers {
+        pub use crate::impl_ext::standard_handlers::configured::*;
+        pub use crate::impl_ext::standard_handlers::small_company::*;
+    }
+
+    pub mod statutory_accounts {
+        pub use crate::impl_ext::statutory_accounts::account_mapping::*;
+    }
+
+    pub mod verification {
+        pub use crate::impl_ext::verification::round_trip_verifier::*;
+    }
 }
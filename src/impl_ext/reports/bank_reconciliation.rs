@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, NaiveDate};
+
+use crate::entities::{Account, Commodity, FinancialRecords, TransactionLabel, TransactionSpecId};
+use crate::presentation::utils::format_amount;
+
+/// Compares the balances imported from the balances CSV (the balance
+/// assertions) against the balances implied by the generated transactions,
+/// listing mismatches along with the transactions that could explain them.
+///
+/// Useful to track down missing or misdated specs before running hledger,
+/// which only reports the first failing assertion.
+pub struct BankReconciliation<'a> {
+    records: &'a FinancialRecords,
+    candidate_window: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconciliationReport {
+    /// One line per balance assertion, ordered by account, then date.
+    pub lines: Vec<ReconciliationLine>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconciliationLine {
+    pub date: NaiveDate,
+    pub account: Account,
+    pub commodity: Commodity,
+    /// Balance reported by the bank.
+    pub bank_balance: f64,
+    /// Balance implied by the transactions up to (and including) the date.
+    pub ledger_balance: f64,
+    /// Bank balance minus ledger balance, rounded to the currency precision.
+    pub difference: f64,
+    /// Date of the previous balance of this account that reconciled, if any.
+    /// Missing specs are expected between that date and this one.
+    pub last_reconciled: Option<NaiveDate>,
+    /// Existing transactions that would explain the difference if they were
+    /// misdated or mis-signed. Empty if the balance reconciles.
+    pub candidates: Vec<ReconciliationCandidate>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconciliationCandidate {
+    pub spec_id: TransactionSpecId,
+    pub date: NaiveDate,
+    /// Amount posted to the reconciled account.
+    pub amount: f64,
+    pub label: Option<TransactionLabel>,
+    pub reason: CandidateReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateReason {
+    /// Booked shortly after the balance date, but matches an amount already
+    /// reflected in the bank balance.
+    DatedAfterBalance,
+    /// Booked since the last reconciled balance, but not reflected in the bank
+    /// balance (ex. duplicated, or not yet cleared by the bank).
+    NotInBankBalance,
+    /// Booked since the last reconciled balance with the opposite sign.
+    SignReversed,
+}
+
+/// Ledger account name and currency code.
+type AccountKey = (String, &'static str);
+type Balance<'r> = (NaiveDate, &'r Account, f64);
+type Posting = (NaiveDate, TransactionSpecId, f64);
+
+impl<'a> BankReconciliation<'a> {
+    pub fn new(records: &'a FinancialRecords) -> Self {
+        Self {
+            records,
+            candidate_window: Duration::days(7),
+        }
+    }
+
+    /// How many days after a balance date to look for misdated transactions
+    /// (default: 7).
+    pub fn with_candidate_window(mut self, days: i64) -> Self {
+        self.candidate_window = Duration::days(days);
+        self
+    }
+
+    pub fn generate(&self) -> ReconciliationReport {
+        // Group balances and postings by account and currency.
+        let mut balances: BTreeMap<AccountKey, (Commodity, Vec<Balance>)> = BTreeMap::new();
+        for assertion in &self.records.assertions {
+            balances
+                .entry((assertion.account.ledger(), assertion.commodity.code()))
+                .or_insert_with(|| (assertion.commodity, Vec::new()))
+                .1
+                .push((assertion.date, &assertion.account, assertion.balance));
+        }
+        let mut postings: BTreeMap<AccountKey, Vec<Posting>> = BTreeMap::new();
+        for transaction in &self.records.transactions {
+            for posting in &transaction.postings {
+                let key = (posting.account.ledger(), posting.commodity.code());
+                if balances.contains_key(&key) {
+                    postings.entry(key).or_default().push((
+                        transaction.date,
+                        transaction.spec_id,
+                        posting.amount,
+                    ));
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        for (key, (commodity, mut account_balances)) in balances {
+            let account_postings = postings.remove(&key).unwrap_or_default();
+            account_balances.sort_by_key(|(date, _, _)| *date);
+            let mut last_reconciled = None;
+            for (date, account, bank_balance) in account_balances {
+                let ledger_balance: f64 = account_postings
+                    .iter()
+                    .filter(|(posting_date, _, _)| *posting_date <= date)
+                    .map(|(_, _, amount)| amount)
+                    .sum();
+                let difference = round(bank_balance - ledger_balance, commodity);
+                let candidates = if difference == 0.0 {
+                    Vec::new()
+                } else {
+                    self.candidates(
+                        &account_postings,
+                        date,
+                        last_reconciled,
+                        difference,
+                        commodity,
+                    )
+                };
+                lines.push(ReconciliationLine {
+                    date,
+                    account: account.clone(),
+                    commodity,
+                    bank_balance,
+                    ledger_balance: round(ledger_balance, commodity),
+                    difference,
+                    last_reconciled,
+                    candidates,
+                });
+                if difference == 0.0 {
+                    last_reconciled = Some(date);
+                }
+            }
+        }
+        ReconciliationReport { lines }
+    }
+
+    fn candidates(
+        &self,
+        postings: &[Posting],
+        date: NaiveDate,
+        last_reconciled: Option<NaiveDate>,
+        difference: f64,
+        commodity: Commodity,
+    ) -> Vec<ReconciliationCandidate> {
+        let since_last_reconciled =
+            |posting_date: NaiveDate| last_reconciled.is_none_or(|d| posting_date > d);
+        postings
+            .iter()
+            .filter_map(|&(posting_date, spec_id, amount)| {
+                let reason = if posting_date > date {
+                    (posting_date <= date + self.candidate_window
+                        && round(amount - difference, commodity) == 0.0)
+                        .then_some(CandidateReason::DatedAfterBalance)
+                } else if !since_last_reconciled(posting_date) {
+                    None
+                } else if round(amount + difference, commodity) == 0.0 {
+                    Some(CandidateReason::NotInBankBalance)
+                } else if round(2.0 * amount + difference, commodity) == 0.0 {
+                    Some(CandidateReason::SignReversed)
+                } else {
+                    None
+                }?;
+                Some(ReconciliationCandidate {
+                    spec_id,
+                    date: posting_date,
+                    amount,
+                    label: self.records.label_lookup.get(&spec_id).cloned(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ReconciliationReport {
+    pub fn mismatches(&self) -> impl Iterator<Item = &ReconciliationLine> {
+        self.lines.iter().filter(|line| !line.is_reconciled())
+    }
+
+    pub fn is_reconciled(&self) -> bool {
+        self.mismatches().next().is_none()
+    }
+
+    /// Plain text report listing only the mismatched balances.
+    pub fn to_text(&self) -> String {
+        let mismatch_count = self.mismatches().count();
+        let mut output = format!(
+            "Bank reconciliation: {} of {} balance(s) mismatched.\n",
+            mismatch_count,
+            self.lines.len()
+        );
+        for line in self.mismatches() {
+            output.push_str(&format!(
+                "\n{}  {}\n  Bank balance:    {}\n  Ledger balance:  {}\n  Difference:      {}\n",
+                line.date,
+                line.account.ledger(),
+                format_amount(line.bank_balance, line.commodity, false),
+                format_amount(line.ledger_balance, line.commodity, false),
+                format_amount(line.difference, line.commodity, false),
+            ));
+            match line.last_reconciled {
+                Some(since) => output.push_str(&format!(
+                    "  Missing spec(s) expected after {} (last reconciled balance).\n",
+                    since
+                )),
+                None => output.push_str("  No earlier balance of this account reconciles.\n"),
+            }
+            for candidate in &line.candidates {
+                let reason = match candidate.reason {
+                    CandidateReason::DatedAfterBalance => "dated after the balance date",
+                    CandidateReason::NotInBankBalance => "not in the bank balance",
+                    CandidateReason::SignReversed => "sign may be reversed",
+                };
+                let label = candidate
+                    .label
+                    .as_ref()
+                    .map(|l| {
+                        [&*l.payee, l.description.as_str()]
+                            .into_iter()
+                            .filter(|s| !s.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" - ")
+                    })
+                    .unwrap_or_default();
+                output.push_str(&format!(
+                    "  Candidate: {} {}, {} ({})\n",
+                    candidate.date,
+                    label,
+                    format_amount(candidate.amount, line.commodity, false),
+                    reason
+                ));
+            }
+        }
+        output
+    }
+}
+
+impl ReconciliationLine {
+    pub fn is_reconciled(&self) -> bool {
+        self.difference == 0.0
+    }
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
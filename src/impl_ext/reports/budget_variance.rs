@@ -0,0 +1,124 @@
+use iso_currency::Currency;
+
+use crate::entities::{Budget, FinancialRecords};
+use crate::presentation::utils::format_amount;
+
+/// Compares each budget in `FinancialRecords::budgets` against the actual
+/// income or expenses booked in its period.
+pub struct BudgetVariance<'a> {
+    records: &'a FinancialRecords,
+}
+
+#[derive(Debug, Clone)]
+pub struct BudgetVarianceReport {
+    /// One line per budget, ordered by period start.
+    pub lines: Vec<BudgetVarianceLine>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BudgetVarianceLine {
+    pub budget: Budget,
+    /// Expenses incurred (or income earned) in the period, as a positive
+    /// number.
+    pub actual: f64,
+    /// Budgeted minus actual amount. For expenses a positive variance is
+    /// favourable (underspent); for income it's unfavourable (shortfall).
+    pub variance: f64,
+    /// Actual amount as a fraction of the budget, if non-zero.
+    pub utilization: Option<f64>,
+}
+
+impl<'a> BudgetVariance<'a> {
+    pub fn new(records: &'a FinancialRecords) -> Self {
+        Self { records }
+    }
+
+    pub fn generate(&self) -> BudgetVarianceReport {
+        let mut lines: Vec<BudgetVarianceLine> = self
+            .records
+            .budgets
+            .iter()
+            .map(|budget| {
+                let posted: f64 = self
+                    .records
+                    .transactions
+                    .iter()
+                    .filter(|tx| tx.date >= budget.from && tx.date <= budget.to)
+                    .flat_map(|tx| tx.postings.iter())
+                    .filter(|p| p.commodity == budget.currency && budget.scope.matches(&p.account))
+                    .map(|p| p.amount)
+                    .sum();
+                let actual = round(
+                    if budget.scope.is_income() {
+                        -posted
+                    } else {
+                        posted
+                    },
+                    budget.currency,
+                );
+                BudgetVarianceLine {
+                    budget: budget.clone(),
+                    actual,
+                    variance: round(budget.amount - actual, budget.currency),
+                    utilization: (budget.amount != 0.0).then(|| actual / budget.amount),
+                }
+            })
+            .collect();
+        lines.sort_by_key(|line| (line.budget.from, line.budget.to));
+        BudgetVarianceReport { lines }
+    }
+}
+
+impl BudgetVarianceReport {
+    /// Expense budgets that were overspent, and income budgets that fell
+    /// short.
+    pub fn unfavourable(&self) -> impl Iterator<Item = &BudgetVarianceLine> {
+        self.lines.iter().filter(|line| line.is_unfavourable())
+    }
+
+    /// Plain text report listing every budget.
+    pub fn to_text(&self) -> String {
+        let mut output = format!(
+            "Budget vs. actual: {} of {} budget(s) unfavourable.\n",
+            self.unfavourable().count(),
+            self.lines.len()
+        );
+        for line in &self.lines {
+            let currency = line.budget.currency;
+            output.push_str(&format!(
+                "\n{} to {}  {}\n  Budget:    {}\n  Actual:    {}{}\n  Variance:  {}{}\n",
+                line.budget.from,
+                line.budget.to,
+                line.budget.scope.account().ledger(),
+                format_amount(line.budget.amount, currency, false),
+                format_amount(line.actual, currency, false),
+                line.utilization
+                    .map(|u| format!(" ({:.1}%)", u * 100.0))
+                    .unwrap_or_default(),
+                format_amount(line.variance, currency, false),
+                if line.is_unfavourable() {
+                    "  (unfavourable)"
+                } else {
+                    ""
+                },
+            ));
+        }
+        output
+    }
+}
+
+impl BudgetVarianceLine {
+    pub fn is_unfavourable(&self) -> bool {
+        if self.budget.scope.is_income() {
+            self.variance > 0.0
+        } else {
+            self.variance < 0.0
+        }
+    }
+}
+
+fn round(amount: f64, currency: Currency) -> f64 {
+    let factor = 10_f64.powi(currency.exponent().unwrap_or(0) as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
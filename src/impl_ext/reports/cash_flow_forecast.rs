@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Months, NaiveDate};
+
+use crate::entities::{
+    Account, AssetAccount, AssetClassification, Commodity, FinancialRecords, TransactionLabel,
+    TransactionSpecId,
+};
+use crate::presentation::utils::format_amount;
+
+/// Projects the balance of each cash account forward from a given date, using
+/// the transactions already generated for future-dated specs (scheduled
+/// payables, fixed expenses, VAT clearings, etc.) plus any recurring cash
+/// flows that don't have specs yet.
+///
+/// Dates on which a balance would go negative are flagged as shortfalls.
+pub struct CashFlowForecast<'a> {
+    records: &'a FinancialRecords,
+    as_of: NaiveDate,
+    months: u32,
+    recurring: Vec<RecurringCashFlow>,
+}
+
+/// Cash flow expected on the same day every month, ex. rent or salaries.
+#[derive(Debug, Clone)]
+pub struct RecurringCashFlow {
+    pub account: AssetAccount,
+    /// Positive for inflows, negative for outflows.
+    pub amount: f64,
+    pub commodity: Commodity,
+    pub description: String,
+    /// Clamped to the last day of shorter months.
+    pub day_of_month: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForecastReport {
+    pub as_of: NaiveDate,
+    pub until: NaiveDate,
+    /// One entry per cash account and currency, ordered by account.
+    pub accounts: Vec<AccountForecast>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountForecast {
+    pub account: Account,
+    pub commodity: Commodity,
+    /// Balance at the end of the `as_of` date.
+    pub opening_balance: f64,
+    pub closing_balance: f64,
+    /// Ordered by date.
+    pub movements: Vec<ForecastMovement>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForecastMovement {
+    pub date: NaiveDate,
+    pub amount: f64,
+    /// Balance after this movement.
+    pub balance: f64,
+    pub source: ForecastSource,
+}
+
+#[derive(Debug, Clone)]
+pub enum ForecastSource {
+    Spec {
+        spec_id: TransactionSpecId,
+        label: Option<TransactionLabel>,
+    },
+    Recurring {
+        description: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct ForecastShortfall<'r> {
+    pub date: NaiveDate,
+    pub account: &'r Account,
+    pub commodity: Commodity,
+    /// Negative balance after the movement on this date.
+    pub balance: f64,
+}
+
+/// Ledger account name and currency code.
+type AccountKey = (String, &'static str);
+
+struct AccountState {
+    account: Account,
+    commodity: Commodity,
+    opening_balance: f64,
+    movements: Vec<(NaiveDate, f64, ForecastSource)>,
+}
+
+impl<'a> CashFlowForecast<'a> {
+    pub fn new(records: &'a FinancialRecords, as_of: NaiveDate, months: u32) -> Self {
+        Self {
+            records,
+            as_of,
+            months,
+            recurring: Vec::new(),
+        }
+    }
+
+    pub fn with_recurring(mut self, recurring: RecurringCashFlow) -> Self {
+        self.recurring.push(recurring);
+        self
+    }
+
+    pub fn generate(&self) -> ForecastReport {
+        let until = self
+            .as_of
+            .checked_add_months(Months::new(self.months))
+            .unwrap_or(NaiveDate::MAX);
+
+        let mut states: BTreeMap<AccountKey, AccountState> = BTreeMap::new();
+        for transaction in &self.records.transactions {
+            if transaction.date > until {
+                continue;
+            }
+            for posting in &transaction.postings {
+                if !is_cash(&posting.account) {
+                    continue;
+                }
+                let state = state(&mut states, &posting.account, posting.commodity);
+                if transaction.date <= self.as_of {
+                    state.opening_balance += posting.amount;
+                } else {
+                    state.movements.push((
+                        transaction.date,
+                        posting.amount,
+                        ForecastSource::Spec {
+                            spec_id: transaction.spec_id,
+                            label: self.records.label_lookup.get(&transaction.spec_id).cloned(),
+                        },
+                    ));
+                }
+            }
+        }
+        for recurring in &self.recurring {
+            let account: Account = recurring.account.clone().into();
+            let state = state(&mut states, &account, recurring.commodity);
+            for date in monthly_dates(recurring.day_of_month, self.as_of, until) {
+                state.movements.push((
+                    date,
+                    recurring.amount,
+                    ForecastSource::Recurring {
+                        description: recurring.description.clone(),
+                    },
+                ));
+            }
+        }
+
+        let accounts = states
+            .into_values()
+            .map(|mut state| {
+                // Stable, so movements on the same day keep the order of the
+                // records, followed by recurring cash flows.
+                state.movements.sort_by_key(|(date, _, _)| *date);
+                let opening_balance = round(state.opening_balance, state.commodity);
+                let mut balance = opening_balance;
+                let movements = state
+                    .movements
+                    .into_iter()
+                    .map(|(date, amount, source)| {
+                        balance = round(balance + amount, state.commodity);
+                        ForecastMovement {
+                            date,
+                            amount,
+                            balance,
+                            source,
+                        }
+                    })
+                    .collect();
+                AccountForecast {
+                    account: state.account,
+                    commodity: state.commodity,
+                    opening_balance,
+                    closing_balance: balance,
+                    movements,
+                }
+            })
+            .collect();
+
+        ForecastReport {
+            as_of: self.as_of,
+            until,
+            accounts,
+        }
+    }
+}
+
+impl ForecastReport {
+    /// Movements leaving an account with a negative balance, ordered by date.
+    pub fn shortfalls(&self) -> Vec<ForecastShortfall<'_>> {
+        let mut shortfalls: Vec<ForecastShortfall> = self
+            .accounts
+            .iter()
+            .flat_map(|forecast| {
+                forecast
+                    .movements
+                    .iter()
+                    .filter(|movement| movement.balance < 0.0)
+                    .map(|movement| ForecastShortfall {
+                        date: movement.date,
+                        account: &forecast.account,
+                        commodity: forecast.commodity,
+                        balance: movement.balance,
+                    })
+            })
+            .collect();
+        shortfalls.sort_by_key(|shortfall| shortfall.date);
+        shortfalls
+    }
+
+    pub fn has_shortfalls(&self) -> bool {
+        self.accounts
+            .iter()
+            .any(|forecast| forecast.movements.iter().any(|m| m.balance < 0.0))
+    }
+
+    /// Plain text report listing each account's movements, with negative
+    /// balances marked.
+    pub fn to_text(&self) -> String {
+        let mut output = format!(
+            "Cash flow forecast: {} to {} ({} shortfall(s)).\n",
+            self.as_of,
+            self.until,
+            self.shortfalls().len()
+        );
+        for forecast in &self.accounts {
+            output.push_str(&format!(
+                "\n{}\n  {}  Opening balance  {}\n",
+                forecast.account.ledger(),
+                self.as_of,
+                format_amount(forecast.opening_balance, forecast.commodity, false),
+            ));
+            for movement in &forecast.movements {
+                let description = match &movement.source {
+                    ForecastSource::Spec { label, .. } => label
+                        .as_ref()
+                        .map(|l| {
+                            [&*l.payee, l.description.as_str()]
+                                .into_iter()
+                                .filter(|s| !s.is_empty())
+                                .collect::<Vec<_>>()
+                                .join(" - ")
+                        })
+                        .unwrap_or_default(),
+                    ForecastSource::Recurring { description } => {
+                        format!("{} (recurring)", description)
+                    }
+                };
+                output.push_str(&format!(
+                    "  {}  {}, {} -> {}{}\n",
+                    movement.date,
+                    description,
+                    format_amount(movement.amount, forecast.commodity, false),
+                    format_amount(movement.balance, forecast.commodity, false),
+                    if movement.balance < 0.0 {
+                        "  ** NEGATIVE **"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+        }
+        output
+    }
+}
+
+fn is_cash(account: &Account) -> bool {
+    matches!(
+        account,
+        Account::Asset(AssetAccount(_, AssetClassification::CashAndCashEquivalents))
+    )
+}
+
+fn state<'s>(
+    states: &'s mut BTreeMap<AccountKey, AccountState>,
+    account: &Account,
+    commodity: Commodity,
+) -> &'s mut AccountState {
+    states
+        .entry((account.ledger(), commodity.code()))
+        .or_insert_with(|| AccountState {
+            account: account.clone(),
+            commodity,
+            opening_balance: 0.0,
+            movements: Vec::new(),
+        })
+}
+
+/// Dates in (`after`, `until`] falling on the given day of the month.
+fn monthly_dates(day_of_month: u32, after: NaiveDate, until: NaiveDate) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    let mut month_start = after.with_day(1).expect("day 1 should always be valid");
+    while month_start <= until {
+        let next_month = month_start + Months::new(1);
+        let last_day = next_month
+            .pred_opt()
+            .expect("date should have a predecessor");
+        let date = month_start
+            .with_day(day_of_month.clamp(1, last_day.day()))
+            .expect("clamped day should be valid");
+        if date > after && date <= until {
+            dates.push(date);
+        }
+        month_start = next_month;
+    }
+    dates
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use fractic_server_error::{CriticalError, ServerError};
+
+use crate::{
+    entities::{Account, FinancialRecords},
+    impl_ext::standard_accounts::overrides::standard_named_accounts,
+    presentation::hledger_printer::format_account_declaration,
+};
+
+/// Full chart of accounts implied by the classifications (one top-level
+/// account each), the standard named accounts, and any other named accounts
+/// registered with it, for sharing with external accountants.
+pub struct ChartOfAccounts {
+    /// Keyed by ledger name. Classifications sharing a ledger name keep the
+    /// first account.
+    accounts: BTreeMap<String, Account>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChartOfAccountsEntry {
+    pub account: Account,
+    /// Ledger name (ex. "Assets:NonCurrent:PropertyPlantEquipment").
+    pub ledger: String,
+    pub description: Option<&'static str>,
+    /// Ex. "Balance sheet".
+    pub statement: &'static str,
+    /// Line item the account is presented under (ex. "Property, plant and
+    /// equipment").
+    pub line: &'static str,
+}
+
+impl ChartOfAccounts {
+    pub fn new() -> Self {
+        let mut chart = Self {
+            accounts: BTreeMap::new(),
+        };
+        chart.register(
+            Account::all_top_level()
+                .into_iter()
+                .chain(standard_named_accounts()),
+        );
+        chart
+    }
+
+    /// Add named accounts (ex. those of the handlers, or
+    /// `PrintOptions::declared_accounts`).
+    pub fn with_accounts(mut self, accounts: impl IntoIterator<Item = Account>) -> Self {
+        self.register(accounts);
+        self
+    }
+
+    /// Add every account used by the records' transactions and assertions.
+    pub fn with_records(mut self, financial_records: &FinancialRecords) -> Self {
+        self.register(
+            financial_records
+                .transactions
+                .iter()
+                .flat_map(|tx| tx.postings.iter().map(|p| p.account.clone()))
+                .chain(
+                    financial_records
+                        .assertions
+                        .iter()
+                        .map(|a| a.account.clone()),
+                ),
+        );
+        self
+    }
+
+    /// Ordered by ledger name.
+    pub fn entries(&self) -> Vec<ChartOfAccountsEntry> {
+        self.accounts
+            .iter()
+            .map(|(ledger, account)| {
+                let (statement, line) = account.statement_line();
+                ChartOfAccountsEntry {
+                    account: account.clone(),
+                    ledger: ledger.clone(),
+                    description: account.description(),
+                    statement,
+                    line,
+                }
+            })
+            .collect()
+    }
+
+    /// hledger account declarations, each followed by its description.
+    pub fn to_hledger(&self) -> String {
+        let mut output = String::new();
+        for entry in self.entries() {
+            output.push_str(&format_account_declaration(&entry.account));
+            output.push('\n');
+            for line in entry
+                .description
+                .map(|d| textwrap::wrap(d, 94))
+                .unwrap_or_default()
+            {
+                output.push_str(&format!("    ; {}\n", line));
+            }
+        }
+        output
+    }
+
+    /// Mapping of each ledger name to its description and statement line.
+    pub fn to_csv(&self) -> Result<String, ServerError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let to_error = |e: csv::Error| {
+            CriticalError::with_debug("failed to write chart of accounts as CSV", &e)
+        };
+        writer
+            .write_record(["account", "type", "description", "statement", "line"])
+            .map_err(to_error)?;
+        for entry in self.entries() {
+            writer
+                .write_record([
+                    entry.ledger.as_str(),
+                    &entry.account.type_tag().to_string(),
+                    entry.description.unwrap_or_default(),
+                    entry.statement,
+                    entry.line,
+                ])
+                .map_err(to_error)?;
+        }
+        let bytes = writer.into_inner().map_err(|e| {
+            CriticalError::with_debug("failed to write chart of accounts as CSV", &e.to_string())
+        })?;
+        String::from_utf8(bytes)
+            .map_err(|e| CriticalError::with_debug("failed to write chart of accounts as CSV", &e))
+    }
+
+    fn register(&mut self, accounts: impl IntoIterator<Item = Account>) {
+        for account in accounts {
+            self.accounts.entry(account.ledger()).or_insert(account);
+        }
+    }
+}
+
+impl Default for ChartOfAccounts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
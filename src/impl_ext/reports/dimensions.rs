@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::entities::{Account, Commodity, FinancialRecords};
+use crate::presentation::utils::format_amount;
+
+/// Copy of the records keeping only the postings tagged with the given
+/// dimension value (see `TransactionSpec::dimensions`), so any report over
+/// `FinancialRecords` can be run for a single project, department, etc.
+///
+/// The remaining transactions are usually not balanced, so the result is
+/// meant for reporting only. Assertions, end-of-year entries, unreimbursed
+/// entries and budgets aren't tagged, and are left out.
+pub fn filter_by_dimension(
+    records: &FinancialRecords,
+    name: &str,
+    value: &str,
+) -> FinancialRecords {
+    FinancialRecords {
+        transactions: records
+            .transactions
+            .iter()
+            .filter_map(|transaction| {
+                let postings: Vec<_> = transaction
+                    .postings
+                    .iter()
+                    .filter(|p| p.custom_tags.get(name).is_some_and(|v| v == value))
+                    .cloned()
+                    .collect();
+                (!postings.is_empty()).then(|| {
+                    let mut transaction = transaction.clone();
+                    transaction.postings = postings;
+                    transaction
+                })
+            })
+            .collect(),
+        assertions: Vec::new(),
+        ledger_extensions: Vec::new(),
+        eoy_entries: Vec::new(),
+        label_lookup: records.label_lookup.clone(),
+        annotations_lookup: records.annotations_lookup.clone(),
+        unreimbursed_entries: Vec::new(),
+        budgets: Vec::new(),
+        payees: records.payees.clone(),
+        metadata: records.metadata.clone(),
+    }
+}
+
+/// All values of the dimension used in the records, in order.
+pub fn dimension_values(records: &FinancialRecords, name: &str) -> BTreeSet<String> {
+    records
+        .transactions
+        .iter()
+        .flat_map(|tx| tx.postings.iter())
+        .filter_map(|p| p.custom_tags.get(name).cloned())
+        .collect()
+}
+
+/// Income, expenses and profit for each value of a dimension, ex. to compare
+/// the profitability of client projects.
+pub struct ProfitByDimension<'a> {
+    records: &'a FinancialRecords,
+    dimension: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfitByDimensionReport {
+    pub dimension: String,
+    /// One line per value and currency, ordered by value. Income and expenses
+    /// not tagged with the dimension are listed last, with no value.
+    pub lines: Vec<DimensionProfit>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DimensionProfit {
+    pub value: Option<String>,
+    pub commodity: Commodity,
+    /// As a positive number.
+    pub income: f64,
+    pub expenses: f64,
+    pub profit: f64,
+    /// Profit as a fraction of income, if there was any.
+    pub margin: Option<f64>,
+}
+
+/// Whether untagged (sorted last), dimension value and currency code.
+type ProfitKey = (bool, String, &'static str);
+
+impl<'a> ProfitByDimension<'a> {
+    pub fn new(records: &'a FinancialRecords, dimension: impl Into<String>) -> Self {
+        Self {
+            records,
+            dimension: dimension.into(),
+        }
+    }
+
+    pub fn generate(&self) -> ProfitByDimensionReport {
+        let mut totals: BTreeMap<ProfitKey, (Commodity, f64, f64)> = BTreeMap::new();
+        for posting in self
+            .records
+            .transactions
+            .iter()
+            .flat_map(|tx| tx.postings.iter())
+        {
+            let is_income = match posting.account {
+                Account::Income(_) => true,
+                Account::Expense(_) => false,
+                _ => continue,
+            };
+            let value = posting.custom_tags.get(&self.dimension);
+            let (_, income, expenses) = totals
+                .entry((
+                    value.is_none(),
+                    value.cloned().unwrap_or_default(),
+                    posting.commodity.code(),
+                ))
+                .or_insert((posting.commodity, 0.0, 0.0));
+            if is_income {
+                *income -= posting.amount;
+            } else {
+                *expenses += posting.amount;
+            }
+        }
+
+        ProfitByDimensionReport {
+            dimension: self.dimension.clone(),
+            lines: totals
+                .into_iter()
+                .map(|((untagged, value, _), (commodity, income, expenses))| {
+                    let income = round(income, commodity);
+                    let expenses = round(expenses, commodity);
+                    let profit = round(income - expenses, commodity);
+                    DimensionProfit {
+                        value: (!untagged).then_some(value),
+                        commodity,
+                        income,
+                        expenses,
+                        profit,
+                        margin: (income != 0.0).then(|| profit / income),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ProfitByDimensionReport {
+    /// Plain text report listing each value's income, expenses and profit.
+    pub fn to_text(&self) -> String {
+        let mut output = format!("Profit by {}.\n", self.dimension);
+        for line in &self.lines {
+            output.push_str(&format!(
+                "\n{}\n  Income:    {}\n  Expenses:  {}\n  Profit:    {}{}\n",
+                line.value.as_deref().unwrap_or("(untagged)"),
+                format_amount(line.income, line.commodity, false),
+                format_amount(line.expenses, line.commodity, false),
+                format_amount(line.profit, line.commodity, false),
+                line.margin
+                    .map(|m| format!(" ({:.1}% margin)", m * 100.0))
+                    .unwrap_or_default(),
+            ));
+        }
+        output
+    }
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::entities::{
+    Account, Commodity, ExpenseAccount, ExpenseClassification, FinancialRecords,
+    IncomeClassification,
+};
+use crate::presentation::utils::format_amount;
+
+use super::report_interval::ReportInterval;
+
+/// Totals the expenses of each classification (and each named sub-account
+/// within it) per period, as a percentage of the period's operating revenue.
+///
+/// Computed directly from the records, so hledger is not needed.
+pub struct ExpenseSummary<'a> {
+    records: &'a FinancialRecords,
+    interval: ReportInterval,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpenseSummaryReport {
+    pub interval: ReportInterval,
+    /// One entry per period and currency with any expenses or revenue, in
+    /// chronological order.
+    pub periods: Vec<ExpenseSummaryPeriod>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpenseSummaryPeriod {
+    pub start: NaiveDate,
+    /// Inclusive.
+    pub end: NaiveDate,
+    pub commodity: Commodity,
+    /// Operating revenue (sales and services), as a positive number.
+    pub revenue: f64,
+    pub total_expenses: f64,
+    /// Ordered by classification.
+    pub classifications: Vec<ClassificationExpenses>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassificationExpenses {
+    pub classification: ExpenseClassification,
+    pub amount: f64,
+    /// Amount as a fraction of the period's revenue, if there was any.
+    pub share_of_revenue: Option<f64>,
+    /// The top-level account and each named sub-account with expenses,
+    /// ordered by name (top-level first).
+    pub accounts: Vec<AccountExpenses>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountExpenses {
+    pub account: ExpenseAccount,
+    pub amount: f64,
+    pub share_of_revenue: Option<f64>,
+}
+
+/// Period start and currency code.
+type PeriodKey = (NaiveDate, &'static str);
+
+#[derive(Default)]
+struct PeriodState {
+    revenue: f64,
+    expenses: BTreeMap<ExpenseClassification, BTreeMap<Option<String>, f64>>,
+}
+
+impl<'a> ExpenseSummary<'a> {
+    pub fn new(records: &'a FinancialRecords, interval: ReportInterval) -> Self {
+        Self { records, interval }
+    }
+
+    pub fn generate(&self) -> ExpenseSummaryReport {
+        let mut states: BTreeMap<PeriodKey, (Commodity, PeriodState)> = BTreeMap::new();
+        for transaction in &self.records.transactions {
+            let start = self.interval.period_start(transaction.date);
+            for posting in &transaction.postings {
+                let state = || (posting.commodity, PeriodState::default());
+                match &posting.account {
+                    Account::Income(i) if is_revenue(i.1) => {
+                        let (_, state) = states
+                            .entry((start, posting.commodity.code()))
+                            .or_insert_with(state);
+                        state.revenue -= posting.amount;
+                    }
+                    Account::Expense(ExpenseAccount(name, classification)) => {
+                        let (_, state) = states
+                            .entry((start, posting.commodity.code()))
+                            .or_insert_with(state);
+                        *state
+                            .expenses
+                            .entry(*classification)
+                            .or_default()
+                            .entry(name.clone())
+                            .or_default() += posting.amount;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let periods = states
+            .into_iter()
+            .map(|((start, _), (commodity, state))| {
+                let revenue = round(state.revenue, commodity);
+                let share = |amount: f64| (revenue != 0.0).then(|| amount / revenue);
+                let classifications: Vec<ClassificationExpenses> = state
+                    .expenses
+                    .into_iter()
+                    .map(|(classification, accounts)| {
+                        let amount = round(accounts.values().sum(), commodity);
+                        ClassificationExpenses {
+                            classification,
+                            amount,
+                            share_of_revenue: share(amount),
+                            accounts: accounts
+                                .into_iter()
+                                .map(|(name, amount)| {
+                                    let amount = round(amount, commodity);
+                                    AccountExpenses {
+                                        account: ExpenseAccount(name, classification),
+                                        amount,
+                                        share_of_revenue: share(amount),
+                                    }
+                                })
+                                .collect(),
+                        }
+                    })
+                    .collect();
+                ExpenseSummaryPeriod {
+                    start,
+                    end: self.interval.period_end(start),
+                    commodity,
+                    revenue,
+                    total_expenses: round(
+                        classifications.iter().map(|c| c.amount).sum(),
+                        commodity,
+                    ),
+                    classifications,
+                }
+            })
+            .collect();
+
+        ExpenseSummaryReport {
+            interval: self.interval,
+            periods,
+        }
+    }
+}
+
+impl ExpenseSummaryReport {
+    /// Plain text report listing each period's expenses by classification,
+    /// with sub-accounts indented below.
+    pub fn to_text(&self) -> String {
+        let mut output = String::from("Expenses by classification.\n");
+        for period in &self.periods {
+            let commodity = period.commodity;
+            output.push_str(&format!(
+                "\n{} ({} to {})\n  Revenue:  {}\n",
+                self.interval.period_name(period.start),
+                period.start,
+                period.end,
+                format_amount(period.revenue, commodity, false),
+            ));
+            for classification in &period.classifications {
+                output.push_str(&format!(
+                    "  {:?}  {}{}\n",
+                    classification.classification,
+                    format_amount(classification.amount, commodity, false),
+                    percentage(classification.share_of_revenue),
+                ));
+                // Only break down classifications with named sub-accounts.
+                if classification
+                    .accounts
+                    .iter()
+                    .any(|a| a.account.0.is_some())
+                {
+                    for account in &classification.accounts {
+                        output.push_str(&format!(
+                            "    {}  {}{}\n",
+                            Account::Expense(account.account.clone()).ledger(),
+                            format_amount(account.amount, commodity, false),
+                            percentage(account.share_of_revenue),
+                        ));
+                    }
+                }
+            }
+            output.push_str(&format!(
+                "  Total expenses:  {}{}\n",
+                format_amount(period.total_expenses, commodity, false),
+                percentage(period.share_of_revenue()),
+            ));
+        }
+        output
+    }
+}
+
+impl ExpenseSummaryPeriod {
+    pub fn share_of_revenue(&self) -> Option<f64> {
+        (self.revenue != 0.0).then(|| self.total_expenses / self.revenue)
+    }
+}
+
+fn is_revenue(classification: IncomeClassification) -> bool {
+    matches!(
+        classification,
+        IncomeClassification::SalesRevenue | IncomeClassification::ServiceRevenue
+    )
+}
+
+fn percentage(share: Option<f64>) -> String {
+    share
+        .map(|s| format!(" ({:.1}% of revenue)", s * 100.0))
+        .unwrap_or_default()
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::entities::{Account, AssetAccount, AssetClassification, Commodity, FinancialRecords};
+use crate::presentation::utils::format_amount;
+
+/// Dimension naming the fund a spec's postings belong to (ex.
+/// `fund:Education grant` in the dimensions column). Postings without it
+/// belong to the general fund.
+pub const FUND_DIMENSION: &str = "fund";
+
+/// Balances of each fund (ex. restricted grant money vs. general funds), for
+/// non-profits and grant-funded entities.
+///
+/// Since every posting of a spec is tagged with its fund, each fund balances
+/// on its own: its net assets are the income received less the expenses paid
+/// from it.
+pub struct FundBalances<'a> {
+    records: &'a FinancialRecords,
+    as_of: NaiveDate,
+    funds: Vec<Fund>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fund {
+    /// Value of the `fund` dimension.
+    pub name: String,
+    pub restriction: FundRestriction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FundRestriction {
+    /// Free to be used for any purpose.
+    #[default]
+    Unrestricted,
+    /// Can only be used for the purpose (or period) set by the grantor.
+    Restricted,
+    /// Principal must be maintained (ex. endowments); only the income earned
+    /// on it may be used.
+    PermanentlyRestricted,
+}
+
+#[derive(Debug, Clone)]
+pub struct FundBalancesReport {
+    pub as_of: NaiveDate,
+    /// One entry per fund and currency, ordered by fund name, followed by the
+    /// general fund.
+    pub funds: Vec<FundBalance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FundBalance {
+    /// `None` for the general fund (postings without a fund).
+    pub fund: Option<String>,
+    pub restriction: FundRestriction,
+    pub commodity: Commodity,
+    /// Total income received into the fund, as a positive number.
+    pub income: f64,
+    /// Total expenses paid from the fund.
+    pub expenses: f64,
+    /// Assets less liabilities held by the fund.
+    pub net_assets: f64,
+    pub cash: f64,
+    /// Non-zero asset and liability balances, ordered by account.
+    pub accounts: Vec<(Account, f64)>,
+}
+
+/// Fund name and currency code. The general fund (empty name) is sorted last.
+type FundKey = (bool, String, &'static str);
+
+#[derive(Default)]
+struct FundState {
+    income: f64,
+    expenses: f64,
+    accounts: BTreeMap<String, (Account, f64)>,
+}
+
+impl<'a> FundBalances<'a> {
+    pub fn new(records: &'a FinancialRecords, as_of: NaiveDate) -> Self {
+        Self {
+            records,
+            as_of,
+            funds: Vec::new(),
+        }
+    }
+
+    /// Funds not registered here are reported as unrestricted.
+    pub fn with_fund(mut self, name: impl Into<String>, restriction: FundRestriction) -> Self {
+        self.funds.push(Fund {
+            name: name.into(),
+            restriction,
+        });
+        self
+    }
+
+    pub fn generate(&self) -> FundBalancesReport {
+        let mut states: BTreeMap<FundKey, (Commodity, FundState)> = BTreeMap::new();
+        for transaction in &self.records.transactions {
+            if transaction.date > self.as_of {
+                continue;
+            }
+            for posting in &transaction.postings {
+                let fund = posting.custom_tags.get(FUND_DIMENSION);
+                let (_, state) = states
+                    .entry((
+                        fund.is_none(),
+                        fund.cloned().unwrap_or_default(),
+                        posting.commodity.code(),
+                    ))
+                    .or_insert_with(|| (posting.commodity, FundState::default()));
+                match &posting.account {
+                    Account::Income(_) => state.income -= posting.amount,
+                    Account::Expense(_) => state.expenses += posting.amount,
+                    Account::Asset(_) | Account::Liability(_) => {
+                        state
+                            .accounts
+                            .entry(posting.account.ledger())
+                            .or_insert_with(|| (posting.account.clone(), 0.0))
+                            .1 += posting.amount;
+                    }
+                    Account::Equity(_) => {}
+                }
+            }
+        }
+
+        let funds = states
+            .into_iter()
+            .map(|((general, name, _), (commodity, state))| {
+                let accounts: Vec<(Account, f64)> = state
+                    .accounts
+                    .into_values()
+                    .map(|(account, balance)| (account, round(balance, commodity)))
+                    .filter(|(_, balance)| *balance != 0.0)
+                    .collect();
+                FundBalance {
+                    restriction: self
+                        .funds
+                        .iter()
+                        .find(|fund| !general && fund.name == name)
+                        .map(|fund| fund.restriction)
+                        .unwrap_or_default(),
+                    fund: (!general).then_some(name),
+                    commodity,
+                    income: round(state.income, commodity),
+                    expenses: round(state.expenses, commodity),
+                    net_assets: round(accounts.iter().map(|(_, b)| b).sum(), commodity),
+                    cash: round(
+                        accounts
+                            .iter()
+                            .filter(|(account, _)| is_cash(account))
+                            .map(|(_, b)| b)
+                            .sum(),
+                        commodity,
+                    ),
+                    accounts,
+                }
+            })
+            .collect();
+
+        FundBalancesReport {
+            as_of: self.as_of,
+            funds,
+        }
+    }
+}
+
+impl FundBalancesReport {
+    /// Restricted funds that have spent more than they received, which means
+    /// general (or other funds') money was used for the restricted purpose.
+    pub fn overdrawn(&self) -> impl Iterator<Item = &FundBalance> {
+        self.funds.iter().filter(|fund| fund.is_overdrawn())
+    }
+
+    /// Cash held by restricted funds, which is not available for general use.
+    pub fn restricted_cash(&self, commodity: impl Into<Commodity>) -> f64 {
+        let commodity = commodity.into();
+        round(
+            self.funds
+                .iter()
+                .filter(|fund| {
+                    fund.commodity == commodity && fund.restriction != FundRestriction::Unrestricted
+                })
+                .map(|fund| fund.cash.max(0.0))
+                .sum(),
+            commodity,
+        )
+    }
+
+    /// Plain text report listing each fund's balances.
+    pub fn to_text(&self) -> String {
+        let mut output = format!(
+            "Fund balances as of {} ({} overdrawn restricted fund(s)).\n",
+            self.as_of,
+            self.overdrawn().count()
+        );
+        for fund in &self.funds {
+            output.push_str(&format!(
+                "\n{} ({:?})\n  Income:      {}\n  Expenses:    {}\n  Net assets:  {}{}\n",
+                fund.fund.as_deref().unwrap_or("General fund"),
+                fund.restriction,
+                format_amount(fund.income, fund.commodity, false),
+                format_amount(fund.expenses, fund.commodity, false),
+                format_amount(fund.net_assets, fund.commodity, false),
+                if fund.is_overdrawn() {
+                    "  ** OVERDRAWN **"
+                } else {
+                    ""
+                },
+            ));
+            for (account, balance) in &fund.accounts {
+                output.push_str(&format!(
+                    "    {}  {}\n",
+                    account.ledger(),
+                    format_amount(*balance, fund.commodity, false),
+                ));
+            }
+        }
+        output
+    }
+}
+
+impl FundBalance {
+    pub fn is_overdrawn(&self) -> bool {
+        self.restriction != FundRestriction::Unrestricted && self.net_assets < 0.0
+    }
+}
+
+fn is_cash(account: &Account) -> bool {
+    matches!(
+        account,
+        Account::Asset(AssetAccount(_, AssetClassification::CashAndCashEquivalents))
+    )
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
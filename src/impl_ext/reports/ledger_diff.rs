@@ -0,0 +1,340 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+#[cfg(feature = "hledger")]
+use fractic_server_error::ServerError;
+
+use crate::entities::{FinancialRecords, TransactionSpecId};
+#[cfg(feature = "hledger")]
+use crate::impl_ext::custom_statements::statement_toolkit::{hledger_postings, LedgerSource};
+
+/// Transactions of a ledger grouped by the spec they were generated from, to
+/// be compared with `LedgerDiff`.
+#[derive(Debug, Clone, Default)]
+pub struct LedgerEntries {
+    entries: BTreeMap<u64, Vec<LedgerEntry>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    pub date: NaiveDate,
+    /// Net amount per account and commodity, ordered by account.
+    pub postings: Vec<LedgerPosting>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerPosting {
+    pub account: String,
+    /// Commodity symbol, as printed in the journal (ex. "$").
+    pub commodity: String,
+    pub amount: f64,
+}
+
+impl LedgerEntries {
+    /// Transactions of processed records. Amounts are rounded to the standard
+    /// decimal places of their commodity, as printed by default, so they can
+    /// be compared with a journal (see `from_journal`).
+    pub fn from_records(records: &FinancialRecords) -> Self {
+        let mut entries = Self::default();
+        for tx in &records.transactions {
+            entries.push(
+                tx.spec_id,
+                tx.date,
+                tx.postings.iter().map(|p| LedgerPosting {
+                    account: p.account.ledger(),
+                    commodity: p.commodity.symbol().to_string(),
+                    amount: round(p.amount, p.commodity.decimal_places() as i32),
+                }),
+            );
+        }
+        entries
+    }
+
+    /// Transactions of a generated journal (ex. last month's output), as read
+    /// by hledger. Transactions not generated from a spec (ex. closing
+    /// entries) are skipped.
+    #[cfg(feature = "hledger")]
+    pub fn from_journal(ledger: &LedgerSource) -> Result<Self, ServerError> {
+        let mut transactions: BTreeMap<usize, (TransactionSpecId, NaiveDate, Vec<LedgerPosting>)> =
+            BTreeMap::new();
+        for posting in hledger_postings(ledger)? {
+            transactions
+                .entry(posting.transaction_index)
+                .or_insert_with(|| (posting.spec_id, posting.date, Vec::new()))
+                .2
+                .push(LedgerPosting {
+                    account: posting.account,
+                    commodity: posting.commodity,
+                    amount: posting.amount,
+                });
+        }
+        let mut entries = Self::default();
+        for (spec_id, date, postings) in transactions.into_values() {
+            entries.push(spec_id, date, postings);
+        }
+        Ok(entries)
+    }
+
+    /// Postings to the same account and commodity are merged, so the entry
+    /// doesn't depend on how the postings were split.
+    fn push(
+        &mut self,
+        spec_id: TransactionSpecId,
+        date: NaiveDate,
+        postings: impl IntoIterator<Item = LedgerPosting>,
+    ) {
+        let mut net: BTreeMap<(String, String), f64> = BTreeMap::new();
+        for posting in postings {
+            *net.entry((posting.account, posting.commodity)).or_default() += posting.amount;
+        }
+        self.entries
+            .entry(spec_id.0)
+            .or_default()
+            .push(LedgerEntry {
+                date,
+                postings: net
+                    .into_iter()
+                    .map(|((account, commodity), amount)| LedgerPosting {
+                        account,
+                        commodity,
+                        amount,
+                    })
+                    .collect(),
+            });
+    }
+}
+
+/// Compares two ledgers (ex. this month's regenerated records against last
+/// month's journal) transaction by transaction, for each spec, to review what
+/// a regeneration changed before committing its output.
+pub struct LedgerDiff<'a> {
+    before: &'a LedgerEntries,
+    after: &'a LedgerEntries,
+}
+
+#[derive(Debug, Clone)]
+pub struct LedgerDiffReport {
+    /// Specs with changed transactions, in spec ID order.
+    pub specs: Vec<SpecChanges>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpecChanges {
+    pub spec_id: TransactionSpecId,
+    /// Ordered by date.
+    pub changes: Vec<LedgerChange>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LedgerChange {
+    Added(LedgerEntry),
+    Removed(LedgerEntry),
+    /// Same date, different postings.
+    AmountChanged {
+        date: NaiveDate,
+        before: Vec<LedgerPosting>,
+        after: Vec<LedgerPosting>,
+    },
+    /// Same postings, different date.
+    DateChanged {
+        before: NaiveDate,
+        after: NaiveDate,
+        postings: Vec<LedgerPosting>,
+    },
+}
+
+impl<'a> LedgerDiff<'a> {
+    pub fn new(before: &'a LedgerEntries, after: &'a LedgerEntries) -> Self {
+        Self { before, after }
+    }
+
+    /// Transactions identical in both ledgers are matched first. The
+    /// remaining ones are matched by postings (date changes), then by date
+    /// (amount changes), and anything left over is added or removed.
+    pub fn generate(&self) -> LedgerDiffReport {
+        let mut spec_ids: Vec<u64> = self
+            .before
+            .entries
+            .keys()
+            .chain(self.after.entries.keys())
+            .copied()
+            .collect();
+        spec_ids.sort();
+        spec_ids.dedup();
+
+        let specs = spec_ids
+            .into_iter()
+            .filter_map(|spec_id| {
+                let entries = |ledger: &LedgerEntries| {
+                    ledger.entries.get(&spec_id).cloned().unwrap_or_default()
+                };
+                let changes = diff_entries(entries(self.before), entries(self.after));
+                (!changes.is_empty()).then_some(SpecChanges {
+                    spec_id: TransactionSpecId(spec_id),
+                    changes,
+                })
+            })
+            .collect();
+        LedgerDiffReport { specs }
+    }
+}
+
+impl LedgerDiffReport {
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty()
+    }
+
+    /// Plain text report listing every change.
+    pub fn to_text(&self) -> String {
+        let mut output = format!(
+            "Ledger diff: {} change(s) in {} spec(s).\n",
+            self.specs.iter().map(|s| s.changes.len()).sum::<usize>(),
+            self.specs.len()
+        );
+        for spec in &self.specs {
+            output.push_str(&format!("\nSpec {}\n", spec.spec_id));
+            for change in &spec.changes {
+                match change {
+                    LedgerChange::Added(entry) => {
+                        output.push_str(&format!("  Added {}\n", entry.date));
+                        push_postings(&mut output, &entry.postings);
+                    }
+                    LedgerChange::Removed(entry) => {
+                        output.push_str(&format!("  Removed {}\n", entry.date));
+                        push_postings(&mut output, &entry.postings);
+                    }
+                    LedgerChange::AmountChanged {
+                        date,
+                        before,
+                        after,
+                    } => {
+                        output.push_str(&format!("  Amount changed {}\n", date));
+                        for (account, commodity, before, after) in amount_changes(before, after) {
+                            output.push_str(&format!(
+                                "    {}  {} {} -> {} {}\n",
+                                account, before, commodity, after, commodity
+                            ));
+                        }
+                    }
+                    LedgerChange::DateChanged {
+                        before,
+                        after,
+                        postings,
+                    } => {
+                        output.push_str(&format!("  Date changed {} -> {}\n", before, after));
+                        push_postings(&mut output, postings);
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+impl LedgerChange {
+    fn date(&self) -> NaiveDate {
+        match self {
+            LedgerChange::Added(entry) | LedgerChange::Removed(entry) => entry.date,
+            LedgerChange::AmountChanged { date, .. } => *date,
+            LedgerChange::DateChanged { after, .. } => *after,
+        }
+    }
+}
+
+fn diff_entries(mut before: Vec<LedgerEntry>, mut after: Vec<LedgerEntry>) -> Vec<LedgerChange> {
+    // Identical transactions.
+    before.retain(|b| match after.iter().position(|a| same(a, b)) {
+        Some(i) => {
+            after.remove(i);
+            false
+        }
+        None => true,
+    });
+
+    let mut changes = Vec::new();
+    // Rescheduled transactions.
+    before.retain(|b| {
+        match after
+            .iter()
+            .position(|a| same_postings(&a.postings, &b.postings))
+        {
+            Some(i) => {
+                let a = after.remove(i);
+                changes.push(LedgerChange::DateChanged {
+                    before: b.date,
+                    after: a.date,
+                    postings: a.postings,
+                });
+                false
+            }
+            None => true,
+        }
+    });
+    // Re-estimated transactions.
+    before.retain(|b| match after.iter().position(|a| a.date == b.date) {
+        Some(i) => {
+            let a = after.remove(i);
+            changes.push(LedgerChange::AmountChanged {
+                date: b.date,
+                before: b.postings.clone(),
+                after: a.postings,
+            });
+            false
+        }
+        None => true,
+    });
+    changes.extend(before.into_iter().map(LedgerChange::Removed));
+    changes.extend(after.into_iter().map(LedgerChange::Added));
+    changes.sort_by_key(LedgerChange::date);
+    changes
+}
+
+fn same(a: &LedgerEntry, b: &LedgerEntry) -> bool {
+    a.date == b.date && same_postings(&a.postings, &b.postings)
+}
+
+/// Amounts are compared with a tolerance, since those read from a journal were
+/// parsed from their printed value.
+fn same_postings(a: &[LedgerPosting], b: &[LedgerPosting]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            a.account == b.account
+                && a.commodity == b.commodity
+                && (a.amount - b.amount).abs() < 1e-6
+        })
+}
+
+/// Before and after amounts of each account and commodity whose amount
+/// changed (zero if only posted to on one side).
+fn amount_changes<'a>(
+    before: &'a [LedgerPosting],
+    after: &'a [LedgerPosting],
+) -> Vec<(&'a str, &'a str, f64, f64)> {
+    let mut amounts: BTreeMap<(&str, &str), (f64, f64)> = BTreeMap::new();
+    for p in before {
+        amounts.entry((&p.account, &p.commodity)).or_default().0 += p.amount;
+    }
+    for p in after {
+        amounts.entry((&p.account, &p.commodity)).or_default().1 += p.amount;
+    }
+    amounts
+        .into_iter()
+        .filter(|(_, (before, after))| (before - after).abs() >= 1e-6)
+        .map(|((account, commodity), (before, after))| (account, commodity, before, after))
+        .collect()
+}
+
+fn push_postings(output: &mut String, postings: &[LedgerPosting]) {
+    for p in postings {
+        output.push_str(&format!(
+            "    {}  {} {}\n",
+            p.account, p.amount, p.commodity
+        ));
+    }
+}
+
+fn round(amount: f64, decimal_places: i32) -> f64 {
+    let factor = 10_f64.powi(decimal_places);
+    // Avoid '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
@@ -0,0 +1,44 @@
+use chrono::{Datelike, Months, NaiveDate};
+
+/// Length of the periods a report groups amounts into. Periods are aligned to
+/// calendar months, quarters or years.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportInterval {
+    #[default]
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl ReportInterval {
+    /// First day of the period containing `date`.
+    pub fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        let month = match self {
+            ReportInterval::Monthly => date.month(),
+            ReportInterval::Quarterly => (date.month0() / 3) * 3 + 1,
+            ReportInterval::Yearly => 1,
+        };
+        NaiveDate::from_ymd_opt(date.year(), month, 1).expect("day 1 should always be valid")
+    }
+
+    /// Last day (inclusive) of the period containing `date`.
+    pub fn period_end(&self, date: NaiveDate) -> NaiveDate {
+        let months = match self {
+            ReportInterval::Monthly => 1,
+            ReportInterval::Quarterly => 3,
+            ReportInterval::Yearly => 12,
+        };
+        (self.period_start(date) + Months::new(months))
+            .pred_opt()
+            .expect("date should have a predecessor")
+    }
+
+    /// Short period name, ex. "2024-03", "2024Q1" or "2024".
+    pub fn period_name(&self, date: NaiveDate) -> String {
+        match self {
+            ReportInterval::Monthly => format!("{}-{:02}", date.year(), date.month()),
+            ReportInterval::Quarterly => format!("{}Q{}", date.year(), date.month0() / 3 + 1),
+            ReportInterval::Yearly => format!("{}", date.year()),
+        }
+    }
+}
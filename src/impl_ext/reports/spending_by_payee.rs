@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::entities::{Account, Commodity, FinancialRecords};
+use crate::presentation::utils::format_amount;
+
+use super::report_interval::ReportInterval;
+
+/// Totals the expenses recognised for each payee (as given in the spec
+/// labels) per period.
+///
+/// Amounts are net, so refunds and reversals reduce a payee's total.
+pub struct SpendingByPayee<'a> {
+    records: &'a FinancialRecords,
+    interval: ReportInterval,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpendingByPayeeReport {
+    pub interval: ReportInterval,
+    /// Periods with any expenses, in chronological order.
+    pub periods: Vec<PayeeSpendingPeriod>,
+    /// Totals over all periods, ordered by amount spent (largest first).
+    pub totals: Vec<PayeeSpending>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PayeeSpendingPeriod {
+    pub start: NaiveDate,
+    /// Inclusive.
+    pub end: NaiveDate,
+    /// Ordered by amount spent (largest first).
+    pub payees: Vec<PayeeSpending>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PayeeSpending {
+    /// Empty if the transactions had no payee.
+    pub payee: String,
+    pub commodity: Commodity,
+    pub amount: f64,
+    /// Number of transactions with expense postings for this payee.
+    pub transactions: usize,
+}
+
+/// Payee and currency code.
+type PayeeKey = (String, &'static str);
+
+impl<'a> SpendingByPayee<'a> {
+    pub fn new(records: &'a FinancialRecords, interval: ReportInterval) -> Self {
+        Self {
+            records,
+            interval,
+            from: None,
+            to: None,
+        }
+    }
+
+    /// Only include transactions dated within [`from`, `to`].
+    pub fn between(mut self, from: NaiveDate, to: NaiveDate) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    pub fn generate(&self) -> SpendingByPayeeReport {
+        let mut periods: BTreeMap<NaiveDate, BTreeMap<PayeeKey, PayeeSpending>> = BTreeMap::new();
+        let mut totals: BTreeMap<PayeeKey, PayeeSpending> = BTreeMap::new();
+        for transaction in &self.records.transactions {
+            if self.from.is_some_and(|from| transaction.date < from)
+                || self.to.is_some_and(|to| transaction.date > to)
+            {
+                continue;
+            }
+            let payee = self
+                .records
+                .label_lookup
+                .get(&transaction.spec_id)
+                .map(|label| label.payee.to_string())
+                .unwrap_or_default();
+            let period = periods
+                .entry(self.interval.period_start(transaction.date))
+                .or_default();
+
+            // Per currency, so a transaction is counted once per payee.
+            let mut amounts: BTreeMap<&'static str, (Commodity, f64)> = BTreeMap::new();
+            for posting in &transaction.postings {
+                if matches!(posting.account, Account::Expense(_)) {
+                    amounts
+                        .entry(posting.commodity.code())
+                        .or_insert((posting.commodity, 0.0))
+                        .1 += posting.amount;
+                }
+            }
+            for (code, (commodity, amount)) in amounts {
+                for spending in [&mut *period, &mut totals] {
+                    let entry =
+                        spending
+                            .entry((payee.clone(), code))
+                            .or_insert_with(|| PayeeSpending {
+                                payee: payee.clone(),
+                                commodity,
+                                amount: 0.0,
+                                transactions: 0,
+                            });
+                    entry.amount += amount;
+                    entry.transactions += 1;
+                }
+            }
+        }
+
+        SpendingByPayeeReport {
+            interval: self.interval,
+            periods: periods
+                .into_iter()
+                .filter(|(_, payees)| !payees.is_empty())
+                .map(|(start, payees)| PayeeSpendingPeriod {
+                    start,
+                    end: self.interval.period_end(start),
+                    payees: sorted(payees),
+                })
+                .collect(),
+            totals: sorted(totals),
+        }
+    }
+}
+
+impl SpendingByPayeeReport {
+    /// The `n` payees with the highest total spending in `currency`.
+    pub fn top(&self, commodity: impl Into<Commodity>, n: usize) -> Vec<&PayeeSpending> {
+        let commodity = commodity.into();
+        self.totals
+            .iter()
+            .filter(|spending| spending.commodity == commodity)
+            .take(n)
+            .collect()
+    }
+
+    /// Plain text report listing each period's payees, followed by the totals.
+    pub fn to_text(&self) -> String {
+        let mut output = String::from("Spending by payee.\n");
+        let push_payees = |output: &mut String, payees: &[PayeeSpending]| {
+            for spending in payees {
+                output.push_str(&format!(
+                    "  {}  {} ({} transaction(s))\n",
+                    if spending.payee.is_empty() {
+                        "(no payee)"
+                    } else {
+                        &spending.payee
+                    },
+                    format_amount(spending.amount, spending.commodity, false),
+                    spending.transactions,
+                ));
+            }
+        };
+        for period in &self.periods {
+            output.push_str(&format!(
+                "\n{} ({} to {})\n",
+                self.interval.period_name(period.start),
+                period.start,
+                period.end
+            ));
+            push_payees(&mut output, &period.payees);
+        }
+        output.push_str("\nTotal\n");
+        push_payees(&mut output, &self.totals);
+        output
+    }
+}
+
+fn sorted(payees: BTreeMap<PayeeKey, PayeeSpending>) -> Vec<PayeeSpending> {
+    let mut payees: Vec<PayeeSpending> = payees
+        .into_values()
+        .map(|mut spending| {
+            spending.amount = round(spending.amount, spending.commodity);
+            spending
+        })
+        .collect();
+    // Stable, so ties stay ordered by payee.
+    payees.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+    payees
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use fractic_server_error::{CriticalError, ServerError};
+
+use crate::entities::{Account, Commodity, FinancialRecords, TransactionSpecId};
+use crate::presentation::utils::format_amount;
+
+/// Outstanding reimbursable balances (ex. expenses paid personally by a
+/// founder or employee) per entity, with the transactions making them up and
+/// how long each has been outstanding.
+///
+/// Based on the entries still unreimbursed after processing all specs, so
+/// entries reimbursed after `as_of` are not included.
+pub struct UnreimbursedBalances<'a> {
+    records: &'a FinancialRecords,
+    as_of: NaiveDate,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnreimbursedBalancesReport {
+    pub as_of: NaiveDate,
+    /// One entry per entity and currency, ordered by account.
+    pub balances: Vec<UnreimbursedBalance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnreimbursedBalance {
+    /// Liability account tracking what is owed to the entity.
+    pub account: Account,
+    pub commodity: Commodity,
+    pub amount: f64,
+    /// Oldest first.
+    pub transactions: Vec<UnreimbursedTransaction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnreimbursedTransaction {
+    pub spec_id: TransactionSpecId,
+    pub date: NaiveDate,
+    /// Empty if the spec had no payee.
+    pub payee: String,
+    /// Accounts the entity paid for (ex. expenses), in posting order.
+    pub accounts: Vec<Account>,
+    /// Unpaid share, if the transaction was partially reimbursed.
+    pub amount: f64,
+    /// Days outstanding as of the report date.
+    pub age_days: i64,
+}
+
+/// Account and currency code.
+type BalanceKey = (String, &'static str);
+
+impl<'a> UnreimbursedBalances<'a> {
+    pub fn new(records: &'a FinancialRecords, as_of: NaiveDate) -> Self {
+        Self { records, as_of }
+    }
+
+    pub fn generate(&self) -> UnreimbursedBalancesReport {
+        let mut balances: BTreeMap<BalanceKey, UnreimbursedBalance> = BTreeMap::new();
+        for (account, entry) in &self.records.unreimbursed_entries {
+            if entry.transaction_date > self.as_of {
+                continue;
+            }
+            let account: Account = account.clone().into();
+            // Entries are tracked in the currency of the reimbursable posting,
+            // which matches the credit postings.
+            let Some(commodity) = entry.credit_postings.first().map(|p| p.commodity) else {
+                continue;
+            };
+            let balance = balances
+                .entry((account.ledger(), commodity.code()))
+                .or_insert_with(|| UnreimbursedBalance {
+                    account,
+                    commodity,
+                    amount: 0.0,
+                    transactions: Vec::new(),
+                });
+            balance.amount += entry.total_amount;
+            balance.transactions.push(UnreimbursedTransaction {
+                spec_id: entry.spec_id,
+                date: entry.transaction_date,
+                payee: self
+                    .records
+                    .label_lookup
+                    .get(&entry.spec_id)
+                    .map(|label| label.payee.to_string())
+                    .unwrap_or_default(),
+                accounts: entry
+                    .credit_postings
+                    .iter()
+                    .map(|p| p.account.clone())
+                    .collect(),
+                amount: round(entry.total_amount, commodity),
+                age_days: (self.as_of - entry.transaction_date).num_days(),
+            });
+        }
+
+        UnreimbursedBalancesReport {
+            as_of: self.as_of,
+            balances: balances
+                .into_values()
+                .map(|mut balance| {
+                    balance.amount = round(balance.amount, balance.commodity);
+                    // Stable, so entries from the same day keep their order.
+                    balance.transactions.sort_by_key(|t| t.date);
+                    balance
+                })
+                .collect(),
+        }
+    }
+}
+
+impl UnreimbursedBalancesReport {
+    /// Plain text report listing each entity's balance and its transactions.
+    pub fn to_text(&self) -> String {
+        let mut output = format!("Unreimbursed balances as of {}.\n", self.as_of);
+        for balance in &self.balances {
+            output.push_str(&format!(
+                "\n{}  {}\n",
+                balance.account.ledger(),
+                format_amount(balance.amount, balance.commodity, false),
+            ));
+            for transaction in &balance.transactions {
+                output.push_str(&format!(
+                    "  {} ({}) {}  {}  {} day(s)\n",
+                    transaction.date,
+                    transaction.spec_id,
+                    if transaction.payee.is_empty() {
+                        "(no payee)"
+                    } else {
+                        &transaction.payee
+                    },
+                    format_amount(transaction.amount, balance.commodity, false),
+                    transaction.age_days,
+                ));
+            }
+        }
+        output
+    }
+
+    /// One row per transaction.
+    pub fn to_csv(&self) -> Result<String, ServerError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let to_error = |e: csv::Error| {
+            CriticalError::with_debug("failed to write unreimbursed balances as CSV", &e)
+        };
+        writer
+            .write_record([
+                "account", "currency", "spec_id", "date", "payee", "accounts", "amount", "age_days",
+            ])
+            .map_err(to_error)?;
+        for balance in &self.balances {
+            for transaction in &balance.transactions {
+                writer
+                    .write_record([
+                        balance.account.ledger(),
+                        balance.commodity.code().to_string(),
+                        transaction.spec_id.to_string(),
+                        transaction.date.to_string(),
+                        transaction.payee.clone(),
+                        transaction
+                            .accounts
+                            .iter()
+                            .map(|a| a.ledger())
+                            .collect::<Vec<_>>()
+                            .join(";"),
+                        transaction.amount.to_string(),
+                        transaction.age_days.to_string(),
+                    ])
+                    .map_err(to_error)?;
+            }
+        }
+        let bytes = writer.into_inner().map_err(|e| {
+            CriticalError::with_debug(
+                "failed to write unreimbursed balances as CSV",
+                &e.to_string(),
+            )
+        })?;
+        String::from_utf8(bytes).map_err(|e| {
+            CriticalError::with_debug("failed to write unreimbursed balances as CSV", &e)
+        })
+    }
+}
+
+fn round(amount: f64, commodity: Commodity) -> f64 {
+    let factor = 10_f64.powi(commodity.decimal_places() as i32);
+    // Avoid reporting '-0.00'.
+    (amount * factor).round() / factor + 0.0
+}
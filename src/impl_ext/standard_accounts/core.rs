@@ -1,8 +1,10 @@
 use crate::entities::{
-    asset, equity_tl, expense, expense_tl, income_tl, liability, AssetAccount, AssetClassification,
-    EquityAccount, EquityClassification, ExpenseAccount, ExpenseClassification, IncomeAccount,
-    IncomeClassification, LiabilityAccount, LiabilityClassification,
+    asset, asset_tl, equity, equity_tl, expense, expense_tl, income, income_tl, liability,
+    liability_tl, AssetAccount, AssetClassification, EquityAccount, EquityClassification,
+    ExpenseAccount, ExpenseClassification, IncomeAccount, IncomeClassification, LiabilityAccount,
+    LiabilityClassification,
 };
+use chrono::{Months, NaiveDate};
 use std::sync::LazyLock;
 
 // Equity-related.
@@ -37,9 +39,26 @@ pub static SHARE_ISSUANCE_COSTS_PAYABLE: LazyLock<LiabilityAccount> = LazyLock::
     )
 });
 
+// For recording treasury shares.
+pub static TREASURY_STOCK: LazyLock<EquityAccount> =
+    LazyLock::new(|| equity_tl(EquityClassification::TreasuryStock));
+pub static TREASURY_SHARE_SURPLUS: LazyLock<EquityAccount> = LazyLock::new(|| {
+    equity(
+        "TreasuryShareSurplus",
+        EquityClassification::ContributedSurplus,
+    )
+});
+
 // FX-related.
 // ----------------------------------------------------------------------------
 
+pub static FOREIGN_CURRENCY_TRANSLATION_RESERVE: LazyLock<EquityAccount> = LazyLock::new(|| {
+    equity(
+        "ForeignCurrencyTranslationReserve",
+        EquityClassification::AccumulatedOtherComprehensiveIncome,
+    )
+});
+
 pub static REALIZED_FX_GAIN: LazyLock<IncomeAccount> =
     LazyLock::new(|| income_tl(IncomeClassification::RealizedFxGain));
 pub static REALIZED_FX_LOSS: LazyLock<ExpenseAccount> =
@@ -53,15 +72,286 @@ pub static FOREIGN_TRANSACTION_FEE: LazyLock<ExpenseAccount> = LazyLock::new(||
     )
 });
 
+// Inflation-related (IAS 29).
+// ----------------------------------------------------------------------------
+
+pub static MONETARY_GAIN: LazyLock<IncomeAccount> = LazyLock::new(|| {
+    income(
+        "GainOnNetMonetaryPosition",
+        IncomeClassification::OtherNonCoreFinancialIncome,
+    )
+});
+pub static MONETARY_LOSS: LazyLock<ExpenseAccount> = LazyLock::new(|| {
+    expense(
+        "LossOnNetMonetaryPosition",
+        ExpenseClassification::OtherNonCoreFinancialExpense,
+    )
+});
+
+// Bank-related.
+// ----------------------------------------------------------------------------
+
+pub static BANK_INTEREST: LazyLock<IncomeAccount> =
+    LazyLock::new(|| income("BankInterest", IncomeClassification::NonCoreInterestIncome));
+pub static INTEREST_RECEIVABLE: LazyLock<AssetAccount> = LazyLock::new(|| {
+    asset(
+        "InterestReceivable",
+        AssetClassification::OtherCurrentAssets,
+    )
+});
+
+// Deposit-related.
+// ----------------------------------------------------------------------------
+
+pub static SHORT_TERM_SECURITY_DEPOSITS: LazyLock<AssetAccount> =
+    LazyLock::new(|| asset("SecurityDeposits", AssetClassification::ShortTermDeposits));
+pub static LONG_TERM_SECURITY_DEPOSITS: LazyLock<AssetAccount> =
+    LazyLock::new(|| asset("SecurityDeposits", AssetClassification::LongTermDeposits));
+
+/// Short-term if expected to be refunded within 12 months of the payment,
+/// otherwise (or if the refund date is unknown) long-term.
+pub fn security_deposits_for(
+    payment_date: NaiveDate,
+    refund_date: Option<NaiveDate>,
+) -> AssetAccount {
+    let short_term = refund_date.is_some_and(|refund_date| {
+        payment_date
+            .checked_add_months(Months::new(12))
+            .is_some_and(|horizon| refund_date <= horizon)
+    });
+    if short_term {
+        SHORT_TERM_SECURITY_DEPOSITS.clone()
+    } else {
+        LONG_TERM_SECURITY_DEPOSITS.clone()
+    }
+}
+
+// Borrowing-related.
+// ----------------------------------------------------------------------------
+
+pub static CURRENT_BORROWINGS: LazyLock<LiabilityAccount> =
+    LazyLock::new(|| liability("CurrentBorrowings", LiabilityClassification::ShortTermDebt));
+pub static NON_CURRENT_BORROWINGS: LazyLock<LiabilityAccount> = LazyLock::new(|| {
+    liability(
+        "NonCurrentBorrowings",
+        LiabilityClassification::LongTermDebt,
+    )
+});
+pub static INTEREST_ON_BORROWINGS: LazyLock<ExpenseAccount> = LazyLock::new(|| {
+    expense(
+        "InterestOnBorrowings",
+        // Interest on loans is a finance cost, unless lending is the core
+        // business.
+        ExpenseClassification::NonCoreInterestExpense,
+    )
+});
+
+// Receivable-related.
+// ----------------------------------------------------------------------------
+
+pub static ALLOWANCE_FOR_DOUBTFUL_DEBTS: LazyLock<AssetAccount> = LazyLock::new(|| {
+    asset(
+        "AllowanceForDoubtfulDebts",
+        // Contra-asset, kept under receivables so they are presented net.
+        AssetClassification::AccountsReceivable,
+    )
+});
+pub static BAD_DEBTS: LazyLock<ExpenseAccount> = LazyLock::new(|| {
+    expense(
+        "BadDebts",
+        // Credit losses on trade receivables are an operating expense.
+        ExpenseClassification::GeneralAdministrativeExpenses,
+    )
+});
+
+/// Sub-account of `ALLOWANCE_FOR_DOUBTFUL_DEBTS` for the receivable account
+/// (ex. "AllowanceForDoubtfulDebts:Consulting"), or the account itself for
+/// the top-level receivables account.
+pub fn allowance_for_doubtful_debts_for(receivable: &AssetAccount) -> AssetAccount {
+    match &receivable.0 {
+        Some(name) => asset(
+            format!("AllowanceForDoubtfulDebts:{}", name),
+            AssetClassification::AccountsReceivable,
+        ),
+        None => ALLOWANCE_FOR_DOUBTFUL_DEBTS.clone(),
+    }
+}
+
+// Grant-related.
+// ----------------------------------------------------------------------------
+
+pub static GRANTS_RECEIVABLE: LazyLock<AssetAccount> =
+    LazyLock::new(|| asset("GrantsReceivable", AssetClassification::OtherCurrentAssets));
+pub static DEFERRED_GRANT_INCOME: LazyLock<LiabilityAccount> = LazyLock::new(|| {
+    liability(
+        "DeferredGrantIncome",
+        LiabilityClassification::DeferredRevenue,
+    )
+});
+pub static GRANT_INCOME: LazyLock<IncomeAccount> = LazyLock::new(|| {
+    income(
+        "GovernmentGrants",
+        IncomeClassification::OtherNonOperatingIncome,
+    )
+});
+
+/// Contra-asset the grants deducted from the asset are held in until released
+/// (ex. "GovernmentGrants:Van"), with the same classification, so they are
+/// netted against its cost for presentation.
+pub fn deducted_grants_for(asset_account: &AssetAccount) -> AssetAccount {
+    match &asset_account.0 {
+        Some(name) => asset(format!("GovernmentGrants:{}", name), asset_account.1),
+        None => asset("GovernmentGrants", asset_account.1),
+    }
+}
+
+// Intercompany-related.
+// ----------------------------------------------------------------------------
+
+pub static DUE_FROM_GROUP_ENTITIES: LazyLock<AssetAccount> = LazyLock::new(|| {
+    asset(
+        "DueFromGroupEntities",
+        AssetClassification::OtherCurrentAssets,
+    )
+});
+pub static DUE_TO_GROUP_ENTITIES: LazyLock<LiabilityAccount> = LazyLock::new(|| {
+    liability(
+        "DueToGroupEntities",
+        LiabilityClassification::OtherCurrentLiabilities,
+    )
+});
+
+/// Sub-account of `DUE_FROM_GROUP_ENTITIES` for the group entity (ex.
+/// "DueFromGroupEntities:Subsidiary").
+pub fn due_from_group_entity(entity: &str) -> AssetAccount {
+    asset(
+        format!("DueFromGroupEntities:{}", entity),
+        AssetClassification::OtherCurrentAssets,
+    )
+}
+
+/// Sub-account of `DUE_TO_GROUP_ENTITIES` for the group entity (ex.
+/// "DueToGroupEntities:Parent").
+pub fn due_to_group_entity(entity: &str) -> LiabilityAccount {
+    liability(
+        format!("DueToGroupEntities:{}", entity),
+        LiabilityClassification::OtherCurrentLiabilities,
+    )
+}
+
+// Disposal-related.
+// ----------------------------------------------------------------------------
+
+pub static GAIN_ON_SALE_OF_ASSETS: LazyLock<IncomeAccount> =
+    LazyLock::new(|| income_tl(IncomeClassification::GainOnSaleOfAssets));
+pub static LOSS_ON_SALE_OF_ASSETS: LazyLock<ExpenseAccount> =
+    LazyLock::new(|| expense_tl(ExpenseClassification::LossOnSaleOfAssets));
+
+// Impairment-related.
+// ----------------------------------------------------------------------------
+
+pub static IMPAIRMENT_LOSS: LazyLock<ExpenseAccount> = LazyLock::new(|| {
+    expense(
+        "ImpairmentLoss",
+        // Write-downs don't use cash, so are added back on the cash flow
+        // statement.
+        ExpenseClassification::OtherNonOperatingNonCashExpense,
+    )
+});
+
+// Investment-related.
+// ----------------------------------------------------------------------------
+
+pub static UNREALIZED_INVESTMENT_GAIN: LazyLock<IncomeAccount> = LazyLock::new(|| {
+    income(
+        "UnrealizedGainOnInvestments",
+        IncomeClassification::OtherNonCoreFinancialIncome,
+    )
+});
+pub static UNREALIZED_INVESTMENT_LOSS: LazyLock<ExpenseAccount> = LazyLock::new(|| {
+    expense(
+        "UnrealizedLossOnInvestments",
+        ExpenseClassification::OtherNonCoreFinancialExpense,
+    )
+});
+pub static FAIR_VALUE_RESERVE: LazyLock<EquityAccount> = LazyLock::new(|| {
+    equity(
+        "FairValueReserve",
+        EquityClassification::AccumulatedOtherComprehensiveIncome,
+    )
+});
+
+// Credit card-related.
+// ----------------------------------------------------------------------------
+
+pub static CARD_INTEREST_AND_FEES: LazyLock<ExpenseAccount> = LazyLock::new(|| {
+    expense(
+        "CardInterestAndFees",
+        // Interest on revolving card balances is a cost of (non-core)
+        // borrowing.
+        ExpenseClassification::NonCoreInterestExpense,
+    )
+});
+
 // Tax-related.
 // ----------------------------------------------------------------------------
 
+pub static DEFERRED_TAX_ASSET: LazyLock<AssetAccount> =
+    LazyLock::new(|| asset_tl(AssetClassification::DeferredIncomeTax));
+pub static DEFERRED_TAX_LIABILITY: LazyLock<LiabilityAccount> =
+    LazyLock::new(|| liability_tl(LiabilityClassification::DeferredIncomeTax));
+pub static DEFERRED_TAX_EXPENSE: LazyLock<ExpenseAccount> = LazyLock::new(|| {
+    expense(
+        "DeferredTaxExpense",
+        ExpenseClassification::IncomeTaxExpense,
+    )
+});
+pub static CURRENT_TAX_EXPENSE: LazyLock<ExpenseAccount> =
+    LazyLock::new(|| expense("CurrentTaxExpense", ExpenseClassification::IncomeTaxExpense));
+pub static INCOME_TAX_TRUE_UP: LazyLock<ExpenseAccount> =
+    LazyLock::new(|| expense("IncomeTaxTrueUp", ExpenseClassification::IncomeTaxExpense));
+pub static INCOME_TAX_PAYABLE: LazyLock<LiabilityAccount> = LazyLock::new(|| {
+    liability(
+        "IncomeTaxPayable",
+        LiabilityClassification::OtherCurrentLiabilities,
+    )
+});
 pub static FOREIGN_WITHHOLDING_TAX: LazyLock<ExpenseAccount> = LazyLock::new(|| {
     expense(
         "ForeignWithholdingTax",
         ExpenseClassification::OtherTaxExpense,
     )
 });
+pub static WITHHOLDING_TAX_PAYABLE: LazyLock<LiabilityAccount> = LazyLock::new(|| {
+    liability(
+        "WithholdingTaxPayable",
+        LiabilityClassification::OtherCurrentLiabilities,
+    )
+});
+pub static SOCIAL_INSURANCE_PAYABLE: LazyLock<LiabilityAccount> = LazyLock::new(|| {
+    liability(
+        "SocialInsurancePayable",
+        LiabilityClassification::OtherCurrentLiabilities,
+    )
+});
+pub static SOCIAL_INSURANCE_EXPENSE: LazyLock<ExpenseAccount> = LazyLock::new(|| {
+    expense(
+        "SocialInsurance",
+        // Employer contributions are an employee benefit cost.
+        ExpenseClassification::GeneralAdministrativeExpenses,
+    )
+});
+pub static CUSTOMS_DUTIES: LazyLock<ExpenseAccount> =
+    LazyLock::new(|| expense("CustomsDuties", ExpenseClassification::OtherTaxExpense));
+
+/// Sub-account of `INCOME_TAX_PAYABLE` for the fiscal year (ex.
+/// "IncomeTaxPayable:2024").
+pub fn income_tax_payable_for(fiscal_year: i32) -> LiabilityAccount {
+    liability(
+        format!("IncomeTaxPayable:{}", fiscal_year),
+        LiabilityClassification::OtherCurrentLiabilities,
+    )
+}
 
 // Miscelanious.
 // ----------------------------------------------------------------------------
@@ -73,3 +363,11 @@ pub static PAYMENT_FEES: LazyLock<ExpenseAccount> = LazyLock::new(|| {
         ExpenseClassification::GeneralAdministrativeExpenses,
     )
 });
+pub static SUSPENSE: LazyLock<AssetAccount> = LazyLock::new(|| {
+    asset(
+        "Suspense",
+        // Temporary holding account for entries that couldn't be classified,
+        // expected to be cleared before the statements are finalized.
+        AssetClassification::OtherCurrentAssets,
+    )
+});
@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{Account, SpecHook, SpecOutcome},
+    errors::UnknownStandardAccount,
+};
+
+use super::{
+    core::{
+        ALLOWANCE_FOR_DOUBTFUL_DEBTS, BAD_DEBTS, BANK_INTEREST, CARD_INTEREST_AND_FEES,
+        CURRENT_BORROWINGS, CURRENT_TAX_EXPENSE, CUSTOMS_DUTIES, DEFERRED_GRANT_INCOME,
+        DEFERRED_TAX_EXPENSE, DISCOUNT_ON_STOCK_ISSUANCE, DUE_FROM_GROUP_ENTITIES,
+        DUE_TO_GROUP_ENTITIES, FAIR_VALUE_RESERVE, FOREIGN_CURRENCY_TRANSLATION_RESERVE,
+        FOREIGN_TRANSACTION_FEE, FOREIGN_WITHHOLDING_TAX, GAIN_ON_SALE_OF_ASSETS,
+        GRANTS_RECEIVABLE, GRANT_INCOME, IMPAIRMENT_LOSS, INCOME_TAX_PAYABLE, INCOME_TAX_TRUE_UP,
+        INTEREST_ON_BORROWINGS, INTEREST_RECEIVABLE, LONG_TERM_SECURITY_DEPOSITS,
+        LOSS_ON_SALE_OF_ASSETS, MONETARY_GAIN, MONETARY_LOSS, NON_CURRENT_BORROWINGS, PAYMENT_FEES,
+        PREPAID_SHARE_ISSUANCE_COSTS, REALIZED_FX_GAIN, REALIZED_FX_LOSS, RETAINED_EARNINGS,
+        SHARE_ISSUANCE_COSTS_PAYABLE, SHORT_TERM_SECURITY_DEPOSITS, SOCIAL_INSURANCE_EXPENSE,
+        SOCIAL_INSURANCE_PAYABLE, SUSPENSE, TREASURY_SHARE_SURPLUS, TREASURY_STOCK,
+        UNPAID_SHARE_CAPITAL_AS_ASSET, UNPAID_SHARE_CAPITAL_AS_EQUITY, UNREALIZED_INVESTMENT_GAIN,
+        UNREALIZED_INVESTMENT_LOSS, WITHHOLDING_TAX_PAYABLE,
+    },
+    vat::{VAT_PAYABLE, VAT_PENDING_RECEIPT, VAT_RECEIVABLE, VAT_REFUND_GAIN, VAT_REFUND_LOSS},
+};
+
+pub(crate) fn standard_named_accounts() -> Vec<Account> {
+    vec![
+        RETAINED_EARNINGS.clone().into(),
+        UNPAID_SHARE_CAPITAL_AS_ASSET.clone().into(),
+        UNPAID_SHARE_CAPITAL_AS_EQUITY.clone().into(),
+        DISCOUNT_ON_STOCK_ISSUANCE.clone().into(),
+        TREASURY_STOCK.clone().into(),
+        TREASURY_SHARE_SURPLUS.clone().into(),
+        PREPAID_SHARE_ISSUANCE_COSTS.clone().into(),
+        SHARE_ISSUANCE_COSTS_PAYABLE.clone().into(),
+        REALIZED_FX_GAIN.clone().into(),
+        REALIZED_FX_LOSS.clone().into(),
+        GAIN_ON_SALE_OF_ASSETS.clone().into(),
+        LOSS_ON_SALE_OF_ASSETS.clone().into(),
+        IMPAIRMENT_LOSS.clone().into(),
+        FOREIGN_CURRENCY_TRANSLATION_RESERVE.clone().into(),
+        FOREIGN_TRANSACTION_FEE.clone().into(),
+        FOREIGN_WITHHOLDING_TAX.clone().into(),
+        WITHHOLDING_TAX_PAYABLE.clone().into(),
+        SOCIAL_INSURANCE_PAYABLE.clone().into(),
+        SOCIAL_INSURANCE_EXPENSE.clone().into(),
+        CUSTOMS_DUTIES.clone().into(),
+        DEFERRED_TAX_EXPENSE.clone().into(),
+        CURRENT_TAX_EXPENSE.clone().into(),
+        INCOME_TAX_TRUE_UP.clone().into(),
+        INCOME_TAX_PAYABLE.clone().into(),
+        PAYMENT_FEES.clone().into(),
+        ALLOWANCE_FOR_DOUBTFUL_DEBTS.clone().into(),
+        BAD_DEBTS.clone().into(),
+        GRANTS_RECEIVABLE.clone().into(),
+        DEFERRED_GRANT_INCOME.clone().into(),
+        GRANT_INCOME.clone().into(),
+        DUE_FROM_GROUP_ENTITIES.clone().into(),
+        DUE_TO_GROUP_ENTITIES.clone().into(),
+        CARD_INTEREST_AND_FEES.clone().into(),
+        BANK_INTEREST.clone().into(),
+        INTEREST_RECEIVABLE.clone().into(),
+        SHORT_TERM_SECURITY_DEPOSITS.clone().into(),
+        LONG_TERM_SECURITY_DEPOSITS.clone().into(),
+        CURRENT_BORROWINGS.clone().into(),
+        NON_CURRENT_BORROWINGS.clone().into(),
+        INTEREST_ON_BORROWINGS.clone().into(),
+        SUSPENSE.clone().into(),
+        UNREALIZED_INVESTMENT_GAIN.clone().into(),
+        UNREALIZED_INVESTMENT_LOSS.clone().into(),
+        FAIR_VALUE_RESERVE.clone().into(),
+        MONETARY_GAIN.clone().into(),
+        MONETARY_LOSS.clone().into(),
+        VAT_PENDING_RECEIPT.clone().into(),
+        VAT_RECEIVABLE.clone().into(),
+        VAT_PAYABLE.clone().into(),
+        VAT_REFUND_GAIN.clone().into(),
+        VAT_REFUND_LOSS.clone().into(),
+    ]
+}
+
+/// Spec hook renaming the standard named sub-accounts (ex. "PaymentFees" to
+/// "BankCharges"), for companies whose chart of accounts uses different
+/// names. Only the name is replaced; the classification is kept, so the
+/// statements are unaffected. Sub-accounts of a renamed account (ex.
+/// "VatPayable:2024H1") are renamed along with it.
+#[derive(Debug)]
+pub struct StandardAccountOverrides {
+    renames: Vec<(Account, Account)>,
+}
+
+impl StandardAccountOverrides {
+    /// Keys are the default sub-account names, values the replacement names.
+    pub fn new(overrides: &BTreeMap<String, String>) -> Result<Self, ServerError> {
+        let standard = standard_named_accounts();
+        let renames = overrides
+            .iter()
+            .map(|(from, to)| {
+                let account = standard
+                    .iter()
+                    .find(|a| a.name() == Some(from.as_str()))
+                    .ok_or_else(|| UnknownStandardAccount::new(from))?;
+                let mut renamed = account.clone();
+                *renamed.name_mut() = Some(to.clone());
+                Ok((account.clone(), renamed))
+            })
+            .collect::<Result<Vec<_>, ServerError>>()?;
+        Ok(Self { renames })
+    }
+
+    fn apply(&self, account: &mut Account) {
+        for (from, renamed) in &self.renames {
+            if from == account {
+                *account = renamed.clone();
+                return;
+            }
+            // Sub-accounts of the standard account (ex. the VAT filing period
+            // sub-accounts) are moved under the renamed account.
+            let Some(suffix) = account
+                .name()
+                .zip(from.name())
+                .and_then(|(name, parent)| name.strip_prefix(parent)?.strip_prefix(':'))
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let mut parent = account.clone();
+            *parent.name_mut() = from.name().map(Into::into);
+            if parent == *from {
+                *account = renamed.clone();
+                *account.name_mut() = renamed.name().map(|name| format!("{}:{}", name, suffix));
+                return;
+            }
+        }
+    }
+}
+
+impl SpecHook for StandardAccountOverrides {
+    fn after_spec(&self, outcome: &mut SpecOutcome) -> Result<(), ServerError> {
+        for tx in outcome
+            .transactions
+            .iter_mut()
+            .chain(outcome.ext_transactions.iter_mut())
+        {
+            for posting in tx.postings.iter_mut() {
+                self.apply(&mut posting.account);
+                if let Some(source_account) = posting.source_account.as_mut() {
+                    self.apply(source_account);
+                }
+            }
+        }
+        for assertion in outcome.ext_assertions.iter_mut() {
+            self.apply(&mut assertion.account);
+        }
+        Ok(())
+    }
+}
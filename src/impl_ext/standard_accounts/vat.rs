@@ -3,6 +3,7 @@ use crate::entities::{
     ExpenseClassification, IncomeAccount, IncomeClassification, LiabilityAccount,
     LiabilityClassification,
 };
+use chrono::{Datelike as _, NaiveDate};
 use std::sync::LazyLock;
 
 pub static VAT_PENDING_RECEIPT: LazyLock<AssetAccount> =
@@ -15,3 +16,30 @@ pub static VAT_REFUND_GAIN: LazyLock<IncomeAccount> =
     LazyLock::new(|| income_tl(IncomeClassification::VatRefundGain));
 pub static VAT_REFUND_LOSS: LazyLock<ExpenseAccount> =
     LazyLock::new(|| expense_tl(ExpenseClassification::VatRefundLoss));
+
+/// VAT filing period (과세기간) containing the date, as used to name the
+/// period sub-accounts of `VAT_RECEIVABLE` and `VAT_PAYABLE`: "H1" for the
+/// first period (January to June), "H2" for the second (July to December).
+/// Preliminary returns (예정신고) clear part of their half-year's period.
+pub fn vat_filing_period(date: NaiveDate) -> String {
+    format!("{}H{}", date.year(), if date.month() <= 6 { 1 } else { 2 })
+}
+
+/// Sub-account of `VAT_RECEIVABLE` for the filing period of the date (ex.
+/// "VatReceivable:2024H1"), so VAT from invoices of the next period doesn't
+/// affect the balance being cleared.
+pub fn vat_receivable_for(date: NaiveDate) -> AssetAccount {
+    asset(
+        format!("VatReceivable:{}", vat_filing_period(date)),
+        AssetClassification::AccountsReceivable,
+    )
+}
+
+/// Sub-account of `VAT_PAYABLE` for the filing period of the date (ex.
+/// "VatPayable:2024H1").
+pub fn vat_payable_for(date: NaiveDate) -> LiabilityAccount {
+    liability(
+        format!("VatPayable:{}", vat_filing_period(date)),
+        LiabilityClassification::AccountsPayable,
+    )
+}
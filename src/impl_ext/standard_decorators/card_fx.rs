@@ -1,15 +1,14 @@
-use std::{iter::once, path::PathBuf, str::FromStr as _, vec};
+use std::{iter::once, str::FromStr as _, sync::Arc, vec};
 
 use async_trait::async_trait;
 use chrono::NaiveDate;
-use fractic_currency_conversion::util::FxUtil;
 use fractic_server_error::ServerError;
 
 use crate::{
     data::models::iso_date_model::ISODateModel,
     entities::{
-        Annotation, CommodityHandler, DecoratedTransactionSpec, DecoratorLogic, Handlers,
-        Transaction, TransactionPosting,
+        Annotation, CommodityHandler, DecoratedTransactionSpec, DecoratorLogic, DiscrepancyPolicy,
+        FxProvider, Handlers, Transaction, TransactionPosting, TransactionStatus,
     },
     ext::standard_accounts::{FOREIGN_TRANSACTION_FEE, REALIZED_FX_GAIN, REALIZED_FX_LOSS},
 };
@@ -26,11 +25,18 @@ enum LogicType {
     },
 }
 
-#[derive(Debug)]
 pub struct StandardDecoratorCardFx {
     logic: LogicType,
-    currency_conversion_cache_dir: PathBuf,
-    currency_conversion_api_key: String,
+    fx_provider: Arc<dyn FxProvider>,
+    discrepancy_policy: DiscrepancyPolicy,
+}
+
+/// Default FX provider, backed by the Open Exchange Rates API (with a local
+/// cache directory).
+#[cfg(feature = "fx-api")]
+pub struct OpenExchangeRatesFxProvider {
+    cache_dir: std::path::PathBuf,
+    api_key: String,
 }
 
 //
@@ -57,19 +63,37 @@ impl StandardDecoratorCardFx {
     /// Since the card fees are not transparently known, we just consider them
     /// immaterial and fold them into the FX gain / loss experienced on the
     /// settlement date, which is generally fine under IFRS.
+    #[cfg(feature = "fx-api")]
     pub fn delayed_settle_unknown_fee(
         settle_date: &String,
         settle_amount: f64,
-        currency_conversion_cache_dir: impl Into<PathBuf>,
+        currency_conversion_cache_dir: impl Into<std::path::PathBuf>,
         currency_conversion_api_key: impl Into<String>,
+    ) -> Result<Self, ServerError> {
+        Self::delayed_settle_unknown_fee_with_provider(
+            settle_date,
+            settle_amount,
+            Arc::new(OpenExchangeRatesFxProvider::new(
+                currency_conversion_cache_dir,
+                currency_conversion_api_key,
+            )),
+        )
+    }
+
+    /// Same as `delayed_settle_unknown_fee`, but with a custom source of
+    /// exchange rates.
+    pub fn delayed_settle_unknown_fee_with_provider(
+        settle_date: &String,
+        settle_amount: f64,
+        fx_provider: Arc<dyn FxProvider>,
     ) -> Result<Self, ServerError> {
         Ok(Self {
             logic: LogicType::DelayedSettleUnknownFee {
                 settle_date: ISODateModel::from_str(settle_date)?.into(),
                 settle_amount,
             },
-            currency_conversion_cache_dir: currency_conversion_cache_dir.into(),
-            currency_conversion_api_key: currency_conversion_api_key.into(),
+            fx_provider,
+            discrepancy_policy: DiscrepancyPolicy::default(),
         })
     }
 
@@ -80,19 +104,45 @@ impl StandardDecoratorCardFx {
     ///
     /// In this case, the fee is recorded as a general administrative expense,
     /// and any remaining discrepancy as FX gain / loss.
+    #[cfg(feature = "fx-api")]
     pub fn immediate_with_fee(
         charged: f64,
         fee: f64,
-        currency_conversion_cache_dir: impl Into<PathBuf>,
+        currency_conversion_cache_dir: impl Into<std::path::PathBuf>,
         currency_conversion_api_key: impl Into<String>,
+    ) -> Result<Self, ServerError> {
+        Self::immediate_with_fee_with_provider(
+            charged,
+            fee,
+            Arc::new(OpenExchangeRatesFxProvider::new(
+                currency_conversion_cache_dir,
+                currency_conversion_api_key,
+            )),
+        )
+    }
+
+    /// Same as `immediate_with_fee`, but with a custom source of exchange
+    /// rates.
+    pub fn immediate_with_fee_with_provider(
+        charged: f64,
+        fee: f64,
+        fx_provider: Arc<dyn FxProvider>,
     ) -> Result<Self, ServerError> {
         Ok(Self {
             logic: LogicType::ImmediateWithFee { charged, fee },
-            currency_conversion_cache_dir: currency_conversion_cache_dir.into(),
-            currency_conversion_api_key: currency_conversion_api_key.into(),
+            fx_provider,
+            discrepancy_policy: DiscrepancyPolicy::default(),
         })
     }
 
+    /// When FX discrepancies are posted as a realized FX gain / loss. Smaller
+    /// discrepancies are absorbed into the recorded amount. By default, any
+    /// discrepancy that would display as a non-zero amount is posted.
+    pub fn with_discrepancy_policy(mut self, policy: DiscrepancyPolicy) -> Self {
+        self.discrepancy_policy = policy;
+        self
+    }
+
     // --
 
     async fn apply_delayed_settle_unknown_fee<H: Handlers>(
@@ -113,6 +163,9 @@ impl StandardDecoratorCardFx {
             commodity: source_commodity,
             backing_account,
             annotations,
+            intercompany,
+            dimensions,
+            status,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -125,15 +178,12 @@ impl StandardDecoratorCardFx {
 
         // Calculate the amount to record on payment date (amount converted at
         // the rate on that day).
-        let fx_util = FxUtil::using_open_exchange_rates_api(
-            &self.currency_conversion_api_key,
-            &self.currency_conversion_cache_dir,
-        )?;
-        let converted_amount = fx_util
+        let converted_amount = self
+            .fx_provider
             .convert(
                 payment_date,
-                source_commodity.iso_symbol(),
-                main_commodity.iso_symbol(),
+                &source_commodity.iso_symbol(),
+                &main_commodity.iso_symbol(),
                 source_amount,
             )
             .await?;
@@ -147,7 +197,16 @@ impl StandardDecoratorCardFx {
             // For expense, positive discrepancy is a loss.
             converted_amount.abs() - settle_amount.abs()
         };
-        let vat_transactions = if fx_discrepancy.abs() >= main_commodity.precision_cutoff()? {
+        // Discrepancies below the policy's threshold are absorbed into the
+        // recorded amount instead.
+        let absorbed =
+            self.discrepancy_policy
+                .absorbed(fx_discrepancy, converted_amount, &main_commodity)?;
+        let vat_transactions = if self.discrepancy_policy.should_post(
+            fx_discrepancy,
+            converted_amount,
+            &main_commodity,
+        )? {
             vec![Transaction {
                 spec_id: id.clone(),
                 date: settle_date,
@@ -167,7 +226,8 @@ impl StandardDecoratorCardFx {
                         main_commodity.currency()?,
                     ),
                 ],
-                comment: Some("Correct FX discrepancy".to_string()),
+                comment: Some("Correct FX discrepancy".into()),
+                status: TransactionStatus::Unmarked,
             }]
         } else {
             vec![]
@@ -185,10 +245,13 @@ impl StandardDecoratorCardFx {
             accounting_logic,
             payee,
             description,
-            amount: converted_amount,
+            amount: converted_amount + absorbed,
             commodity: main_commodity,
             backing_account,
             annotations: annotations.into_iter().chain(once(note)).collect(),
+            intercompany,
+            dimensions,
+            status,
             ext_transactions: ext_transactions
                 .into_iter()
                 .chain(vat_transactions)
@@ -216,6 +279,9 @@ impl StandardDecoratorCardFx {
             commodity: source_commodity,
             backing_account,
             annotations,
+            intercompany,
+            dimensions,
+            status,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -228,15 +294,12 @@ impl StandardDecoratorCardFx {
 
         // Calculate the amount to record on payment date (amount converted at
         // the rate on that day).
-        let fx_util = FxUtil::using_open_exchange_rates_api(
-            &self.currency_conversion_api_key,
-            &self.currency_conversion_cache_dir,
-        )?;
-        let converted_amount = fx_util
+        let converted_amount = self
+            .fx_provider
             .convert(
                 payment_date,
-                source_commodity.iso_symbol(),
-                main_commodity.iso_symbol(),
+                &source_commodity.iso_symbol(),
+                &main_commodity.iso_symbol(),
                 source_amount,
             )
             .await?;
@@ -255,9 +318,11 @@ impl StandardDecoratorCardFx {
                         FOREIGN_TRANSACTION_FEE.clone().into(),
                         fee,
                         main_commodity.currency()?,
-                    ),
+                    )
+                    .with_comment("issuer FX fee"),
                 ],
-                comment: Some("Foreign transaction fee".to_string()),
+                comment: Some("Foreign transaction fee".into()),
+                status: TransactionStatus::Unmarked,
             })
         } else {
             None
@@ -273,32 +338,41 @@ impl StandardDecoratorCardFx {
                 -abs
             }
         };
-        let fx_discrepency_transaction =
-            if fx_discrepency.abs() >= main_commodity.precision_cutoff()? {
-                Some(Transaction {
-                    spec_id: id.clone(),
-                    date: payment_date,
-                    postings: vec![
-                        TransactionPosting::new(
-                            backing_account.account().into(),
-                            fx_discrepency,
-                            main_commodity.currency()?,
-                        ),
-                        TransactionPosting::new(
-                            if fx_discrepency > 0.0 {
-                                REALIZED_FX_GAIN.clone().into()
-                            } else {
-                                REALIZED_FX_LOSS.clone().into()
-                            },
-                            -fx_discrepency,
-                            main_commodity.currency()?,
-                        ),
-                    ],
-                    comment: Some("Correct FX discrepancy".to_string()),
-                })
-            } else {
-                None
-            };
+        // Discrepancies below the policy's threshold are absorbed into the
+        // recorded amount instead.
+        let absorbed =
+            self.discrepancy_policy
+                .absorbed(fx_discrepency, converted_amount, &main_commodity)?;
+        let fx_discrepency_transaction = if self.discrepancy_policy.should_post(
+            fx_discrepency,
+            converted_amount,
+            &main_commodity,
+        )? {
+            Some(Transaction {
+                spec_id: id.clone(),
+                date: payment_date,
+                postings: vec![
+                    TransactionPosting::new(
+                        backing_account.account().into(),
+                        fx_discrepency,
+                        main_commodity.currency()?,
+                    ),
+                    TransactionPosting::new(
+                        if fx_discrepency > 0.0 {
+                            REALIZED_FX_GAIN.clone().into()
+                        } else {
+                            REALIZED_FX_LOSS.clone().into()
+                        },
+                        -fx_discrepency,
+                        main_commodity.currency()?,
+                    ),
+                ],
+                comment: Some("Correct FX discrepancy".into()),
+                status: TransactionStatus::Unmarked,
+            })
+        } else {
+            None
+        };
 
         // Tag this transaction, since the accounting logic deserves a note in
         // the financial records.
@@ -312,10 +386,13 @@ impl StandardDecoratorCardFx {
             accounting_logic,
             payee,
             description,
-            amount: converted_amount,
+            amount: converted_amount + absorbed,
             commodity: main_commodity,
             backing_account,
             annotations: annotations.into_iter().chain(once(note)).collect(),
+            intercompany,
+            dimensions,
+            status,
             ext_transactions: ext_transactions
                 .into_iter()
                 .chain(
@@ -330,7 +407,47 @@ impl StandardDecoratorCardFx {
     }
 }
 
-#[async_trait]
+impl std::fmt::Debug for StandardDecoratorCardFx {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StandardDecoratorCardFx")
+            .field("logic", &self.logic)
+            .field("discrepancy_policy", &self.discrepancy_policy)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "fx-api")]
+impl OpenExchangeRatesFxProvider {
+    pub fn new(cache_dir: impl Into<std::path::PathBuf>, api_key: impl Into<String>) -> Self {
+        Self {
+            cache_dir: cache_dir.into(),
+            api_key: api_key.into(),
+        }
+    }
+}
+
+#[cfg(feature = "fx-api")]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl FxProvider for OpenExchangeRatesFxProvider {
+    async fn convert(
+        &self,
+        date: NaiveDate,
+        from: &str,
+        to: &str,
+        amount: f64,
+    ) -> Result<f64, ServerError> {
+        fractic_currency_conversion::util::FxUtil::using_open_exchange_rates_api(
+            &self.api_key,
+            &self.cache_dir,
+        )?
+        .convert(date, from, to, amount)
+        .await
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorCardFx {
     async fn apply(
         &self,
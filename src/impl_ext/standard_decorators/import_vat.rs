@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{
+        Account, Annotation, CommodityHandler, DecoratedTransactionSpec, DecoratorLogic, Handlers,
+        LiabilityAccount, Transaction, TransactionPosting, TransactionStatus,
+    },
+    errors::UnexpectedPositiveValue,
+    ext::standard_accounts::{vat_receivable_for, CUSTOMS_DUTIES},
+};
+
+/// How the customs duty on an import is booked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum CustomsDutyTreatment {
+    /// Added to the cost of the purchase, so it follows the spec's accounting
+    /// logic (ex. included in the capitalized cost of an asset, as required
+    /// by IAS 16 / IAS 2).
+    Capitalize,
+    /// Recorded separately as a customs duty expense.
+    Expense,
+}
+
+/// Goods or services imported from abroad, where the import VAT (and any
+/// customs duty) is paid at customs separately from the vendor. The spec
+/// amount is what is paid to the vendor, and must be negative (a purchase).
+///
+/// Import VAT is recoverable, so it is booked to VAT receivable, for the
+/// filing period of the payment date. By default the import VAT and duty are
+/// paid from the spec's backing account on the payment date; use `via_broker`
+/// if a customs broker paid them on the company's behalf, to be reimbursed
+/// later (ex. with a `Reimburse` spec).
+#[derive(Debug)]
+pub struct StandardDecoratorImportVat {
+    import_vat: f64,
+    customs_duty: f64,
+    duty_treatment: CustomsDutyTreatment,
+    broker: Option<LiabilityAccount>,
+}
+
+impl StandardDecoratorImportVat {
+    /// import_vat, customs_duty: Amounts paid at customs (sign is ignored).
+    pub fn new(
+        import_vat: f64,
+        customs_duty: f64,
+        duty_treatment: CustomsDutyTreatment,
+    ) -> Result<Self, ServerError> {
+        Ok(Self {
+            import_vat: import_vat.abs(),
+            customs_duty: customs_duty.abs(),
+            duty_treatment,
+            broker: None,
+        })
+    }
+
+    /// Import VAT and customs duty were paid by a customs broker, and are
+    /// owed to it (ex. the account of a reimbursable entity).
+    pub fn via_broker(mut self, broker: LiabilityAccount) -> Self {
+        self.broker = Some(broker);
+        self
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorImportVat {
+    async fn apply(
+        &self,
+        mut tx: DecoratedTransactionSpec<H>,
+    ) -> Result<DecoratedTransactionSpec<H>, ServerError> {
+        if tx.amount > 0.0 {
+            return Err(UnexpectedPositiveValue::new(tx.amount, "ImportVat", &tx.id));
+        }
+
+        let currency = tx.commodity.currency()?;
+        let backing_account: Account = tx.backing_account.account().into();
+        let payer: Account = match &self.broker {
+            Some(broker) => broker.clone().into(),
+            None => backing_account.clone(),
+        };
+
+        let mut postings = vec![TransactionPosting::new(
+            vat_receivable_for(tx.payment_date).into(),
+            self.import_vat,
+            currency,
+        )];
+        let mut amount_paid = self.import_vat;
+        match self.duty_treatment {
+            CustomsDutyTreatment::Capitalize => {
+                // The duty is paid by the backing account as part of the
+                // spec's own transactions, so it only needs to be moved to the
+                // broker (if any) here.
+                tx.amount -= self.customs_duty;
+                if self.broker.is_some() && self.customs_duty > 0.0 {
+                    postings.push(TransactionPosting::new(
+                        backing_account,
+                        self.customs_duty,
+                        currency,
+                    ));
+                    amount_paid += self.customs_duty;
+                }
+                tx.annotations
+                    .push(Annotation::ImportVatCustomsDutyCapitalized);
+            }
+            CustomsDutyTreatment::Expense => {
+                if self.customs_duty > 0.0 {
+                    postings.push(TransactionPosting::new(
+                        CUSTOMS_DUTIES.clone().into(),
+                        self.customs_duty,
+                        currency,
+                    ));
+                    amount_paid += self.customs_duty;
+                }
+                tx.annotations
+                    .push(Annotation::ImportVatCustomsDutyExpensed);
+            }
+        }
+        postings.push(TransactionPosting::new(payer, -amount_paid, currency));
+
+        tx.ext_transactions.push(Transaction {
+            spec_id: tx.id.clone(),
+            date: tx.payment_date,
+            postings,
+            comment: Some("Import VAT and customs duty".into()),
+            status: TransactionStatus::Unmarked,
+        });
+        Ok(tx)
+    }
+}
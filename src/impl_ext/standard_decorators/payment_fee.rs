@@ -4,7 +4,7 @@ use fractic_server_error::ServerError;
 use crate::{
     entities::{
         CommodityHandler, DecoratedTransactionSpec, DecoratorLogic, Handlers, Transaction,
-        TransactionPosting,
+        TransactionPosting, TransactionStatus,
     },
     ext::standard_accounts::PAYMENT_FEES,
 };
@@ -20,7 +20,8 @@ impl StandardDecoratorPaymentFee {
     }
 }
 
-#[async_trait]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorPaymentFee {
     async fn apply(
         &self,
@@ -33,15 +34,16 @@ impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorPaymentFee {
                 TransactionPosting::new(
                     tx.backing_account.account().into(),
                     -self.fee,
-                    tx.commodity.currency()?,
+                    tx.commodity.commodity()?,
                 ),
                 TransactionPosting::new(
                     PAYMENT_FEES.clone().into(),
                     self.fee,
-                    tx.commodity.currency()?,
+                    tx.commodity.commodity()?,
                 ),
             ],
-            comment: Some("Payment fee".to_string()),
+            comment: Some("Payment fee".into()),
+            status: TransactionStatus::Unmarked,
         });
         Ok(tx)
     }
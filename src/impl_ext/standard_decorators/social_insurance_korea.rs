@@ -0,0 +1,146 @@
+use async_trait::async_trait;
+use fractic_server_error::ServerError;
+use iso_currency::Currency;
+
+use crate::{
+    entities::{
+        Annotation, CommodityHandler, DecoratedTransactionSpec, DecoratorLogic, Handlers,
+        Transaction, TransactionPosting, TransactionStatus,
+    },
+    errors::UnexpectedPositiveValue,
+    ext::standard_accounts::{SOCIAL_INSURANCE_EXPENSE, SOCIAL_INSURANCE_PAYABLE},
+};
+
+/// Contribution rates of the four major social insurances (4대보험), as
+/// fractions of the monthly wage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KoreaSocialInsuranceRates {
+    /// National pension (국민연금), paid by both the employee and the
+    /// employer.
+    pub national_pension: f64,
+    /// Bounds of the monthly wage the national pension is based on
+    /// (기준소득월액 하한 / 상한).
+    pub national_pension_base_min: f64,
+    pub national_pension_base_max: f64,
+    /// Health insurance (건강보험), paid by both the employee and the
+    /// employer.
+    pub health_insurance: f64,
+    /// Long-term care insurance (장기요양보험), as a fraction of the health
+    /// insurance premium.
+    pub long_term_care: f64,
+    /// Employment insurance (고용보험). The employer rate includes the
+    /// employment stabilization premium, which depends on company size.
+    pub employment_insurance_employee: f64,
+    pub employment_insurance_employer: f64,
+    /// Industrial accident insurance (산재보험), paid by the employer only.
+    /// Depends on the industry.
+    pub industrial_accident: f64,
+}
+
+impl KoreaSocialInsuranceRates {
+    /// Rates for 2024, with the employment stabilization premium of
+    /// companies under 150 employees.
+    pub fn standard_2024(industrial_accident: f64) -> Self {
+        Self {
+            national_pension: 0.045,
+            national_pension_base_min: 390_000.0,
+            national_pension_base_max: 6_170_000.0,
+            health_insurance: 0.03545,
+            long_term_care: 0.1295,
+            employment_insurance_employee: 0.009,
+            employment_insurance_employer: 0.0115,
+            industrial_accident,
+        }
+    }
+}
+
+/// Payroll with the four major social insurances (4대보험). The spec amount
+/// is the gross monthly wage (negative, since it's an expense):
+///
+/// - The employee portions are withheld from the wage, so only the net
+///   amount is paid from the backing account.
+/// - The employer portions are recorded as an expense.
+///
+/// Both are recorded as a social insurance payable, remitted by the 10th of
+/// the following month (see `WithholdingRemittanceGenerator`). Each premium
+/// is truncated to 10 won for KRW amounts.
+#[derive(Debug)]
+pub struct StandardDecoratorSocialInsuranceKorea {
+    rates: KoreaSocialInsuranceRates,
+}
+
+impl StandardDecoratorSocialInsuranceKorea {
+    pub fn new(rates: KoreaSocialInsuranceRates) -> Result<Self, ServerError> {
+        Ok(Self { rates })
+    }
+
+    /// Employee and employer portions for a monthly wage.
+    fn contributions(&self, wage: f64, currency: Currency) -> (f64, f64) {
+        let r = &self.rates;
+        let pension_base = wage.clamp(r.national_pension_base_min, r.national_pension_base_max);
+        let pension = truncate_premium(pension_base * r.national_pension, currency);
+        let health = truncate_premium(wage * r.health_insurance, currency);
+        let long_term_care = truncate_premium(health * r.long_term_care, currency);
+        let shared = pension + health + long_term_care;
+
+        let employee = shared + truncate_premium(wage * r.employment_insurance_employee, currency);
+        let employer = shared
+            + truncate_premium(wage * r.employment_insurance_employer, currency)
+            + truncate_premium(wage * r.industrial_accident, currency);
+        (employee, employer)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorSocialInsuranceKorea {
+    async fn apply(
+        &self,
+        mut tx: DecoratedTransactionSpec<H>,
+    ) -> Result<DecoratedTransactionSpec<H>, ServerError> {
+        if tx.amount > 0.0 {
+            return Err(UnexpectedPositiveValue::new(
+                tx.amount,
+                "SocialInsuranceKorea",
+                &tx.id,
+            ));
+        }
+
+        let currency = tx.commodity.currency()?;
+        let (employee, employer) = self.contributions(-tx.amount, currency);
+
+        tx.ext_transactions.push(Transaction {
+            spec_id: tx.id.clone(),
+            date: tx.payment_date,
+            postings: vec![
+                TransactionPosting::new(tx.backing_account.account().into(), employee, currency),
+                TransactionPosting::new(
+                    SOCIAL_INSURANCE_EXPENSE.clone().into(),
+                    employer,
+                    currency,
+                ),
+                TransactionPosting::new(
+                    SOCIAL_INSURANCE_PAYABLE.clone().into(),
+                    -(employee + employer),
+                    currency,
+                ),
+            ],
+            comment: Some("Social insurance".into()),
+            status: TransactionStatus::Unmarked,
+        });
+
+        // Tag this transaction, since the accounting logic deserves a note in
+        // the financial records.
+        tx.annotations.push(Annotation::KoreaSocialInsurance);
+        Ok(tx)
+    }
+}
+
+/// Amounts below 10 won are dropped from premiums paid in KRW (after rounding
+/// off any floating point error).
+fn truncate_premium(amount: f64, currency: Currency) -> f64 {
+    match currency {
+        Currency::KRW => ((amount * 100.0).round() / 1000.0).trunc() * 10.0,
+        _ => amount,
+    }
+}
@@ -8,20 +8,85 @@ use crate::{
     data::models::iso_date_model::ISODateModel,
     entities::{
         Annotation, CommodityHandler as _, DecoratedTransactionSpec, DecoratorLogic, Handlers,
-        Transaction, TransactionPosting,
+        PayeeHandler as _, Transaction, TransactionPosting, TransactionStatus,
     },
+    errors::PayeeCountryUnknown,
     ext::standard_accounts::{
-        VAT_PAYABLE, VAT_PENDING_RECEIPT, VAT_RECEIVABLE, VAT_REFUND_GAIN, VAT_REFUND_LOSS,
+        vat_payable_for, vat_receivable_for, VAT_PENDING_RECEIPT, VAT_REFUND_GAIN, VAT_REFUND_LOSS,
     },
 };
 
+/// Credit for VAT paid on purchases by simplified taxpayers, as a fraction of
+/// the purchase amount (including VAT).
+const SIMPLIFIED_INPUT_CREDIT_RATE: f64 = 0.005;
+
 #[derive(Debug)]
 enum LogicType {
     AwaitingInvoice,
+    ByPayeeCountry,
     Recoverable { invoice_date: NaiveDate },
     Unrecoverable,
     ReverseChargeExempt,
     RefundAdjustment { core_amount: f64 },
+    SimplifiedSale { industry: SimplifiedVatIndustry },
+    SimplifiedPurchase,
+    ZeroRated { basis: ZeroRatedBasis },
+}
+
+/// Industry of a simplified taxpayer (간이과세자), which sets the value-added
+/// rate (업종별 부가가치율) applied to its sales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum SimplifiedVatIndustry {
+    /// Retail, restaurants, and recyclable material collection and sale.
+    RetailAndRestaurants,
+    /// Manufacturing, agriculture, forestry, fishing, and parcel delivery.
+    Manufacturing,
+    Accommodation,
+    /// Construction, transportation and warehousing (except parcel
+    /// delivery), and information and communications.
+    ConstructionTransportAndIct,
+    /// Finance and insurance, professional, scientific and technical
+    /// services, business support, real estate services and rental.
+    ProfessionalServicesAndRealEstate,
+    OtherServices,
+}
+
+impl SimplifiedVatIndustry {
+    /// Fraction of sales deemed to be value added.
+    pub fn value_added_rate(&self) -> f64 {
+        match self {
+            SimplifiedVatIndustry::RetailAndRestaurants => 0.15,
+            SimplifiedVatIndustry::Manufacturing => 0.20,
+            SimplifiedVatIndustry::Accommodation => 0.25,
+            SimplifiedVatIndustry::ConstructionTransportAndIct => 0.30,
+            SimplifiedVatIndustry::ProfessionalServicesAndRealEstate => 0.40,
+            SimplifiedVatIndustry::OtherServices => 0.30,
+        }
+    }
+}
+
+/// Grounds for applying the zero rate (영세율) to a sale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum ZeroRatedBasis {
+    /// Goods exported out of Korea (수출하는 재화).
+    ExportOfGoods,
+    /// Services provided outside of Korea (국외제공용역).
+    ServicesAbroad,
+    /// Services provided in Korea to a foreign entity without a domestic
+    /// place of business, paid in foreign currency (외화 획득 용역).
+    ForeignCurrencyServices,
+}
+
+impl ZeroRatedBasis {
+    pub fn description(&self) -> &'static str {
+        match self {
+            ZeroRatedBasis::ExportOfGoods => "export of goods",
+            ZeroRatedBasis::ServicesAbroad => "services provided outside of Korea",
+            ZeroRatedBasis::ForeignCurrencyServices => {
+                "services provided to a foreign entity, paid in foreign currency"
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -36,6 +101,16 @@ impl StandardDecoratorVatKorea {
         })
     }
 
+    /// Purchase treated by the payee's country (see
+    /// `PayeeHandler::metadata`): as `awaiting_invoice` from Korean payees,
+    /// and as `reverse_charge_exempt` from foreign ones, which don't charge
+    /// Korean VAT.
+    pub fn by_payee_country() -> Result<Self, ServerError> {
+        Ok(Self {
+            logic: LogicType::ByPayeeCountry,
+        })
+    }
+
     pub fn recoverable(invoice_date: &String) -> Result<Self, ServerError> {
         Ok(Self {
             logic: LogicType::Recoverable {
@@ -67,6 +142,38 @@ impl StandardDecoratorVatKorea {
         })
     }
 
+    /// Sale by a simplified taxpayer (간이과세자). Instead of 1/11 of the sale
+    /// amount, the VAT owed is 10% of the sale amount (including VAT, 공급대가)
+    /// multiplied by the value-added rate of the industry, and there is no
+    /// separate VAT invoice step.
+    ///
+    /// Note: taxpayers below the payment exemption threshold (연 4,800만원)
+    /// don't pay the VAT at all, so they should not use this decorator.
+    pub fn simplified_sale(industry: SimplifiedVatIndustry) -> Result<Self, ServerError> {
+        Ok(Self {
+            logic: LogicType::SimplifiedSale { industry },
+        })
+    }
+
+    /// Purchase by a simplified taxpayer (간이과세자), with a valid VAT invoice
+    /// or card receipt. Instead of recovering the VAT charged, 0.5% of the
+    /// purchase amount (including VAT) is credited against VAT payable.
+    pub fn simplified_purchase() -> Result<Self, ServerError> {
+        Ok(Self {
+            logic: LogicType::SimplifiedPurchase,
+        })
+    }
+
+    /// Zero-rated sale (영세율), ex. services sold abroad. Output VAT is
+    /// charged at 0%, so the full amount is recorded as revenue, but unlike
+    /// exempt sales, VAT paid on related purchases remains recoverable (use
+    /// `recoverable` or `awaiting_invoice` on those purchases as usual).
+    pub fn zero_rated(basis: ZeroRatedBasis) -> Result<Self, ServerError> {
+        Ok(Self {
+            logic: LogicType::ZeroRated { basis },
+        })
+    }
+
     // --
 
     fn apply_awaiting_invoice<H: Handlers>(
@@ -85,6 +192,9 @@ impl StandardDecoratorVatKorea {
             commodity,
             backing_account,
             annotations,
+            intercompany,
+            dimensions,
+            status,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -93,7 +203,7 @@ impl StandardDecoratorVatKorea {
         // IMPORTANT NOTE:
         //   If this transaction is an expense, the amount is negative.
 
-        let amount_core = amount_total / 1.1;
+        let amount_core = commodity.round(amount_total / 1.1)?;
         let amount_vat = amount_total - amount_core;
 
         let vat_transactions = vec![Transaction {
@@ -111,7 +221,8 @@ impl StandardDecoratorVatKorea {
                     commodity.currency()?,
                 ),
             ],
-            comment: Some("VAT awaiting invoice".to_string()),
+            comment: Some("VAT awaiting invoice".into()),
+            status: TransactionStatus::Unmarked,
         }];
 
         // Tag this transaction, since the accounting logic deserves a note in
@@ -130,6 +241,9 @@ impl StandardDecoratorVatKorea {
             commodity,
             backing_account,
             annotations: annotations.into_iter().chain(once(note)).collect(),
+            intercompany,
+            dimensions,
+            status,
             ext_transactions: ext_transactions
                 .into_iter()
                 .chain(vat_transactions)
@@ -156,6 +270,9 @@ impl StandardDecoratorVatKorea {
             commodity,
             backing_account,
             annotations,
+            intercompany,
+            dimensions,
+            status,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -164,7 +281,7 @@ impl StandardDecoratorVatKorea {
         // IMPORTANT NOTE:
         //   If this transaction is an expense, the amount is negative.
 
-        let amount_core = amount_total / 1.1;
+        let amount_core = commodity.round(amount_total / 1.1)?;
         let amount_vat = amount_total - amount_core;
 
         let vat_transactions = vec![
@@ -183,7 +300,8 @@ impl StandardDecoratorVatKorea {
                         commodity.currency()?,
                     ),
                 ],
-                comment: Some("VAT awaiting invoice".to_string()),
+                comment: Some("VAT awaiting invoice".into()),
+                status: TransactionStatus::Unmarked,
             },
             Transaction {
                 spec_id: id.clone(),
@@ -195,16 +313,18 @@ impl StandardDecoratorVatKorea {
                         commodity.currency()?,
                     ),
                     TransactionPosting::new(
+                        // Booked to the filing period of the invoice.
                         if amount_vat > 0.0 {
-                            VAT_PAYABLE.clone().into()
+                            vat_payable_for(invoice_date).into()
                         } else {
-                            VAT_RECEIVABLE.clone().into()
+                            vat_receivable_for(invoice_date).into()
                         },
                         -amount_vat,
                         commodity.currency()?,
                     ),
                 ],
-                comment: Some("VAT invoice received".to_string()),
+                comment: Some("VAT invoice received".into()),
+                status: TransactionStatus::Unmarked,
             },
         ];
 
@@ -224,6 +344,9 @@ impl StandardDecoratorVatKorea {
             commodity,
             backing_account,
             annotations: annotations.into_iter().chain(once(note)).collect(),
+            intercompany,
+            dimensions,
+            status,
             ext_transactions: ext_transactions
                 .into_iter()
                 .chain(vat_transactions)
@@ -253,6 +376,95 @@ impl StandardDecoratorVatKorea {
         Ok(tx)
     }
 
+    fn apply_zero_rated<H: Handlers>(
+        &self,
+        mut tx: DecoratedTransactionSpec<H>,
+        basis: ZeroRatedBasis,
+    ) -> Result<DecoratedTransactionSpec<H>, ServerError> {
+        // No VAT to separate from the amount, but tag this transaction, since
+        // the sale still has to be reported on the VAT return.
+        tx.annotations
+            .push(Annotation::VatKoreaZeroRated(basis.description().into()));
+        Ok(tx)
+    }
+
+    fn apply_simplified<H: Handlers>(
+        &self,
+        tx: DecoratedTransactionSpec<H>,
+        rate: f64,
+        note: Annotation,
+        comment: &str,
+    ) -> Result<DecoratedTransactionSpec<H>, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end,
+            payment_date,
+            accounting_logic,
+            payee,
+            description,
+            amount: amount_total,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany,
+            dimensions,
+            status,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = tx;
+
+        // IMPORTANT NOTE:
+        //   If this transaction is an expense, the amount is negative, so the
+        //   input credit is booked as a reduction of VAT payable.
+
+        let amount_vat = commodity.round(amount_total * rate)?;
+        let amount_core = amount_total - amount_vat;
+
+        let vat_transactions = vec![Transaction {
+            spec_id: id.clone(),
+            date: payment_date,
+            postings: vec![
+                TransactionPosting::new(
+                    backing_account.account().into(),
+                    amount_vat,
+                    commodity.currency()?,
+                ),
+                TransactionPosting::new(
+                    vat_payable_for(payment_date).into(),
+                    -amount_vat,
+                    commodity.currency()?,
+                ),
+            ],
+            comment: Some(comment.into()),
+            status: TransactionStatus::Unmarked,
+        }];
+
+        Ok(DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end,
+            payment_date,
+            accounting_logic,
+            payee,
+            description,
+            amount: amount_core,
+            commodity,
+            backing_account,
+            annotations: annotations.into_iter().chain(once(note)).collect(),
+            intercompany,
+            dimensions,
+            status,
+            ext_transactions: ext_transactions
+                .into_iter()
+                .chain(vat_transactions)
+                .collect(),
+            ext_assertions,
+            ext_raw,
+        })
+    }
+
     fn apply_refund_adjustment<H: Handlers>(
         &self,
         tx: DecoratedTransactionSpec<H>,
@@ -270,6 +482,9 @@ impl StandardDecoratorVatKorea {
             commodity,
             backing_account,
             annotations,
+            intercompany,
+            dimensions,
+            status,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -305,7 +520,8 @@ impl StandardDecoratorVatKorea {
                         commodity.currency()?,
                     ),
                 ],
-                comment: Some("VAT refund adjustment".to_string()),
+                comment: Some("VAT refund adjustment".into()),
+                status: TransactionStatus::Unmarked,
             }]
         } else {
             vec![]
@@ -323,6 +539,9 @@ impl StandardDecoratorVatKorea {
             commodity,
             backing_account,
             annotations,
+            intercompany,
+            dimensions,
+            status,
             ext_transactions: ext_transactions
                 .into_iter()
                 .chain(vat_transactions)
@@ -333,7 +552,8 @@ impl StandardDecoratorVatKorea {
     }
 }
 
-#[async_trait]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorVatKorea {
     async fn apply(
         &self,
@@ -341,12 +561,36 @@ impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorVatKorea {
     ) -> Result<DecoratedTransactionSpec<H>, ServerError> {
         match &self.logic {
             LogicType::AwaitingInvoice => self.apply_awaiting_invoice(tx),
+            LogicType::ByPayeeCountry => {
+                let metadata = tx.payee.metadata();
+                match metadata.country {
+                    Some(_) if metadata.is_in("KR") => self.apply_awaiting_invoice(tx),
+                    Some(_) => self.apply_reverse_charge_exempt(tx),
+                    None => Err(PayeeCountryUnknown::new(&tx.payee.name(), "Korean VAT")),
+                }
+            }
             LogicType::Recoverable { invoice_date } => self.apply_recoverable(tx, *invoice_date),
             LogicType::Unrecoverable => self.apply_unrecoverable(tx),
             LogicType::ReverseChargeExempt => self.apply_reverse_charge_exempt(tx),
             LogicType::RefundAdjustment { core_amount } => {
                 self.apply_refund_adjustment(tx, *core_amount)
             }
+            LogicType::SimplifiedSale { industry } => {
+                let value_added_rate = industry.value_added_rate();
+                self.apply_simplified(
+                    tx,
+                    value_added_rate * 0.1,
+                    Annotation::VatKoreaSimplifiedSale((value_added_rate * 100.0).round() as i32),
+                    "VAT on sale (simplified taxpayer)",
+                )
+            }
+            LogicType::SimplifiedPurchase => self.apply_simplified(
+                tx,
+                SIMPLIFIED_INPUT_CREDIT_RATE,
+                Annotation::VatKoreaSimplifiedPurchase,
+                "VAT input credit (simplified taxpayer)",
+            ),
+            LogicType::ZeroRated { basis } => self.apply_zero_rated(tx, *basis),
         }
     }
 }
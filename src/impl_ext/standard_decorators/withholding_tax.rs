@@ -1,21 +1,30 @@
-use std::iter::once;
+use std::{collections::BTreeMap, iter::once};
 
 use async_trait::async_trait;
 use fractic_server_error::{DivisionByZeroError, ServerError};
+use iso_currency::Currency;
 
 use crate::{
     entities::{
         Annotation, CommodityHandler, DecoratedTransactionSpec, DecoratorLogic, Handlers,
-        Transaction, TransactionPosting,
+        PayeeHandler as _, Transaction, TransactionPosting, TransactionStatus,
     },
-    ext::standard_accounts::FOREIGN_WITHHOLDING_TAX,
+    errors::PayeeCountryUnknown,
+    ext::standard_accounts::{FOREIGN_WITHHOLDING_TAX, WITHHOLDING_TAX_PAYABLE},
 };
 
 #[derive(Debug)]
 enum LogicType {
     UnrecoverableForeign { percent: f64 },
+    UnrecoverableForeignByCountry { percents: BTreeMap<String, f64> },
+    KoreaFreelancer,
 }
 
+/// Korean withholding on business income paid to individuals (사업소득
+/// 원천징수): 3% income tax, plus 10% of it as local income tax.
+const KOREA_FREELANCER_INCOME_TAX_RATE: f64 = 0.03;
+const KOREA_LOCAL_INCOME_TAX_RATE: f64 = 0.1;
+
 #[derive(Debug)]
 pub struct StandardDecoratorWithholdingTax {
     logic: LogicType,
@@ -28,6 +37,38 @@ impl StandardDecoratorWithholdingTax {
         })
     }
 
+    /// Same as `unrecoverable_foreign`, with the rate withheld by the payee's
+    /// country (see `PayeeHandler::metadata`), ex. the treaty rates of each
+    /// country. Payments to payees in other countries are left as-is.
+    ///
+    /// percents: Rate withheld, by ISO country code (ex. "US").
+    pub fn unrecoverable_foreign_by_payee_country(
+        percents: BTreeMap<String, f64>,
+    ) -> Result<Self, ServerError> {
+        Ok(Self {
+            logic: LogicType::UnrecoverableForeignByCountry {
+                percents: percents
+                    .into_iter()
+                    .map(|(country, percent)| (country.to_uppercase(), percent))
+                    .collect(),
+            },
+        })
+    }
+
+    /// Payment to a Korean freelancer (individual business income), with
+    /// 3.3% withheld from the fee. The spec amount is the gross fee (negative,
+    /// since it's an expense); only the net amount is paid from the backing
+    /// account, and the withheld amount is recorded as a withholding tax
+    /// payable, to be remitted by the 10th of the following month (see
+    /// `WithholdingRemittanceGenerator`).
+    ///
+    /// Each tax is truncated to 10 won (10원 미만 절사) for KRW amounts.
+    pub fn korea_freelancer() -> Result<Self, ServerError> {
+        Ok(Self {
+            logic: LogicType::KoreaFreelancer,
+        })
+    }
+
     // --
 
     fn apply_unrecoverable_foreign<H: Handlers>(
@@ -47,6 +88,9 @@ impl StandardDecoratorWithholdingTax {
             commodity,
             backing_account,
             annotations,
+            intercompany,
+            dimensions,
+            status,
             ext_transactions,
             ext_assertions,
             ext_raw,
@@ -76,7 +120,8 @@ impl StandardDecoratorWithholdingTax {
                     commodity.currency()?,
                 ),
             ],
-            comment: Some("Foreign withholding tax".to_string()),
+            comment: Some("Foreign withholding tax".into()),
+            status: TransactionStatus::Unmarked,
         };
 
         // Tag this transaction, since the accounting logic deserves a note in
@@ -95,6 +140,88 @@ impl StandardDecoratorWithholdingTax {
             commodity,
             backing_account,
             annotations: annotations.into_iter().chain(once(note)).collect(),
+            intercompany,
+            dimensions,
+            status,
+            ext_transactions: ext_transactions
+                .into_iter()
+                .chain(once(withholding_transaction))
+                .collect(),
+            ext_assertions,
+            ext_raw,
+        })
+    }
+
+    fn apply_korea_freelancer<H: Handlers>(
+        &self,
+        tx: DecoratedTransactionSpec<H>,
+    ) -> Result<DecoratedTransactionSpec<H>, ServerError> {
+        let DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end,
+            payment_date,
+            accounting_logic,
+            payee,
+            description,
+            amount: amount_gross,
+            commodity,
+            backing_account,
+            annotations,
+            intercompany,
+            dimensions,
+            status,
+            ext_transactions,
+            ext_assertions,
+            ext_raw,
+        } = tx;
+
+        // IMPORTANT NOTE:
+        //   Since this transaction is an expense, the amount is negative.
+
+        let currency = commodity.currency()?;
+        let income_tax = truncate_tax(amount_gross * KOREA_FREELANCER_INCOME_TAX_RATE, currency);
+        let local_income_tax = truncate_tax(income_tax * KOREA_LOCAL_INCOME_TAX_RATE, currency);
+        let withholding_amount = income_tax + local_income_tax;
+
+        let withholding_transaction = Transaction {
+            spec_id: id.clone(),
+            date: payment_date,
+            postings: vec![
+                TransactionPosting::new(
+                    backing_account.account().into(),
+                    -withholding_amount,
+                    currency,
+                ),
+                TransactionPosting::new(
+                    WITHHOLDING_TAX_PAYABLE.clone().into(),
+                    withholding_amount,
+                    currency,
+                ),
+            ],
+            comment: Some("Withholding tax (3.3%)".into()),
+            status: TransactionStatus::Unmarked,
+        };
+
+        // Tag this transaction, since the accounting logic deserves a note in
+        // the financial records.
+        let note = Annotation::KoreaFreelancerWithholding;
+
+        Ok(DecoratedTransactionSpec {
+            id,
+            accrual_start,
+            accrual_end,
+            payment_date,
+            accounting_logic,
+            payee,
+            description,
+            amount: amount_gross,
+            commodity,
+            backing_account,
+            annotations: annotations.into_iter().chain(once(note)).collect(),
+            intercompany,
+            dimensions,
+            status,
             ext_transactions: ext_transactions
                 .into_iter()
                 .chain(once(withholding_transaction))
@@ -105,7 +232,18 @@ impl StandardDecoratorWithholdingTax {
     }
 }
 
-#[async_trait]
+/// Amounts below 10 won are dropped from taxes paid in KRW. Since the amount
+/// is negative for expenses, truncate towards zero (after rounding off any
+/// floating point error, ex. 134,999.99999 for 135,000).
+fn truncate_tax(amount: f64, currency: Currency) -> f64 {
+    match currency {
+        Currency::KRW => ((amount * 100.0).round() / 1000.0).trunc() * 10.0,
+        _ => amount,
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorWithholdingTax {
     async fn apply(
         &self,
@@ -115,6 +253,20 @@ impl<H: Handlers> DecoratorLogic<H> for StandardDecoratorWithholdingTax {
             LogicType::UnrecoverableForeign { percent } => {
                 self.apply_unrecoverable_foreign(tx, *percent)
             }
+            LogicType::UnrecoverableForeignByCountry { percents } => {
+                let metadata = tx.payee.metadata();
+                let Some(country) = metadata.country else {
+                    return Err(PayeeCountryUnknown::new(
+                        &tx.payee.name(),
+                        "withholding tax",
+                    ));
+                };
+                match percents.get(&country) {
+                    Some(percent) => self.apply_unrecoverable_foreign(tx, *percent),
+                    None => Ok(tx),
+                }
+            }
+            LogicType::KoreaFreelancer => self.apply_korea_freelancer(tx),
         }
     }
 }
@@ -0,0 +1,391 @@
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+use fractic_server_error::ServerError;
+use iso_currency::Currency;
+use serde_derive::Deserialize;
+
+use crate::{
+    entities::{
+        asset, asset_tl, default_accumulated_amortization, equity, equity_tl, expense, expense_tl,
+        income, income_tl, liability, liability_tl, AssetAccount, AssetClassification,
+        AssetHandler, CashHandler, Commodity, CommodityHandler, EquityAccount,
+        EquityClassification, ExpenseAccount, ExpenseClassification, ExpenseHandler, Handlers,
+        IncomeAccount, IncomeClassification, IncomeHandler, LiabilityAccount,
+        LiabilityClassification, PayeeHandler, ReimbursableEntityHandler, ShareholderHandler,
+    },
+    errors::{InvalidHandlersConfig, InvalidIsoCurrencyCode, UnresolvedDefaultCommodity},
+};
+
+thread_local! {
+    static CONFIG: RefCell<Option<Arc<HandlersConfig>>> = const { RefCell::new(None) };
+}
+
+/// Handlers resolved by name against a configuration loaded at runtime (see
+/// `HandlersConfig`), for front-ends where handlers can't be written in code
+/// (ex. the CLI). With an `expenses` entry named "Coffee", the spec logic
+/// `SimpleExpense("Coffee")` books against that entry's account.
+///
+/// Specs must be parsed within `with_config`, which resolves each name to its
+/// account or commodity as it's parsed (so unknown names are reported as
+/// parse errors). The parsed specs hold the resolved values, so processing
+/// them, and printing the records, doesn't need the configuration. Each caller
+/// passes its own configuration, so several can be used at once (ex. one per
+/// request).
+///
+/// `CommodityHandler::default` is resolved against the configuration too, so
+/// defaults created outside of `with_config` fail when used. Specs resolve
+/// the defaults they need when parsed.
+///
+/// Decorators and macros require custom logic, so they can't be configured
+/// (`()` is used as a placeholder).
+#[derive(Debug)]
+pub struct ConfiguredHandlers;
+
+impl Handlers for ConfiguredHandlers {
+    type A = ConfiguredAsset;
+    type I = ConfiguredIncome;
+    type E = ConfiguredExpense;
+    type R = ConfiguredReimbursableEntity;
+    type C = ConfiguredCash;
+    type S = ConfiguredShareholder;
+    type D = ();
+    type M = ConfiguredCommodity;
+    type P = ConfiguredPayee;
+    type F = ();
+}
+
+impl ConfiguredHandlers {
+    /// Run `f` (ex. parsing specs) with names resolved against the
+    /// configuration, on the current thread.
+    pub fn with_config<R>(config: &Arc<HandlersConfig>, f: impl FnOnce() -> R) -> R {
+        let _restore = RestoreConfig(CONFIG.with(|c| c.replace(Some(config.clone()))));
+        f()
+    }
+}
+
+/// Restores the configuration of the enclosing `with_config`, if any, even if
+/// the wrapped code panics.
+struct RestoreConfig(Option<Arc<HandlersConfig>>);
+
+impl Drop for RestoreConfig {
+    fn drop(&mut self) {
+        CONFIG.with(|c| *c.borrow_mut() = self.0.take());
+    }
+}
+
+fn config() -> Result<Arc<HandlersConfig>, String> {
+    CONFIG.with(|c| c.borrow().clone()).ok_or_else(|| {
+        "names can only be resolved within `ConfiguredHandlers::with_config`".to_string()
+    })
+}
+
+// Configuration.
+// ----------------------------------------------------------------------------
+
+/// Accounts and commodities referenced by name from the specs, usually read
+/// from a JSON file (see `from_json`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HandlersConfig {
+    /// ISO code used when a spec doesn't specify a commodity (default: USD).
+    pub main_currency: Option<String>,
+    /// Non-ISO commodities specs can use (ex. shares or crypto), by symbol.
+    pub commodities: HashMap<String, CommodityConfig>,
+    pub assets: HashMap<String, AssetConfig>,
+    pub income: HashMap<String, AccountConfig<IncomeClassification>>,
+    pub expenses: HashMap<String, AccountConfig<ExpenseClassification>>,
+    pub cash: HashMap<String, AccountConfig<AssetClassification>>,
+    pub shareholders: HashMap<String, AccountConfig<EquityClassification>>,
+    pub reimbursable_entities: HashMap<String, AccountConfig<LiabilityClassification>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig<C> {
+    /// Sub-account name. If omitted, the top-level account of the
+    /// classification is used.
+    pub name: Option<String>,
+    pub classification: C,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssetConfig {
+    pub name: Option<String>,
+    pub classification: AssetClassification,
+    /// Expense account receiving amortization / depreciation, if any.
+    pub upon_accrual: Option<AccountConfig<ExpenseClassification>>,
+    /// Contra-asset account credited with amortization / depreciation, if not
+    /// the default (see `AssetHandler::accumulated_amortization`).
+    pub accumulated_amortization: Option<AccountConfig<AssetClassification>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommodityConfig {
+    pub decimal_places: u32,
+}
+
+impl HandlersConfig {
+    pub fn from_json(json: &str) -> Result<Self, ServerError> {
+        serde_json::from_str(json).map_err(|e| InvalidHandlersConfig::new(&e.to_string()))
+    }
+}
+
+impl<C: Copy> AccountConfig<C> {
+    fn account<T>(&self, named: fn(String, C) -> T, top_level: fn(C) -> T) -> T {
+        match &self.name {
+            Some(name) => named(name.clone(), self.classification),
+            None => top_level(self.classification),
+        }
+    }
+}
+
+fn unknown(kind: &str, name: &str) -> String {
+    format!("no {kind} named '{name}' in the handlers configuration")
+}
+
+// Account handlers.
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ConfiguredAsset {
+    account: AssetAccount,
+    upon_accrual: Option<ExpenseAccount>,
+    accumulated_amortization: Option<AssetAccount>,
+}
+
+impl TryFrom<String> for ConfiguredAsset {
+    type Error = String;
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let config = config()?;
+        let c = config
+            .assets
+            .get(&name)
+            .ok_or_else(|| unknown("asset", &name))?;
+        let account = match &c.name {
+            Some(n) => asset(n, c.classification),
+            None => asset_tl(c.classification),
+        };
+        let upon_accrual = c
+            .upon_accrual
+            .as_ref()
+            .map(|e| e.account(expense, expense_tl));
+        let accumulated_amortization = c
+            .accumulated_amortization
+            .as_ref()
+            .map(|a| a.account(asset, asset_tl));
+        Ok(Self {
+            account,
+            upon_accrual,
+            accumulated_amortization,
+        })
+    }
+}
+
+impl AssetHandler for ConfiguredAsset {
+    fn account(&self) -> AssetAccount {
+        self.account.clone()
+    }
+    fn upon_accrual(&self) -> Option<ExpenseAccount> {
+        self.upon_accrual.clone()
+    }
+    fn accumulated_amortization(&self) -> Option<AssetAccount> {
+        self.accumulated_amortization
+            .clone()
+            .or_else(|| default_accumulated_amortization(self.account.clone()))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ConfiguredIncome(IncomeAccount);
+
+impl TryFrom<String> for ConfiguredIncome {
+    type Error = String;
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        config()?
+            .income
+            .get(&name)
+            .map(|c| Self(c.account(income, income_tl)))
+            .ok_or_else(|| unknown("income", &name))
+    }
+}
+
+impl IncomeHandler for ConfiguredIncome {
+    fn account(&self) -> IncomeAccount {
+        self.0.clone()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ConfiguredExpense(ExpenseAccount);
+
+impl TryFrom<String> for ConfiguredExpense {
+    type Error = String;
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        config()?
+            .expenses
+            .get(&name)
+            .map(|c| Self(c.account(expense, expense_tl)))
+            .ok_or_else(|| unknown("expense", &name))
+    }
+}
+
+impl ExpenseHandler for ConfiguredExpense {
+    fn account(&self) -> ExpenseAccount {
+        self.0.clone()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ConfiguredCash(AssetAccount);
+
+impl TryFrom<String> for ConfiguredCash {
+    type Error = String;
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        config()?
+            .cash
+            .get(&name)
+            .map(|c| Self(c.account(asset, asset_tl)))
+            .ok_or_else(|| unknown("cash account", &name))
+    }
+}
+
+impl CashHandler for ConfiguredCash {
+    fn account(&self) -> AssetAccount {
+        self.0.clone()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ConfiguredShareholder(EquityAccount);
+
+impl TryFrom<String> for ConfiguredShareholder {
+    type Error = String;
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        config()?
+            .shareholders
+            .get(&name)
+            .map(|c| Self(c.account(equity, equity_tl)))
+            .ok_or_else(|| unknown("shareholder", &name))
+    }
+}
+
+impl ShareholderHandler for ConfiguredShareholder {
+    fn account(&self) -> EquityAccount {
+        self.0.clone()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ConfiguredReimbursableEntity(LiabilityAccount);
+
+impl TryFrom<String> for ConfiguredReimbursableEntity {
+    type Error = String;
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        config()?
+            .reimbursable_entities
+            .get(&name)
+            .map(|c| Self(c.account(liability, liability_tl)))
+            .ok_or_else(|| unknown("reimbursable entity", &name))
+    }
+}
+
+impl ReimbursableEntityHandler for ConfiguredReimbursableEntity {
+    fn account(&self) -> LiabilityAccount {
+        self.0.clone()
+    }
+}
+
+// Other.
+// ----------------------------------------------------------------------------
+
+/// Payees are used verbatim, so don't need to be configured.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct ConfiguredPayee(String);
+
+impl PayeeHandler for ConfiguredPayee {
+    fn name(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// ISO currency code (ex. `"USD"`), or the symbol of a commodity in the
+/// `commodities` configuration (ex. `"AAPL"`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ConfiguredCommodity {
+    symbol: String,
+    /// `None` for the default commodity outside of `with_config`, or an
+    /// invalid main currency.
+    commodity: Option<Commodity>,
+}
+
+impl ConfiguredCommodity {
+    fn resolve(symbol: String, config: &HandlersConfig) -> Result<Self, String> {
+        let commodity = match config.commodities.get(&symbol) {
+            Some(custom) => {
+                Commodity::custom(&symbol, custom.decimal_places).map_err(|e| e.to_string())?
+            }
+            None => Currency::from_code(&symbol)
+                .ok_or_else(|| unknown("commodity", &symbol))?
+                .into(),
+        };
+        Ok(Self {
+            symbol,
+            commodity: Some(commodity),
+        })
+    }
+
+    fn unresolved(&self) -> ServerError {
+        if self.symbol.is_empty() {
+            UnresolvedDefaultCommodity::new()
+        } else {
+            InvalidIsoCurrencyCode::new(&self.symbol)
+        }
+    }
+}
+
+impl TryFrom<String> for ConfiguredCommodity {
+    type Error = String;
+    fn try_from(symbol: String) -> Result<Self, Self::Error> {
+        let config = config()?;
+        Self::resolve(symbol, &config)
+    }
+}
+
+impl CommodityHandler for ConfiguredCommodity {
+    fn iso_symbol(&self) -> String {
+        self.symbol.clone()
+    }
+    fn currency(&self) -> Result<Currency, ServerError> {
+        self.commodity
+            .and_then(|c| c.currency())
+            .ok_or_else(|| self.unresolved())
+    }
+    fn commodity(&self) -> Result<Commodity, ServerError> {
+        self.commodity.ok_or_else(|| self.unresolved())
+    }
+    /// The configuration's main currency (USD if not set), failing when used
+    /// if created outside of `with_config`.
+    fn default() -> Self {
+        let Ok(config) = config() else {
+            return Self {
+                symbol: String::new(),
+                commodity: None,
+            };
+        };
+        let symbol = config.main_currency.clone().unwrap_or_else(|| "USD".into());
+        Self::resolve(symbol.clone(), &config).unwrap_or(Self {
+            symbol,
+            commodity: None,
+        })
+    }
+}
@@ -0,0 +1,327 @@
+use std::fmt;
+
+use fractic_server_error::ServerError;
+use iso_currency::Currency;
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{
+    entities::{
+        asset, equity, expense, income, liability, AssetAccount, AssetClassification, AssetHandler,
+        CashHandler, CommodityHandler, DecoratorHandler, DecoratorLogic, EquityAccount,
+        EquityClassification, ExpenseAccount, ExpenseClassification, ExpenseHandler, Handlers,
+        IncomeAccount, IncomeClassification, IncomeHandler, LiabilityAccount,
+        LiabilityClassification, PayeeHandler, ReimbursableEntityHandler, ShareholderHandler,
+    },
+    ext::{
+        standard_accounts::BANK_INTEREST,
+        standard_decorators::{
+            CustomsDutyTreatment, KoreaSocialInsuranceRates, SimplifiedVatIndustry,
+            StandardDecoratorImportVat, StandardDecoratorPaymentFee,
+            StandardDecoratorSocialInsuranceKorea, StandardDecoratorVatKorea,
+            StandardDecoratorWithholdingTax, ZeroRatedBasis,
+        },
+    },
+};
+
+/// Ready-made handlers with a typical startup chart of accounts: one bank
+/// account, a corporate card, a founder (as shareholder, and for expenses
+/// paid personally), and common income and expense categories. Handlers are
+/// written in the CSVs as plain RON variants. Ex.:
+///
+/// ```text
+/// 2024-01-05,,2024-01-05,SimpleExpense(Cloud),,"""AWS""",Hosting,(50.00),USD,Reimburse(Card),
+/// 2024-01-01,2024-01-31,2024-02-10,PayCardStatement(Card),,"""Card Co""",Statement,(50.00),USD,Cash(Bank),
+/// ```
+///
+/// Macros aren't supported, and FX decorators are left out since they need
+/// an FX provider; use custom handlers for those.
+#[derive(Debug)]
+pub struct StandardSmallCompanyHandlers;
+
+impl Handlers for StandardSmallCompanyHandlers {
+    type A = SmallCompanyAsset;
+    type I = SmallCompanyIncome;
+    type E = SmallCompanyExpense;
+    type R = SmallCompanyReimbursable;
+    type C = SmallCompanyCash;
+    type S = SmallCompanyShareholder;
+    type D = SmallCompanyDecorator;
+    type M = SmallCompanyCommodity;
+    type P = SmallCompanyPayee;
+    type F = ();
+}
+
+// Account handlers.
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum SmallCompanyAsset {
+    /// Computers and other hardware, depreciated over their useful life.
+    Equipment,
+    /// Purchased software licenses, amortized over their term.
+    Software,
+    /// Refundable deposits (ex. office lease deposit). Not amortized.
+    Deposit,
+}
+
+impl AssetHandler for SmallCompanyAsset {
+    fn account(&self) -> AssetAccount {
+        match self {
+            SmallCompanyAsset::Equipment => {
+                asset("Equipment", AssetClassification::PropertyPlantEquipment)
+            }
+            SmallCompanyAsset::Software => asset("Software", AssetClassification::IntangibleAssets),
+            SmallCompanyAsset::Deposit => asset("Deposits", AssetClassification::LongTermDeposits),
+        }
+    }
+    fn upon_accrual(&self) -> Option<ExpenseAccount> {
+        match self {
+            SmallCompanyAsset::Equipment => Some(expense(
+                "Equipment",
+                ExpenseClassification::DepreciationExpense,
+            )),
+            SmallCompanyAsset::Software => Some(expense(
+                "Software",
+                ExpenseClassification::AmortizationExpense,
+            )),
+            SmallCompanyAsset::Deposit => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum SmallCompanyIncome {
+    Sales,
+    Services,
+    /// Interest paid by the bank.
+    Interest,
+    Other,
+}
+
+impl IncomeHandler for SmallCompanyIncome {
+    fn account(&self) -> IncomeAccount {
+        match self {
+            SmallCompanyIncome::Sales => income("Sales", IncomeClassification::SalesRevenue),
+            SmallCompanyIncome::Services => {
+                income("Services", IncomeClassification::ServiceRevenue)
+            }
+            SmallCompanyIncome::Interest => BANK_INTEREST.clone(),
+            SmallCompanyIncome::Other => {
+                income("Other", IncomeClassification::OtherNonOperatingIncome)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum SmallCompanyExpense {
+    Salaries,
+    Rent,
+    /// Office supplies, utilities, etc.
+    Office,
+    /// SaaS subscriptions.
+    Software,
+    /// Hosting and cloud infrastructure.
+    Cloud,
+    Marketing,
+    Travel,
+    Meals,
+    /// Legal, accounting and other advisors.
+    ProfessionalFees,
+    Insurance,
+    BankFees,
+    /// Taxes other than income tax (ex. local taxes, stamp duties).
+    Taxes,
+    Other,
+}
+
+impl ExpenseHandler for SmallCompanyExpense {
+    fn account(&self) -> ExpenseAccount {
+        use ExpenseClassification::*;
+        let (name, classification) = match self {
+            SmallCompanyExpense::Salaries => ("Salaries", GeneralAdministrativeExpenses),
+            SmallCompanyExpense::Rent => ("Rent", GeneralAdministrativeExpenses),
+            SmallCompanyExpense::Office => ("Office", GeneralAdministrativeExpenses),
+            SmallCompanyExpense::Software => ("Software", GeneralAdministrativeExpenses),
+            SmallCompanyExpense::Cloud => ("Cloud", CloudServicesExpenses),
+            SmallCompanyExpense::Marketing => ("Marketing", SellingExpenses),
+            SmallCompanyExpense::Travel => ("Travel", GeneralAdministrativeExpenses),
+            SmallCompanyExpense::Meals => ("Meals", GeneralAdministrativeExpenses),
+            SmallCompanyExpense::ProfessionalFees => {
+                ("ProfessionalFees", GeneralAdministrativeExpenses)
+            }
+            SmallCompanyExpense::Insurance => ("Insurance", GeneralAdministrativeExpenses),
+            SmallCompanyExpense::BankFees => ("BankFees", OtherNonCoreFinancialExpense),
+            SmallCompanyExpense::Taxes => ("Taxes", OtherTaxExpense),
+            SmallCompanyExpense::Other => ("Other", OtherNonOperatingCashExpense),
+        };
+        expense(name, classification)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum SmallCompanyCash {
+    Bank,
+}
+
+impl CashHandler for SmallCompanyCash {
+    fn account(&self) -> AssetAccount {
+        asset("Bank", AssetClassification::CashAndCashEquivalents)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum SmallCompanyShareholder {
+    Founder,
+}
+
+impl ShareholderHandler for SmallCompanyShareholder {
+    fn account(&self) -> EquityAccount {
+        equity("Founder", EquityClassification::CommonStock)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Deserialize)]
+pub enum SmallCompanyReimbursable {
+    /// Expenses paid personally by the founder, to be reimbursed.
+    Founder,
+    /// Corporate credit card, settled with `PayCardStatement(Card)`.
+    Card,
+}
+
+impl ReimbursableEntityHandler for SmallCompanyReimbursable {
+    fn account(&self) -> LiabilityAccount {
+        match self {
+            SmallCompanyReimbursable::Founder => {
+                liability("Founder", LiabilityClassification::OtherCurrentLiabilities)
+            }
+            SmallCompanyReimbursable::Card => liability(
+                "CorporateCard",
+                LiabilityClassification::OtherCurrentLiabilities,
+            ),
+        }
+    }
+}
+
+// Other.
+// ----------------------------------------------------------------------------
+
+/// Standard decorators that don't need any configuration.
+#[derive(Debug, Clone, PartialEq, serde_derive::Deserialize)]
+pub enum SmallCompanyDecorator {
+    /// Fee charged on top of the payment (ex. `PaymentFee(1.50)`).
+    PaymentFee(f64),
+    VatAwaitingInvoice,
+    /// Invoice date as an ISO date (ex. `VatRecoverable("2024-01-31")`).
+    VatRecoverable(String),
+    VatUnrecoverable,
+    VatReverseChargeExempt,
+    VatRefundAdjustment(f64),
+    /// Sale by a simplified VAT taxpayer (ex. `VatSimplifiedSale(OtherServices)`).
+    VatSimplifiedSale(SimplifiedVatIndustry),
+    VatSimplifiedPurchase,
+    /// Zero-rated sale (ex. `VatZeroRated(ServicesAbroad)`).
+    VatZeroRated(ZeroRatedBasis),
+    /// Import VAT and customs duty paid at customs, from the backing account
+    /// (ex. `ImportVat(100000.0, 80000.0, Capitalize)`).
+    ImportVat(f64, f64, CustomsDutyTreatment),
+    /// Unrecoverable foreign withholding tax, in percent.
+    ForeignWithholdingTax(f64),
+    /// 3.3% withheld from the (gross) fee paid to a Korean freelancer.
+    FreelancerWithholding,
+    /// Social insurances on a gross monthly wage, at 2024 rates, given the
+    /// industrial accident insurance rate in percent (ex. `SocialInsurance(0.7)`).
+    SocialInsurance(f64),
+}
+
+impl DecoratorHandler for SmallCompanyDecorator {
+    fn logic<H: Handlers>(&self) -> Result<Box<dyn DecoratorLogic<H>>, ServerError> {
+        Ok(match self {
+            SmallCompanyDecorator::PaymentFee(fee) => {
+                Box::new(StandardDecoratorPaymentFee::new(*fee))
+            }
+            SmallCompanyDecorator::VatAwaitingInvoice => {
+                Box::new(StandardDecoratorVatKorea::awaiting_invoice()?)
+            }
+            SmallCompanyDecorator::VatRecoverable(invoice_date) => {
+                Box::new(StandardDecoratorVatKorea::recoverable(invoice_date)?)
+            }
+            SmallCompanyDecorator::VatUnrecoverable => {
+                Box::new(StandardDecoratorVatKorea::unrecoverable()?)
+            }
+            SmallCompanyDecorator::VatReverseChargeExempt => {
+                Box::new(StandardDecoratorVatKorea::reverse_charge_exempt()?)
+            }
+            SmallCompanyDecorator::VatRefundAdjustment(core_amount) => {
+                Box::new(StandardDecoratorVatKorea::refund_adjustment(*core_amount)?)
+            }
+            SmallCompanyDecorator::VatSimplifiedSale(industry) => {
+                Box::new(StandardDecoratorVatKorea::simplified_sale(*industry)?)
+            }
+            SmallCompanyDecorator::VatSimplifiedPurchase => {
+                Box::new(StandardDecoratorVatKorea::simplified_purchase()?)
+            }
+            SmallCompanyDecorator::VatZeroRated(basis) => {
+                Box::new(StandardDecoratorVatKorea::zero_rated(*basis)?)
+            }
+            SmallCompanyDecorator::ImportVat(import_vat, customs_duty, duty_treatment) => Box::new(
+                StandardDecoratorImportVat::new(*import_vat, *customs_duty, *duty_treatment)?,
+            ),
+            SmallCompanyDecorator::ForeignWithholdingTax(percent) => Box::new(
+                StandardDecoratorWithholdingTax::unrecoverable_foreign(*percent)?,
+            ),
+            SmallCompanyDecorator::FreelancerWithholding => {
+                Box::new(StandardDecoratorWithholdingTax::korea_freelancer()?)
+            }
+            SmallCompanyDecorator::SocialInsurance(industrial_accident_percent) => {
+                Box::new(StandardDecoratorSocialInsuranceKorea::new(
+                    KoreaSocialInsuranceRates::standard_2024(*industrial_accident_percent / 100.0),
+                )?)
+            }
+        })
+    }
+}
+
+/// Used verbatim, ex. `"Acme"` (`"""Acme"""` once quoted for CSV).
+#[derive(Debug, Clone, PartialEq, Eq, serde_derive::Deserialize)]
+#[serde(transparent)]
+pub struct SmallCompanyPayee(pub String);
+
+impl PayeeHandler for SmallCompanyPayee {
+    fn name(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// Any ISO currency, written as its bare code (ex. `KRW`). Defaults to USD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmallCompanyCommodity(pub Currency);
+
+impl CommodityHandler for SmallCompanyCommodity {
+    fn iso_symbol(&self) -> String {
+        self.0.code().into()
+    }
+    fn default() -> Self {
+        Self(Currency::USD)
+    }
+}
+
+impl<'de> Deserialize<'de> for SmallCompanyCommodity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CodeVisitor;
+        impl de::Visitor<'_> for CodeVisitor {
+            type Value = SmallCompanyCommodity;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an ISO currency code")
+            }
+            fn visit_str<E: de::Error>(self, code: &str) -> Result<Self::Value, E> {
+                Currency::from_code(code)
+                    .map(SmallCompanyCommodity)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(code), &self))
+            }
+        }
+        // Identifiers, so codes can be written bare in RON (ex. `KRW` rather
+        // than `"KRW"`).
+        deserializer.deserialize_identifier(CodeVisitor)
+    }
+}
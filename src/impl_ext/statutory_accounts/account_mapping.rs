@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use fractic_server_error::{CriticalError, ServerError};
+
+use crate::entities::Account;
+
+/// Mapping of accounts to local statutory account codes (ex. the Korean
+/// 계정과목 코드), to present statements in the local chart of accounts.
+///
+/// Accounts are mapped by ledger name, covering their sub-accounts unless
+/// these are mapped separately (ex. a mapping for
+/// `Assets:Current:AccountsReceivable` also covers
+/// `Assets:Current:AccountsReceivable:Customers`).
+#[derive(Debug, Clone, Default)]
+pub struct StatutoryAccountMapping {
+    /// Keyed by ledger name.
+    accounts: BTreeMap<String, StatutoryAccount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatutoryAccount {
+    pub code: String,
+    pub name: String,
+}
+
+impl StatutoryAccountMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map the account (and its sub-accounts), replacing any existing mapping.
+    pub fn with_account(
+        mut self,
+        account: impl Into<Account>,
+        code: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.accounts.insert(
+            account.into().ledger(),
+            StatutoryAccount {
+                code: code.into(),
+                name: name.into(),
+            },
+        );
+        self
+    }
+
+    /// Most specific mapping covering the account, if any.
+    pub fn get(&self, account: &Account) -> Option<&StatutoryAccount> {
+        let mut ledger = account.ledger();
+        loop {
+            if let Some(statutory) = self.accounts.get(&ledger) {
+                return Some(statutory);
+            }
+            ledger.truncate(ledger.rfind(':')?);
+        }
+    }
+
+    /// `alias` directives renaming each mapped account to its code and name
+    /// (ex. `Assets:101 현금`), keeping the top-level account so hledger still
+    /// recognizes the account type.
+    pub fn to_hledger_aliases(&self) -> String {
+        // hledger applies the most recently seen alias directive first, so
+        // sub-accounts must come after their parents.
+        self.aliases()
+            .map(|(from, to)| format!("alias {} = {}\n", from, to))
+            .collect()
+    }
+
+    /// Translation table of each ledger name to its code and name.
+    pub fn to_csv(&self) -> Result<String, ServerError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let to_error = |e: csv::Error| {
+            CriticalError::with_debug("failed to write statutory account mapping as CSV", &e)
+        };
+        writer
+            .write_record(["account", "code", "name"])
+            .map_err(to_error)?;
+        for (ledger, statutory) in &self.accounts {
+            writer
+                .write_record([ledger, &statutory.code, &statutory.name])
+                .map_err(to_error)?;
+        }
+        let bytes = writer.into_inner().map_err(|e| {
+            CriticalError::with_debug(
+                "failed to write statutory account mapping as CSV",
+                &e.to_string(),
+            )
+        })?;
+        String::from_utf8(bytes).map_err(|e| {
+            CriticalError::with_debug("failed to write statutory account mapping as CSV", &e)
+        })
+    }
+
+    /// Values for hledger's `--alias` option. Unlike directives, these are
+    /// applied in order, so sub-accounts come first.
+    #[cfg(feature = "hledger")]
+    pub(crate) fn alias_args(&self) -> Vec<String> {
+        let mut args = self
+            .aliases()
+            .map(|(from, to)| format!("{}={}", from, to))
+            .collect::<Vec<_>>();
+        args.reverse();
+        args
+    }
+
+    /// Ordered by ledger name (so parents come before their sub-accounts).
+    fn aliases(&self) -> impl Iterator<Item = (&str, String)> {
+        self.accounts.iter().map(|(ledger, statutory)| {
+            let root = ledger.split(':').next().unwrap_or_default();
+            (
+                ledger.as_str(),
+                format!("{}:{} {}", root, statutory.code, statutory.name),
+            )
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{
+    entities::{
+        asset_tl, equity_tl, expense_tl, income_tl, liability_tl, AssetClassification,
+        EquityClassification, ExpenseClassification, IncomeClassification, LiabilityClassification,
+    },
+    ext::standard_accounts::{
+        BANK_INTEREST, CARD_INTEREST_AND_FEES, FOREIGN_TRANSACTION_FEE, PAYMENT_FEES,
+        SOCIAL_INSURANCE_EXPENSE, SOCIAL_INSURANCE_PAYABLE, VAT_PAYABLE, VAT_RECEIVABLE,
+        WITHHOLDING_TAX_PAYABLE,
+    },
+};
+
+use super::account_mapping::StatutoryAccountMapping;
+
+impl StatutoryAccountMapping {
+    /// Korean standard account codes (계정과목 코드), following the code ranges
+    /// common to Korean accounting software (1xx assets, 2xx liabilities, 3xx
+    /// equity, 4xx revenue and cost of sales, 8xx selling and administrative
+    /// expenses, 9xx non-operating income and expenses).
+    ///
+    /// Each classification is mapped to its most typical account, plus the
+    /// standard named accounts with a dedicated code (ex. VAT). Named
+    /// accounts of the handlers should be mapped with `with_account` where
+    /// the company's chart uses a more specific code.
+    pub fn korea() -> Self {
+        use AssetClassification as A;
+        use EquityClassification as Q;
+        use ExpenseClassification as X;
+        use IncomeClassification as I;
+        use LiabilityClassification as L;
+
+        let mut mapping = Self::new();
+        for (classification, code, name) in [
+            (A::CashAndCashEquivalents, "101", "현금"),
+            (A::ShortTermDeposits, "105", "정기예금"),
+            (A::ShortTermInvestments, "107", "단기매매증권"),
+            (A::AccountsReceivable, "108", "외상매출금"),
+            (A::OtherCurrentAssets, "120", "미수금"),
+            (A::PrepaidExpenses, "133", "선급비용"),
+            (A::Inventory, "146", "상품"),
+            (A::LongTermInvestments, "178", "매도가능증권"),
+            (A::DeferredIncomeTax, "189", "이연법인세자산"),
+            (A::OtherNonCurrentAssets, "195", "기타비유동자산"),
+            (A::PropertyPlantEquipment, "212", "비품"),
+            (A::AccumulatedDepreciation, "213", "감가상각누계액"),
+            (A::IntangibleAssets, "240", "소프트웨어"),
+            (A::AccumulatedAmortization, "241", "상각누계액"),
+            (A::LongTermDeposits, "962", "임차보증금"),
+        ] {
+            mapping = mapping.with_account(asset_tl(classification), code, name);
+        }
+        for (classification, code, name) in [
+            (L::AccountsPayable, "251", "외상매입금"),
+            (L::OtherCurrentLiabilities, "253", "미지급금"),
+            (L::DeferredRevenue, "259", "선수금"),
+            (L::ShortTermDebt, "260", "단기차입금"),
+            (L::AccruedExpenses, "262", "미지급비용"),
+            (L::LongTermDebt, "293", "장기차입금"),
+            (L::OtherNonCurrentLiabilities, "305", "기타비유동부채"),
+            (L::DeferredIncomeTax, "310", "이연법인세부채"),
+        ] {
+            mapping = mapping.with_account(liability_tl(classification), code, name);
+        }
+        for (classification, code, name) in [
+            (Q::CommonStock, "331", "보통주자본금"),
+            (Q::PreferredStock, "332", "우선주자본금"),
+            (Q::UnpaidShareCapital, "333", "미납입자본금"),
+            (Q::SharePremium, "341", "주식발행초과금"),
+            (Q::ContributedSurplus, "343", "기타자본잉여금"),
+            (Q::RetainedEarnings, "375", "이월이익잉여금"),
+            (Q::DiscountOnStockIssuance, "381", "주식할인발행차금"),
+            (Q::TreasuryStock, "383", "자기주식"),
+            (
+                Q::AccumulatedOtherComprehensiveIncome,
+                "392",
+                "기타포괄손익누계액",
+            ),
+        ] {
+            mapping = mapping.with_account(equity_tl(classification), code, name);
+        }
+        for (classification, code, name) in [
+            (I::SalesRevenue, "401", "상품매출"),
+            (I::ServiceRevenue, "411", "용역매출"),
+            (I::InterestIncome, "901", "이자수익"),
+            (I::NonCoreInterestIncome, "901", "이자수익"),
+            (I::DividendIncome, "903", "배당금수익"),
+            (I::NonCoreDividendIncome, "903", "배당금수익"),
+            (I::RentalIncome, "904", "임대료"),
+            (I::NonCoreRentalIncome, "904", "임대료"),
+            (I::RealizedFxGain, "907", "외환차익"),
+            (I::GainOnSaleOfAssets, "914", "유형자산처분이익"),
+            (I::OtherNonCoreFinancialIncome, "930", "잡이익"),
+            (I::VatRefundGain, "930", "잡이익"),
+            (I::OtherNonOperatingIncome, "930", "잡이익"),
+        ] {
+            mapping = mapping.with_account(income_tl(classification), code, name);
+        }
+        for (classification, code, name) in [
+            (X::CostOfGoodsSold, "451", "상품매출원가"),
+            (X::OtherTaxExpense, "817", "세금과공과"),
+            (X::DepreciationExpense, "818", "감가상각비"),
+            (X::CloudServicesExpenses, "831", "지급수수료"),
+            (X::SellingExpenses, "833", "광고선전비"),
+            (X::AmortizationExpense, "840", "무형자산상각비"),
+            (X::GeneralAdministrativeExpenses, "848", "잡비"),
+            (X::ResearchAndDevelopmentExpenses, "852", "경상연구개발비"),
+            (X::InterestExpense, "951", "이자비용"),
+            (X::NonCoreInterestExpense, "951", "이자비용"),
+            (X::RealizedFxLoss, "952", "외환차손"),
+            (X::LossOnSaleOfAssets, "970", "유형자산처분손실"),
+            (X::VatRefundLoss, "980", "잡손실"),
+            (X::OtherNonCoreFinancialExpense, "980", "잡손실"),
+            (X::OtherNonOperatingCashExpense, "980", "잡손실"),
+            (X::IncomeTaxExpense, "998", "법인세등"),
+        ] {
+            mapping = mapping.with_account(expense_tl(classification), code, name);
+        }
+        mapping
+            .with_account(VAT_RECEIVABLE.clone(), "135", "부가세대급금")
+            .with_account(WITHHOLDING_TAX_PAYABLE.clone(), "254", "예수금")
+            .with_account(SOCIAL_INSURANCE_PAYABLE.clone(), "254", "예수금")
+            .with_account(VAT_PAYABLE.clone(), "255", "부가세예수금")
+            .with_account(SOCIAL_INSURANCE_EXPENSE.clone(), "811", "복리후생비")
+            .with_account(PAYMENT_FEES.clone(), "831", "지급수수료")
+            .with_account(FOREIGN_TRANSACTION_FEE.clone(), "831", "지급수수료")
+            .with_account(BANK_INTEREST.clone(), "901", "이자수익")
+            .with_account(CARD_INTEREST_AND_FEES.clone(), "951", "이자비용")
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{Account, Commodity, SpecHook, SpecOutcome, Transaction},
+    errors::RoundTripMismatch,
+};
+
+/// Spec hook checking that each spec's generated transactions add up to the
+/// spec (after decorators), catching double-posted or missing postings in
+/// custom decorators and hooks before the journal is written:
+///
+/// - Every transaction (including those added by decorators) balances.
+/// - The spec's own transactions move exactly the spec amount through its
+///   backing account (cash, reimbursable entity or shareholder).
+/// - For logics that book the amount to a single account (see
+///   `SpecSummary::target_account`), that account ends up with exactly the
+///   spec amount, so any prepaid, payable or receivable balances used along
+///   the way are cleared.
+///
+/// Hooks that rename accounts should be added after this one.
+#[derive(Debug, Default)]
+pub struct RoundTripVerifier;
+
+impl RoundTripVerifier {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl SpecHook for RoundTripVerifier {
+    fn after_spec(&self, outcome: &mut SpecOutcome) -> Result<(), ServerError> {
+        for tx in outcome
+            .transactions
+            .iter()
+            .chain(outcome.ext_transactions.iter())
+        {
+            verify_balanced(tx)?;
+        }
+
+        let spec = &outcome.spec;
+        // Remeasurements don't move any cash.
+        if spec.accounting_logic != "RemeasureInvestment" {
+            verify_net(
+                &outcome.transactions,
+                &spec.backing_account,
+                spec.amount,
+                spec.commodity,
+                "backing account",
+            )?;
+        }
+        if let Some(target_account) = &spec.target_account {
+            verify_net(
+                &outcome.transactions,
+                target_account,
+                -spec.amount,
+                spec.commodity,
+                "target account",
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn verify_balanced(tx: &Transaction) -> Result<(), ServerError> {
+    let mut totals: BTreeMap<&str, (Commodity, f64)> = BTreeMap::new();
+    for posting in &tx.postings {
+        let (amount, commodity) = posting.cost();
+        totals.entry(commodity.code()).or_insert((commodity, 0.0)).1 += amount;
+    }
+    match totals
+        .into_values()
+        .find(|(commodity, total)| total.abs() >= precision_cutoff(*commodity))
+    {
+        Some((commodity, total)) => Err(RoundTripMismatch::new(&format!(
+            "transaction on {} is unbalanced by {:.2} {}",
+            tx.date,
+            total,
+            commodity.code()
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn verify_net(
+    transactions: &[Transaction],
+    account: &Account,
+    expected: f64,
+    commodity: Commodity,
+    role: &str,
+) -> Result<(), ServerError> {
+    let net: f64 = transactions
+        .iter()
+        .flat_map(|tx| tx.postings.iter())
+        .filter(|p| &p.account == account && p.commodity == commodity)
+        .map(|p| p.amount)
+        .sum();
+    if (net - expected).abs() >= precision_cutoff(commodity) {
+        return Err(RoundTripMismatch::new(&format!(
+            "net change of {} '{}' is {:.2} {}, expected {:.2}",
+            role,
+            account.ledger(),
+            net,
+            commodity.code(),
+            expected
+        )));
+    }
+    Ok(())
+}
+
+/// Smallest difference that would display as a non-zero amount.
+fn precision_cutoff(commodity: Commodity) -> f64 {
+    0.5 / 10_f64.powi(commodity.decimal_places() as i32)
+}
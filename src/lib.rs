@@ -10,4 +10,10 @@ pub mod ext {
 }
 
 pub mod errors;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod util;
@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use fractic_server_error::{CriticalError, ServerError};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+
+use crate::{
+    data::repositories::records_repository_impl::RecordsRepositoryImpl,
+    domain::repositories::records_repository::RecordsRepository as _,
+    entities::{CommodityHandler as _, FinancialRecordSpecs, FinancialRecords, PipelineConfig},
+    errors::InvalidStandardStatementPeriods,
+    ext::{
+        custom_statements::{
+            CashFlowStatementGenerator, FinancialRatiosGenerator, ReportPeriod, StandardStatement,
+            StandardStatementGenerator, StatementFormat,
+        },
+        standard_handlers::{ConfiguredCommodity, ConfiguredHandlers, HandlersConfig},
+    },
+    util::{check_assertions, process_and_print, Ledger},
+};
+
+// Python bindings, built as the `fractic_ifrs_hledger` extension module (ex.
+// with `maturin develop`, see `pyproject.toml`). Handlers are resolved by name
+// against a configuration loaded at runtime (see `ConfiguredHandlers`), since
+// they can't be written in Python. Ex.:
+//
+// ```python
+// import fractic_ifrs_hledger as ih
+//
+// handlers = ih.Handlers.from_json(open("handlers.json").read())
+// specs = ih.Specs.from_csv(handlers, open("transactions.csv").read())
+// records = ih.process(specs)
+// print(records.journal())
+// balance_sheet = json.loads(records.statement("balance-sheet", "2024"))
+// ```
+//
+// Errors are raised as `IfrsHledgerError`, with the error code at the start
+// of the message (ex. "[E104] Invalid AccountingLogic ...").
+
+create_exception!(fractic_ifrs_hledger, IfrsHledgerError, PyException);
+
+#[pymodule]
+fn fractic_ifrs_hledger(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("IfrsHledgerError", m.py().get_type::<IfrsHledgerError>())?;
+    m.add_function(wrap_pyfunction!(process, m)?)?;
+    m.add_class::<Handlers>()?;
+    m.add_class::<Specs>()?;
+    m.add_class::<Records>()?;
+    Ok(())
+}
+
+/// Handlers configuration (JSON, same format as the CLI's `--handlers` file)
+/// that names in the specs are resolved against.
+#[pyclass(frozen)]
+struct Handlers {
+    config: Arc<HandlersConfig>,
+}
+
+#[pymethods]
+impl Handlers {
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        Ok(Self {
+            config: Arc::new(HandlersConfig::from_json(json).map_err(to_py)?),
+        })
+    }
+}
+
+/// Process the specs into records (and their journal), with the options of
+/// the given pipeline config (TOML, see `PipelineConfig`), if any.
+#[pyfunction]
+#[pyo3(signature = (specs, config_toml = None))]
+fn process(py: Python<'_>, specs: &Specs, config_toml: Option<&str>) -> PyResult<Records> {
+    let config = match config_toml {
+        Some(toml) => PipelineConfig::from_toml(toml).map_err(to_py)?,
+        None => PipelineConfig::default(),
+    };
+    let handlers = specs.handlers.clone();
+    // Names are resolved as the specs are parsed.
+    let specs = specs.parse().map_err(to_py)?;
+    py.allow_threads(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| CriticalError::with_debug("failed to start async runtime", &e))?;
+        let (records, _, ledger) = runtime.block_on(process_and_print(
+            specs,
+            config.process_options,
+            config.print_options,
+        ))?;
+        Ok(Records {
+            records,
+            ledger,
+            handlers,
+        })
+    })
+    .map_err(to_py)
+}
+
+/// Transaction, balance and budget specs (CSV), with the handlers they're
+/// resolved against. The specs are validated when loaded.
+#[pyclass(frozen)]
+struct Specs {
+    handlers: Arc<HandlersConfig>,
+    transactions_csv: String,
+    balances_csv: String,
+    budgets_csv: Option<String>,
+    transaction_count: usize,
+}
+
+#[pymethods]
+impl Specs {
+    #[staticmethod]
+    #[pyo3(signature = (handlers, transactions, balances = "", budgets = None))]
+    fn from_csv(
+        handlers: &Handlers,
+        transactions: &str,
+        balances: &str,
+        budgets: Option<&str>,
+    ) -> PyResult<Self> {
+        let mut specs = Self {
+            handlers: handlers.config.clone(),
+            transactions_csv: transactions.to_string(),
+            balances_csv: balances.to_string(),
+            budgets_csv: budgets.map(str::to_string),
+            transaction_count: 0,
+        };
+        specs.transaction_count = specs.parse().map_err(to_py)?.transaction_specs.len();
+        Ok(specs)
+    }
+
+    /// Number of transaction specs.
+    fn __len__(&self) -> usize {
+        self.transaction_count
+    }
+}
+
+impl Specs {
+    /// Specs are consumed by processing, so they're parsed again each time.
+    fn parse(&self) -> Result<FinancialRecordSpecs<ConfiguredHandlers>, ServerError> {
+        ConfiguredHandlers::with_config(&self.handlers, || {
+            let repository = RecordsRepositoryImpl::<ConfiguredHandlers>::new();
+            let mut specs = repository.from_string(&self.transactions_csv, &self.balances_csv)?;
+            if let Some(budgets) = &self.budgets_csv {
+                specs.budget_specs = repository.budgets_from_string(budgets)?;
+            }
+            Ok(specs)
+        })
+    }
+}
+
+/// Processed records, and their generated journal.
+#[pyclass(frozen)]
+struct Records {
+    records: FinancialRecords,
+    ledger: Ledger,
+    handlers: Arc<HandlersConfig>,
+}
+
+#[pymethods]
+impl Records {
+    /// The generated hledger journal.
+    fn journal(&self) -> &str {
+        &self.ledger
+    }
+
+    /// Number of generated transactions.
+    fn __len__(&self) -> usize {
+        self.records.transactions.len()
+    }
+
+    /// Balance assertions that don't hold for the generated transactions, as
+    /// dicts with the `date`, `account`, `commodity`, `expected` and `actual`
+    /// balances.
+    fn failed_assertions<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        check_assertions(&self.records)
+            .into_iter()
+            .map(|failed| {
+                let dict = PyDict::new(py);
+                dict.set_item("date", failed.assertion.date.to_string())?;
+                dict.set_item("account", failed.assertion.account.ledger())?;
+                dict.set_item("commodity", failed.assertion.commodity.code())?;
+                dict.set_item("expected", failed.assertion.balance)?;
+                dict.set_item("actual", failed.actual)?;
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// Statement as a JSON document (requires the hledger binary): one of
+    /// `balance-sheet`, `income-statement`, `cash-flow` or `ratios`, for the
+    /// comma-separated periods (ex. `"2024,2023"`; a single period for balance
+    /// sheets and income statements). Cash flow statements take a `currency`
+    /// (defaults to the default commodity's).
+    #[pyo3(signature = (statement, period, currency = None))]
+    fn statement(
+        &self,
+        py: Python<'_>,
+        statement: &str,
+        period: &str,
+        currency: Option<String>,
+    ) -> PyResult<String> {
+        let periods = period
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<ReportPeriod>, ServerError>>()
+            .map_err(to_py)?;
+        let statement = match statement {
+            "balance-sheet" => Statement::Standard(StandardStatement::BalanceSheet),
+            "income-statement" => Statement::Standard(StandardStatement::IncomeStatement),
+            "cash-flow" => Statement::CashFlow,
+            "ratios" => Statement::Ratios,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown statement '{statement}' (expected balance-sheet, \
+                     income-statement, cash-flow or ratios)"
+                )))
+            }
+        };
+        let records = &self.records;
+        py.allow_threads(|| match statement {
+            Statement::Standard(statement) => {
+                let [period] = periods[..] else {
+                    return Err(InvalidStandardStatementPeriods::new(periods.len()));
+                };
+                StandardStatementGenerator::from_records(records, period, statement)
+                    .generate(StatementFormat::Json)
+            }
+            Statement::CashFlow => {
+                let currency = currency.unwrap_or_else(|| {
+                    ConfiguredHandlers::with_config(&self.handlers, ConfiguredCommodity::default)
+                        .iso_symbol()
+                });
+                CashFlowStatementGenerator::from_records(records, periods, currency)?
+                    .generate_with_format(StatementFormat::Json)
+            }
+            Statement::Ratios => FinancialRatiosGenerator::from_records(records, periods)?
+                .generate_with_format(StatementFormat::Json),
+        })
+        .map_err(to_py)
+    }
+}
+
+enum Statement {
+    Standard(StandardStatement),
+    CashFlow,
+    Ratios,
+}
+
+fn to_py(error: ServerError) -> PyErr {
+    IfrsHledgerError::new_err(error.to_string())
+}
@@ -0,0 +1,344 @@
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use fractic_server_error::{CriticalError, ServerError};
+use serde_derive::Deserialize;
+use serde_json::json;
+
+use crate::{
+    data::repositories::records_repository_impl::RecordsRepositoryImpl,
+    domain::repositories::records_repository::RecordsRepository as _,
+    entities::{
+        CommodityHandler, FinancialRecordSpecs, FinancialRecords, Handlers, PipelineConfig,
+        ProcessOptions,
+    },
+    errors::{ErrorCategory, InvalidStandardStatementPeriods, ServerErrorExt as _, UnknownJournal},
+    ext::custom_statements::{
+        CashFlowStatementGenerator, FinancialRatiosGenerator, ReportPeriod, StandardStatement,
+        StandardStatementGenerator, StatementFormat,
+    },
+    presentation::print_options::PrintOptions,
+    util::{check_assertions, process_and_print, Ledger},
+};
+
+/// HTTP service processing specs into journals, and generating statements
+/// from them (requires the hledger binary), so other tools can use this crate
+/// without linking it.
+///
+/// Journals are kept in memory until deleted, and identified by the ID
+/// returned when submitting their specs. Endpoints:
+///
+/// - `POST /journals`: process specs, given as a JSON object with the
+///   `transactions` CSV, and optionally the `balances` and `budgets` CSVs.
+///   Responds with the journal's `id`.
+/// - `GET /journals/{id}`: the generated hledger journal.
+/// - `DELETE /journals/{id}`
+/// - `GET /journals/{id}/statements/{statement}`: one of `balance-sheet`,
+///   `income-statement`, `cash-flow` or `ratios`, for the comma-separated
+///   `period` query (ex. `2024,2023`; a single period for balance sheets and
+///   income statements), in the given `format` (`text`, `json` or `csv`).
+///   Cash flow statements take a `currency` (defaults to the default
+///   commodity's).
+///
+/// Errors respond with their code and message as JSON, with status 404 for
+/// unknown journals, 400 for invalid input, 422 for accounting rule
+/// violations, 502 if hledger failed, and 500 otherwise.
+pub struct JournalService<H: Handlers> {
+    process_options: ProcessOptions,
+    print_options: PrintOptions,
+    parse_scope: Option<ParseScope>,
+    journals: RwLock<HashMap<u64, Arc<StoredJournal>>>,
+    next_id: AtomicU64,
+    _phantom: PhantomData<H>,
+}
+
+/// Wraps parsing the submitted specs (see `JournalService::with_parse_scope`).
+type ParseScope = Box<dyn Fn(&mut dyn FnMut()) + Send + Sync>;
+
+struct StoredJournal {
+    records: FinancialRecords,
+    ledger: Ledger,
+    /// ISO code of the default commodity, resolved with the specs.
+    default_currency: String,
+}
+
+#[derive(Deserialize)]
+struct SubmitSpecs {
+    transactions: String,
+    #[serde(default)]
+    balances: String,
+    budgets: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum StatementKind {
+    BalanceSheet,
+    IncomeStatement,
+    CashFlow,
+    Ratios,
+}
+
+#[derive(Deserialize)]
+struct StatementQuery {
+    period: String,
+    #[serde(default)]
+    format: Format,
+    currency: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+/// `ServerError` as an HTTP response.
+struct ApiError(ServerError);
+
+impl<H: Handlers> JournalService<H> {
+    pub fn new() -> Self {
+        Self {
+            process_options: ProcessOptions::default(),
+            print_options: PrintOptions::default(),
+            parse_scope: None,
+            journals: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Override the options used when processing specs.
+    pub fn with_process_options(mut self, options: ProcessOptions) -> Self {
+        self.process_options = options;
+        self
+    }
+
+    /// Override the layout options used when printing journals.
+    pub fn with_print_options(mut self, options: PrintOptions) -> Self {
+        self.print_options = options;
+        self
+    }
+
+    /// Parse the submitted specs within the given scope, for handlers
+    /// resolving names while parsed. Ex.:
+    ///
+    /// ```ignore
+    /// JournalService::<ConfiguredHandlers>::new()
+    ///     .with_parse_scope(move |parse| ConfiguredHandlers::with_config(&config, parse))
+    /// ```
+    pub fn with_parse_scope(
+        mut self,
+        scope: impl Fn(&mut dyn FnMut()) + Send + Sync + 'static,
+    ) -> Self {
+        self.parse_scope = Some(Box::new(scope));
+        self
+    }
+
+    /// Use the processing and printing options from a loaded config.
+    pub fn with_config(self, config: &PipelineConfig) -> Self {
+        self.with_process_options(config.process_options.clone())
+            .with_print_options(config.print_options.clone())
+    }
+
+    /// Routes of the service, to be served with `axum::serve` (or nested in
+    /// an existing router).
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/journals", post(submit::<H>))
+            .route("/journals/{id}", get(journal::<H>).delete(delete::<H>))
+            .route("/journals/{id}/statements/{statement}", get(statement::<H>))
+            .with_state(Arc::new(self))
+    }
+
+    async fn process(&self, specs: SubmitSpecs) -> Result<StoredJournal, ServerError> {
+        let mut parsed = None;
+        let mut parse = || parsed = Some(Self::parse(&specs));
+        match &self.parse_scope {
+            Some(scope) => scope(&mut parse),
+            None => parse(),
+        }
+        let (input, default_currency) = parsed.ok_or_else(|| {
+            CriticalError::new("the parse scope of the journal service didn't parse the specs")
+        })??;
+        let (records, _, ledger) = process_and_print(
+            input,
+            self.process_options.clone(),
+            self.print_options.clone(),
+        )
+        .await?;
+        Ok(StoredJournal {
+            records,
+            ledger,
+            default_currency,
+        })
+    }
+
+    /// The specs, and the ISO code of the default commodity.
+    fn parse(specs: &SubmitSpecs) -> Result<(FinancialRecordSpecs<H>, String), ServerError> {
+        let repository = RecordsRepositoryImpl::<H>::new();
+        let mut input = repository.from_string(&specs.transactions, &specs.balances)?;
+        if let Some(budgets) = &specs.budgets {
+            input.budget_specs = repository.budgets_from_string(budgets)?;
+        }
+        Ok((input, H::M::default().iso_symbol()))
+    }
+
+    fn get(&self, id: u64) -> Result<Arc<StoredJournal>, ServerError> {
+        self.journals
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| UnknownJournal::new(id))
+    }
+}
+
+impl<H: Handlers> Default for JournalService<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn submit<H: Handlers>(
+    State(service): State<Arc<JournalService<H>>>,
+    Json(specs): Json<SubmitSpecs>,
+) -> Result<Response, ApiError> {
+    let journal = service.process(specs).await?;
+    let id = service.next_id.fetch_add(1, Ordering::Relaxed);
+    let body = json!({
+        "id": id,
+        "transactions": journal.records.transactions.len(),
+        "failed_assertions": check_assertions(&journal.records).len(),
+    });
+    service
+        .journals
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id, Arc::new(journal));
+    Ok((StatusCode::CREATED, Json(body)).into_response())
+}
+
+async fn journal<H: Handlers>(
+    State(service): State<Arc<JournalService<H>>>,
+    Path(id): Path<u64>,
+) -> Result<Response, ApiError> {
+    let journal = service.get(id)?;
+    Ok(text(&journal.ledger, "text/plain; charset=utf-8"))
+}
+
+async fn delete<H: Handlers>(
+    State(service): State<Arc<JournalService<H>>>,
+    Path(id): Path<u64>,
+) -> Result<StatusCode, ApiError> {
+    service
+        .journals
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&id)
+        .ok_or_else(|| UnknownJournal::new(id))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn statement<H: Handlers>(
+    State(service): State<Arc<JournalService<H>>>,
+    Path((id, statement)): Path<(u64, StatementKind)>,
+    Query(query): Query<StatementQuery>,
+) -> Result<Response, ApiError> {
+    let journal = service.get(id)?;
+    let periods = query
+        .period
+        .split(',')
+        .map(|p| p.trim().parse())
+        .collect::<Result<Vec<ReportPeriod>, ServerError>>()?;
+    let format = match query.format {
+        Format::Text => StatementFormat::Text,
+        Format::Json => StatementFormat::Json,
+        Format::Csv => StatementFormat::Csv,
+    };
+    let currency = query
+        .currency
+        .unwrap_or_else(|| journal.default_currency.clone());
+    // Statements are generated by running hledger.
+    let output = tokio::task::spawn_blocking(move || {
+        let records = &journal.records;
+        match statement {
+            StatementKind::BalanceSheet | StatementKind::IncomeStatement => {
+                let [period] = periods[..] else {
+                    return Err(InvalidStandardStatementPeriods::new(periods.len()));
+                };
+                StandardStatementGenerator::from_records(
+                    records,
+                    period,
+                    match statement {
+                        StatementKind::BalanceSheet => StandardStatement::BalanceSheet,
+                        _ => StandardStatement::IncomeStatement,
+                    },
+                )
+                .generate(format)
+            }
+            StatementKind::CashFlow => {
+                CashFlowStatementGenerator::from_records(records, periods, currency)?
+                    .generate_with_format(format)
+            }
+            StatementKind::Ratios => FinancialRatiosGenerator::from_records(records, periods)?
+                .generate_with_format(format),
+        }
+    })
+    .await
+    .map_err(|e| CriticalError::with_debug("statement generation task failed", &e))??;
+    let content_type = match query.format {
+        Format::Text => "text/plain; charset=utf-8",
+        Format::Json => "application/json",
+        Format::Csv => "text/csv; charset=utf-8",
+    };
+    Ok(text(&output, content_type))
+}
+
+fn text(body: &str, content_type: &'static str) -> Response {
+    ([(header::CONTENT_TYPE, content_type)], body.to_string()).into_response()
+}
+
+impl From<ServerError> for ApiError {
+    fn from(error: ServerError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let code = self.0.code();
+        let status = match code {
+            Some(code) if code == UnknownJournal::CODE => StatusCode::NOT_FOUND,
+            Some(code) => match code.category {
+                ErrorCategory::InputError => StatusCode::BAD_REQUEST,
+                ErrorCategory::AccountingRuleViolation => StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCategory::ExternalToolError => StatusCode::BAD_GATEWAY,
+                ErrorCategory::InternalInvariant => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            None => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = json!({
+            "code": code.map(|c| c.code),
+            "message": self.0.to_string(),
+            "spec_id": self.0.spec_id().map(|id| id.0),
+        });
+        (status, Json(body)).into_response()
+    }
+}
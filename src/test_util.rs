@@ -0,0 +1,172 @@
+//! Helpers for regression-testing handlers and decorators against snapshots of
+//! the processed records (enabled by the `test-util` feature).
+//!
+//! Processing is deterministic given its inputs, except for FX rates, which
+//! `FixedFxProvider` pins to a fixed table, and the generation timestamp, which
+//! `FixedClock` pins to a fixed instant. Snapshots are rendered from the
+//! records rather than the printed journal, with a fixed layout that doesn't
+//! change with print options or the width of the longest account name.
+
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+#[cfg(feature = "fs")]
+pub mod golden;
+pub mod minimal;
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use fractic_server_error::ServerError;
+
+use crate::{
+    entities::{Clock, Commodity, FinancialRecords, FxProvider, Handlers, ProcessOptions},
+    errors::MissingFxRate,
+    printing::PrintOptions,
+    util,
+};
+
+/// Width of the account column in snapshots.
+const ACCOUNT_WIDTH: usize = 60;
+/// Width of the (right-aligned) amount column in snapshots.
+const AMOUNT_WIDTH: usize = 20;
+
+/// FX provider returning the same rate on every date. The inverse of each
+/// rate is derived automatically.
+#[derive(Debug, Clone, Default)]
+pub struct FixedFxProvider {
+    rates: HashMap<(String, String), f64>,
+}
+
+impl FixedFxProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Amount of `to` per unit of `from` (ex. `with_rate("USD", "KRW",
+    /// 1300.0)`).
+    pub fn with_rate(mut self, from: &str, to: &str, rate: f64) -> Self {
+        self.rates.insert((from.into(), to.into()), rate);
+        self
+    }
+
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        self.rates
+            .get(&(from.into(), to.into()))
+            .copied()
+            .or_else(|| {
+                self.rates
+                    .get(&(to.into(), from.into()))
+                    .map(|rate| 1.0 / rate)
+            })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl FxProvider for FixedFxProvider {
+    async fn convert(
+        &self,
+        _date: NaiveDate,
+        from: &str,
+        to: &str,
+        amount: f64,
+    ) -> Result<f64, ServerError> {
+        self.rate(from, to)
+            .map(|rate| amount * rate)
+            .ok_or_else(|| MissingFxRate::new(from, to))
+    }
+}
+
+/// Clock always returning the same instant, for journals printed with
+/// `PrintOptions::generation_header` (see `ProcessOptions::clock`).
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Parse and process the CSVs with default options, and render the records
+/// as a snapshot (see `render_snapshot`).
+pub async fn snapshot_csv<H: Handlers>(
+    transactions_csv: &str,
+    balances_csv: &str,
+) -> Result<String, ServerError> {
+    let (records, _, _) = util::process_csv::<H>(
+        transactions_csv,
+        balances_csv,
+        ProcessOptions::default(),
+        PrintOptions::default(),
+    )
+    .await?;
+    Ok(render_snapshot(&records))
+}
+
+/// Render the transactions and assertions in a normalized journal format,
+/// suitable for snapshot tests (ex. `insta::assert_snapshot!`):
+///
+/// - Transactions are ordered by date, then spec ID. Postings keep their
+///   order within the transaction.
+/// - Accounts and amounts are printed in fixed-width columns, with amounts
+///   rounded to the currency's precision and no thousands separators.
+/// - Tags are sorted by name. Annotations and notes are left out, so
+///   rewording them doesn't invalidate snapshots.
+pub fn render_snapshot(records: &FinancialRecords) -> String {
+    let mut transactions: Vec<_> = records.transactions.iter().collect();
+    transactions.sort_by_key(|tx| (tx.date, tx.spec_id.0));
+
+    let mut output = String::new();
+    for tx in transactions {
+        let label = records
+            .label_lookup
+            .get(&tx.spec_id)
+            .map(|l| format!("{} | {}", l.payee, l.description))
+            .unwrap_or_default();
+        output.push_str(&format!("{} ({}) {}\n", tx.date, tx.spec_id, label.trim()));
+        for posting in &tx.postings {
+            let mut tags: Vec<String> = posting
+                .custom_tags
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, v))
+                .collect();
+            tags.sort();
+            let line = format!(
+                "    {:<ACCOUNT_WIDTH$} {:>AMOUNT_WIDTH$}",
+                posting.account.ledger(),
+                format_fixed(posting.amount, posting.commodity),
+            );
+            if tags.is_empty() {
+                output.push_str(&format!("{}\n", line));
+            } else {
+                output.push_str(&format!("{}  ; {}\n", line, tags.join(", ")));
+            }
+        }
+        output.push('\n');
+    }
+
+    let mut assertions: Vec<_> = records.assertions.iter().collect();
+    assertions.sort_by_key(|a| (a.date, a.account.ledger(), a.commodity.code()));
+    for assertion in assertions {
+        output.push_str(&format!(
+            "{} assert\n    {:<ACCOUNT_WIDTH$} {:>AMOUNT_WIDTH$}\n\n",
+            assertion.date,
+            assertion.account.ledger(),
+            format!("= {}", format_fixed(assertion.balance, assertion.commodity)),
+        ));
+    }
+    output
+}
+
+fn format_fixed(amount: f64, commodity: Commodity) -> String {
+    let decimals = commodity.decimal_places() as usize;
+    let factor = 10_f64.powi(decimals as i32);
+    // Avoid printing '-0.00'.
+    let amount = (amount * factor).round() / factor + 0.0;
+    format!("{:.decimals$} {}", amount, commodity.code())
+}
@@ -0,0 +1,223 @@
+//! `proptest` strategies for specs (enabled by the `proptest` feature), for
+//! property tests over random inputs (ex. "all generated transactions
+//! balance").
+//!
+//! Generated specs respect the processor's invariants: expenses are negative
+//! and income positive, only logics with a period have an accrual end, and
+//! accrual periods end on a month end after their start (since accruals are
+//! booked at month ends). Reimbursed specs are only generated for logics
+//! that book a single debit, since the reimbursement tracer doesn't support
+//! fixed expenses split between prepaid and payable amounts. Logics that
+//! depend on earlier
+//! specs (`VariableExpense`, `Reimburse`, `PayCardStatement`, etc.) or on
+//! handler configuration (`Amortize`, `CommonStock`) are not generated.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike as _, Duration, Months, NaiveDate};
+use proptest::prelude::*;
+
+use super::minimal;
+use crate::entities::{
+    AccountingLogic, BackingAccount, FinancialRecordSpecs, Handlers, TransactionSpec,
+    TransactionSpecId, TransactionStatus,
+};
+
+/// Generated dates fall in [`FIRST_DATE`, `FIRST_DATE` + `DATE_RANGE_DAYS`).
+const FIRST_DATE: NaiveDate = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+const DATE_RANGE_DAYS: i64 = 5 * 365;
+
+/// Longest accrual period generated, in months.
+const MAX_PERIOD_MONTHS: u32 = 12;
+/// Largest difference between the accrual and payment dates, in days.
+const MAX_PAYMENT_OFFSET_DAYS: i64 = 90;
+
+/// Dates within a five-year range starting in 2020.
+pub fn dates() -> impl Strategy<Value = NaiveDate> {
+    (0..DATE_RANGE_DAYS).prop_map(|days| FIRST_DATE + Duration::days(days))
+}
+
+/// Non-zero amounts with two decimals, up to 100,000.00. Negative if
+/// `negative` is set (ex. for expenses).
+pub fn amounts(negative: bool) -> impl Strategy<Value = f64> {
+    (1..=10_000_000_i64).prop_map(move |cents| {
+        let amount = cents as f64 / 100.0;
+        if negative {
+            -amount
+        } else {
+            amount
+        }
+    })
+}
+
+impl<E, A, I, R, S> Arbitrary for AccountingLogic<E, A, I, R, S>
+where
+    E: Arbitrary + 'static,
+    A: Arbitrary + 'static,
+    I: Arbitrary + 'static,
+    R: std::fmt::Debug + 'static,
+    S: std::fmt::Debug + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        prop_oneof![
+            any::<E>().prop_map(AccountingLogic::SimpleExpense),
+            any::<E>().prop_map(AccountingLogic::FixedExpense),
+            any::<E>().prop_map(AccountingLogic::ImmaterialExpense),
+            any::<A>().prop_map(AccountingLogic::Capitalize),
+            any::<I>().prop_map(AccountingLogic::ImmaterialIncome),
+        ]
+        .boxed()
+    }
+}
+
+impl<H: Handlers> Arbitrary for TransactionSpec<H>
+where
+    H::E: Arbitrary,
+    H::A: Arbitrary,
+    H::I: Arbitrary,
+    H::R: Arbitrary,
+    H::C: Arbitrary,
+    H::P: Arbitrary,
+    H::M: Arbitrary,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// The spec ID is always 0; use `spec_sets` for unique IDs.
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            any::<AccountingLogic<H::E, H::A, H::I, H::R, H::S>>(),
+            dates(),
+            0..MAX_PERIOD_MONTHS,
+            -MAX_PAYMENT_OFFSET_DAYS..=MAX_PAYMENT_OFFSET_DAYS,
+            any::<H::P>(),
+            "[A-Za-z][A-Za-z ]{0,19}",
+            any::<H::M>(),
+            any::<H::C>(),
+            proptest::option::weighted(0.2, any::<H::R>()),
+        )
+            .prop_flat_map(
+                |(logic, accrual_start, period, offset, payee, description, commodity, cash, r)| {
+                    let is_income = matches!(logic, AccountingLogic::ImmaterialIncome(_));
+                    let is_fixed = matches!(logic, AccountingLogic::FixedExpense(_));
+                    let accrual_end =
+                        is_fixed.then(|| month_end(accrual_start + Months::new(period)));
+                    let backing_account = match r {
+                        // Income is always received in cash.
+                        Some(r) if !is_income && !is_fixed => BackingAccount::Reimburse(r),
+                        _ => BackingAccount::Cash(cash),
+                    };
+                    let payment_date = accrual_start + Duration::days(offset);
+                    amounts(!is_income).prop_map(move |amount| TransactionSpec {
+                        id: TransactionSpecId(0),
+                        accrual_start,
+                        accrual_end,
+                        payment_date,
+                        accounting_logic: logic.clone(),
+                        decorators: Vec::new(),
+                        payee: payee.clone(),
+                        description: description.clone(),
+                        amount,
+                        commodity: commodity.clone(),
+                        backing_account: backing_account.clone(),
+                        annotations: Vec::new(),
+                        intercompany: None,
+                        dimensions: BTreeMap::new(),
+                        status: TransactionStatus::Unmarked,
+                        entity: None,
+                        recurrence: None,
+                        amends: None,
+                    })
+                },
+            )
+            .boxed()
+    }
+}
+
+/// Sets of up to `max_specs` transaction specs with unique IDs, and no
+/// assertions, commands or budgets.
+pub fn spec_sets<H: Handlers>(max_specs: usize) -> impl Strategy<Value = FinancialRecordSpecs<H>>
+where
+    TransactionSpec<H>: Arbitrary,
+{
+    proptest::collection::vec(any::<TransactionSpec<H>>(), 1..=max_specs).prop_map(|specs| {
+        FinancialRecordSpecs {
+            transaction_specs: specs
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut spec)| {
+                    // Same numbering as CSV input (line numbers).
+                    spec.id = TransactionSpecId((i + 2) as u64);
+                    spec
+                })
+                .collect(),
+            assertion_specs: Vec::new(),
+            commands: Vec::new(),
+            budget_specs: Vec::new(),
+        }
+    })
+}
+
+macro_rules! arbitrary_variants {
+    ($type:ty, [$($variant:expr),+ $(,)?]) => {
+        impl Arbitrary for $type {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                prop_oneof![$(Just($variant)),+].boxed()
+            }
+        }
+    };
+}
+
+// Handlers of `MinimalHandlers`, so `spec_sets::<MinimalHandlers>` can be used
+// directly.
+arbitrary_variants!(
+    minimal::Asset,
+    [minimal::Asset::Equipment, minimal::Asset::Software]
+);
+arbitrary_variants!(
+    minimal::Income,
+    [
+        minimal::Income::Sales,
+        minimal::Income::Services,
+        minimal::Income::Interest,
+        minimal::Income::Other,
+    ]
+);
+arbitrary_variants!(
+    minimal::Expense,
+    [
+        minimal::Expense::Rent,
+        minimal::Expense::Salaries,
+        minimal::Expense::Supplies,
+        minimal::Expense::Cloud,
+        minimal::Expense::BankFees,
+        minimal::Expense::Other,
+    ]
+);
+arbitrary_variants!(minimal::Cash, [minimal::Cash::Bank, minimal::Cash::UsdBank]);
+arbitrary_variants!(minimal::Reimbursable, [minimal::Reimbursable::Founder]);
+arbitrary_variants!(
+    minimal::Commodity,
+    [minimal::Commodity::Krw, minimal::Commodity::Usd]
+);
+
+impl Arbitrary for minimal::Payee {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        "[A-Z][a-z]{2,11}".prop_map(minimal::Payee).boxed()
+    }
+}
+
+fn month_end(date: NaiveDate) -> NaiveDate {
+    (date.with_day(1).expect("day 1 should always be valid") + Months::new(1))
+        .pred_opt()
+        .expect("date should have a predecessor")
+}
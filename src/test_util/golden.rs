@@ -0,0 +1,255 @@
+//! Golden-file regression runner, for maintaining a corpus of real-world
+//! scenarios as executable tests (enabled by the `test-util` and `fs`
+//! features).
+//!
+//! Each subdirectory of the corpus directory is one case:
+//!
+//! ```text
+//! scenarios/
+//!   prepaid-rent/
+//!     transactions.csv
+//!     balances.csv        (optional)
+//!     expected.journal
+//! ```
+//!
+//! Subdirectories without a `transactions.csv` are ignored, so shared
+//! fixtures can live alongside the cases. Ex.:
+//!
+//! ```ignore
+//! #[tokio::test]
+//! async fn golden() {
+//!     GoldenRunner::new("tests/scenarios")
+//!         .bless(std::env::var_os("BLESS").is_some())
+//!         .run::<MyHandlers>()
+//!         .await
+//!         .unwrap()
+//!         .assert_passed();
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use fractic_server_error::ServerError;
+use similar::TextDiff;
+
+use crate::{
+    entities::{Handlers, ProcessOptions},
+    errors::{ReadError, WriteError},
+    printing::PrintOptions,
+    util,
+};
+
+pub const TRANSACTIONS_FILE: &str = "transactions.csv";
+pub const BALANCES_FILE: &str = "balances.csv";
+pub const EXPECTED_FILE: &str = "expected.journal";
+
+pub struct GoldenRunner {
+    dir: PathBuf,
+    process_options: ProcessOptions,
+    print_options: PrintOptions,
+    bless: bool,
+}
+
+#[derive(Debug)]
+pub struct GoldenReport {
+    /// One result per case, ordered by directory name.
+    pub cases: Vec<GoldenCase>,
+}
+
+#[derive(Debug)]
+pub struct GoldenCase {
+    /// Name of the case's directory.
+    pub name: String,
+    pub outcome: GoldenOutcome,
+}
+
+#[derive(Debug)]
+pub enum GoldenOutcome {
+    Passed,
+    /// Output differs from the expected journal. Holds a unified diff from
+    /// the expected to the actual output.
+    Mismatch(String),
+    /// No expected journal exists yet (run with `bless` to create it).
+    MissingExpected,
+    /// Expected journal was written (or rewritten) in bless mode.
+    Blessed,
+    /// Specs failed to parse or process.
+    Failed(ServerError),
+}
+
+impl GoldenRunner {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            process_options: ProcessOptions::default(),
+            print_options: PrintOptions::default(),
+            bless: false,
+        }
+    }
+
+    pub fn with_process_options(mut self, options: ProcessOptions) -> Self {
+        self.process_options = options;
+        self
+    }
+
+    pub fn with_print_options(mut self, options: PrintOptions) -> Self {
+        self.print_options = options;
+        self
+    }
+
+    /// Instead of comparing, write each case's output as its expected
+    /// journal. Useful after intended changes to the output; review the
+    /// resulting changes to the corpus before committing them.
+    pub fn bless(mut self, bless: bool) -> Self {
+        self.bless = bless;
+        self
+    }
+
+    /// Process every case in the corpus. Errors are only returned if the
+    /// corpus itself can't be read or written; failures of individual cases
+    /// are reported in their outcome.
+    pub async fn run<H: Handlers>(&self) -> Result<GoldenReport, ServerError> {
+        let mut cases = Vec::new();
+        for dir in case_dirs(&self.dir).await? {
+            let name = dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let outcome = self.run_case::<H>(&dir).await?;
+            cases.push(GoldenCase { name, outcome });
+        }
+        Ok(GoldenReport { cases })
+    }
+
+    async fn run_case<H: Handlers>(&self, dir: &Path) -> Result<GoldenOutcome, ServerError> {
+        let transactions = read(&dir.join(TRANSACTIONS_FILE)).await?;
+        let balances = read_optional(&dir.join(BALANCES_FILE))
+            .await?
+            .unwrap_or_default();
+        let actual = match util::process_csv::<H>(
+            &transactions,
+            &balances,
+            self.process_options.clone(),
+            self.print_options.clone(),
+        )
+        .await
+        {
+            Ok((_, _, ledger)) => normalize(&ledger),
+            Err(e) => return Ok(GoldenOutcome::Failed(e)),
+        };
+
+        let expected_path = dir.join(EXPECTED_FILE);
+        if self.bless {
+            tokio::fs::write(&expected_path, &actual)
+                .await
+                .map_err(|e| WriteError::with_debug(&e))?;
+            return Ok(GoldenOutcome::Blessed);
+        }
+        let Some(expected) = read_optional(&expected_path).await? else {
+            return Ok(GoldenOutcome::MissingExpected);
+        };
+        let expected = normalize(&expected);
+        if expected == actual {
+            Ok(GoldenOutcome::Passed)
+        } else {
+            Ok(GoldenOutcome::Mismatch(
+                TextDiff::from_lines(&expected, &actual)
+                    .unified_diff()
+                    .header(EXPECTED_FILE, "actual")
+                    .to_string(),
+            ))
+        }
+    }
+}
+
+impl GoldenReport {
+    /// Cases that neither passed nor were blessed.
+    pub fn failures(&self) -> impl Iterator<Item = &GoldenCase> {
+        self.cases
+            .iter()
+            .filter(|case| !matches!(case.outcome, GoldenOutcome::Passed | GoldenOutcome::Blessed))
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Panic with the report if any case failed (for use in tests).
+    pub fn assert_passed(&self) {
+        if !self.is_success() {
+            panic!("{}", self.to_text());
+        }
+    }
+
+    /// Plain text report listing each case's outcome, with diffs and errors
+    /// for failed cases.
+    pub fn to_text(&self) -> String {
+        let mut output = format!(
+            "{} golden case(s), {} failed.\n",
+            self.cases.len(),
+            self.failures().count()
+        );
+        for case in &self.cases {
+            let status = match &case.outcome {
+                GoldenOutcome::Passed => "ok",
+                GoldenOutcome::Mismatch(_) => "MISMATCH",
+                GoldenOutcome::MissingExpected => "MISSING EXPECTED",
+                GoldenOutcome::Blessed => "blessed",
+                GoldenOutcome::Failed(_) => "FAILED",
+            };
+            output.push_str(&format!("\n{} ... {}\n", case.name, status));
+            match &case.outcome {
+                GoldenOutcome::Mismatch(diff) => output.push_str(diff),
+                GoldenOutcome::Failed(e) => output.push_str(&format!("{}\n", e)),
+                _ => {}
+            }
+        }
+        output
+    }
+}
+
+/// Subdirectories containing a transactions CSV, ordered by name.
+async fn case_dirs(dir: &Path) -> Result<Vec<PathBuf>, ServerError> {
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .map_err(|e| ReadError::with_debug(&e))?;
+    let mut dirs = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| ReadError::with_debug(&e))?
+    {
+        let path = entry.path();
+        if path.join(TRANSACTIONS_FILE).is_file() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+async fn read(path: &Path) -> Result<String, ServerError> {
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| ReadError::with_debug(&e))
+}
+
+async fn read_optional(path: &Path) -> Result<Option<String>, ServerError> {
+    if path.is_file() {
+        read(path).await.map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Ignore line ending and trailing whitespace differences (ex. from editors
+/// or git's autocrlf).
+fn normalize(journal: &str) -> String {
+    let mut output: String = journal
+        .lines()
+        .map(|line| format!("{}\n", line.trim_end()))
+        .collect();
+    output.truncate(output.trim_end().len());
+    output.push('\n');
+    output
+}
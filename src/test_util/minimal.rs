@@ -0,0 +1,198 @@
+//! Small, fixed `Handlers` implementation for tests, so decorators, macros
+//! and reports can be tested without writing a full set of handlers.
+//!
+//! The chart of accounts has a KRW and a USD bank account, a founder (as
+//! shareholder and reimbursable entity), and a few common income, expense and
+//! asset accounts. Handlers are written in the CSVs as plain RON variants
+//! (ex. `Rent`, `Bank`, `USD`), and payees as RON strings (ex. `"Acme"`,
+//! which is `"""Acme"""` once quoted for CSV).
+//!
+//! The decorator handler is a type parameter, so a decorator under test can
+//! be plugged in directly (ex. `MinimalHandlers<MyDecorator>`). Macros aren't
+//! supported.
+
+use std::marker::PhantomData;
+
+use iso_currency::Currency;
+use serde_derive::Deserialize;
+
+use crate::entities::{
+    asset, equity, expense, income, liability, AssetAccount, AssetClassification, AssetHandler,
+    CashHandler, CommodityHandler, DecoratorHandler, EquityAccount, EquityClassification,
+    ExpenseAccount, ExpenseClassification, ExpenseHandler, Handlers, IncomeAccount,
+    IncomeClassification, IncomeHandler, LiabilityAccount, LiabilityClassification, PayeeHandler,
+    ReimbursableEntityHandler, ShareholderHandler,
+};
+
+#[derive(Debug)]
+pub struct MinimalHandlers<D = ()>(PhantomData<D>);
+
+impl<D: DecoratorHandler> Handlers for MinimalHandlers<D> {
+    type A = Asset;
+    type I = Income;
+    type E = Expense;
+    type R = Reimbursable;
+    type C = Cash;
+    type S = Shareholder;
+    type D = D;
+    type M = Commodity;
+    type P = Payee;
+    type F = ();
+}
+
+// Account handlers.
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Asset {
+    /// Depreciated when capitalized over a period.
+    Equipment,
+    /// Amortized when capitalized over a period.
+    Software,
+}
+
+impl AssetHandler for Asset {
+    fn account(&self) -> AssetAccount {
+        match self {
+            Asset::Equipment => asset("Equipment", AssetClassification::PropertyPlantEquipment),
+            Asset::Software => asset("Software", AssetClassification::IntangibleAssets),
+        }
+    }
+    fn upon_accrual(&self) -> Option<ExpenseAccount> {
+        Some(match self {
+            Asset::Equipment => expense("Equipment", ExpenseClassification::DepreciationExpense),
+            Asset::Software => expense("Software", ExpenseClassification::AmortizationExpense),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Income {
+    Sales,
+    Services,
+    Interest,
+    Other,
+}
+
+impl IncomeHandler for Income {
+    fn account(&self) -> IncomeAccount {
+        match self {
+            Income::Sales => income("Sales", IncomeClassification::SalesRevenue),
+            Income::Services => income("Services", IncomeClassification::ServiceRevenue),
+            Income::Interest => income("Interest", IncomeClassification::NonCoreInterestIncome),
+            Income::Other => income("Other", IncomeClassification::OtherNonOperatingIncome),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Expense {
+    Rent,
+    Salaries,
+    Supplies,
+    Cloud,
+    BankFees,
+    Other,
+}
+
+impl ExpenseHandler for Expense {
+    fn account(&self) -> ExpenseAccount {
+        match self {
+            Expense::Rent => expense("Rent", ExpenseClassification::GeneralAdministrativeExpenses),
+            Expense::Salaries => expense(
+                "Salaries",
+                ExpenseClassification::GeneralAdministrativeExpenses,
+            ),
+            Expense::Supplies => expense(
+                "Supplies",
+                ExpenseClassification::GeneralAdministrativeExpenses,
+            ),
+            Expense::Cloud => expense("Cloud", ExpenseClassification::CloudServicesExpenses),
+            Expense::BankFees => expense(
+                "BankFees",
+                ExpenseClassification::OtherNonCoreFinancialExpense,
+            ),
+            Expense::Other => expense("Other", ExpenseClassification::OtherNonOperatingCashExpense),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Cash {
+    /// KRW account.
+    Bank,
+    /// USD account.
+    UsdBank,
+}
+
+impl CashHandler for Cash {
+    fn account(&self) -> AssetAccount {
+        match self {
+            Cash::Bank => asset("Bank", AssetClassification::CashAndCashEquivalents),
+            Cash::UsdBank => asset("UsdBank", AssetClassification::CashAndCashEquivalents),
+        }
+    }
+    fn currency(&self) -> Option<Currency> {
+        Some(match self {
+            Cash::Bank => Currency::KRW,
+            Cash::UsdBank => Currency::USD,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Shareholder {
+    Founder,
+}
+
+impl ShareholderHandler for Shareholder {
+    fn account(&self) -> EquityAccount {
+        equity("Founder", EquityClassification::CommonStock)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Reimbursable {
+    Founder,
+}
+
+impl ReimbursableEntityHandler for Reimbursable {
+    fn account(&self) -> LiabilityAccount {
+        liability("Founder", LiabilityClassification::OtherCurrentLiabilities)
+    }
+}
+
+// Other.
+// ----------------------------------------------------------------------------
+
+/// Used verbatim, ex. `"Acme"`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Payee(pub String);
+
+impl PayeeHandler for Payee {
+    fn name(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// Defaults to KRW.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Commodity {
+    #[serde(rename = "KRW")]
+    Krw,
+    #[serde(rename = "USD")]
+    Usd,
+}
+
+impl CommodityHandler for Commodity {
+    fn iso_symbol(&self) -> String {
+        match self {
+            Commodity::Krw => "KRW".into(),
+            Commodity::Usd => "USD".into(),
+        }
+    }
+    fn default() -> Self {
+        Commodity::Krw
+    }
+}
@@ -1,17 +1,228 @@
 use fractic_server_error::ServerError;
 
 use crate::{
-    domain::usecases::process_usecase::{ProcessUsecase as _, ProcessUsecaseImpl},
+    data::repositories::records_repository_impl::RecordsRepositoryImpl,
+    domain::{
+        logic::assertion_preflight::AssertionPreflight,
+        repositories::records_repository::RecordsRepository as _,
+        usecases::process_usecase::{
+            process_specs, stream_specs, ProcessUsecase as _, ProcessUsecaseImpl,
+        },
+    },
     entities::{
-        AssetHandler, CashHandler, CommodityHandler, DecoratorHandler, ExpenseHandler,
-        FinancialRecords, HandlersImpl, IncomeHandler, MacroHandler, NotesToFinancialRecords,
-        PayeeHandler, ReimbursableEntityHandler, ShareholderHandler,
+        AssetHandler, BudgetSpec, CashHandler, CommodityHandler, DecoratorHandler, ExpenseHandler,
+        FailedAssertion, FinancialRecordSpecs, FinancialRecords, Handlers, HandlersImpl, IfrsLogic,
+        IncomeHandler, MacroHandler, NotesToFinancialRecords, PayeeHandler, PipelineConfig,
+        ProcessOptions, ReimbursableEntityHandler, ShareholderHandler, StandardIfrsLogic,
+        Transaction, TransactionLabel,
     },
-    presentation::hledger_printer::HledgerPrinter,
+    presentation::{hledger_printer::HledgerPrinter, print_options::PrintOptions},
 };
 
+#[cfg(feature = "fs")]
+use crate::errors::InvalidConfig;
+use crate::errors::WriteError;
+
 pub type Ledger = String;
 
+/// Process specs constructed in code (rather than parsed from CSV) into
+/// financial records.
+pub async fn process<H: Handlers>(
+    specs: FinancialRecordSpecs<H>,
+    options: ProcessOptions,
+) -> Result<FinancialRecords, ServerError> {
+    process_with_logic(specs, &StandardIfrsLogic, options).await
+}
+
+/// Same as `process`, but using an alternative spec processing strategy.
+pub async fn process_with_logic<H: Handlers>(
+    specs: FinancialRecordSpecs<H>,
+    logic: &dyn IfrsLogic<H>,
+    options: ProcessOptions,
+) -> Result<FinancialRecords, ServerError> {
+    let (financial_records, _) = process_specs(specs, logic, &options).await?;
+    Ok(financial_records)
+}
+
+/// Same as `process`, but also generates the notes and prints the ledger using
+/// the given print options.
+pub async fn process_and_print<H: Handlers>(
+    specs: FinancialRecordSpecs<H>,
+    options: ProcessOptions,
+    print_options: PrintOptions,
+) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError> {
+    let (financial_records, notes_to_financial_records) =
+        process_specs(specs, &StandardIfrsLogic, &options).await?;
+    let ledger = HledgerPrinter::with_options(print_options).print_ledger(&financial_records);
+    Ok((financial_records, notes_to_financial_records, ledger))
+}
+
+/// Print the ledger of already processed records to the writer (ex. a
+/// buffered file), streaming it section by section rather than building the
+/// full journal in memory as `process_and_print` does.
+pub fn write_ledger(
+    financial_records: &FinancialRecords,
+    print_options: PrintOptions,
+    writer: impl std::io::Write,
+) -> Result<(), ServerError> {
+    HledgerPrinter::with_options(print_options)
+        .print_ledger_to(writer, financial_records)
+        .map_err(|e| WriteError::with_debug(&e))
+}
+
+/// Evaluate the balance assertions of already processed records against the
+/// balances implied by their transactions, without running hledger. See
+/// `ProcessOptions::preflight_assertions` to fail processing instead.
+pub fn check_assertions(financial_records: &FinancialRecords) -> Vec<FailedAssertion> {
+    AssertionPreflight::new(financial_records).failures()
+}
+
+/// Process specs incrementally, yielding the generated transactions (with the
+/// label of their originating spec) one spec at a time, in payment date order.
+/// Useful for very large spec sets, since neither the full `FinancialRecords`
+/// nor the printed ledger are materialized.
+///
+/// Only decorator and spec processing is applied: balance assertions,
+/// amendments and commands (ex. closing entries) require the complete ledger,
+/// and are not included.
+pub async fn process_iter<H: Handlers>(
+    specs: FinancialRecordSpecs<H>,
+    options: ProcessOptions,
+) -> Result<impl Iterator<Item = Result<(Transaction, TransactionLabel), ServerError>>, ServerError>
+{
+    stream_specs(specs, &options).await
+}
+
+/// Ledger of one group entity, generated by `process_by_entity`.
+#[derive(Debug, Clone)]
+pub struct EntityLedger {
+    /// `None` for the specs without an entity.
+    pub entity: Option<String>,
+    /// Path of the journal referenced by the combined ledger, relative to it.
+    pub file_name: String,
+    pub financial_records: FinancialRecords,
+    pub notes_to_financial_records: NotesToFinancialRecords,
+    pub ledger: Ledger,
+}
+
+#[derive(Debug, Clone)]
+pub struct EntityLedgers {
+    pub entities: Vec<EntityLedger>,
+    /// Journal including each entity's journal (by `file_name`), to be written
+    /// in the same directory.
+    pub combined: Ledger,
+}
+
+/// Same as `process_and_print`, but processes the specs of each group entity
+/// (see `TransactionSpec::entity`) separately, so that processing state such
+/// as outstanding reimbursements and expense history is never shared between
+/// entities. Produces one ledger per entity, plus a combined ledger including
+/// them all.
+pub async fn process_by_entity<H: Handlers>(
+    specs: FinancialRecordSpecs<H>,
+    options: ProcessOptions,
+    print_options: PrintOptions,
+) -> Result<EntityLedgers, ServerError> {
+    let printer = HledgerPrinter::with_options(print_options);
+    let mut entities = Vec::new();
+    for (entity, entity_specs) in specs.partition_by_entity() {
+        let (financial_records, notes_to_financial_records) =
+            process_specs(entity_specs, &StandardIfrsLogic, &options).await?;
+        let ledger = printer.print_ledger(&financial_records);
+        entities.push(EntityLedger {
+            file_name: entity_file_name(entity.as_deref()),
+            entity,
+            financial_records,
+            notes_to_financial_records,
+            ledger,
+        });
+    }
+    let combined = printer.print_includes_journal(
+        &entities
+            .iter()
+            .map(|e| e.file_name.clone())
+            .collect::<Vec<_>>(),
+    );
+    Ok(EntityLedgers { entities, combined })
+}
+
+/// Same as `process_csv`, but split by group entity (see `process_by_entity`).
+pub async fn process_csv_by_entity<H: Handlers>(
+    transactions_csv: &str,
+    balances_csv: &str,
+    options: ProcessOptions,
+    print_options: PrintOptions,
+) -> Result<EntityLedgers, ServerError> {
+    let specs = parse_csv::<H>(transactions_csv, balances_csv)?;
+    process_by_entity(specs, options, print_options).await
+}
+
+/// Parse the transactions and balances CSVs into specs, to be processed with
+/// `process_and_print` (or `process_by_entity`).
+pub fn parse_csv<H: Handlers>(
+    transactions_csv: &str,
+    balances_csv: &str,
+) -> Result<FinancialRecordSpecs<H>, ServerError> {
+    RecordsRepositoryImpl::<H>::new().from_string(transactions_csv, balances_csv)
+}
+
+/// Parse a budgets CSV (columns: target, from, to, amount, commodity,
+/// entity), to be set as `FinancialRecordSpecs::budget_specs`.
+pub fn parse_budgets_csv<H: Handlers>(
+    budgets_csv: &str,
+) -> Result<Vec<BudgetSpec<H>>, ServerError> {
+    RecordsRepositoryImpl::<H>::new().budgets_from_string(budgets_csv)
+}
+
+/// Journal file name for the entity, keeping only characters safe in paths.
+fn entity_file_name(entity: Option<&str>) -> String {
+    let name: String = entity
+        .unwrap_or("unassigned")
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' || c == '_' => c,
+            _ => '-',
+        })
+        .collect();
+    format!("{name}.journal")
+}
+
+/// Parse the transactions and balances CSVs, process them, and print the
+/// ledger. Equivalent to `IfrsHledgerUtil::from_string`, but generic over a
+/// single `Handlers` implementation.
+pub async fn process_csv<H: Handlers>(
+    transactions_csv: &str,
+    balances_csv: &str,
+    options: ProcessOptions,
+    print_options: PrintOptions,
+) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError> {
+    let specs = parse_csv::<H>(transactions_csv, balances_csv)?;
+    process_and_print(specs, options, print_options).await
+}
+
+/// Run `process_csv` on the browser's event loop (via `wasm-bindgen-futures`),
+/// passing the result to the callback. No executor is blocked, so this can be
+/// called directly from UI event handlers. Set `ProcessOptions::clock` (ex.
+/// to one reading `js_sys::Date`), since the system clock isn't available in
+/// the browser.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn spawn_process_csv<H, F>(
+    transactions_csv: String,
+    balances_csv: String,
+    options: ProcessOptions,
+    print_options: PrintOptions,
+    on_complete: F,
+) where
+    H: Handlers,
+    F: FnOnce(Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError>) + 'static,
+{
+    wasm_bindgen_futures::spawn_local(async move {
+        on_complete(
+            process_csv::<H>(&transactions_csv, &balances_csv, options, print_options).await,
+        );
+    });
+}
+
 pub struct IfrsHledgerUtil<
     // `()` provides default (placeholder) implementations for all handlers.
     A = (),
@@ -60,32 +271,129 @@ where
         }
     }
 
+    /// Override the options used when processing specs.
+    pub fn with_process_options(mut self, options: ProcessOptions) -> Self {
+        self.process_usecase = self.process_usecase.with_options(options);
+        self
+    }
+
+    /// Override the layout options used when printing the ledger.
+    pub fn with_print_options(mut self, options: PrintOptions) -> Self {
+        self.printer = HledgerPrinter::with_options(options);
+        self
+    }
+
+    /// Use the processing and printing options from a loaded config.
+    pub fn with_config(self, config: &PipelineConfig) -> Self {
+        self.with_process_options(config.process_options.clone())
+            .with_print_options(config.print_options.clone())
+    }
+
     pub async fn from_string(
         &self,
         transactions_csv: &str,
         balances_csv: &str,
+    ) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError> {
+        self.process_strings(transactions_csv, balances_csv, None)
+            .await
+    }
+
+    /// Same as `from_string`, but also loads budgets (see `parse_budgets_csv`
+    /// for the columns) into `FinancialRecords::budgets`.
+    pub async fn from_string_with_budgets(
+        &self,
+        transactions_csv: &str,
+        balances_csv: &str,
+        budgets_csv: &str,
+    ) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError> {
+        self.process_strings(transactions_csv, balances_csv, Some(budgets_csv))
+            .await
+    }
+
+    async fn process_strings(
+        &self,
+        transactions_csv: &str,
+        balances_csv: &str,
+        budgets_csv: Option<&str>,
     ) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError> {
         let (financial_records, notes_to_financial_records) = self
             .process_usecase
-            .from_string(transactions_csv, balances_csv)
+            .from_string(transactions_csv, balances_csv, budgets_csv)
             .await?;
         let ledger = self.printer.print_ledger(&financial_records);
         Ok((financial_records, notes_to_financial_records, ledger))
     }
 
+    #[cfg(feature = "fs")]
     pub async fn from_file<T>(
         &self,
         transactions_csv: T,
         balances_csv: T,
     ) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError>
+    where
+        T: AsRef<std::path::Path> + Send,
+    {
+        self.process_files(transactions_csv, balances_csv, None)
+            .await
+    }
+
+    /// Same as `from_file`, but also loads budgets (see
+    /// `from_string_with_budgets`).
+    #[cfg(feature = "fs")]
+    pub async fn from_file_with_budgets<T>(
+        &self,
+        transactions_csv: T,
+        balances_csv: T,
+        budgets_csv: T,
+    ) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError>
+    where
+        T: AsRef<std::path::Path> + Send,
+    {
+        self.process_files(transactions_csv, balances_csv, Some(budgets_csv))
+            .await
+    }
+
+    #[cfg(feature = "fs")]
+    async fn process_files<T>(
+        &self,
+        transactions_csv: T,
+        balances_csv: T,
+        budgets_csv: Option<T>,
+    ) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError>
     where
         T: AsRef<std::path::Path> + Send,
     {
         let (financial_records, notes_to_financial_records) = self
             .process_usecase
-            .from_file(transactions_csv, balances_csv)
+            .from_file(transactions_csv, balances_csv, budgets_csv)
             .await?;
         let ledger = self.printer.print_ledger(&financial_records);
         Ok((financial_records, notes_to_financial_records, ledger))
     }
+
+    /// Process the transaction and balance files (and budgets file, if any)
+    /// configured in the config's `[inputs]` section. The config's options are not applied (see
+    /// `with_config`).
+    #[cfg(feature = "fs")]
+    pub async fn from_config(
+        &self,
+        config: &PipelineConfig,
+    ) -> Result<(FinancialRecords, NotesToFinancialRecords, Ledger), ServerError> {
+        let transactions_csv = config
+            .transactions_path
+            .as_ref()
+            .ok_or_else(|| InvalidConfig::new("missing 'inputs.transactions'"))?;
+        let balances_csv = config
+            .balances_path
+            .as_ref()
+            .ok_or_else(|| InvalidConfig::new("missing 'inputs.balances'"))?;
+        self.process_files(transactions_csv, balances_csv, config.budgets_path.as_ref())
+            .await
+    }
+
+    /// Print the balance assertions as a standalone journal. Intended to be
+    /// written to the path configured in `PrintOptions::assertions_include`.
+    pub fn print_assertions(&self, financial_records: &FinancialRecords) -> Ledger {
+        self.printer.print_assertions_journal(financial_records)
+    }
 }